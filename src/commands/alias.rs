@@ -0,0 +1,225 @@
+use crate::commands::traits::{AliasAction, AliasArgs, Command};
+use crate::config::{self, ALIAS_PREFIX};
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::ui::Icon;
+use console::{Color, style};
+use git2::{Config, ConfigLevel, Repository};
+use rust_i18n::t;
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Alias命令实现
+pub struct AliasCommand {
+    /// 内置子命令名称，别名不能覆盖它们
+    builtins: Vec<String>,
+    /// `-C/--repo` 指定的仓库路径，未指定时为当前目录
+    repo_path: Option<String>,
+}
+
+impl AliasCommand {
+    pub fn new(builtins: Vec<String>, repo_path: Option<String>) -> Self {
+        AliasCommand {
+            builtins,
+            repo_path,
+        }
+    }
+
+    fn repo_path(&self) -> &str {
+        self.repo_path.as_deref().unwrap_or(".")
+    }
+
+    /// 打开别名写入的配置文件：全局的 `~/.gitconfig` 或当前仓库的 `.git/config`
+    fn open_config(&self, global: bool) -> CcResult<Config> {
+        if !global {
+            let repo = Repository::open(self.repo_path()).map_err(|_| {
+                CheckpointError::InvalidArgument(t!("alias_not_in_repo").to_string())
+            })?;
+            return Ok(repo.config()?.open_level(ConfigLevel::Local)?);
+        }
+        let path = match Config::find_global() {
+            Ok(path) => path,
+            // 全局配置文件还不存在时在 HOME 下创建
+            Err(_) => env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".gitconfig"))
+                .ok_or_else(|| {
+                    CheckpointError::InvalidArgument(t!("alias_no_global_config").to_string())
+                })?,
+        };
+        Ok(Config::open(&path)?)
+    }
+
+    /// 保存别名，名称不能与内置命令重名
+    pub fn set_alias(&self, config: &mut Config, name: &str, expansion: &str) -> CcResult<()> {
+        if !is_valid_name(name) {
+            return Err(CheckpointError::InvalidArgument(
+                t!("alias_invalid_name", name = name).to_string(),
+            ));
+        }
+        if self.builtins.iter().any(|builtin| builtin == name) {
+            return Err(CheckpointError::InvalidArgument(
+                t!("alias_shadows_builtin", name = name).to_string(),
+            ));
+        }
+        if split_words(expansion)?.is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("alias_empty_expansion").to_string(),
+            ));
+        }
+        config.set_str(&format!("{ALIAS_PREFIX}{name}"), expansion.trim())?;
+        Ok(())
+    }
+
+    /// 删除别名
+    pub fn unset_alias(&self, config: &mut Config, name: &str) -> CcResult<()> {
+        config
+            .remove(&format!("{ALIAS_PREFIX}{name}"))
+            .map_err(|e| match e.code() {
+                git2::ErrorCode::NotFound => {
+                    CheckpointError::InvalidArgument(t!("alias_not_found", name = name).to_string())
+                }
+                _ => CheckpointError::GitOperationFailed(e),
+            })
+    }
+
+    fn print_aliases(aliases: &BTreeMap<String, String>) {
+        if aliases.is_empty() {
+            println!("{}{}", Icon::Empty.prefix(), t!("alias_none"));
+            return;
+        }
+        println!(
+            "{}{}",
+            style(Icon::List.prefix()).fg(Color::Blue),
+            style(t!("alias_header")).fg(Color::Blue).bold()
+        );
+        let width = aliases.keys().map(|name| name.len()).max().unwrap_or(0);
+        for (name, expansion) in aliases {
+            println!(
+                "  {}  {}",
+                style(format!("{name:width$}")).fg(Color::Cyan).bold(),
+                expansion
+            );
+        }
+    }
+}
+
+impl Command for AliasCommand {
+    type Args = AliasArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        match args.action {
+            AliasAction::List => {
+                let aliases = Repository::open(self.repo_path())
+                    .and_then(|repo| repo.config())
+                    .or_else(|_| Config::open_default())
+                    .map(|config| config::aliases(&config))
+                    .unwrap_or_default();
+                Self::print_aliases(&aliases);
+            }
+            AliasAction::Set { name, expansion } => {
+                let mut config = self.open_config(args.global)?;
+                self.set_alias(&mut config, &name, &expansion)?;
+                println!(
+                    "{}{}",
+                    style(Icon::Success.prefix()).fg(Color::Green),
+                    t!("alias_set_done", name = name, expansion = expansion.trim())
+                );
+            }
+            AliasAction::Unset { name } => {
+                let mut config = self.open_config(args.global)?;
+                self.unset_alias(&mut config, &name)?;
+                println!(
+                    "{}{}",
+                    style(Icon::Success.prefix()).fg(Color::Green),
+                    t!("alias_unset_done", name = name)
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 别名名称遵循 git config 变量名规则：字母开头，只含字母、数字和 `-`
+fn is_valid_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// 按空白拆分别名展开，支持单引号和双引号包住含空格的参数
+pub fn split_words(text: &str) -> CcResult<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(CheckpointError::InvalidArgument(
+            t!("alias_unclosed_quote", text = text).to_string(),
+        ));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+/// 在 clap 解析之前展开命令行中的别名
+///
+/// 跳过开头的全局参数（`--ascii`、`--read-only`、`--width`、`-C/--repo`）找到子命令名；
+/// 若它是别名且不是 `is_builtin` 认可的内置命令，则替换为别名展开后的参数。
+/// 展开结果的首个词也可以是别名，循环引用时报错。
+pub fn expand_aliases(
+    mut args: Vec<OsString>,
+    aliases: &BTreeMap<String, String>,
+    is_builtin: impl Fn(&str) -> bool,
+) -> CcResult<Vec<OsString>> {
+    let mut position = 1;
+    while let Some(arg) = args.get(position).and_then(|arg| arg.to_str()) {
+        match arg {
+            "--ascii" | "--read-only" => position += 1,
+            "--width" | "--repo" | "-C" => position += 2,
+            _ if arg.starts_with("--width=") || arg.starts_with("--repo=") => position += 1,
+            _ if arg.starts_with("-C") => position += 1,
+            _ => break,
+        }
+    }
+
+    let mut seen = HashSet::new();
+    while let Some(name) = args.get(position).and_then(|arg| arg.to_str()) {
+        if is_builtin(name) {
+            break;
+        }
+        let Some(expansion) = aliases.get(name) else {
+            break;
+        };
+        if !seen.insert(name.to_string()) {
+            return Err(CheckpointError::InvalidArgument(
+                t!("alias_loop", name = name).to_string(),
+            ));
+        }
+        let words = split_words(expansion)?;
+        args.splice(position..=position, words.into_iter().map(OsString::from));
+    }
+    Ok(args)
+}
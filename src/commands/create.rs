@@ -1,5 +1,13 @@
 use crate::commands::traits::{Command, CommandContext, CreateArgs};
-use crate::error::Result as CcResult;
+use crate::config::Granularity;
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::git_ops::pending::MAX_ATTEMPTS;
+use crate::git_ops::{CheckpointSource, HookSkip, ToolEdit};
+use crate::services::CreateOptions;
+use crate::services::summary;
+use crate::ui::Icon;
+use console::{Color, style};
+use rust_i18n::t;
 use serde::Deserialize;
 use serde_json;
 use std::io::{self, Read};
@@ -25,6 +33,50 @@ struct HookData {
     tool_response: ToolResponse,
     tool_input: serde_json::Value,
     cwd: Option<String>,
+    session_id: Option<String>,
+}
+
+/// `ccg create` 的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CreateOutcome {
+    /// 创建了检查点，多个时为最后一个
+    Created(String),
+    /// 没有变更，未创建检查点
+    Unchanged,
+    /// 钩子事件被 `ccg.hooks.tools` 或 `ccg.hooks.paths` 过滤
+    Filtered(HookSkip),
+}
+
+impl CreateOutcome {
+    /// 服务返回的哈希，空字符串表示没有变更
+    fn from_hash(hash: String) -> Self {
+        if hash.is_empty() {
+            CreateOutcome::Unchanged
+        } else {
+            CreateOutcome::Created(hash)
+        }
+    }
+
+    /// 创建的检查点哈希，没有创建时为空字符串
+    pub fn hash(&self) -> String {
+        match self {
+            CreateOutcome::Created(hash) => hash.clone(),
+            _ => String::new(),
+        }
+    }
+
+    /// `--porcelain` 输出的一行，字段以制表符分隔：
+    /// `created <hash>`、`unchanged`、`skipped tool <tool>` 或 `skipped path <file>...`
+    pub fn porcelain(&self) -> String {
+        match self {
+            CreateOutcome::Created(hash) => format!("created\t{hash}"),
+            CreateOutcome::Unchanged => "unchanged".to_string(),
+            CreateOutcome::Filtered(HookSkip::Tool { tool }) => format!("skipped\ttool\t{tool}"),
+            CreateOutcome::Filtered(HookSkip::Path { files }) => {
+                format!("skipped\tpath\t{}", files.join("\t"))
+            }
+        }
+    }
 }
 
 /// Create命令实现
@@ -37,24 +89,283 @@ impl CreateCommand {
         CreateCommand { context }
     }
 
-    fn format_commit_message(&self, data: &HookData) -> String {
-        let file_path = data
-            .tool_input
+    /// 根据钩子通过 stdin 传入的内容创建检查点
+    ///
+    /// 能解析为 Claude Code 钩子 JSON 时使用格式化后的提交信息，并在 `cwd`
+    /// 指定的仓库中创建；否则直接把原始内容作为提交信息。
+    pub fn create_from_payload(&self, payload: &str) -> CcResult<String> {
+        self.create_from_payload_with(payload, &CreateOptions::default())
+    }
+
+    /// 同 `create_from_payload`，按 `options` 创建（空检查点、创建前验证）
+    ///
+    /// 钩子事件因临时错误（如锁竞争）失败时放入 `.git/ccg/pending/` 队列，
+    /// 下一个钩子事件或 `ccg flush` 会先按顺序重放队列中的事件。
+    pub fn create_from_payload_with(
+        &self,
+        payload: &str,
+        options: &CreateOptions,
+    ) -> CcResult<String> {
+        self.create_from_payload_outcome(payload, options)
+            .map(|outcome| outcome.hash())
+    }
+
+    /// 同 `create_from_payload_with`，返回钩子事件是否被过滤等处理结果
+    pub fn create_from_payload_outcome(
+        &self,
+        payload: &str,
+        options: &CreateOptions,
+    ) -> CcResult<CreateOutcome> {
+        match serde_json::from_str::<HookData>(payload) {
+            Ok(parsed_data) => {
+                let context = if let Some(cwd) = &parsed_data.cwd {
+                    CommandContext::new_with_path(Some(cwd))?
+                } else {
+                    self.context.clone()
+                };
+                if let Err(e) = Self::replay_pending(&context) {
+                    println!(
+                        "{}{}",
+                        style(Icon::Warning.prefix()).fg(Color::Yellow),
+                        style(t!("pending_replay_failed", error = e)).fg(Color::Yellow)
+                    );
+                }
+                match Self::create_from_hook(&context, &parsed_data, options) {
+                    Err(e) if e.is_transient() => {
+                        if context.git_ops.pending().push(payload)? {
+                            println!(
+                                "{}{}",
+                                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                                style(t!("pending_queued", error = e)).fg(Color::Yellow)
+                            );
+                        }
+                        Err(e)
+                    }
+                    result => result,
+                }
+            }
+            Err(_) => self
+                .context
+                .checkpoint_service
+                .create_checkpoint_with_options(Some(payload), options)
+                .map(CreateOutcome::from_hash),
+        }
+    }
+
+    /// 重放排队的钩子事件
+    ///
+    /// # Returns
+    /// 成功重放（包括没有变更可提交）的事件数
+    pub fn flush_pending(&self) -> CcResult<usize> {
+        Self::replay_pending(&self.context)
+    }
+
+    /// 按排队顺序重放 `context` 仓库中的钩子事件
+    ///
+    /// 事件只在重放成功后才从队列中删除；再次遇到临时错误时记下失败次数并
+    /// 停止，剩余事件留到下次。其他错误说明事件无法重放，打印警告后丢弃；
+    /// 重放失败 [`MAX_ATTEMPTS`] 次的事件同样丢弃，不会一直挡住后面的事件。
+    /// 检查点记录的是重放时工作目录的内容。
+    fn replay_pending(context: &CommandContext) -> CcResult<usize> {
+        let queue = context.git_ops.pending();
+        let events = queue.entries()?;
+        let mut replayed = 0;
+        for (index, event) in events.iter().enumerate() {
+            println!(
+                "{}{}",
+                style(Icon::Replay.prefix()).fg(Color::Blue),
+                style(t!(
+                    "pending_replaying",
+                    current = index + 1,
+                    total = events.len()
+                ))
+                .fg(Color::White)
+            );
+            let result = match serde_json::from_str::<HookData>(&event.payload) {
+                Ok(data) => Self::create_from_hook(context, &data, &CreateOptions::default()),
+                Err(e) => Err(CheckpointError::InvalidArgument(e.to_string())),
+            };
+            match result {
+                Ok(_) => replayed += 1,
+                Err(e) if e.is_transient() && event.attempts + 1 < MAX_ATTEMPTS => {
+                    queue.record_attempt(event)?;
+                    return Err(e);
+                }
+                Err(e) => println!(
+                    "{}{}",
+                    style(Icon::Warning.prefix()).fg(Color::Yellow),
+                    style(t!("pending_dropped", error = e)).fg(Color::Yellow)
+                ),
+            }
+            queue.remove(event)?;
+        }
+        Ok(replayed)
+    }
+
+    /// 在 `context` 的仓库中为解析后的钩子数据创建检查点
+    ///
+    /// 被 `ccg.hooks.tools` 或 `ccg.hooks.paths` 过滤的事件不创建检查点，
+    /// 只记入 `.git/ccg/hook-filter.log`。
+    fn create_from_hook(
+        context: &CommandContext,
+        parsed_data: &HookData,
+        options: &CreateOptions,
+    ) -> CcResult<CreateOutcome> {
+        let files = Self::file_paths(parsed_data);
+        let filter = context.git_ops.hook_filter();
+        if let Some(skip) = filter.check(&parsed_data.tool_name, &files) {
+            let message = match &skip {
+                HookSkip::Tool { tool } => t!("hook_filtered_tool", tool = tool),
+                HookSkip::Path { .. } => t!("hook_filtered_path", tool = &parsed_data.tool_name),
+            };
+            println!("{}{}", Icon::Info.prefix(), style(message).dim());
+            filter.record(&parsed_data.tool_name, skip.clone())?;
+            return Ok(CreateOutcome::Filtered(skip));
+        }
+
+        let options = &CreateOptions {
+            session: parsed_data.session_id.clone(),
+            tool: Some(parsed_data.tool_name.clone()),
+            source: Some(CheckpointSource::Hook),
+            tool_edits: Self::tool_edits(parsed_data),
+            ..options.clone()
+        };
+        let locale = context.git_ops.commits().message_locale();
+        // 按文件粒度时，修改多个文件的事件为每个文件创建一个检查点
+        if files.len() > 1 && context.git_ops.config().granularity == Some(Granularity::PerFile) {
+            let parts: Vec<(String, String)> = files
+                .iter()
+                .map(|path| {
+                    let message = Self::format_commit_message(
+                        parsed_data,
+                        &locale,
+                        Self::base_name(path),
+                        None,
+                    );
+                    (path.to_string(), message)
+                })
+                .collect();
+            let hashes = context
+                .checkpoint_service
+                .create_checkpoint_per_file(&parts, options)?;
+            return Ok(CreateOutcome::from_hash(
+                hashes.last().cloned().unwrap_or_default(),
+            ));
+        }
+        // 钩子数据没有指明文件时，用待提交的变更生成描述
+        let file_name = Self::file_name(parsed_data);
+        let description = if file_name.is_empty() {
+            summary::describe_changes(&context.git_ops, &locale)
+        } else {
+            None
+        };
+        let commit_message =
+            Self::format_commit_message(parsed_data, &locale, file_name, description.as_deref());
+        context
+            .checkpoint_service
+            .create_checkpoint_with_options(Some(&commit_message), options)
+            .map(CreateOutcome::from_hash)
+    }
+
+    /// 钩子数据中被修改文件的文件名，没有时为空
+    fn file_name(data: &HookData) -> &str {
+        data.tool_input
             .get("file_path")
             .and_then(|v| v.as_str())
-            .map(|s| s.split('/').next_back().unwrap_or(s))
-            .unwrap_or("");
+            .map(Self::base_name)
+            .unwrap_or("")
+    }
 
+    /// 钩子数据中工具报告的修改：Edit/MultiEdit 的 `old_string`/`new_string`，
+    /// Write 的 `content`（整个文件）
+    fn tool_edits(data: &HookData) -> Vec<ToolEdit> {
+        let input = &data.tool_input;
+        let top_path = input.get("file_path").and_then(|v| v.as_str());
+        let text = |value: &serde_json::Value, key: &str| {
+            value.get(key).and_then(|v| v.as_str()).map(str::to_string)
+        };
+        let edits = input.get("edits").and_then(|v| v.as_array());
+        let mut result = Vec::new();
+        for value in std::iter::once(input).chain(edits.into_iter().flatten()) {
+            let Some(path) = value.get("file_path").and_then(|v| v.as_str()).or(top_path) else {
+                continue;
+            };
+            let content = text(value, "content");
+            let added: Vec<String> = text(value, "new_string")
+                .into_iter()
+                .chain(content.clone())
+                .collect();
+            if added.is_empty() {
+                continue;
+            }
+            result.push(ToolEdit {
+                path: path.to_string(),
+                removed: text(value, "old_string").into_iter().collect(),
+                added,
+                whole_file: content.is_some(),
+            });
+        }
+        result
+    }
+
+    /// 路径的最后一段
+    fn base_name(path: &str) -> &str {
+        path.split('/').next_back().unwrap_or(path)
+    }
+
+    /// 钩子数据修改的所有文件路径（`file_path` 和各 `edits[].file_path`），去重并保持顺序
+    fn file_paths(data: &HookData) -> Vec<&str> {
+        let edits = data
+            .tool_input
+            .get("edits")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten();
+        let mut paths: Vec<&str> = Vec::new();
+        for value in std::iter::once(&data.tool_input).chain(edits) {
+            if let Some(path) = value.get("file_path").and_then(|v| v.as_str())
+                && !paths.contains(&path)
+            {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+
+    /// 按 `locale` 指定的提交信息语言格式化钩子数据，`file_path` 为标题中的文件名
+    ///
+    /// 没有文件名时标题使用 `description`（由变更生成的描述），也没有描述时只有工具名。
+    fn format_commit_message(
+        data: &HookData,
+        locale: &str,
+        file_path: &str,
+        description: Option<&str>,
+    ) -> String {
         let title = if file_path.is_empty() {
-            data.tool_name.to_string()
+            match description {
+                Some(summary) => t!(
+                    "message_tool_summary",
+                    locale = locale,
+                    tool = data.tool_name,
+                    summary = summary
+                )
+                .to_string(),
+                None => data.tool_name.to_string(),
+            }
         } else {
-            format!("{} on {}", data.tool_name, file_path)
+            t!(
+                "message_tool_on_file",
+                locale = locale,
+                tool = data.tool_name,
+                file = file_path
+            )
         };
 
         let mut message = format!("{title}\n\n");
 
         if let Some(patches) = &data.tool_response.structured_patch {
-            message.push_str("Changes:\n");
+            message.push_str(&t!("message_changes", locale = locale));
+            message.push('\n');
             for patch in patches {
                 for line in &patch.lines {
                     message.push_str(&format!("  {line}\n"));
@@ -63,7 +374,8 @@ impl CreateCommand {
             message.push('\n');
         }
 
-        message.push_str("Tool Input:\n");
+        message.push_str(&t!("message_tool_input", locale = locale));
+        message.push('\n');
         if let Ok(input_pretty) = serde_json::to_string_pretty(&data.tool_input) {
             message.push_str(&input_pretty);
         } else {
@@ -79,53 +391,76 @@ impl Command for CreateCommand {
     type Output = String;
 
     fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
-        if let Some(message) = args.message {
+        let verify = if args.verify || args.require_pass {
+            let command = self.context.git_ops.verify_command().ok_or_else(|| {
+                CheckpointError::InvalidArgument(t!("verify_no_command").to_string())
+            })?;
+            Some(command)
+        } else {
+            None
+        };
+        let options = CreateOptions {
+            allow_empty: args.allow_empty,
+            verify,
+            require_pass: args.require_pass,
+            source: Some(CheckpointSource::Manual),
+            bypass_guard: args.bypass_guard,
+            ..CreateOptions::default()
+        };
+
+        let outcome = self.create(args.message, &options)?;
+        if args.porcelain {
+            println!("{}", outcome.porcelain());
+        }
+        Ok(outcome.hash())
+    }
+
+    fn validate_args(&self, _args: &Self::Args) -> CcResult<()> {
+        // Create命令的tool_input_json参数是可选的，无需特殊验证
+        Ok(())
+    }
+}
+
+impl CreateCommand {
+    /// 按消息、stdin 中的钩子数据或待提交的变更创建检查点
+    fn create(&self, message: Option<String>, options: &CreateOptions) -> CcResult<CreateOutcome> {
+        if let Some(message) = message {
             // 如果直接提供了消息，则使用默认上下文
             return self
                 .context
                 .checkpoint_service
-                .create_checkpoint(Some(&message));
+                .create_checkpoint_with_options(Some(&message), options)
+                .map(CreateOutcome::from_hash);
         }
 
-        // 尝试从stdin读取
-        let mut buffer = String::new();
+        // 尝试从stdin读取，工具输出中的无效 UTF-8 替换为 U+FFFD
+        let mut buffer = Vec::new();
         let (tx, rx) = mpsc::channel();
         thread::spawn(move || {
-            if io::stdin().read_to_string(&mut buffer).is_ok() {
-                tx.send(buffer).ok();
+            if io::stdin().read_to_end(&mut buffer).is_ok() {
+                tx.send(String::from_utf8_lossy(&buffer).into_owned()).ok();
             }
         });
 
-        if let Ok(stdin_data) = rx.recv_timeout(Duration::from_millis(100)) {
-            if !stdin_data.trim().is_empty() {
-                return match serde_json::from_str::<HookData>(&stdin_data) {
-                    Ok(parsed_data) => {
-                        let commit_message = self.format_commit_message(&parsed_data);
-                        let context = if let Some(cwd) = parsed_data.cwd {
-                            CommandContext::new_with_path(Some(&cwd))?
-                        } else {
-                            self.context.clone()
-                        };
-                        context
-                            .checkpoint_service
-                            .create_checkpoint(Some(&commit_message))
-                    }
-                    Err(_) => self
-                        .context
-                        .checkpoint_service
-                        .create_checkpoint(Some(&stdin_data)),
-                };
-            }
+        if let Ok(stdin_data) = rx.recv_timeout(Duration::from_millis(100))
+            && !stdin_data.trim().is_empty()
+        {
+            return self.create_from_payload_outcome(&stdin_data, options);
         }
 
-        // 如果没有输入，则创建手动检查点
+        // 如果没有输入，则创建手动检查点，并用待提交的变更生成描述
+        let locale = self.context.git_ops.commits().message_locale();
+        let message = match summary::describe_changes(&self.context.git_ops, &locale) {
+            Some(summary) => t!(
+                "message_manual_summary",
+                locale = &locale,
+                summary = summary
+            ),
+            _ => t!("message_manual", locale = &locale),
+        };
         self.context
             .checkpoint_service
-            .create_checkpoint(Some("Manual checkpoint"))
-    }
-
-    fn validate_args(&self, _args: &Self::Args) -> CcResult<()> {
-        // Create命令的tool_input_json参数是可选的，无需特殊验证
-        Ok(())
+            .create_checkpoint_with_options(Some(&message), options)
+            .map(CreateOutcome::from_hash)
     }
 }
@@ -1,5 +1,8 @@
+use crate::commands::evolution::parse_time;
 use crate::commands::traits::{Command, CommandContext, DiffArgs};
 use crate::error::Result as CcResult;
+use crate::git_ops::set_diff_settings;
+use std::path::Path;
 
 /// Diff命令实现
 pub struct DiffCommand {
@@ -17,13 +20,27 @@ impl Command for DiffCommand {
     type Output = ();
 
     fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        set_diff_settings(args.diff_settings);
+        if let Some(since) = &args.since {
+            let since = parse_time(since)?;
+            return self.context.checkpoint_service.diff_since(since);
+        }
+        if let Some(task) = &args.task {
+            return self.context.checkpoint_service.diff_task(task);
+        }
+        if let Some(dir) = &args.dir {
+            return self
+                .context
+                .checkpoint_service
+                .diff_directory(args.hash_a.as_deref(), Path::new(dir));
+        }
         self.context
             .checkpoint_service
-            .diff_checkpoints(&args.hash_a, args.hash_b.as_deref())
+            .diff_checkpoints(args.hash_a.as_deref(), args.hash_b.as_deref())
     }
 
     fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
-        if args.hash_a.is_empty() {
+        if args.hash_a.as_deref().is_some_and(str::is_empty) {
             return Err(crate::error::CheckpointError::InvalidArgument(
                 "第一个检查点哈希值不能为空".to_string(),
             ));
@@ -0,0 +1,228 @@
+use crate::commands::traits::{Command, CommandContext, DoctorArgs};
+use crate::error::Result as CcResult;
+use crate::services::hooks::{HookEvent, HookRunner, is_executable};
+use crate::ui::{Icon, short_hash};
+use chrono::DateTime;
+use console::{Color, style};
+use rust_i18n::t;
+
+/// 诊断结果级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckLevel {
+    Ok,
+    Warn,
+    Error,
+}
+
+/// 单项诊断结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorCheck {
+    pub level: CheckLevel,
+    pub name: String,
+    pub detail: String,
+}
+
+impl DoctorCheck {
+    fn new(level: CheckLevel, name: impl Into<String>, detail: impl Into<String>) -> Self {
+        DoctorCheck {
+            level,
+            name: name.into(),
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Doctor命令实现，检查仓库状态并报告已知限制（浅克隆、部分克隆等）
+pub struct DoctorCommand {
+    context: CommandContext,
+}
+
+impl DoctorCommand {
+    pub fn new(context: CommandContext) -> Self {
+        DoctorCommand { context }
+    }
+
+    /// 运行所有诊断项
+    pub fn checks(&self) -> Vec<DoctorCheck> {
+        vec![
+            self.check_head(),
+            self.check_ccg_branch(),
+            self.check_history(),
+            self.check_objects(),
+            self.check_identity(),
+            self.check_hooks(),
+            self.check_staging(),
+        ]
+    }
+
+    fn check_head(&self) -> DoctorCheck {
+        let name = t!("doctor_check_head");
+        let repo = self.context.git_ops.get_repo();
+        match repo.head() {
+            Ok(head) if repo.head_detached().unwrap_or(false) => {
+                let hash = head.target().map(|oid| oid.to_string()).unwrap_or_default();
+                DoctorCheck::new(
+                    CheckLevel::Warn,
+                    name,
+                    t!("doctor_head_detached", hash = short_hash(&hash)),
+                )
+            }
+            Ok(head) => DoctorCheck::new(
+                CheckLevel::Ok,
+                name,
+                t!(
+                    "doctor_head_branch",
+                    branch = head.shorthand().unwrap_or("?")
+                ),
+            ),
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+                DoctorCheck::new(CheckLevel::Warn, name, t!("doctor_head_unborn"))
+            }
+            Err(e) => DoctorCheck::new(CheckLevel::Error, name, e.message().to_string()),
+        }
+    }
+
+    fn check_ccg_branch(&self) -> DoctorCheck {
+        let name = t!("doctor_check_checkpoints");
+        let git_ops = &self.context.git_ops;
+        let branch = match git_ops.branches().get_ccg_branch() {
+            Ok(branch) => branch,
+            Err(_) => return DoctorCheck::new(CheckLevel::Warn, name, t!("doctor_ccg_missing")),
+        };
+
+        let count = branch.get().target().and_then(|oid| {
+            let mut revwalk = git_ops.get_repo().revwalk().ok()?;
+            revwalk.push(oid).ok()?;
+            Some(revwalk.count())
+        });
+        match count {
+            Some(count) => {
+                DoctorCheck::new(CheckLevel::Ok, name, t!("doctor_ccg_ok", count = count))
+            }
+            None => DoctorCheck::new(CheckLevel::Error, name, t!("doctor_ccg_unreadable")),
+        }
+    }
+
+    fn check_history(&self) -> DoctorCheck {
+        let name = t!("doctor_check_history");
+        let repository = self.context.git_ops.repository();
+        if repository.is_shallow() {
+            DoctorCheck::new(
+                CheckLevel::Warn,
+                name,
+                t!(
+                    "doctor_shallow",
+                    count = repository.shallow_boundaries().len()
+                ),
+            )
+        } else {
+            DoctorCheck::new(CheckLevel::Ok, name, t!("doctor_history_complete"))
+        }
+    }
+
+    fn check_objects(&self) -> DoctorCheck {
+        let name = t!("doctor_check_objects");
+        if self.context.git_ops.repository().is_partial_clone() {
+            DoctorCheck::new(CheckLevel::Warn, name, t!("doctor_partial_clone"))
+        } else {
+            DoctorCheck::new(CheckLevel::Ok, name, t!("doctor_objects_complete"))
+        }
+    }
+
+    fn check_identity(&self) -> DoctorCheck {
+        let name = t!("doctor_check_identity");
+        match self.context.git_ops.commits().create_signature() {
+            Ok(sig) => DoctorCheck::new(
+                CheckLevel::Ok,
+                name,
+                format!(
+                    "{} <{}>",
+                    sig.name().unwrap_or("?"),
+                    sig.email().unwrap_or("?")
+                ),
+            ),
+            Err(e) => DoctorCheck::new(CheckLevel::Error, name, e.to_string()),
+        }
+    }
+
+    fn check_hooks(&self) -> DoctorCheck {
+        let name = t!("doctor_check_hooks");
+        let Some(workdir) = self.context.git_ops.get_repo().workdir() else {
+            return DoctorCheck::new(CheckLevel::Ok, name, t!("doctor_hooks_none"));
+        };
+        let runner = HookRunner::new(workdir);
+        let installed: Vec<&str> = HookEvent::ALL
+            .iter()
+            .filter(|event| is_executable(&runner.hook_path(**event)))
+            .map(|event| event.name())
+            .collect();
+        if installed.is_empty() {
+            DoctorCheck::new(CheckLevel::Ok, name, t!("doctor_hooks_none"))
+        } else {
+            DoctorCheck::new(CheckLevel::Ok, name, installed.join(", "))
+        }
+    }
+
+    /// 报告修改检查点的命令修复过的中断创建；尚未修复时标记仍在
+    fn check_staging(&self) -> DoctorCheck {
+        let name = t!("doctor_check_staging");
+        let staging = self.context.git_ops.staging();
+        if staging.is_interrupted() {
+            return DoctorCheck::new(CheckLevel::Error, name, t!("doctor_staging_interrupted"));
+        }
+        let Some(repair) = staging.take_repair() else {
+            return DoctorCheck::new(CheckLevel::Ok, name, t!("doctor_staging_ok"));
+        };
+        let time = DateTime::from_timestamp(repair.interrupted_at, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let detail = if repair.restored {
+            t!("doctor_staging_restored", time = time)
+        } else {
+            t!("doctor_staging_completed", time = time)
+        };
+        DoctorCheck::new(CheckLevel::Warn, name, detail)
+    }
+}
+
+impl Command for DoctorCommand {
+    type Args = DoctorArgs;
+    type Output = ();
+
+    fn execute(&self, _args: Self::Args) -> CcResult<Self::Output> {
+        println!(
+            "{}",
+            style(format!("{}{}", Icon::Doctor.prefix(), t!("doctor_header")))
+                .fg(Color::Cyan)
+                .bold()
+        );
+        println!();
+
+        let checks = self.checks();
+        for check in &checks {
+            let icon = match check.level {
+                CheckLevel::Ok => style(Icon::Success.prefix()).fg(Color::Green),
+                CheckLevel::Warn => style(Icon::Warning.prefix()).fg(Color::Yellow),
+                CheckLevel::Error => style(Icon::Error.prefix()).fg(Color::Red),
+            };
+            println!(
+                "  {}{} {}",
+                icon,
+                style(format!("{:<12}", check.name)).fg(Color::White).bold(),
+                check.detail
+            );
+        }
+
+        let problems = checks.iter().filter(|c| c.level != CheckLevel::Ok).count();
+        println!();
+        if problems == 0 {
+            println!("{}", style(t!("doctor_summary_ok")).fg(Color::Green).bold());
+        } else {
+            println!(
+                "{}",
+                style(t!("doctor_summary_problems", count = problems)).fg(Color::Yellow)
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use crate::commands::traits::{Command, CommandContext, DriftArgs};
+use crate::error::Result as CcResult;
+use crate::git_ops::BranchDrift;
+
+/// Drift命令实现，报告最新检查点与当前分支的偏离
+pub struct DriftCommand {
+    context: CommandContext,
+}
+
+impl DriftCommand {
+    pub fn new(context: CommandContext) -> Self {
+        DriftCommand { context }
+    }
+}
+
+impl Command for DriftCommand {
+    type Args = DriftArgs;
+    type Output = BranchDrift;
+
+    fn execute(&self, _args: Self::Args) -> CcResult<Self::Output> {
+        self.context.checkpoint_service.show_drift()
+    }
+}
@@ -0,0 +1,81 @@
+use crate::commands::traits::{Command, CommandContext, EvolutionArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use rust_i18n::t;
+
+/// Evolution命令实现
+pub struct EvolutionCommand {
+    context: CommandContext,
+}
+
+impl EvolutionCommand {
+    pub fn new(context: CommandContext) -> Self {
+        EvolutionCommand { context }
+    }
+}
+
+impl Command for EvolutionCommand {
+    type Args = EvolutionArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let since = args.since.as_deref().map(parse_time).transpose()?;
+        let until = args.until.as_deref().map(parse_time).transpose()?;
+        self.context
+            .checkpoint_service
+            .show_file_evolution(&args.file, args.full, since, until)
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.file.trim().is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("file_path_empty").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 解析时间参数为 Unix 时间戳
+///
+/// 支持 RFC 3339、本地时间 `YYYY-MM-DD [HH:MM[:SS]]`，以及相对当前时间的
+/// `30m`、`2h`、`3d`、`1w` 或 `1 hour ago`、`30 minutes ago`。
+pub fn parse_time(text: &str) -> CcResult<i64> {
+    let text = text.trim();
+    let invalid = || CheckpointError::InvalidDateFormat(text.to_string());
+
+    let relative = text.strip_suffix("ago").map_or(text, str::trim_end);
+    let digits = relative
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(relative.len());
+    let unit = relative[digits..].trim_start();
+    if digits > 0 && !unit.is_empty() && unit.chars().all(|c| c.is_ascii_alphabetic()) {
+        let amount: i64 = relative[..digits].parse().map_err(|_| invalid())?;
+        let seconds = match unit.to_ascii_lowercase().as_str() {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+            "d" | "day" | "days" => 86400,
+            "w" | "week" | "weeks" => 7 * 86400,
+            _ => return Err(invalid()),
+        };
+        return Ok(Local::now().timestamp() - amount * seconds);
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Ok(datetime.timestamp());
+    }
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(invalid)?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|datetime| datetime.timestamp())
+        .ok_or_else(invalid)
+}
@@ -0,0 +1,49 @@
+use crate::commands::traits::{Command, CommandContext, ExportMetadataArgs};
+use crate::error::Result as CcResult;
+use crate::services::metadata;
+use crate::ui::Icon;
+use console::{Color, style};
+use rust_i18n::t;
+
+/// ExportMetadata命令实现，把每个检查点的元数据导出为 CSV 或 JSONL
+pub struct ExportMetadataCommand {
+    context: CommandContext,
+}
+
+impl ExportMetadataCommand {
+    pub fn new(context: CommandContext) -> Self {
+        ExportMetadataCommand { context }
+    }
+}
+
+impl Command for ExportMetadataCommand {
+    type Args = ExportMetadataArgs;
+    /// 导出的内容
+    type Output = String;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let rows = self.context.checkpoint_service.checkpoint_metadata()?;
+        let content = metadata::render(&rows, args.format);
+        match &args.output {
+            Some(path) => {
+                std::fs::write(path, &content)?;
+                println!(
+                    "{}{}",
+                    style(Icon::Success.prefix()).fg(Color::Green),
+                    style(t!(
+                        "export_metadata_written",
+                        count = rows.len(),
+                        path = path
+                    ))
+                    .fg(Color::Green)
+                );
+            }
+            None => print!("{content}"),
+        }
+        Ok(content)
+    }
+
+    fn validate_args(&self, _args: &Self::Args) -> CcResult<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use crate::commands::traits::{Command, CommandContext, FetchArgs};
+use crate::error::Result as CcResult;
+use crate::git_ops::FetchReport;
+use rust_i18n::t;
+
+/// Fetch命令实现，从远程获取检查点
+pub struct FetchCommand {
+    context: CommandContext,
+}
+
+impl FetchCommand {
+    pub fn new(context: CommandContext) -> Self {
+        FetchCommand { context }
+    }
+}
+
+impl Command for FetchCommand {
+    type Args = FetchArgs;
+    type Output = FetchReport;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        self.context
+            .checkpoint_service
+            .fetch_checkpoints(&args.remote, args.depth)
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.depth == Some(0) {
+            return Err(crate::error::CheckpointError::InvalidArgument(
+                t!("fetch_depth_zero").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
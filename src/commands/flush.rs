@@ -0,0 +1,40 @@
+use crate::commands::create::CreateCommand;
+use crate::commands::traits::{Command, CommandContext, FlushArgs};
+use crate::error::Result as CcResult;
+use crate::ui::Icon;
+use console::{Color, style};
+use rust_i18n::t;
+
+/// Flush命令实现
+pub struct FlushCommand {
+    context: CommandContext,
+}
+
+impl FlushCommand {
+    pub fn new(context: CommandContext) -> Self {
+        FlushCommand { context }
+    }
+}
+
+impl Command for FlushCommand {
+    type Args = FlushArgs;
+    type Output = usize;
+
+    fn execute(&self, _args: Self::Args) -> CcResult<Self::Output> {
+        if self.context.git_ops.pending().is_empty()? {
+            println!(
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!("flush_empty")).fg(Color::White)
+            );
+            return Ok(0);
+        }
+        let replayed = CreateCommand::new(self.context.clone()).flush_pending()?;
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green).bold(),
+            style(t!("flush_done", count = replayed)).fg(Color::Green)
+        );
+        Ok(replayed)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::commands::traits::{Command, CommandContext, HookAction, HookArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::services::integration::{
+    InstallTarget, PlannedChange, plan_claude_settings, plan_git_hook,
+};
+use crate::ui::Icon;
+use console::{Color, style};
+use rust_i18n::t;
+
+/// Hook命令实现，将 ccg 接入 Claude Code 配置和 git 钩子
+pub struct HookCommand {
+    context: CommandContext,
+}
+
+impl HookCommand {
+    pub fn new(context: CommandContext) -> Self {
+        HookCommand { context }
+    }
+
+    /// 计算所有安装目标的变更，任一目标无法合并时不写入任何文件
+    pub fn plan(&self, targets: &[InstallTarget]) -> CcResult<Vec<PlannedChange>> {
+        let repo = self.context.git_ops.get_repo();
+        targets
+            .iter()
+            .map(|target| match target {
+                InstallTarget::ClaudeSettings => {
+                    let workdir = repo.workdir().ok_or(CheckpointError::RepositoryNotFound)?;
+                    plan_claude_settings(workdir)
+                }
+                InstallTarget::GitHook => plan_git_hook(repo),
+            })
+            .collect()
+    }
+
+    fn print_change(change: &PlannedChange) {
+        let note = if change.is_unchanged() {
+            t!("install_print_unchanged")
+        } else if change.original.is_some() {
+            t!("install_print_modified")
+        } else {
+            t!("install_print_new")
+        };
+        println!(
+            "{}{} {}",
+            style(Icon::File.prefix()).fg(Color::Blue),
+            style(change.path.display()).fg(Color::Cyan).bold(),
+            style(note).fg(Color::White).dim()
+        );
+        print!("{}", change.content);
+        println!();
+    }
+
+    fn apply_change(change: &PlannedChange) -> CcResult<()> {
+        let path = change.path.display().to_string();
+        if change.is_unchanged() {
+            println!(
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!("install_already", path = path)).fg(Color::White)
+            );
+            return Ok(());
+        }
+
+        match change.apply()? {
+            Some(backup) => println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!(
+                    "install_updated",
+                    path = path,
+                    backup = backup.display().to_string()
+                ))
+                .fg(Color::Green)
+            ),
+            None => println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!("install_created", path = path)).fg(Color::Green)
+            ),
+        }
+        Ok(())
+    }
+}
+
+impl Command for HookCommand {
+    type Args = HookArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        match args.action {
+            HookAction::Install => {
+                let changes = self.plan(&args.targets)?;
+                for change in &changes {
+                    if args.print {
+                        Self::print_change(change);
+                    } else {
+                        Self::apply_change(change)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.targets.is_empty() {
+            return Err(CheckpointError::InvalidArgument(t!("install_no_target")));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use crate::commands::traits::{Command, CommandContext, ImpactArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::git_ops::ImpactReport;
+use crate::ui::{Icon, short_hash};
+use console::{Color, style};
+use rust_i18n::t;
+
+/// Impact命令实现
+pub struct ImpactCommand {
+    context: CommandContext,
+}
+
+impl ImpactCommand {
+    pub fn new(context: CommandContext) -> Self {
+        ImpactCommand { context }
+    }
+
+    fn print_report(report: &ImpactReport) {
+        println!(
+            "{}{}",
+            style(Icon::Search.prefix()).fg(Color::Blue),
+            style(t!(
+                "impact_header",
+                hash = short_hash(&report.hash),
+                count = report.later_checkpoints
+            ))
+            .fg(Color::White)
+            .bold()
+        );
+        println!();
+
+        if report.overlaps.is_empty() {
+            println!("  {}", style(t!("impact_no_overlap")).fg(Color::Green));
+        }
+        for overlap in &report.overlaps {
+            let files: Vec<String> = overlap
+                .files
+                .iter()
+                .map(|file| {
+                    if overlap.overlapping_lines.contains(file) {
+                        format!("{} {}", style(file).fg(Color::Red), t!("impact_same_lines"))
+                    } else {
+                        style(file).fg(Color::Cyan).to_string()
+                    }
+                })
+                .collect();
+            println!(
+                "  {} {} {}",
+                style(short_hash(&overlap.hash)).fg(Color::Yellow).bold(),
+                style(&overlap.summary).fg(Color::White),
+                style(format!("— {}", files.join(", "))).dim()
+            );
+        }
+        println!();
+
+        if report.revert_conflicts.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!("impact_revert_clean")).fg(Color::Green)
+            );
+        } else {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!(
+                    "impact_revert_conflicts",
+                    files = report.revert_conflicts.join(", ")
+                ))
+                .fg(Color::Yellow)
+            );
+        }
+    }
+}
+
+impl Command for ImpactCommand {
+    type Args = ImpactArgs;
+    type Output = ImpactReport;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let report = self
+            .context
+            .checkpoint_service
+            .checkpoint_impact(&args.hash)?;
+        Self::print_report(&report);
+        Ok(report)
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.hash.is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("hash_empty").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
 use crate::commands::traits::{Command, CommandContext, InitArgs};
+use crate::config::AutoInit;
 use crate::error::Result as CcResult;
+use crate::ui::prompt::{self, PromptKind};
+use rust_i18n::t;
+use std::io::IsTerminal;
 
 /// Init命令实现
 pub struct InitCommand {
@@ -12,16 +16,50 @@ impl InitCommand {
     }
 }
 
+/// 判断是否在不是 git 仓库的 `path` 中自动初始化仓库
+///
+/// `forced`（`--auto-init`）优先于 `ccg.autoInit` 配置；默认只在终端中询问，
+/// 钩子等非交互环境不会在意外的目录（如用户主目录）中创建仓库。
+pub fn confirm_auto_init(path: &str, forced: bool, mode: AutoInit) -> CcResult<bool> {
+    if forced {
+        return Ok(true);
+    }
+    match mode {
+        AutoInit::Always => Ok(true),
+        AutoInit::Never => Ok(false),
+        AutoInit::Prompt if !std::io::stdin().is_terminal() => Ok(false),
+        AutoInit::Prompt => prompt::confirm(
+            PromptKind::Routine,
+            &t!("auto_init_prompt", path = path),
+            Some(false),
+        ),
+    }
+}
+
 impl Command for InitCommand {
     type Args = InitArgs;
     type Output = ();
 
-    fn execute(&self, _args: Self::Args) -> CcResult<Self::Output> {
-        self.context.checkpoint_service.init()
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let service = &self.context.checkpoint_service;
+        if args.orphan {
+            service.init_orphan()?;
+        } else {
+            service.init_from(args.from.as_deref())?;
+        }
+        service.apply_exclude_profiles(&args.profiles)
     }
 
-    fn validate_args(&self, _args: &Self::Args) -> CcResult<()> {
-        // Init命令无参数需要验证
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args
+            .from
+            .as_deref()
+            .is_some_and(|from| from.trim().is_empty())
+        {
+            return Err(crate::error::CheckpointError::InvalidArgument(
+                "分支名称不能为空".to_string(),
+            ));
+        }
         Ok(())
     }
 }
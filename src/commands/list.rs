@@ -1,5 +1,6 @@
 use crate::commands::traits::{Command, CommandContext, ListArgs};
 use crate::error::Result as CcResult;
+use crate::git_ops::{ListColumn, ListFilter};
 
 /// List命令实现
 pub struct ListCommand {
@@ -17,9 +18,27 @@ impl Command for ListCommand {
     type Output = ();
 
     fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
-        self.context
-            .checkpoint_service
-            .list_checkpoints(args.number)
+        let mut columns = if args.columns.is_empty() {
+            ListColumn::DEFAULT.to_vec()
+        } else {
+            args.columns
+        };
+        if args.stat && !columns.contains(&ListColumn::Stats) {
+            columns.push(ListColumn::Stats);
+        }
+        let filter = ListFilter {
+            labels: args.labels,
+            only_passing: args.only_passing,
+            task: args.task,
+            skip_format_only: args.skip_format_only,
+            user: args.user,
+        };
+        self.context.checkpoint_service.list_checkpoints(
+            args.number,
+            &columns,
+            args.with_restores,
+            &filter,
+        )
     }
 
     fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
@@ -0,0 +1,435 @@
+use crate::commands::traits::{Command, CommandContext, ListenArgs};
+use crate::config::Granularity;
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::git_ops::CheckpointSource;
+use crate::services::CreateOptions;
+use crate::ui::Icon;
+use console::{Color, style};
+use rust_i18n::t;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 未设置 `ccg.listen.debounce` 时合并保存事件的时间窗口（毫秒）
+pub const DEFAULT_DEBOUNCE_MS: u64 = 1000;
+
+/// 等待保存事件时检查计时和终止信号的间隔
+#[cfg(unix)]
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Listen命令实现：编辑器通过本地套接字通知文件已保存，ccg 合并一段时间内的
+/// 保存后创建检查点，使 Claude Code 以外的编辑器也有同样的保护
+pub struct ListenCommand {
+    context: CommandContext,
+}
+
+impl ListenCommand {
+    pub fn new(context: CommandContext) -> Self {
+        ListenCommand { context }
+    }
+
+    /// 为一批保存过的文件创建检查点
+    ///
+    /// 只有工作目录内、未被 git 忽略且匹配 `ccg.listen.include`（未设置时匹配所有文件）
+    /// 的路径会触发检查点；相对路径按工作目录解析。检查点与钩子创建的一样记录工作目录
+    /// 的全部变更，`ccg.checkpoint.granularity` 为 per-file 时每个文件一个检查点。
+    ///
+    /// 没有路径在范围内或没有变更时返回 None。
+    pub fn checkpoint_saved(&self, paths: &[String]) -> CcResult<Option<String>> {
+        let git_ops = &self.context.git_ops;
+        let config = git_ops.config();
+        let repo = git_ops.get_repo();
+        let Some(workdir) = repo.workdir() else {
+            return Ok(None);
+        };
+        let include = if config.listen_include.is_empty() {
+            None
+        } else {
+            Some(git2::Pathspec::new(config.listen_include.iter())?)
+        };
+
+        let mut saved: Vec<String> = Vec::new();
+        for path in paths {
+            let Some(relative) = relative_to(workdir, Path::new(path)) else {
+                continue;
+            };
+            if relative.as_os_str().is_empty()
+                || relative.starts_with(".git")
+                || repo.is_path_ignored(&relative).unwrap_or(false)
+                || include
+                    .as_ref()
+                    .is_some_and(|spec| !spec.matches_path(&relative, git2::PathspecFlags::DEFAULT))
+            {
+                continue;
+            }
+            let relative = relative.to_string_lossy().to_string();
+            if !saved.contains(&relative) {
+                saved.push(relative);
+            }
+        }
+        if saved.is_empty() {
+            return Ok(None);
+        }
+
+        let locale = git_ops.commits().message_locale();
+        let options = CreateOptions {
+            source: Some(CheckpointSource::Editor),
+            ..CreateOptions::default()
+        };
+        let hash = if saved.len() > 1 && config.granularity == Some(Granularity::PerFile) {
+            let parts: Vec<(String, String)> = saved
+                .iter()
+                .map(|path| {
+                    let message = t!("message_saved", locale = &locale, files = base_name(path));
+                    (path.clone(), message.to_string())
+                })
+                .collect();
+            self.context
+                .checkpoint_service
+                .create_checkpoint_per_file(&parts, &options)?
+                .pop()
+                .unwrap_or_default()
+        } else {
+            let files: Vec<&str> = saved.iter().map(|path| base_name(path)).collect();
+            let message = t!("message_saved", locale = &locale, files = files.join(", "));
+            self.context
+                .checkpoint_service
+                .create_checkpoint_with_options(Some(&message), &options)?
+        };
+        Ok(Some(hash).filter(|hash| !hash.is_empty()))
+    }
+
+    /// 为工作目录中自上个检查点以来的全部变更创建检查点，不管它们是否被报告为保存
+    ///
+    /// 空闲触发和收到终止信号时使用，`message` 为提交信息。没有变更时返回 None。
+    pub fn checkpoint_pending(&self, message: &str) -> CcResult<Option<String>> {
+        let options = CreateOptions {
+            source: Some(CheckpointSource::Editor),
+            ..CreateOptions::default()
+        };
+        let hash = self
+            .context
+            .checkpoint_service
+            .create_checkpoint_with_options(Some(message), &options)?;
+        Ok(Some(hash).filter(|hash| !hash.is_empty()))
+    }
+
+    /// 监听套接字，直到收到 SIGINT 或 SIGTERM
+    ///
+    /// 保存事件在 `debounce` 内没有新的保存时创建检查点；`idle` 不为 None 时，
+    /// 每连续这么久没有保存事件，就为工作目录中累积的变更创建检查点。收到信号时先为
+    /// 尚在等待的保存和其余变更创建检查点，再删除套接字并退出。
+    #[cfg(unix)]
+    fn listen(&self, socket: &Path, debounce: Duration, idle: Option<Duration>) -> CcResult<()> {
+        use std::os::unix::net::UnixListener;
+        use std::sync::mpsc::{self, RecvTimeoutError};
+        use std::thread;
+        use std::time::Instant;
+
+        remove_stale_socket(socket)?;
+        let listener = UnixListener::bind(socket)?;
+        signals::install();
+        println!(
+            "{}{}",
+            style(Icon::Info.prefix()).fg(Color::Blue),
+            style(t!(
+                "listen_started",
+                socket = socket.display(),
+                debounce = debounce.as_millis()
+            ))
+            .fg(Color::White)
+        );
+        if let Some(idle) = idle {
+            println!(
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!("listen_idle_enabled", minutes = idle.as_secs() / 60)).fg(Color::White)
+            );
+        }
+
+        let (sender, receiver) = mpsc::channel::<Vec<String>>();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    let _ = serve_connection(stream, &sender);
+                });
+            }
+        });
+
+        let locale = self.context.git_ops.commits().message_locale();
+        let mut pending: Vec<String> = Vec::new();
+        let mut last_save = Instant::now();
+        // 空闲计时从最近一次保存或空闲检查点开始
+        let mut idle_since = Instant::now();
+        while !signals::received() {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(paths) => {
+                    pending.extend(paths);
+                    last_save = Instant::now();
+                    idle_since = last_save;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            // 等到 debounce 时间内没有新的保存
+            if !pending.is_empty() && last_save.elapsed() >= debounce {
+                report_failure(self.checkpoint_saved(&std::mem::take(&mut pending)));
+            }
+            if let Some(idle) = idle
+                && idle_since.elapsed() >= idle
+            {
+                let message = t!(
+                    "message_idle",
+                    locale = &locale,
+                    minutes = idle.as_secs() / 60
+                );
+                report_failure(self.checkpoint_pending(&message));
+                idle_since = Instant::now();
+            }
+        }
+
+        if signals::received() {
+            println!(
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!("listen_stopping")).fg(Color::White)
+            );
+            if !pending.is_empty() {
+                report_failure(self.checkpoint_saved(&pending));
+            }
+            let message = t!("message_interrupted", locale = &locale);
+            report_failure(self.checkpoint_pending(&message));
+            let _ = std::fs::remove_file(socket);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn listen(&self, _socket: &Path, _debounce: Duration, _idle: Option<Duration>) -> CcResult<()> {
+        Err(CheckpointError::InvalidArgument(
+            t!("listen_unsupported").to_string(),
+        ))
+    }
+}
+
+impl Command for ListenCommand {
+    type Args = ListenArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let config = self.context.git_ops.config();
+        let debounce = args
+            .debounce
+            .or(config.listen_debounce)
+            .unwrap_or(DEFAULT_DEBOUNCE_MS);
+        let idle = args
+            .idle
+            .or(config.listen_idle)
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes * 60));
+        self.listen(
+            Path::new(&args.socket),
+            Duration::from_millis(debounce),
+            idle,
+        )
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.socket.trim().is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("listen_socket_required").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 从一个保存事件中取出文件路径
+///
+/// 事件是 JSON 对象，支持 `{"path": "..."}`、`{"paths": [...]}`，以及 LSP
+/// `textDocument/didSave` 的参数 `{"textDocument": {"uri": "file:///..."}}`
+/// 或包含它的完整消息（`params` 字段）。无法识别时返回空列表。
+pub fn parse_event(body: &str) -> Vec<String> {
+    let Ok(value) = serde_json::from_str::<Value>(body) else {
+        return Vec::new();
+    };
+    let value = value.get("params").unwrap_or(&value);
+    let mut paths = Vec::new();
+    if let Some(path) = value.get("path").and_then(Value::as_str) {
+        paths.push(path.to_string());
+    }
+    if let Some(list) = value.get("paths").and_then(Value::as_array) {
+        paths.extend(list.iter().filter_map(Value::as_str).map(str::to_string));
+    }
+    let uri = value
+        .get("textDocument")
+        .unwrap_or(value)
+        .get("uri")
+        .and_then(Value::as_str);
+    if let Some(path) = uri.and_then(file_uri_path) {
+        paths.push(path);
+    }
+    paths
+}
+
+/// 处理一个连接：HTTP POST 请求的正文是一个事件，回复 202；
+/// 否则每行一个 JSON 事件，直到连接关闭
+#[cfg(unix)]
+pub fn serve_connection(
+    stream: std::os::unix::net::UnixStream,
+    events: &std::sync::mpsc::Sender<Vec<String>>,
+) -> std::io::Result<()> {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(());
+    }
+
+    if !line.trim_end().ends_with("HTTP/1.1") && !line.trim_end().ends_with("HTTP/1.0") {
+        loop {
+            let paths = parse_event(line.trim());
+            if !paths.is_empty() {
+                let _ = events.send(paths);
+            }
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let paths = parse_event(&String::from_utf8_lossy(&body));
+    let status = if line.starts_with("POST ") && !paths.is_empty() {
+        let _ = events.send(paths);
+        "202 Accepted"
+    } else {
+        "400 Bad Request"
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+    )?;
+    writer.flush()
+}
+
+/// 检查点创建失败时打印警告，监听继续
+#[cfg(unix)]
+fn report_failure(result: CcResult<Option<String>>) {
+    if let Err(e) = result {
+        eprintln!(
+            "{}{}",
+            style(Icon::Warning.prefix()).fg(Color::Yellow),
+            style(t!("listen_checkpoint_failed", error = e)).fg(Color::Yellow)
+        );
+    }
+}
+
+/// SIGINT 和 SIGTERM 只设置标志，由监听循环保存变更后退出
+#[cfg(unix)]
+mod signals {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static RECEIVED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn handle(_signal: libc::c_int) {
+        RECEIVED.store(true, Ordering::SeqCst);
+    }
+
+    pub fn install() {
+        let handler = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        // SAFETY: 处理函数只写入一个原子变量，是异步信号安全的
+        unsafe {
+            libc::signal(libc::SIGINT, handler);
+            libc::signal(libc::SIGTERM, handler);
+        }
+    }
+
+    pub fn received() -> bool {
+        RECEIVED.load(Ordering::SeqCst)
+    }
+}
+
+/// 删除上次未正常退出时留下的套接字文件；套接字仍有进程在监听时返回错误
+#[cfg(unix)]
+fn remove_stale_socket(socket: &Path) -> CcResult<()> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixStream;
+
+    let Ok(metadata) = std::fs::symlink_metadata(socket) else {
+        return Ok(());
+    };
+    if !metadata.file_type().is_socket() || UnixStream::connect(socket).is_ok() {
+        return Err(CheckpointError::InvalidArgument(
+            t!("listen_socket_in_use", socket = socket.display()).to_string(),
+        ));
+    }
+    std::fs::remove_file(socket)?;
+    Ok(())
+}
+
+/// `path` 相对于工作目录的路径，不在工作目录内时为 None
+fn relative_to(workdir: &Path, path: &Path) -> Option<PathBuf> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        workdir.join(path)
+    };
+    if let Ok(relative) = path.strip_prefix(workdir) {
+        return Some(relative.to_path_buf());
+    }
+    // 编辑器可能通过符号链接（如 macOS 的 /var）打开文件
+    let workdir = workdir.canonicalize().ok()?;
+    let path = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent.canonicalize().ok()?.join(name),
+        _ => path.canonicalize().ok()?,
+    };
+    path.strip_prefix(workdir).ok().map(Path::to_path_buf)
+}
+
+/// `file://` URI 对应的本地路径，其中的百分号编码会被解码
+fn file_uri_path(uri: &str) -> Option<String> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| encoded.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// 路径的最后一段
+fn base_name(path: &str) -> &str {
+    path.split('/').next_back().unwrap_or(path)
+}
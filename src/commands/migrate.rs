@@ -0,0 +1,34 @@
+use crate::commands::traits::{Command, CommandContext, MigrateArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use rust_i18n::t;
+
+/// Migrate命令实现
+pub struct MigrateCommand {
+    context: CommandContext,
+}
+
+impl MigrateCommand {
+    pub fn new(context: CommandContext) -> Self {
+        MigrateCommand { context }
+    }
+}
+
+impl Command for MigrateCommand {
+    type Args = MigrateArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        self.context
+            .checkpoint_service
+            .migrate_branch(&args.old_branch, &args.new_branch)
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.old_branch == args.new_branch {
+            return Err(CheckpointError::InvalidArgument(
+                t!("migrate_same_name").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -1,18 +1,76 @@
 pub mod traits;
 
 // 命令模块
+pub mod alias;
 pub mod create;
 pub mod diff;
+pub mod doctor;
+pub mod drift;
+pub mod evolution;
+pub mod export_metadata;
+pub mod fetch;
+pub mod flush;
+pub mod hook;
+pub mod impact;
 pub mod init;
 pub mod list;
+pub mod listen;
+pub mod migrate;
+pub mod multi;
+pub mod plugins;
+pub mod promote;
+pub mod prune;
+pub mod push;
+pub mod rebase;
+pub mod repack;
+pub mod report;
 pub mod restore;
+pub mod revert;
+pub mod reword;
+pub mod run;
+pub mod schedule;
+pub mod search;
 pub mod show;
+pub mod snapshot;
+pub mod split;
+pub mod stats;
+pub mod status;
+pub mod task;
 
 // 重新导出主要类型
+pub use alias::AliasCommand;
 pub use create::CreateCommand;
 pub use diff::DiffCommand;
+pub use doctor::DoctorCommand;
+pub use drift::DriftCommand;
+pub use evolution::EvolutionCommand;
+pub use export_metadata::ExportMetadataCommand;
+pub use fetch::FetchCommand;
+pub use flush::FlushCommand;
+pub use hook::HookCommand;
+pub use impact::ImpactCommand;
 pub use init::InitCommand;
 pub use list::ListCommand;
+pub use listen::ListenCommand;
+pub use migrate::MigrateCommand;
+pub use multi::MultiCommand;
+pub use plugins::PluginsCommand;
+pub use promote::PromoteCommand;
+pub use prune::PruneCommand;
+pub use push::PushCommand;
+pub use rebase::RebaseCommand;
+pub use repack::RepackCommand;
+pub use report::ReportCommand;
 pub use restore::RestoreCommand;
+pub use revert::RevertCommand;
+pub use reword::RewordCommand;
+pub use run::RunCommand;
+pub use schedule::ScheduleCommand;
+pub use search::SearchCommand;
 pub use show::ShowCommand;
+pub use snapshot::SnapshotCommand;
+pub use split::SplitCommand;
+pub use stats::StatsCommand;
+pub use status::StatusCommand;
+pub use task::TaskCommand;
 pub use traits::{Command, CommandContext};
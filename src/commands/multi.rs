@@ -0,0 +1,170 @@
+use crate::commands::traits::{Command, CommandContext, MultiArgs, MultiOperation};
+use crate::error::{CheckpointError, CliError, Result as CcResult};
+use crate::git_ops::{ListColumn, ListFilter};
+use crate::ui::{self, Icon};
+use console::{Color, style};
+use git2::Repository;
+use rust_i18n::t;
+use std::fs;
+use std::path::Path;
+
+/// 工作区文件名，每行一个仓库路径（相对路径相对于该文件所在目录）
+pub const WORKSPACE_FILE: &str = ".ccg-workspace";
+
+/// 批量操作的汇总结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultiReport {
+    /// 成功的仓库
+    pub succeeded: Vec<String>,
+    /// 失败的仓库及错误信息
+    pub failed: Vec<(String, String)>,
+}
+
+/// Multi命令实现
+pub struct MultiCommand {
+    /// 查找工作区文件的目录（`-C/--repo` 指定，默认为当前目录）
+    dir: Option<String>,
+    /// 在每个仓库中以只读模式执行（`--read-only`）
+    read_only: bool,
+}
+
+impl MultiCommand {
+    pub fn new(dir: Option<String>) -> Self {
+        MultiCommand {
+            dir,
+            read_only: false,
+        }
+    }
+
+    /// 设置是否以只读模式执行
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// 读取工作区文件中的仓库路径，忽略空行和 `#` 开头的注释
+    pub fn read_workspace(path: &Path) -> CcResult<Vec<String>> {
+        let content = fs::read_to_string(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => CheckpointError::InvalidArgument(
+                t!("multi_no_roots", file = WORKSPACE_FILE).to_string(),
+            ),
+            _ => CheckpointError::IoError(e),
+        })?;
+        let base = path.parent().filter(|base| *base != Path::new("."));
+        Ok(content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| match base {
+                Some(base) => base.join(line).to_string_lossy().to_string(),
+                None => line.to_string(),
+            })
+            .collect())
+    }
+
+    /// 在单个仓库中执行操作
+    ///
+    /// 不是 git 仓库的路径直接报错，不会像单仓库命令那样自动初始化。
+    /// `read_only` 为 true 时强制只读，否则沿用该仓库的 `ccg.readOnly` 配置。
+    pub fn run_in(root: &str, operation: &MultiOperation, read_only: bool) -> CcResult<()> {
+        if Repository::open(root).is_err() {
+            return Err(CheckpointError::RepositoryNotFound);
+        }
+        let mut context = CommandContext::new_with_path(Some(root))?;
+        if read_only {
+            context.checkpoint_service.set_read_only(true);
+        }
+        let service = &context.checkpoint_service;
+        match operation {
+            MultiOperation::List { number } => service.list_checkpoints(
+                *number,
+                &ListColumn::DEFAULT,
+                false,
+                &ListFilter::default(),
+            ),
+            MultiOperation::Create { message } => {
+                service.create_checkpoint(message.as_deref()).map(|_| ())
+            }
+            MultiOperation::Status => service.show_status(),
+        }
+    }
+
+    fn print_header(root: &str) {
+        println!(
+            "{}",
+            style(Icon::Separator.repeat(ui::width()))
+                .fg(Color::Blue)
+                .dim()
+        );
+        println!(
+            "{}{}",
+            style(Icon::Folder.prefix()).fg(Color::Blue),
+            style(root).fg(Color::Cyan).bold()
+        );
+    }
+
+    fn print_summary(report: &MultiReport) {
+        println!(
+            "{}",
+            style(Icon::Separator.repeat(ui::width()))
+                .fg(Color::Blue)
+                .dim()
+        );
+        println!(
+            "{}{}",
+            style(Icon::Stats.prefix()).fg(Color::Blue),
+            t!(
+                "multi_summary",
+                succeeded = report.succeeded.len(),
+                failed = report.failed.len()
+            )
+        );
+        for (root, error) in &report.failed {
+            println!(
+                "  {}{} {}",
+                style(Icon::Error.prefix()).fg(Color::Red),
+                style(root).fg(Color::Red).bold(),
+                style(error).fg(Color::Red)
+            );
+        }
+    }
+}
+
+impl Command for MultiCommand {
+    type Args = MultiArgs;
+    type Output = MultiReport;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let roots = if args.roots.is_empty() {
+            let dir = Path::new(self.dir.as_deref().unwrap_or("."));
+            Self::read_workspace(&dir.join(WORKSPACE_FILE))?
+        } else {
+            args.roots
+        };
+
+        // 每个仓库单独执行，一个仓库失败不影响其余仓库
+        let mut report = MultiReport::default();
+        for root in roots {
+            Self::print_header(&root);
+            match Self::run_in(&root, &args.operation, self.read_only) {
+                Ok(()) => report.succeeded.push(root),
+                Err(e) => {
+                    let message = e.to_string();
+                    eprint!("{}", ui::report::render(&CliError::from(e)));
+                    report.failed.push((root, message));
+                }
+            }
+        }
+        Self::print_summary(&report);
+        Ok(report)
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.roots.iter().any(|root| root.trim().is_empty()) {
+            return Err(CheckpointError::InvalidArgument(
+                t!("multi_empty_root").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
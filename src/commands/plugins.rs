@@ -0,0 +1,130 @@
+use crate::commands::traits::{Command, CommandContext, PluginsAction, PluginsArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::services::hooks::is_executable;
+use crate::ui::Icon;
+use console::{Color, style};
+use rust_i18n::t;
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+
+/// 插件可执行文件的前缀，`ccg-foo` 对应 `ccg foo`
+pub const PLUGIN_PREFIX: &str = "ccg-";
+
+/// 在 PATH 上发现的插件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Plugins命令实现
+pub struct PluginsCommand {
+    context: Option<CommandContext>,
+}
+
+impl PluginsCommand {
+    pub fn new(context: Option<CommandContext>) -> Self {
+        PluginsCommand { context }
+    }
+
+    /// 扫描 PATH，按名称返回所有插件（同名时 PATH 中靠前的优先）
+    pub fn discover() -> Vec<Plugin> {
+        let mut plugins = BTreeMap::new();
+        let Some(paths) = env::var_os("PATH") else {
+            return Vec::new();
+        };
+
+        for dir in env::split_paths(&paths) {
+            let Ok(entries) = dir.read_dir() else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Some(name) = plugin_name(&path) else {
+                    continue;
+                };
+                if is_executable(&path) {
+                    plugins.entry(name.clone()).or_insert(Plugin { name, path });
+                }
+            }
+        }
+
+        plugins.into_values().collect()
+    }
+
+    /// 查找指定名称的插件
+    pub fn find(name: &str) -> Option<Plugin> {
+        Self::discover().into_iter().find(|p| p.name == name)
+    }
+
+    /// 运行插件，透传参数并通过环境变量导出仓库上下文
+    pub fn run_plugin(&self, name: &str, args: &[OsString]) -> CcResult<ExitStatus> {
+        let plugin = Self::find(name)
+            .ok_or_else(|| CheckpointError::InvalidArgument(t!("plugin_not_found", name = name)))?;
+
+        let mut command = std::process::Command::new(&plugin.path);
+        command.args(args);
+        if let Some(context) = &self.context {
+            command.envs(context.export_env());
+        }
+        command.env("CCG_VERSION", env!("CARGO_PKG_VERSION"));
+
+        Ok(command.status()?)
+    }
+}
+
+impl Command for PluginsCommand {
+    type Args = PluginsArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        match args.action {
+            PluginsAction::List => {
+                let plugins = Self::discover();
+                if plugins.is_empty() {
+                    println!(
+                        "{}",
+                        style(t!("plugins_none", prefix = PLUGIN_PREFIX)).fg(Color::Yellow)
+                    );
+                    return Ok(());
+                }
+
+                println!(
+                    "{}",
+                    style(format!("{}{}", Icon::Plugin.prefix(), t!("plugins_header")))
+                        .fg(Color::Green)
+                        .bold()
+                );
+                println!();
+                for plugin in plugins {
+                    println!(
+                        "  {} {}",
+                        style(format!("{:<16}", plugin.name))
+                            .fg(Color::Yellow)
+                            .bold(),
+                        style(plugin.path.display()).fg(Color::Cyan)
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 从文件名中提取插件名称（Windows 下只接受可执行扩展名并去掉扩展名）
+fn plugin_name(path: &Path) -> Option<String> {
+    let file_name = if cfg!(windows) {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        if !matches!(ext.as_str(), "exe" | "bat" | "cmd") {
+            return None;
+        }
+        path.file_stem()?.to_str()?
+    } else {
+        path.file_name()?.to_str()?
+    };
+    let name = file_name.strip_prefix(PLUGIN_PREFIX)?;
+    (!name.is_empty()).then(|| name.to_string())
+}
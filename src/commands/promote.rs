@@ -0,0 +1,41 @@
+use crate::commands::traits::{Command, CommandContext, PromoteArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use rust_i18n::t;
+
+/// Promote命令实现
+pub struct PromoteCommand {
+    context: CommandContext,
+}
+
+impl PromoteCommand {
+    pub fn new(context: CommandContext) -> Self {
+        PromoteCommand { context }
+    }
+}
+
+impl Command for PromoteCommand {
+    type Args = PromoteArgs;
+    type Output = String;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        self.context.checkpoint_service.promote_task(
+            &args.task,
+            args.message.as_deref(),
+            args.skip_format_only,
+        )
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.task.trim().is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("promote_empty_task").to_string(),
+            ));
+        }
+        if args.message.as_deref().is_some_and(|m| m.trim().is_empty()) {
+            return Err(CheckpointError::InvalidArgument(
+                t!("promote_empty_message").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
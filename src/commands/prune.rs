@@ -0,0 +1,263 @@
+use crate::commands::traits::{Command, CommandContext, PruneArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::git_ops::CheckpointEntry;
+use crate::services::CheckpointMetadata;
+use crate::ui::prompt::{self, PromptKind};
+use crate::ui::{Icon, short_hash};
+use chrono::DateTime;
+use console::{Color, style};
+use rust_i18n::t;
+use std::collections::HashSet;
+
+/// 确认清理前最多列出的检查点数量
+const PRUNE_PREVIEW_LIMIT: usize = 10;
+
+/// 交互选择列表中的一行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PruneItem {
+    /// 同一天同一会话的连续检查点，选中时选中整组
+    Group {
+        /// `日期 · 会话` 形式的标题
+        title: String,
+        /// 组内检查点在元数据列表中的下标
+        members: Vec<usize>,
+    },
+    /// 元数据列表中的一个检查点
+    Checkpoint(usize),
+}
+
+/// 把检查点（从新到旧）按日期和会话分组，每组标题在前、检查点在后
+///
+/// 只有相邻的检查点才会归入同一组，交错的会话因此保持时间顺序。
+pub fn prune_items(metadata: &[CheckpointMetadata]) -> Vec<PruneItem> {
+    let mut items = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+    for (i, entry) in metadata.iter().map(|m| &m.entry).enumerate() {
+        let title = group_title(entry);
+        match &current {
+            Some((last, header)) if *last == title => {
+                if let PruneItem::Group { members, .. } = &mut items[*header] {
+                    members.push(i);
+                }
+            }
+            _ => {
+                current = Some((title.clone(), items.len()));
+                items.push(PruneItem::Group {
+                    title,
+                    members: vec![i],
+                });
+            }
+        }
+        items.push(PruneItem::Checkpoint(i));
+    }
+    items
+}
+
+/// 选中的列表行对应的检查点下标，从新到旧且不重复
+pub fn selected_checkpoints(items: &[PruneItem], selected: &[usize]) -> Vec<usize> {
+    let mut chosen: Vec<usize> = selected
+        .iter()
+        .flat_map(|&row| match &items[row] {
+            PruneItem::Group { members, .. } => members.clone(),
+            PruneItem::Checkpoint(i) => vec![*i],
+        })
+        .collect();
+    chosen.sort_unstable();
+    chosen.dedup();
+    chosen
+}
+
+/// 分组标题：检查点的日期和会话
+fn group_title(entry: &CheckpointEntry) -> String {
+    let day = DateTime::from_timestamp(entry.time, 0)
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let session = match entry.session() {
+        Some(session) => t!("prune_group_session", session = short_hash(&session)).to_string(),
+        None => t!("prune_group_no_session").to_string(),
+    };
+    format!("{day}  {session}")
+}
+
+/// Prune命令实现：丢弃选中的检查点并一次性改写历史，带标签的和里程碑检查点受保护
+pub struct PruneCommand {
+    context: CommandContext,
+}
+
+impl PruneCommand {
+    pub fn new(context: CommandContext) -> Self {
+        PruneCommand { context }
+    }
+
+    /// 在多选列表中选择要清理的检查点，`checked` 中的检查点预先选中
+    ///
+    /// 受保护的检查点不出现在列表中。
+    fn choose(&self, checked: &[CheckpointEntry]) -> CcResult<Vec<String>> {
+        let service = &self.context.checkpoint_service;
+        let protected = service.protected_checkpoints()?;
+        let metadata: Vec<CheckpointMetadata> = service
+            .checkpoint_metadata()?
+            .into_iter()
+            .filter(|m| !protected.contains_key(&m.entry.hash))
+            .collect();
+        if !protected.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!("prune_protected_hidden", count = protected.len())).fg(Color::White)
+            );
+        }
+        if metadata.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let items = prune_items(&metadata);
+        let labels: Vec<String> = items
+            .iter()
+            .map(|item| match item {
+                PruneItem::Group { title, members } => style(format!(
+                    "{title} ({})",
+                    t!("prune_group_count", count = members.len())
+                ))
+                .bold()
+                .to_string(),
+                PruneItem::Checkpoint(i) => item_label(&metadata[*i]),
+            })
+            .collect();
+        let checked: HashSet<&str> = checked.iter().map(|e| e.hash.as_str()).collect();
+        let defaults: Vec<bool> = items
+            .iter()
+            .map(|item| match item {
+                PruneItem::Checkpoint(i) => checked.contains(metadata[*i].entry.hash.as_str()),
+                PruneItem::Group { .. } => false,
+            })
+            .collect();
+
+        let rows = prompt::select(&t!("prune_select"), &labels, &defaults)?;
+        Ok(selected_checkpoints(&items, &rows)
+            .into_iter()
+            .map(|i| metadata[i].entry.hash.clone())
+            .collect())
+    }
+}
+
+/// 列表中一个检查点的文字：短 hash、时间、ID、摘要和增删行数
+fn item_label(metadata: &CheckpointMetadata) -> String {
+    let entry = &metadata.entry;
+    let time = DateTime::from_timestamp(entry.time, 0)
+        .map(|dt| dt.format("%H:%M").to_string())
+        .unwrap_or_default();
+    let id = entry.display_id().unwrap_or_default();
+    format!(
+        "  {} {} {:<6} {} {}",
+        style(short_hash(&entry.hash)).fg(Color::Yellow),
+        style(time).fg(Color::Cyan),
+        style(id).fg(Color::Magenta),
+        entry.summary(),
+        style(format!(
+            "(+{} {}{})",
+            metadata.stats.additions,
+            Icon::Minus.glyph(),
+            metadata.stats.deletions
+        ))
+        .dim()
+    )
+}
+
+/// 列出将被丢弃的检查点，最多显示 [`PRUNE_PREVIEW_LIMIT`] 个
+fn print_preview(entries: &[CheckpointEntry]) {
+    println!(
+        "{}{}",
+        style(Icon::Warning.prefix()).fg(Color::Yellow),
+        style(t!("prune_preview", count = entries.len())).fg(Color::Yellow)
+    );
+    for entry in entries.iter().take(PRUNE_PREVIEW_LIMIT) {
+        let time = DateTime::from_timestamp(entry.time, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        println!(
+            "  {} {} {} {}",
+            style(Icon::Drop.glyph()).fg(Color::Red),
+            style(short_hash(&entry.hash)).fg(Color::Yellow).bold(),
+            style(time).fg(Color::Cyan),
+            entry.summary()
+        );
+    }
+    if entries.len() > PRUNE_PREVIEW_LIMIT {
+        println!(
+            "  {}",
+            style(t!(
+                "prune_preview_more",
+                count = entries.len() - PRUNE_PREVIEW_LIMIT
+            ))
+            .dim()
+        );
+    }
+}
+
+impl Command for PruneCommand {
+    type Args = PruneArgs;
+    /// 被丢弃的检查点，从新到旧
+    type Output = Vec<CheckpointEntry>;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let service = &self.context.checkpoint_service;
+        let candidates = if args.keep.is_some() || args.before.is_some() || args.strategy.is_some()
+        {
+            service.prune_candidates(args.keep, args.before.as_deref(), args.strategy)?
+        } else {
+            Vec::new()
+        };
+
+        // 传给服务的检查点可以包括受保护的，服务会跳过并说明；`count` 是实际丢弃的数量
+        let (hashes, count) = if args.interactive {
+            let hashes = self.choose(&candidates)?;
+            if hashes.is_empty() {
+                println!(
+                    "{}{}",
+                    style(Icon::Info.prefix()).fg(Color::Blue),
+                    style(t!("prune_nothing")).fg(Color::White)
+                );
+                return Ok(Vec::new());
+            }
+            let count = hashes.len();
+            (hashes, count)
+        } else {
+            let protected = service.protected_checkpoints()?;
+            let dropped: Vec<CheckpointEntry> = candidates
+                .iter()
+                .filter(|entry| !protected.contains_key(&entry.hash))
+                .cloned()
+                .collect();
+            if dropped.is_empty() {
+                return service.prune_checkpoints(&[]);
+            }
+            print_preview(&dropped);
+            let hashes: Vec<String> = candidates.iter().map(|entry| entry.hash.clone()).collect();
+            (hashes, dropped.len())
+        };
+
+        if !prompt::confirm(
+            PromptKind::Destructive,
+            &t!("prune_confirm", count = count),
+            None,
+        )? {
+            println!("{}", t!("prune_cancelled"));
+            return Ok(Vec::new());
+        }
+        service.prune_checkpoints(&hashes)
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if !args.interactive
+            && args.keep.is_none()
+            && args.before.is_none()
+            && args.strategy.is_none()
+        {
+            return Err(CheckpointError::InvalidArgument(
+                t!("prune_nothing_selected").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use crate::commands::traits::{Command, CommandContext, PushArgs};
+use crate::error::Result as CcResult;
+
+/// Push命令实现，把检查点发布为自己在远程上的命名空间
+pub struct PushCommand {
+    context: CommandContext,
+}
+
+impl PushCommand {
+    pub fn new(context: CommandContext) -> Self {
+        PushCommand { context }
+    }
+}
+
+impl Command for PushCommand {
+    type Args = PushArgs;
+    type Output = String;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        self.context
+            .checkpoint_service
+            .publish_checkpoints(&args.remote)
+    }
+}
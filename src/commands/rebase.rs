@@ -0,0 +1,39 @@
+use crate::commands::traits::{Command, CommandContext, RebaseArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use rust_i18n::t;
+
+/// Rebase命令实现
+pub struct RebaseCommand {
+    context: CommandContext,
+}
+
+impl RebaseCommand {
+    pub fn new(context: CommandContext) -> Self {
+        RebaseCommand { context }
+    }
+}
+
+impl Command for RebaseCommand {
+    type Args = RebaseArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        self.context
+            .checkpoint_service
+            .rebase_checkpoints(args.onto.as_deref(), args.replay)?;
+        Ok(())
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args
+            .onto
+            .as_deref()
+            .is_some_and(|onto| onto.trim().is_empty())
+        {
+            return Err(CheckpointError::InvalidArgument(
+                t!("rebase_empty_onto").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
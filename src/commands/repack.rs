@@ -0,0 +1,31 @@
+use crate::commands::traits::{Command, CommandContext, RepackArgs};
+use crate::error::Result as CcResult;
+use crate::git_ops::{RepackReport, RepackSettings};
+
+/// Repack命令实现：把反复变更的大文件打包为增量，减少检查点占用的空间
+pub struct RepackCommand {
+    context: CommandContext,
+}
+
+impl RepackCommand {
+    pub fn new(context: CommandContext) -> Self {
+        RepackCommand { context }
+    }
+}
+
+impl Command for RepackCommand {
+    type Args = RepackArgs;
+    type Output = RepackReport;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let defaults = self.context.git_ops.repack_settings();
+        let settings = RepackSettings {
+            window: args.window.unwrap_or(defaults.window),
+            depth: args.depth.unwrap_or(defaults.depth),
+            min_size: args.min_size.unwrap_or(defaults.min_size),
+        };
+        self.context
+            .checkpoint_service
+            .repack_large_files(&settings)
+    }
+}
@@ -0,0 +1,51 @@
+use crate::commands::traits::{Command, CommandContext, ReportArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::ui::Icon;
+use console::{Color, style};
+use rust_i18n::t;
+
+/// Report命令实现，把一个会话的检查点整理成 Markdown 或 HTML 报告
+pub struct ReportCommand {
+    context: CommandContext,
+}
+
+impl ReportCommand {
+    pub fn new(context: CommandContext) -> Self {
+        ReportCommand { context }
+    }
+}
+
+impl Command for ReportCommand {
+    type Args = ReportArgs;
+    /// 生成的报告
+    type Output = String;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let report = self
+            .context
+            .checkpoint_service
+            .session_report(args.session.as_deref())?
+            .render(args.format);
+        match &args.output {
+            Some(path) => {
+                std::fs::write(path, &report)?;
+                println!(
+                    "{}{}",
+                    style(Icon::Success.prefix()).fg(Color::Green),
+                    style(t!("report_written", path = path)).fg(Color::Green)
+                );
+            }
+            None => print!("{report}"),
+        }
+        Ok(report)
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.session.as_deref().is_some_and(|s| s.trim().is_empty()) {
+            return Err(CheckpointError::InvalidArgument(
+                t!("report_empty_session").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
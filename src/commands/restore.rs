@@ -1,6 +1,12 @@
 use crate::commands::traits::{Command, CommandContext, RestoreArgs};
 use crate::error::Result as CcResult;
-use dialoguer::Confirm;
+use crate::services::RestoreOptions;
+use crate::ui::prompt::{self, PromptKind};
+use rust_i18n::t;
+use std::path::Path;
+
+/// 确认恢复前最多列出的被丢弃检查点数量
+const DISCARDED_PREVIEW_LIMIT: usize = 10;
 
 /// Restore命令实现
 pub struct RestoreCommand {
@@ -11,6 +17,35 @@ impl RestoreCommand {
     pub fn new(context: CommandContext) -> Self {
         RestoreCommand { context }
     }
+
+    /// 逐个变更块确认，只把选中的部分恢复到工作目录
+    fn restore_patch(&self, hash: &str) -> CcResult<()> {
+        let service = &self.context.checkpoint_service;
+        let hunks = service.restore_hunks(hash)?;
+        if hunks.is_empty() {
+            println!("{}", t!("restore_no_hunks"));
+            return Ok(());
+        }
+
+        let mut selected = Vec::with_capacity(hunks.len());
+        for (index, hunk) in hunks.iter().enumerate() {
+            service.print_restore_hunk(hunk, index, hunks.len());
+            // 选择变更块不是确认，不受 ccg.prompt 影响
+            selected.push(prompt::ask(&t!("restore_hunk_prompt"), Some(false))?);
+            println!();
+        }
+
+        let applied = service.apply_restore_hunks(hash, &selected)?;
+        if applied == 0 {
+            println!("{}", t!("restore_no_hunks_selected"));
+        } else {
+            println!(
+                "{}",
+                t!("restore_hunks_applied", count = applied, hash = hash)
+            );
+        }
+        Ok(())
+    }
 }
 
 impl Command for RestoreCommand {
@@ -18,17 +53,56 @@ impl Command for RestoreCommand {
     type Output = ();
 
     fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
-        if Confirm::new()
-            .with_prompt("您确定要恢复此检查点吗？这将覆盖当前的工作目录。")
-            .interact()?
-        {
-            println!("正在恢复检查点...");
+        if args.patch {
+            return self.restore_patch(&args.hash);
+        }
+        // 写入其他目录不影响工作目录，无需确认
+        if let Some(dir) = &args.to {
+            self.context
+                .checkpoint_service
+                .restore_to_directory(&args.hash, Path::new(dir))?;
+            return Ok(());
+        }
+
+        // 先展示将被修改的文件，让确认有据可依
+        self.context
+            .checkpoint_service
+            .show_restore_preview(&args.hash, args.clean)?;
+        if args.preview {
+            return Ok(());
+        }
+        // 在确认之前检查基准分支，避免确认后才被拒绝
+        if !args.force {
             self.context
                 .checkpoint_service
-                .restore_checkpoint(&args.hash)?;
-            println!("检查点 {} 已成功恢复。", args.hash);
+                .check_restore_base(&args.hash)?;
+        }
+
+        // 列出将被丢弃的后续检查点，用户看过之后再确认
+        let service = &self.context.checkpoint_service;
+        let discarded = service.discarded_checkpoints(&args.hash)?;
+        let divergence = service.restore_divergence(&args.hash)?;
+        service.print_discarded_checkpoints(&discarded, divergence, DISCARDED_PREVIEW_LIMIT);
+        let question = if discarded.is_empty() {
+            t!("restore_confirm")
+        } else {
+            t!("restore_confirm_discard", count = discarded.len())
+        };
+
+        if prompt::confirm(PromptKind::Destructive, &question, None)? {
+            println!("{}", t!("restore_in_progress"));
+            self.context.checkpoint_service.restore_checkpoint_with(
+                &args.hash,
+                RestoreOptions {
+                    force: args.force,
+                    clean: args.clean,
+                    continue_on_error: args.continue_on_error,
+                    bypass_guard: args.bypass_guard,
+                },
+            )?;
+            println!("{}", t!("restore_done", hash = args.hash));
         } else {
-            println!("恢复操作已取消。");
+            println!("{}", t!("restore_cancelled"));
         }
         Ok(())
     }
@@ -36,7 +110,7 @@ impl Command for RestoreCommand {
     fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
         if args.hash.is_empty() {
             return Err(crate::error::CheckpointError::InvalidArgument(
-                "检查点哈希值不能为空".to_string(),
+                t!("restore_hash_empty").to_string(),
             ));
         }
         Ok(())
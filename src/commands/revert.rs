@@ -0,0 +1,35 @@
+use crate::commands::traits::{Command, CommandContext, RevertArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use rust_i18n::t;
+
+/// Revert命令实现
+pub struct RevertCommand {
+    context: CommandContext,
+}
+
+impl RevertCommand {
+    pub fn new(context: CommandContext) -> Self {
+        RevertCommand { context }
+    }
+}
+
+impl Command for RevertCommand {
+    type Args = RevertArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        self.context
+            .checkpoint_service
+            .revert_checkpoint(&args.hash)?;
+        Ok(())
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.hash.is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("hash_empty").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use crate::commands::traits::{Command, CommandContext, RewordArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use dialoguer::Editor;
+use rust_i18n::t;
+
+/// Reword命令实现
+pub struct RewordCommand {
+    context: CommandContext,
+}
+
+impl RewordCommand {
+    pub fn new(context: CommandContext) -> Self {
+        RewordCommand { context }
+    }
+
+    /// 在编辑器中修改当前提交信息，未保存时视为取消
+    fn edit_message(&self, hash: &str) -> CcResult<String> {
+        let full_hash = self.context.checkpoint_service.resolve_checkpoint(hash)?;
+        let commit = self.context.git_ops.find_commit(&full_hash)?;
+        let current = commit.message().unwrap_or_default().to_string();
+        Editor::new()
+            .edit(&current)?
+            .ok_or(CheckpointError::UserCancelled)
+    }
+}
+
+impl Command for RewordCommand {
+    type Args = RewordArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let message = match args.message {
+            Some(message) => message,
+            None => self.edit_message(&args.hash)?,
+        };
+        self.context
+            .checkpoint_service
+            .reword_checkpoint(&args.hash, &message)?;
+        Ok(())
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.hash.is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("hash_empty").to_string(),
+            ));
+        }
+        if args.message.as_deref().is_some_and(|m| m.trim().is_empty()) {
+            return Err(CheckpointError::InvalidArgument(t!("reword_empty_message")));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use crate::commands::traits::{Command, CommandContext, RunArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use rust_i18n::t;
+use std::process::ExitStatus;
+
+/// Run命令实现
+pub struct RunCommand {
+    context: CommandContext,
+}
+
+impl RunCommand {
+    pub fn new(context: CommandContext) -> Self {
+        RunCommand { context }
+    }
+}
+
+impl Command for RunCommand {
+    type Args = RunArgs;
+    type Output = ExitStatus;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        self.context
+            .checkpoint_service
+            .run_in_checkpoint(&args.hash, &args.command, args.keep)
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.hash.is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("hash_empty").to_string(),
+            ));
+        }
+        if args.command.is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("run_no_command").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
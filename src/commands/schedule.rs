@@ -0,0 +1,202 @@
+use crate::commands::traits::{Command, CommandContext, ScheduleAction, ScheduleArgs};
+use crate::config::{KEY_SCHEDULE_EVERY, KEY_SCHEDULE_SCHEDULER};
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::services::schedule::{
+    SCHEDULED_MESSAGE, ScheduleEntry, ScheduleInterval, Scheduler, home_dir,
+};
+use crate::ui::Icon;
+use chrono::DateTime;
+use console::{Color, style};
+use rust_i18n::t;
+
+/// Schedule命令实现，在用户级调度器中注册定时快照
+pub struct ScheduleCommand {
+    context: CommandContext,
+}
+
+impl ScheduleCommand {
+    pub fn new(context: CommandContext) -> Self {
+        ScheduleCommand { context }
+    }
+
+    /// 本仓库使用 `scheduler` 和 `interval` 的条目
+    pub fn entry(
+        &self,
+        scheduler: Scheduler,
+        interval: ScheduleInterval,
+    ) -> CcResult<ScheduleEntry> {
+        let workdir = self
+            .context
+            .git_ops
+            .get_repo()
+            .workdir()
+            .ok_or_else(|| CheckpointError::InvalidArgument(t!("schedule_bare").to_string()))?;
+        Ok(ScheduleEntry {
+            scheduler,
+            repo: workdir.canonicalize()?,
+            exe: std::env::current_exe()?,
+            interval,
+            home: home_dir()?,
+        })
+    }
+
+    fn install(
+        &self,
+        every: ScheduleInterval,
+        scheduler: Option<Scheduler>,
+        print: bool,
+    ) -> CcResult<()> {
+        let entry = self.entry(scheduler.unwrap_or_else(Scheduler::detect), every)?;
+        if print {
+            print!("{}", entry.describe()?);
+            return Ok(());
+        }
+        // 换用其他调度器时先删除原来的条目，避免重复快照
+        let config = self.context.git_ops.config();
+        if let Some(previous) = config.schedule_scheduler
+            && previous != entry.scheduler
+        {
+            self.entry(previous, every)?.remove()?;
+        }
+        entry.install()?;
+
+        let mut local = self
+            .context
+            .git_ops
+            .get_repo()
+            .config()?
+            .open_level(git2::ConfigLevel::Local)?;
+        local.set_str(KEY_SCHEDULE_SCHEDULER, entry.scheduler.name())?;
+        local.set_str(KEY_SCHEDULE_EVERY, &every.to_string())?;
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            style(t!(
+                "schedule_installed",
+                every = every,
+                scheduler = entry.scheduler,
+                name = entry.name()
+            ))
+            .fg(Color::Green)
+        );
+        Ok(())
+    }
+
+    fn status(&self) -> CcResult<()> {
+        let config = self.context.git_ops.config();
+        let (Some(scheduler), Some(every)) = (config.schedule_scheduler, config.schedule_every)
+        else {
+            println!(
+                "{}{}",
+                style(Icon::Empty.prefix()).fg(Color::Yellow),
+                style(t!("schedule_none")).fg(Color::Yellow)
+            );
+            return Ok(());
+        };
+        let entry = self.entry(scheduler, every)?;
+        if entry.is_installed() {
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!(
+                    "schedule_status",
+                    every = every,
+                    scheduler = scheduler,
+                    name = entry.name()
+                ))
+                .fg(Color::Green)
+            );
+        } else {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!(
+                    "schedule_status_missing",
+                    every = every,
+                    scheduler = scheduler
+                ))
+                .fg(Color::Yellow)
+            );
+        }
+        for (path, _) in entry.files() {
+            println!("   {}", style(path.display()).dim());
+        }
+
+        let last = self
+            .context
+            .checkpoint_service
+            .checkpoints(usize::MAX)?
+            .into_iter()
+            .find(|checkpoint| checkpoint.summary() == SCHEDULED_MESSAGE);
+        let line = match last.and_then(|checkpoint| DateTime::from_timestamp(checkpoint.time, 0)) {
+            Some(time) => t!(
+                "schedule_last",
+                time = time.format("%Y-%m-%d %H:%M:%S").to_string()
+            ),
+            None => t!("schedule_never"),
+        };
+        println!("   {}", style(line).dim());
+        Ok(())
+    }
+
+    fn remove(&self) -> CcResult<()> {
+        let config = self.context.git_ops.config();
+        let scheduler = config.schedule_scheduler.unwrap_or_else(Scheduler::detect);
+        // 删除只需要条目名称，间隔无关紧要
+        let every = config
+            .schedule_every
+            .unwrap_or_else(|| "30m".parse().expect("valid interval"));
+        let entry = self.entry(scheduler, every)?;
+        let removed = entry.remove()?;
+
+        let mut local = self
+            .context
+            .git_ops
+            .get_repo()
+            .config()?
+            .open_level(git2::ConfigLevel::Local)?;
+        for key in [KEY_SCHEDULE_SCHEDULER, KEY_SCHEDULE_EVERY] {
+            match local.remove(key) {
+                Ok(()) => {}
+                Err(e) if e.code() == git2::ErrorCode::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if removed {
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!(
+                    "schedule_removed",
+                    scheduler = scheduler,
+                    name = entry.name()
+                ))
+                .fg(Color::Green)
+            );
+        } else {
+            println!(
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!("schedule_none")).fg(Color::White)
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Command for ScheduleCommand {
+    type Args = ScheduleArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        match args.action {
+            ScheduleAction::Install {
+                every,
+                scheduler,
+                print,
+            } => self.install(every, scheduler, print),
+            ScheduleAction::Status => self.status(),
+            ScheduleAction::Remove => self.remove(),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use crate::commands::traits::{Command, CommandContext, SearchArgs};
+use crate::error::Result as CcResult;
+use crate::services::{CheckpointMetadata, query};
+
+/// Search命令实现，列出满足查询的检查点
+pub struct SearchCommand {
+    context: CommandContext,
+}
+
+impl SearchCommand {
+    pub fn new(context: CommandContext) -> Self {
+        SearchCommand { context }
+    }
+}
+
+impl Command for SearchCommand {
+    type Args = SearchArgs;
+    /// 满足查询的检查点，从新到旧
+    type Output = Vec<CheckpointMetadata>;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let matches = self
+            .context
+            .checkpoint_service
+            .search_checkpoints(&args.query)?;
+        print!("{}", query::render(&args.text, &matches));
+        Ok(matches)
+    }
+}
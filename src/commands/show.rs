@@ -1,5 +1,8 @@
 use crate::commands::traits::{Command, CommandContext, ShowArgs};
 use crate::error::Result as CcResult;
+use crate::git_ops::set_diff_settings;
+use rust_i18n::t;
+use std::path::Path;
 
 /// Show命令实现
 pub struct ShowCommand {
@@ -17,15 +20,28 @@ impl Command for ShowCommand {
     type Output = ();
 
     fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
-        self.context
-            .checkpoint_service
-            .show_checkpoint(&args.hash, args.diff)
+        set_diff_settings(args.diff_settings);
+        let service = &self.context.checkpoint_service;
+        match &args.file {
+            Some(file) => service.show_checkpoint_file(
+                &args.hash,
+                file,
+                args.highlight,
+                args.output.as_deref().map(Path::new),
+            ),
+            None => service.show_checkpoint(&args.hash, args.diff, args.full, &args.files),
+        }
     }
 
     fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
         if args.hash.is_empty() {
             return Err(crate::error::CheckpointError::InvalidArgument(
-                "检查点哈希值不能为空".to_string(),
+                t!("hash_empty").to_string(),
+            ));
+        }
+        if args.file.as_deref().is_some_and(|f| f.trim().is_empty()) {
+            return Err(crate::error::CheckpointError::InvalidArgument(
+                t!("file_path_empty").to_string(),
             ));
         }
         Ok(())
@@ -0,0 +1,45 @@
+use crate::commands::traits::{Command, CommandContext, SnapshotAction, SnapshotArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::git_ops::Snapshot;
+use rust_i18n::t;
+
+/// Snapshot命令实现
+pub struct SnapshotCommand {
+    context: CommandContext,
+}
+
+impl SnapshotCommand {
+    pub fn new(context: CommandContext) -> Self {
+        SnapshotCommand { context }
+    }
+}
+
+impl Command for SnapshotCommand {
+    type Args = SnapshotArgs;
+    /// 推入或弹出的快照，或列出的全部快照
+    type Output = Vec<Snapshot>;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        let service = &self.context.checkpoint_service;
+        match args.action {
+            SnapshotAction::Push { message } => {
+                Ok(vec![service.push_snapshot(message.as_deref())?])
+            }
+            SnapshotAction::Pop { number } => Ok(vec![service.pop_snapshot(number)?]),
+            SnapshotAction::List => service.list_snapshots(),
+        }
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if let SnapshotAction::Push {
+            message: Some(message),
+        } = &args.action
+            && message.trim().is_empty()
+        {
+            return Err(CheckpointError::InvalidArgument(
+                t!("snapshot_empty_message").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use crate::commands::traits::{Command, CommandContext, SplitArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use rust_i18n::t;
+
+/// Split命令实现
+pub struct SplitCommand {
+    context: CommandContext,
+}
+
+impl SplitCommand {
+    pub fn new(context: CommandContext) -> Self {
+        SplitCommand { context }
+    }
+}
+
+impl Command for SplitCommand {
+    type Args = SplitArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        self.context
+            .checkpoint_service
+            .split_checkpoint(&args.hash)?;
+        Ok(())
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if args.hash.is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("hash_empty").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
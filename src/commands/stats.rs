@@ -0,0 +1,24 @@
+use crate::commands::traits::{Command, CommandContext, StatsArgs};
+use crate::error::Result as CcResult;
+
+/// Stats命令实现
+pub struct StatsCommand {
+    context: CommandContext,
+}
+
+impl StatsCommand {
+    pub fn new(context: CommandContext) -> Self {
+        StatsCommand { context }
+    }
+}
+
+impl Command for StatsCommand {
+    type Args = StatsArgs;
+    type Output = ();
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        self.context
+            .checkpoint_service
+            .show_storage_stats(args.dedupe)
+    }
+}
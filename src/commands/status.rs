@@ -0,0 +1,22 @@
+use crate::commands::traits::{Command, CommandContext, StatusArgs};
+use crate::error::Result as CcResult;
+
+/// Status命令实现
+pub struct StatusCommand {
+    context: CommandContext,
+}
+
+impl StatusCommand {
+    pub fn new(context: CommandContext) -> Self {
+        StatusCommand { context }
+    }
+}
+
+impl Command for StatusCommand {
+    type Args = StatusArgs;
+    type Output = ();
+
+    fn execute(&self, _args: Self::Args) -> CcResult<Self::Output> {
+        self.context.checkpoint_service.show_status()
+    }
+}
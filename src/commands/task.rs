@@ -0,0 +1,38 @@
+use crate::commands::traits::{Command, CommandContext, TaskAction, TaskArgs};
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::git_ops::Task;
+use rust_i18n::t;
+
+/// Task命令实现
+pub struct TaskCommand {
+    context: CommandContext,
+}
+
+impl TaskCommand {
+    pub fn new(context: CommandContext) -> Self {
+        TaskCommand { context }
+    }
+}
+
+impl Command for TaskCommand {
+    type Args = TaskArgs;
+    type Output = Task;
+
+    fn execute(&self, args: Self::Args) -> CcResult<Self::Output> {
+        match args.action {
+            TaskAction::Start { name } => self.context.checkpoint_service.start_task(&name),
+            TaskAction::End => self.context.checkpoint_service.end_task(),
+        }
+    }
+
+    fn validate_args(&self, args: &Self::Args) -> CcResult<()> {
+        if let TaskAction::Start { name } = &args.action
+            && name.trim().is_empty()
+        {
+            return Err(CheckpointError::InvalidArgument(
+                t!("task_empty_name").to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
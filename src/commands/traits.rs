@@ -1,6 +1,14 @@
+use crate::config::ExcludeProfile;
 use crate::error::Result as CcResult;
-use crate::git_ops::GitOperations;
+use crate::git_ops::{
+    CCG_BRANCH_NAME, CheckpointLabel, DiffSettings, GitOperations, ListColumn, abbrev_len,
+};
 use crate::services::CheckpointService;
+use crate::services::integration::InstallTarget;
+use crate::services::schedule::{ScheduleInterval, Scheduler};
+use crate::services::{CheckpointQuery, MetadataFormat, PruneStrategy, ReportFormat};
+use crate::ui::{self, SHORT_HASH_LEN};
+use git2::Repository;
 
 /// 统一的命令接口
 pub trait Command {
@@ -32,6 +40,12 @@ impl CommandContext {
 
     pub fn new_with_path(path: Option<&str>) -> CcResult<Self> {
         let git_ops = GitOperations::new(path)?;
+        let git_dir = git_ops.get_repo().path().to_path_buf();
+        ui::set_abbrev_with(move || {
+            Repository::open(&git_dir)
+                .map(|repo| abbrev_len(&repo))
+                .unwrap_or(SHORT_HASH_LEN)
+        });
         let checkpoint_service = CheckpointService::new(git_ops.clone())?;
 
         Ok(CommandContext {
@@ -39,30 +53,174 @@ impl CommandContext {
             checkpoint_service,
         })
     }
+
+    /// 导出仓库上下文为环境变量，供插件等外部程序使用
+    pub fn export_env(&self) -> Vec<(String, String)> {
+        let repo = self.git_ops.get_repo();
+        let mut vars = vec![
+            (
+                "CCG_GIT_DIR".to_string(),
+                repo.path().to_string_lossy().to_string(),
+            ),
+            ("CCG_BRANCH".to_string(), CCG_BRANCH_NAME.to_string()),
+        ];
+        if let Some(workdir) = repo.workdir() {
+            vars.push((
+                "CCG_REPO_ROOT".to_string(),
+                workdir.to_string_lossy().to_string(),
+            ));
+        }
+        if let Ok(branch) = self.git_ops.get_current_branch_name() {
+            vars.push(("CCG_CURRENT_BRANCH".to_string(), branch));
+        }
+        vars
+    }
 }
 
 // 命令参数结构体定义
 
-/// Init命令参数（无参数）
+/// Init命令参数
+#[derive(Debug, Clone, Default)]
+pub struct InitArgs {
+    /// 基于此分支创建 ccg 分支，默认为当前 HEAD
+    pub from: Option<String>,
+    /// 创建不与当前分支共享历史的孤立 ccg 分支，与 `from` 互斥
+    pub orphan: bool,
+    /// 写入 `ccg.checkpoint.exclude` 的内置排除规则
+    pub profiles: Vec<ExcludeProfile>,
+}
+
+/// Migrate命令参数
+#[derive(Debug, Clone)]
+pub struct MigrateArgs {
+    /// 要重命名的分支
+    pub old_branch: String,
+    /// 新的分支名称
+    pub new_branch: String,
+}
+
+/// Doctor命令参数（无参数）
+#[derive(Debug, Clone)]
+pub struct DoctorArgs;
+
+/// Flush命令参数（无参数）
+#[derive(Debug, Clone)]
+pub struct FlushArgs;
+
+/// Evolution命令参数
+#[derive(Debug, Clone)]
+pub struct EvolutionArgs {
+    pub file: String,
+    /// 输出每个版本的完整内容而不是差异
+    pub full: bool,
+    /// 只显示此时间之后的检查点
+    pub since: Option<String>,
+    /// 只显示此时间之前的检查点
+    pub until: Option<String>,
+}
+
+/// Drift命令参数（无参数）
+#[derive(Debug, Clone)]
+pub struct DriftArgs;
+
+/// Status命令参数（无参数）
+#[derive(Debug, Clone)]
+pub struct StatusArgs;
+
+/// Fetch命令参数
+#[derive(Debug, Clone)]
+pub struct FetchArgs {
+    /// 远程名称
+    pub remote: String,
+    /// 只获取最近的若干个检查点，None 时获取全部
+    pub depth: Option<u32>,
+}
+
+/// Push命令参数
+#[derive(Debug, Clone)]
+pub struct PushArgs {
+    /// 远程名称
+    pub remote: String,
+}
+
+/// Stats命令参数
+#[derive(Debug, Clone)]
+pub struct StatsArgs {
+    /// 遍历对象图，区分与其他分支共享和只属于检查点的内容
+    pub dedupe: bool,
+}
+
+/// Multi命令在每个仓库中执行的操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiOperation {
+    List { number: usize },
+    Create { message: Option<String> },
+    Status,
+}
+
+/// Multi命令参数
 #[derive(Debug, Clone)]
-pub struct InitArgs;
+pub struct MultiArgs {
+    /// 仓库路径，为空时从工作区文件读取
+    pub roots: Vec<String>,
+    pub operation: MultiOperation,
+}
 
 /// Create命令参数
 #[derive(Debug, Clone)]
 pub struct CreateArgs {
     pub message: Option<String>,
+    /// 没有变更时也创建带标记的空检查点
+    pub allow_empty: bool,
+    /// 创建前运行 `ccg.verifyCommand` 并记录结果
+    pub verify: bool,
+    /// 验证未通过时不创建检查点（隐含 `verify`）
+    pub require_pass: bool,
+    /// 跳过 pre-create 钩子并记入审计日志
+    pub bypass_guard: bool,
+    /// 最后输出一行便于脚本解析的结果，包括钩子事件是否被过滤
+    pub porcelain: bool,
 }
 
 /// List命令参数
 #[derive(Debug, Clone)]
 pub struct ListArgs {
     pub number: usize,
+    pub stat: bool,
+    /// 显示的列及顺序，来自 `--columns` 或 `ccg.list.columns`；`stat` 会追加统计列
+    pub columns: Vec<ListColumn>,
+    /// 在时间线中显示恢复记录
+    pub with_restores: bool,
+    /// 只显示带有这些标签的检查点，为空时显示全部
+    pub labels: Vec<CheckpointLabel>,
+    /// 只显示验证通过的检查点
+    pub only_passing: bool,
+    /// 只显示在此任务中创建的检查点
+    pub task: Option<String>,
+    /// 不显示只修改空白的检查点
+    pub skip_format_only: bool,
+    /// 显示此用户同步过来的检查点（团队模式），而不是自己的
+    pub user: Option<String>,
 }
 
 /// Restore命令参数
 #[derive(Debug, Clone)]
 pub struct RestoreArgs {
     pub hash: String,
+    /// 只显示将被修改的文件，不执行恢复
+    pub preview: bool,
+    /// 检查点早于所基于分支上的新提交时仍然恢复
+    pub force: bool,
+    /// 同时删除未跟踪的文件
+    pub clean: bool,
+    /// 跳过无法写入的文件，恢复其余文件
+    pub continue_on_error: bool,
+    /// 逐个变更块确认，只恢复选中的部分
+    pub patch: bool,
+    /// 把检查点的文件写入此目录，不修改工作目录
+    pub to: Option<String>,
+    /// 跳过 pre-restore 钩子并记入审计日志
+    pub bypass_guard: bool,
 }
 
 /// Show命令参数
@@ -70,18 +228,238 @@ pub struct RestoreArgs {
 pub struct ShowArgs {
     pub hash: String,
     pub diff: bool,
+    /// 根检查点也显示完整差异，而不是每个文件的统计
+    pub full: bool,
+    pub files: Vec<String>,
+    /// 输出该文件在检查点中的完整内容，而不是检查点详情
+    pub file: Option<String>,
+    /// 将 `file` 的内容写入此路径
+    pub output: Option<String>,
+    pub highlight: bool,
+    /// 命令行指定的差异选项，优先于 `ccg.diff.*` 配置
+    pub diff_settings: DiffSettings,
 }
 
 /// Diff命令参数
 #[derive(Debug, Clone)]
 pub struct DiffArgs {
-    pub hash_a: String,
+    /// 未指定时使用最新检查点
+    pub hash_a: Option<String>,
     pub hash_b: Option<String>,
+    /// 显示此任务所有检查点合并后的差异，与哈希值互斥
+    pub task: Option<String>,
+    /// 与磁盘上的这个目录比较，而不是工作目录，与 `hash_b` 互斥
+    pub dir: Option<String>,
+    /// 显示此时间之后所有检查点合并后的差异，与哈希值互斥
+    pub since: Option<String>,
+    /// 命令行指定的差异选项，优先于 `ccg.diff.*` 配置
+    pub diff_settings: DiffSettings,
+}
+
+/// Run命令参数
+#[derive(Debug, Clone)]
+pub struct RunArgs {
+    pub hash: String,
+    /// 要运行的命令及其参数
+    pub command: Vec<String>,
+    /// 运行后保留临时目录
+    pub keep: bool,
+}
+
+/// Impact命令参数
+#[derive(Debug, Clone)]
+pub struct ImpactArgs {
+    pub hash: String,
+}
+
+/// Revert命令参数
+#[derive(Debug, Clone)]
+pub struct RevertArgs {
+    pub hash: String,
+}
+
+/// Reword命令参数
+#[derive(Debug, Clone)]
+pub struct RewordArgs {
+    pub hash: String,
+    /// 新的提交信息，为空时打开编辑器
+    pub message: Option<String>,
+}
+
+/// Split命令参数
+#[derive(Debug, Clone)]
+pub struct SplitArgs {
+    pub hash: String,
+}
+
+/// Rebase命令参数
+#[derive(Debug, Clone)]
+pub struct RebaseArgs {
+    /// 新基准的分支或提交，未指定时使用当前分支
+    pub onto: Option<String>,
+    /// 把检查点的变更重放到新基准上，而不是保留原来的文件树
+    pub replay: bool,
+}
+
+/// Plugins命令的子操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginsAction {
+    List,
+}
+
+/// Plugins命令参数
+#[derive(Debug, Clone)]
+pub struct PluginsArgs {
+    pub action: PluginsAction,
+}
+
+/// Alias命令的子操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AliasAction {
+    List,
+    Set { name: String, expansion: String },
+    Unset { name: String },
+}
+
+/// Search命令参数
+#[derive(Debug, Clone)]
+pub struct SearchArgs {
+    /// 解析后的查询
+    pub query: CheckpointQuery,
+    /// 用户输入的查询，用于输出
+    pub text: String,
+}
+
+/// ExportMetadata命令参数
+#[derive(Debug, Clone)]
+pub struct ExportMetadataArgs {
+    pub format: MetadataFormat,
+    /// 写入的文件，None 时输出到标准输出
+    pub output: Option<String>,
+}
+
+/// Listen命令参数
+#[derive(Debug, Clone)]
+pub struct ListenArgs {
+    /// 监听的 Unix 套接字路径
+    pub socket: String,
+    /// 合并保存事件的时间窗口（毫秒），None 时使用配置
+    pub debounce: Option<u64>,
+    /// 没有保存事件多少分钟后为累积的变更创建检查点，None 时使用配置，0 表示关闭
+    pub idle: Option<u64>,
+}
+
+/// Report命令参数
+#[derive(Debug, Clone)]
+pub struct ReportArgs {
+    /// 会话 ID，None 时使用最新的会话
+    pub session: Option<String>,
+    pub format: ReportFormat,
+    /// 写入的文件，None 时输出到标准输出
+    pub output: Option<String>,
+}
+
+/// Snapshot命令的子操作
+#[derive(Debug, Clone)]
+pub enum SnapshotAction {
+    Push { message: Option<String> },
+    Pop { number: Option<usize> },
+    List,
+}
+
+/// Snapshot命令参数
+#[derive(Debug, Clone)]
+pub struct SnapshotArgs {
+    pub action: SnapshotAction,
+}
+
+/// Schedule命令的子操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleAction {
+    Install {
+        every: ScheduleInterval,
+        /// None 时按平台选择
+        scheduler: Option<Scheduler>,
+        /// 只打印条目，不注册
+        print: bool,
+    },
+    Status,
+    Remove,
+}
+
+/// Schedule命令参数
+#[derive(Debug, Clone)]
+pub struct ScheduleArgs {
+    pub action: ScheduleAction,
+}
+
+/// Task命令的子操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskAction {
+    Start { name: String },
+    End,
+}
+
+/// Task命令参数
+#[derive(Debug, Clone)]
+pub struct TaskArgs {
+    pub action: TaskAction,
+}
+
+/// Promote命令参数
+#[derive(Debug, Clone)]
+pub struct PromoteArgs {
+    /// 要压缩到当前分支的任务 ID
+    pub task: String,
+    /// 提交信息，未指定时使用任务名称
+    pub message: Option<String>,
+    /// 不包含只修改空白的检查点
+    pub skip_format_only: bool,
+}
+
+/// Alias命令参数
+#[derive(Debug, Clone)]
+pub struct AliasArgs {
+    pub action: AliasAction,
+    /// 写入全局配置，而不是当前仓库的配置
+    pub global: bool,
+}
+
+/// Hook命令的子操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookAction {
+    Install,
+}
+
+/// Hook命令参数
+#[derive(Debug, Clone)]
+pub struct HookArgs {
+    pub action: HookAction,
+    pub targets: Vec<InstallTarget>,
+    /// 只打印合并后的配置，不写入文件
+    pub print: bool,
+}
+
+/// Repack命令参数，未给出的值使用 `ccg.repack.*` 配置
+#[derive(Debug, Clone, Default)]
+pub struct RepackArgs {
+    /// 查找增量基准时比较的对象数量
+    pub window: Option<u64>,
+    /// 增量链的最大长度
+    pub depth: Option<u64>,
+    /// 只处理不小于此字节数的文件版本
+    pub min_size: Option<u64>,
 }
 
 /// Prune命令参数
 #[derive(Debug, Clone)]
 pub struct PruneArgs {
+    /// 保留最新的几个检查点
     pub keep: Option<usize>,
+    /// 清理早于此时间的检查点
     pub before: Option<String>,
+    /// 按策略选出要清理的检查点
+    pub strategy: Option<PruneStrategy>,
+    /// 在列表中逐个选择要清理的检查点，`keep` 和 `before` 选出的预先选中
+    pub interactive: bool,
 }
@@ -0,0 +1,515 @@
+//! ccg 配置
+//!
+//! 所有配置都保存在 git config 的 `ccg.*` 命名空间下，因此可以用
+//! `git config ccg.<key> <value>` 按仓库或全局设置。
+
+use crate::git_ops::{ListColumn, Permissions, RepackSettings, UsageLimits, parse_list_columns};
+use crate::services::schedule::{ScheduleInterval, Scheduler};
+use crate::services::summary::SummaryProvider;
+use crate::ui::IconMode;
+use crate::ui::prompt::PromptLevel;
+use git2::{Config, Repository};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// 检查点作者名称
+pub const KEY_AUTHOR_NAME: &str = "ccg.authorName";
+/// 检查点作者邮箱
+pub const KEY_AUTHOR_EMAIL: &str = "ccg.authorEmail";
+/// 检查点提交者名称
+pub const KEY_COMMITTER_NAME: &str = "ccg.committerName";
+/// 检查点提交者邮箱
+pub const KEY_COMMITTER_EMAIL: &str = "ccg.committerEmail";
+/// 使用固定的机器提交者身份，标记检查点为自动生成
+pub const KEY_MACHINE_COMMITTER: &str = "ccg.machineCommitter";
+/// 检查点提交信息使用的语言，与界面语言无关
+pub const KEY_MESSAGE_LANG: &str = "ccg.messageLang";
+/// 输出图标模式：unicode、ascii 或 none
+pub const KEY_ICONS: &str = "ccg.ui.icons";
+/// 面向屏幕阅读器的输出：文字代替图标和颜色，不输出分隔线
+pub const KEY_ACCESSIBLE: &str = "ccg.ui.accessible";
+/// 检查点数量超过此值时提示清理（0 表示不检查）
+pub const KEY_MAX_CHECKPOINTS: &str = "ccg.maxCheckpoints";
+/// 检查点占用的对象大小超过此值时提示清理，支持 k/m/g 后缀（0 表示不检查）
+pub const KEY_MAX_SIZE: &str = "ccg.maxSize";
+/// `ccg repack` 查找增量基准时比较的对象数量
+pub const KEY_REPACK_WINDOW: &str = "ccg.repack.window";
+/// `ccg repack` 生成的增量链的最大长度
+pub const KEY_REPACK_DEPTH: &str = "ccg.repack.depth";
+/// `ccg repack` 只处理不小于此大小的文件版本，支持 k/m/g 后缀
+pub const KEY_REPACK_MIN_SIZE: &str = "ccg.repack.minSize";
+/// 是否维护 commit-graph 文件以加快长历史的遍历
+pub const KEY_COMMIT_GRAPH: &str = "ccg.commitGraph";
+/// 在不是 git 仓库的目录中 `ccg create` 是否初始化仓库：prompt、always 或 never
+pub const KEY_AUTO_INIT: &str = "ccg.autoInit";
+/// 只读模式：拒绝创建、恢复、改写和清理检查点等修改操作
+pub const KEY_READ_ONLY: &str = "ccg.readOnly";
+/// show/diff 中折叠为一行的生成文件（glob，可多次设置或用逗号分隔）
+pub const KEY_DIFF_IGNORE: &str = "ccg.diff.ignore";
+/// 用 `git status` 检查工作区，以便使用 core.fsmonitor 配置的 fsmonitor 或 watchman
+pub const KEY_FSMONITOR: &str = "ccg.fsmonitor";
+/// 渲染后差异缓存的大小上限，支持 k/m/g 后缀（0 表示不缓存）
+pub const KEY_CACHE_SIZE: &str = "ccg.cacheSize";
+/// `ccg list` 显示的列及顺序，逗号分隔，如 `hash,time,message`
+pub const KEY_LIST_COLUMNS: &str = "ccg.list.columns";
+/// 哪些确认需要询问：always、destructive-only（只询问恢复等会丢弃数据的确认）或 never
+pub const KEY_PROMPT: &str = "ccg.prompt";
+/// `ccg create --checkpoint-if-tests-pass` 在创建前运行的验证命令，如 `cargo check`
+pub const KEY_VERIFY_COMMAND: &str = "ccg.verifyCommand";
+/// 用模型生成检查点摘要的服务：openai、anthropic 或 ollama（未设置时只使用本地描述）
+pub const KEY_SUMMARY_PROVIDER: &str = "ccg.summary.provider";
+/// 生成摘要的模型名称，未设置时使用服务的默认模型
+pub const KEY_SUMMARY_MODEL: &str = "ccg.summary.model";
+/// 摘要服务的请求地址，未设置时使用服务的默认地址
+pub const KEY_SUMMARY_URL: &str = "ccg.summary.url";
+/// 等待摘要的最长秒数，超时后使用本地描述
+pub const KEY_SUMMARY_TIMEOUT: &str = "ccg.summary.timeout";
+/// 发送给摘要服务的差异上限，支持 k/m/g 后缀
+pub const KEY_SUMMARY_MAX_BYTES: &str = "ccg.summary.maxBytes";
+/// 钩子事件修改多个文件时的检查点粒度：per-event（一个检查点）或 per-file（每个文件一个）
+pub const KEY_GRANULARITY: &str = "ccg.checkpoint.granularity";
+/// 不保存到检查点中的文件（pathspec，可多次设置或用逗号分隔），`ccg init --profile` 会填入
+pub const KEY_CHECKPOINT_EXCLUDE: &str = "ccg.checkpoint.exclude";
+/// 超过此大小的新文件或修改的文件不保存到检查点中，支持 k/m/g 后缀（0 或未设置时不限）
+pub const KEY_CHECKPOINT_MAX_FILE_SIZE: &str = "ccg.checkpoint.maxFileSize";
+/// 钩子中哪些工具创建检查点，如 `Edit,Write,MultiEdit`（可多次设置或用逗号分隔，未设置时不限）
+pub const KEY_HOOK_TOOLS: &str = "ccg.hooks.tools";
+/// 钩子只为修改了匹配这些 pathspec 的文件的事件创建检查点（可多次设置或用逗号分隔，未设置时不限）
+pub const KEY_HOOK_PATHS: &str = "ccg.hooks.paths";
+/// 为 true 时 ccg 分支创建为孤立分支，不与当前分支共享历史（`ccg init --orphan` 会设置）
+pub const KEY_ORPHAN: &str = "ccg.orphan";
+/// 检查点仓库的路径（相对于工作目录），设置后检查点保存在该仓库而不是项目仓库中
+pub const KEY_STORE: &str = "ccg.store";
+/// 显示差异时使用的算法：myers（默认）、minimal、patience 或 histogram
+pub const KEY_DIFF_ALGORITHM: &str = "ccg.diff.algorithm";
+/// 两个差异块之间不超过这么多未修改行时合并显示
+pub const KEY_DIFF_INTER_HUNK_CONTEXT: &str = "ccg.diff.interHunkContext";
+/// 相似度达到此百分比时把删除和新增显示为重命名，0 或未设置时不检测
+pub const KEY_DIFF_RENAMES: &str = "ccg.diff.renames";
+/// 为 true 时把移动位置的代码块用单独的颜色显示，而不是显示为删除和新增
+pub const KEY_DIFF_COLOR_MOVED: &str = "ccg.diff.colorMoved";
+/// 等待其他 git 进程释放 `.git/index.lock` 的最长时间（毫秒），超时后创建检查点时不写回索引
+pub const KEY_LOCK_TIMEOUT: &str = "ccg.lockTimeout";
+/// `ccg listen` 合并保存事件的时间窗口（毫秒），窗口内没有新的保存时才创建检查点
+pub const KEY_LISTEN_DEBOUNCE: &str = "ccg.listen.debounce";
+/// `ccg listen` 只为匹配这些 glob 的文件创建检查点（可多次设置或用逗号分隔，未设置时不限）
+pub const KEY_LISTEN_INCLUDE: &str = "ccg.listen.include";
+/// `ccg listen` 连续这么多分钟没有保存事件时，为工作目录中累积的变更创建检查点（0 表示关闭）
+pub const KEY_LISTEN_IDLE: &str = "ccg.listen.idle";
+/// 检查点提交信息的字节上限，支持 k/m/g 后缀，超出部分保存为 git note
+pub const KEY_MESSAGE_MAX_BYTES: &str = "ccg.message.maxBytes";
+/// 团队模式下自己的命名空间 `refs/ccg/users/<name>/ccg` 使用的名称，未设置时取自 `user.name`
+pub const KEY_TEAM_USER: &str = "ccg.team.user";
+/// 同事的命名空间超过多少天没有新检查点时在 `ccg fetch` 后删除（0 或未设置时保留）
+pub const KEY_TEAM_EXPIRE_DAYS: &str = "ccg.team.expireDays";
+/// 谁可以丢弃或改写检查点：`open`（默认）、`owner`（只有创建者）或 `append-only`（只能新增）
+pub const KEY_TEAM_PERMISSIONS: &str = "ccg.team.permissions";
+/// `ccg schedule install` 注册定时快照使用的调度器：cron、launchd、systemd 或 schtasks
+pub const KEY_SCHEDULE_SCHEDULER: &str = "ccg.schedule.scheduler";
+/// `ccg schedule install` 注册的定时快照间隔，如 `30m`
+pub const KEY_SCHEDULE_EVERY: &str = "ccg.schedule.every";
+/// 短哈希的最小显示长度（默认 7，`auto` 同默认），不足以区分所有检查点时自动加长
+pub const KEY_ABBREV: &str = "ccg.abbrev";
+/// 单个用户的保留策略，`ccg.user.<name>.expireDays` 覆盖 `ccg.team.expireDays`
+pub const USER_PREFIX: &str = "ccg.user.";
+/// 命令别名的前缀，`ccg.alias.cr` 保存别名 `cr` 展开后的参数
+pub const ALIAS_PREFIX: &str = "ccg.alias.";
+
+/// 从 git config 读取的 ccg 配置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CcgConfig {
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    pub committer_name: Option<String>,
+    pub committer_email: Option<String>,
+    pub machine_committer: bool,
+    pub message_lang: Option<String>,
+    pub icons: Option<IconMode>,
+    pub accessible: bool,
+    pub max_checkpoints: Option<u64>,
+    pub max_size: Option<u64>,
+    pub repack_window: Option<u64>,
+    pub repack_depth: Option<u64>,
+    pub repack_min_size: Option<u64>,
+    pub commit_graph: bool,
+    pub auto_init: Option<AutoInit>,
+    pub read_only: bool,
+    pub diff_ignore: Vec<String>,
+    pub fsmonitor: bool,
+    pub cache_size: Option<u64>,
+    pub list_columns: Option<Vec<ListColumn>>,
+    pub prompt: Option<PromptLevel>,
+    pub verify_command: Option<String>,
+    pub summary_provider: Option<SummaryProvider>,
+    pub summary_model: Option<String>,
+    pub summary_url: Option<String>,
+    pub summary_timeout: Option<u64>,
+    pub summary_max_bytes: Option<u64>,
+    pub granularity: Option<Granularity>,
+    pub checkpoint_exclude: Vec<String>,
+    pub checkpoint_max_file_size: Option<u64>,
+    pub hook_tools: Vec<String>,
+    pub hook_paths: Vec<String>,
+    pub orphan: bool,
+    pub store: Option<String>,
+    pub diff_algorithm: Option<DiffAlgorithm>,
+    pub diff_inter_hunk_context: Option<u64>,
+    pub diff_renames: Option<u64>,
+    pub diff_color_moved: Option<bool>,
+    pub lock_timeout: Option<u64>,
+    pub listen_debounce: Option<u64>,
+    pub listen_include: Vec<String>,
+    pub listen_idle: Option<u64>,
+    pub message_max_bytes: Option<u64>,
+    pub team_user: Option<String>,
+    pub team_expire_days: Option<u64>,
+    pub team_permissions: Option<Permissions>,
+    pub schedule_scheduler: Option<Scheduler>,
+    pub schedule_every: Option<ScheduleInterval>,
+    pub abbrev: Option<u64>,
+    /// 按用户名设置的 `ccg.user.<name>.expireDays`
+    pub user_expire_days: BTreeMap<String, u64>,
+}
+
+/// 在不是 git 仓库的目录中创建检查点时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AutoInit {
+    /// 在终端中询问，非交互环境（如钩子）中不初始化（默认）
+    #[default]
+    Prompt,
+    /// 直接初始化
+    Always,
+    /// 从不初始化，需要先运行 `ccg init`
+    Never,
+}
+
+impl FromStr for AutoInit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "prompt" => Ok(AutoInit::Prompt),
+            "always" | "true" | "yes" | "on" => Ok(AutoInit::Always),
+            "never" | "false" | "no" | "off" => Ok(AutoInit::Never),
+            other => Err(format!("unknown auto-init mode: {other}")),
+        }
+    }
+}
+
+/// 一次钩子事件修改多个文件时创建的检查点数量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Granularity {
+    /// 每个事件一个检查点（默认）
+    #[default]
+    PerEvent,
+    /// 每个文件一个检查点，便于按文件恢复或挑选
+    PerFile,
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "per-event" | "event" => Ok(Granularity::PerEvent),
+            "per-file" | "file" => Ok(Granularity::PerFile),
+            other => Err(format!("unknown checkpoint granularity: {other}")),
+        }
+    }
+}
+
+/// `ccg init --profile` 内置的排除规则，按项目类型列出不需要保存到检查点的文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExcludeProfile {
+    /// Node.js：依赖目录和构建输出
+    Node,
+    /// Rust：构建目录
+    Rust,
+    /// Python：虚拟环境和缓存
+    Python,
+    /// 机器学习：模型权重和实验记录
+    Ml,
+}
+
+impl ExcludeProfile {
+    /// 所有内置排除规则
+    pub const ALL: [ExcludeProfile; 4] = [
+        ExcludeProfile::Node,
+        ExcludeProfile::Rust,
+        ExcludeProfile::Python,
+        ExcludeProfile::Ml,
+    ];
+
+    /// 命令行中使用的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            ExcludeProfile::Node => "node",
+            ExcludeProfile::Rust => "rust",
+            ExcludeProfile::Python => "python",
+            ExcludeProfile::Ml => "ml",
+        }
+    }
+
+    /// 写入 `ccg.checkpoint.exclude` 的 pathspec
+    pub fn patterns(self) -> &'static [&'static str] {
+        match self {
+            ExcludeProfile::Node => &[
+                "node_modules",
+                "*/node_modules/*",
+                ".next",
+                ".nuxt",
+                ".turbo",
+                "coverage",
+                "*.tsbuildinfo",
+            ],
+            ExcludeProfile::Rust => &["target", "*/target/*"],
+            ExcludeProfile::Python => &[
+                ".venv",
+                "venv",
+                "*/__pycache__/*",
+                "__pycache__",
+                "*.pyc",
+                ".pytest_cache",
+                ".mypy_cache",
+                ".tox",
+            ],
+            ExcludeProfile::Ml => &[
+                "*.ckpt",
+                "*.pt",
+                "*.pth",
+                "*.safetensors",
+                "*.onnx",
+                "*.h5",
+                "wandb",
+                "mlruns",
+            ],
+        }
+    }
+
+    /// 写入 `ccg.checkpoint.maxFileSize` 的大小上限：锁文件和打包依赖通常在
+    /// 几 MB 以内，模型相关的项目允许更大的数据文件
+    pub fn max_file_size(self) -> u64 {
+        match self {
+            ExcludeProfile::Ml => 100 * 1024 * 1024,
+            _ => 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl FromStr for ExcludeProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        ExcludeProfile::ALL
+            .into_iter()
+            .find(|profile| profile.name() == s)
+            .ok_or_else(|| format!("unknown profile: {s}"))
+    }
+}
+
+/// 显示差异时匹配行的算法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffAlgorithm {
+    /// git 的默认算法
+    #[default]
+    Myers,
+    /// 额外花时间找出最小的差异
+    Minimal,
+    /// 以只出现一次的行为锚点，适合大段改写
+    Patience,
+    /// libgit2 没有实现 histogram，使用效果相近的 patience
+    Histogram,
+}
+
+impl FromStr for DiffAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "myers" | "default" => Ok(DiffAlgorithm::Myers),
+            "minimal" => Ok(DiffAlgorithm::Minimal),
+            "patience" => Ok(DiffAlgorithm::Patience),
+            "histogram" => Ok(DiffAlgorithm::Histogram),
+            other => Err(format!("unknown diff algorithm: {other}")),
+        }
+    }
+}
+
+impl CcgConfig {
+    /// 读取仓库的配置（包含全局和系统级配置）
+    pub fn load(repo: &Repository) -> Self {
+        repo.config()
+            .map(|config| Self::from_config(&config))
+            .unwrap_or_default()
+    }
+
+    /// 从已打开的 git config 中读取
+    pub fn from_config(config: &Config) -> Self {
+        CcgConfig {
+            author_name: get_string(config, KEY_AUTHOR_NAME),
+            author_email: get_string(config, KEY_AUTHOR_EMAIL),
+            committer_name: get_string(config, KEY_COMMITTER_NAME),
+            committer_email: get_string(config, KEY_COMMITTER_EMAIL),
+            machine_committer: get_bool(config, KEY_MACHINE_COMMITTER).unwrap_or(false),
+            message_lang: get_string(config, KEY_MESSAGE_LANG),
+            icons: get_string(config, KEY_ICONS).and_then(|v| v.parse().ok()),
+            accessible: get_bool(config, KEY_ACCESSIBLE).unwrap_or(false),
+            max_checkpoints: get_u64(config, KEY_MAX_CHECKPOINTS),
+            max_size: get_u64(config, KEY_MAX_SIZE),
+            repack_window: get_u64(config, KEY_REPACK_WINDOW),
+            repack_depth: get_u64(config, KEY_REPACK_DEPTH),
+            repack_min_size: get_u64(config, KEY_REPACK_MIN_SIZE),
+            commit_graph: get_bool(config, KEY_COMMIT_GRAPH).unwrap_or(false),
+            auto_init: get_string(config, KEY_AUTO_INIT).and_then(|v| v.parse().ok()),
+            read_only: get_bool(config, KEY_READ_ONLY).unwrap_or(false),
+            diff_ignore: get_list(config, KEY_DIFF_IGNORE),
+            fsmonitor: get_bool(config, KEY_FSMONITOR).unwrap_or(false),
+            cache_size: get_u64(config, KEY_CACHE_SIZE),
+            list_columns: get_string(config, KEY_LIST_COLUMNS)
+                .and_then(|v| parse_list_columns(&v).ok()),
+            prompt: get_string(config, KEY_PROMPT).and_then(|v| v.parse().ok()),
+            verify_command: get_string(config, KEY_VERIFY_COMMAND),
+            summary_provider: get_string(config, KEY_SUMMARY_PROVIDER).and_then(|v| v.parse().ok()),
+            summary_model: get_string(config, KEY_SUMMARY_MODEL),
+            summary_url: get_string(config, KEY_SUMMARY_URL),
+            summary_timeout: get_u64(config, KEY_SUMMARY_TIMEOUT),
+            summary_max_bytes: get_u64(config, KEY_SUMMARY_MAX_BYTES),
+            granularity: get_string(config, KEY_GRANULARITY).and_then(|v| v.parse().ok()),
+            checkpoint_exclude: get_list(config, KEY_CHECKPOINT_EXCLUDE),
+            checkpoint_max_file_size: get_u64(config, KEY_CHECKPOINT_MAX_FILE_SIZE),
+            hook_tools: get_list(config, KEY_HOOK_TOOLS),
+            hook_paths: get_list(config, KEY_HOOK_PATHS),
+            orphan: get_bool(config, KEY_ORPHAN).unwrap_or(false),
+            store: get_string(config, KEY_STORE),
+            diff_algorithm: get_string(config, KEY_DIFF_ALGORITHM).and_then(|v| v.parse().ok()),
+            diff_inter_hunk_context: get_u64(config, KEY_DIFF_INTER_HUNK_CONTEXT),
+            diff_renames: get_u64(config, KEY_DIFF_RENAMES),
+            diff_color_moved: get_bool(config, KEY_DIFF_COLOR_MOVED),
+            lock_timeout: get_u64(config, KEY_LOCK_TIMEOUT),
+            listen_debounce: get_u64(config, KEY_LISTEN_DEBOUNCE),
+            listen_include: get_list(config, KEY_LISTEN_INCLUDE),
+            listen_idle: get_u64(config, KEY_LISTEN_IDLE),
+            message_max_bytes: get_u64(config, KEY_MESSAGE_MAX_BYTES),
+            team_user: get_string(config, KEY_TEAM_USER),
+            team_expire_days: get_u64(config, KEY_TEAM_EXPIRE_DAYS),
+            team_permissions: get_string(config, KEY_TEAM_PERMISSIONS).and_then(|v| v.parse().ok()),
+            schedule_scheduler: get_string(config, KEY_SCHEDULE_SCHEDULER)
+                .and_then(|v| v.parse().ok()),
+            schedule_every: get_string(config, KEY_SCHEDULE_EVERY).and_then(|v| v.parse().ok()),
+            abbrev: get_u64(config, KEY_ABBREV),
+            user_expire_days: user_expire_days(config),
+        }
+    }
+
+    /// 检查点数量和大小的提示阈值，未配置时使用默认值
+    pub fn usage_limits(&self) -> UsageLimits {
+        let defaults = UsageLimits::default();
+        UsageLimits {
+            max_checkpoints: self.max_checkpoints.unwrap_or(defaults.max_checkpoints),
+            max_bytes: self.max_size.unwrap_or(defaults.max_bytes),
+        }
+    }
+
+    /// 定向重新打包的参数，未配置时使用默认值
+    pub fn repack_settings(&self) -> RepackSettings {
+        let defaults = RepackSettings::default();
+        RepackSettings {
+            window: self.repack_window.unwrap_or(defaults.window),
+            depth: self.repack_depth.unwrap_or(defaults.depth),
+            min_size: self.repack_min_size.unwrap_or(defaults.min_size),
+        }
+    }
+
+    /// `user` 的命名空间的保留天数，未配置或为 0 时不删除
+    pub fn expire_days_for(&self, user: &str) -> Option<u64> {
+        self.user_expire_days
+            .get(user)
+            .or(self.team_expire_days.as_ref())
+            .copied()
+            .filter(|days| *days > 0)
+    }
+
+    /// 生成提交信息使用的语言，未配置或不支持时使用英语
+    pub fn message_locale(&self) -> String {
+        crate::i18n::message_locale(self.message_lang.as_deref())
+    }
+}
+
+/// 读取非空字符串配置
+pub fn get_string(config: &Config, key: &str) -> Option<String> {
+    config
+        .get_string(key)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// 读取非负整数配置，支持 git 的 k/m/g 后缀
+pub fn get_u64(config: &Config, key: &str) -> Option<u64> {
+    config.get_i64(key).ok().and_then(|v| u64::try_from(v).ok())
+}
+
+/// 读取布尔配置
+pub fn get_bool(config: &Config, key: &str) -> Option<bool> {
+    config.get_bool(key).ok()
+}
+
+/// 读取多值配置，每个值还可以用逗号分隔多项，忽略空项
+pub fn get_list(config: &Config, key: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let Ok(mut entries) = config.multivar(key, None) else {
+        return values;
+    };
+    while let Some(Ok(entry)) = entries.next() {
+        if let Some(value) = entry.value() {
+            values.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_string),
+            );
+        }
+    }
+    values
+}
+
+/// 读取按用户设置的保留天数 `ccg.user.<name>.expireDays`
+fn user_expire_days(config: &Config) -> BTreeMap<String, u64> {
+    let mut days = BTreeMap::new();
+    let Ok(mut entries) = config.entries(Some(r"^ccg\.user\..*\.expiredays$")) else {
+        return days;
+    };
+    while let Some(Ok(entry)) = entries.next() {
+        // 子节（用户名）区分大小写，节名和键名由 git 转为小写
+        if let Some(name) = entry.name()
+            && let Some(user) = name
+                .strip_prefix(USER_PREFIX)
+                .and_then(|name| name.strip_suffix(".expiredays"))
+            && let Some(value) = get_u64(config, name)
+        {
+            days.insert(user.to_string(), value);
+        }
+    }
+    days
+}
+
+/// 读取所有命令别名，按名称排序
+///
+/// 同名别名在多个配置级别中出现时，优先级高的（仓库级）生效。
+pub fn aliases(config: &Config) -> BTreeMap<String, String> {
+    let mut aliases = BTreeMap::new();
+    let Ok(mut entries) = config.entries(Some(r"^ccg\.alias\.")) else {
+        return aliases;
+    };
+    while let Some(Ok(entry)) = entries.next() {
+        if let (Some(name), Some(value)) = (entry.name(), entry.value())
+            && let Some(alias) = name.strip_prefix(ALIAS_PREFIX)
+            && !value.trim().is_empty()
+        {
+            aliases.insert(alias.to_string(), value.trim().to_string());
+        }
+    }
+    aliases
+}
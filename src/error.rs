@@ -1,3 +1,5 @@
+use rust_i18n::t;
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -14,6 +16,9 @@ pub enum CheckpointError {
     #[error("Checkpoint not found: {0}")]
     CheckpointNotFound(String),
 
+    #[error("Checkpoint not in the fetched history: {0} (fetch more with `ccg fetch --depth N`)")]
+    NotFetched(String),
+
     #[error("Invalid checkpoint hash: {0}")]
     InvalidHash(String),
 
@@ -26,12 +31,36 @@ pub enum CheckpointError {
     #[error("No changes to commit")]
     NoChangesToCommit,
 
+    #[error("Conflicting changes in: {0}")]
+    Conflict(String),
+
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
 
+    #[error("Restore failed and was rolled back, blocked by: {0}")]
+    RestoreRolledBack(String),
+
     #[error("Operation cancelled by user")]
     UserCancelled,
 
+    #[error("Hook rejected the operation: {0}")]
+    HookRejected(String),
+
+    #[error("Checkpoint predates newer commits: {0}")]
+    StaleBase(String),
+
+    #[error("Verification failed, checkpoint not created: {0}")]
+    VerificationFailed(String),
+
+    #[error("Read-only mode: {0} is not allowed (remove --read-only or unset ccg.readOnly)")]
+    ReadOnly(String),
+
+    #[error("Not permitted by ccg.team.permissions: {0}")]
+    NotPermitted(String),
+
+    #[error("Unsupported object format: {0} (this build of ccg reads SHA-1 repositories only)")]
+    UnsupportedObjectFormat(String),
+
     #[error("Invalid argument: {0}")]
     InvalidArgument(String),
 
@@ -42,4 +71,109 @@ pub enum CheckpointError {
     DialoguerError(#[from] dialoguer::Error),
 }
 
+impl CheckpointError {
+    /// 是否为稍后重试可能成功的临时错误：锁被其他进程持有，或 I/O 被中断、
+    /// 暂时无法进行
+    ///
+    /// 权限不足、磁盘已满等其他 I/O 错误重试也不会成功，不算临时错误。
+    pub fn is_transient(&self) -> bool {
+        match self {
+            CheckpointError::GitOperationFailed(e) => e.code() == git2::ErrorCode::Locked,
+            CheckpointError::IoError(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted
+            ),
+            _ => false,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, CheckpointError>;
+
+/// `ccg` 命令行的错误：检查点操作失败或命令行参数无效
+///
+/// 所有失败都通过 [`crate::ui::report`] 输出，错误信息和建议按界面语言显示。
+#[derive(Debug)]
+pub enum CliError {
+    /// 检查点操作失败
+    Checkpoint(CheckpointError),
+    /// 参数 `arg` 的值 `value` 无法解析
+    InvalidValue {
+        arg: String,
+        value: String,
+        reason: String,
+    },
+}
+
+impl CliError {
+    /// 解析参数 `arg` 的值，失败时返回 `InvalidValue`
+    pub fn parse<T>(arg: &str, value: &str) -> std::result::Result<T, CliError>
+    where
+        T: std::str::FromStr,
+        T::Err: fmt::Display,
+    {
+        value.parse().map_err(|e: T::Err| CliError::InvalidValue {
+            arg: arg.to_string(),
+            value: value.to_string(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// 解决问题的建议，没有合适的建议时为 None
+    pub fn suggestion(&self) -> Option<String> {
+        let key = match self {
+            CliError::InvalidValue { .. } => "suggest_invalid_value",
+            CliError::Checkpoint(error) => match error {
+                CheckpointError::RepositoryNotFound => "suggest_repository_not_found",
+                CheckpointError::BranchNotFound(_) => "suggest_branch_not_found",
+                CheckpointError::CheckpointNotFound(_) | CheckpointError::InvalidHash(_) => {
+                    "suggest_checkpoint_not_found"
+                }
+                CheckpointError::InvalidDateFormat(_) => "suggest_invalid_date",
+                CheckpointError::UncommittedChanges => "suggest_uncommitted_changes",
+                CheckpointError::Conflict(_) => "suggest_conflict",
+                CheckpointError::PermissionDenied(_) => "suggest_permission_denied",
+                CheckpointError::StaleBase(_) => "suggest_stale_base",
+                CheckpointError::NotPermitted(_) => "suggest_not_permitted",
+                CheckpointError::HookRejected(_) => "suggest_hook_rejected",
+                CheckpointError::UnsupportedObjectFormat(_) => "suggest_unsupported_object_format",
+                CheckpointError::GitOperationFailed(_) | CheckpointError::IoError(_)
+                    if error.is_transient() =>
+                {
+                    "suggest_transient"
+                }
+                _ => return None,
+            },
+        };
+        Some(t!(key).to_string())
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Checkpoint(error) => write!(f, "{error}"),
+            CliError::InvalidValue { arg, value, reason } => f.write_str(&t!(
+                "error_invalid_value",
+                arg = arg,
+                value = value,
+                reason = reason
+            )),
+        }
+    }
+}
+
+impl std::error::Error for CliError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CliError::Checkpoint(error) => error.source(),
+            CliError::InvalidValue { .. } => None,
+        }
+    }
+}
+
+impl<E: Into<CheckpointError>> From<E> for CliError {
+    fn from(error: E) -> Self {
+        CliError::Checkpoint(error.into())
+    }
+}
@@ -1,26 +1,102 @@
-//! Git operations module - refactored version
+//! Git operations module
 //!
-//! This module provides git operations functionality through a modular architecture.
-//! The implementation has been split into focused sub-modules for better maintainability.
-
+//! `GitOperations` owns the repository handle and is a thin facade over the
+//! focused sub-operation structs (`BranchOperations`, `CommitOperations`,
+//! `DiffOperations`, `DiffCache`, `JournalOperations`, `RepositoryOperations`,
+//! `RewriteOperations`, `CheckpointIds`, `Summarizer`, `WorktreeStatus`). Each
+//! behavior is implemented exactly once in a sub-module; library users can also
+//! borrow those structs directly via [`GitOperations::branches`],
+//! [`GitOperations::commits`], [`GitOperations::diffs`] and
+//! [`GitOperations::rewrites`].
+
+use crate::config::{CcgConfig, KEY_ORPHAN};
 use crate::error::{CheckpointError, Result as CcResult};
-use chrono::DateTime;
+use crate::ui::Icon;
 use console::{Color, style};
-use git2::{Commit, Delta, Oid, Repository, Signature};
+use git2::{Commit, Repository};
+use rust_i18n::t;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 // Sub-modules for organization
+pub mod abbrev;
+pub mod audit;
+pub mod backend;
 pub mod branch;
+pub mod cache;
 pub mod commit;
+pub mod commit_graph;
 pub mod diff;
+pub mod exclude;
+pub mod fetch;
+pub mod graph;
+pub mod hook_filter;
+pub mod ids;
+pub mod interleave;
+pub mod journal;
+pub mod labels;
+pub mod memory;
+pub mod object_format;
+pub mod paths;
+pub mod pending;
+pub mod repack;
 pub mod repository;
+pub mod rewrite;
+pub mod snapshots;
+pub mod staging;
+pub mod stats;
+pub mod status;
+pub mod summarize;
+pub mod tasks;
 pub mod types;
+pub mod usage;
+pub mod users;
 
 // Re-export main types
+pub use abbrev::{abbrev_len, unique_abbrev_len};
+pub use audit::AuditLog;
+pub use backend::GitBackend;
+pub use branch::BranchOperations;
+pub use cache::DiffCache;
+pub use commit::CommitOperations;
+pub use commit_graph::{COMMIT_GRAPH_REFRESH, CommitGraph};
+pub use diff::{DiffFormatter, DiffOperations, DiffSettings, set_diff_settings};
+pub use exclude::ExcludeRules;
+pub use fetch::FetchOperations;
+pub use graph::GraphBuilder;
+pub use hook_filter::HookFilter;
+pub use ids::CheckpointIds;
+pub use interleave::InterleaveCheck;
+pub use journal::JournalOperations;
+pub use labels::CheckpointLabel;
+pub use memory::MemoryBackend;
+pub use object_format::ObjectFormat;
+pub use paths::{display_bytes, display_path, literal_pathspec, path_from_bytes};
+pub use pending::{PendingEvent, PendingQueue};
+pub use repack::Repacker;
+pub use repository::RepositoryOperations;
+pub use rewrite::RewriteOperations;
+pub use snapshots::SnapshotStore;
+pub use staging::StagingGuard;
+pub use stats::{ParallelStats, StatsIndex};
+pub use status::{StatusCache, StatusQuery, WorktreeStatus};
+pub use summarize::Summarizer;
+pub use tasks::ActiveTask;
 pub use types::*;
+pub use usage::CheckpointUsage;
+pub use users::UserNamespaces;
+
+/// Directory inside the git directory holding ccg's own state, `.git/ccg`
+pub fn ccg_dir(repo: &Repository) -> PathBuf {
+    repo.path().join("ccg")
+}
 
 /// Main GitOperations struct that coordinates all git operations
 pub struct GitOperations {
     repo: Repository,
+    /// Working directory status answers reused for this invocation
+    status: StatusCache,
 }
 
 impl Clone for GitOperations {
@@ -29,35 +105,45 @@ impl Clone for GitOperations {
         let repo_path = self.repo.path();
         let repo = Repository::open(repo_path).expect("Failed to reopen repository");
 
-        GitOperations { repo }
+        GitOperations {
+            repo,
+            status: StatusCache::new(),
+        }
     }
 }
 
 impl GitOperations {
-    /// Create a new GitOperations instance
+    /// Open the repository at `path` (the current directory when None)
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::RepositoryNotFound` if the path is not inside
+    /// a git repository; use [`GitOperations::init_at`] to create one
     pub fn new(path: Option<&str>) -> CcResult<Self> {
-        let repo_path = path.unwrap_or(".");
-        let repo = match Repository::open(repo_path) {
-            Ok(repo) => repo,
-            Err(e) => match e.class() {
-                git2::ErrorClass::Repository => {
-                    // 如果不是Git仓库，尝试初始化
-                    repository::RepositoryOperations::init_repository(repo_path)?
-                }
-                _ => return Err(CheckpointError::GitOperationFailed(e)),
-            },
-        };
+        Self::new_from_path(path.unwrap_or("."))
+    }
 
-        Ok(GitOperations { repo })
+    /// Initialize a new repository at `path` with HEAD on the CCG branch
+    pub fn init_at(path: &str) -> CcResult<Self> {
+        let repo = RepositoryOperations::init_repository(path)?;
+        Ok(GitOperations {
+            repo,
+            status: StatusCache::new(),
+        })
     }
 
     /// Create GitOperations from a path
+    ///
+    /// When the repository sets `ccg.store`, the separate checkpoint store is
+    /// opened instead, see [`RepositoryOperations::open_store`].
     pub fn new_from_path<P: AsRef<std::path::Path>>(path: P) -> CcResult<Self> {
-        let repo = Repository::open(path).map_err(|e| match e.class() {
-            git2::ErrorClass::Repository => CheckpointError::RepositoryNotFound,
-            _ => CheckpointError::GitOperationFailed(e),
-        })?;
-        Ok(GitOperations { repo })
+        let mut repo = RepositoryOperations::open_repository(path)?;
+        if let Some(store) = CcgConfig::load(&repo).store {
+            repo = RepositoryOperations::open_store(&repo, &store)?;
+        }
+        Ok(GitOperations {
+            repo,
+            status: StatusCache::new(),
+        })
     }
 
     /// Get reference to the underlying repository
@@ -65,657 +151,715 @@ impl GitOperations {
         &self.repo
     }
 
+    /// Branch operations on this repository
+    pub fn branches(&self) -> BranchOperations<'_> {
+        BranchOperations::new(&self.repo)
+    }
+
+    /// Commit operations on this repository
+    pub fn commits(&self) -> CommitOperations<'_> {
+        CommitOperations::new(&self.repo).with_status_cache(&self.status)
+    }
+
+    /// Working directory status checks, sharing this instance's cache
+    pub fn status(&self) -> WorktreeStatus<'_> {
+        WorktreeStatus::new(&self.repo, Some(&self.status))
+    }
+
+    /// Diff operations on this repository
+    pub fn diffs(&self) -> DiffOperations<'_> {
+        DiffOperations::new(&self.repo)
+    }
+
+    /// Rendered diff cache of this repository
+    pub fn cache(&self) -> DiffCache<'_> {
+        DiffCache::new(&self.repo)
+    }
+
+    /// Checkpoint ID index of this repository
+    pub fn ids(&self) -> CheckpointIds<'_> {
+        CheckpointIds::new(&self.repo)
+    }
+
+    /// Checkpoint size index on this repository
+    pub fn usage(&self) -> CheckpointUsage<'_> {
+        CheckpointUsage::new(&self.repo)
+    }
+
+    /// Per-checkpoint statistics index of this repository
+    pub fn stats(&self) -> StatsIndex<'_> {
+        StatsIndex::new(&self.repo)
+    }
+
+    /// Audit log of bypassed guard hooks
+    pub fn audit(&self) -> AuditLog<'_> {
+        AuditLog::new(&self.repo)
+    }
+
+    /// Filtering of hook events by `ccg.hooks.tools` and `ccg.hooks.paths`
+    pub fn hook_filter(&self) -> HookFilter<'_> {
+        HookFilter::new(&self.repo)
+    }
+
+    /// Queue of hook events waiting to be retried
+    pub fn pending(&self) -> PendingQueue<'_> {
+        PendingQueue::new(&self.repo)
+    }
+
+    /// Fetches checkpoints from remotes
+    pub fn fetches(&self) -> FetchOperations<'_> {
+        FetchOperations::new(&self.repo)
+    }
+
+    /// Working directory snapshots parked outside the checkpoint timeline
+    pub fn snapshots(&self) -> SnapshotStore<'_> {
+        SnapshotStore::new(&self.repo)
+    }
+
+    /// Guard of the index writes made while creating checkpoints
+    pub fn staging(&self) -> StagingGuard<'_> {
+        StagingGuard::new(&self.repo)
+    }
+
+    /// Restore journal operations on this repository
+    pub fn journal(&self) -> JournalOperations<'_> {
+        JournalOperations::new(&self.repo)
+    }
+
+    /// Repository-level operations on this repository
+    pub fn repository(&self) -> RepositoryOperations<'_> {
+        RepositoryOperations::new(&self.repo)
+    }
+
+    /// History rewriting operations on this repository
+    pub fn rewrites(&self) -> RewriteOperations<'_> {
+        RewriteOperations::new(&self.repo)
+    }
+
     /// Initialize checkpoints (create CCG branch)
     pub fn init_checkpoints(&self) -> CcResult<()> {
         self.create_or_get_checkpoints_branch()?;
         Ok(())
     }
 
-    /// Create or get the CCG branch
-    pub fn create_or_get_checkpoints_branch(&self) -> CcResult<git2::Branch> {
-        // Try to get existing branch
-        if let Ok(branch) = self
-            .repo
-            .find_branch(CCG_BRANCH_NAME, git2::BranchType::Local)
-        {
-            println!("🌿 检测到已存在的 '{CCG_BRANCH_NAME}' 分支");
-            return Ok(branch);
-        }
+    /// Initialize checkpoints with the CCG branch at the tip of `from`
+    pub fn init_checkpoints_from(&self, from: &str) -> CcResult<()> {
+        self.branches().create_ccg_branch_from(from)?;
+        Ok(())
+    }
 
-        // Check if we have HEAD commit
-        let head_commit = match self.repo.head() {
-            Ok(head) => head.peel_to_commit().ok(),
-            Err(_) => None,
-        };
+    /// Initialize checkpoints on an orphan CCG branch and record `ccg.orphan`
+    pub fn init_checkpoints_orphan(&self) -> CcResult<()> {
+        self.branches().create_orphan_ccg_branch()?;
+        self.repo.config()?.set_bool(KEY_ORPHAN, true)?;
+        Ok(())
+    }
 
-        if let Some(commit) = head_commit {
-            // Create branch based on current HEAD
-            let branch = self
-                .repo
-                .branch(CCG_BRANCH_NAME, &commit, false)
-                .map_err(CheckpointError::GitOperationFailed)?;
-            println!("✅ '{CCG_BRANCH_NAME}' 分支创建成功");
-            Ok(branch)
-        } else {
-            // Empty repository, create initial commit first
-            println!("📝 空仓库检测到，创建初始提交...");
-            let _commit_id = self.create_initial_commit()?;
-
-            // Now try to get the branch
-            self.repo
-                .find_branch(CCG_BRANCH_NAME, git2::BranchType::Local)
-                .map_err(CheckpointError::GitOperationFailed)
-        }
+    /// Rename a local branch, e.g. an existing checkpoint branch
+    pub fn rename_branch(&self, old: &str, new: &str) -> CcResult<()> {
+        self.branches().rename_branch(old, new)
+    }
+
+    /// Create or get the CCG branch
+    pub fn create_or_get_checkpoints_branch(&self) -> CcResult<git2::Branch<'_>> {
+        self.branches().create_or_get_ccg_branch()
     }
 
     /// Create a checkpoint (commit)
     pub fn create_checkpoint(&self, message: &str) -> CcResult<String> {
         let original_branch = self.ensure_ccg_branch()?;
-        let result = self.create_commit_internal(message);
+        let result = self.commits().create_commit(message);
         self.restore_original_branch(&original_branch)?;
-        result
+        let hash = result?;
+        self.record_checkpoint(&hash)?;
+        Ok(hash)
     }
 
-    /// Internal commit creation
-    fn create_commit_internal(&self, message: &str) -> CcResult<String> {
-        if !self.has_changes_to_commit()? {
-            return Err(CheckpointError::NoChangesToCommit);
-        }
+    /// Create a checkpoint from the changes to `paths` only
+    pub fn create_checkpoint_paths(&self, message: &str, paths: &[String]) -> CcResult<String> {
+        let original_branch = self.ensure_ccg_branch()?;
+        let result = self.commits().create_commit_paths(message, paths);
+        self.restore_original_branch(&original_branch)?;
+        let hash = result?;
+        self.record_checkpoint(&hash)?;
+        Ok(hash)
+    }
 
-        let signature = self.create_signature()?;
-        let mut index = self
-            .repo
-            .index()
-            .map_err(CheckpointError::GitOperationFailed)?;
+    /// Attach a note to a checkpoint under `refs/notes/ccg`
+    pub fn add_note(&self, hash: &str, note: &str) -> CcResult<()> {
+        self.commits().add_note(hash, note)
+    }
 
-        index
-            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
-            .map_err(CheckpointError::GitOperationFailed)?;
-        index.write().map_err(CheckpointError::GitOperationFailed)?;
+    /// The note attached to a checkpoint under `refs/notes/ccg`, if any
+    pub fn checkpoint_note(&self, hash: &str) -> CcResult<Option<String>> {
+        self.commits().note(hash)
+    }
 
-        let tree_id = index
-            .write_tree()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        let parent_commit = self.get_parent_commit()?;
-        let parents: Vec<&Commit> = parent_commit.as_ref().map(|c| vec![c]).unwrap_or_default();
+    /// Tag names keyed by the full hash of the commit they point at
+    pub fn checkpoint_tags(&self) -> CcResult<HashMap<String, Vec<String>>> {
+        self.commits().tags()
+    }
 
-        let commit_id = self
-            .repo
-            .commit(
-                Some("HEAD"),
-                &signature,
-                &signature,
-                message,
-                &self
-                    .repo
-                    .find_tree(tree_id)
-                    .map_err(CheckpointError::GitOperationFailed)?,
-                &parents,
-            )
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        Ok(commit_id.to_string())
+    /// Create an empty checkpoint marked with the empty-checkpoint trailer
+    pub fn create_empty_checkpoint(&self, message: &str) -> CcResult<String> {
+        let original_branch = self.ensure_ccg_branch()?;
+        let result = self.commits().create_empty_commit(message);
+        self.restore_original_branch(&original_branch)?;
+        let hash = result?;
+        self.record_checkpoint(&hash)?;
+        Ok(hash)
+    }
+
+    /// Assign a new checkpoint its `cp-N` ID and record it in the usage and
+    /// statistics indexes
+    fn record_checkpoint(&self, hash: &str) -> CcResult<()> {
+        self.ids().assign(hash)?;
+        self.usage().record(hash)?;
+        self.stats().record_commit(hash)
     }
 
     /// List checkpoints
-    pub fn list_checkpoints(&self, limit: usize) -> CcResult<Vec<String>> {
-        let mut revwalk = self
-            .repo
-            .revwalk()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        revwalk
-            .set_sorting(git2::Sort::TIME)
-            .map_err(CheckpointError::GitOperationFailed)?;
-        revwalk
-            .push_head()
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        let mut commits = Vec::new();
-        for (i, oid) in revwalk.enumerate() {
-            if i >= limit {
-                break;
-            }
+    ///
+    /// Each line shows `columns` in order; the `Stats` column holds the
+    /// checkpoint's line and file counts. Only checkpoints `filter` keeps
+    /// are listed.
+    pub fn list_checkpoints(
+        &self,
+        limit: usize,
+        columns: &[ListColumn],
+        filter: &ListFilter,
+    ) -> CcResult<Vec<String>> {
+        self.commits().list_commits(limit, columns, filter)
+    }
 
-            let oid = oid.map_err(CheckpointError::GitOperationFailed)?;
-            let commit = self
-                .repo
-                .find_commit(oid)
-                .map_err(CheckpointError::GitOperationFailed)?;
-
-            let short_hash = &oid.to_string()[..7];
-            let message = commit
-                .message()
-                .unwrap_or("No commit message")
-                .lines()
-                .next()
-                .unwrap_or("No commit message");
-            let time = commit.time();
-            let datetime = DateTime::from_timestamp(time.seconds(), 0)
-                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-                .unwrap_or_else(|| "Unknown time".to_string());
-
-            let final_message = message
-                .strip_prefix("Checkpoint created with raw input: ")
-                .unwrap_or(message);
-
-            let formatted = format!(
-                "{} {} {}",
-                style(short_hash).fg(Color::Yellow).bold(),
-                style(datetime).fg(Color::Cyan),
-                style(final_message).fg(Color::White)
-            );
-            commits.push(formatted);
-        }
+    /// List checkpoints as structured entries, newest first
+    pub fn checkpoint_entries(&self, limit: usize) -> CcResult<Vec<CheckpointEntry>> {
+        self.commits().list_entries(limit)
+    }
 
-        Ok(commits)
+    /// Checkpoint thresholds from `ccg.maxCheckpoints` and `ccg.maxSize`
+    pub fn usage_limits(&self) -> UsageLimits {
+        CcgConfig::load(&self.repo).usage_limits()
     }
 
-    /// Find a commit by hash
-    pub fn find_commit(&self, hash: &str) -> CcResult<Commit> {
-        if let Ok(oid) = Oid::from_str(hash) {
-            if let Ok(commit) = self.repo.find_commit(oid) {
-                return Ok(commit);
-            }
-        }
+    /// Targeted repack settings from `ccg.repack.*`
+    pub fn repack_settings(&self) -> RepackSettings {
+        CcgConfig::load(&self.repo).repack_settings()
+    }
 
-        // Try short hash
-        if hash.len() >= 2 && hash.len() < 40 {
-            let mut revwalk = self
-                .repo
-                .revwalk()
-                .map_err(CheckpointError::GitOperationFailed)?;
-            revwalk
-                .set_sorting(git2::Sort::TIME)
-                .map_err(CheckpointError::GitOperationFailed)?;
-            revwalk
-                .push_head()
-                .map_err(CheckpointError::GitOperationFailed)?;
-
-            let mut matches = Vec::new();
-            for oid_result in revwalk {
-                let oid = oid_result.map_err(CheckpointError::GitOperationFailed)?;
-                if oid.to_string().starts_with(hash) {
-                    matches.push(oid);
-                }
-            }
+    /// Size cap of checkpoint messages, `ccg.message.maxBytes` or the default
+    pub fn message_max_bytes(&self) -> usize {
+        CcgConfig::load(&self.repo)
+            .message_max_bytes
+            .map_or(DEFAULT_MESSAGE_MAX_BYTES, |bytes| bytes as usize)
+    }
 
-            match matches.len() {
-                0 => Err(CheckpointError::CheckpointNotFound(hash.to_string())),
-                1 => self
-                    .repo
-                    .find_commit(matches[0])
-                    .map_err(CheckpointError::GitOperationFailed),
-                _ => Err(CheckpointError::InvalidHash(format!(
-                    "短hash '{hash}' 匹配到多个提交"
-                ))),
-            }
-        } else {
-            Err(CheckpointError::InvalidHash(format!(
-                "无效的hash格式: {hash}"
-            )))
-        }
+    /// Whether `ccg.readOnly` forbids modifying checkpoints
+    pub fn read_only(&self) -> bool {
+        CcgConfig::load(&self.repo).read_only
     }
 
-    /// Get commit details
-    pub fn get_commit_details(&self, hash: &str) -> CcResult<String> {
-        let commit = self.find_commit(hash)?;
-        let full_hash = commit.id().to_string();
-        let author = commit.author();
-        let message = commit.message().unwrap_or("");
-        let time = commit.time();
-
-        let datetime = DateTime::from_timestamp(time.seconds(), 0)
-            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-            .unwrap_or_else(|| "Unknown time".to_string());
-
-        let result = format!(
-            "{} {}\n{} {} <{}>\n{} {}\n\n{}\n{}\n",
-            style("Commit:").fg(Color::White).bold(),
-            style(&full_hash).fg(Color::Yellow).bold(),
-            style("Author:").fg(Color::White).bold(),
-            style(author.name().unwrap_or("Unknown")).fg(Color::Cyan),
-            style(author.email().unwrap_or("unknown")).fg(Color::Cyan),
-            style("Date:").fg(Color::White).bold(),
-            style(&datetime).fg(Color::Green),
-            style("Message:").fg(Color::White).bold(),
-            style(message).fg(Color::White)
-        );
-
-        Ok(result)
-    }
-
-    /// Restore to a checkpoint
-    pub fn restore_checkpoint(&self, hash: &str) -> CcResult<()> {
-        let commit = self.find_commit(hash)?;
-        let tree = commit.tree().map_err(CheckpointError::GitOperationFailed)?;
+    /// Description of the changes the next checkpoint would record, in `locale`
+    pub fn describe_changes(&self, locale: &str) -> CcResult<Option<String>> {
+        Summarizer::new(&self.repo).describe(locale)
+    }
 
-        if self.has_uncommitted_changes()? {
-            return Err(CheckpointError::UncommittedChanges);
-        }
+    /// The pending changes as a unified patch of at most `max_bytes`
+    pub fn pending_patch(&self, max_bytes: usize) -> CcResult<String> {
+        Summarizer::new(&self.repo).pending_patch(max_bytes)
+    }
 
-        // 设置 checkout 选项以强制更新工作目录
-        let mut checkout_opts = git2::build::CheckoutBuilder::new();
-        checkout_opts.force(); // 强制覆盖工作目录文件
-        checkout_opts.remove_untracked(true); // 移除未跟踪的文件
+    /// The `ccg.*` configuration of this repository
+    pub fn config(&self) -> CcgConfig {
+        CcgConfig::load(&self.repo)
+    }
 
-        // 检出树到工作目录
-        self.repo
-            .checkout_tree(tree.as_object(), Some(&mut checkout_opts))
-            .map_err(CheckpointError::GitOperationFailed)?;
+    /// Verification command from `ccg.verifyCommand`
+    pub fn verify_command(&self) -> Option<String> {
+        CcgConfig::load(&self.repo).verify_command
+    }
 
-        // 设置 HEAD 为分离状态指向目标提交
-        self.repo
-            .set_head_detached(commit.id())
-            .map_err(CheckpointError::GitOperationFailed)?;
+    /// Advisory to show when checkpoint usage first exceeds `limits`
+    pub fn check_usage(&self, limits: &UsageLimits) -> CcResult<Option<UsageAdvisory>> {
+        self.usage().check(limits)
+    }
 
-        Ok(())
+    /// Checkpoint count and recorded size
+    pub fn storage_usage(&self) -> CcResult<StorageUsage> {
+        self.usage().totals()
     }
 
-    /// 硬重置分支到指定检查点 - 真正的时光机效果
-    pub fn reset_branch_to_checkpoint(&self, hash: &str) -> CcResult<()> {
-        let commit = self.find_commit(hash)?;
+    /// Fetch `remote`'s CCG branch, only the latest `depth` checkpoints if set
+    pub fn fetch_checkpoints(&self, remote: &str, depth: Option<u32>) -> CcResult<FetchReport> {
+        self.fetches().fetch(remote, depth)
+    }
+
+    /// Per-user checkpoint namespaces shared in team mode
+    pub fn users(&self) -> UserNamespaces<'_> {
+        UserNamespaces::new(&self.repo)
+    }
+
+    /// Publish the CCG branch as the current user's namespace on `remote`
+    pub fn publish_checkpoints(&self, remote: &str) -> CcResult<String> {
+        self.users().publish(remote)
+    }
+
+    /// Who may discard or rewrite checkpoints, from `ccg.team.permissions`
+    pub fn team_permissions(&self) -> Permissions {
+        self.config().team_permissions.unwrap_or_default()
+    }
+
+    /// Short hash length that tells every commit apart, at least `ccg.abbrev`
+    pub fn abbrev_len(&self) -> usize {
+        abbrev::abbrev_len(&self.repo)
+    }
+
+    /// Whether history was cut by a shallow clone or fetch
+    pub fn is_shallow(&self) -> bool {
+        self.repo.is_shallow()
+    }
+
+    /// Storage checkpoints share with the rest of the history and add to it
+    pub fn dedupe_report(&self) -> CcResult<DedupeReport> {
+        self.usage().dedupe_report()
+    }
+
+    /// Targeted repacking of large checkpointed files
+    pub fn repacker(&self) -> Repacker<'_> {
+        Repacker::new(&self.repo)
+    }
 
-        if self.has_uncommitted_changes()? {
-            return Err(CheckpointError::UncommittedChanges);
+    /// Pack the loose versions of large files with delta compression
+    pub fn repack_large_files(&self, settings: &RepackSettings) -> CcResult<RepackReport> {
+        let report = self.repacker().repack(settings)?;
+        let _ = self.maintain_commit_graph(true);
+        Ok(report)
+    }
+
+    /// The report of the last targeted repack, if any
+    pub fn last_repack(&self) -> CcResult<Option<RepackReport>> {
+        self.repacker().last_report()
+    }
+
+    /// The commit-graph file of the repository, if there is a usable one
+    pub fn commit_graph(&self) -> CcResult<Option<CommitGraph>> {
+        CommitGraph::load(&self.repo)
+    }
+
+    /// Write the commit-graph file when `ccg.commitGraph` is enabled
+    ///
+    /// With `force` the graph is always rewritten; otherwise only when it is
+    /// missing or at least [`COMMIT_GRAPH_REFRESH`] commits of the CCG branch
+    /// are not in it yet.
+    ///
+    /// # Returns
+    /// Whether the graph was written
+    pub fn maintain_commit_graph(&self, force: bool) -> CcResult<bool> {
+        if !CcgConfig::load(&self.repo).commit_graph {
+            return Ok(false);
         }
+        if !force {
+            let Ok(branch) = self.branches().get_ccg_branch() else {
+                return Ok(false);
+            };
+            let tip = branch.get().peel_to_commit()?;
+            if let Some(graph) = self.commit_graph()?
+                && graph.missing_from(&self.repo, tip.id(), COMMIT_GRAPH_REFRESH)?
+                    < COMMIT_GRAPH_REFRESH
+            {
+                return Ok(false);
+            }
+        }
+        CommitGraph::write(&self.repo)?;
+        Ok(true)
+    }
 
-        // 获取当前分支引用
-        let head = self
-            .repo
-            .head()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        let branch_name = head.shorthand().unwrap_or("ccg");
+    /// The `cp-N` number assigned to a checkpoint
+    pub fn checkpoint_id(&self, hash: &str) -> CcResult<Option<u64>> {
+        let hash = self.find_commit(hash)?.id().to_string();
+        Ok(self.ids().by_hash()?.get(&hash).copied())
+    }
 
-        // 强制重置分支到目标提交
-        let mut branch = self
-            .repo
-            .find_branch(branch_name, git2::BranchType::Local)
-            .map_err(CheckpointError::GitOperationFailed)?;
+    /// Find a commit by hash
+    pub fn find_commit(&self, hash: &str) -> CcResult<Commit<'_>> {
+        self.commits().find_commit(hash)
+    }
 
-        // 设置分支指向目标提交
-        let reference = branch.get_mut();
-        reference
-            .set_target(commit.id(), "Reset branch to checkpoint")
-            .map_err(CheckpointError::GitOperationFailed)?;
+    /// Get commit details
+    pub fn get_commit_details(&self, hash: &str) -> CcResult<String> {
+        self.commits().get_commit_details(hash)
+    }
 
-        // 硬重置工作目录和索引到目标提交
-        self.repo
-            .reset(commit.as_object(), git2::ResetType::Hard, None)
-            .map_err(CheckpointError::GitOperationFailed)?;
+    /// Restore to a checkpoint (`clean` also removes untracked files)
+    pub fn restore_checkpoint(&self, hash: &str, clean: bool) -> CcResult<()> {
+        self.commits().restore_commit(hash, clean)
+    }
 
-        Ok(())
+    /// 硬重置分支到指定检查点 - 真正的时光机效果（`clean` 时同时删除未跟踪的文件，
+    /// `continue_on_error` 时跳过无法写入的文件）
+    pub fn reset_branch_to_checkpoint(
+        &self,
+        hash: &str,
+        clean: bool,
+        continue_on_error: bool,
+    ) -> CcResult<CheckoutReport> {
+        self.commits()
+            .reset_branch_to_commit(hash, clean, continue_on_error)
+    }
+
+    /// Untracked files in the working directory
+    pub fn untracked_files(&self) -> CcResult<Vec<String>> {
+        self.commits().untracked_files()
+    }
+
+    /// Record a restore in the restore journal
+    pub fn record_restore(
+        &self,
+        target: &str,
+        discarded_tip: &str,
+        discarded: usize,
+    ) -> CcResult<String> {
+        self.journal()
+            .record_restore(target, discarded_tip, discarded)
+    }
+
+    /// Restores recorded in the restore journal, newest first
+    pub fn restore_records(&self) -> CcResult<Vec<RestoreRecord>> {
+        self.journal().restores()
+    }
+
+    /// The checkpoint history as a DAG: the CCG branch, the checkpoints
+    /// restores discarded, their parent links and the restores
+    pub fn checkpoint_graph(&self) -> CcResult<CheckpointGraph> {
+        GraphBuilder::new(&self.repo).build()
+    }
+
+    /// Record a skipped guard hook in the audit log
+    pub fn record_bypass(&self, hook: &str, target: &str, via: &str) -> CcResult<GuardBypass> {
+        self.audit().record(hook, target, via)
+    }
+
+    /// Guard hooks skipped so far, oldest first
+    pub fn guard_bypasses(&self) -> CcResult<Vec<GuardBypass>> {
+        self.audit().entries()
     }
 
     /// 获取当前 HEAD 提交
-    pub fn get_head_commit(&self) -> CcResult<git2::Commit> {
-        let head = self
-            .repo
-            .head()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        let commit = head
-            .peel_to_commit()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        Ok(commit)
+    pub fn get_head_commit(&self) -> CcResult<Commit<'_>> {
+        self.commits().get_head_commit()
+    }
+
+    /// Get the latest checkpoint without switching HEAD to the CCG branch
+    pub fn checkpoint_tip(&self) -> CcResult<Commit<'_>> {
+        self.commits().checkpoint_tip()
     }
 
     /// 计算两个提交之间的提交数量
     pub fn count_commits_between(&self, from_hash: &str, to_hash: &str) -> CcResult<usize> {
-        let from_commit = self.find_commit(from_hash)?;
-        let to_commit = self.find_commit(to_hash)?;
-
-        // 使用 git2 的 revwalk 来计算提交数量
-        let mut revwalk = self
-            .repo
-            .revwalk()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        revwalk
-            .push(to_commit.id())
-            .map_err(CheckpointError::GitOperationFailed)?;
-        revwalk
-            .hide(from_commit.id())
-            .map_err(CheckpointError::GitOperationFailed)?;
+        self.commits().count_commits_between(from_hash, to_hash)
+    }
 
-        let count = revwalk.count();
-        Ok(count)
+    /// Count the commits on each side of two possibly unrelated commits
+    pub fn ahead_behind(&self, from_hash: &str, to_hash: &str) -> CcResult<AheadBehind> {
+        self.commits().ahead_behind(from_hash, to_hash)
     }
 
     /// Get current branch name
     pub fn get_current_branch_name(&self) -> CcResult<String> {
-        match self.repo.head() {
-            Ok(head) => {
-                let head_name = head.name().ok_or_else(|| {
-                    CheckpointError::GitOperationFailed(git2::Error::from_str("HEAD has no name"))
-                })?;
-
-                if head_name == "HEAD" {
-                    return Ok("HEAD".to_string());
-                }
-
-                Ok(head_name
-                    .strip_prefix("refs/heads/")
-                    .unwrap_or(head_name)
-                    .to_string())
-            }
-            Err(e) => {
-                if e.code() == git2::ErrorCode::UnbornBranch {
-                    Ok("main".to_string())
-                } else {
-                    Err(CheckpointError::GitOperationFailed(e))
-                }
-            }
-        }
+        self.branches().get_current_branch_name()
     }
 
     /// Check if HEAD is detached
     pub fn is_head_detached(&self) -> CcResult<bool> {
-        match self.repo.head() {
-            Ok(head) => Ok(head.name().is_none_or(|name| name == "HEAD")),
-            Err(e) => {
-                if e.code() == git2::ErrorCode::UnbornBranch {
-                    Ok(false)
-                } else {
-                    Err(CheckpointError::GitOperationFailed(e))
-                }
-            }
-        }
+        self.branches().is_head_detached()
     }
 
     /// Check if there are uncommitted changes
     pub fn has_uncommitted_changes(&self) -> CcResult<bool> {
-        let mut opts = git2::StatusOptions::new();
-        opts.include_untracked(true);
-        let statuses = self
-            .repo
-            .statuses(Some(&mut opts))
-            .map_err(CheckpointError::GitOperationFailed)?;
-        Ok(!statuses.is_empty())
+        self.commits().has_uncommitted_changes()
     }
 
     /// Create initial commit
     pub fn create_initial_commit(&self) -> CcResult<String> {
-        println!("📝 创建初始提交...");
-
-        let signature = self.create_signature()?;
-        let mut index = self
-            .repo
-            .index()
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        if !self.has_non_ignored_files()? {
-            println!("📝 没有文件可添加，创建空的初始提交...");
-        } else {
-            index
-                .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
-                .map_err(CheckpointError::GitOperationFailed)?;
-            index.write().map_err(CheckpointError::GitOperationFailed)?;
-        }
-
-        let tree_id = index
-            .write_tree()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        let tree = self
-            .repo
-            .find_tree(tree_id)
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        let commit_id = self
-            .repo
-            .commit(
-                Some("HEAD"),
-                &signature,
-                &signature,
-                DEFAULT_COMMIT_MESSAGE,
-                &tree,
-                &[],
-            )
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        println!("✅ 初始提交创建成功: {commit_id}");
-        Ok(commit_id.to_string())
+        self.commits().create_initial_commit()
     }
 
     /// Show checkpoint with optional diff
-    pub fn show_checkpoint(&self, hash: &str, show_diff: bool) -> CcResult<String> {
+    ///
+    /// Output is streamed to `out` section by section. `files` holds pathspec
+    /// patterns limiting both the file list and the diff (empty = all files).
+    /// The diff of a root checkpoint (no parent, or a shallow clone boundary)
+    /// adds every file, so it is shown as a per-file stat unless `full` is set.
+    pub fn show_checkpoint(
+        &self,
+        hash: &str,
+        show_diff: bool,
+        full: bool,
+        files: &[String],
+        out: &mut dyn Write,
+    ) -> CcResult<()> {
         let commit = self.find_commit(hash)?;
         let mut result = self.get_commit_details(hash)?;
 
         // 添加文件变更信息
-        let diff_ops = diff::DiffOperations::new(&self.repo);
-        if let Ok(diff) = diff_ops.get_commit_diff(&commit) {
-            let mut stats = (0, 0, 0); // (added, modified, deleted)
-            let mut files = Vec::new();
-
-            for delta in diff.deltas() {
-                if let Some(file) = delta.new_file().path() {
-                    let status = delta.status();
-                    let (status_str, color) = match status {
-                        Delta::Added => {
-                            stats.0 += 1;
-                            ("A", Color::Green)
-                        }
-                        Delta::Deleted => {
-                            stats.2 += 1;
-                            ("D", Color::Red)
-                        }
-                        Delta::Modified => {
-                            stats.1 += 1;
-                            ("M", Color::Yellow)
-                        }
-                        Delta::Renamed => ("R", Color::Blue),
-                        Delta::Copied => ("C", Color::Magenta),
-                        _ => ("?", Color::White),
-                    };
-
-                    files.push(format!(
-                        "  {} {}",
-                        style(status_str).fg(color).bold(),
-                        style(file.display()).fg(Color::White)
-                    ));
-                }
-            }
-
-            if !files.is_empty() {
-                result.push_str(&format!(
-                    "\n{} {} files changed",
-                    style("Files:").fg(Color::White).bold(),
-                    style(files.len()).fg(Color::Cyan).bold()
-                ));
-
-                if stats.0 > 0 || stats.1 > 0 || stats.2 > 0 {
-                    result.push_str(" (");
-                    let mut parts = Vec::new();
-                    if stats.0 > 0 {
-                        parts.push(format!(
-                            "{} {}",
-                            style(stats.0).fg(Color::Green).bold(),
-                            style("added").fg(Color::Green)
-                        ));
-                    }
-                    if stats.1 > 0 {
-                        parts.push(format!(
-                            "{} {}",
-                            style(stats.1).fg(Color::Yellow).bold(),
-                            style("modified").fg(Color::Yellow)
-                        ));
-                    }
-                    if stats.2 > 0 {
-                        parts.push(format!(
-                            "{} {}",
-                            style(stats.2).fg(Color::Red).bold(),
-                            style("deleted").fg(Color::Red)
-                        ));
-                    }
-                    result.push_str(&parts.join(", "));
-                    result.push(')');
-                }
-                result.push_str("\n\n");
-
-                for file in files {
-                    result.push_str(&format!("{file}\n"));
-                }
-            }
-        }
-
-        if show_diff {
-            result.push('\n');
+        let diff_ops = self.diffs();
+        let diff = diff_ops.get_commit_diff_for_paths(&commit, files);
+        if diff_ops.is_shallow_boundary(&commit) {
             result.push_str(&format!(
-                "{}\n",
-                style("Detailed Diff:").fg(Color::White).bold()
+                "\n{}{}\n",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!("shallow_boundary_note")).fg(Color::Yellow)
             ));
-            result.push_str(&diff_ops.get_commit_diff_content(hash)?);
         }
+        if let Ok(diff) = &diff {
+            result.push_str(&diff_ops.format_changed_files(diff));
+        }
+
+        out.write_all(result.as_bytes())?;
+        out.flush()?;
+
+        let root = commit.parent_count() == 0 || diff_ops.is_shallow_boundary(&commit);
+        if show_diff && root && !full {
+            writeln!(out)?;
+            writeln!(out, "{}", style("Diff Stat:").fg(Color::White).bold())?;
+            writeln!(
+                out,
+                "{}{}",
+                style(Icon::Note.prefix()).fg(Color::White).dim(),
+                style(t!("show_root_stat_note")).dim()
+            )?;
+            diff_ops.write_diff_stat(&diff?, out)?;
+        } else if show_diff {
+            writeln!(out)?;
+            writeln!(out, "{}", style("Detailed Diff:").fg(Color::White).bold())?;
+            let diff = diff?;
+            let cache = self.cache();
+            let key = cache.key("show", &[commit.id()], files);
+            cache.write_through(&key, out, |out| diff_ops.write_diff_output(&diff, out))?;
+        }
+
+        Ok(())
+    }
+
+    /// Branch HEAD is on (other than the CCG branch) and its tip
+    pub fn base_branch_tip(&self) -> CcResult<Option<(String, String)>> {
+        self.branches().base_branch_tip()
+    }
+
+    /// Commits added to a checkpoint's base branch since it was created
+    pub fn base_drift(&self, hash: &str) -> CcResult<Option<BaseDrift>> {
+        self.commits().base_drift(hash)
+    }
+
+    /// How the latest checkpoint and the current branch have diverged
+    pub fn branch_drift(&self) -> CcResult<BranchDrift> {
+        self.diffs().branch_drift()
+    }
+
+    /// Files changed in the working directory since a checkpoint
+    pub fn changes_since(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>> {
+        self.diffs().changes_since(hash)
+    }
+
+    /// Each file changed between two commits with its plain unified diff
+    pub fn file_patches(&self, from: Option<&str>, to: &str) -> CcResult<Vec<FilePatch>> {
+        self.diffs().file_patches(from, to)
+    }
+
+    /// Files a restore to a checkpoint would change
+    pub fn restore_preview(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>> {
+        self.diffs().restore_preview(hash)
+    }
+
+    /// Hunks a restore to a checkpoint would apply
+    pub fn restore_hunks(&self, hash: &str) -> CcResult<Vec<RestoreHunk>> {
+        self.diffs().restore_hunks(hash)
+    }
+
+    /// Apply the selected restore hunks to the working directory
+    pub fn apply_restore_hunks(&self, hash: &str, selected: &[bool]) -> CcResult<usize> {
+        self.diffs().apply_restore_hunks(hash, selected)
+    }
+
+    /// Content of a file as it existed in a checkpoint
+    pub fn checkpoint_file(&self, hash: &str, path: &str) -> CcResult<Vec<u8>> {
+        self.commits().file_content(hash, path)
+    }
+
+    /// Write the files of a checkpoint below `dir`
+    pub fn export_checkpoint(&self, hash: &str, dir: &Path) -> CcResult<usize> {
+        self.commits().export_tree(hash, dir)
+    }
+
+    /// Successive versions of a file across checkpoints, oldest first
+    pub fn file_history(&self, path: &str) -> CcResult<Vec<FileVersion>> {
+        self.commits().file_history(path)
+    }
 
-        Ok(result)
+    /// Whether the CCG branch exists
+    pub fn has_ccg_branch(&self) -> CcResult<bool> {
+        self.branches().has_ccg_branch()
     }
 
     /// Ensure we're on CCG branch and return original branch
     pub fn ensure_ccg_branch(&self) -> CcResult<String> {
-        let current_branch = self.get_current_branch_name()?;
-
-        if current_branch != CCG_BRANCH_NAME {
-            println!(
-                "{} {} {} {} {}",
-                style("🔄").fg(Color::Blue),
-                style("切换到").fg(Color::White),
-                style(CCG_BRANCH_NAME).fg(Color::Yellow).bold(),
-                style("分支执行操作，当前分支:").fg(Color::White),
-                style(&current_branch).fg(Color::Cyan)
-            );
-
-            let branch = self
-                .repo
-                .find_branch(CCG_BRANCH_NAME, git2::BranchType::Local)
-                .map_err(CheckpointError::GitOperationFailed)?;
-            let branch_ref = branch.get();
-            self.repo
-                .set_head(branch_ref.name().unwrap())
-                .map_err(CheckpointError::GitOperationFailed)?;
-        }
-
-        Ok(current_branch)
+        self.branches().ensure_ccg_branch()
     }
 
     /// Restore to original branch
     pub fn restore_original_branch(&self, original_branch: &str) -> CcResult<()> {
-        if original_branch != CCG_BRANCH_NAME {
-            let branch_ref = format!("refs/heads/{original_branch}");
-            if let Err(e) = self.repo.set_head(&branch_ref) {
-                println!(
-                    "{} {} {}",
-                    style("⚠️").fg(Color::Yellow),
-                    style("警告: 无法切回原始分支").fg(Color::Yellow),
-                    style(original_branch).fg(Color::Cyan)
-                );
-                return Err(CheckpointError::GitOperationFailed(e));
-            } else {
-                println!(
-                    "{} {} {}",
-                    style("🔄").fg(Color::Blue),
-                    style("已切回原始分支:").fg(Color::White),
-                    style(original_branch).fg(Color::Cyan)
-                );
-            }
-        }
-        Ok(())
+        self.branches().restore_original_branch(original_branch)
     }
 
     /// Diff checkpoints
     pub fn diff_checkpoints(&self, hash_a: &str, hash_b: Option<&str>) -> CcResult<String> {
-        let diff_ops = diff::DiffOperations::new(&self.repo);
-        diff_ops.diff_commits(hash_a, hash_b)
+        self.diffs().diff_commits(hash_a, hash_b)
     }
 
-    /// Get working directory diff
-    pub fn get_workdir_diff(&self) -> CcResult<git2::Diff> {
-        let head = self.repo.head()?;
-        let head_commit = head.peel_to_commit()?;
-        let head_tree = head_commit.tree()?;
+    /// Diff a checkpoint against a directory on disk
+    pub fn diff_directory(&self, hash: &str, dir: &Path) -> CcResult<String> {
+        self.diffs().diff_directory(hash, dir)
+    }
 
-        self.repo
-            .diff_tree_to_index(Some(&head_tree), None, None)
-            .map_err(CheckpointError::GitOperationFailed)
+    /// Get working directory diff
+    pub fn get_workdir_diff(&self) -> CcResult<git2::Diff<'_>> {
+        self.diffs().get_workdir_diff()
     }
 
     /// Get commit diff content
     pub fn get_commit_diff_content(&self, hash: &str) -> CcResult<String> {
-        let diff_ops = diff::DiffOperations::new(&self.repo);
-        diff_ops.get_commit_diff_content(hash)
+        self.diffs().get_commit_diff_content(hash)
     }
 
-    /// Prune checkpoints (placeholder implementation)
-    pub fn prune_checkpoints(&self, _keep: Option<usize>, _before: Option<&str>) -> CcResult<()> {
-        Ok(())
+    /// Report which later checkpoints touch the same files and lines as `hash`
+    pub fn checkpoint_impact(&self, hash: &str) -> CcResult<ImpactReport> {
+        self.diffs().checkpoint_impact(hash)
     }
 
-    // Helper methods
-    fn create_signature(&self) -> CcResult<Signature> {
-        let config = self
-            .repo
-            .config()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        let name = config
-            .get_str("user.name")
-            .unwrap_or("Claude Code Checkpoint");
-        let email = config
-            .get_str("user.email")
-            .unwrap_or("claudecode@checkpoint.local");
-        Signature::now(name, email).map_err(CheckpointError::GitOperationFailed)
-    }
-
-    fn get_parent_commit(&self) -> CcResult<Option<Commit>> {
-        let head = self
-            .repo
-            .head()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        Ok(head.peel_to_commit().ok())
+    /// Create a checkpoint that undoes the changes of `hash`
+    pub fn revert_checkpoint(&self, hash: &str) -> CcResult<String> {
+        let hash = self.commits().revert_commit(hash)?;
+        self.record_checkpoint(&hash)?;
+        Ok(hash)
     }
 
-    fn has_changes_to_commit(&self) -> CcResult<bool> {
-        let parent_commit = match self.get_parent_commit()? {
-            Some(commit) => commit,
-            None => return self.has_non_ignored_files(),
-        };
+    /// The task started with `ccg task start` and not yet ended
+    pub fn active_task(&self) -> CcResult<Option<Task>> {
+        ActiveTask::new(&self.repo).load()
+    }
 
-        let parent_tree = parent_commit
-            .tree()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        let mut temp_index = self
-            .repo
-            .index()
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        temp_index
-            .clear()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        temp_index
-            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        let temp_tree_id = temp_index
-            .write_tree()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        let temp_tree = self
-            .repo
-            .find_tree(temp_tree_id)
-            .map_err(CheckpointError::GitOperationFailed)?;
+    /// Make `task` the active task, or end the active task when None
+    pub fn set_active_task(&self, task: Option<&Task>) -> CcResult<()> {
+        ActiveTask::new(&self.repo).save(task)
+    }
 
-        let diff = self
-            .repo
-            .diff_tree_to_tree(Some(&parent_tree), Some(&temp_tree), None)
-            .map_err(CheckpointError::GitOperationFailed)?;
+    /// The first parent of a commit, None for a root commit
+    pub fn checkpoint_parent(&self, hash: &str) -> CcResult<Option<String>> {
+        let commit = self.commits().find_commit(hash)?;
+        Ok(commit.parent_id(0).ok().map(|id| id.to_string()))
+    }
+
+    /// Squash the changes from `base` to `tip`, minus those of `skip`, into
+    /// one commit on HEAD
+    pub fn squash_onto_head(
+        &self,
+        base: &str,
+        tip: &str,
+        skip: &[String],
+        message: &str,
+    ) -> CcResult<String> {
+        self.commits().squash_onto_head(base, tip, skip, message)
+    }
+
+    /// Diff statistics of each checkpoint, in the same order
+    ///
+    /// Read from the statistics index, computing and recording missing ones.
+    pub fn checkpoint_stats(&self, hashes: &[String]) -> CcResult<Vec<DiffStats>> {
+        let oids = hashes
+            .iter()
+            .map(|hash| Ok(self.find_commit(hash)?.id()))
+            .collect::<CcResult<Vec<_>>>()?;
+        self.stats().stats_for(&oids)
+    }
+
+    /// Whether a checkpoint's changes only touch whitespace
+    ///
+    /// Read from the statistics index, computing and recording it if missing.
+    pub fn is_format_only(&self, hash: &str) -> CcResult<bool> {
+        let oid = self.find_commit(hash)?.id();
+        let stats = self.stats().stats_for(&[oid])?;
+        Ok(stats.first().is_some_and(|stat| stat.format_only))
+    }
 
-        Ok(diff.deltas().len() > 0)
+    /// Change a checkpoint's message, replaying later checkpoints on top
+    pub fn reword_checkpoint(&self, hash: &str, message: &str) -> CcResult<RewrittenHistory> {
+        let original = self.find_commit(hash)?.id().to_string();
+        let history = self.rewrites().reword(hash, message)?;
+        self.carry_over_ids(&original, &history)?;
+        Ok(history)
     }
 
-    fn has_non_ignored_files(&self) -> CcResult<bool> {
-        let mut opts = git2::StatusOptions::new();
-        opts.include_untracked(true);
-        opts.include_ignored(false);
+    /// Split a checkpoint into one checkpoint per changed file
+    pub fn split_checkpoint(&self, hash: &str) -> CcResult<RewrittenHistory> {
+        let original = self.find_commit(hash)?.id().to_string();
+        let history = self.rewrites().split(hash)?;
+        self.carry_over_ids(&original, &history)?;
+        Ok(history)
+    }
 
-        let statuses = self
+    /// Checkpoints a rebase onto `onto` would move, newest first
+    pub fn rebase_candidates(&self, onto: &str) -> CcResult<Vec<CheckpointEntry>> {
+        let onto = self
             .repo
-            .statuses(Some(&mut opts))
-            .map_err(CheckpointError::GitOperationFailed)?;
-        Ok(!statuses.is_empty())
+            .revparse_single(onto)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|_| CheckpointError::BranchNotFound(onto.to_string()))?;
+        let moved = self.rewrites().rebase_candidates(&onto)?;
+        let Some(tip) = moved.last() else {
+            return Ok(Vec::new());
+        };
+        self.commits().entries_from(tip.id(), moved.len())
+    }
+
+    /// Move the checkpoints `onto` does not contain onto its head, keeping
+    /// their IDs
+    pub fn rebase_checkpoints(
+        &self,
+        onto: &str,
+        replay: bool,
+        progress: &mut dyn FnMut(usize, usize, &str),
+    ) -> CcResult<RewrittenHistory> {
+        let history = self
+            .rewrites()
+            .rebase_onto(onto, replay, &mut |i, n, commit| {
+                progress(i, n, &commit.id().to_string())
+            })?;
+        self.ids().remap(&history.replayed)?;
+        Ok(history)
+    }
+
+    /// Move IDs to rewritten checkpoints; the first replacement inherits the
+    /// rewritten checkpoint's ID and further replacements get new ones
+    fn carry_over_ids(&self, original: &str, history: &RewrittenHistory) -> CcResult<()> {
+        let ids = self.ids();
+        let mut rewritten = history.replayed.clone();
+        if let Some(first) = history.replacements.first() {
+            rewritten.push((original.to_string(), first.clone()));
+        }
+        ids.remap(&rewritten)?;
+        for extra in history.replacements.iter().skip(1) {
+            ids.assign(extra)?;
+        }
+        Ok(())
+    }
+
+    /// Drop checkpoints from the CCG branch in one pass, keeping the IDs of
+    /// the replayed checkpoints
+    pub fn drop_checkpoints(&self, hashes: &[String]) -> CcResult<RewrittenHistory> {
+        let history = self.rewrites().drop(hashes)?;
+        self.ids().remap(&history.replayed)?;
+        // 改写后的提交不在旧的 commit-graph 中；写入失败只是失去加速
+        let _ = self.maintain_commit_graph(true);
+        Ok(history)
     }
 }
@@ -0,0 +1,190 @@
+//! Length of displayed short hashes
+//!
+//! Seven hex digits stop telling commits apart once a history grows large
+//! enough. Like git's `core.abbrev=auto`, the length ccg displays is worked
+//! out per repository: the shortest length at which no two commits reachable
+//! from HEAD, the local branches or the synced user namespaces share a
+//! prefix. `ccg.abbrev` sets the minimum, which is raised when it is not
+//! unique; the length never exceeds a full hash.
+//!
+//! Walking the whole history is too slow to repeat on every invocation, so
+//! the result is kept in `.git/ccg/abbrev` with the ref tips it was computed
+//! for. When the tips move, only the new commits are checked against the
+//! object database; a full walk is needed only when one of them is ambiguous
+//! at the cached length or a cached tip is gone.
+
+use super::ccg_dir;
+use super::object_format::ObjectFormat;
+use super::types::USERS_REF_PREFIX;
+use crate::config::CcgConfig;
+use crate::error::Result as CcResult;
+use git2::{ErrorCode, Oid, Repository};
+use std::fs;
+use std::path::PathBuf;
+
+/// Length used when `ccg.abbrev` is unset or `auto`
+pub const DEFAULT_ABBREV: usize = 7;
+
+/// Shortest length `ccg.abbrev` may set, as in git
+pub const MIN_ABBREV: usize = 4;
+
+/// The short hash length to display in a repository, honouring `ccg.abbrev`
+///
+/// Reads the cached length when the ref tips have not moved. Falls back to
+/// the configured minimum when the history cannot be read.
+pub fn abbrev_len(repo: &Repository) -> usize {
+    let min = CcgConfig::load(repo)
+        .abbrev
+        .and_then(|len| usize::try_from(len).ok())
+        .unwrap_or(DEFAULT_ABBREV);
+    let full = ObjectFormat::of(repo).hex_len();
+    AbbrevCache::new(repo)
+        .distinct_len()
+        .map(|len| len.clamp(min.clamp(MIN_ABBREV, full), full))
+        .unwrap_or(min)
+}
+
+/// The shortest length, at least `min`, at which every commit in the
+/// repository's history has a distinct prefix
+///
+/// # Arguments
+/// * `repo` - The repository
+/// * `min` - The minimum length, clamped to [`MIN_ABBREV`] and a full hash
+///
+/// # Errors
+/// Returns CheckpointError::GitOperationFailed if the history cannot be walked
+pub fn unique_abbrev_len(repo: &Repository, min: usize) -> CcResult<usize> {
+    let full = ObjectFormat::of(repo).hex_len();
+    let min = min.clamp(MIN_ABBREV, full);
+    Ok(walk_distinct_len(repo)?.clamp(min, full))
+}
+
+/// Shortest length, at least [`MIN_ABBREV`], at which every commit reachable
+/// from the tips has a distinct prefix, found by walking the whole history
+fn walk_distinct_len(repo: &Repository) -> CcResult<usize> {
+    let mut revwalk = repo.revwalk()?;
+    // 未出生的 HEAD 没有提交可遍历
+    let _ = revwalk.push_head();
+    revwalk.push_glob("refs/heads")?;
+    revwalk.push_glob(USERS_REF_PREFIX.trim_end_matches('/'))?;
+    let mut hashes = revwalk
+        .map(|oid| oid.map(|oid| oid.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    hashes.sort_unstable();
+
+    // 排序后共享最长前缀的哈希一定相邻
+    let shared = hashes
+        .windows(2)
+        .map(|pair| common_prefix_len(&pair[0], &pair[1]))
+        .max()
+        .unwrap_or(0);
+    Ok((shared + 1).max(MIN_ABBREV))
+}
+
+/// The distinct prefix length cached with the ref tips it was computed for
+struct AbbrevCache<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> AbbrevCache<'a> {
+    fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// File holding the length on its first line and the tips on the rest
+    fn path(&self) -> PathBuf {
+        ccg_dir(self.repo).join("abbrev")
+    }
+
+    /// Commits HEAD, the local branches and the user namespaces point at,
+    /// sorted
+    fn tips(&self) -> CcResult<Vec<Oid>> {
+        let mut tips: Vec<Oid> = self
+            .repo
+            .head()
+            .ok()
+            .and_then(|head| head.target())
+            .into_iter()
+            .collect();
+        for glob in ["refs/heads/*", &format!("{USERS_REF_PREFIX}*")] {
+            for reference in self.repo.references_glob(glob)? {
+                tips.extend(reference?.target());
+            }
+        }
+        tips.sort_unstable();
+        tips.dedup();
+        Ok(tips)
+    }
+
+    /// The cached length and tips; None when there is no readable cache
+    fn load(&self) -> Option<(usize, Vec<Oid>)> {
+        let content = fs::read_to_string(self.path()).ok()?;
+        let mut lines = content.lines();
+        let len = lines.next()?.parse().ok()?;
+        let tips = lines
+            .map(Oid::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        Some((len, tips))
+    }
+
+    /// The distinct prefix length for the current tips
+    ///
+    /// A cache that cannot be written, as in a read-only repository, is
+    /// ignored.
+    fn distinct_len(&self) -> CcResult<usize> {
+        let tips = self.tips()?;
+        let cached = self.load();
+        if let Some((len, cached_tips)) = &cached
+            && *cached_tips == tips
+        {
+            return Ok(*len);
+        }
+        let len = match cached {
+            Some((len, cached_tips)) => match self.extend(len, &cached_tips, &tips)? {
+                Some(len) => len,
+                None => walk_distinct_len(self.repo)?,
+            },
+            None => walk_distinct_len(self.repo)?,
+        };
+
+        let mut content = format!("{len}\n");
+        for tip in &tips {
+            content.push_str(&format!("{tip}\n"));
+        }
+        let _ =
+            fs::create_dir_all(ccg_dir(self.repo)).and_then(|()| fs::write(self.path(), content));
+        Ok(len)
+    }
+
+    /// Check the commits added since the cached tips at the cached length
+    ///
+    /// # Returns
+    /// `len` when every new commit is still unambiguous at it, None when a
+    /// full walk is needed
+    fn extend(&self, len: usize, cached: &[Oid], tips: &[Oid]) -> CcResult<Option<usize>> {
+        let mut revwalk = self.repo.revwalk()?;
+        for tip in tips {
+            revwalk.push(*tip)?;
+        }
+        for tip in cached {
+            // 缓存的提交已不存在（如被垃圾回收）时无法确定新增的提交
+            if revwalk.hide(*tip).is_err() {
+                return Ok(None);
+            }
+        }
+        let odb = self.repo.odb()?;
+        for oid in revwalk {
+            match odb.exists_prefix(oid?, len) {
+                Ok(_) => {}
+                Err(e) if e.code() == ErrorCode::Ambiguous => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(Some(len))
+    }
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.bytes().zip(b.bytes()).take_while(|(x, y)| x == y).count()
+}
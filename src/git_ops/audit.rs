@@ -0,0 +1,92 @@
+//! Audit log of bypassed guard hooks
+//!
+//! Blocking `pre-create` and `pre-restore` hooks enforce team policies. In an
+//! emergency they can be skipped with `--bypass-guard` or
+//! `CCG_BYPASS_GUARD=1`; every skip is appended to `.git/ccg/audit.log`, one
+//! JSON object per line, recording who skipped which hook for what.
+
+use super::ccg_dir;
+use super::types::GuardBypass;
+use crate::error::Result as CcResult;
+use git2::Repository;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// The guard bypass audit log
+pub struct AuditLog<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> AuditLog<'a> {
+    /// Create a new AuditLog instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// File holding the log
+    pub fn path(&self) -> PathBuf {
+        ccg_dir(self.repo).join("audit.log")
+    }
+
+    /// The person bypassing a hook: the git identity, or the login name
+    /// when git has none
+    pub fn user(&self) -> String {
+        let config = self.repo.config().ok();
+        let get = |key: &str| config.as_ref().and_then(|c| c.get_string(key).ok());
+        match (get("user.name"), get("user.email")) {
+            (Some(name), Some(email)) => format!("{name} <{email}>"),
+            (Some(name), None) => name,
+            _ => std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_else(|_| "unknown".to_string()),
+        }
+    }
+
+    /// Append a bypass to the log
+    ///
+    /// # Arguments
+    /// * `hook` - Name of the skipped hook, e.g. `pre-create`
+    /// * `target` - What the hook would have checked: the checkpoint message
+    ///   or the restore target
+    /// * `via` - How the bypass was requested
+    ///
+    /// # Returns
+    /// The recorded entry
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the log cannot be written
+    pub fn record(&self, hook: &str, target: &str, via: &str) -> CcResult<GuardBypass> {
+        let entry = GuardBypass {
+            time: chrono::Utc::now().timestamp(),
+            user: self.user(),
+            hook: hook.to_string(),
+            target: target.to_string(),
+            via: via.to_string(),
+        };
+        let path = self.path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let line = serde_json::to_string(&entry).map_err(std::io::Error::other)?;
+        writeln!(file, "{line}")?;
+        Ok(entry)
+    }
+
+    /// All recorded bypasses, oldest first; unreadable lines are skipped
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the log exists but cannot be read
+    pub fn entries(&self) -> CcResult<Vec<GuardBypass>> {
+        let content = match fs::read_to_string(self.path()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
@@ -0,0 +1,833 @@
+//! Backend abstraction over checkpoint storage
+//!
+//! `CheckpointService` only talks to storage through the [`GitBackend`] trait.
+//! The real implementation is the libgit2-backed [`GitOperations`]; tests can
+//! swap in [`MemoryBackend`](super::memory::MemoryBackend) to exercise service
+//! workflows without touching the filesystem.
+
+use crate::config::ExcludeProfile;
+use crate::error::Result as CcResult;
+use crate::git_ops::{
+    AheadBehind, BaseDrift, BranchDrift, CheckoutReport, CheckpointEntry, DedupeReport, DiffStats,
+    ExcludeRules, FetchReport, FileChangeInfo, FilePatch, FileVersion, GitOperations, GuardBypass,
+    ImpactReport, IndexRepair, InterleaveCheck, ListColumn, ListFilter, Permissions, RepackReport,
+    RepackSettings, RestoreHunk, RestoreRecord, RewrittenHistory, Snapshot, StorageUsage, Task,
+    ToolEdit, UsageAdvisory, UsageLimits,
+};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Storage operations the checkpoint service depends on
+///
+/// Checkpoints are identified by hex hashes. Methods accepting a hash also
+/// accept any unambiguous prefix of at least two characters and `cp-N` IDs.
+pub trait GitBackend {
+    /// Working directory of the repository, if it has one
+    fn workdir(&self) -> Option<PathBuf>;
+
+    /// Create the CCG branch if it does not exist yet
+    ///
+    /// # Errors
+    /// Returns an error if the branch or an initial commit cannot be created
+    fn init_checkpoints(&self) -> CcResult<()>;
+
+    /// Create the CCG branch at the tip of another branch or revision
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` if the CCG branch already
+    /// exists and `CheckpointError::BranchNotFound` if `from` is unknown
+    fn init_checkpoints_from(&self, from: &str) -> CcResult<()>;
+
+    /// Create the CCG branch as an orphan starting from a parentless copy of
+    /// HEAD's files, and keep creating it that way if it is ever recreated
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` if the CCG branch already exists
+    fn init_checkpoints_orphan(&self) -> CcResult<()>;
+
+    /// Add a built-in profile's patterns to `ccg.checkpoint.exclude` and its
+    /// size limit to `ccg.checkpoint.maxFileSize` unless one is configured
+    ///
+    /// # Returns
+    /// The patterns that were not listed yet
+    fn apply_exclude_profile(&self, profile: ExcludeProfile) -> CcResult<Vec<String>>;
+
+    /// Rename a local branch
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if `old` does not exist and
+    /// `CheckpointError::InvalidArgument` if `new` is taken or invalid
+    fn rename_branch(&self, old: &str, new: &str) -> CcResult<()>;
+
+    /// Whether the CCG branch exists (an unborn branch HEAD points to does not)
+    fn has_ccg_branch(&self) -> CcResult<bool>;
+
+    /// Name of the branch HEAD points to (`"HEAD"` when detached)
+    fn get_current_branch_name(&self) -> CcResult<String>;
+
+    /// Point HEAD at the CCG branch without touching the working directory
+    ///
+    /// # Returns
+    /// The branch HEAD pointed to before the switch, or the commit hash when
+    /// HEAD was detached; either can be passed to `restore_original_branch`
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if the CCG branch is missing
+    fn ensure_ccg_branch(&self) -> CcResult<String>;
+
+    /// Point HEAD back at a branch name or detached commit hash returned by
+    /// `ensure_ccg_branch` (no-op for the CCG branch)
+    fn restore_original_branch(&self, original_branch: &str) -> CcResult<()>;
+
+    /// Locale used for generated checkpoint messages (`ccg.messageLang`)
+    fn message_locale(&self) -> String;
+
+    /// Checkpoint count and size thresholds (`ccg.maxCheckpoints`, `ccg.maxSize`)
+    fn usage_limits(&self) -> UsageLimits;
+
+    /// Size cap of checkpoint messages in bytes (`ccg.message.maxBytes`)
+    fn message_max_bytes(&self) -> usize;
+
+    /// Whether the repository is configured read-only (`ccg.readOnly`)
+    fn read_only(&self) -> bool;
+
+    /// Advisory to show the first time checkpoint usage exceeds `limits`
+    ///
+    /// Returns None while usage is under the limits and after the advisory
+    /// has been shown once; dropping back under the limits re-arms it.
+    fn check_usage(&self, limits: &UsageLimits) -> CcResult<Option<UsageAdvisory>>;
+
+    /// Checkpoint count and the bytes recorded when they were created
+    fn storage_usage(&self) -> CcResult<StorageUsage>;
+
+    /// How much of the checkpoints' content is shared with other branches,
+    /// tags and remotes, and how much only checkpoints add
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if the CCG branch does not exist
+    fn dedupe_report(&self) -> CcResult<DedupeReport>;
+
+    /// Targeted repack settings (`ccg.repack.window`, `ccg.repack.depth`,
+    /// `ccg.repack.minSize`)
+    fn repack_settings(&self) -> RepackSettings;
+
+    /// Pack the loose versions of large files that changed in several
+    /// checkpoints, so they are stored as deltas of each other
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if the CCG branch does not
+    /// exist and `CheckpointError::GitOperationFailed` if git fails
+    fn repack_large_files(&self, settings: &RepackSettings) -> CcResult<RepackReport>;
+
+    /// The report of the last repack that packed anything, if any
+    fn last_repack(&self) -> CcResult<Option<RepackReport>>;
+
+    /// Write the commit-graph file when `ccg.commitGraph` is enabled and it
+    /// is missing or falls behind the CCG branch (always with `force`)
+    ///
+    /// # Returns
+    /// Whether the graph was written
+    fn maintain_commit_graph(&self, force: bool) -> CcResult<bool>;
+
+    /// Fetch the CCG branch of `remote` and fast-forward the local one
+    ///
+    /// # Arguments
+    /// * `remote` - Name of a configured remote
+    /// * `depth` - Only fetch this many of the most recent checkpoints
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` for an unknown remote
+    fn fetch_checkpoints(&self, remote: &str, depth: Option<u32>) -> CcResult<FetchReport>;
+
+    /// Whether older history is missing after a shallow clone or fetch
+    fn is_shallow(&self) -> bool;
+
+    /// Who may discard or rewrite checkpoints (`ccg.team.permissions`)
+    fn team_permissions(&self) -> Permissions;
+
+    /// The current user's team name, recorded in the `Ccg-User` trailer
+    fn team_user(&self) -> String;
+
+    /// Files whose pending changes include lines the tool calls described by
+    /// `edits` did not write, relative to the repository root
+    fn interleaved_files(&self, edits: &[ToolEdit]) -> CcResult<Vec<String>>;
+
+    /// Publish the CCG branch as the current user's namespace
+    /// (`refs/ccg/users/<name>/ccg`) on `remote`
+    ///
+    /// # Returns
+    /// The namespace name
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` for an unknown remote
+    fn publish_checkpoints(&self, remote: &str) -> CcResult<String>;
+
+    /// Commit the working directory onto the current branch
+    ///
+    /// # Returns
+    /// The full hash of the new checkpoint
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::NoChangesToCommit` if nothing changed
+    fn create_checkpoint(&self, message: &str) -> CcResult<String>;
+
+    /// Commit only `paths` (relative to the repository root) onto the
+    /// current branch; other changes stay pending
+    ///
+    /// # Returns
+    /// The full hash of the new checkpoint
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::NoChangesToCommit` if none of `paths` changed
+    fn create_checkpoint_paths(&self, message: &str, paths: &[String]) -> CcResult<String>;
+
+    /// Commit the current tree again as an explicitly empty checkpoint
+    ///
+    /// The message is marked with the `Ccg-Empty: true` trailer.
+    ///
+    /// # Returns
+    /// The full hash of the new checkpoint
+    fn create_empty_checkpoint(&self, message: &str) -> CcResult<String>;
+
+    /// Attach `note` to a checkpoint under `refs/notes/ccg`, replacing any
+    /// note it already has
+    ///
+    /// # Arguments
+    /// * `hash` - Full hash of the checkpoint
+    /// * `note` - Text of the note
+    fn add_note(&self, hash: &str, note: &str) -> CcResult<()>;
+
+    /// The note attached to a checkpoint, None if it has none
+    fn checkpoint_note(&self, hash: &str) -> CcResult<Option<String>>;
+
+    /// Tag names keyed by the full hash of the commit they point at
+    fn checkpoint_tags(&self) -> CcResult<HashMap<String, Vec<String>>>;
+
+    /// Formatted one-line summaries of the most recent checkpoints showing
+    /// `columns`, limited to those `filter` keeps
+    fn list_checkpoints(
+        &self,
+        limit: usize,
+        columns: &[ListColumn],
+        filter: &ListFilter,
+    ) -> CcResult<Vec<String>>;
+
+    /// Structured entries for the most recent checkpoints, newest first
+    fn checkpoint_entries(&self, limit: usize) -> CcResult<Vec<CheckpointEntry>>;
+
+    /// Resolve a full or abbreviated hash to the full checkpoint hash
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::CheckpointNotFound` or
+    /// `CheckpointError::InvalidHash` for unknown or ambiguous hashes
+    fn resolve_checkpoint(&self, hash: &str) -> CcResult<String>;
+
+    /// The `cp-N` number assigned to a checkpoint, if any
+    fn checkpoint_id(&self, hash: &str) -> CcResult<Option<u64>>;
+
+    /// Full hash of the latest checkpoint, the tip of the CCG branch (HEAD
+    /// when there is none); HEAD need not point at the CCG branch
+    fn head_checkpoint(&self) -> CcResult<String>;
+
+    /// Whether tracked files in the working directory differ from HEAD
+    /// (untracked files are not counted)
+    fn has_uncommitted_changes(&self) -> CcResult<bool>;
+
+    /// Untracked, non-ignored files in the working directory, in path order
+    fn untracked_files(&self) -> CcResult<Vec<String>>;
+
+    /// Number of commits reachable from `to_hash` but not from `from_hash`
+    fn count_commits_between(&self, from_hash: &str, to_hash: &str) -> CcResult<usize>;
+
+    /// Commits only `to_hash` reaches (`ahead`) and only `from_hash` reaches
+    /// (`behind`); the two need not be related
+    fn ahead_behind(&self, from_hash: &str, to_hash: &str) -> CcResult<AheadBehind>;
+
+    /// Move the current branch to `hash` and reset the working directory
+    ///
+    /// Untracked files are kept unless `clean` is set; ignored files are never
+    /// removed. Returns the paths that were updated, removed or left alone.
+    /// With `continue_on_error`, files that cannot be written are left as
+    /// they are and reported instead of failing the restore.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::UncommittedChanges` if the working directory
+    /// is dirty and `CheckpointError::PermissionDenied` if files cannot be
+    /// written and `continue_on_error` is not set
+    fn reset_branch_to_checkpoint(
+        &self,
+        hash: &str,
+        clean: bool,
+        continue_on_error: bool,
+    ) -> CcResult<CheckoutReport>;
+
+    /// Append a restore to the restore journal
+    ///
+    /// The discarded checkpoints stay reachable from the journal.
+    ///
+    /// # Arguments
+    /// * `target` - Full hash the CCG branch was reset to
+    /// * `discarded_tip` - Full hash of the CCG tip before the reset
+    /// * `discarded` - Number of checkpoints dropped by the reset
+    fn record_restore(&self, target: &str, discarded_tip: &str, discarded: usize) -> CcResult<()>;
+
+    /// Restores recorded in the restore journal, newest first
+    fn restore_records(&self) -> CcResult<Vec<RestoreRecord>>;
+
+    /// Append a skipped guard hook to the audit log, with the current time
+    /// and user
+    ///
+    /// # Arguments
+    /// * `hook` - Name of the skipped hook
+    /// * `target` - The checkpoint message or restore target it would have checked
+    /// * `via` - How the bypass was requested
+    ///
+    /// # Returns
+    /// The recorded entry
+    fn record_bypass(&self, hook: &str, target: &str, via: &str) -> CcResult<GuardBypass>;
+
+    /// Guard hooks skipped so far, oldest first
+    fn guard_bypasses(&self) -> CcResult<Vec<GuardBypass>>;
+
+    /// Restore the staging area left behind by a create that was interrupted
+    /// between writing the index and committing
+    ///
+    /// # Returns
+    /// The repair, or None if no create was interrupted or the create is
+    /// still running in another process
+    fn repair_interrupted_create(&self) -> CcResult<Option<IndexRepair>>;
+
+    /// Write checkpoint details (and optionally its diff) to `out`
+    ///
+    /// The diff of a root checkpoint is summarized per file unless `full`
+    /// is set.
+    fn show_checkpoint(
+        &self,
+        hash: &str,
+        show_diff: bool,
+        full: bool,
+        files: &[String],
+        out: &mut dyn Write,
+    ) -> CcResult<()>;
+
+    /// Branch HEAD is on and its tip, recorded as the base of new checkpoints
+    ///
+    /// # Returns
+    /// None when HEAD is on the CCG branch, detached or unborn
+    fn base_branch_tip(&self) -> CcResult<Option<(String, String)>>;
+
+    /// Commits added to a checkpoint's base branch since it was created
+    fn base_drift(&self, hash: &str) -> CcResult<Option<BaseDrift>>;
+
+    /// Compare the latest checkpoint with the commit HEAD points to, each
+    /// against their common ancestor
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if there is no CCG branch
+    /// and `CheckpointError::InvalidArgument` if HEAD is on it
+    fn branch_drift(&self) -> CcResult<BranchDrift>;
+
+    /// Files changed in the working directory since `hash`, untracked files
+    /// included
+    fn changes_since(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>>;
+
+    /// Files a restore to `hash` would change in the working directory,
+    /// with lines brought back (additions) and removed (deletions)
+    fn restore_preview(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>>;
+
+    /// Hunks a restore to `hash` would apply, in file then line order
+    fn restore_hunks(&self, hash: &str) -> CcResult<Vec<RestoreHunk>>;
+
+    /// Apply the hunks flagged in `selected` (one flag per hunk listed by
+    /// `restore_hunks`) to the working directory, returning how many applied
+    fn apply_restore_hunks(&self, hash: &str, selected: &[bool]) -> CcResult<usize>;
+
+    /// Raw content of `path` as it existed in a checkpoint
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` if the path is missing or a directory
+    fn checkpoint_file(&self, hash: &str, path: &str) -> CcResult<Vec<u8>>;
+
+    /// Successive versions of a file on the current branch, oldest first,
+    /// one per checkpoint that changed or deleted it
+    fn file_history(&self, path: &str) -> CcResult<Vec<FileVersion>>;
+
+    /// Write the files of a checkpoint below `dir` without touching the
+    /// working directory or the CCG branch
+    ///
+    /// # Returns
+    /// The number of files written
+    fn export_checkpoint(&self, hash: &str, dir: &Path) -> CcResult<usize>;
+
+    /// Each file changed from `from` (None for the empty tree) to `to`, with
+    /// its uncolored unified diff, in path order
+    fn file_patches(&self, from: Option<&str>, to: &str) -> CcResult<Vec<FilePatch>>;
+
+    /// Formatted diff between two checkpoints, or a checkpoint and the working directory
+    fn diff_checkpoints(&self, hash_a: &str, hash_b: Option<&str>) -> CcResult<String>;
+
+    /// Formatted diff from a checkpoint to an arbitrary directory on disk,
+    /// without writing the directory's contents to the repository
+    fn diff_directory(&self, hash: &str, dir: &Path) -> CcResult<String>;
+
+    /// Report which later checkpoints on the CCG branch touch the files and
+    /// lines changed by `hash`, and where reverting it would conflict
+    fn checkpoint_impact(&self, hash: &str) -> CcResult<ImpactReport>;
+
+    /// Create a checkpoint on the current branch that undoes the changes of
+    /// `hash`, updating the affected files in the working directory
+    ///
+    /// # Returns
+    /// The full hash of the new checkpoint
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::Conflict` if later checkpoints or uncommitted
+    /// changes touch the same content
+    fn revert_checkpoint(&self, hash: &str) -> CcResult<String>;
+
+    /// The task started with `ccg task start` and not yet ended
+    fn active_task(&self) -> CcResult<Option<Task>>;
+
+    /// Make `task` the active task, or end the active task when None
+    fn set_active_task(&self, task: Option<&Task>) -> CcResult<()>;
+
+    /// The first parent of a commit, None for a root commit
+    fn checkpoint_parent(&self, hash: &str) -> CcResult<Option<String>>;
+
+    /// Diff statistics of each checkpoint in `hashes`, in the same order
+    fn checkpoint_stats(&self, hashes: &[String]) -> CcResult<Vec<DiffStats>>;
+
+    /// Whether a checkpoint's changes only add, remove or move whitespace
+    fn is_format_only(&self, hash: &str) -> CcResult<bool>;
+
+    /// Squash the changes from `base` to `tip` into one commit on the
+    /// current branch, updating the affected files in the working directory
+    ///
+    /// The changes of the checkpoints in `skip` (newest first) are left out.
+    ///
+    /// # Returns
+    /// The full hash of the new commit
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::Conflict` if the current branch changed the
+    /// same content or a skipped checkpoint cannot be left out cleanly, and
+    /// `CheckpointError::NoChangesToCommit` if it already has the changes
+    fn squash_onto_head(
+        &self,
+        base: &str,
+        tip: &str,
+        skip: &[String],
+        message: &str,
+    ) -> CcResult<String>;
+
+    /// Change the message of a checkpoint on the CCG branch
+    ///
+    /// Later checkpoints are replayed with unchanged trees and the CCG branch
+    /// is moved to the new tip; the working directory is not touched.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::CheckpointNotFound` if the checkpoint is not
+    /// on the CCG branch
+    fn reword_checkpoint(&self, hash: &str, message: &str) -> CcResult<RewrittenHistory>;
+
+    /// Split a checkpoint on the CCG branch into one checkpoint per changed
+    /// file, replaying later checkpoints on top
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` if the checkpoint changes
+    /// fewer than two files
+    fn split_checkpoint(&self, hash: &str) -> CcResult<RewrittenHistory>;
+
+    /// Checkpoints on the CCG branch that `onto` (a branch or commit) does
+    /// not contain, newest first; empty when the branch already starts there
+    fn rebase_candidates(&self, onto: &str) -> CcResult<Vec<CheckpointEntry>>;
+
+    /// Move the checkpoints `onto` does not contain onto its head
+    ///
+    /// Checkpoints keep their trees, or with `replay` get their changes
+    /// applied on top of `onto`. `progress` is called before each checkpoint
+    /// with its position (from 1), the total and its hash.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if `onto` does not resolve
+    /// and `CheckpointError::Conflict` if a replayed checkpoint conflicts;
+    /// the CCG branch is left untouched on any error
+    fn rebase_checkpoints(
+        &self,
+        onto: &str,
+        replay: bool,
+        progress: &mut dyn FnMut(usize, usize, &str),
+    ) -> CcResult<RewrittenHistory>;
+
+    /// Drop checkpoints from the CCG branch, replaying the history after
+    /// them once and keeping the IDs of the replayed checkpoints
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::CheckpointNotFound` if a checkpoint is not on
+    /// the CCG branch and `CheckpointError::InvalidArgument` if nothing would
+    /// be left on it; the CCG branch is left untouched on any error
+    fn drop_checkpoints(&self, hashes: &[String]) -> CcResult<RewrittenHistory>;
+
+    /// Snapshots parked with `push_snapshot`, newest first
+    fn list_snapshots(&self) -> CcResult<Vec<Snapshot>>;
+
+    /// Park the working directory, untracked files included, as a snapshot
+    /// outside the checkpoint timeline and reset it to HEAD
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::NoChangesToCommit` if the working directory
+    /// matches HEAD
+    fn push_snapshot(&self, message: &str) -> CcResult<Snapshot>;
+
+    /// Merge a snapshot (the newest when `number` is None) back into the
+    /// working directory and delete it
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` if there is no such snapshot
+    /// and `CheckpointError::Conflict` if the working directory changed the
+    /// same content since the push
+    fn pop_snapshot(&self, number: Option<usize>) -> CcResult<Snapshot>;
+}
+
+impl GitBackend for GitOperations {
+    fn workdir(&self) -> Option<PathBuf> {
+        self.get_repo().workdir().map(|p| p.to_path_buf())
+    }
+
+    fn init_checkpoints(&self) -> CcResult<()> {
+        GitOperations::init_checkpoints(self)
+    }
+
+    fn get_current_branch_name(&self) -> CcResult<String> {
+        GitOperations::get_current_branch_name(self)
+    }
+
+    fn init_checkpoints_from(&self, from: &str) -> CcResult<()> {
+        GitOperations::init_checkpoints_from(self, from)
+    }
+
+    fn init_checkpoints_orphan(&self) -> CcResult<()> {
+        GitOperations::init_checkpoints_orphan(self)
+    }
+
+    fn apply_exclude_profile(&self, profile: ExcludeProfile) -> CcResult<Vec<String>> {
+        ExcludeRules::apply_profile(self.get_repo(), profile)
+    }
+
+    fn rename_branch(&self, old: &str, new: &str) -> CcResult<()> {
+        GitOperations::rename_branch(self, old, new)
+    }
+
+    fn has_ccg_branch(&self) -> CcResult<bool> {
+        GitOperations::has_ccg_branch(self)
+    }
+
+    fn ensure_ccg_branch(&self) -> CcResult<String> {
+        GitOperations::ensure_ccg_branch(self)
+    }
+
+    fn restore_original_branch(&self, original_branch: &str) -> CcResult<()> {
+        GitOperations::restore_original_branch(self, original_branch)
+    }
+
+    fn message_locale(&self) -> String {
+        self.commits().message_locale()
+    }
+
+    fn usage_limits(&self) -> UsageLimits {
+        GitOperations::usage_limits(self)
+    }
+
+    fn message_max_bytes(&self) -> usize {
+        GitOperations::message_max_bytes(self)
+    }
+
+    fn read_only(&self) -> bool {
+        GitOperations::read_only(self)
+    }
+
+    fn check_usage(&self, limits: &UsageLimits) -> CcResult<Option<UsageAdvisory>> {
+        GitOperations::check_usage(self, limits)
+    }
+
+    fn storage_usage(&self) -> CcResult<StorageUsage> {
+        GitOperations::storage_usage(self)
+    }
+
+    fn dedupe_report(&self) -> CcResult<DedupeReport> {
+        GitOperations::dedupe_report(self)
+    }
+
+    fn repack_settings(&self) -> RepackSettings {
+        GitOperations::repack_settings(self)
+    }
+
+    fn repack_large_files(&self, settings: &RepackSettings) -> CcResult<RepackReport> {
+        GitOperations::repack_large_files(self, settings)
+    }
+
+    fn last_repack(&self) -> CcResult<Option<RepackReport>> {
+        GitOperations::last_repack(self)
+    }
+
+    fn maintain_commit_graph(&self, force: bool) -> CcResult<bool> {
+        GitOperations::maintain_commit_graph(self, force)
+    }
+
+    fn fetch_checkpoints(&self, remote: &str, depth: Option<u32>) -> CcResult<FetchReport> {
+        GitOperations::fetch_checkpoints(self, remote, depth)
+    }
+
+    fn is_shallow(&self) -> bool {
+        GitOperations::is_shallow(self)
+    }
+
+    fn team_permissions(&self) -> Permissions {
+        GitOperations::team_permissions(self)
+    }
+
+    fn team_user(&self) -> String {
+        self.users().current()
+    }
+
+    fn interleaved_files(&self, edits: &[ToolEdit]) -> CcResult<Vec<String>> {
+        InterleaveCheck::new(self.get_repo()).interleaved_files(edits)
+    }
+
+    fn publish_checkpoints(&self, remote: &str) -> CcResult<String> {
+        GitOperations::publish_checkpoints(self, remote)
+    }
+
+    fn create_checkpoint(&self, message: &str) -> CcResult<String> {
+        GitOperations::create_checkpoint(self, message)
+    }
+
+    fn create_checkpoint_paths(&self, message: &str, paths: &[String]) -> CcResult<String> {
+        GitOperations::create_checkpoint_paths(self, message, paths)
+    }
+
+    fn create_empty_checkpoint(&self, message: &str) -> CcResult<String> {
+        GitOperations::create_empty_checkpoint(self, message)
+    }
+
+    fn add_note(&self, hash: &str, note: &str) -> CcResult<()> {
+        GitOperations::add_note(self, hash, note)
+    }
+
+    fn checkpoint_note(&self, hash: &str) -> CcResult<Option<String>> {
+        GitOperations::checkpoint_note(self, hash)
+    }
+
+    fn checkpoint_tags(&self) -> CcResult<HashMap<String, Vec<String>>> {
+        GitOperations::checkpoint_tags(self)
+    }
+
+    fn list_checkpoints(
+        &self,
+        limit: usize,
+        columns: &[ListColumn],
+        filter: &ListFilter,
+    ) -> CcResult<Vec<String>> {
+        GitOperations::list_checkpoints(self, limit, columns, filter)
+    }
+
+    fn checkpoint_entries(&self, limit: usize) -> CcResult<Vec<CheckpointEntry>> {
+        GitOperations::checkpoint_entries(self, limit)
+    }
+
+    fn resolve_checkpoint(&self, hash: &str) -> CcResult<String> {
+        Ok(self.find_commit(hash)?.id().to_string())
+    }
+
+    fn head_checkpoint(&self) -> CcResult<String> {
+        Ok(self.checkpoint_tip()?.id().to_string())
+    }
+
+    fn has_uncommitted_changes(&self) -> CcResult<bool> {
+        GitOperations::has_uncommitted_changes(self)
+    }
+
+    fn count_commits_between(&self, from_hash: &str, to_hash: &str) -> CcResult<usize> {
+        GitOperations::count_commits_between(self, from_hash, to_hash)
+    }
+
+    fn ahead_behind(&self, from_hash: &str, to_hash: &str) -> CcResult<AheadBehind> {
+        GitOperations::ahead_behind(self, from_hash, to_hash)
+    }
+
+    fn untracked_files(&self) -> CcResult<Vec<String>> {
+        GitOperations::untracked_files(self)
+    }
+
+    fn reset_branch_to_checkpoint(
+        &self,
+        hash: &str,
+        clean: bool,
+        continue_on_error: bool,
+    ) -> CcResult<CheckoutReport> {
+        GitOperations::reset_branch_to_checkpoint(self, hash, clean, continue_on_error)
+    }
+
+    fn show_checkpoint(
+        &self,
+        hash: &str,
+        show_diff: bool,
+        full: bool,
+        files: &[String],
+        out: &mut dyn Write,
+    ) -> CcResult<()> {
+        GitOperations::show_checkpoint(self, hash, show_diff, full, files, out)
+    }
+
+    fn base_branch_tip(&self) -> CcResult<Option<(String, String)>> {
+        GitOperations::base_branch_tip(self)
+    }
+
+    fn base_drift(&self, hash: &str) -> CcResult<Option<BaseDrift>> {
+        GitOperations::base_drift(self, hash)
+    }
+
+    fn branch_drift(&self) -> CcResult<BranchDrift> {
+        GitOperations::branch_drift(self)
+    }
+
+    fn changes_since(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>> {
+        GitOperations::changes_since(self, hash)
+    }
+
+    fn restore_preview(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>> {
+        GitOperations::restore_preview(self, hash)
+    }
+
+    fn restore_hunks(&self, hash: &str) -> CcResult<Vec<RestoreHunk>> {
+        GitOperations::restore_hunks(self, hash)
+    }
+
+    fn apply_restore_hunks(&self, hash: &str, selected: &[bool]) -> CcResult<usize> {
+        GitOperations::apply_restore_hunks(self, hash, selected)
+    }
+
+    fn checkpoint_file(&self, hash: &str, path: &str) -> CcResult<Vec<u8>> {
+        GitOperations::checkpoint_file(self, hash, path)
+    }
+
+    fn file_history(&self, path: &str) -> CcResult<Vec<FileVersion>> {
+        GitOperations::file_history(self, path)
+    }
+
+    fn export_checkpoint(&self, hash: &str, dir: &Path) -> CcResult<usize> {
+        GitOperations::export_checkpoint(self, hash, dir)
+    }
+
+    fn file_patches(&self, from: Option<&str>, to: &str) -> CcResult<Vec<FilePatch>> {
+        GitOperations::file_patches(self, from, to)
+    }
+
+    fn diff_checkpoints(&self, hash_a: &str, hash_b: Option<&str>) -> CcResult<String> {
+        GitOperations::diff_checkpoints(self, hash_a, hash_b)
+    }
+
+    fn diff_directory(&self, hash: &str, dir: &Path) -> CcResult<String> {
+        GitOperations::diff_directory(self, hash, dir)
+    }
+
+    fn checkpoint_id(&self, hash: &str) -> CcResult<Option<u64>> {
+        GitOperations::checkpoint_id(self, hash)
+    }
+
+    fn record_restore(&self, target: &str, discarded_tip: &str, discarded: usize) -> CcResult<()> {
+        GitOperations::record_restore(self, target, discarded_tip, discarded).map(|_| ())
+    }
+
+    fn restore_records(&self) -> CcResult<Vec<RestoreRecord>> {
+        GitOperations::restore_records(self)
+    }
+
+    fn record_bypass(&self, hook: &str, target: &str, via: &str) -> CcResult<GuardBypass> {
+        GitOperations::record_bypass(self, hook, target, via)
+    }
+
+    fn guard_bypasses(&self) -> CcResult<Vec<GuardBypass>> {
+        GitOperations::guard_bypasses(self)
+    }
+
+    fn repair_interrupted_create(&self) -> CcResult<Option<IndexRepair>> {
+        self.staging().repair()
+    }
+
+    fn checkpoint_impact(&self, hash: &str) -> CcResult<ImpactReport> {
+        GitOperations::checkpoint_impact(self, hash)
+    }
+
+    fn revert_checkpoint(&self, hash: &str) -> CcResult<String> {
+        GitOperations::revert_checkpoint(self, hash)
+    }
+
+    fn active_task(&self) -> CcResult<Option<Task>> {
+        GitOperations::active_task(self)
+    }
+
+    fn set_active_task(&self, task: Option<&Task>) -> CcResult<()> {
+        GitOperations::set_active_task(self, task)
+    }
+
+    fn checkpoint_parent(&self, hash: &str) -> CcResult<Option<String>> {
+        GitOperations::checkpoint_parent(self, hash)
+    }
+
+    fn checkpoint_stats(&self, hashes: &[String]) -> CcResult<Vec<DiffStats>> {
+        GitOperations::checkpoint_stats(self, hashes)
+    }
+
+    fn is_format_only(&self, hash: &str) -> CcResult<bool> {
+        GitOperations::is_format_only(self, hash)
+    }
+
+    fn squash_onto_head(
+        &self,
+        base: &str,
+        tip: &str,
+        skip: &[String],
+        message: &str,
+    ) -> CcResult<String> {
+        GitOperations::squash_onto_head(self, base, tip, skip, message)
+    }
+
+    fn reword_checkpoint(&self, hash: &str, message: &str) -> CcResult<RewrittenHistory> {
+        GitOperations::reword_checkpoint(self, hash, message)
+    }
+
+    fn split_checkpoint(&self, hash: &str) -> CcResult<RewrittenHistory> {
+        GitOperations::split_checkpoint(self, hash)
+    }
+
+    fn rebase_candidates(&self, onto: &str) -> CcResult<Vec<CheckpointEntry>> {
+        GitOperations::rebase_candidates(self, onto)
+    }
+
+    fn rebase_checkpoints(
+        &self,
+        onto: &str,
+        replay: bool,
+        progress: &mut dyn FnMut(usize, usize, &str),
+    ) -> CcResult<RewrittenHistory> {
+        GitOperations::rebase_checkpoints(self, onto, replay, progress)
+    }
+
+    fn drop_checkpoints(&self, hashes: &[String]) -> CcResult<RewrittenHistory> {
+        GitOperations::drop_checkpoints(self, hashes)
+    }
+
+    fn list_snapshots(&self) -> CcResult<Vec<Snapshot>> {
+        self.snapshots().list()
+    }
+
+    fn push_snapshot(&self, message: &str) -> CcResult<Snapshot> {
+        self.snapshots().push(message)
+    }
+
+    fn pop_snapshot(&self, number: Option<usize>) -> CcResult<Snapshot> {
+        self.snapshots().pop(number)
+    }
+}
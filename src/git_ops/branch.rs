@@ -1,9 +1,14 @@
 //! Branch management operations
 
+use super::commit::CommitOperations;
+use super::object_format::ObjectFormat;
 use super::types::CCG_BRANCH_NAME;
+use crate::config::CcgConfig;
 use crate::error::{CheckpointError, Result as CcResult};
+use crate::ui::Icon;
 use console::{Color, style};
 use git2::{Branch, Repository};
+use rust_i18n::t;
 
 /// Operations related to branch management
 pub struct BranchOperations<'a> {
@@ -37,9 +42,16 @@ impl<'a> BranchOperations<'a> {
             Err(e) => {
                 // 如果是 UnbornBranch 错误，说明仓库刚初始化，还没有提交
                 if e.code() == git2::ErrorCode::UnbornBranch {
-                    // 返回默认分支名称，通常是 main 或 master
-                    let default_branch = self
-                        .get_default_branch_name()
+                    // HEAD 仍指向尚未创建的分支，优先使用它的名称
+                    let unborn_branch = self
+                        .repo
+                        .find_reference("HEAD")
+                        .ok()
+                        .and_then(|r| r.symbolic_target().map(str::to_string))
+                        .and_then(|t| t.strip_prefix("refs/heads/").map(str::to_string));
+                    // 否则返回默认分支名称，通常是 main 或 master
+                    let default_branch = unborn_branch
+                        .or_else(|| self.get_default_branch_name())
                         .unwrap_or_else(|| "main".to_string());
                     Ok(default_branch)
                 } else {
@@ -57,6 +69,24 @@ impl<'a> BranchOperations<'a> {
             .map_err(CheckpointError::GitOperationFailed)
     }
 
+    /// Switch to a local branch, or detach HEAD if `target` is a full commit
+    /// hash that does not name a branch
+    fn switch_to_branch_or_commit(&self, target: &str) -> CcResult<()> {
+        if ObjectFormat::of(self.repo).is_full_hash(target)
+            && self
+                .repo
+                .find_branch(target, git2::BranchType::Local)
+                .is_err()
+            && let Ok(oid) = git2::Oid::from_str(target)
+        {
+            return self
+                .repo
+                .set_head_detached(oid)
+                .map_err(CheckpointError::GitOperationFailed);
+        }
+        self.switch_to_branch(target)
+    }
+
     /// Validate that a branch is in a good state
     pub fn validate_branch(&self, branch: &Branch) -> CcResult<()> {
         // 验证分支是否可以获取到引用
@@ -79,135 +109,205 @@ impl<'a> BranchOperations<'a> {
     /// Get the default branch name from Git configuration
     fn get_default_branch_name(&self) -> Option<String> {
         // 尝试从 Git 配置获取默认分支名称
-        if let Ok(config) = self.repo.config() {
-            if let Ok(branch_name) = config.get_str("init.defaultBranch") {
-                return Some(branch_name.to_string());
-            }
+        if let Ok(config) = self.repo.config()
+            && let Ok(branch_name) = config.get_str("init.defaultBranch")
+        {
+            return Some(branch_name.to_string());
         }
 
         // 如果没有配置，返回 None，调用者会使用默认值
         None
     }
 
+    /// The branch HEAD is on and its tip, unless HEAD is on the CCG branch,
+    /// detached or unborn
+    ///
+    /// # Returns
+    /// The branch name and the full hash of its tip
+    pub fn base_branch_tip(&self) -> CcResult<Option<(String, String)>> {
+        let Ok(head) = self.repo.head() else {
+            return Ok(None);
+        };
+        let Some(branch) = head.shorthand().filter(|_| head.is_branch()) else {
+            return Ok(None);
+        };
+        if branch == CCG_BRANCH_NAME {
+            return Ok(None);
+        }
+        Ok(head
+            .target()
+            .map(|oid| (branch.to_string(), oid.to_string())))
+    }
+
+    /// Check if HEAD is detached
+    pub fn is_head_detached(&self) -> CcResult<bool> {
+        match self.repo.head() {
+            Ok(head) => Ok(head.name().is_none_or(|name| name == "HEAD")),
+            Err(e) => {
+                if e.code() == git2::ErrorCode::UnbornBranch {
+                    Ok(false)
+                } else {
+                    Err(CheckpointError::GitOperationFailed(e))
+                }
+            }
+        }
+    }
+
     /// Create or get the CCG branch
     ///
-    /// This method will either find an existing CCG branch or create a new one.
-    /// It handles various edge cases including empty repositories and missing branches.
-    pub fn create_or_get_ccg_branch(&self) -> CcResult<Branch> {
+    /// Finds the existing CCG branch, or creates it from the current HEAD
+    /// without switching to it. In an empty repository an initial commit is
+    /// created first. With `ccg.orphan` set the new branch is an orphan, see
+    /// [`Self::create_orphan_ccg_branch`].
+    pub fn create_or_get_ccg_branch(&self) -> CcResult<Branch<'a>> {
         // 尝试获取已存在的分支
         if let Ok(branch) = self
             .repo
             .find_branch(CCG_BRANCH_NAME, git2::BranchType::Local)
         {
-            // 分支已存在，验证并准备
-            println!("🌿 检测到已存在的 '{CCG_BRANCH_NAME}' 分支");
-            self.ensure_ccg_branch_ready(&branch)?;
+            println!(
+                "{}检测到已存在的 '{CCG_BRANCH_NAME}' 分支",
+                Icon::Branch.prefix()
+            );
             return Ok(branch);
         }
 
-        // 检查当前分支是否就是ccg分支（可能由于某种原因find_branch没有找到）
-        let current_branch_name = self.get_current_branch_name()?;
-        if current_branch_name == CCG_BRANCH_NAME {
-            println!("🌿 当前已在 '{CCG_BRANCH_NAME}' 分支上");
-            // 尝试重新获取分支
-            if let Ok(branch) = self
-                .repo
-                .find_branch(CCG_BRANCH_NAME, git2::BranchType::Local)
-            {
-                self.ensure_ccg_branch_ready(&branch)?;
-                return Ok(branch);
-            }
-        }
-
-        // 如果分支不存在，创建新分支
-        println!("🌿 创建 '{CCG_BRANCH_NAME}' 分支...");
-
         // 首先检查是否有 HEAD 提交
         let head_commit = match self.repo.head() {
             Ok(head) => head.peel_to_commit().ok(),
             Err(_) => None,
         };
 
+        if head_commit.is_some() && CcgConfig::load(self.repo).orphan {
+            return self.create_orphan_ccg_branch();
+        }
         if let Some(commit) = head_commit {
             // 有提交，基于当前 HEAD 创建分支
             let branch = self
                 .repo
                 .branch(CCG_BRANCH_NAME, &commit, false)
                 .map_err(CheckpointError::GitOperationFailed)?;
-            println!("✅ '{CCG_BRANCH_NAME}' 分支创建成功");
-
-            // 切换到新创建的分支
-            let branch_ref = branch.get();
-            self.repo
-                .set_head(branch_ref.name().unwrap())
-                .map_err(CheckpointError::GitOperationFailed)?;
-            println!("🔄 已切换到 '{CCG_BRANCH_NAME}' 分支");
-
+            println!("{}'{CCG_BRANCH_NAME}' 分支创建成功", Icon::Success.prefix());
             Ok(branch)
         } else {
-            // 没有提交，这是一个空的仓库
-            // 检查HEAD是否已经指向ccg分支
-            let head_ref_name = match self.repo.head() {
-                Ok(head) => head.name().map(|s| s.to_string()),
-                Err(_) => None,
-            };
+            // 空仓库，先在当前分支上创建初始提交
+            println!("{}空仓库检测到，创建初始提交...", Icon::Note.prefix());
+            let commit_id = CommitOperations::new(self.repo).create_initial_commit()?;
 
-            if head_ref_name == Some(format!("refs/heads/{CCG_BRANCH_NAME}")) {
-                // HEAD已经指向ccg分支，需要创建初始提交
-                // 注意：这里我们需要调用 CommitOperations，但为了避免循环依赖，
-                // 我们将在更高层次的 GitOperations 中处理这个逻辑
-                println!(
-                    "📝 空仓库检测到，HEAD已指向 '{CCG_BRANCH_NAME}' 分支，需要创建初始提交..."
-                );
-
-                // 返回一个特殊错误，让调用者知道需要创建初始提交
-                Err(CheckpointError::GitOperationFailed(git2::Error::from_str(
-                    "Empty repository detected, initial commit needed",
-                )))
-            } else {
-                // HEAD不指向ccg分支，也需要创建初始提交
-                println!("📝 空仓库检测到，需要创建初始提交...");
-
-                // 返回一个特殊错误，让调用者知道需要创建初始提交
-                Err(CheckpointError::GitOperationFailed(git2::Error::from_str(
-                    "Empty repository detected, initial commit needed",
-                )))
+            // HEAD 未指向 ccg 时，初始提交落在其他分支上，需要再创建 ccg 分支
+            if let Ok(branch) = self
+                .repo
+                .find_branch(CCG_BRANCH_NAME, git2::BranchType::Local)
+            {
+                return Ok(branch);
             }
+            if CcgConfig::load(self.repo).orphan {
+                return self.create_orphan_ccg_branch();
+            }
+            let commit = CommitOperations::new(self.repo).find_commit(&commit_id)?;
+            self.repo
+                .branch(CCG_BRANCH_NAME, &commit, false)
+                .map_err(CheckpointError::GitOperationFailed)
         }
     }
 
-    /// Ensure CCG branch is ready for use
+    /// Create the CCG branch as an orphan that shares no history with HEAD
     ///
-    /// This method validates the branch and ensures it has at least one commit.
-    pub fn ensure_ccg_branch_ready(&self, branch: &Branch) -> CcResult<()> {
-        // 验证分支有效性
-        self.validate_branch(branch)?;
-
-        // 切换到ccg分支
-        self.switch_to_ccg_branch()?;
-        println!("🔄 已切换到 '{CCG_BRANCH_NAME}' 分支");
-
-        // 检查分支是否有提交
-        let has_commits = match self.repo.head() {
-            Ok(head) => head.peel_to_commit().is_ok(),
-            Err(_) => false,
-        };
+    /// The branch starts at a parentless commit holding HEAD's files, so
+    /// checkpoints still diff against the state they started from, while
+    /// pruning or deleting the branch can never touch objects reachable from
+    /// other branches.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` if the CCG branch already exists
+    pub fn create_orphan_ccg_branch(&self) -> CcResult<Branch<'a>> {
+        if self.has_ccg_branch()? {
+            return Err(CheckpointError::InvalidArgument(
+                t!("ccg_branch_exists", branch = CCG_BRANCH_NAME).to_string(),
+            ));
+        }
+        let root = CommitOperations::new(self.repo).create_orphan_root()?;
+        let branch = self
+            .repo
+            .branch(CCG_BRANCH_NAME, &self.repo.find_commit(root)?, false)?;
+        println!(
+            "{}{}",
+            Icon::Success.prefix(),
+            t!("ccg_branch_created_orphan", branch = CCG_BRANCH_NAME)
+        );
+        Ok(branch)
+    }
 
-        if !has_commits {
-            println!("📝 '{CCG_BRANCH_NAME}' 分支没有提交，需要创建初始提交...");
-            // 返回错误让调用者处理初始提交创建
-            return Err(CheckpointError::GitOperationFailed(git2::Error::from_str(
-                "Branch has no commits, initial commit needed",
-            )));
-        } else {
-            println!("✅ '{CCG_BRANCH_NAME}' 分支已就绪");
+    /// Create the CCG branch at the tip of another branch instead of HEAD
+    ///
+    /// # Arguments
+    /// * `from` - A local branch name, or any other revision such as a
+    ///   remote-tracking branch (`origin/main`)
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` if the CCG branch already
+    /// exists, and `CheckpointError::BranchNotFound` if `from` does not resolve
+    /// to a commit
+    pub fn create_ccg_branch_from(&self, from: &str) -> CcResult<Branch<'a>> {
+        if self.has_ccg_branch()? {
+            return Err(CheckpointError::InvalidArgument(
+                t!("ccg_branch_exists", branch = CCG_BRANCH_NAME).to_string(),
+            ));
         }
+        let commit = match self.repo.find_branch(from, git2::BranchType::Local) {
+            Ok(branch) => branch.get().peel_to_commit(),
+            Err(_) => self
+                .repo
+                .revparse_single(from)
+                .and_then(|object| object.peel_to_commit()),
+        }
+        .map_err(|_| CheckpointError::BranchNotFound(from.to_string()))?;
+
+        let branch = self.repo.branch(CCG_BRANCH_NAME, &commit, false)?;
+        println!(
+            "{}{}",
+            Icon::Success.prefix(),
+            t!(
+                "ccg_branch_created_from",
+                branch = CCG_BRANCH_NAME,
+                from = from
+            )
+        );
+        Ok(branch)
+    }
 
+    /// Rename a local branch, carrying its reflog and `branch.<name>.*` config
+    ///
+    /// Only the branch ref moves. Tags, notes under `refs/notes/ccg` and
+    /// checkpoint metadata under `.git/ccg/` are keyed by commit hash and the
+    /// restore journal lives in its own ref, so all of them carry over
+    /// unchanged.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if `old` does not exist, and
+    /// `CheckpointError::InvalidArgument` if `new` is invalid or already exists
+    pub fn rename_branch(&self, old: &str, new: &str) -> CcResult<()> {
+        let mut branch = self
+            .repo
+            .find_branch(old, git2::BranchType::Local)
+            .map_err(|_| CheckpointError::BranchNotFound(old.to_string()))?;
+        if !Branch::name_is_valid(new)? {
+            return Err(CheckpointError::InvalidArgument(
+                t!("branch_name_invalid", name = new).to_string(),
+            ));
+        }
+        if self.repo.find_branch(new, git2::BranchType::Local).is_ok() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("branch_already_exists", name = new).to_string(),
+            ));
+        }
+        // HEAD 指向被重命名的分支时 libgit2 会同步更新 HEAD
+        branch.rename(new, false)?;
         Ok(())
     }
 
     /// Get the CCG branch
-    pub fn get_ccg_branch(&self) -> CcResult<Branch> {
+    pub fn get_ccg_branch(&self) -> CcResult<Branch<'a>> {
         self.repo
             .find_branch(CCG_BRANCH_NAME, git2::BranchType::Local)
             .map_err(|e| {
@@ -219,6 +319,15 @@ impl<'a> BranchOperations<'a> {
             })
     }
 
+    /// Whether the CCG branch exists
+    pub fn has_ccg_branch(&self) -> CcResult<bool> {
+        match self.get_ccg_branch() {
+            Ok(_) => Ok(true),
+            Err(CheckpointError::BranchNotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Switch to the CCG branch
     pub fn switch_to_ccg_branch(&self) -> CcResult<()> {
         let branch = self.get_ccg_branch()?;
@@ -231,13 +340,26 @@ impl<'a> BranchOperations<'a> {
     }
 
     /// Ensure we're on the CCG branch for operations, return original branch name
+    ///
+    /// When HEAD is detached the returned value is the full hash of the
+    /// detached commit, which `restore_original_branch` re-detaches onto.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if the CCG branch does not
+    /// exist, including when HEAD already points at it but it has no commits
     pub fn ensure_ccg_branch(&self) -> CcResult<String> {
-        let current_branch = self.get_current_branch_name()?;
+        self.get_ccg_branch()?;
+        let mut current_branch = self.get_current_branch_name()?;
+        if current_branch == "HEAD"
+            && let Some(oid) = self.repo.head().ok().and_then(|h| h.target())
+        {
+            current_branch = oid.to_string();
+        }
 
         if current_branch != CCG_BRANCH_NAME {
             println!(
-                "{} {} {} {} {}",
-                style("🔄").fg(Color::Blue),
+                "{}{} {} {} {}",
+                style(Icon::Switch.prefix()).fg(Color::Blue),
                 style("切换到").fg(Color::White),
                 style(CCG_BRANCH_NAME).fg(Color::Yellow).bold(),
                 style("分支执行操作，当前分支:").fg(Color::White),
@@ -252,11 +374,10 @@ impl<'a> BranchOperations<'a> {
     /// Restore to original branch (if not CCG branch)
     pub fn restore_original_branch(&self, original_branch: &str) -> CcResult<()> {
         if original_branch != CCG_BRANCH_NAME {
-            let branch_ref = format!("refs/heads/{original_branch}");
-            if let Err(e) = self.switch_to_branch(&branch_ref) {
+            if let Err(e) = self.switch_to_branch_or_commit(original_branch) {
                 println!(
-                    "{} {} {}",
-                    style("⚠️").fg(Color::Yellow),
+                    "{}{} {}",
+                    style(Icon::Warning.prefix()).fg(Color::Yellow),
                     style("警告: 无法切回原始分支").fg(Color::Yellow),
                     style(original_branch).fg(Color::Cyan)
                 );
@@ -264,8 +385,8 @@ impl<'a> BranchOperations<'a> {
                 return Err(e);
             } else {
                 println!(
-                    "{} {} {}",
-                    style("🔄").fg(Color::Blue),
+                    "{}{} {}",
+                    style(Icon::Switch.prefix()).fg(Color::Blue),
                     style("已切回原始分支:").fg(Color::White),
                     style(original_branch).fg(Color::Cyan)
                 );
@@ -0,0 +1,182 @@
+//! Cache of rendered checkpoint diffs
+//!
+//! Checkpoints are immutable, so the formatted diff of a checkpoint (or of
+//! two checkpoints) only depends on the commits and the rendering options:
+//! file filters, layout width, icon mode, colors, UI language and
+//! `ccg.diff.ignore`. Rendered output is stored under `.git/ccg/cache/`, one
+//! file per key, and the least recently used files are evicted once the
+//! cache grows beyond `ccg.cacheSize`. Cache failures never fail a command;
+//! the diff is simply rendered again.
+
+use super::ccg_dir;
+use super::diff::DiffOperations;
+use super::types::DEFAULT_CACHE_SIZE;
+use crate::config::CcgConfig;
+use crate::error::Result as CcResult;
+use crate::ui;
+use git2::{ObjectType, Oid, Repository};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// The on-disk cache of rendered diffs
+pub struct DiffCache<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> DiffCache<'a> {
+    /// Create a new DiffCache instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    fn dir(&self) -> PathBuf {
+        ccg_dir(self.repo).join("cache")
+    }
+
+    /// Maximum total bytes of cached output (`ccg.cacheSize`, 0 disables)
+    pub fn limit(&self) -> u64 {
+        CcgConfig::load(self.repo)
+            .cache_size
+            .unwrap_or(DEFAULT_CACHE_SIZE)
+    }
+
+    /// Cache key for rendering `commits` with the current output options
+    ///
+    /// # Arguments
+    /// * `kind` - What is rendered, e.g. `show` or `diff`
+    /// * `commits` - Commit OIDs the output is computed from
+    /// * `files` - Path filters applied to the diff
+    pub fn key(&self, kind: &str, commits: &[Oid], files: &[String]) -> String {
+        let mut parts = vec![
+            env!("CARGO_PKG_VERSION").to_string(),
+            kind.to_string(),
+            commits
+                .iter()
+                .map(Oid::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            files.join("\u{0}"),
+            ui::width().to_string(),
+            format!("{:?}", ui::icon_mode()),
+            console::colors_enabled().to_string(),
+            rust_i18n::locale().to_string(),
+        ];
+        parts.push(format!(
+            "{:?}",
+            DiffOperations::new(self.repo).display_settings()
+        ));
+        parts.extend(CcgConfig::load(self.repo).diff_ignore);
+        let key = parts.join("\n");
+        // 用 git 的对象哈希把任意长度的键变成文件名
+        Oid::hash_object(ObjectType::Blob, key.as_bytes())
+            .map(|oid| oid.to_string())
+            .unwrap_or(key)
+    }
+
+    /// Cached output for `key`, marking it as recently used
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        if self.limit() == 0 {
+            return None;
+        }
+        let path = self.dir().join(key);
+        let data = fs::read(&path).ok()?;
+        // 更新修改时间作为最近使用时间
+        if let Ok(file) = File::options().append(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(data)
+    }
+
+    /// Store output for `key`, then evict least recently used entries
+    ///
+    /// Output larger than the whole cache is not stored.
+    pub fn put(&self, key: &str, data: &[u8]) -> CcResult<()> {
+        let limit = self.limit();
+        if limit == 0 || data.len() as u64 > limit {
+            return Ok(());
+        }
+        let dir = self.dir();
+        fs::create_dir_all(&dir)?;
+        // 先写临时文件再重命名，避免并发读取到不完整的内容
+        let tmp = dir.join(format!("{key}.tmp"));
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, dir.join(key))?;
+        self.evict(limit)
+    }
+
+    /// Remove least recently used entries until the cache fits in `limit`
+    fn evict(&self, limit: u64) -> CcResult<()> {
+        let mut entries: Vec<(SystemTime, u64, PathBuf)> = fs::read_dir(self.dir())?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let meta = entry.metadata().ok()?;
+                if !meta.is_file() {
+                    return None;
+                }
+                Some((meta.modified().ok()?, meta.len(), entry.path()))
+            })
+            .collect();
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        entries.sort();
+        for (_, len, path) in entries {
+            if total <= limit {
+                break;
+            }
+            fs::remove_file(path)?;
+            total -= len;
+        }
+        Ok(())
+    }
+
+    /// Remove every cached entry
+    pub fn clear(&self) -> CcResult<()> {
+        match fs::remove_dir_all(self.dir()) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write cached output for `key` to `out`, or render it and cache the result
+    ///
+    /// `render` streams to `out` as usual; a copy of what it wrote is cached
+    /// only when it succeeds.
+    pub fn write_through(
+        &self,
+        key: &str,
+        out: &mut dyn Write,
+        render: impl FnOnce(&mut dyn Write) -> CcResult<()>,
+    ) -> CcResult<()> {
+        if let Some(data) = self.get(key) {
+            out.write_all(&data)?;
+            out.flush()?;
+            return Ok(());
+        }
+        let mut tee = Tee {
+            out,
+            copy: Vec::new(),
+        };
+        render(&mut tee)?;
+        let _ = self.put(key, &tee.copy);
+        Ok(())
+    }
+}
+
+/// A writer that keeps a copy of everything written through it
+struct Tee<'w> {
+    out: &'w mut dyn Write,
+    copy: Vec<u8>,
+}
+
+impl Write for Tee<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.out.write(buf)?;
+        self.copy.extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -1,42 +1,421 @@
 //! Commit creation and management operations
 
+use super::branch::BranchOperations;
+use super::commit_graph::CommitGraph;
+use super::exclude::ExcludeRules;
+use super::ids::{CheckpointIds, format_checkpoint_id, parse_checkpoint_id, parse_latest_ref};
+use super::object_format::ObjectFormat;
+use super::paths::{display_path, literal_pathspec, path_from_bytes};
+use super::snapshots::worktree_tree;
+use super::staging::StagingGuard;
+use super::stats::StatsIndex;
+use super::status::{StatusCache, WorktreeStatus};
+use super::types::{
+    AheadBehind, BASE_BRANCH_TRAILER, BASE_COMMIT_TRAILER, BaseDrift, CCG_BRANCH_NAME,
+    CHECKPOINT_NOTES_REF, CheckoutReport, CheckpointEntry, DEFAULT_AUTHOR_EMAIL,
+    DEFAULT_AUTHOR_NAME, DEFAULT_LOCK_TIMEOUT_MS, EMPTY_CHECKPOINT_TRAILER, FileVersion,
+    ListColumn, ListFilter, MACHINE_COMMITTER_EMAIL, MACHINE_COMMITTER_NAME, Trailers,
+    VERIFY_COMMAND_TRAILER, VerifyStatus,
+};
+use super::users::UserNamespaces;
+use crate::config::{self, CcgConfig};
 use crate::error::{CheckpointError, Result as CcResult};
-use chrono::{DateTime, Utc};
-use console::{Color, style};
-use git2::{Commit, Oid, Repository, Signature, Tree};
+use crate::ui::columns::{self, Column};
+use crate::ui::{self, Icon};
+use chrono::DateTime;
+use console::{Color, Style, style};
+use git2::{Commit, ObjectType, Oid, Repository, Signature, Tree};
+use rust_i18n::t;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Width of the `  ● ` marker the service prints before each list line
+const LIST_MARKER_WIDTH: usize = 4;
+
+/// Append a `Key: value` trailer line to a commit message
+///
+/// Shorthand for [`Trailers::apply_to`] with a single trailer; joins the
+/// message's trailer block when it has one.
+pub fn append_trailer(message: &str, trailer: &str) -> String {
+    let (key, value) = trailer.split_once(':').unwrap_or((trailer, ""));
+    let mut trailers = Trailers::new();
+    trailers.push(key.trim(), value);
+    trailers.apply_to(message)
+}
+
+/// Value of the first `key: value` trailer in a commit message
+///
+/// Only the trailer block is read (see [`Trailers`]), so lines in the body
+/// that happen to look like trailers are ignored.
+pub fn trailer_value(message: &str, key: &str) -> Option<String> {
+    Trailers::parse(message).get(key).map(str::to_string)
+}
+
+/// Make text from hook payloads and tool output safe to commit
+///
+/// Removes ANSI escape sequences (colors, cursor movement, OSC titles and
+/// links) and other control characters except newlines and tabs; `\r\n`
+/// becomes `\n`.
+pub fn sanitize_message(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => match chars.next() {
+                // CSI：参数和中间字节之后以 0x40..=0x7e 结束
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\u{40}'..='\u{7e}').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC：以 BEL 或 ESC \ 结束
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{7}' {
+                            break;
+                        }
+                        if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\n' | '\t' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Split a commit message at `max_bytes`
+///
+/// Cuts at the last line break within the cap, or at the last character
+/// boundary when the first line alone is longer.
+///
+/// # Returns
+/// The kept part, and the rest when the message is longer than the cap
+pub fn cap_message(message: &str, max_bytes: usize) -> (&str, Option<&str>) {
+    if message.len() <= max_bytes {
+        return (message, None);
+    }
+    let end = message.floor_char_boundary(max_bytes);
+    let end = match message[..end].rfind('\n') {
+        Some(newline) if newline > 0 => newline + 1,
+        _ => end,
+    };
+    (&message[..end], Some(&message[end..]))
+}
+
+/// Write one exported file with the mode git recorded for it
+pub(super) fn write_entry(path: &Path, content: &[u8], mode: i32) -> CcResult<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::PermissionsExt;
+        if mode == i32::from(git2::FileMode::Link) {
+            let target = Path::new(std::ffi::OsStr::from_bytes(content));
+            std::os::unix::fs::symlink(target, path)?;
+            return Ok(());
+        }
+        fs::write(path, content)?;
+        if mode == i32::from(git2::FileMode::BlobExecutable) {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = mode;
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+/// Report checkout conflicts with uncommitted changes as `Conflict`, and
+/// writes the filesystem refused as `PermissionDenied`
+fn checkout_error(e: git2::Error) -> CheckpointError {
+    if e.code() == git2::ErrorCode::Conflict {
+        CheckpointError::Conflict(e.message().to_string())
+    } else {
+        permission_error(e)
+    }
+}
+
+/// Report an access refused by the filesystem (EACCES, EPERM, EROFS) as
+/// `PermissionDenied` with the offending path; other errors pass through
+fn permission_error(e: git2::Error) -> CheckpointError {
+    const DENIED: [&str; 3] = [
+        "Permission denied",
+        "Operation not permitted",
+        "Read-only file system",
+    ];
+    let message = e.message();
+    if e.class() != git2::ErrorClass::Os || !DENIED.iter().any(|m| message.contains(m)) {
+        return CheckpointError::GitOperationFailed(e);
+    }
+    // libgit2 把出错的路径放在单引号中
+    CheckpointError::PermissionDenied(blocked_path(&e))
+}
+
+/// The path a libgit2 error names (quoted in its message), or the message
+fn blocked_path(e: &git2::Error) -> String {
+    let message = e.message();
+    message.split('\'').nth(1).unwrap_or(message).to_string()
+}
+
+/// Whether an I/O error means the filesystem refuses writes
+fn is_write_denied(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem
+    )
+}
+
+/// Paths among `paths` (relative to `workdir`) that a checkout could not write
+///
+/// Existing files are opened for appending, which fails on read-only,
+/// immutable or foreign files without changing them; for new files the
+/// nearest existing directory must not be read-only.
+fn unwritable_paths(workdir: &Path, paths: &[PathBuf]) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .filter(|path| {
+            let full = workdir.join(path);
+            match fs::symlink_metadata(&full) {
+                Ok(meta) if meta.is_file() => fs::OpenOptions::new()
+                    .append(true)
+                    .open(&full)
+                    .is_err_and(|e| is_write_denied(&e)),
+                Ok(_) => false,
+                Err(_) => full
+                    .ancestors()
+                    .skip(1)
+                    .find_map(|dir| fs::metadata(dir).ok())
+                    .is_some_and(|meta| meta.permissions().readonly()),
+            }
+        })
+        .cloned()
+        .collect()
+}
+
+/// The history reachable from `tip` in list order, newest first, at most
+/// `limit` commits
+///
+/// Children always come before their parents; otherwise newer commits come
+/// first. Unrelated commits created in the same second (common with rapid
+/// agent edits) are ordered by hash, so `list`, `latest~N` and every other
+/// walk over the history agree on one stable order.
+pub(super) fn ordered_history(
+    repo: &Repository,
+    tip: Oid,
+    limit: usize,
+) -> CcResult<Vec<Commit<'_>>> {
+    ordered_history_from(repo, &[tip], limit)
+}
+
+/// The history reachable from any of `tips` in list order, newest first, at
+/// most `limit` commits
+///
+/// Orders like [`ordered_history`]; commits reachable from several tips
+/// appear once.
+pub(super) fn ordered_history_from<'r>(
+    repo: &'r Repository,
+    tips: &[Oid],
+    limit: usize,
+) -> CcResult<Vec<Commit<'r>>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    for tip in tips {
+        revwalk.push(*tip)?;
+    }
+
+    let mut ordered = Vec::new();
+    // 同一秒内的一组连续提交，整组读完后再排序
+    let mut run: Vec<Commit> = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if run
+            .first()
+            .is_some_and(|first| first.time().seconds() != commit.time().seconds())
+        {
+            ordered.extend(sort_same_second(std::mem::take(&mut run)));
+            if ordered.len() >= limit {
+                break;
+            }
+        }
+        run.push(commit);
+    }
+    ordered.extend(sort_same_second(run));
+    ordered.truncate(limit);
+    Ok(ordered)
+}
+
+/// The structured entry of `commit`, with its `cp-N` number from `ids`
+pub(super) fn checkpoint_entry(commit: &Commit<'_>, ids: &HashMap<String, u64>) -> CheckpointEntry {
+    let hash = commit.id().to_string();
+    CheckpointEntry {
+        message: commit.message().unwrap_or("No commit message").to_string(),
+        author: commit.author().name().unwrap_or("Unknown").to_string(),
+        time: commit.time().seconds(),
+        id: ids.get(&hash).copied(),
+        hash,
+    }
+}
+
+/// Order commits of the same second by hash without moving a parent before
+/// its child
+///
+/// In a topological order a commit adjacent to its ancestor is its child,
+/// so only swaps across a parent link have to be refused.
+fn sort_same_second(mut run: Vec<Commit<'_>>) -> Vec<Commit<'_>> {
+    for i in 1..run.len() {
+        let mut j = i;
+        while j > 0
+            && run[j].id() < run[j - 1].id()
+            && !run[j - 1].parent_ids().any(|parent| parent == run[j].id())
+        {
+            run.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+    run
+}
+
+/// Width of the change bar in `--stat` output
+const CHANGE_BAR_WIDTH: usize = 10;
+
+/// Change magnitude bar for `--stat` output, padded to `CHANGE_BAR_WIDTH`
+///
+/// The bar grows by one mark each time the changed line count doubles, so a
+/// one-line tweak shows a single mark and a checkpoint of 512 lines or more
+/// fills the bar. Marks are split between `+` and `-` in proportion to the
+/// added and deleted lines, keeping at least one mark for each side present.
+///
+/// The bar is purely visual, so it is left out in accessible mode.
+pub(super) fn change_bar(additions: i32, deletions: i32) -> String {
+    if ui::accessible() {
+        return String::new();
+    }
+    let additions = additions.max(0) as usize;
+    let deletions = deletions.max(0) as usize;
+    let total = additions + deletions;
+    let width = ((usize::BITS - total.leading_zeros()) as usize).min(CHANGE_BAR_WIDTH);
+    let mut plus = (width * additions + total / 2)
+        .checked_div(total)
+        .unwrap_or(0);
+    if width >= 2 {
+        plus = plus.clamp(
+            usize::from(additions > 0),
+            width - usize::from(deletions > 0),
+        );
+    }
+    let minus = width - plus;
+    format!(
+        "{}{}{}",
+        style("+".repeat(plus)).fg(Color::Green),
+        style("-".repeat(minus)).fg(Color::Red),
+        " ".repeat(CHANGE_BAR_WIDTH - width)
+    )
+}
+
+/// Badge for a verification status: a check mark or a cross, empty when unknown
+fn verify_badge(status: VerifyStatus) -> String {
+    let (icon, text, color) = match status {
+        VerifyStatus::Passed => (Icon::Success, "pass", Color::Green),
+        VerifyStatus::Failed => (Icon::Error, "fail", Color::Red),
+        VerifyStatus::Unknown => return String::new(),
+    };
+    // 不显示图标或无障碍模式下用文字代替
+    let badge = match icon.glyph() {
+        "" => text,
+        _ if ui::accessible() => text,
+        glyph => glyph,
+    };
+    style(badge).fg(color).bold().to_string()
+}
 
 /// Operations related to commit management
 pub struct CommitOperations<'a> {
     repo: &'a Repository,
+    /// Cache for working directory status checks, if any
+    status: Option<&'a StatusCache>,
 }
 
 impl<'a> CommitOperations<'a> {
     /// Create a new CommitOperations instance
     pub fn new(repo: &'a Repository) -> Self {
-        Self { repo }
+        Self { repo, status: None }
     }
 
-    /// Create a signature for commits
-    pub fn create_signature(&self) -> CcResult<Signature> {
-        let _now = Utc::now();
+    /// Reuse status check answers from `cache`
+    pub fn with_status_cache(mut self, cache: &'a StatusCache) -> Self {
+        self.status = Some(cache);
+        self
+    }
 
-        // 尝试获取 Git 配置中的用户信息
+    /// Create the author signature for checkpoints
+    ///
+    /// Each field is resolved independently: `ccg.authorName`/`ccg.authorEmail`
+    /// first, then `user.name`/`user.email`, then the built-in defaults.
+    pub fn create_signature(&self) -> CcResult<Signature<'static>> {
         let config = self
             .repo
             .config()
             .map_err(CheckpointError::GitOperationFailed)?;
-        let name = config
-            .get_str("user.name")
-            .unwrap_or("Claude Code Checkpoint");
-        let email = config
-            .get_str("user.email")
-            .unwrap_or("claudecode@checkpoint.local");
+        let ccg_config = CcgConfig::from_config(&config);
+
+        let name = ccg_config
+            .author_name
+            .or_else(|| config::get_string(&config, "user.name"))
+            .unwrap_or_else(|| DEFAULT_AUTHOR_NAME.to_string());
+        let email = ccg_config
+            .author_email
+            .or_else(|| config::get_string(&config, "user.email"))
+            .unwrap_or_else(|| DEFAULT_AUTHOR_EMAIL.to_string());
+
+        Signature::now(&name, &email).map_err(CheckpointError::GitOperationFailed)
+    }
 
-        Signature::now(name, email).map_err(CheckpointError::GitOperationFailed)
+    /// Create the committer signature for checkpoints
+    ///
+    /// Uses `ccg.committerName`/`ccg.committerEmail` when set. Otherwise, if
+    /// `ccg.machineCommitter` is enabled, a fixed machine identity marks the
+    /// checkpoint as generated; failing both, the author identity is reused.
+    pub fn create_committer_signature(&self) -> CcResult<Signature<'static>> {
+        let ccg_config = CcgConfig::load(self.repo);
+        let author = self.create_signature()?;
+
+        let (default_name, default_email) = if ccg_config.machine_committer {
+            (
+                MACHINE_COMMITTER_NAME.to_string(),
+                MACHINE_COMMITTER_EMAIL.to_string(),
+            )
+        } else {
+            (
+                author.name().unwrap_or(DEFAULT_AUTHOR_NAME).to_string(),
+                author.email().unwrap_or(DEFAULT_AUTHOR_EMAIL).to_string(),
+            )
+        };
+
+        let name = ccg_config.committer_name.unwrap_or(default_name);
+        let email = ccg_config.committer_email.unwrap_or(default_email);
+        Signature::now(&name, &email).map_err(CheckpointError::GitOperationFailed)
+    }
+
+    /// Language used for generated checkpoint messages (`ccg.messageLang`)
+    pub fn message_locale(&self) -> String {
+        CcgConfig::load(self.repo).message_locale()
     }
 
     /// Get the parent commit (HEAD)
-    pub fn get_parent_commit(&self) -> CcResult<Option<Commit>> {
+    pub fn get_parent_commit(&self) -> CcResult<Option<Commit<'a>>> {
         let head = self
             .repo
             .head()
@@ -46,114 +425,194 @@ impl<'a> CommitOperations<'a> {
     }
 
     /// Check if there are changes to commit
+    ///
+    /// Compares the working directory with the parent commit's tree, so
+    /// unstaged edits and untracked (non-ignored) files both count.
     pub fn has_changes_to_commit(&self) -> CcResult<bool> {
-        // 获取父提交作为比较基准
-        let parent_commit = match self.get_parent_commit()? {
-            Some(commit) => commit,
-            None => {
-                // 没有父提交（初始状态），检查是否有非忽略的文件
-                return self.has_non_ignored_files();
-            }
+        // 获取父提交作为比较基准，没有父提交时与空树比较
+        let parent_tree = match self.get_parent_commit()? {
+            Some(commit) => Some(commit.tree()?),
+            None => None,
         };
 
-        // 比较工作目录与父提交的差异
-        let parent_tree = parent_commit
-            .tree()
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        // 创建一个临时索引，包含工作目录的所有变更
-        let mut temp_index = self
-            .repo
-            .index()
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        // 清空临时索引并添加所有文件（这样可以检测到所有变更，包括新文件、修改和删除）
-        temp_index
-            .clear()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        temp_index
-            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        // 写入临时树对象
-        let temp_tree_id = temp_index
-            .write_tree()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        let temp_tree = self
-            .repo
-            .find_tree(temp_tree_id)
-            .map_err(CheckpointError::GitOperationFailed)?;
-
-        // 比较父提交的树与临时树的差异
+        let mut opts = git2::DiffOptions::new();
+        opts.include_untracked(true).recurse_untracked_dirs(true);
         let diff = self
             .repo
-            .diff_tree_to_tree(Some(&parent_tree), Some(&temp_tree), None)
+            .diff_tree_to_workdir_with_index(parent_tree.as_ref(), Some(&mut opts))
             .map_err(CheckpointError::GitOperationFailed)?;
 
-        // 检查是否有变更
         Ok(diff.deltas().len() > 0)
     }
 
     /// Check if there are non-ignored files in the working directory
     pub fn has_non_ignored_files(&self) -> CcResult<bool> {
-        // 检查工作目录中是否有非忽略的文件
-        let mut opts = git2::StatusOptions::new();
-        opts.include_untracked(true);
-        opts.include_ignored(false);
-
-        let statuses = self
-            .repo
-            .statuses(Some(&mut opts))
-            .map_err(CheckpointError::GitOperationFailed)?;
-        Ok(!statuses.is_empty())
+        WorktreeStatus::new(self.repo, self.status).has_non_ignored_files()
     }
 
-    /// Check if there are uncommitted changes
+    /// Check if tracked files have uncommitted changes
+    ///
+    /// Untracked files are not counted: restores leave them in place unless
+    /// asked to clean them. Submodules are not checked.
     pub fn has_uncommitted_changes(&self) -> CcResult<bool> {
+        WorktreeStatus::new(self.repo, self.status).has_tracked_changes()
+    }
+
+    /// List untracked files in the working directory (ignored files excluded)
+    ///
+    /// # Returns
+    /// Paths relative to the repository root for display (see
+    /// [`display_path`]), in path order
+    pub fn untracked_files(&self) -> CcResult<Vec<String>> {
+        Ok(self
+            .untracked_paths()?
+            .iter()
+            .map(|path| display_path(path))
+            .collect())
+    }
+
+    /// [`Self::untracked_files`] with the paths as git stores them
+    pub fn untracked_paths(&self) -> CcResult<Vec<PathBuf>> {
         let mut opts = git2::StatusOptions::new();
-        opts.include_untracked(true);
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+
+        let statuses = self.repo.statuses(Some(&mut opts))?;
+        let mut files: Vec<PathBuf> = statuses
+            .iter()
+            .filter(|entry| entry.status().contains(git2::Status::WT_NEW))
+            .map(|entry| path_from_bytes(entry.path_bytes()))
+            .collect();
+        files.sort();
+        Ok(files)
+    }
 
-        let statuses = self
-            .repo
-            .statuses(Some(&mut opts))
-            .map_err(CheckpointError::GitOperationFailed)?;
-        Ok(!statuses.is_empty())
+    /// Checkout options for restores: overwrite tracked files, and remove
+    /// untracked files only when `clean` is set
+    fn restore_checkout<'cb>(clean: bool) -> git2::build::CheckoutBuilder<'cb> {
+        let mut checkout_opts = git2::build::CheckoutBuilder::new();
+        checkout_opts.force(); // 强制覆盖工作目录文件
+        checkout_opts.remove_untracked(clean);
+        checkout_opts
     }
 
     /// Create a new commit (checkpoint)
     ///
+    /// The working directory is staged into a candidate tree which is
+    /// compared with the parent's tree by OID, so rewriting files with
+    /// identical content does not produce a commit.
+    ///
     /// # Arguments
     /// * `message` - The commit message
     ///
     /// # Returns
     /// The commit ID as a string
+    ///
+    /// # Errors
+    /// Returns CheckpointError::NoChangesToCommit if the candidate tree is
+    /// identical to the parent's tree
     pub fn create_commit(&self, message: &str) -> CcResult<String> {
-        // 检查是否有实际的文件变更
-        if !self.has_changes_to_commit()? {
-            return Err(CheckpointError::NoChangesToCommit);
-        }
+        self.commit_index(message, None)
+    }
+
+    /// Create a new commit from the changes to `paths` only
+    ///
+    /// The index is reset to the parent's tree before `paths` are staged,
+    /// so changes to other files stay pending for later checkpoints.
+    ///
+    /// # Arguments
+    /// * `message` - The commit message
+    /// * `paths` - Paths relative to the repository root, matched literally
+    ///
+    /// # Returns
+    /// The commit ID as a string
+    ///
+    /// # Errors
+    /// Returns CheckpointError::NoChangesToCommit if none of `paths` changed
+    pub fn create_commit_paths(&self, message: &str, paths: &[String]) -> CcResult<String> {
+        self.commit_index(message, Some(paths))
+    }
 
+    /// Stage the working directory (or only `paths`) and commit it
+    fn commit_index(&self, message: &str, paths: Option<&[String]>) -> CcResult<String> {
         let signature = self.create_signature()?;
+        let committer = self.create_committer_signature()?;
 
         // 添加文件到索引并创建树
         let mut index = self
             .repo
             .index()
             .map_err(CheckpointError::GitOperationFailed)?;
+        let parent_commit = self.get_parent_commit()?;
 
-        // 添加所有变更的文件到暂存区
-        index
-            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
-            .map_err(CheckpointError::GitOperationFailed)?;
+        // ccg.checkpoint.exclude 和 ccg.checkpoint.maxFileSize 排除的文件不暂存
+        let excludes = ExcludeRules::load(self.repo);
+        let mut filter = excludes.add_filter();
+        match paths {
+            // 添加所有变更的文件到暂存区
+            None => index
+                .add_all(
+                    ["*"].iter(),
+                    git2::IndexAddOption::DEFAULT,
+                    Some(&mut filter),
+                )
+                .map_err(permission_error)?,
+            // 从父提交的树开始，只暂存指定的文件（包括删除）
+            Some(paths) => {
+                if let Some(parent) = &parent_commit {
+                    index.read_tree(&parent.tree()?)?;
+                } else {
+                    index.clear()?;
+                }
+                // add_all 同时移除已删除的文件。libgit2 匹配时会忽略
+                // DISABLE_PATHSPEC_MATCH，`[ab].txt` 这样的文件名要先转义
+                index
+                    .add_all(
+                        paths.iter().map(|path| literal_pathspec(Path::new(path))),
+                        git2::IndexAddOption::DEFAULT,
+                        Some(&mut filter),
+                    )
+                    .map_err(permission_error)?;
+            }
+        }
 
-        // 写入索引到磁盘
-        index.write().map_err(CheckpointError::GitOperationFailed)?;
+        let tree_id = index.write_tree().map_err(permission_error)?;
 
-        let tree_id = index
-            .write_tree()
-            .map_err(CheckpointError::GitOperationFailed)?;
+        // 候选树与父提交的树相同（包括内容相同的重写），说明没有实际变更
+        let parent_tree_id = match &parent_commit {
+            Some(parent) => parent.tree_id(),
+            None => self.empty_tree_id()?,
+        };
+        if tree_id == parent_tree_id {
+            return Err(CheckpointError::NoChangesToCommit);
+        }
 
-        let parent_commit = self.get_parent_commit()?;
+        // 写入索引到磁盘；用户的 git 进程持有 index.lock 时等待其释放，
+        // 超时后不写回索引，检查点只使用内存中的索引生成的树
+        // 写入前备份原索引，进程在提交前中断时下次运行据此恢复暂存区
+        let staging = StagingGuard::new(self.repo);
+        let written = self.wait_for_index_lock() && {
+            staging.begin(parent_commit.as_ref().map(Commit::id))?;
+            match index.write() {
+                Ok(()) => true,
+                Err(e) if e.code() == git2::ErrorCode::Locked => {
+                    staging.finish()?;
+                    false
+                }
+                Err(e) => {
+                    staging.finish()?;
+                    return Err(permission_error(e));
+                }
+            }
+        };
+        if !written {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!("index_locked_in_memory")).fg(Color::Yellow)
+            );
+        }
 
         let parents: Vec<&Commit> = parent_commit.as_ref().map(|c| vec![c]).unwrap_or_default();
         let commit_id = self
@@ -161,7 +620,7 @@ impl<'a> CommitOperations<'a> {
             .commit(
                 Some("HEAD"),
                 &signature,
-                &signature,
+                &committer,
                 message,
                 &self
                     .repo
@@ -169,25 +628,147 @@ impl<'a> CommitOperations<'a> {
                     .map_err(CheckpointError::GitOperationFailed)?,
                 &parents,
             )
-            .map_err(CheckpointError::GitOperationFailed)?;
+            .map_err(permission_error);
+        let commit_id = match commit_id {
+            Ok(commit_id) => commit_id,
+            Err(e) => {
+                if written {
+                    staging.rollback()?;
+                }
+                return Err(e);
+            }
+        };
+        if written {
+            staging.finish()?;
+        }
 
-        // 提交后，确保工作区与最新提交同步
-        // 这会清理工作区，使其与刚创建的提交保持一致
-        self.reset_to_head()?;
+        // 索引已与新提交一致，无需再重置工作区（否则会覆盖提交期间的写入）
+        Ok(commit_id.to_string())
+    }
 
+    /// Wait for another process to release `.git/index.lock`
+    ///
+    /// Polls with exponential backoff (10 ms doubling up to 250 ms) for at
+    /// most `ccg.lockTimeout` milliseconds.
+    ///
+    /// # Returns
+    /// true once the lock is gone, false if it is still held at the timeout
+    fn wait_for_index_lock(&self) -> bool {
+        let lock = self.repo.path().join("index.lock");
+        let timeout = Duration::from_millis(
+            CcgConfig::load(self.repo)
+                .lock_timeout
+                .unwrap_or(DEFAULT_LOCK_TIMEOUT_MS),
+        );
+        let started = Instant::now();
+        let mut delay = Duration::from_millis(10);
+        while lock.exists() {
+            let elapsed = started.elapsed();
+            if elapsed >= timeout {
+                return false;
+            }
+            std::thread::sleep(delay.min(timeout - elapsed));
+            delay = (delay * 2).min(Duration::from_millis(250));
+        }
+        true
+    }
+
+    /// Create an empty commit on HEAD, marked with the empty-checkpoint trailer
+    ///
+    /// The commit reuses HEAD's tree and ignores the working directory.
+    ///
+    /// # Arguments
+    /// * `message` - The commit message; the trailer is appended to it
+    ///
+    /// # Returns
+    /// The commit ID as a string
+    pub fn create_empty_commit(&self, message: &str) -> CcResult<String> {
+        let head = self.get_head_commit()?;
+        let message = append_trailer(message, EMPTY_CHECKPOINT_TRAILER);
+        let commit_id = self.repo.commit(
+            Some("HEAD"),
+            &self.create_signature()?,
+            &self.create_committer_signature()?,
+            &message,
+            &head.tree()?,
+            &[&head],
+        )?;
         Ok(commit_id.to_string())
     }
 
+    /// Attach `note` to a commit under [`CHECKPOINT_NOTES_REF`], replacing
+    /// any note it already has
+    ///
+    /// # Arguments
+    /// * `hash` - Commit hash (full or partial) or `cp-N` ID
+    /// * `note` - Text of the note
+    pub fn add_note(&self, hash: &str, note: &str) -> CcResult<()> {
+        let commit = self.find_commit(hash)?;
+        let committer = self.create_committer_signature()?;
+        self.repo.note(
+            &self.create_signature()?,
+            &committer,
+            Some(CHECKPOINT_NOTES_REF),
+            commit.id(),
+            note,
+            true,
+        )?;
+        Ok(())
+    }
+
+    /// The note attached to a commit under [`CHECKPOINT_NOTES_REF`]
+    ///
+    /// # Arguments
+    /// * `hash` - Commit hash (full or partial) or `cp-N` ID
+    ///
+    /// # Returns
+    /// The text of the note, or None if the commit has none
+    pub fn note(&self, hash: &str) -> CcResult<Option<String>> {
+        let commit = self.find_commit(hash)?;
+        match self.repo.find_note(Some(CHECKPOINT_NOTES_REF), commit.id()) {
+            Ok(note) => Ok(Some(
+                String::from_utf8_lossy(note.message_bytes()).into_owned(),
+            )),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Names of the tags pointing at each commit
+    ///
+    /// # Returns
+    /// Tag names (without `refs/tags/`) keyed by the full hash of the commit
+    /// they peel to; tags of other objects are left out
+    pub fn tags(&self) -> CcResult<HashMap<String, Vec<String>>> {
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        for reference in self.repo.references_glob("refs/tags/*")? {
+            let reference = reference?;
+            let (Some(name), Ok(commit)) = (reference.shorthand(), reference.peel_to_commit())
+            else {
+                continue;
+            };
+            tags.entry(commit.id().to_string())
+                .or_default()
+                .push(name.to_string());
+        }
+        Ok(tags)
+    }
+
+    /// OID of the empty tree
+    fn empty_tree_id(&self) -> CcResult<Oid> {
+        Ok(self.repo.treebuilder(None)?.write()?)
+    }
+
     /// Create an initial commit for a new repository
     ///
     /// # Returns
     /// The commit ID as a string
     pub fn create_initial_commit(&self) -> CcResult<String> {
-        use super::types::DEFAULT_COMMIT_MESSAGE;
-
-        println!("📝 创建初始提交...");
+        println!("{}创建初始提交...", Icon::Note.prefix());
 
         let signature = self.create_signature()?;
+        let committer = self.create_committer_signature()?;
+        let message = t!("message_initial", locale = &self.message_locale());
 
         // 添加所有文件到索引
         let mut index = self
@@ -198,7 +779,7 @@ impl<'a> CommitOperations<'a> {
         // 检查是否有文件可以添加
         if !self.has_non_ignored_files()? {
             // 如果没有文件，创建一个空的初始提交
-            println!("📝 没有文件可添加，创建空的初始提交...");
+            println!("{}没有文件可添加，创建空的初始提交...", Icon::Note.prefix());
 
             // 创建空树
             let tree_id = index
@@ -215,14 +796,14 @@ impl<'a> CommitOperations<'a> {
                 .commit(
                     Some("HEAD"),
                     &signature,
-                    &signature,
-                    DEFAULT_COMMIT_MESSAGE,
+                    &committer,
+                    &message,
                     &tree,
                     &[], // 没有父提交
                 )
                 .map_err(CheckpointError::GitOperationFailed)?;
 
-            println!("✅ 初始提交创建成功: {commit_id}");
+            println!("{}初始提交创建成功: {commit_id}", Icon::Success.prefix());
             return Ok(commit_id.to_string());
         }
 
@@ -247,14 +828,14 @@ impl<'a> CommitOperations<'a> {
             .commit(
                 Some("HEAD"),
                 &signature,
-                &signature,
-                DEFAULT_COMMIT_MESSAGE,
+                &committer,
+                &message,
                 &tree,
                 &[], // 没有父提交
             )
             .map_err(CheckpointError::GitOperationFailed)?;
 
-        println!("✅ 初始提交创建成功: {commit_id}");
+        println!("{}初始提交创建成功: {commit_id}", Icon::Success.prefix());
 
         Ok(commit_id.to_string())
     }
@@ -279,42 +860,85 @@ impl<'a> CommitOperations<'a> {
         Ok(())
     }
 
-    /// Find a commit by hash (supports both full and short hashes)
+    /// Find a commit by hash (supports full and short hashes, `cp-N` IDs and
+    /// `latest~N`)
     ///
     /// # Arguments
-    /// * `hash` - Full or partial commit hash
+    /// * `hash` - Full or partial commit hash, a `cp-N` checkpoint ID, or
+    ///   `latest`/`latest~N` counted in list order on the CCG branch
     ///
     /// # Returns
     /// The found commit
-    pub fn find_commit(&self, hash: &str) -> CcResult<Commit> {
-        // 首先尝试完整的hash
-        if let Ok(oid) = Oid::from_str(hash) {
-            if let Ok(commit) = self.repo.find_commit(oid) {
-                return Ok(commit);
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::NotFetched` instead of `CheckpointNotFound`
+    /// when the history is shallow, since the checkpoint may just be older
+    /// than what was fetched
+    pub fn find_commit(&self, hash: &str) -> CcResult<Commit<'a>> {
+        match self.lookup_commit(hash) {
+            Err(CheckpointError::CheckpointNotFound(hash)) if self.repo.is_shallow() => {
+                Err(CheckpointError::NotFetched(hash))
             }
+            result => result,
         }
+    }
 
-        // 如果完整hash失败，尝试短hash查询
-        if hash.len() >= 2 && hash.len() < 40 {
-            // 遍历所有提交，查找匹配的短hash
-            let mut revwalk = self
+    fn lookup_commit(&self, hash: &str) -> CcResult<Commit<'a>> {
+        // 人类友好的检查点 ID（cp-N）
+        if let Some(number) = parse_checkpoint_id(hash) {
+            let full = CheckpointIds::new(self.repo)
+                .lookup(number)?
+                .ok_or_else(|| CheckpointError::CheckpointNotFound(hash.to_string()))?;
+            return self
                 .repo
-                .revwalk()
-                .map_err(CheckpointError::GitOperationFailed)?;
-            revwalk
-                .set_sorting(git2::Sort::TIME)
-                .map_err(CheckpointError::GitOperationFailed)?;
-            revwalk
-                .push_head()
-                .map_err(CheckpointError::GitOperationFailed)?;
+                .find_commit(Oid::from_str(&full)?)
+                .map_err(|_| CheckpointError::CheckpointNotFound(hash.to_string()));
+        }
 
-            let mut matches = Vec::new();
-            for oid_result in revwalk {
-                let oid = oid_result.map_err(CheckpointError::GitOperationFailed)?;
-                let oid_str = oid.to_string();
+        // latest~N 按 list 的顺序计数，没有 ccg 分支时使用 HEAD
+        if let Some(offset) = parse_latest_ref(hash) {
+            let tip = self.checkpoint_tip()?;
+            // 线性历史上任何遍历顺序都一致，可以直接沿 commit-graph 的父提交回溯
+            if let Some(id) = CommitGraph::load(self.repo)?
+                .and_then(|graph| graph.linear_ancestor(tip.id(), offset))
+            {
+                return Ok(self.repo.find_commit(id)?);
+            }
+            return ordered_history(self.repo, tip.id(), offset.saturating_add(1))?
+                .into_iter()
+                .nth(offset)
+                .ok_or_else(|| CheckpointError::CheckpointNotFound(hash.to_string()));
+        }
+
+        // 首先尝试完整的hash，长度取决于仓库的对象格式（SHA-1 或 SHA-256）
+        let format = ObjectFormat::of(self.repo);
+        if format.is_full_hash(hash)
+            && let Ok(oid) = Oid::from_str(hash)
+            && let Ok(commit) = self.repo.find_commit(oid)
+        {
+            return Ok(commit);
+        }
 
-                if oid_str.starts_with(hash) {
-                    matches.push(oid);
+        // 如果完整hash失败，尝试短hash查询
+        if hash.len() >= 2 && hash.len() < format.hex_len() {
+            // 按 list 的顺序遍历所有提交，查找匹配的短hash
+            let tip = self.checkpoint_tip()?;
+            let mut matches: Vec<Oid> = ordered_history(self.repo, tip.id(), usize::MAX)?
+                .iter()
+                .map(|commit| commit.id())
+                .filter(|oid| oid.to_string().starts_with(hash))
+                .collect();
+            // 自己的历史中没有时，再查找同事同步过来的命名空间
+            if matches.is_empty() {
+                for user in UserNamespaces::new(self.repo).list()? {
+                    for commit in ordered_history(self.repo, Oid::from_str(&user.tip)?, usize::MAX)?
+                    {
+                        if commit.id().to_string().starts_with(hash)
+                            && !matches.contains(&commit.id())
+                        {
+                            matches.push(commit.id());
+                        }
+                    }
                 }
             }
 
@@ -332,7 +956,8 @@ impl<'a> CommitOperations<'a> {
                     let mut error_msg = format!("短hash '{hash}' 匹配到多个提交:\n");
                     for (i, oid) in matches.iter().take(5).enumerate() {
                         if let Ok(commit) = self.repo.find_commit(*oid) {
-                            let short_hash = &oid.to_string()[..7];
+                            let full_hash = oid.to_string();
+                            let short_hash = ui::short_hash(&full_hash);
                             let message = commit
                                 .message()
                                 .unwrap_or("No message")
@@ -355,6 +980,9 @@ impl<'a> CommitOperations<'a> {
             Err(CheckpointError::InvalidHash(format!(
                 "hash太短，至少需要2个字符: {hash}"
             )))
+        } else if format.is_full_hash(hash) {
+            // 格式正确的完整hash，只是仓库中没有这个提交
+            Err(CheckpointError::CheckpointNotFound(hash.to_string()))
         } else {
             Err(CheckpointError::InvalidHash(format!(
                 "无效的hash格式: {hash}"
@@ -392,6 +1020,16 @@ impl<'a> CommitOperations<'a> {
             style(&full_hash).fg(Color::Yellow).bold()
         ));
 
+        // 检查点 ID - 品红色
+        if let Some(number) = CheckpointIds::new(self.repo).by_hash()?.get(&full_hash) {
+            result.push_str(&format!(
+                "{} {}
+",
+                style("ID:").fg(Color::White).bold(),
+                style(format_checkpoint_id(*number)).fg(Color::Magenta)
+            ));
+        }
+
         // 作者信息 - 青色
         result.push_str(&format!(
             "{} {} <{}>\n",
@@ -424,75 +1062,375 @@ impl<'a> CommitOperations<'a> {
             style(message).fg(Color::White)
         ));
 
+        // 创建时记录的验证结果
+        let status = match VerifyStatus::from_message(message) {
+            VerifyStatus::Passed => style("passed").fg(Color::Green),
+            VerifyStatus::Failed => style("failed").fg(Color::Red),
+            VerifyStatus::Unknown => style("unknown (not verified)").dim(),
+        };
+        let mut line = vec![
+            verify_badge(VerifyStatus::from_message(message)),
+            status.to_string(),
+        ];
+        if let Some(command) = trailer_value(message, VERIFY_COMMAND_TRAILER) {
+            line.push(style(format!("({command})")).fg(Color::Cyan).to_string());
+        }
+        line.retain(|part| !part.is_empty());
+        result.push_str(&format!(
+            "\n{}\n  {}\n",
+            style("Verification:").fg(Color::White).bold(),
+            line.join(" ")
+        ));
+
         Ok(result)
     }
 
-    /// List commits with formatting
+    /// Read a file's content as it existed in a commit
     ///
     /// # Arguments
-    /// * `limit` - Maximum number of commits to return
+    /// * `hash` - Commit hash (full or partial) or `cp-N` ID
+    /// * `path` - Path of the file relative to the repository root
     ///
     /// # Returns
-    /// Vector of formatted commit strings
-    pub fn list_commits(&self, limit: usize) -> CcResult<Vec<String>> {
-        let mut revwalk = self
-            .repo
-            .revwalk()
-            .map_err(CheckpointError::GitOperationFailed)?;
-        revwalk
-            .set_sorting(git2::Sort::TIME)
-            .map_err(CheckpointError::GitOperationFailed)?;
-        revwalk
-            .push_head()
-            .map_err(CheckpointError::GitOperationFailed)?;
+    /// The raw blob content
+    ///
+    /// # Errors
+    /// `InvalidArgument` if the path does not exist in the commit or is a directory
+    pub fn file_content(&self, hash: &str, path: &str) -> CcResult<Vec<u8>> {
+        let commit = self.find_commit(hash)?;
+        let full_hash = commit.id().to_string();
+        let short_hash = ui::short_hash(&full_hash);
+        let entry = commit
+            .tree()?
+            .get_path(Path::new(path.trim_start_matches("./")))
+            .map_err(|_| {
+                CheckpointError::InvalidArgument(format!(
+                    "文件 '{path}' 不存在于检查点 {short_hash} 中"
+                ))
+            })?;
+        let blob = entry
+            .to_object(self.repo)?
+            .into_blob()
+            .map_err(|_| CheckpointError::InvalidArgument(format!("'{path}' 是一个目录")))?;
+        Ok(blob.content().to_vec())
+    }
 
-        let mut commits = Vec::new();
-        for (i, oid) in revwalk.enumerate() {
-            if i >= limit {
-                break;
+    /// Write every file of a commit's tree below `dir`
+    ///
+    /// Executable files keep their mode and symlinks are recreated on Unix;
+    /// submodules are skipped. Neither the index nor the working directory
+    /// is touched.
+    ///
+    /// # Arguments
+    /// * `hash` - Commit hash (full or partial) or `cp-N` ID
+    /// * `dir` - Target directory, created if missing
+    ///
+    /// # Returns
+    /// The number of files written
+    pub fn export_tree(&self, hash: &str, dir: &Path) -> CcResult<usize> {
+        let tree = self.find_commit(hash)?.tree()?;
+        fs::create_dir_all(dir)?;
+        let mut written = 0;
+        let mut result = Ok(());
+        tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+            let Some(ObjectType::Blob) = entry.kind() else {
+                return git2::TreeWalkResult::Ok;
+            };
+            let path = dir.join(root).join(entry.name().unwrap_or_default());
+            let write = || -> CcResult<()> {
+                let blob = self.repo.find_blob(entry.id())?;
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                write_entry(&path, blob.content(), entry.filemode())
+            };
+            match write() {
+                Ok(()) => {
+                    written += 1;
+                    git2::TreeWalkResult::Ok
+                }
+                Err(e) => {
+                    result = Err(e);
+                    git2::TreeWalkResult::Abort
+                }
             }
+        })?;
+        result.map(|_| written)
+    }
 
-            let oid = oid.map_err(CheckpointError::GitOperationFailed)?;
-            let commit = self
-                .repo
-                .find_commit(oid)
-                .map_err(CheckpointError::GitOperationFailed)?;
+    /// List the most recent commits reachable from HEAD
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of commits to return
+    ///
+    /// # Returns
+    /// Commits ordered newest first
+    pub fn list_entries(&self, limit: usize) -> CcResult<Vec<CheckpointEntry>> {
+        let Ok(tip) = self.checkpoint_tip() else {
+            return Ok(Vec::new());
+        };
+        self.entries_from(tip.id(), limit)
+    }
 
-            // 获取提交的短hash（前7位）
-            let short_hash = &oid.to_string()[..7];
-
-            // 获取提交信息（第一行）
-            let message = commit
-                .message()
-                .unwrap_or("No commit message")
-                .lines()
-                .next()
-                .unwrap_or("No commit message");
-
-            // 获取提交时间
-            let time = commit.time();
-            let datetime = DateTime::from_timestamp(time.seconds(), 0)
-                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-                .unwrap_or_else(|| "Unknown time".to_string());
-
-            // 格式化输出：短hash + 时间 + 提交信息，添加颜色
-            let formatted = format!(
-                "{} {} {}",
-                style(short_hash).fg(Color::Yellow).bold(),
-                style(datetime).fg(Color::Cyan),
-                style(message).fg(Color::White)
-            );
-            commits.push(formatted);
+    /// List the history reachable from `tip` as structured entries, newest first
+    pub fn entries_from(&self, tip: Oid, limit: usize) -> CcResult<Vec<CheckpointEntry>> {
+        let ids = CheckpointIds::new(self.repo).by_hash()?;
+        Ok(ordered_history(self.repo, tip, limit)?
+            .iter()
+            .map(|commit| checkpoint_entry(commit, &ids))
+            .collect())
+    }
+
+    /// Successive versions of one file in the history reachable from HEAD
+    ///
+    /// Walks the history oldest first and keeps only the commits where the
+    /// file's blob changed, including the commit that deleted it.
+    ///
+    /// # Arguments
+    /// * `path` - File path relative to the repository root
+    ///
+    /// # Returns
+    /// Versions ordered oldest first; empty if no commit ever contained the file
+    pub fn file_history(&self, path: &str) -> CcResult<Vec<FileVersion>> {
+        let tip = self.checkpoint_tip()?;
+        let mut history = ordered_history(self.repo, tip.id(), usize::MAX)?;
+        history.reverse();
+
+        let ids = CheckpointIds::new(self.repo).by_hash()?;
+        let mut versions = Vec::new();
+        let mut previous: Option<Oid> = None;
+        for commit in history {
+            let blob_id = commit
+                .tree()?
+                .get_path(Path::new(path))
+                .ok()
+                .filter(|entry| entry.kind() == Some(git2::ObjectType::Blob))
+                .map(|entry| entry.id());
+            if blob_id == previous {
+                continue;
+            }
+            previous = blob_id;
+            let content = match blob_id {
+                Some(id) => Some(self.repo.find_blob(id)?.content().to_vec()),
+                None => None,
+            };
+            let hash = commit.id().to_string();
+            versions.push(FileVersion {
+                checkpoint: CheckpointEntry {
+                    message: commit.message().unwrap_or("No commit message").to_string(),
+                    author: commit.author().name().unwrap_or("Unknown").to_string(),
+                    time: commit.time().seconds(),
+                    id: ids.get(&hash).copied(),
+                    hash,
+                },
+                content,
+            });
         }
+        Ok(versions)
+    }
+
+    /// List commits with formatting
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of commits to return
+    /// * `columns` - Columns to show, in order; line and file counts for the
+    ///   `Stats` column are read from the statistics index and computed in
+    ///   parallel when missing
+    /// * `filter` - Which commits to list, e.g. only those carrying a label,
+    ///   whose verification passed or that change more than whitespace
+    ///
+    /// # Returns
+    /// Vector of formatted commit strings
+    pub fn list_commits(
+        &self,
+        limit: usize,
+        columns: &[ListColumn],
+        filter: &ListFilter,
+    ) -> CcResult<Vec<String>> {
+        // 团队模式下可以查看同事同步过来的命名空间
+        let user_tip = match &filter.user {
+            Some(user) => Some(UserNamespaces::new(self.repo).tip(user)?),
+            None => None,
+        };
+        let entries_for = |limit| match user_tip {
+            Some(tip) => self.entries_from(tip, limit),
+            None => self.list_entries(limit),
+        };
+        let mut entries = if filter.is_empty() {
+            entries_for(limit)?
+        } else {
+            entries_for(usize::MAX)?
+                .into_iter()
+                .filter(|e| filter.matches(e))
+                .collect()
+        };
+        // 跳过纯格式检查点需要先知道每个检查点的统计，之后才能截取
+        if !filter.skip_format_only {
+            entries.truncate(limit);
+        }
+
+        // 标签列同时显示纯格式徽章，也需要统计
+        let needs_stats = filter.skip_format_only
+            || columns.contains(&ListColumn::Stats)
+            || columns.contains(&ListColumn::Label);
+        let stats = if needs_stats {
+            let oids = entries
+                .iter()
+                .map(|e| Oid::from_str(&e.hash))
+                .collect::<Result<Vec<_>, _>>()?;
+            StatsIndex::new(self.repo).stats_for(&oids)?
+        } else {
+            Vec::new()
+        };
+        let (entries, stats): (Vec<_>, Vec<_>) = if filter.skip_format_only {
+            entries
+                .into_iter()
+                .zip(stats)
+                .filter(|(_, stat)| !stat.format_only)
+                .take(limit)
+                .unzip()
+        } else {
+            (entries, stats)
+        };
 
-        Ok(commits)
+        // 各列按终端宽度对齐，过长的提交信息被截断
+        let table = columns
+            .iter()
+            .map(|column| match column {
+                ListColumn::Hash => Column::fixed(
+                    entries
+                        .iter()
+                        .map(|e| style(e.short_hash()).fg(Color::Yellow).bold().to_string())
+                        .collect(),
+                ),
+                ListColumn::Id => Column::fixed(
+                    entries
+                        .iter()
+                        .map(|e| {
+                            let id = e.display_id().unwrap_or_default();
+                            style(id).fg(Color::Magenta).to_string()
+                        })
+                        .collect(),
+                ),
+                ListColumn::Time => Column::fixed(
+                    entries
+                        .iter()
+                        .map(|e| {
+                            let datetime = DateTime::from_timestamp(e.time, 0)
+                                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                                .unwrap_or_else(|| "Unknown time".to_string());
+                            style(datetime).fg(Color::Cyan).to_string()
+                        })
+                        .collect(),
+                ),
+                // 标签徽章单独占一列，没有标签的行用空格补齐
+                ListColumn::Label => Column::fixed(
+                    entries
+                        .iter()
+                        .enumerate()
+                        .map(|(i, e)| {
+                            let label = e.label().map(|label| {
+                                style(format!("[{label}]"))
+                                    .fg(label.color())
+                                    .bold()
+                                    .to_string()
+                            });
+                            let format_only = stats
+                                .get(i)
+                                .is_some_and(|stat| stat.format_only)
+                                .then(|| style("[format-only]").dim().to_string());
+                            let interleaved = (!e.interleaved().is_empty())
+                                .then(|| style("[interleaved]").fg(Color::Red).to_string());
+                            [label, format_only, interleaved]
+                                .into_iter()
+                                .flatten()
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        })
+                        .collect(),
+                ),
+                // 没有记录验证结果的行留空，全部为空时整列不显示
+                ListColumn::Verify => Column::fixed(
+                    entries
+                        .iter()
+                        .map(|e| verify_badge(e.verification()))
+                        .collect(),
+                ),
+                ListColumn::Message => Column::flexible(
+                    entries.iter().map(|e| e.summary().to_string()).collect(),
+                    Style::new().fg(Color::White),
+                ),
+                // 行数补齐到同一宽度，变更条在各行对齐
+                ListColumn::Stats => {
+                    let counts: Vec<(String, String)> = stats
+                        .iter()
+                        .map(|stat| {
+                            (
+                                format!("+{}", stat.additions),
+                                format!("{}{}", Icon::Minus.glyph(), stat.deletions),
+                            )
+                        })
+                        .collect();
+                    let counts_width = counts
+                        .iter()
+                        .map(|(added, deleted)| {
+                            console::measure_text_width(added)
+                                + console::measure_text_width(deleted)
+                        })
+                        .max()
+                        .unwrap_or(0);
+                    Column::fixed(
+                        stats
+                            .iter()
+                            .zip(&counts)
+                            .map(|(stat, (added, deleted))| {
+                                let files = match stat.generated_files {
+                                    0 => format!("({} files)", stat.total_files),
+                                    generated => format!(
+                                        "({} files, {generated} generated)",
+                                        stat.total_files
+                                    ),
+                                };
+                                let padding = counts_width
+                                    - console::measure_text_width(added)
+                                    - console::measure_text_width(deleted);
+                                format!(
+                                    "{} {}{} {} {}",
+                                    style(added).fg(Color::Green),
+                                    style(deleted).fg(Color::Red),
+                                    " ".repeat(padding),
+                                    change_bar(stat.additions, stat.deletions),
+                                    style(files).dim()
+                                )
+                            })
+                            .collect(),
+                    )
+                }
+                ListColumn::Author => Column::fixed(
+                    entries
+                        .iter()
+                        .map(|e| style(&e.author).fg(Color::Blue).to_string())
+                        .collect(),
+                ),
+            })
+            .collect();
+
+        Ok(columns::layout(table, LIST_MARKER_WIDTH))
     }
 
     /// Restore (checkout) to a specific commit
     ///
+    /// Tracked files in the working directory are forcibly overwritten and
+    /// HEAD is detached at the target commit. Untracked files are kept unless
+    /// `clean` is set.
+    ///
     /// # Arguments
     /// * `hash` - Commit hash to restore to
-    pub fn restore_commit(&self, hash: &str) -> CcResult<()> {
+    /// * `clean` - Also remove untracked (but not ignored) files
+    ///
+    /// # Errors
+    /// Returns CheckpointError::UncommittedChanges if the working directory is dirty
+    pub fn restore_commit(&self, hash: &str, clean: bool) -> CcResult<()> {
         let commit = self.find_commit(hash)?;
         let tree = commit.tree().map_err(CheckpointError::GitOperationFailed)?;
 
@@ -501,12 +1439,15 @@ impl<'a> CommitOperations<'a> {
             return Err(CheckpointError::UncommittedChanges);
         }
 
-        // 执行 checkout 操作
+        // 设置 checkout 选项以强制更新工作目录，未跟踪的文件默认保留
+        let mut checkout_opts = Self::restore_checkout(clean);
+
+        // 检出树到工作目录
         self.repo
-            .checkout_tree(tree.as_object(), None)
-            .map_err(CheckpointError::GitOperationFailed)?;
+            .checkout_tree(tree.as_object(), Some(&mut checkout_opts))
+            .map_err(permission_error)?;
 
-        // 更新 HEAD 指针
+        // 设置 HEAD 为分离状态指向目标提交
         self.repo
             .set_head_detached(commit.id())
             .map_err(CheckpointError::GitOperationFailed)?;
@@ -514,6 +1455,550 @@ impl<'a> CommitOperations<'a> {
         Ok(())
     }
 
+    /// Hard reset the current branch to a specific commit
+    ///
+    /// Checks the target tree out over the working directory, then moves the
+    /// branch HEAD points to and resets the index to match. Untracked files
+    /// are kept unless `clean` is set; ignored files are never removed.
+    ///
+    /// Files the checkout would have to write but cannot (read-only,
+    /// immutable or owned by another user) are checked for up front. If the
+    /// checkout still fails partway (a file locked by another program), the
+    /// working directory is rolled back to how it was before the restore.
+    ///
+    /// # Arguments
+    /// * `hash` - Commit hash to reset to
+    /// * `clean` - Also remove untracked (but not ignored) files
+    /// * `continue_on_error` - Leave unwritable files as they are and restore
+    ///   the rest instead of failing
+    ///
+    /// # Returns
+    /// The paths the checkout updated, removed, left alone or could not write
+    ///
+    /// # Errors
+    /// Returns CheckpointError::UncommittedChanges if the working directory is
+    /// dirty, CheckpointError::PermissionDenied with the unwritable paths
+    /// unless `continue_on_error` is set, and CheckpointError::RestoreRolledBack
+    /// with the blocking path if the checkout failed and was undone
+    pub fn reset_branch_to_commit(
+        &self,
+        hash: &str,
+        clean: bool,
+        continue_on_error: bool,
+    ) -> CcResult<CheckoutReport> {
+        let commit = self.find_commit(hash)?;
+
+        if self.has_uncommitted_changes()? {
+            return Err(CheckpointError::UncommittedChanges);
+        }
+
+        // 工作目录与 HEAD 一致，检出会写入的就是两棵树之间变化的路径（以及要清理的未跟踪文件）
+        let mut touched = Vec::new();
+        let head_tree = self.get_head_commit()?.tree()?;
+        let diff = self
+            .repo
+            .diff_tree_to_tree(Some(&head_tree), Some(&commit.tree()?), None)?;
+        for delta in diff.deltas() {
+            for file in [delta.old_file(), delta.new_file()] {
+                if let Some(path) = file.path() {
+                    touched.push(path.to_path_buf());
+                }
+            }
+        }
+        if clean {
+            touched.extend(self.untracked_paths()?);
+        }
+        touched.sort();
+        touched.dedup();
+        let unwritable = match self.repo.workdir() {
+            Some(workdir) => unwritable_paths(workdir, &touched),
+            None => Vec::new(),
+        };
+        if !unwritable.is_empty() && !continue_on_error {
+            let paths: Vec<String> = unwritable.iter().map(|path| display_path(path)).collect();
+            return Err(CheckpointError::PermissionDenied(paths.join(", ")));
+        }
+
+        // 获取当前分支引用
+        let head = self
+            .repo
+            .head()
+            .map_err(CheckpointError::GitOperationFailed)?;
+        let branch_name = head.shorthand().unwrap_or(CCG_BRANCH_NAME);
+
+        // 强制重置分支到目标提交
+        let mut branch = self
+            .repo
+            .find_branch(branch_name, git2::BranchType::Local)
+            .map_err(CheckpointError::GitOperationFailed)?;
+
+        // 检出中途失败时用于回滚的工作目录快照
+        let snapshot = worktree_tree(self.repo, &head_tree)?;
+
+        // 在移动分支之前检出目标树，使 HEAD 仍是比较基准；通过通知回调记录每个路径的去向
+        let mut report = CheckoutReport::default();
+        {
+            let mut checkout_opts = Self::restore_checkout(clean);
+            checkout_opts.notify_on(
+                git2::CheckoutNotificationType::UPDATED
+                    | git2::CheckoutNotificationType::UNTRACKED
+                    | git2::CheckoutNotificationType::IGNORED,
+            );
+            checkout_opts.notify(|kind, path, _, _, _| {
+                if let Some(path) = path {
+                    let path = display_path(path);
+                    if kind.contains(git2::CheckoutNotificationType::UPDATED) {
+                        report.updated.push(path);
+                    } else if kind.contains(git2::CheckoutNotificationType::UNTRACKED) && clean {
+                        report.removed.push(path);
+                    } else {
+                        report.skipped.push(path);
+                    }
+                }
+                true
+            });
+            // 跳过无法写入的文件时只检出其余路径；没有可写的路径时不检出（空路径列表表示全部）
+            let writable: Vec<&PathBuf> = touched
+                .iter()
+                .filter(|path| !unwritable.contains(path))
+                .collect();
+            if !unwritable.is_empty() {
+                for path in &writable {
+                    checkout_opts.path(literal_pathspec(path));
+                }
+            }
+            if (unwritable.is_empty() || !writable.is_empty())
+                && let Err(e) = self
+                    .repo
+                    .checkout_tree(commit.as_object(), Some(&mut checkout_opts))
+            {
+                return Err(self.rollback_checkout(snapshot, e));
+            }
+        }
+        report.unwritable = unwritable.iter().map(|path| display_path(path)).collect();
+
+        // 设置分支指向目标提交
+        let reference = branch.get_mut();
+        reference
+            .set_target(commit.id(), "Reset branch to checkpoint")
+            .map_err(CheckpointError::GitOperationFailed)?;
+
+        // 索引与目标提交完全一致（不再改动工作目录）
+        self.repo
+            .reset(commit.as_object(), git2::ResetType::Mixed, None)
+            .map_err(CheckpointError::GitOperationFailed)?;
+
+        report.updated.sort();
+        report.removed.sort();
+        report.skipped.sort();
+        Ok(report)
+    }
+
+    /// Put the working directory back to `snapshot` after a checkout failed
+    /// partway, removing the files it had already created
+    ///
+    /// # Returns
+    /// CheckpointError::RestoreRolledBack naming the path that blocked the
+    /// checkout, or the checkout error itself if the rollback failed too
+    fn rollback_checkout(&self, snapshot: Oid, error: git2::Error) -> CheckpointError {
+        let rollback = || -> CcResult<()> {
+            // 先把索引恢复为 HEAD，检出中途写入的新文件才会被视为未跟踪文件而删除
+            let head = self.get_head_commit()?;
+            self.repo
+                .reset(head.as_object(), git2::ResetType::Mixed, None)?;
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.force().remove_untracked(true).update_index(false);
+            let tree = self.repo.find_tree(snapshot)?;
+            self.repo
+                .checkout_tree(tree.as_object(), Some(&mut checkout))?;
+            Ok(())
+        };
+        match rollback() {
+            Ok(()) => CheckpointError::RestoreRolledBack(blocked_path(&error)),
+            Err(_) => permission_error(error),
+        }
+    }
+
+    /// Files that would conflict if `target` were reverted onto `onto`
+    ///
+    /// The revert is computed in memory; nothing is written.
+    pub fn revert_conflicts(&self, target: &Commit, onto: &Commit) -> CcResult<Vec<String>> {
+        let index = self.repo.revert_commit(target, onto, 0, None)?;
+        Self::conflicted_paths(&index)
+    }
+
+    /// Paths with conflict entries in an in-memory merge index
+    pub(super) fn conflicted_paths(index: &git2::Index) -> CcResult<Vec<String>> {
+        if !index.has_conflicts() {
+            return Ok(Vec::new());
+        }
+        let mut paths: Vec<String> = index
+            .conflicts()?
+            .filter_map(|conflict| {
+                let entry = conflict.ok()?;
+                let entry = entry.our.or(entry.their).or(entry.ancestor)?;
+                Some(String::from_utf8_lossy(&entry.path).to_string())
+            })
+            .collect();
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+
+    /// Create a commit on HEAD that undoes the changes of `hash`
+    ///
+    /// The inverse of the checkpoint is three-way merged onto HEAD, and the
+    /// affected files in the working directory are updated to match. Files
+    /// the revert does not touch are left alone, even if they are dirty.
+    ///
+    /// # Arguments
+    /// * `hash` - Commit hash of the checkpoint to revert
+    ///
+    /// # Returns
+    /// The full hash of the new commit
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::Conflict` if later commits changed the same
+    /// lines or a file the revert touches has uncommitted changes, and
+    /// `CheckpointError::NoChangesToCommit` if the revert changes nothing
+    pub fn revert_commit(&self, hash: &str) -> CcResult<String> {
+        let target = self.find_commit(hash)?;
+        let head = self.get_head_commit()?;
+
+        let mut index = self.repo.revert_commit(&target, &head, 0, None)?;
+        let conflicts = Self::conflicted_paths(&index)?;
+        if !conflicts.is_empty() {
+            return Err(CheckpointError::Conflict(conflicts.join(", ")));
+        }
+
+        let tree_id = index.write_tree_to(self.repo)?;
+        let head_tree = head.tree()?;
+        if tree_id == head_tree.id() {
+            return Err(CheckpointError::NoChangesToCommit);
+        }
+        let tree = self.repo.find_tree(tree_id)?;
+
+        // 只更新被撤销的文件；这些文件相对 HEAD 有未提交修改时 libgit2 会在写入前报告冲突
+        self.checkout_changed(&head_tree, &tree)?;
+
+        let message = t!(
+            "message_revert",
+            locale = &self.message_locale(),
+            summary = target.summary().unwrap_or_default(),
+            hash = target.id()
+        ) + "\n";
+        let commit_id = self.repo.commit(
+            Some("HEAD"),
+            &self.create_signature()?,
+            &self.create_committer_signature()?,
+            &message,
+            &tree,
+            &[&head],
+        )?;
+        Ok(commit_id.to_string())
+    }
+
+    /// Update the files that differ between `from` and `to` in the working
+    /// directory and index, leaving all other files alone
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::Conflict` if one of those files has
+    /// uncommitted changes that would be overwritten
+    fn checkout_changed(&self, from: &git2::Tree, to: &git2::Tree) -> CcResult<()> {
+        let diff = self.repo.diff_tree_to_tree(Some(from), Some(to), None)?;
+        let mut opts = git2::build::CheckoutBuilder::new();
+        opts.safe();
+        for delta in diff.deltas() {
+            if let Some(path) = delta.new_file().path().or(delta.old_file().path()) {
+                opts.path(literal_pathspec(path));
+            }
+        }
+        self.repo
+            .checkout_tree(to.as_object(), Some(&mut opts))
+            .map_err(checkout_error)
+    }
+
+    /// Squash the changes from `base` to `tip` into one commit on HEAD
+    ///
+    /// The changes are three-way merged onto HEAD's tree, and the affected
+    /// files in the working directory and index are updated to match; files
+    /// that already contain the changes (as they do after checkpointing the
+    /// working directory) are left as they are. Files that still hold the
+    /// changes of skipped commits are left as well, with only the index
+    /// updated, so those changes remain as uncommitted modifications.
+    ///
+    /// # Arguments
+    /// * `base` - Commit the changes start from (excluded)
+    /// * `tip` - Commit the changes end at
+    /// * `skip` - Commits between `base` and `tip`, newest first, whose own
+    ///   changes are reverted from the squash
+    /// * `message` - Message of the new commit
+    ///
+    /// # Returns
+    /// The full hash of the new commit
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::Conflict` if HEAD changed the same lines or a
+    /// skipped commit cannot be reverted cleanly, and
+    /// `CheckpointError::NoChangesToCommit` if HEAD already has the changes
+    pub fn squash_onto_head(
+        &self,
+        base: &str,
+        tip: &str,
+        skip: &[String],
+        message: &str,
+    ) -> CcResult<String> {
+        let base = self.find_commit(base)?;
+        let tip = self.find_commit(tip)?;
+        let head = self.get_head_commit()?;
+        let head_tree = head.tree()?;
+
+        // 依次从最终结果中撤销跳过的提交
+        let mut tip_tree = tip.tree()?;
+        let original_tip_tree = tip.tree()?;
+        for hash in skip {
+            let skipped = self.find_commit(hash)?;
+            let parent_tree = skipped.parent(0)?.tree()?;
+            let mut index =
+                self.repo
+                    .merge_trees(&skipped.tree()?, &tip_tree, &parent_tree, None)?;
+            let conflicts = Self::conflicted_paths(&index)?;
+            if !conflicts.is_empty() {
+                return Err(CheckpointError::Conflict(conflicts.join(", ")));
+            }
+            tip_tree = self.repo.find_tree(index.write_tree_to(self.repo)?)?;
+        }
+
+        let mut index = self
+            .repo
+            .merge_trees(&base.tree()?, &head_tree, &tip_tree, None)?;
+        let conflicts = Self::conflicted_paths(&index)?;
+        if !conflicts.is_empty() {
+            return Err(CheckpointError::Conflict(conflicts.join(", ")));
+        }
+        let tree_id = index.write_tree_to(self.repo)?;
+        if tree_id == head_tree.id() {
+            return Err(CheckpointError::NoChangesToCommit);
+        }
+        let tree = self.repo.find_tree(tree_id)?;
+
+        // 工作目录中已经是目标内容的文件（检查点来自工作目录）只更新索引，其余文件才检出
+        let workdir = self
+            .repo
+            .workdir()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let diff = self
+            .repo
+            .diff_tree_to_tree(Some(&head_tree), Some(&tree), None)?;
+        let mut index = self.repo.index()?;
+        let mut checkout = git2::build::CheckoutBuilder::new();
+        checkout.safe();
+        let mut pending = false;
+        for delta in diff.deltas() {
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let file = workdir.join(path);
+            let on_disk = Oid::hash_file(ObjectType::Blob, &file).ok();
+            let in_place = if delta.new_file().exists() {
+                on_disk == Some(delta.new_file().id())
+            } else {
+                !file.exists()
+            };
+            // 跳过的变更仍留在工作目录中：只把提交的内容写入索引，工作目录保持不变
+            let kept_on_disk = !skip.is_empty()
+                && delta.new_file().exists()
+                && on_disk.is_some()
+                && on_disk == original_tip_tree.get_path(path).ok().map(|e| e.id());
+            match (in_place, delta.new_file().exists()) {
+                (true, true) => index.add_path(path)?,
+                (true, false) => index.remove_path(path)?,
+                (false, _) if kept_on_disk => {
+                    let path_bytes = delta.new_file().path_bytes().unwrap_or_default().to_vec();
+                    index.add(&git2::IndexEntry {
+                        ctime: git2::IndexTime::new(0, 0),
+                        mtime: git2::IndexTime::new(0, 0),
+                        dev: 0,
+                        ino: 0,
+                        mode: u32::from(delta.new_file().mode()),
+                        uid: 0,
+                        gid: 0,
+                        file_size: 0,
+                        id: delta.new_file().id(),
+                        flags: path_bytes.len().min(0xfff) as u16,
+                        flags_extended: 0,
+                        path: path_bytes,
+                    })?;
+                }
+                (false, _) => {
+                    checkout.path(literal_pathspec(path));
+                    pending = true;
+                }
+            }
+        }
+        index.write()?;
+        if pending {
+            self.repo
+                .checkout_tree(tree.as_object(), Some(&mut checkout))
+                .map_err(checkout_error)?;
+        }
+
+        let commit_id = self.repo.commit(
+            Some("HEAD"),
+            &self.create_signature()?,
+            &self.create_committer_signature()?,
+            message,
+            &tree,
+            &[&head],
+        )?;
+        Ok(commit_id.to_string())
+    }
+
+    /// Get the commit HEAD points to
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if HEAD is unborn
+    pub fn get_head_commit(&self) -> CcResult<Commit<'a>> {
+        let head = self
+            .repo
+            .head()
+            .map_err(CheckpointError::GitOperationFailed)?;
+        head.peel_to_commit()
+            .map_err(CheckpointError::GitOperationFailed)
+    }
+
+    /// Get the latest checkpoint: the tip of the CCG branch, or the commit
+    /// HEAD points to when there is no CCG branch
+    ///
+    /// Reading the checkpoint history goes through here, so it does not
+    /// need HEAD switched to the CCG branch.
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if neither has a commit
+    pub fn checkpoint_tip(&self) -> CcResult<Commit<'a>> {
+        match BranchOperations::new(self.repo).get_ccg_branch() {
+            Ok(branch) => branch
+                .get()
+                .peel_to_commit()
+                .map_err(CheckpointError::GitOperationFailed),
+            Err(_) => self.get_head_commit(),
+        }
+    }
+
+    /// Count the commits reachable from `to_hash` but not from `from_hash`
+    ///
+    /// # Arguments
+    /// * `from_hash` - Exclusive lower bound
+    /// * `to_hash` - Inclusive upper bound
+    pub fn count_commits_between(&self, from_hash: &str, to_hash: &str) -> CcResult<usize> {
+        Ok(self.ahead_behind(from_hash, to_hash)?.ahead)
+    }
+
+    /// Count the commits on each side of two commits, like
+    /// `git rev-list --left-right --count from...to`
+    ///
+    /// The commits need not be ancestors of each other: after a hard restore
+    /// rewrote the branch, an old tip and the new one share only part of
+    /// their history, and commits of unrelated histories share none. Uses the
+    /// generation numbers of the commit-graph file when both commits are in
+    /// it.
+    ///
+    /// # Returns
+    /// `ahead`: commits only `to_hash` reaches; `behind`: commits only
+    /// `from_hash` reaches
+    pub fn ahead_behind(&self, from_hash: &str, to_hash: &str) -> CcResult<AheadBehind> {
+        let from = self.find_commit(from_hash)?.id();
+        let to = self.find_commit(to_hash)?.id();
+        if let Some(counts) =
+            CommitGraph::load(self.repo)?.and_then(|graph| graph.ahead_behind(from, to))
+        {
+            return Ok(counts);
+        }
+
+        let (ahead, behind) = self
+            .repo
+            .graph_ahead_behind(to, from)
+            .map_err(CheckpointError::GitOperationFailed)?;
+        Ok(AheadBehind { ahead, behind })
+    }
+
+    /// Create a parentless commit with the tree of HEAD, the root of an
+    /// orphan CCG branch
+    ///
+    /// No reference is updated. The orphan branch shares no history with the
+    /// branch it starts from, so that branch and its tip are recorded as base
+    /// trailers instead.
+    ///
+    /// # Returns
+    /// The id of the new commit; its tree is empty when HEAD is unborn
+    pub fn create_orphan_root(&self) -> CcResult<Oid> {
+        let tree = match self.repo.head().and_then(|head| head.peel_to_tree()) {
+            Ok(tree) => tree,
+            Err(_) => {
+                let tree_id = self.repo.treebuilder(None)?.write()?;
+                self.repo.find_tree(tree_id)?
+            }
+        };
+        let mut message = t!("message_orphan_root", locale = &self.message_locale()).to_string();
+        if let Some((branch, tip)) = BranchOperations::new(self.repo).base_branch_tip()? {
+            message = Trailers::new()
+                .push(BASE_BRANCH_TRAILER, &branch)
+                .push(BASE_COMMIT_TRAILER, &tip)
+                .apply_to(&message);
+        }
+        let signature = self.create_signature()?;
+        let committer = self.create_committer_signature()?;
+        Ok(self
+            .repo
+            .commit(None, &signature, &committer, &message, &tree, &[])?)
+    }
+
+    /// Commits added to a checkpoint's base branch since it was created
+    ///
+    /// Reads the base trailers recorded at creation time and counts the
+    /// commits on that branch which the recorded base does not contain.
+    ///
+    /// # Arguments
+    /// * `hash` - Commit hash (full or partial) or `cp-N` ID
+    ///
+    /// # Returns
+    /// None if the checkpoint has no base, the branch is gone, or it has not
+    /// moved past the base
+    pub fn base_drift(&self, hash: &str) -> CcResult<Option<BaseDrift>> {
+        let commit = self.find_commit(hash)?;
+        let trailers = Trailers::parse(commit.message().unwrap_or_default());
+        let (Some(branch), Some(base)) = (
+            trailers.get(BASE_BRANCH_TRAILER).map(str::to_string),
+            trailers.base_commit().map(str::to_string),
+        ) else {
+            return Ok(None);
+        };
+        let Ok(tip) = self
+            .repo
+            .find_branch(&branch, git2::BranchType::Local)
+            .and_then(|b| b.get().peel_to_commit())
+        else {
+            return Ok(None);
+        };
+        let Ok(base_oid) = Oid::from_str(&base) else {
+            return Ok(None);
+        };
+        if self.repo.find_commit(base_oid).is_err() {
+            return Ok(None);
+        }
+
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(tip.id())?;
+        revwalk.hide(base_oid)?;
+        let commits = revwalk.count();
+        Ok((commits > 0).then_some(BaseDrift {
+            branch,
+            base,
+            commits,
+        }))
+    }
+
     /// Checkout a tree to the working directory
     ///
     /// # Arguments
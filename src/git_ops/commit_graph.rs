@@ -0,0 +1,302 @@
+//! Commit-graph files for faster walks over long checkpoint histories
+//!
+//! Git can store the parents and generation numbers of all reachable commits
+//! in `objects/info/commit-graph`, so walking the history does not have to
+//! inflate every commit object. With `ccg.commitGraph` enabled the file is
+//! written by `git commit-graph write` after prune and repack, and after a
+//! checkpoint once enough commits are missing from it.
+//!
+//! Reading does not depend on the setting: whenever a single-file graph is
+//! present (git gc writes one too), counting commits between two hashes and
+//! resolving `latest~N` use it for the commits it covers. A graph is closed
+//! under parents, so a commit that is in it brings all its ancestors along;
+//! commits created after the last write fall back to a regular revwalk.
+
+use super::types::AheadBehind;
+use crate::error::{CheckpointError, Result as CcResult};
+use git2::{Oid, Repository};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Rewrite the graph after a checkpoint once this many commits reachable from
+/// the CCG branch are missing from it
+pub const COMMIT_GRAPH_REFRESH: usize = 50;
+
+/// Parent value of a commit without this parent
+const PARENT_NONE: u32 = 0x7000_0000;
+/// Set on the second parent when the parents continue in the EDGE chunk, and
+/// on the last entry of such a list
+const PARENT_EXTRA: u32 = 0x8000_0000;
+const HASH_LEN: usize = 20;
+
+/// The commits of a commit-graph file with their parents and generation
+/// numbers
+///
+/// Generation numbers are topological levels: a root has generation 1 and
+/// every other commit is one higher than its highest parent.
+#[derive(Debug, Clone)]
+pub struct CommitGraph {
+    ids: Vec<Oid>,
+    positions: HashMap<Oid, u32>,
+    parents: Vec<Vec<u32>>,
+    generations: Vec<u32>,
+}
+
+impl CommitGraph {
+    fn path(repo: &Repository) -> PathBuf {
+        repo.path()
+            .join("objects")
+            .join("info")
+            .join("commit-graph")
+    }
+
+    /// Read the commit-graph file of `repo`
+    ///
+    /// # Returns
+    /// None when there is no single-file graph, or when it cannot be used:
+    /// split graph chains, SHA-256 graphs and files without generation numbers
+    /// are left to git
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the file exists but cannot be read
+    pub fn load(repo: &Repository) -> CcResult<Option<Self>> {
+        let path = Self::path(repo);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Self::parse(&fs::read(path)?))
+    }
+
+    fn parse(data: &[u8]) -> Option<Self> {
+        // 文件头：签名、版本、哈希版本、块数量、基础图数量
+        if data.get(..4)? != b"CGPH" || data[4] != 1 || data[5] != 1 || data[7] != 0 {
+            return None;
+        }
+        let chunk_count = data[6] as usize;
+        let mut chunks = HashMap::new();
+        for i in 0..chunk_count {
+            let entry = data.get(8 + i * 12..20 + i * 12)?;
+            let next = data.get(20 + i * 12..32 + i * 12)?;
+            let id: [u8; 4] = entry[..4].try_into().ok()?;
+            let start = u64::from_be_bytes(entry[4..].try_into().ok()?) as usize;
+            let end = u64::from_be_bytes(next[4..].try_into().ok()?) as usize;
+            chunks.insert(id, data.get(start..end)?);
+        }
+
+        let fanout = chunks.get(b"OIDF")?;
+        let count = read_u32(fanout, 255)? as usize;
+        let lookup = chunks.get(b"OIDL")?;
+        let commits = chunks.get(b"CDAT")?;
+        let edges = chunks.get(b"EDGE").copied().unwrap_or_default();
+        if lookup.len() < count * HASH_LEN || commits.len() < count * (HASH_LEN + 16) {
+            return None;
+        }
+
+        let mut graph = CommitGraph {
+            ids: Vec::with_capacity(count),
+            positions: HashMap::with_capacity(count),
+            parents: Vec::with_capacity(count),
+            generations: Vec::with_capacity(count),
+        };
+        for i in 0..count {
+            let id = Oid::from_bytes(&lookup[i * HASH_LEN..(i + 1) * HASH_LEN]).ok()?;
+            graph.positions.insert(id, i as u32);
+            graph.ids.push(id);
+
+            // 提交数据：树、两个父提交位置、生成号（高 30 位）与提交时间
+            let record = &commits[i * (HASH_LEN + 16) + HASH_LEN..(i + 1) * (HASH_LEN + 16)];
+            let mut parents = Vec::new();
+            let first = read_u32(record, 0)?;
+            if first != PARENT_NONE {
+                parents.push(first);
+            }
+            let second = read_u32(record, 1)?;
+            if second & PARENT_EXTRA != 0 {
+                // 章鱼合并：其余父提交在 EDGE 块中，最后一项带有最高位
+                let mut edge = (second & !PARENT_EXTRA) as usize;
+                loop {
+                    let value = read_u32(edges, edge)?;
+                    parents.push(value & !PARENT_EXTRA);
+                    if value & PARENT_EXTRA != 0 {
+                        break;
+                    }
+                    edge += 1;
+                }
+            } else if second != PARENT_NONE {
+                parents.push(second);
+            }
+            if parents.iter().any(|&parent| parent as usize >= count) {
+                return None;
+            }
+            graph.parents.push(parents);
+
+            let generation = read_u32(record, 2)? >> 2;
+            // 旧版 git 写入的生成号为 0，无法用于剪枝
+            if generation == 0 {
+                return None;
+            }
+            graph.generations.push(generation);
+        }
+        Some(graph)
+    }
+
+    /// Write a commit-graph of all reachable commits with `git commit-graph`
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if git cannot be started and
+    /// CheckpointError::GitOperationFailed with git's message if it fails
+    pub fn write(repo: &Repository) -> CcResult<()> {
+        let output = Command::new("git")
+            .arg("--git-dir")
+            .arg(repo.path())
+            .args(["commit-graph", "write", "--reachable", "--no-progress"])
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CheckpointError::GitOperationFailed(git2::Error::from_str(
+                stderr.trim(),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Number of commits in the graph
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Whether the graph has no commits
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Whether `id` is in the graph
+    pub fn contains(&self, id: Oid) -> bool {
+        self.positions.contains_key(&id)
+    }
+
+    /// Generation number of `id`, if it is in the graph
+    pub fn generation(&self, id: Oid) -> Option<u32> {
+        self.positions
+            .get(&id)
+            .map(|&pos| self.generations[pos as usize])
+    }
+
+    /// Commits reachable from `tip` that are not in the graph, counting at
+    /// most `limit`
+    pub fn missing_from(&self, repo: &Repository, tip: Oid, limit: usize) -> CcResult<usize> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![tip];
+        while let Some(id) = stack.pop() {
+            if seen.len() >= limit {
+                break;
+            }
+            if self.contains(id) || !seen.insert(id) {
+                continue;
+            }
+            stack.extend(repo.find_commit(id)?.parent_ids());
+        }
+        Ok(seen.len())
+    }
+
+    /// Count the commits on each side of `from` and `to`, like
+    /// `git rev-list --left-right --count from...to`
+    ///
+    /// The commits need not be related. Commits are visited from the highest
+    /// generation down, so a commit's children are all settled before it; the
+    /// walk stops as soon as every commit left to visit is reachable from
+    /// both sides.
+    ///
+    /// # Returns
+    /// None if either commit is not in the graph
+    pub fn ahead_behind(&self, from: Oid, to: Oid) -> Option<AheadBehind> {
+        let from = *self.positions.get(&from)?;
+        let to = *self.positions.get(&to)?;
+
+        let mut walk = Walk::default();
+        walk.mark(self, to, TO);
+        walk.mark(self, from, FROM);
+
+        let mut counts = AheadBehind::default();
+        // 队列中只剩两侧都能到达的提交时，结果已经确定
+        while walk.pending > 0 {
+            let Some((_, pos)) = walk.queue.pop() else {
+                break;
+            };
+            let flag = walk.flags[&pos];
+            match flag {
+                TO => counts.ahead += 1,
+                FROM => counts.behind += 1,
+                _ => {}
+            }
+            if flag != TO | FROM {
+                walk.pending -= 1;
+            }
+            for &parent in &self.parents[pos as usize] {
+                walk.mark(self, parent, flag);
+            }
+        }
+        Some(counts)
+    }
+
+    /// The commit `offset` first parents before `tip`, if the history up to
+    /// it is in the graph and linear
+    ///
+    /// On a linear history every walk order agrees, so this is also the
+    /// commit at `offset` in list order. Merges return None and leave the
+    /// ordering to a revwalk.
+    pub fn linear_ancestor(&self, tip: Oid, offset: usize) -> Option<Oid> {
+        let mut pos = *self.positions.get(&tip)?;
+        for _ in 0..offset {
+            match self.parents[pos as usize].as_slice() {
+                [parent] => pos = *parent,
+                _ => return None,
+            }
+        }
+        Some(self.ids[pos as usize])
+    }
+}
+
+/// Reachable from the `to` side of [`CommitGraph::ahead_behind`]
+const TO: u8 = 1;
+/// Reachable from the `from` side of [`CommitGraph::ahead_behind`]
+const FROM: u8 = 2;
+
+/// State of a walk over the graph in generation order
+#[derive(Default)]
+struct Walk {
+    /// Which sides reach each visited commit
+    flags: HashMap<u32, u8>,
+    /// Commits to visit, highest generation first
+    queue: BinaryHeap<(u32, u32)>,
+    /// Queued commits reachable from only one side
+    pending: usize,
+}
+
+impl Walk {
+    fn mark(&mut self, graph: &CommitGraph, pos: u32, flag: u8) {
+        let old = self.flags.get(&pos).copied().unwrap_or(0);
+        let new = old | flag;
+        if old == new {
+            return;
+        }
+        self.flags.insert(pos, new);
+        if old == 0 {
+            self.queue.push((graph.generations[pos as usize], pos));
+        }
+        // 单侧可达的提交变为两侧可达时不再计入
+        if old == 0 {
+            self.pending += 1;
+        } else if new == TO | FROM {
+            self.pending -= 1;
+        }
+    }
+}
+
+/// The `index`-th big-endian u32 of `data`
+fn read_u32(data: &[u8], index: usize) -> Option<u32> {
+    let bytes = data.get(index * 4..index * 4 + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
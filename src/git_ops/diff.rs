@@ -3,11 +3,305 @@
 //! This module handles all diff-related operations including generating diffs,
 //! formatting diff output, and calculating diff statistics.
 
+use crate::config::{CcgConfig, DiffAlgorithm};
 use crate::error::{CheckpointError, Result as CcResult};
-use crate::git_ops::types::{DiffStats, FileChangeInfo};
-use console::{Color, style};
-use git2::{Commit, Diff, Repository};
-use std::collections::HashMap;
+use crate::git_ops::cache::DiffCache;
+use crate::git_ops::commit::{CommitOperations, change_bar};
+use crate::git_ops::paths::display_path;
+use crate::git_ops::repository::RepositoryOperations;
+use crate::git_ops::rewrite::RewriteOperations;
+use crate::git_ops::types::{
+    BranchDrift, CCG_BRANCH_NAME, CheckpointOverlap, DiffStats, FileChangeInfo, FilePatch,
+    ImpactReport, RestoreHunk,
+};
+use crate::ui::{self, Icon};
+use console::{Color, measure_text_width, style};
+use git2::{
+    Commit, Delta, Diff, DiffDelta, DiffFindOptions, DiffOptions, FileMode, Oid, Patch, Pathspec,
+    PathspecFlags, Repository, Tree,
+};
+use rust_i18n::t;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Options for the diffs `show` and `diff` display
+///
+/// Unset fields fall back to the `ccg.diff.*` configuration, and then to
+/// git's defaults: Myers, no extra inter-hunk context, no rename detection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffSettings {
+    pub algorithm: Option<DiffAlgorithm>,
+    /// Merge hunks separated by at most this many unchanged lines
+    pub inter_hunk_context: Option<u32>,
+    /// Show a deletion and an addition at least this similar (in percent) as
+    /// a rename; 0 turns detection off
+    pub rename_threshold: Option<u16>,
+    /// Show blocks of lines moved elsewhere in the diff in their own colors
+    pub color_moved: Option<bool>,
+}
+
+/// Settings given on the command line, taking precedence over the config
+static OVERRIDES: RwLock<DiffSettings> = RwLock::new(DiffSettings {
+    algorithm: None,
+    inter_hunk_context: None,
+    rename_threshold: None,
+    color_moved: None,
+});
+
+/// Override the configured diff settings for this process
+///
+/// Used for command-line flags such as `--diff-algorithm`; fields left unset
+/// keep their configured values.
+pub fn set_diff_settings(settings: DiffSettings) {
+    if let Ok(mut overrides) = OVERRIDES.write() {
+        *overrides = settings;
+    }
+}
+
+impl DiffSettings {
+    /// Settings from `ccg.diff.algorithm`, `ccg.diff.interHunkContext`,
+    /// `ccg.diff.renames` and `ccg.diff.colorMoved`
+    pub fn from_config(config: &CcgConfig) -> Self {
+        DiffSettings {
+            algorithm: config.diff_algorithm,
+            inter_hunk_context: config
+                .diff_inter_hunk_context
+                .map(|lines| lines.min(u32::MAX as u64) as u32),
+            rename_threshold: config.diff_renames.map(|percent| percent.min(100) as u16),
+            color_moved: config.diff_color_moved,
+        }
+    }
+
+    /// These settings, with unset fields taken from `fallback`
+    pub fn or(self, fallback: Self) -> Self {
+        DiffSettings {
+            algorithm: self.algorithm.or(fallback.algorithm),
+            inter_hunk_context: self.inter_hunk_context.or(fallback.inter_hunk_context),
+            rename_threshold: self.rename_threshold.or(fallback.rename_threshold),
+            color_moved: self.color_moved.or(fallback.color_moved),
+        }
+    }
+
+    /// Apply the algorithm and inter-hunk context to `opts`
+    pub fn apply(&self, opts: &mut DiffOptions) {
+        match self.algorithm.unwrap_or_default() {
+            DiffAlgorithm::Myers => {}
+            DiffAlgorithm::Minimal => {
+                opts.minimal(true);
+            }
+            DiffAlgorithm::Patience | DiffAlgorithm::Histogram => {
+                opts.patience(true);
+            }
+        }
+        if let Some(lines) = self.inter_hunk_context {
+            opts.interhunk_lines(lines);
+        }
+    }
+
+    /// Detect renames in `diff` when a threshold is set
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if similarity detection fails
+    pub fn find_renames(&self, diff: &mut Diff) -> CcResult<()> {
+        if let Some(threshold) = self.rename_threshold.filter(|percent| *percent > 0) {
+            let mut find = DiffFindOptions::new();
+            find.renames(true).rename_threshold(threshold);
+            diff.find_similar(Some(&mut find))?;
+        }
+        Ok(())
+    }
+}
+
+/// Minimum number of consecutive non-blank lines for a block to count as moved
+const MOVED_BLOCK_LINES: usize = 3;
+
+/// Lines of a diff that belong to moved blocks
+///
+/// Keyed by delta index; each entry holds `('-', old_lineno)` for lines moved
+/// away and `('+', new_lineno)` for lines moved in.
+type MovedLines = HashMap<usize, HashSet<(char, u32)>>;
+
+/// Formats diffs for display
+///
+/// Holds everything the formatter reads from the repository configuration,
+/// so any diff can be formatted, including one parsed from patch text with
+/// [`Diff::from_buffer`]. [`DiffOperations::formatter`] builds one from the
+/// repository's `ccg.diff.*` settings.
+pub struct DiffFormatter {
+    /// Generated files, shown as a single summary line
+    generated: Option<Pathspec>,
+    /// Color blocks of lines moved elsewhere in the diff
+    color_moved: bool,
+}
+
+impl DiffFormatter {
+    /// Create a formatter
+    ///
+    /// # Arguments
+    /// * `generated` - Pathspecs of generated files, as in `ccg.diff.ignore`
+    /// * `color_moved` - Whether to detect and color moved blocks
+    pub fn new(generated: &[String], color_moved: bool) -> Self {
+        let generated = if generated.is_empty() {
+            None
+        } else {
+            Pathspec::new(generated.iter()).ok()
+        };
+        Self {
+            generated,
+            color_moved,
+        }
+    }
+
+    /// Format a diff into a human-readable string
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if a patch cannot be generated
+    pub fn format(&self, diff: &Diff) -> CcResult<String> {
+        let mut buffer = Vec::new();
+        self.write(diff, &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+
+    /// Stream a formatted diff to a writer, one file section at a time
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if a patch cannot be generated,
+    /// or CheckpointError::IoError if writing fails
+    pub fn write(&self, diff: &Diff, out: &mut dyn Write) -> CcResult<()> {
+        let mut file_stats = HashMap::new();
+        let spec = self.generated.as_ref();
+        // 检测移动的代码块需要先看到整个差异
+        let moved = if self.color_moved {
+            DiffOperations::find_moved_lines(diff, spec)?
+        } else {
+            MovedLines::new()
+        };
+        // 生成文件的 (文件数, 新增行, 删除行)，与其余文件分开统计
+        let mut generated = (0, 0, 0);
+
+        // First collect file statistics
+        for delta in diff.deltas() {
+            if DiffOperations::is_generated(spec, &delta) {
+                continue;
+            }
+            if let Some(new_file) = delta.new_file().path() {
+                file_stats.insert(display_path(new_file), (0, 0)); // (additions, deletions)
+            }
+        }
+
+        // 未跟踪文件放在最后，单独成节
+        let (tracked, untracked): (Vec<usize>, Vec<usize>) =
+            (0..diff.deltas().len()).partition(|&idx| {
+                diff.get_delta(idx)
+                    .is_none_or(|delta| delta.status() != Delta::Untracked)
+            });
+
+        let mut wrote_any = false;
+        for idx in tracked.into_iter().chain(untracked.iter().copied()) {
+            if untracked.first() == Some(&idx) {
+                if wrote_any {
+                    writeln!(out)?;
+                }
+                writeln!(
+                    out,
+                    "{}{}",
+                    style(Icon::Search.prefix()).fg(Color::Green),
+                    style(format!("未跟踪文件 ({})", untracked.len()))
+                        .fg(Color::Green)
+                        .bold()
+                )?;
+            }
+            // 生成文件只显示一行，不输出完整差异
+            if let Some(delta) = diff.get_delta(idx)
+                && DiffOperations::is_generated(spec, &delta)
+            {
+                let (additions, deletions) = DiffOperations::delta_line_stats(diff, idx)?;
+                generated.0 += 1;
+                generated.1 += additions;
+                generated.2 += deletions;
+                let path = delta
+                    .new_file()
+                    .path()
+                    .or(delta.old_file().path())
+                    .map(display_path)
+                    .unwrap_or_default();
+                if !wrote_any {
+                    writeln!(
+                        out,
+                        "{}",
+                        style(Icon::Separator.repeat(ui::width()))
+                            .fg(Color::Blue)
+                            .dim()
+                    )?;
+                }
+                writeln!(
+                    out,
+                    "{}{}",
+                    style(Icon::Note.prefix()).fg(Color::White).dim(),
+                    style(t!(
+                        "diff_generated_changed",
+                        path = path,
+                        additions = additions,
+                        deletions = deletions
+                    ))
+                    .dim()
+                )?;
+                wrote_any = true;
+                continue;
+            }
+            let section = match Patch::from_diff(diff, idx) {
+                Ok(Some(mut patch)) => DiffOperations::format_patch(
+                    &mut patch,
+                    &mut file_stats,
+                    moved.get(&idx),
+                    !wrote_any,
+                )?,
+                // Binary or unchanged files have no patch, only show the header
+                Ok(None) => match diff.get_delta(idx) {
+                    Some(delta) => DiffOperations::format_file_header(&delta, !wrote_any),
+                    None => String::new(),
+                },
+                // Blobs missing from a partial clone, show the header with a note
+                Err(e) if e.code() == git2::ErrorCode::NotFound => match diff.get_delta(idx) {
+                    Some(delta) => format!(
+                        "{}{}\n",
+                        DiffOperations::format_file_header(&delta, !wrote_any),
+                        style(t!("diff_content_unavailable"))
+                            .fg(Color::Yellow)
+                            .dim()
+                    ),
+                    None => String::new(),
+                },
+                Err(e) => return Err(CheckpointError::GitOperationFailed(e)),
+            };
+
+            if !section.is_empty() {
+                out.write_all(section.as_bytes())?;
+                out.flush()?;
+                wrote_any = true;
+            }
+        }
+
+        if !wrote_any {
+            writeln!(
+                out,
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style("没有发现文件差异").fg(Color::Yellow)
+            )?;
+            return Ok(());
+        }
+
+        // Add statistics summary
+        out.write_all(DiffOperations::generate_diff_summary(&file_stats, generated).as_bytes())?;
+        out.flush()?;
+        Ok(())
+    }
+}
 
 /// Operations for handling git diffs and comparisons
 ///
@@ -30,6 +324,60 @@ impl<'a> DiffOperations<'a> {
         Self { repo }
     }
 
+    /// Settings for displayed diffs: command-line overrides, then `ccg.diff.*`
+    pub fn display_settings(&self) -> DiffSettings {
+        let overrides = OVERRIDES.read().map(|o| *o).unwrap_or_default();
+        overrides.or(DiffSettings::from_config(&CcgConfig::load(self.repo)))
+    }
+
+    /// Formatter configured by `ccg.diff.ignore` and the display settings
+    pub fn formatter(&self) -> DiffFormatter {
+        DiffFormatter::new(
+            &CcgConfig::load(self.repo).diff_ignore,
+            self.display_settings().color_moved.unwrap_or(false),
+        )
+    }
+
+    /// Pathspec of generated files configured in `ccg.diff.ignore`
+    ///
+    /// # Returns
+    /// None when no ignore rules are configured
+    fn generated_spec(&self) -> Option<Pathspec> {
+        let patterns = CcgConfig::load(self.repo).diff_ignore;
+        if patterns.is_empty() {
+            return None;
+        }
+        Pathspec::new(patterns.iter()).ok()
+    }
+
+    /// Whether a delta touches a generated file
+    ///
+    /// Either side of the delta may match, so deleting or renaming a
+    /// generated file is collapsed as well.
+    fn is_generated(spec: Option<&Pathspec>, delta: &DiffDelta) -> bool {
+        let Some(spec) = spec else {
+            return false;
+        };
+        [delta.new_file().path(), delta.old_file().path()]
+            .into_iter()
+            .flatten()
+            .any(|path| spec.matches_path(path, PathspecFlags::DEFAULT))
+    }
+
+    /// Added and deleted line counts of one file in a diff
+    fn delta_line_stats(diff: &Diff, idx: usize) -> CcResult<(i32, i32)> {
+        match Patch::from_diff(diff, idx) {
+            Ok(Some(patch)) => {
+                let (_, additions, deletions) = patch.line_stats()?;
+                Ok((additions as i32, deletions as i32))
+            }
+            Ok(None) => Ok((0, 0)),
+            // 部分克隆中缺失的内容不计入行数
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok((0, 0)),
+            Err(e) => Err(CheckpointError::GitOperationFailed(e)),
+        }
+    }
+
     /// Get the diff for a specific commit
     ///
     /// Generates a diff showing the changes introduced by the given commit.
@@ -43,20 +391,54 @@ impl<'a> DiffOperations<'a> {
     ///
     /// # Errors
     /// Returns CheckpointError::GitOperationFailed if the diff cannot be generated
-    pub fn get_commit_diff(&self, commit: &Commit) -> CcResult<Diff> {
-        if let Ok(parent) = commit.parent(0) {
-            let tree_a = parent.tree()?;
-            let tree_b = commit.tree()?;
-            self.repo
-                .diff_tree_to_tree(Some(&tree_a), Some(&tree_b), None)
-                .map_err(CheckpointError::GitOperationFailed)
-        } else {
-            // This is the first commit, compare against empty tree
-            let tree_b = commit.tree()?;
-            self.repo
-                .diff_tree_to_tree(None, Some(&tree_b), None)
-                .map_err(CheckpointError::GitOperationFailed)
+    pub fn get_commit_diff(&self, commit: &Commit) -> CcResult<Diff<'a>> {
+        self.commit_diff_with_options(commit, &mut DiffOptions::new())
+    }
+
+    /// Get the diff for a specific commit, limited to matching paths
+    ///
+    /// Patterns use git pathspec syntax, so globs such as `src/*.rs` are
+    /// supported. An empty slice matches every path. The diff is meant for
+    /// display and follows [`Self::display_settings`].
+    ///
+    /// # Arguments
+    /// * `commit` - The commit to generate a diff for
+    /// * `pathspecs` - Path patterns the diff is restricted to
+    ///
+    /// # Returns
+    /// A git2::Diff object representing the matching changes
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if the diff cannot be generated
+    pub fn get_commit_diff_for_paths(
+        &self,
+        commit: &Commit,
+        pathspecs: &[String],
+    ) -> CcResult<Diff<'a>> {
+        let settings = self.display_settings();
+        let mut opts = DiffOptions::new();
+        settings.apply(&mut opts);
+        for spec in pathspecs {
+            opts.pathspec(spec);
         }
+        let mut diff = self.commit_diff_with_options(commit, &mut opts)?;
+        settings.find_renames(&mut diff)?;
+        Ok(diff)
+    }
+
+    /// Get the diff for a specific commit using custom diff options
+    fn commit_diff_with_options(
+        &self,
+        commit: &Commit,
+        opts: &mut DiffOptions,
+    ) -> CcResult<Diff<'a>> {
+        let tree_b = commit.tree()?;
+        // A missing parent (first commit, or a shallow clone boundary) is
+        // compared against the empty tree
+        let tree_a = commit.parent(0).ok().and_then(|parent| parent.tree().ok());
+        self.repo
+            .diff_tree_to_tree(tree_a.as_ref(), Some(&tree_b), Some(opts))
+            .map_err(CheckpointError::GitOperationFailed)
     }
 
     /// Compare two commits and generate a formatted diff
@@ -77,39 +459,375 @@ impl<'a> DiffOperations<'a> {
         // Find the first commit using the commit operations logic
         let commit_a = self.find_commit_by_hash(hash_a)?;
         let tree_a = commit_a.tree()?;
+        let settings = self.display_settings();
 
-        let diff = if let Some(hash_b) = hash_b {
-            let commit_b = self.find_commit_by_hash(hash_b)?;
-            let tree_b = commit_b.tree()?;
-            self.repo
-                .diff_tree_to_tree(Some(&tree_a), Some(&tree_b), None)?
-        } else {
-            // Compare with working directory
-            self.repo.diff_tree_to_index(Some(&tree_a), None, None)?
+        let Some(hash_b) = hash_b else {
+            // Compare with working directory, untracked files included
+            let mut diff = self.workdir_diff_with(Some(&tree_a), &settings)?;
+            settings.find_renames(&mut diff)?;
+            return self.format_diff_output(&diff);
         };
 
-        self.format_diff_output(&diff)
+        // 两个检查点都不可变，渲染结果可以缓存
+        let commit_b = self.find_commit_by_hash(hash_b)?;
+        let cache = DiffCache::new(self.repo);
+        let key = cache.key("diff", &[commit_a.id(), commit_b.id()], &[]);
+        let mut buffer = Vec::new();
+        cache.write_through(&key, &mut buffer, |out| {
+            let mut opts = DiffOptions::new();
+            settings.apply(&mut opts);
+            let mut diff = self.repo.diff_tree_to_tree(
+                Some(&tree_a),
+                Some(&commit_b.tree()?),
+                Some(&mut opts),
+            )?;
+            settings.find_renames(&mut diff)?;
+            self.write_diff_output(&diff, out)
+        })?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+
+    /// Formatted diff from a checkpoint to a directory on disk
+    ///
+    /// The directory is hashed into a temporary tree held in an in-memory
+    /// object store, so nothing is written to the repository, and the two
+    /// trees are compared like two checkpoints. `.git` and paths matched by
+    /// the repository's ignore rules are skipped.
+    ///
+    /// # Arguments
+    /// * `hash` - The checkpoint to compare from
+    /// * `dir` - The directory to compare to
+    ///
+    /// # Errors
+    /// Returns CheckpointError::InvalidArgument if `dir` is not a directory
+    pub fn diff_directory(&self, hash: &str, dir: &Path) -> CcResult<String> {
+        if !dir.is_dir() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("diff_dir_missing", dir = dir.display()).to_string(),
+            ));
+        }
+        // 另开一个仓库句柄并挂上内存对象库，目录的对象不会写入 .git
+        let scratch = Repository::open(self.repo.path())?;
+        scratch.odb()?.add_new_mempack_backend(1000)?;
+        let diffs = DiffOperations::new(&scratch);
+        let tree_a = diffs.find_commit_by_hash(hash)?.tree()?;
+        let tree_b = match write_directory_tree(&scratch, self.repo, dir, OsStr::new(""))? {
+            Some(oid) => scratch.find_tree(oid)?,
+            None => scratch.find_tree(scratch.treebuilder(None)?.write()?)?,
+        };
+        let settings = self.display_settings();
+        let mut opts = DiffOptions::new();
+        settings.apply(&mut opts);
+        let mut diff = scratch.diff_tree_to_tree(Some(&tree_a), Some(&tree_b), Some(&mut opts))?;
+        settings.find_renames(&mut diff)?;
+        diffs.format_diff_output(&diff)
     }
 
     /// Get diff between working directory and HEAD
     ///
-    /// Generates a diff showing uncommitted changes in the working directory.
+    /// Generates a diff showing uncommitted changes in the working directory,
+    /// including untracked files.
     ///
     /// # Returns
     /// A git2::Diff object representing the working directory changes
     ///
     /// # Errors
     /// Returns CheckpointError::GitOperationFailed if the diff cannot be generated
-    pub fn get_workdir_diff(&self) -> CcResult<Diff> {
+    pub fn get_workdir_diff(&self) -> CcResult<Diff<'a>> {
         let head = self.repo.head()?;
         let head_commit = head.peel_to_commit()?;
         let head_tree = head_commit.tree()?;
 
+        self.workdir_diff(Some(&head_tree))
+    }
+
+    /// Diff a tree against the working directory
+    ///
+    /// Unlike a tree-to-index diff this sees unstaged edits, and untracked
+    /// files (ignored ones excluded) are reported with their full content as
+    /// `Delta::Untracked` entries.
+    ///
+    /// # Arguments
+    /// * `tree` - The tree to compare from, or None for the empty tree
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if the diff cannot be generated
+    pub fn workdir_diff(&self, tree: Option<&Tree>) -> CcResult<Diff<'a>> {
+        self.workdir_diff_with(tree, &DiffSettings::default())
+    }
+
+    /// [`Self::workdir_diff`] with the algorithm and context of `settings`
+    fn workdir_diff_with(
+        &self,
+        tree: Option<&Tree>,
+        settings: &DiffSettings,
+    ) -> CcResult<Diff<'a>> {
+        let mut opts = DiffOptions::new();
+        settings.apply(&mut opts);
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
         self.repo
-            .diff_tree_to_index(Some(&head_tree), None, None)
+            .diff_tree_to_workdir_with_index(tree, Some(&mut opts))
             .map_err(CheckpointError::GitOperationFailed)
     }
 
+    /// Files a restore to `hash` would change in the working directory
+    ///
+    /// Compares the working directory (through the index) with the target
+    /// checkpoint's tree, oriented so that additions are lines the restore
+    /// brings back and deletions are lines it removes. Untracked files are
+    /// left alone by a restore and are not listed.
+    ///
+    /// # Arguments
+    /// * `hash` - The checkpoint to restore to
+    ///
+    /// # Returns
+    /// One entry per changed file, in path order
+    ///
+    /// # Errors
+    /// Returns CheckpointError if the commit cannot be found or diff cannot be generated
+    pub fn restore_preview(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>> {
+        let diff = self.restore_diff(hash)?;
+
+        let mut files = Vec::new();
+        for index in 0..diff.deltas().len() {
+            let delta = diff.get_delta(index).expect("delta index in range");
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let (_, additions, deletions) = match Patch::from_diff(&diff, index)? {
+                Some(patch) => patch.line_stats()?,
+                None => (0, 0, 0),
+            };
+            files.push(FileChangeInfo::with_stats(
+                path,
+                delta.status(),
+                additions as i32,
+                deletions as i32,
+            ));
+        }
+        Ok(files)
+    }
+
+    /// Files changed in the working directory since a checkpoint
+    ///
+    /// Includes unstaged edits and untracked files (reported as
+    /// `Delta::Untracked`), with line statistics relative to the checkpoint.
+    ///
+    /// # Arguments
+    /// * `hash` - The checkpoint to compare against
+    ///
+    /// # Returns
+    /// One entry per changed file, in path order
+    ///
+    /// # Errors
+    /// Returns CheckpointError if the commit cannot be found or diff cannot be generated
+    pub fn changes_since(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>> {
+        let tree = self.find_commit_by_hash(hash)?.tree()?;
+        let diff = self.workdir_diff(Some(&tree))?;
+
+        let mut files = Vec::new();
+        for index in 0..diff.deltas().len() {
+            let delta = diff.get_delta(index).expect("delta index in range");
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let (_, additions, deletions) = match Patch::from_diff(&diff, index)? {
+                Some(patch) => patch.line_stats()?,
+                None => (0, 0, 0),
+            };
+            files.push(FileChangeInfo::with_stats(
+                path,
+                delta.status(),
+                additions as i32,
+                deletions as i32,
+            ));
+        }
+        Ok(files)
+    }
+
+    /// Each file changed between two commits with its plain unified diff
+    ///
+    /// Follows [`Self::display_settings`] (algorithm, context, renames) but
+    /// is never colored, so the patches can be embedded in reports.
+    ///
+    /// # Arguments
+    /// * `from` - The commit to compare from, or None for the empty tree
+    /// * `to` - The commit to compare to
+    ///
+    /// # Returns
+    /// One entry per changed file, in path order
+    ///
+    /// # Errors
+    /// Returns CheckpointError if a commit cannot be found or diff cannot be generated
+    pub fn file_patches(&self, from: Option<&str>, to: &str) -> CcResult<Vec<FilePatch>> {
+        let old_tree = match from {
+            Some(hash) => Some(self.find_commit_by_hash(hash)?.tree()?),
+            None => None,
+        };
+        let new_tree = self.find_commit_by_hash(to)?.tree()?;
+        let settings = self.display_settings();
+        let mut opts = DiffOptions::new();
+        settings.apply(&mut opts);
+        let mut diff =
+            self.repo
+                .diff_tree_to_tree(old_tree.as_ref(), Some(&new_tree), Some(&mut opts))?;
+        settings.find_renames(&mut diff)?;
+
+        let mut files = Vec::new();
+        for index in 0..diff.deltas().len() {
+            let delta = diff.get_delta(index).expect("delta index in range");
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let (change, patch) = match Patch::from_diff(&diff, index)? {
+                Some(mut patch) => {
+                    let (_, additions, deletions) = patch.line_stats()?;
+                    let text = String::from_utf8_lossy(&patch.to_buf()?).into_owned();
+                    (
+                        FileChangeInfo::with_stats(
+                            path,
+                            delta.status(),
+                            additions as i32,
+                            deletions as i32,
+                        ),
+                        text,
+                    )
+                }
+                None => (FileChangeInfo::new(path, delta.status()), String::new()),
+            };
+            files.push(FilePatch { change, patch });
+        }
+        Ok(files)
+    }
+
+    /// Diff from the working directory to a checkpoint's tree
+    fn restore_diff(&self, hash: &str) -> CcResult<Diff<'a>> {
+        let target_tree = self.find_commit_by_hash(hash)?.tree()?;
+        let mut opts = DiffOptions::new();
+        opts.reverse(true);
+        Ok(self
+            .repo
+            .diff_tree_to_workdir_with_index(Some(&target_tree), Some(&mut opts))?)
+    }
+
+    /// Hunks a restore to `hash` would apply to the working directory
+    ///
+    /// Uses the same orientation as [`Self::restore_preview`]. Binary files
+    /// have no hunks and are not listed.
+    ///
+    /// # Arguments
+    /// * `hash` - The checkpoint to restore from
+    ///
+    /// # Returns
+    /// Hunks in file order, then in line order within each file
+    ///
+    /// # Errors
+    /// Returns CheckpointError if the commit cannot be found or diff cannot be generated
+    pub fn restore_hunks(&self, hash: &str) -> CcResult<Vec<RestoreHunk>> {
+        let diff = self.restore_diff(hash)?;
+
+        let mut hunks = Vec::new();
+        for index in 0..diff.deltas().len() {
+            let Some(patch) = Patch::from_diff(&diff, index)? else {
+                continue;
+            };
+            let delta = patch.delta();
+            let path = delta
+                .new_file()
+                .path()
+                .or(delta.old_file().path())
+                .map(display_path)
+                .unwrap_or_default();
+            for hunk_idx in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(hunk_idx)?;
+                let mut lines = Vec::with_capacity(line_count);
+                for line_idx in 0..line_count {
+                    let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                    let origin = match line.origin() {
+                        origin @ ('+' | '-') => origin,
+                        _ => ' ',
+                    };
+                    let content = String::from_utf8_lossy(line.content());
+                    lines.push(format!(
+                        "{origin}{}",
+                        content.trim_end_matches(['\r', '\n'])
+                    ));
+                }
+                hunks.push(RestoreHunk {
+                    path: path.clone(),
+                    header: String::from_utf8_lossy(hunk.header())
+                        .trim_end()
+                        .to_string(),
+                    lines,
+                });
+            }
+        }
+        Ok(hunks)
+    }
+
+    /// Apply the selected restore hunks to the working directory
+    ///
+    /// The diff is recomputed and `selected` is matched against it in the
+    /// order [`Self::restore_hunks`] returns hunks. Files with no selected
+    /// hunk are left untouched; the index and branches are never changed.
+    ///
+    /// # Arguments
+    /// * `hash` - The checkpoint to restore from
+    /// * `selected` - One flag per hunk, true to apply it
+    ///
+    /// # Returns
+    /// The number of hunks applied
+    ///
+    /// # Errors
+    /// Returns CheckpointError::InvalidArgument if the working directory changed
+    /// since the hunks were listed, or GitOperationFailed if applying fails
+    pub fn apply_restore_hunks(&self, hash: &str, selected: &[bool]) -> CcResult<usize> {
+        let diff = self.restore_diff(hash)?;
+
+        // 每个文件第一个变更块在 selected 中的位置，以及文件是否有选中的变更块
+        let mut starts = Vec::with_capacity(diff.deltas().len());
+        let mut any_selected = Vec::with_capacity(diff.deltas().len());
+        let mut total = 0;
+        for index in 0..diff.deltas().len() {
+            let count = match Patch::from_diff(&diff, index)? {
+                Some(patch) => patch.num_hunks(),
+                None => 0,
+            };
+            starts.push(total);
+            any_selected.push(selected.iter().skip(total).take(count).any(|&s| s));
+            total += count;
+        }
+        if total != selected.len() {
+            return Err(CheckpointError::InvalidArgument(
+                "工作目录在选择变更块期间发生了变化，请重新运行".to_string(),
+            ));
+        }
+        let applied = selected.iter().filter(|&&s| s).count();
+        if applied == 0 {
+            return Ok(0);
+        }
+
+        let delta_index = std::cell::Cell::new(0usize);
+        let cursor = std::cell::Cell::new(0usize);
+        let mut opts = git2::ApplyOptions::new();
+        opts.delta_callback(|_| {
+            let index = delta_index.get();
+            delta_index.set(index + 1);
+            cursor.set(starts[index]);
+            any_selected[index]
+        });
+        opts.hunk_callback(|_| {
+            let position = cursor.get();
+            cursor.set(position + 1);
+            selected[position]
+        });
+        self.repo
+            .apply(&diff, git2::ApplyLocation::WorkDir, Some(&mut opts))?;
+        Ok(applied)
+    }
+
     /// Get formatted diff content for a specific commit
     ///
     /// This is the main method for generating detailed, colored diff output
@@ -142,12 +860,17 @@ impl<'a> DiffOperations<'a> {
     pub fn calculate_diff_stats(&self, diff: &Diff) -> DiffStats {
         let mut stats = DiffStats::new();
         let mut file_changes = Vec::new();
+        let spec = self.generated_spec();
 
         // Collect file-level statistics
         for delta in diff.deltas() {
+            // 生成文件单独计数
+            if Self::is_generated(spec.as_ref(), &delta) {
+                stats.generated_files += 1;
+                continue;
+            }
             if let Some(new_file) = delta.new_file().path() {
-                let file_path = new_file.to_string_lossy().to_string();
-                let file_change = FileChangeInfo::new(file_path, delta.status());
+                let file_change = FileChangeInfo::new(new_file, delta.status());
                 file_changes.push(file_change);
 
                 // Count file modifications by type
@@ -160,8 +883,9 @@ impl<'a> DiffOperations<'a> {
         stats.total_files = file_changes.len();
 
         // Calculate line-level statistics by processing the diff
-        let _ = diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+        let _ = diff.print(git2::DiffFormat::Patch, |delta, _hunk, line| {
             let origin = line.origin();
+            let generated = Self::is_generated(spec.as_ref(), &delta);
 
             // Skip special markers and binary content indicators
             let content = std::str::from_utf8(line.content()).unwrap_or("");
@@ -173,13 +897,11 @@ impl<'a> DiffOperations<'a> {
                 return true;
             }
 
-            match origin {
-                '+' => {
-                    stats.additions += 1;
-                }
-                '-' => {
-                    stats.deletions += 1;
-                }
+            match (origin, generated) {
+                ('+', false) => stats.additions += 1,
+                ('-', false) => stats.deletions += 1,
+                ('+', true) => stats.generated_additions += 1,
+                ('-', true) => stats.generated_deletions += 1,
                 _ => {} // Context lines and headers don't count
             }
             true
@@ -188,6 +910,103 @@ impl<'a> DiffOperations<'a> {
         stats
     }
 
+    /// Calculate statistics for the changes introduced by a commit
+    ///
+    /// Uses libgit2's native stat computation instead of rendering the
+    /// patch, which keeps it cheap enough to run for many commits. Files
+    /// matching `ccg.diff.ignore` are moved into the generated counts.
+    ///
+    /// # Arguments
+    /// * `commit` - The commit to compute statistics for
+    ///
+    /// # Returns
+    /// A DiffStats struct for the commit's changes
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if the diff cannot be generated
+    pub fn commit_stats(&self, commit: &Commit) -> CcResult<DiffStats> {
+        let diff = self.get_commit_diff(commit)?;
+        let git_stats = diff.stats().map_err(CheckpointError::GitOperationFailed)?;
+
+        let mut stats = DiffStats {
+            total_files: git_stats.files_changed(),
+            additions: git_stats.insertions() as i32,
+            deletions: git_stats.deletions() as i32,
+            modifications: diff
+                .deltas()
+                .filter(|d| d.status() == git2::Delta::Modified)
+                .count() as i32,
+            format_only: self.is_format_only(&diff)?,
+            ..DiffStats::new()
+        };
+
+        let spec = self.generated_spec();
+        for (idx, delta) in diff.deltas().enumerate() {
+            if !Self::is_generated(spec.as_ref(), &delta) {
+                continue;
+            }
+            let (additions, deletions) = Self::delta_line_stats(&diff, idx)?;
+            stats.total_files -= 1;
+            stats.additions -= additions;
+            stats.deletions -= deletions;
+            if delta.status() == git2::Delta::Modified {
+                stats.modifications -= 1;
+            }
+            stats.generated_files += 1;
+            stats.generated_additions += additions;
+            stats.generated_deletions += deletions;
+        }
+        Ok(stats)
+    }
+
+    /// Whether every change in a tree diff only touches whitespace
+    ///
+    /// Each delta must be a modification whose old and new contents are
+    /// equal once all whitespace is removed, so reindenting, rewrapping and
+    /// line ending changes count while renames, mode changes and binary files
+    /// do not. An empty diff is not format-only.
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if a blob cannot be read
+    pub fn is_format_only(&self, diff: &Diff) -> CcResult<bool> {
+        if diff.deltas().len() == 0 {
+            return Ok(false);
+        }
+        for delta in diff.deltas() {
+            if delta.status() != Delta::Modified
+                || delta.old_file().mode() != delta.new_file().mode()
+            {
+                return Ok(false);
+            }
+            let blobs = (
+                self.repo.find_blob(delta.old_file().id()),
+                self.repo.find_blob(delta.new_file().id()),
+            );
+            let (old, new) = match blobs {
+                (Ok(old), Ok(new)) => (old, new),
+                // 部分克隆中缺失的内容无法比较
+                (Err(e), _) | (_, Err(e)) if e.code() == git2::ErrorCode::NotFound => {
+                    return Ok(false);
+                }
+                (Err(e), _) | (_, Err(e)) => return Err(CheckpointError::GitOperationFailed(e)),
+            };
+            if old.is_binary() || new.is_binary() {
+                return Ok(false);
+            }
+            let normalized = |content: &[u8]| -> Vec<u8> {
+                content
+                    .iter()
+                    .copied()
+                    .filter(|byte| !byte.is_ascii_whitespace())
+                    .collect()
+            };
+            if normalized(old.content()) != normalized(new.content()) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
     /// Get a summary string of diff statistics
     ///
     /// Creates a human-readable summary of the changes in a diff.
@@ -215,73 +1034,291 @@ impl<'a> DiffOperations<'a> {
 
             summary.push_str(&parts.join(", "));
         }
+        if stats.generated_files > 0 {
+            summary.push_str(&format!(
+                " ({} generated files, +{} -{})",
+                stats.generated_files, stats.generated_additions, stats.generated_deletions
+            ));
+        }
 
         summary
     }
 
-    /// Helper method to find a commit by hash (supports short hashes)
+    /// Check whether a commit sits on the shallow clone boundary
+    ///
+    /// Boundary commits have parents that were never fetched, so their diff
+    /// is computed against the empty tree and lists every file as added.
+    pub fn is_shallow_boundary(&self, commit: &Commit) -> bool {
+        self.repo.is_shallow()
+            && RepositoryOperations::new(self.repo)
+                .shallow_boundaries()
+                .contains(&commit.id())
+    }
+
+    /// Inclusive line ranges touched by each file's hunks in a diff
     ///
-    /// This is a simplified version of the commit finding logic.
-    /// In the full implementation, this would delegate to CommitOperations.
+    /// Hunks include their context lines, so pass a diff generated without
+    /// context to get only the changed lines.
     ///
     /// # Arguments
-    /// * `hash` - Full or partial commit hash
+    /// * `diff` - The diff to inspect
+    /// * `new_side` - Use new-file line numbers instead of old-file ones
     ///
     /// # Returns
-    /// The found commit
+    /// Ranges keyed by path. Pure insertions and deletions, which cover no
+    /// lines on one side, are recorded as the single line they sit at, so
+    /// adjacent edits count as overlapping like they do in a merge.
+    pub fn changed_line_ranges(
+        &self,
+        diff: &Diff,
+        new_side: bool,
+    ) -> CcResult<BTreeMap<String, Vec<(u32, u32)>>> {
+        let mut ranges: BTreeMap<String, Vec<(u32, u32)>> = BTreeMap::new();
+        for (idx, delta) in diff.deltas().enumerate() {
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let file_ranges = ranges.entry(display_path(path)).or_default();
+            let Some(patch) = Patch::from_diff(diff, idx)? else {
+                continue;
+            };
+            for hunk_idx in 0..patch.num_hunks() {
+                let (hunk, _) = patch.hunk(hunk_idx)?;
+                let (start, lines) = if new_side {
+                    (hunk.new_start(), hunk.new_lines())
+                } else {
+                    (hunk.old_start(), hunk.old_lines())
+                };
+                file_ranges.push((start, start + lines.max(1) - 1));
+            }
+        }
+        Ok(ranges)
+    }
+
+    /// Report which later checkpoints touch the files and lines changed by `hash`
+    ///
+    /// # Arguments
+    /// * `hash` - Hash of a checkpoint on the CCG branch
+    ///
+    /// # Returns
+    /// The overlaps with every later checkpoint, plus the files a revert onto
+    /// the current tip would conflict in
     ///
     /// # Errors
-    /// Returns CheckpointError if the commit cannot be found
-    fn find_commit_by_hash(&self, hash: &str) -> CcResult<Commit> {
-        // First try complete hash
-        if let Ok(oid) = git2::Oid::from_str(hash) {
-            if let Ok(commit) = self.repo.find_commit(oid) {
-                return Ok(commit);
-            }
-        }
-
-        // If complete hash fails, try short hash query
-        if hash.len() >= 2 && hash.len() < 40 {
-            let mut revwalk = self
-                .repo
-                .revwalk()
-                .map_err(CheckpointError::GitOperationFailed)?;
-            revwalk
-                .set_sorting(git2::Sort::TIME)
-                .map_err(CheckpointError::GitOperationFailed)?;
-            revwalk
-                .push_head()
-                .map_err(CheckpointError::GitOperationFailed)?;
-
-            let mut matches = Vec::new();
-            for oid_result in revwalk {
-                let oid = oid_result.map_err(CheckpointError::GitOperationFailed)?;
-                let oid_str = oid.to_string();
-
-                if oid_str.starts_with(hash) {
-                    matches.push(oid);
-                }
+    /// Returns `CheckpointError::CheckpointNotFound` if the checkpoint is not
+    /// on the CCG branch
+    pub fn checkpoint_impact(&self, hash: &str) -> CcResult<ImpactReport> {
+        let commits = CommitOperations::new(self.repo);
+        let target = commits.find_commit(hash)?;
+        let branch = RewriteOperations::new(self.repo).branch_commits_from(&target)?;
+        let diff_of = |commit: &Commit| {
+            let mut opts = DiffOptions::new();
+            opts.context_lines(0);
+            self.commit_diff_with_options(commit, &mut opts)
+        };
+        let target_ranges = self.changed_line_ranges(&diff_of(&target)?, true)?;
+
+        let mut overlaps = Vec::new();
+        for later in branch.iter().skip(1) {
+            let later_ranges = self.changed_line_ranges(&diff_of(later)?, false)?;
+            let files: Vec<String> = later_ranges
+                .keys()
+                .filter(|path| target_ranges.contains_key(*path))
+                .cloned()
+                .collect();
+            if files.is_empty() {
+                continue;
             }
+            let overlapping_lines = files
+                .iter()
+                .filter(|path| {
+                    target_ranges[*path]
+                        .iter()
+                        .any(|a| later_ranges[*path].iter().any(|b| a.0 <= b.1 && b.0 <= a.1))
+                })
+                .cloned()
+                .collect();
+            overlaps.push(CheckpointOverlap {
+                hash: later.id().to_string(),
+                summary: later.summary().unwrap_or_default().to_string(),
+                files,
+                overlapping_lines,
+            });
+        }
 
-            match matches.len() {
-                0 => Err(CheckpointError::CheckpointNotFound(hash.to_string())),
-                1 => {
-                    let commit = self
-                        .repo
-                        .find_commit(matches[0])
-                        .map_err(CheckpointError::GitOperationFailed)?;
-                    Ok(commit)
-                }
-                _ => {
-                    let error_msg = format!("短hash '{hash}' 匹配到多个提交，请使用更长的hash前缀");
-                    Err(CheckpointError::InvalidHash(error_msg))
-                }
-            }
+        let tip = branch.last().unwrap_or(&target);
+        let revert_conflicts = commits.revert_conflicts(&target, tip)?;
+
+        Ok(ImpactReport {
+            hash: target.id().to_string(),
+            later_checkpoints: branch.len().saturating_sub(1),
+            overlaps,
+            revert_conflicts,
+        })
+    }
+
+    /// Find a commit by hash (supports short hashes)
+    ///
+    /// Delegates to `CommitOperations::find_commit` so hash resolution and its
+    /// error messages stay identical across commands.
+    fn find_commit_by_hash(&self, hash: &str) -> CcResult<Commit<'a>> {
+        CommitOperations::new(self.repo).find_commit(hash)
+    }
+
+    /// Compare the latest checkpoint with the commit HEAD points to
+    ///
+    /// Each side is diffed against the merge base of the two (the empty tree
+    /// when their histories are unrelated) and the changed files are sorted
+    /// into changed only by the checkpoints, only on the branch, or on both
+    /// with different results.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if there is no CCG branch
+    /// and `CheckpointError::InvalidArgument` if HEAD is on it
+    pub fn branch_drift(&self) -> CcResult<BranchDrift> {
+        let checkpoint = self
+            .repo
+            .find_branch(CCG_BRANCH_NAME, git2::BranchType::Local)
+            .map_err(|_| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?
+            .get()
+            .peel_to_commit()?;
+        let head = self.repo.head()?;
+        let head_commit = head.peel_to_commit()?;
+        let branch = if self.repo.head_detached()? {
+            head_commit.id().to_string()
         } else {
-            Err(CheckpointError::InvalidHash(format!(
-                "无效的hash格式: {hash}"
-            )))
+            head.shorthand().unwrap_or("HEAD").to_string()
+        };
+        if branch == CCG_BRANCH_NAME {
+            return Err(CheckpointError::InvalidArgument(
+                t!("drift_on_ccg").to_string(),
+            ));
+        }
+
+        let base = self.repo.merge_base(checkpoint.id(), head_commit.id()).ok();
+        let base_tree = base
+            .map(|oid| self.repo.find_commit(oid)?.tree())
+            .transpose()?;
+        let ours = self.changed_blobs(base_tree.as_ref(), &checkpoint.tree()?)?;
+        let theirs = self.changed_blobs(base_tree.as_ref(), &head_commit.tree()?)?;
+
+        let mut drift = BranchDrift {
+            branch,
+            head: head_commit.id().to_string(),
+            checkpoint: checkpoint.id().to_string(),
+            base: base.map(|oid| oid.to_string()),
+            ..BranchDrift::default()
+        };
+        let paths: BTreeSet<&String> = ours.keys().chain(theirs.keys()).collect();
+        for path in paths {
+            match (ours.get(path), theirs.get(path)) {
+                (Some(_), None) => drift.only_checkpoint.push(path.clone()),
+                (None, Some(_)) => drift.only_branch.push(path.clone()),
+                (Some(a), Some(b)) if a != b => drift.diverged.push(path.clone()),
+                _ => {}
+            }
+        }
+        Ok(drift)
+    }
+
+    /// Files changed from `base` (the empty tree when None) to `tree`, with
+    /// their new blob (zero when deleted)
+    fn changed_blobs(&self, base: Option<&Tree>, tree: &Tree) -> CcResult<BTreeMap<String, Oid>> {
+        let diff = self.repo.diff_tree_to_tree(base, Some(tree), None)?;
+        Ok(diff
+            .deltas()
+            .filter_map(|delta| {
+                let path = delta.new_file().path().or(delta.old_file().path())?;
+                Some((display_path(path), delta.new_file().id()))
+            })
+            .collect())
+    }
+
+    /// Format the list of files changed in a diff
+    ///
+    /// Produces a `Files: N files changed (...)` header followed by one
+    /// colored status line per file.
+    ///
+    /// # Arguments
+    /// * `diff` - The diff whose files should be listed
+    ///
+    /// # Returns
+    /// The formatted list, or an empty string if the diff has no files
+    pub fn format_changed_files(&self, diff: &Diff) -> String {
+        let mut result = String::new();
+        let mut stats = (0, 0, 0); // (added, modified, deleted)
+        let mut files = Vec::new();
+
+        for delta in diff.deltas() {
+            if let Some(file) = delta.new_file().path() {
+                let (status_str, color) = match delta.status() {
+                    Delta::Added => {
+                        stats.0 += 1;
+                        ("A", Color::Green)
+                    }
+                    Delta::Deleted => {
+                        stats.2 += 1;
+                        ("D", Color::Red)
+                    }
+                    Delta::Modified => {
+                        stats.1 += 1;
+                        ("M", Color::Yellow)
+                    }
+                    Delta::Renamed => ("R", Color::Blue),
+                    Delta::Copied => ("C", Color::Magenta),
+                    _ => ("?", Color::White),
+                };
+
+                files.push(format!(
+                    "  {} {}",
+                    style(status_str).fg(color).bold(),
+                    style(file.display()).fg(Color::White)
+                ));
+            }
+        }
+
+        if files.is_empty() {
+            return result;
+        }
+
+        result.push_str(&format!(
+            "\n{} {} files changed",
+            style("Files:").fg(Color::White).bold(),
+            style(files.len()).fg(Color::Cyan).bold()
+        ));
+
+        if stats.0 > 0 || stats.1 > 0 || stats.2 > 0 {
+            let mut parts = Vec::new();
+            if stats.0 > 0 {
+                parts.push(format!(
+                    "{} {}",
+                    style(stats.0).fg(Color::Green).bold(),
+                    style("added").fg(Color::Green)
+                ));
+            }
+            if stats.1 > 0 {
+                parts.push(format!(
+                    "{} {}",
+                    style(stats.1).fg(Color::Yellow).bold(),
+                    style("modified").fg(Color::Yellow)
+                ));
+            }
+            if stats.2 > 0 {
+                parts.push(format!(
+                    "{} {}",
+                    style(stats.2).fg(Color::Red).bold(),
+                    style("deleted").fg(Color::Red)
+                ));
+            }
+            result.push_str(&format!(" ({})", parts.join(", ")));
+        }
+        result.push_str("\n\n");
+
+        for file in files {
+            result.push_str(&format!("{file}\n"));
         }
+
+        result
     }
 
     /// Format a git2::Diff object into a human-readable string
@@ -301,28 +1338,261 @@ impl<'a> DiffOperations<'a> {
     /// # Errors
     /// Returns CheckpointError::GitOperationFailed if formatting fails
     pub fn format_diff_output(&self, diff: &Diff) -> CcResult<String> {
-        let mut result = String::new();
-        let mut current_file = String::new();
+        self.formatter().format(diff)
+    }
+
+    /// Stream a formatted diff to a writer, one file section at a time
+    ///
+    /// Each file's patch is generated, formatted and written before the next
+    /// one is computed, so huge diffs start printing immediately and never
+    /// have to be held in memory as a whole.
+    ///
+    /// # Arguments
+    /// * `diff` - The git2::Diff object to format
+    /// * `out` - Destination for the formatted output
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if a patch cannot be generated,
+    /// or CheckpointError::IoError if writing fails
+    pub fn write_diff_output(&self, diff: &Diff, out: &mut dyn Write) -> CcResult<()> {
+        self.formatter().write(diff, out)
+    }
+
+    /// Write a `--stat` style summary of a diff: one line per file with its
+    /// changed line count and change bar, then the totals
+    ///
+    /// Used instead of the full patch for root checkpoints, whose diff
+    /// against the empty tree adds every file in the repository. Lines of
+    /// added files are counted straight from their blobs, so no patch is
+    /// generated for them.
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if a patch cannot be generated,
+    /// or CheckpointError::IoError if writing fails
+    pub fn write_diff_stat(&self, diff: &Diff, out: &mut dyn Write) -> CcResult<()> {
+        // (路径, 新增行, 删除行)，二进制文件没有行数
+        let mut rows: Vec<(String, Option<(i32, i32)>)> = Vec::new();
         let mut file_stats = HashMap::new();
-        let mut old_line_num = 1;
-        let mut new_line_num = 1;
-        let mut hunk_initialized = false;
+        for (idx, delta) in diff.deltas().enumerate() {
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let path = display_path(path);
+            let lines = if delta.status() == Delta::Added {
+                match self.repo.find_blob(delta.new_file().id()) {
+                    Ok(blob) if blob.is_binary() => None,
+                    Ok(blob) => Some((count_lines(blob.content()), 0)),
+                    // 部分克隆中缺失的内容不计入行数
+                    Err(e) if e.code() == git2::ErrorCode::NotFound => Some((0, 0)),
+                    Err(e) => return Err(CheckpointError::GitOperationFailed(e)),
+                }
+            } else if delta.flags().is_binary() {
+                None
+            } else {
+                Some(Self::delta_line_stats(diff, idx)?)
+            };
+            file_stats.insert(path.clone(), lines.unwrap_or((0, 0)));
+            rows.push((path, lines));
+        }
 
-        // First collect file statistics
-        for delta in diff.deltas() {
-            if let Some(new_file) = delta.new_file().path() {
-                let file_path = new_file.to_string_lossy().to_string();
-                file_stats.insert(file_path, (0, 0)); // (additions, deletions)
+        if rows.is_empty() {
+            writeln!(
+                out,
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style("没有发现文件差异").fg(Color::Yellow)
+            )?;
+            return Ok(());
+        }
+
+        let path_width = rows
+            .iter()
+            .map(|(path, _)| measure_text_width(path))
+            .max()
+            .unwrap_or(0);
+        let count_width = rows
+            .iter()
+            .filter_map(|(_, lines)| lines.map(|(a, d)| (a + d).to_string().len()))
+            .max()
+            .unwrap_or(0)
+            .max(3);
+        for (path, lines) in &rows {
+            let padding = " ".repeat(path_width - measure_text_width(path));
+            let count = match lines {
+                Some((additions, deletions)) => format!(
+                    "{:>count_width$} {}",
+                    additions + deletions,
+                    change_bar(*additions, *deletions)
+                ),
+                None => format!("{:>count_width$}", "Bin"),
+            };
+            writeln!(
+                out,
+                " {}{padding} | {}",
+                style(path).fg(Color::White),
+                count.trim_end()
+            )?;
+        }
+        out.write_all(Self::generate_diff_summary(&file_stats, (0, 0, 0)).as_bytes())?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Find the added and deleted lines that belong to moved blocks
+    ///
+    /// A run of consecutive deleted (or added) lines is split into maximal
+    /// segments whose lines also appear on the other side of the diff; a
+    /// segment with at least [`MOVED_BLOCK_LINES`] non-blank lines counts as
+    /// moved. Leading and trailing whitespace is ignored when comparing, so
+    /// re-indented code is detected too. Generated files are skipped.
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if a patch cannot be generated
+    fn find_moved_lines(diff: &Diff, spec: Option<&Pathspec>) -> CcResult<MovedLines> {
+        // 每个文件中连续的删除行或新增行组成一段: (文件序号, 来源, [(行号, 内容)])
+        type Run = (usize, char, Vec<(u32, String)>);
+        let mut runs: Vec<Run> = Vec::new();
+        for idx in 0..diff.deltas().len() {
+            if diff
+                .get_delta(idx)
+                .is_some_and(|delta| Self::is_generated(spec, &delta))
+            {
+                continue;
+            }
+            let patch = match Patch::from_diff(diff, idx) {
+                Ok(Some(patch)) => patch,
+                Ok(None) => continue,
+                Err(e) if e.code() == git2::ErrorCode::NotFound => continue,
+                Err(e) => return Err(CheckpointError::GitOperationFailed(e)),
+            };
+            for hunk_idx in 0..patch.num_hunks() {
+                let mut current: Option<(char, Vec<(u32, String)>)> = None;
+                for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+                    let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                    let origin = line.origin();
+                    let lineno = match origin {
+                        '-' => line.old_lineno(),
+                        '+' => line.new_lineno(),
+                        _ => None,
+                    };
+                    let Some(lineno) = lineno else {
+                        if let Some((origin, lines)) = current.take() {
+                            runs.push((idx, origin, lines));
+                        }
+                        continue;
+                    };
+                    let content = String::from_utf8_lossy(line.content()).trim().to_string();
+                    match &mut current {
+                        Some((run_origin, lines)) if *run_origin == origin => {
+                            lines.push((lineno, content));
+                        }
+                        _ => {
+                            if let Some((origin, lines)) = current.take() {
+                                runs.push((idx, origin, lines));
+                            }
+                            current = Some((origin, vec![(lineno, content)]));
+                        }
+                    }
+                }
+                if let Some((origin, lines)) = current.take() {
+                    runs.push((idx, origin, lines));
+                }
             }
         }
 
-        // First collect file statistics
-        for delta in diff.deltas() {
-            if let Some(new_file) = delta.new_file().path() {
-                let file_path = new_file.to_string_lossy().to_string();
-                file_stats.insert(file_path, (0, 0)); // (additions, deletions)
+        let side = |wanted: char| -> HashSet<&str> {
+            runs.iter()
+                .filter(|(_, origin, _)| *origin == wanted)
+                .flat_map(|(_, _, lines)| lines.iter().map(|(_, content)| content.as_str()))
+                .filter(|content| !content.is_empty())
+                .collect()
+        };
+        let deleted = side('-');
+        let added = side('+');
+
+        let mut moved = MovedLines::new();
+        for (idx, origin, lines) in &runs {
+            let other = if *origin == '-' { &added } else { &deleted };
+            // 空行不单独计数，但可以出现在移动块中间
+            let segments = lines
+                .split(|(_, content)| !content.is_empty() && !other.contains(content.as_str()));
+            for segment in segments {
+                let non_blank = segment
+                    .iter()
+                    .filter(|(_, content)| !content.is_empty())
+                    .count();
+                if non_blank >= MOVED_BLOCK_LINES {
+                    moved
+                        .entry(*idx)
+                        .or_default()
+                        .extend(segment.iter().map(|(lineno, _)| (*origin, *lineno)));
+                }
             }
         }
+        Ok(moved)
+    }
+
+    /// Format the status header shown at the top of each file section
+    fn format_file_header(delta: &DiffDelta, is_first: bool) -> String {
+        let Some(new_file) = delta.new_file().path() else {
+            return String::new();
+        };
+        let current_file = display_path(new_file);
+        let mut result = String::new();
+
+        if !is_first {
+            result.push('\n');
+        }
+
+        // Add file separator
+        result.push_str(&format!(
+            "{}\n",
+            style(Icon::Separator.repeat(ui::width()))
+                .fg(Color::Blue)
+                .dim()
+        ));
+
+        // File status indicator
+        let (status_icon, status_text, status_color) = match delta.status() {
+            git2::Delta::Added => (Icon::FileAdded, "新增文件", Color::Green),
+            git2::Delta::Deleted => (Icon::FileDeleted, "删除文件", Color::Red),
+            git2::Delta::Modified => (Icon::FileModified, "修改文件", Color::Yellow),
+            git2::Delta::Renamed => (Icon::FileRenamed, "重命名文件", Color::Blue),
+            git2::Delta::Copied => (Icon::FileCopied, "复制文件", Color::Magenta),
+            git2::Delta::Untracked => (Icon::FileAdded, "未跟踪文件", Color::Green),
+            _ => (Icon::File, "文件变更", Color::White),
+        };
+
+        // 路径过长时从开头截断，保证文件名可见
+        let status = format!("{}{status_text} ", status_icon.prefix());
+        let path = ui::truncate_start(
+            &current_file,
+            ui::width().saturating_sub(measure_text_width(&status)),
+        );
+        result.push_str(&format!(
+            "{}{}\n",
+            style(status).fg(status_color).bold(),
+            style(path).fg(Color::Cyan).bold()
+        ));
+        result
+    }
+
+    /// Format the patch of a single file
+    ///
+    /// Line statistics are accumulated into `file_stats` so the caller can
+    /// print an overall summary once every file has been written. Lines in
+    /// `moved` are shown in magenta (moved away) or cyan (moved in).
+    fn format_patch(
+        patch: &mut Patch,
+        file_stats: &mut HashMap<String, (i32, i32)>,
+        moved: Option<&HashSet<(char, u32)>>,
+        is_first: bool,
+    ) -> CcResult<String> {
+        let mut result = String::new();
+        let mut current_file = String::new();
+        let mut old_line_num = 1;
+        let mut new_line_num = 1;
+        let mut hunk_initialized = false;
 
         // Variables for intelligent newline handling
         let mut pending_deletions: Vec<(String, i32)> = Vec::new();
@@ -330,140 +1600,122 @@ impl<'a> DiffOperations<'a> {
         let mut in_newline_context = false;
 
         // Generate formatted diff output
-        diff.print(git2::DiffFormat::Patch, |delta, hunk, line| {
-            let origin = line.origin();
-            let content = std::str::from_utf8(line.content()).unwrap_or("<binary>");
-
-            // Detect newline-related special cases
-            if content.contains("No newline at end of file")
-                || content.contains("\\ No newline at end of file")
-                || origin == '>'
-                || origin == '<'
-            {
-                in_newline_context = true;
-                return true; // Skip these marker lines
-            }
-
-            // In newline context, collect + and - changes
-            if in_newline_context && (origin == '+' || origin == '-') {
-                if origin == '+' {
-                    pending_additions.push((content.to_string(), new_line_num));
-                } else if origin == '-' {
-                    pending_deletions.push((content.to_string(), old_line_num));
+        patch
+            .print(&mut |delta, hunk, line| {
+                let origin = line.origin();
+                let content = std::str::from_utf8(line.content()).unwrap_or("<binary>");
+                let is_moved = |side: char, lineno: Option<u32>| {
+                    lineno.is_some_and(|lineno| {
+                        moved.is_some_and(|moved| moved.contains(&(side, lineno)))
+                    })
+                };
+
+                // Detect newline-related special cases
+                if content.contains("No newline at end of file")
+                    || content.contains("\\ No newline at end of file")
+                    || origin == '>'
+                    || origin == '<'
+                {
+                    in_newline_context = true;
+                    return true; // Skip these marker lines
                 }
-                return true;
-            }
-
-            match origin {
-                'F' => {
-                    // File header information
-                    if content.starts_with("diff --git") {
-                        if !current_file.is_empty() {
-                            result.push('\n');
-                        }
 
-                        // Extract filename
-                        if let Some(new_file) = delta.new_file().path() {
-                            current_file = new_file.to_string_lossy().to_string();
-                            // Reset line numbers and hunk initialization flag for new file
-                            hunk_initialized = false;
-                            old_line_num = 0;
-                            new_line_num = 0;
+                // In newline context, collect + and - changes
+                if in_newline_context && (origin == '+' || origin == '-') {
+                    if origin == '+' {
+                        pending_additions.push((content.to_string(), new_line_num));
+                    } else if origin == '-' {
+                        pending_deletions.push((content.to_string(), old_line_num));
+                    }
+                    return true;
+                }
 
-                            // Add file separator
+                match origin {
+                    'F' => {
+                        // File header information
+                        if content.starts_with("diff --git") {
+                            // Extract filename
+                            if let Some(new_file) = delta.new_file().path() {
+                                current_file = display_path(new_file);
+                                // Reset line numbers and hunk initialization flag for new file
+                                hunk_initialized = false;
+                                old_line_num = 0;
+                                new_line_num = 0;
+
+                                result.push_str(&Self::format_file_header(&delta, is_first));
+                            }
+                        } else if content.starts_with("index ") {
+                            // Show file mode information (if changed)
                             result.push_str(&format!(
-                                "{}\n",
-                                style("─".repeat(100)).fg(Color::Blue).dim()
+                                "{}{}\n",
+                                style(Icon::List.prefix()).fg(Color::Blue),
+                                style(content.trim()).fg(Color::Blue).dim()
                             ));
+                        }
+                    }
+                    'H' => {
+                        // New hunk starts, first process previous pending changes
+                        if in_newline_context
+                            && (!pending_deletions.is_empty() || !pending_additions.is_empty())
+                        {
+                            // Intelligently handle newline-related changes
+                            Self::handle_pending_newline_changes(
+                                &mut result,
+                                &pending_deletions,
+                                &pending_additions,
+                                file_stats,
+                                &current_file,
+                            );
+
+                            // Clear pending changes
+                            pending_deletions.clear();
+                            pending_additions.clear();
+                            in_newline_context = false;
+                        }
 
-                            // File status indicator
-                            let (status_icon, status_text, status_color) = match delta.status() {
-                                git2::Delta::Added => ("📄", "新增文件", Color::Green),
-                                git2::Delta::Deleted => ("🗑️", "删除文件", Color::Red),
-                                git2::Delta::Modified => ("📝", "修改文件", Color::Yellow),
-                                git2::Delta::Renamed => ("📋", "重命名文件", Color::Blue),
-                                git2::Delta::Copied => ("📑", "复制文件", Color::Magenta),
-                                _ => ("📄", "文件变更", Color::White),
-                            };
+                        // Hunk header information - unified parsing of line number ranges
+                        // Prefer git2 provided hunk information, otherwise parse manually
+                        if let Some(hunk) = hunk {
+                            old_line_num = hunk.old_start() as i32;
+                            new_line_num = hunk.new_start() as i32;
+                            hunk_initialized = true;
 
                             result.push_str(&format!(
-                                "{} {} {}\n",
-                                style(status_icon).fg(status_color),
-                                style(status_text).fg(status_color).bold(),
-                                style(&current_file).fg(Color::Cyan).bold()
+                                "{}{} {} {} {}\n",
+                                style(Icon::Location.prefix()).fg(Color::Cyan),
+                                style("行号范围:").fg(Color::Cyan).bold(),
+                                style(format!(
+                                    "旧文件:{}-{}",
+                                    hunk.old_start(),
+                                    if hunk.old_lines() > 0 {
+                                        hunk.old_start() + hunk.old_lines() - 1
+                                    } else {
+                                        hunk.old_start()
+                                    }
+                                ))
+                                .fg(Color::Red)
+                                .bold(),
+                                style(Icon::Arrow.glyph()).fg(Color::White),
+                                style(format!(
+                                    "新文件:{}-{}",
+                                    hunk.new_start(),
+                                    if hunk.new_lines() > 0 {
+                                        hunk.new_start() + hunk.new_lines() - 1
+                                    } else {
+                                        hunk.new_start()
+                                    }
+                                ))
+                                .fg(Color::Green)
+                                .bold()
                             ));
-                        }
-                    } else if content.starts_with("index ") {
-                        // Show file mode information (if changed)
-                        result.push_str(&format!(
-                            "{} {}\n",
-                            style("📋").fg(Color::Blue),
-                            style(content.trim()).fg(Color::Blue).dim()
-                        ));
-                    }
-                }
-                'H' => {
-                    // New hunk starts, first process previous pending changes
-                    if in_newline_context
-                        && (!pending_deletions.is_empty() || !pending_additions.is_empty())
-                    {
-                        // Intelligently handle newline-related changes
-                        self.handle_pending_newline_changes(
-                            &mut result,
-                            &pending_deletions,
-                            &pending_additions,
-                            &mut file_stats,
-                            &current_file,
-                        );
-
-                        // Clear pending changes
-                        pending_deletions.clear();
-                        pending_additions.clear();
-                        in_newline_context = false;
-                    }
-
-                    // Hunk header information - unified parsing of line number ranges
-                    // Prefer git2 provided hunk information, otherwise parse manually
-                    if let Some(hunk) = hunk {
-                        old_line_num = hunk.old_start() as i32;
-                        new_line_num = hunk.new_start() as i32;
-                        hunk_initialized = true;
-
-                        result.push_str(&format!(
-                            "{} {} {} {} {}\n",
-                            style("📍").fg(Color::Cyan),
-                            style("行号范围:").fg(Color::Cyan).bold(),
-                            style(format!(
-                                "旧文件:{}-{}",
-                                hunk.old_start(),
-                                if hunk.old_lines() > 0 {
-                                    hunk.old_start() + hunk.old_lines() - 1
-                                } else {
-                                    hunk.old_start()
-                                }
-                            ))
-                            .fg(Color::Red)
-                            .bold(),
-                            style("→").fg(Color::White),
-                            style(format!(
-                                "新文件:{}-{}",
-                                hunk.new_start(),
-                                if hunk.new_lines() > 0 {
-                                    hunk.new_start() + hunk.new_lines() - 1
-                                } else {
-                                    hunk.new_start()
-                                }
-                            ))
-                            .fg(Color::Green)
-                            .bold()
-                        ));
-                    } else if content.starts_with("@@") {
-                        // Manually parse hunk header information
-                        let parts: Vec<&str> = content.split_whitespace().collect();
-                        if parts.len() >= 3 {
-                            // Parse -old_start,old_count
-                            if let Some(old_part) = parts.get(1) {
-                                if let Some(old_start_str) = old_part.strip_prefix('-') {
+                        } else if content.starts_with("@@") {
+                            // Manually parse hunk header information
+                            let parts: Vec<&str> = content.split_whitespace().collect();
+                            if parts.len() >= 3 {
+                                // Parse -old_start,old_count
+                                if let Some(old_part) = parts.get(1)
+                                    && let Some(old_start_str) = old_part.strip_prefix('-')
+                                {
                                     if let Some(comma_pos) = old_start_str.find(',') {
                                         if let Ok(start) = old_start_str[..comma_pos].parse::<i32>()
                                         {
@@ -475,10 +1727,10 @@ impl<'a> DiffOperations<'a> {
                                         hunk_initialized = true;
                                     }
                                 }
-                            }
-                            // Parse +new_start,new_count
-                            if let Some(new_part) = parts.get(2) {
-                                if let Some(new_start_str) = new_part.strip_prefix('+') {
+                                // Parse +new_start,new_count
+                                if let Some(new_part) = parts.get(2)
+                                    && let Some(new_start_str) = new_part.strip_prefix('+')
+                                {
                                     if let Some(comma_pos) = new_start_str.find(',') {
                                         if let Ok(start) = new_start_str[..comma_pos].parse::<i32>()
                                         {
@@ -489,114 +1741,119 @@ impl<'a> DiffOperations<'a> {
                                     }
                                 }
                             }
-                        }
 
-                        result.push_str(&format!(
-                            "{} {} {} {} {}\n",
-                            style("📍").fg(Color::Cyan),
-                            style("行号范围:").fg(Color::Cyan).bold(),
-                            style(format!("旧文件:{old_line_num}"))
-                                .fg(Color::Red)
-                                .bold(),
-                            style("→").fg(Color::White),
-                            style(format!("新文件:{new_line_num}"))
-                                .fg(Color::Green)
-                                .bold()
-                        ));
-                    } else {
-                        // Other @ prefixed lines
-                        result.push_str(&format!(
-                            "{} {}\n",
-                            style("📍").fg(Color::Cyan),
-                            style(content.trim()).fg(Color::Cyan).bold()
-                        ));
-                    }
-                }
-                '+' => {
-                    // Added line
-                    if let Some(stats) = file_stats.get_mut(&current_file) {
-                        stats.0 += 1;
+                            result.push_str(&format!(
+                                "{}{} {} {} {}\n",
+                                style(Icon::Location.prefix()).fg(Color::Cyan),
+                                style("行号范围:").fg(Color::Cyan).bold(),
+                                style(format!("旧文件:{old_line_num}"))
+                                    .fg(Color::Red)
+                                    .bold(),
+                                style(Icon::Arrow.glyph()).fg(Color::White),
+                                style(format!("新文件:{new_line_num}"))
+                                    .fg(Color::Green)
+                                    .bold()
+                            ));
+                        } else {
+                            // Other @ prefixed lines
+                            result.push_str(&format!(
+                                "{}{}\n",
+                                style(Icon::Location.prefix()).fg(Color::Cyan),
+                                style(content.trim()).fg(Color::Cyan).bold()
+                            ));
+                        }
                     }
-                    if hunk_initialized {
-                        result.push_str(&format!(
-                            "{} {} {}",
-                            style(format!("{:>4}", "")).fg(Color::White).dim(),
-                            style(format!("{new_line_num:>4}")).fg(Color::Green).bold(),
-                            style(format!("+ {content}")).fg(Color::Green)
-                        ));
-                        new_line_num += 1;
-                    } else {
-                        result.push_str(&format!("+ {}", style(content).fg(Color::Green)));
+                    '+' => {
+                        // Added line
+                        if let Some(stats) = file_stats.get_mut(&current_file) {
+                            stats.0 += 1;
+                        }
+                        let moved_line = is_moved('+', line.new_lineno());
+                        let marker = ui::diff_marker('+', moved_line);
+                        let color = if moved_line {
+                            Color::Cyan
+                        } else {
+                            Color::Green
+                        };
+                        if hunk_initialized {
+                            result.push_str(&format!(
+                                "{} {} {}",
+                                style(format!("{:>4}", "")).fg(Color::White).dim(),
+                                style(format!("{new_line_num:>4}")).fg(color).bold(),
+                                style(format!("{marker} {content}")).fg(color)
+                            ));
+                            new_line_num += 1;
+                        } else {
+                            result.push_str(&format!("{marker} {}", style(content).fg(color)));
+                        }
                     }
-                }
-                '-' => {
-                    // Deleted line
-                    if let Some(stats) = file_stats.get_mut(&current_file) {
-                        stats.1 += 1;
+                    '-' => {
+                        // Deleted line
+                        if let Some(stats) = file_stats.get_mut(&current_file) {
+                            stats.1 += 1;
+                        }
+                        let moved_line = is_moved('-', line.old_lineno());
+                        let marker = ui::diff_marker('-', moved_line);
+                        let color = if moved_line {
+                            Color::Magenta
+                        } else {
+                            Color::Red
+                        };
+                        if hunk_initialized {
+                            result.push_str(&format!(
+                                "{} {} {}",
+                                style(format!("{old_line_num:>4}")).fg(color).bold(),
+                                style(format!("{:>4}", "")).fg(Color::White).dim(),
+                                style(format!("{marker} {content}")).fg(color)
+                            ));
+                            old_line_num += 1;
+                        } else {
+                            result.push_str(&format!("{marker} {}", style(content).fg(color)));
+                        }
                     }
-                    if hunk_initialized {
-                        result.push_str(&format!(
-                            "{} {} {}",
-                            style(format!("{old_line_num:>4}")).fg(Color::Red).bold(),
-                            style(format!("{:>4}", "")).fg(Color::White).dim(),
-                            style(format!("- {content}")).fg(Color::Red)
-                        ));
-                        old_line_num += 1;
-                    } else {
-                        result.push_str(&format!("- {}", style(content).fg(Color::Red)));
+                    ' ' => {
+                        // Context line
+                        if hunk_initialized {
+                            result.push_str(&format!(
+                                "{} {} {}",
+                                style(format!("{old_line_num:>4}")).fg(Color::White).dim(),
+                                style(format!("{new_line_num:>4}")).fg(Color::White).dim(),
+                                style(format!("{} {content}", ui::diff_marker(' ', false))).dim()
+                            ));
+                            old_line_num += 1;
+                            new_line_num += 1;
+                        } else {
+                            result.push_str(&format!(
+                                "{} {}",
+                                ui::diff_marker(' ', false),
+                                style(content).dim()
+                            ));
+                        }
                     }
-                }
-                ' ' => {
-                    // Context line
-                    if hunk_initialized {
-                        result.push_str(&format!(
-                            "{} {} {}",
-                            style(format!("{old_line_num:>4}")).fg(Color::White).dim(),
-                            style(format!("{new_line_num:>4}")).fg(Color::White).dim(),
-                            style(format!("  {content}")).dim()
-                        ));
-                        old_line_num += 1;
-                        new_line_num += 1;
-                    } else {
-                        result.push_str(&format!("  {}", style(content).dim()));
+                    _ => {
+                        // Other origins - skip
                     }
                 }
-                _ => {
-                    // Other origins - skip
-                    result.push_str("");
-                }
-            }
-            true
-        })
-        .map_err(CheckpointError::GitOperationFailed)?;
+                true
+            })
+            .map_err(CheckpointError::GitOperationFailed)?;
 
         // Process remaining pending changes
         if in_newline_context && (!pending_deletions.is_empty() || !pending_additions.is_empty()) {
-            self.handle_remaining_newline_changes(
+            Self::handle_remaining_newline_changes(
                 &mut result,
                 &pending_deletions,
                 &pending_additions,
-                &mut file_stats,
+                file_stats,
                 &current_file,
             );
         }
 
-        if result.is_empty() {
-            return Ok(format!(
-                "{} {}\n",
-                style("ℹ️").fg(Color::Blue),
-                style("没有发现文件差异").fg(Color::Yellow)
-            ));
-        }
-
-        // Add statistics summary
-        let summary = self.generate_diff_summary(&file_stats);
-        Ok(format!("{result}{summary}"))
+        Ok(result)
     }
 
     /// Helper method to format pending changes
     fn format_pending_changes(
-        &self,
         result: &mut String,
         pending_deletions: &[(String, i32)],
         pending_additions: &[(String, i32)],
@@ -611,7 +1868,7 @@ impl<'a> DiffOperations<'a> {
                 "{} {} {}\n",
                 style(format!("{del_line:>4}")).fg(Color::Red).bold(),
                 style(format!("{:>4}", "")).fg(Color::White).dim(),
-                style(format!("- {del_content}")).fg(Color::Red)
+                style(format!("{} {del_content}", ui::diff_marker('-', false))).fg(Color::Red)
             ));
         }
         for (add_content, add_line) in pending_additions {
@@ -622,14 +1879,13 @@ impl<'a> DiffOperations<'a> {
                 "{} {} {}\n",
                 style(format!("{:>4}", "")).fg(Color::White).dim(),
                 style(format!("{add_line:>4}")).fg(Color::Green).bold(),
-                style(format!("+ {add_content}")).fg(Color::Green)
+                style(format!("{} {add_content}", ui::diff_marker('+', false))).fg(Color::Green)
             ));
         }
     }
 
     /// Handle pending newline-related changes with intelligent processing
     fn handle_pending_newline_changes(
-        &self,
         result: &mut String,
         pending_deletions: &[(String, i32)],
         pending_additions: &[(String, i32)],
@@ -649,7 +1905,12 @@ impl<'a> DiffOperations<'a> {
                     "{} {} {}\n",
                     style(format!("{del_line:>4}")).fg(Color::White).dim(),
                     style(format!("{add1_line:>4}")).fg(Color::White).dim(),
-                    style(format!("  {}", del_content.trim())).dim()
+                    style(format!(
+                        "{} {}",
+                        ui::diff_marker(' ', false),
+                        del_content.trim()
+                    ))
+                    .dim()
                 ));
 
                 // Show the new line
@@ -660,11 +1921,16 @@ impl<'a> DiffOperations<'a> {
                     "{} {} {}\n",
                     style(format!("{:>4}", "")).fg(Color::White).dim(),
                     style(format!("{add2_line:>4}")).fg(Color::Green).bold(),
-                    style(format!("+ {}", add2_content.trim())).fg(Color::Green)
+                    style(format!(
+                        "{} {}",
+                        ui::diff_marker('+', false),
+                        add2_content.trim()
+                    ))
+                    .fg(Color::Green)
                 ));
             } else {
                 // Cannot intelligently handle, fall back to original display
-                self.format_pending_changes(
+                Self::format_pending_changes(
                     result,
                     pending_deletions,
                     pending_additions,
@@ -674,7 +1940,7 @@ impl<'a> DiffOperations<'a> {
             }
         } else {
             // Other cases, fall back to original display
-            self.format_pending_changes(
+            Self::format_pending_changes(
                 result,
                 pending_deletions,
                 pending_additions,
@@ -686,7 +1952,6 @@ impl<'a> DiffOperations<'a> {
 
     /// Handle remaining newline-related changes with intelligent processing
     fn handle_remaining_newline_changes(
-        &self,
         result: &mut String,
         pending_deletions: &[(String, i32)],
         pending_additions: &[(String, i32)],
@@ -705,7 +1970,12 @@ impl<'a> DiffOperations<'a> {
                     "{} {} {}\n",
                     style(format!("{del_line:>4}")).fg(Color::White).dim(),
                     style(format!("{add1_line:>4}")).fg(Color::White).dim(),
-                    style(format!("  {}", del_content.trim())).dim()
+                    style(format!(
+                        "{} {}",
+                        ui::diff_marker(' ', false),
+                        del_content.trim()
+                    ))
+                    .dim()
                 ));
 
                 // Show the new line
@@ -716,11 +1986,16 @@ impl<'a> DiffOperations<'a> {
                     "{} {} {}\n",
                     style(format!("{:>4}", "")).fg(Color::White).dim(),
                     style(format!("{add2_line:>4}")).fg(Color::Green).bold(),
-                    style(format!("+ {}", add2_content.trim())).fg(Color::Green)
+                    style(format!(
+                        "{} {}",
+                        ui::diff_marker('+', false),
+                        add2_content.trim()
+                    ))
+                    .fg(Color::Green)
                 ));
             } else {
                 // Cannot intelligently handle, fall back to original display
-                self.format_pending_changes(
+                Self::format_pending_changes(
                     result,
                     pending_deletions,
                     pending_additions,
@@ -735,7 +2010,8 @@ impl<'a> DiffOperations<'a> {
             let (add_content, add_line) = &pending_additions[0];
 
             println!(
-                "🔍 比较内容: del1='{}' + del2='{}' vs add='{}'",
+                "{}比较内容: del1='{}' + del2='{}' vs add='{}'",
+                Icon::Search.prefix(),
                 del1_content.trim(),
                 del2_content.trim(),
                 add_content.trim()
@@ -743,14 +2019,19 @@ impl<'a> DiffOperations<'a> {
 
             // Check if it's: delete "content1\n" + delete "content2" -> add "content1" (remove second line)
             if del1_content.trim() == add_content.trim() {
-                println!("🔍 智能优化生效（删除换行符）！");
+                println!("{}智能优化生效（删除换行符）！", Icon::Search.prefix());
 
                 // Show as removing second line, first line remains unchanged
                 result.push_str(&format!(
                     "{} {} {}\n",
                     style(format!("{del1_line:>4}")).fg(Color::White).dim(),
                     style(format!("{add_line:>4}")).fg(Color::White).dim(),
-                    style(format!("  {}", del1_content.trim())).dim()
+                    style(format!(
+                        "{} {}",
+                        ui::diff_marker(' ', false),
+                        del1_content.trim()
+                    ))
+                    .dim()
                 ));
 
                 // Show deleted second line
@@ -761,11 +2042,16 @@ impl<'a> DiffOperations<'a> {
                     "{} {} {}\n",
                     style(format!("{del2_line:>4}")).fg(Color::Red).bold(),
                     style(format!("{:>4}", "")).fg(Color::White).dim(),
-                    style(format!("- {}", del2_content.trim())).fg(Color::Red)
+                    style(format!(
+                        "{} {}",
+                        ui::diff_marker('-', false),
+                        del2_content.trim()
+                    ))
+                    .fg(Color::Red)
                 ));
             } else {
                 // Cannot intelligently handle, fall back to original display
-                self.format_pending_changes(
+                Self::format_pending_changes(
                     result,
                     pending_deletions,
                     pending_additions,
@@ -775,7 +2061,7 @@ impl<'a> DiffOperations<'a> {
             }
         } else {
             // Other cases, fall back to original display
-            self.format_pending_changes(
+            Self::format_pending_changes(
                 result,
                 pending_deletions,
                 pending_additions,
@@ -786,11 +2072,19 @@ impl<'a> DiffOperations<'a> {
     }
 
     /// Generate a summary of diff statistics
-    fn generate_diff_summary(&self, file_stats: &HashMap<String, (i32, i32)>) -> String {
+    ///
+    /// `generated` holds the file, addition and deletion counts of the
+    /// collapsed generated files, reported on their own line.
+    fn generate_diff_summary(
+        file_stats: &HashMap<String, (i32, i32)>,
+        generated: (usize, i32, i32),
+    ) -> String {
         let mut summary = String::new();
         summary.push_str(&format!(
             "\n{}\n",
-            style("─".repeat(80)).fg(Color::Blue).dim()
+            style(Icon::Separator.repeat(ui::width()))
+                .fg(Color::Blue)
+                .dim()
         ));
 
         let total_files = file_stats.len();
@@ -798,8 +2092,8 @@ impl<'a> DiffOperations<'a> {
         let total_deletions: i32 = file_stats.values().map(|(_, d)| *d).sum();
 
         summary.push_str(&format!(
-            "{} {} {} 个文件变更",
-            style("📊").fg(Color::Blue),
+            "{}{} {} 个文件变更",
+            style(Icon::Stats.prefix()).fg(Color::Blue),
             style("统计:").fg(Color::White).bold(),
             style(total_files).fg(Color::Cyan).bold()
         ));
@@ -825,11 +2119,24 @@ impl<'a> DiffOperations<'a> {
             }
         }
         summary.push('\n');
+        if generated.0 > 0 {
+            summary.push_str(&format!(
+                "{}{}\n",
+                style(Icon::Note.prefix()).fg(Color::White).dim(),
+                style(t!(
+                    "diff_generated_summary",
+                    count = generated.0,
+                    additions = generated.1,
+                    deletions = generated.2
+                ))
+                .dim()
+            ));
+        }
 
         // Add line number explanation
         summary.push_str(&format!(
-            "{} {} {} {} {}\n",
-            style("💡").fg(Color::Yellow),
+            "{}{} {} {} {}\n",
+            style(Icon::Hint.prefix()).fg(Color::Yellow),
             style("行号格式:").fg(Color::White).bold(),
             style("旧行号").fg(Color::Red),
             style("新行号").fg(Color::Green),
@@ -839,3 +2146,82 @@ impl<'a> DiffOperations<'a> {
         summary
     }
 }
+
+/// Hash the files below `dir` into a tree of `repo`
+///
+/// # Arguments
+/// * `rules` - The repository whose ignore rules decide which paths are skipped
+/// * `prefix` - Path of `dir` relative to the compared directory, `/`-terminated
+///
+/// # Returns
+/// None when nothing below `dir` is kept, since git has no empty trees
+fn write_directory_tree(
+    repo: &Repository,
+    rules: &Repository,
+    dir: &Path,
+    prefix: &OsStr,
+) -> CcResult<Option<Oid>> {
+    let mut builder = repo.treebuilder(None)?;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == ".git" {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        // 文件名可能不是合法的 UTF-8，按原始字节拼接
+        let mut relative = prefix.to_os_string();
+        relative.push(&name);
+        if file_type.is_dir() {
+            // 以 / 结尾时按目录匹配 `target/` 之类的规则
+            relative.push("/");
+        }
+        if rules
+            .status_should_ignore(Path::new(&relative))
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        let (oid, mode) = if file_type.is_dir() {
+            match write_directory_tree(repo, rules, &entry.path(), &relative)? {
+                Some(oid) => (oid, FileMode::Tree),
+                None => continue,
+            }
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(entry.path())?;
+            (
+                repo.blob(target.as_os_str().as_encoded_bytes())?,
+                FileMode::Link,
+            )
+        } else {
+            let metadata = entry.metadata()?;
+            (repo.blob(&fs::read(entry.path())?)?, blob_mode(&metadata))
+        };
+        builder.insert(Path::new(&name), oid, i32::from(mode))?;
+    }
+    if builder.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(builder.write()?))
+}
+
+/// File mode git would record for a regular file
+pub(super) fn blob_mode(metadata: &fs::Metadata) -> FileMode {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 != 0 {
+            return FileMode::BlobExecutable;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    FileMode::Blob
+}
+
+/// Number of lines in a file's content, counting a last line without a newline
+fn count_lines(content: &[u8]) -> i32 {
+    let newlines = content.iter().filter(|&&byte| byte == b'\n').count();
+    let unterminated = !content.is_empty() && !content.ends_with(b"\n");
+    (newlines + usize::from(unterminated)) as i32
+}
@@ -0,0 +1,92 @@
+//! Files left out of checkpoints
+//!
+//! `ccg.checkpoint.exclude` lists pathspecs, such as `node_modules` or
+//! `*.ckpt`, that are never staged into a checkpoint even when git does not
+//! ignore them, and `ccg.checkpoint.maxFileSize` skips any file larger than
+//! the limit, which keeps large lockfiles and model weights out as well.
+//! `ccg init --profile <name>` fills both from a built-in
+//! [`ExcludeProfile`]; afterwards they are ordinary git config values.
+//!
+//! Only new and modified files are filtered. A file the user already
+//! committed stays in checkpoints as it is in the index.
+
+use crate::config::{
+    CcgConfig, ExcludeProfile, KEY_CHECKPOINT_EXCLUDE, KEY_CHECKPOINT_MAX_FILE_SIZE,
+};
+use crate::error::Result as CcResult;
+use git2::{Pathspec, PathspecFlags, Repository};
+use std::path::Path;
+
+/// The exclusion rules of a repository
+pub struct ExcludeRules {
+    spec: Option<Pathspec>,
+    max_file_size: Option<u64>,
+    workdir: Option<std::path::PathBuf>,
+}
+
+impl ExcludeRules {
+    /// Read the rules from the repository's configuration
+    pub fn load(repo: &Repository) -> Self {
+        let config = CcgConfig::load(repo);
+        let spec = if config.checkpoint_exclude.is_empty() {
+            None
+        } else {
+            Pathspec::new(config.checkpoint_exclude.iter()).ok()
+        };
+        Self {
+            spec,
+            max_file_size: config.checkpoint_max_file_size.filter(|size| *size > 0),
+            workdir: repo.workdir().map(Path::to_path_buf),
+        }
+    }
+
+    /// Whether `path`, relative to the repository root, is left out
+    pub fn excludes(&self, path: &Path) -> bool {
+        if let Some(spec) = &self.spec
+            && spec.matches_path(path, PathspecFlags::DEFAULT)
+        {
+            return true;
+        }
+        match (self.max_file_size, &self.workdir) {
+            (Some(limit), Some(workdir)) => workdir
+                .join(path)
+                .metadata()
+                .is_ok_and(|meta| meta.is_file() && meta.len() > limit),
+            _ => false,
+        }
+    }
+
+    /// Callback for `Index::add_all` that skips excluded paths
+    pub fn add_filter(&self) -> impl FnMut(&Path, &[u8]) -> i32 + '_ {
+        |path, _| i32::from(self.excludes(path))
+    }
+
+    /// Add a profile's patterns to `ccg.checkpoint.exclude` in the
+    /// repository's own configuration, skipping those already listed, and set
+    /// `ccg.checkpoint.maxFileSize` unless it is configured
+    ///
+    /// # Returns
+    /// The patterns that were added
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if the configuration
+    /// cannot be written
+    pub fn apply_profile(repo: &Repository, profile: ExcludeProfile) -> CcResult<Vec<String>> {
+        let current = CcgConfig::load(repo);
+        let mut config = repo.config()?.open_level(git2::ConfigLevel::Local)?;
+        let mut added = Vec::new();
+        for pattern in profile.patterns() {
+            if current.checkpoint_exclude.iter().any(|p| p == pattern) {
+                continue;
+            }
+            // 正则不匹配任何已有值，git 会追加一项
+            config.set_multivar(KEY_CHECKPOINT_EXCLUDE, "^$ccg-never^", pattern)?;
+            added.push(pattern.to_string());
+        }
+        if current.checkpoint_max_file_size.is_none() {
+            let size = i64::try_from(profile.max_file_size()).unwrap_or(i64::MAX);
+            config.set_i64(KEY_CHECKPOINT_MAX_FILE_SIZE, size)?;
+        }
+        Ok(added)
+    }
+}
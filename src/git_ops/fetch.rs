@@ -0,0 +1,180 @@
+//! Fetching checkpoints from a remote
+//!
+//! `ccg fetch` copies another clone's CCG branch into
+//! `refs/remotes/<remote>/ccg` and fast-forwards the local CCG branch to it.
+//! With a depth only the most recent checkpoints are transferred: git
+//! negotiates a shallow fetch and records the cut in `.git/shallow`, which
+//! list, show and diff already treat as the start of history.
+//!
+//! Published user namespaces (`refs/ccg/users/*`, see
+//! [`UserNamespaces`](super::users::UserNamespaces)) are fetched alongside
+//! and then expired by their retention policy.
+//!
+//! The transfer is delegated to the `git` executable, which speaks every
+//! transport and honours the user's credential helpers; libgit2's local
+//! transport ignores the requested depth.
+
+use super::commit::ordered_history;
+use super::types::{CCG_BRANCH_NAME, FetchReport, FetchUpdate, USERS_REF_PREFIX};
+use super::users::UserNamespaces;
+use crate::error::{CheckpointError, Result as CcResult};
+use git2::{BranchType, Oid, Repository};
+use std::process::{Command, Stdio};
+
+/// Fetches checkpoints from remotes
+pub struct FetchOperations<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> FetchOperations<'a> {
+    /// Create a new FetchOperations instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// Remote-tracking ref holding `remote`'s CCG branch
+    pub fn tracking_ref(remote: &str) -> String {
+        format!("refs/remotes/{remote}/{CCG_BRANCH_NAME}")
+    }
+
+    /// Fetch `remote`'s CCG branch and bring the local one up to date
+    ///
+    /// # Arguments
+    /// * `remote` - Name of a configured remote
+    /// * `depth` - Only fetch this many of the most recent checkpoints
+    ///
+    /// # Returns
+    /// What was fetched and how the local CCG branch changed. A local branch
+    /// that has diverged from the remote is left alone.
+    ///
+    /// # Errors
+    /// Returns CheckpointError::InvalidArgument for an unknown remote or a
+    /// depth of 0, CheckpointError::BranchNotFound when the remote has
+    /// neither a CCG branch nor user namespaces, and
+    /// CheckpointError::GitOperationFailed when git fails
+    pub fn fetch(&self, remote: &str, depth: Option<u32>) -> CcResult<FetchReport> {
+        if self.repo.find_remote(remote).is_err() {
+            return Err(CheckpointError::InvalidArgument(format!(
+                "unknown remote: {remote}"
+            )));
+        }
+        if depth == Some(0) {
+            return Err(CheckpointError::InvalidArgument(
+                "--depth must be at least 1".to_string(),
+            ));
+        }
+
+        let tracking = Self::tracking_ref(remote);
+        let branch = format!("refs/heads/{CCG_BRANCH_NAME}");
+        let users = format!("{USERS_REF_PREFIX}*");
+        // 团队模式下远程可能只有用户命名空间，先确认有哪些可获取
+        let advertised = git_output(self.repo, &["ls-remote", remote, &branch, &users])?;
+        let has_branch = advertised.lines().any(|line| line.ends_with(&branch));
+        let has_users = advertised.contains(USERS_REF_PREFIX);
+        if !has_branch && !has_users {
+            return Err(CheckpointError::BranchNotFound(format!(
+                "{remote}/{CCG_BRANCH_NAME}"
+            )));
+        }
+
+        let depth_arg = depth.map(|depth| format!("--depth={depth}"));
+        let branch_spec = format!("+{branch}:{tracking}");
+        let users_spec = format!("+{users}:{users}");
+        let mut args = vec!["fetch", "--no-tags", "--quiet"];
+        args.extend(depth_arg.as_deref());
+        args.push(remote);
+        if has_branch {
+            args.push(&branch_spec);
+        }
+        if has_users {
+            args.push(&users_spec);
+        }
+        run_git(self.repo, &args)?;
+
+        // libgit2 只在打开仓库时读取 .git/shallow，重新打开才能看到新的浅克隆边界
+        let repo = Repository::open(self.repo.path())?;
+        let (tip, update, available) = if has_branch {
+            let tip = repo
+                .find_reference(&tracking)?
+                .target()
+                .ok_or_else(|| CheckpointError::BranchNotFound(tracking.clone()))?;
+            let update = Self::update_local_branch(&repo, tip)?;
+            let available = ordered_history(&repo, tip, usize::MAX)?.len();
+            (Some(tip.to_string()), Some(update), available)
+        } else {
+            (None, None, 0)
+        };
+        let namespaces = UserNamespaces::new(&repo);
+        let users = if has_users {
+            namespaces
+                .list()?
+                .into_iter()
+                .map(|user| user.name)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok(FetchReport {
+            remote: remote.to_string(),
+            tip,
+            update,
+            available,
+            shallow: depth.is_some() && repo.is_shallow(),
+            users,
+            expired: namespaces.expire(chrono::Utc::now().timestamp())?,
+        })
+    }
+
+    /// Point the local CCG branch at `tip` when that loses no checkpoints
+    fn update_local_branch(repo: &Repository, tip: Oid) -> CcResult<FetchUpdate> {
+        let commit = repo.find_commit(tip)?;
+        let Ok(mut branch) = repo.find_branch(CCG_BRANCH_NAME, BranchType::Local) else {
+            repo.branch(CCG_BRANCH_NAME, &commit, false)?;
+            return Ok(FetchUpdate::Created);
+        };
+        let local = branch.get().peel_to_commit()?.id();
+        if local == tip || repo.graph_descendant_of(local, tip)? {
+            return Ok(FetchUpdate::UpToDate);
+        }
+        if !repo.graph_descendant_of(tip, local)? {
+            return Ok(FetchUpdate::Diverged);
+        }
+
+        // 检出的正是 ccg 分支时同步更新工作目录；有冲突的本地修改时在移动分支前失败
+        if branch.is_head() && repo.workdir().is_some() {
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.safe();
+            repo.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+        }
+        branch
+            .get_mut()
+            .set_target(tip, &format!("ccg fetch: fast-forward to {tip}"))?;
+        Ok(FetchUpdate::FastForward)
+    }
+}
+
+/// Run git on the repository and return its standard output
+///
+/// # Errors
+/// Returns CheckpointError::IoError if git cannot be started and
+/// CheckpointError::GitOperationFailed with git's message if it fails
+pub(super) fn git_output(repo: &Repository, args: &[&str]) -> CcResult<String> {
+    let output = Command::new("git")
+        .arg("--git-dir")
+        .arg(repo.path())
+        .args(args)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CheckpointError::GitOperationFailed(git2::Error::from_str(
+            stderr.trim(),
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run git on the repository, discarding its output
+pub(super) fn run_git(repo: &Repository, args: &[&str]) -> CcResult<()> {
+    git_output(repo, args).map(|_| ())
+}
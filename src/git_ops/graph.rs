@@ -0,0 +1,87 @@
+//! The checkpoint history as a typed DAG
+//!
+//! Front-ends that draw the history need the commits, their parent links and
+//! the restores that cut lines of checkpoints off the branch. `GraphBuilder`
+//! collects them in one walk from the CCG tip and the discarded tips of the
+//! restore journal, so each front-end does not repeat the revwalk and the
+//! parsing of journal entries.
+
+use super::branch::BranchOperations;
+use super::commit::{CommitOperations, checkpoint_entry, ordered_history_from};
+use super::ids::CheckpointIds;
+use super::journal::JournalOperations;
+use super::types::{CheckpointGraph, GraphEdge, GraphNode};
+use crate::error::Result as CcResult;
+use git2::{Oid, Repository, Sort};
+use std::collections::HashSet;
+
+/// Builds the [`CheckpointGraph`] of a repository
+pub struct GraphBuilder<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> GraphBuilder<'a> {
+    /// Create a new GraphBuilder instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// Build the graph of the CCG branch and the checkpoints restores
+    /// discarded
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if the CCG branch does not exist
+    pub fn build(&self) -> CcResult<CheckpointGraph> {
+        let tip = BranchOperations::new(self.repo)
+            .get_ccg_branch()?
+            .get()
+            .peel_to_commit()?
+            .id();
+        let restores = JournalOperations::new(self.repo).restores()?;
+        let mut tips = vec![tip];
+        // 被丢弃的末端由恢复日志保持可达；找不到的（例如日志被手动删减）直接跳过
+        tips.extend(
+            restores
+                .iter()
+                .filter_map(|record| Oid::from_str(&record.discarded_tip).ok())
+                .filter(|id| self.repo.find_commit(*id).is_ok()),
+        );
+
+        let mut on_branch = HashSet::new();
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::NONE)?;
+        revwalk.push(tip)?;
+        for id in revwalk {
+            on_branch.insert(id?);
+        }
+
+        let ids = CheckpointIds::new(self.repo).by_hash()?;
+        let tags = CommitOperations::new(self.repo).tags()?;
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        for commit in ordered_history_from(self.repo, &tips, usize::MAX)? {
+            let entry = checkpoint_entry(&commit, &ids);
+            let parents: Vec<String> = commit.parent_ids().map(|id| id.to_string()).collect();
+            for (i, parent) in parents.iter().enumerate() {
+                edges.push(GraphEdge {
+                    child: entry.hash.clone(),
+                    parent: parent.clone(),
+                    first_parent: i == 0,
+                });
+            }
+            nodes.push(GraphNode {
+                tags: tags.get(&entry.hash).cloned().unwrap_or_default(),
+                on_branch: on_branch.contains(&commit.id()),
+                entry,
+                parents,
+            });
+        }
+
+        Ok(CheckpointGraph {
+            tip: tip.to_string(),
+            nodes,
+            edges,
+            restores,
+        })
+    }
+}
@@ -0,0 +1,127 @@
+//! Filtering of hook events before they create checkpoints
+//!
+//! The Claude Code hook fires for every matching tool call. `ccg.hooks.tools`
+//! narrows that down to the tools that should checkpoint, and
+//! `ccg.hooks.paths` to the files that matter, as pathspecs relative to the
+//! repository root. A filtered event creates nothing; it is appended to
+//! `.git/ccg/hook-filter.log`, one JSON object per line, so a missing
+//! checkpoint can be traced back to the configuration.
+
+use super::ccg_dir;
+use super::types::{FilteredEvent, HookSkip};
+use crate::config::CcgConfig;
+use crate::error::Result as CcResult;
+use git2::{Pathspec, PathspecFlags, Repository};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Decides which hook events create checkpoints
+pub struct HookFilter<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> HookFilter<'a> {
+    /// Create a new HookFilter instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// File holding the filtered events
+    pub fn path(&self) -> PathBuf {
+        ccg_dir(self.repo).join("hook-filter.log")
+    }
+
+    /// Check a hook event against the configuration
+    ///
+    /// Tool names are compared without regard to case. An event that names no
+    /// files, such as a Bash call, is only filtered by tool.
+    ///
+    /// # Arguments
+    /// * `tool` - The hook's `tool_name`
+    /// * `files` - Files the event names, absolute or relative to the
+    ///   repository root
+    ///
+    /// # Returns
+    /// Why the event is filtered, or None if it should create a checkpoint
+    pub fn check(&self, tool: &str, files: &[&str]) -> Option<HookSkip> {
+        let config = CcgConfig::load(self.repo);
+        if !config.hook_tools.is_empty()
+            && !config
+                .hook_tools
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(tool))
+        {
+            return Some(HookSkip::Tool {
+                tool: tool.to_string(),
+            });
+        }
+        if config.hook_paths.is_empty() || files.is_empty() {
+            return None;
+        }
+        let spec = Pathspec::new(config.hook_paths.iter()).ok()?;
+        let relative: Vec<String> = files
+            .iter()
+            .map(|file| relative_path(self.repo, file))
+            .collect();
+        let in_scope = relative
+            .iter()
+            .any(|file| spec.matches_path(Path::new(file), PathspecFlags::DEFAULT));
+        (!in_scope).then_some(HookSkip::Path { files: relative })
+    }
+
+    /// Append a filtered event to the log
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the log cannot be written
+    pub fn record(&self, tool: &str, skip: HookSkip) -> CcResult<FilteredEvent> {
+        let event = FilteredEvent {
+            time: chrono::Utc::now().timestamp(),
+            tool: tool.to_string(),
+            skip,
+        };
+        let path = self.path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let line = serde_json::to_string(&event).map_err(std::io::Error::other)?;
+        writeln!(file, "{line}")?;
+        Ok(event)
+    }
+
+    /// All recorded events, oldest first; unreadable lines are skipped
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the log exists but cannot be read
+    pub fn entries(&self) -> CcResult<Vec<FilteredEvent>> {
+        let content = match fs::read_to_string(self.path()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
+
+/// `file` relative to the working directory when it lies inside it
+pub(super) fn relative_path(repo: &Repository, file: &str) -> String {
+    let path = Path::new(file);
+    let Some(workdir) = repo.workdir() else {
+        return file.to_string();
+    };
+    if let Ok(relative) = path.strip_prefix(workdir) {
+        return relative.to_string_lossy().into_owned();
+    }
+    // 钩子传入的路径可能经过符号链接（如 macOS 的 /var 与 /private/var）
+    match (path.canonicalize(), workdir.canonicalize()) {
+        (Ok(path), Ok(workdir)) => match path.strip_prefix(&workdir) {
+            Ok(relative) => relative.to_string_lossy().into_owned(),
+            Err(_) => file.to_string(),
+        },
+        _ => file.to_string(),
+    }
+}
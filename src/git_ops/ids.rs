@@ -0,0 +1,136 @@
+//! Human-friendly checkpoint IDs
+//!
+//! Every checkpoint gets a short, monotonically increasing ID (`cp-1`,
+//! `cp-2`, …) recorded in a metadata index at `.git/ccg/ids`, one
+//! `<number> <hash>` pair per line. IDs are never reused: checkpoints
+//! discarded by a restore keep theirs, and rewritten checkpoints carry their
+//! ID over to the new hash.
+
+use super::ccg_dir;
+use super::types::{CHECKPOINT_ID_PREFIX, LATEST_REF};
+use crate::error::Result as CcResult;
+use git2::Repository;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+/// Parse a `cp-N` checkpoint ID
+///
+/// # Returns
+/// The number, or None if `text` is not a checkpoint ID
+pub fn parse_checkpoint_id(text: &str) -> Option<u64> {
+    text.strip_prefix(CHECKPOINT_ID_PREFIX)?
+        .parse()
+        .ok()
+        .filter(|n| *n > 0)
+}
+
+/// Parse a `latest` or `latest~N` reference
+///
+/// # Returns
+/// The position in list order (0 for the newest checkpoint), or None if
+/// `text` is not such a reference
+pub fn parse_latest_ref(text: &str) -> Option<usize> {
+    match text.strip_prefix(LATEST_REF)? {
+        "" => Some(0),
+        offset => offset.strip_prefix('~')?.parse().ok(),
+    }
+}
+
+/// Format a checkpoint number as a `cp-N` ID
+pub fn format_checkpoint_id(number: u64) -> String {
+    format!("{CHECKPOINT_ID_PREFIX}{number}")
+}
+
+/// The checkpoint ID metadata index
+pub struct CheckpointIds<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> CheckpointIds<'a> {
+    /// Create a new CheckpointIds instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    fn path(&self) -> PathBuf {
+        ccg_dir(self.repo).join("ids")
+    }
+
+    /// All assigned IDs, keyed by number
+    pub fn load(&self) -> CcResult<BTreeMap<u64, String>> {
+        let content = match fs::read_to_string(self.path()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| {
+                let (number, hash) = line.split_once(' ')?;
+                Some((number.parse().ok()?, hash.trim().to_string()))
+            })
+            .collect())
+    }
+
+    fn save(&self, ids: &BTreeMap<u64, String>) -> CcResult<()> {
+        let path = self.path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content: String = ids
+            .iter()
+            .map(|(number, hash)| format!("{number} {hash}\n"))
+            .collect();
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Number assigned to each hash
+    pub fn by_hash(&self) -> CcResult<HashMap<String, u64>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .map(|(number, hash)| (hash, number))
+            .collect())
+    }
+
+    /// Full hash for a checkpoint number
+    pub fn lookup(&self, number: u64) -> CcResult<Option<String>> {
+        Ok(self.load()?.remove(&number))
+    }
+
+    /// Assign the next ID to `hash` (or return the ID it already has)
+    ///
+    /// # Arguments
+    /// * `hash` - Full hash of the checkpoint
+    pub fn assign(&self, hash: &str) -> CcResult<u64> {
+        let mut ids = self.load()?;
+        if let Some((number, _)) = ids.iter().find(|(_, h)| h.as_str() == hash) {
+            return Ok(*number);
+        }
+        let number = ids.keys().next_back().map_or(1, |last| last + 1);
+        ids.insert(number, hash.to_string());
+        self.save(&ids)?;
+        Ok(number)
+    }
+
+    /// Move IDs from rewritten checkpoints to their replacements
+    ///
+    /// # Arguments
+    /// * `rewritten` - Old and new full hashes
+    pub fn remap(&self, rewritten: &[(String, String)]) -> CcResult<()> {
+        let mut ids = self.load()?;
+        let mut changed = false;
+        for hash in ids.values_mut() {
+            if let Some((_, new)) = rewritten.iter().find(|(old, _)| old == hash) {
+                *hash = new.clone();
+                changed = true;
+            }
+        }
+        if changed {
+            self.save(&ids)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+//! Detection of edits that interleave with a tool call
+//!
+//! A hook checkpoint snapshots the whole file, so when someone edits the
+//! file while the agent works on it the checkpoint holds both. Each hunk of
+//! the pending changes to the files a tool call names is compared with the
+//! text the call reports: a changed line that appears in neither the
+//! replaced nor the written text was made by someone else, and the file is
+//! recorded in a `Ccg-Interleaved` trailer.
+//!
+//! Tool edits may replace part of a line, so a line counts as the tool's
+//! when it contains, or is contained in, a line of the reported text.
+//! Blank lines are ignored.
+
+use super::diff::DiffOperations;
+use super::hook_filter::relative_path;
+use super::types::{CCG_BRANCH_NAME, ToolEdit};
+use crate::error::Result as CcResult;
+use git2::{BranchType, Patch, Repository};
+use std::collections::BTreeMap;
+
+/// Compares tool calls with the pending changes
+pub struct InterleaveCheck<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> InterleaveCheck<'a> {
+    /// Create a new InterleaveCheck instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// Files whose pending changes include lines the tool calls did not write
+    ///
+    /// # Arguments
+    /// * `edits` - What the tool calls report, possibly several per file
+    ///
+    /// # Returns
+    /// Paths relative to the repository root, sorted
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if the diff cannot be generated
+    pub fn interleaved_files(&self, edits: &[ToolEdit]) -> CcResult<Vec<String>> {
+        let mut by_file: BTreeMap<String, Vec<&ToolEdit>> = BTreeMap::new();
+        for edit in edits {
+            by_file
+                .entry(relative_path(self.repo, &edit.path))
+                .or_default()
+                .push(edit);
+        }
+        if by_file.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let base = self
+            .repo
+            .find_branch(CCG_BRANCH_NAME, BranchType::Local)
+            .ok()
+            .and_then(|branch| branch.get().peel_to_tree().ok());
+        let diff = DiffOperations::new(self.repo).workdir_diff(base.as_ref())?;
+        let mut interleaved = Vec::new();
+        for (index, delta) in diff.deltas().enumerate() {
+            let Some(path) = delta.new_file().path().and_then(|p| p.to_str()) else {
+                continue;
+            };
+            let Some(edits) = by_file.get(path) else {
+                continue;
+            };
+            let Some(patch) = Patch::from_diff(&diff, index)? else {
+                continue;
+            };
+            if !Self::explains(&patch, edits)? {
+                interleaved.push(path.to_string());
+            }
+        }
+        Ok(interleaved)
+    }
+
+    /// Whether every changed line of `patch` comes from one of `edits`
+    fn explains(patch: &Patch, edits: &[&ToolEdit]) -> CcResult<bool> {
+        let whole_file = edits.iter().any(|edit| edit.whole_file);
+        for hunk in 0..patch.num_hunks() {
+            for line in 0..patch.num_lines_in_hunk(hunk)? {
+                let line = patch.line_in_hunk(hunk, line)?;
+                let content = String::from_utf8_lossy(line.content());
+                let explained = match line.origin() {
+                    '+' => edits
+                        .iter()
+                        .any(|edit| reported_line(&edit.added, &content)),
+                    '-' => {
+                        whole_file
+                            || edits
+                                .iter()
+                                .any(|edit| reported_line(&edit.removed, &content))
+                    }
+                    _ => true,
+                };
+                if !explained {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Whether `line` is blank or overlaps a line of the reported `texts`
+pub fn reported_line(texts: &[String], line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty()
+        || texts.iter().any(|text| {
+            text.contains(line)
+                || text
+                    .lines()
+                    .map(str::trim)
+                    .any(|reported| !reported.is_empty() && line.contains(reported))
+        })
+}
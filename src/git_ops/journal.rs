@@ -0,0 +1,116 @@
+//! Operation journal for history-discarding operations
+//!
+//! Each restore appends a commit to the `refs/ccg/restores` marker ref. The
+//! commit carries the restore target and the discarded tip as trailers, and
+//! keeps the discarded tip as its last parent so the discarded checkpoints
+//! stay reachable (and recoverable) after the CCG branch is reset.
+
+use super::commit::CommitOperations;
+use super::types::{
+    RESTORE_DISCARDED_TIP_TRAILER, RESTORE_DISCARDED_TRAILER, RESTORE_JOURNAL_REF,
+    RESTORE_TARGET_TRAILER, RestoreRecord, Trailers,
+};
+use crate::error::Result as CcResult;
+use crate::ui::short_hash;
+use git2::{Commit, Oid, Repository};
+use rust_i18n::t;
+
+/// Operations on the restore journal
+pub struct JournalOperations<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> JournalOperations<'a> {
+    /// Create a new JournalOperations instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// Append a restore to the journal
+    ///
+    /// # Arguments
+    /// * `target` - Full hash of the checkpoint the CCG branch was reset to
+    /// * `discarded_tip` - Full hash of the CCG tip before the reset
+    /// * `discarded` - Number of checkpoints dropped by the reset
+    ///
+    /// # Returns
+    /// The full hash of the journal entry
+    pub fn record_restore(
+        &self,
+        target: &str,
+        discarded_tip: &str,
+        discarded: usize,
+    ) -> CcResult<String> {
+        let target_commit = self.repo.find_commit(Oid::from_str(target)?)?;
+        let discarded_commit = self.repo.find_commit(Oid::from_str(discarded_tip)?)?;
+        let previous = self.journal_tip()?;
+
+        let commits = CommitOperations::new(self.repo);
+        let mut parents: Vec<&Commit> = previous.iter().collect();
+        parents.push(&discarded_commit);
+
+        let subject = t!(
+            "message_restore",
+            locale = &commits.message_locale(),
+            hash = short_hash(target)
+        );
+        let message = Trailers::new()
+            .push(RESTORE_TARGET_TRAILER, &target_commit.id().to_string())
+            .push(
+                RESTORE_DISCARDED_TIP_TRAILER,
+                &discarded_commit.id().to_string(),
+            )
+            .push(RESTORE_DISCARDED_TRAILER, &discarded.to_string())
+            .apply_to(&subject);
+        let tree = self.repo.find_tree(self.repo.treebuilder(None)?.write()?)?;
+        let oid = self.repo.commit(
+            None,
+            &commits.create_signature()?,
+            &commits.create_committer_signature()?,
+            &message,
+            &tree,
+            &parents,
+        )?;
+        self.repo
+            .reference(RESTORE_JOURNAL_REF, oid, true, "ccg: record restore")?;
+
+        Ok(oid.to_string())
+    }
+
+    /// All recorded restores, newest first
+    pub fn restores(&self) -> CcResult<Vec<RestoreRecord>> {
+        let mut records = Vec::new();
+        let mut current = self.journal_tip()?;
+        while let Some(commit) = current {
+            if let Some(record) = Self::parse_record(&commit) {
+                records.push(record);
+            }
+            // 最后一个父提交是被丢弃的检查点，其余的是上一条日志
+            current = if commit.parent_count() > 1 {
+                Some(commit.parent(0)?)
+            } else {
+                None
+            };
+        }
+        Ok(records)
+    }
+
+    fn journal_tip(&self) -> CcResult<Option<Commit<'a>>> {
+        match self.repo.find_reference(RESTORE_JOURNAL_REF) {
+            Ok(reference) => Ok(Some(reference.peel_to_commit()?)),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn parse_record(commit: &Commit) -> Option<RestoreRecord> {
+        let trailers = Trailers::parse(commit.message()?);
+        let trailer = |key: &str| trailers.get(key).map(str::to_string);
+        Some(RestoreRecord {
+            target: trailer(RESTORE_TARGET_TRAILER)?,
+            discarded_tip: trailer(RESTORE_DISCARDED_TIP_TRAILER)?,
+            discarded: trailer(RESTORE_DISCARDED_TRAILER)?.parse().ok()?,
+            time: commit.time().seconds(),
+        })
+    }
+}
@@ -0,0 +1,150 @@
+//! Checkpoint labels derived from message conventions
+//!
+//! Checkpoint messages follow a few fixed shapes: hook-created checkpoints
+//! start with the tool name (`Edit on main.rs`), manual ones use the localized
+//! "Manual checkpoint" text, reverts and restores use their own templates, and
+//! users mark milestones with a `milestone:` prefix. Messages may have been
+//! written in any supported locale, so every locale's template is checked.
+
+use console::Color;
+use rust_i18n::t;
+use std::fmt;
+use std::str::FromStr;
+
+/// Placeholder used to cut a localized template at its first argument
+const CUT: &str = "\u{0}";
+
+/// Message prefixes marking a milestone, compared case-insensitively
+const MILESTONE_PREFIXES: [&str; 4] = ["milestone:", "[milestone]", "里程碑:", "里程碑："];
+
+/// The kind of a checkpoint, derived from its message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheckpointLabel {
+    /// Created by the `Edit`, `MultiEdit` or `NotebookEdit` tool hook
+    Edit,
+    /// Created by the `Write` tool hook
+    Write,
+    /// Created by `ccg create` without a message
+    Manual,
+    /// Message starts with a milestone marker
+    Milestone,
+    /// A revert checkpoint or a restore record
+    Restore,
+}
+
+impl CheckpointLabel {
+    /// All labels, in the order they are documented
+    pub const ALL: [CheckpointLabel; 5] = [
+        CheckpointLabel::Edit,
+        CheckpointLabel::Write,
+        CheckpointLabel::Manual,
+        CheckpointLabel::Milestone,
+        CheckpointLabel::Restore,
+    ];
+
+    /// Lowercase name used on the command line and in badges
+    pub fn name(self) -> &'static str {
+        match self {
+            CheckpointLabel::Edit => "edit",
+            CheckpointLabel::Write => "write",
+            CheckpointLabel::Manual => "manual",
+            CheckpointLabel::Milestone => "milestone",
+            CheckpointLabel::Restore => "restore",
+        }
+    }
+
+    /// Badge color in `list`
+    pub fn color(self) -> Color {
+        match self {
+            CheckpointLabel::Edit => Color::Yellow,
+            CheckpointLabel::Write => Color::Green,
+            CheckpointLabel::Manual => Color::Blue,
+            CheckpointLabel::Milestone => Color::Magenta,
+            CheckpointLabel::Restore => Color::Red,
+        }
+    }
+
+    /// Classify a checkpoint by the first line of its message
+    ///
+    /// # Returns
+    /// None for free-form messages that match no convention
+    pub fn classify(summary: &str) -> Option<CheckpointLabel> {
+        let summary = summary.trim();
+        let lower = summary.to_lowercase();
+        if MILESTONE_PREFIXES
+            .iter()
+            .any(|prefix| lower.starts_with(prefix))
+        {
+            return Some(CheckpointLabel::Milestone);
+        }
+
+        for locale in rust_i18n::available_locales!() {
+            let manual = t!("message_manual_summary", locale = locale, summary = CUT);
+            if summary == t!("message_manual", locale = locale)
+                || summary.starts_with(template_prefix(&manual))
+            {
+                return Some(CheckpointLabel::Manual);
+            }
+            let restore = t!("message_restore", locale = locale, hash = CUT);
+            let revert = t!("message_revert", locale = locale, summary = CUT, hash = CUT);
+            if [restore, revert]
+                .iter()
+                .any(|template| summary.starts_with(template_prefix(template)))
+            {
+                return Some(CheckpointLabel::Restore);
+            }
+        }
+
+        let tool = summary
+            .split(|c: char| c.is_whitespace() || c == ':')
+            .next()
+            .filter(|tool| !tool.is_empty())?;
+        let label = match tool {
+            "Edit" | "MultiEdit" | "NotebookEdit" => CheckpointLabel::Edit,
+            "Write" => CheckpointLabel::Write,
+            _ => return None,
+        };
+        // 只有工具名本身、“工具 on 文件” 或 “工具: 变更描述” 形式才算钩子创建的检查点
+        let from_hook = summary == tool
+            || rust_i18n::available_locales!().iter().any(|locale| {
+                let on_file = t!(
+                    "message_tool_on_file",
+                    locale = locale,
+                    tool = tool,
+                    file = CUT
+                );
+                let described = t!(
+                    "message_tool_summary",
+                    locale = locale,
+                    tool = tool,
+                    summary = CUT
+                );
+                [on_file, described]
+                    .iter()
+                    .any(|template| summary.starts_with(template_prefix(template)))
+            });
+        from_hook.then_some(label)
+    }
+}
+
+/// The literal text of a rendered template before its first `CUT` argument
+fn template_prefix(template: &str) -> &str {
+    template.split(CUT).next().unwrap_or(template)
+}
+
+impl fmt::Display for CheckpointLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for CheckpointLabel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CheckpointLabel::ALL
+            .into_iter()
+            .find(|label| label.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown label '{s}'"))
+    }
+}
@@ -0,0 +1,1866 @@
+//! In-memory checkpoint backend
+//!
+//! [`MemoryBackend`] models just enough of a git repository (commits with
+//! parents, local branches, HEAD and a flat working directory) to drive
+//! `CheckpointService` in unit tests. Clones share state, so a test can keep a
+//! handle to inspect the repository after handing a clone to the service.
+
+use crate::config::ExcludeProfile;
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::git_ops::backend::GitBackend;
+use crate::git_ops::commit::append_trailer;
+use crate::git_ops::ids::{parse_checkpoint_id, parse_latest_ref};
+use crate::git_ops::interleave::reported_line;
+use crate::git_ops::types::{
+    AheadBehind, BASE_BRANCH_TRAILER, BaseDrift, BranchDrift, CCG_BRANCH_NAME, CheckoutReport,
+    CheckpointEntry, CheckpointOverlap, DEFAULT_COMMIT_MESSAGE, DEFAULT_MESSAGE_MAX_BYTES,
+    DedupeReport, DiffStats, EMPTY_CHECKPOINT_TRAILER, FetchReport, FileChangeInfo, FilePatch,
+    FileVersion, GuardBypass, ImpactReport, IndexRepair, ListColumn, ListFilter, Permissions,
+    RepackReport, RepackSettings, RestoreHunk, RestoreRecord, RewrittenHistory, Snapshot,
+    StorageUsage, Task, ToolEdit, Trailers, UsageAdvisory, UsageLimits,
+};
+use crate::git_ops::users::UserNamespaces;
+use crate::i18n::DEFAULT_MESSAGE_LOCALE;
+use crate::ui::short_hash;
+use rust_i18n::t;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// File path to contents
+type Files = BTreeMap<String, String>;
+
+/// A commit stored by [`MemoryBackend`]
+#[derive(Debug, Clone)]
+struct MemoryCommit {
+    message: String,
+    parent: Option<String>,
+    files: Files,
+}
+
+/// Where HEAD points
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Head {
+    /// A local branch, which may not have any commits yet
+    Branch(String),
+    /// A commit hash
+    Detached(String),
+}
+
+#[derive(Debug)]
+struct MemoryState {
+    commits: HashMap<String, MemoryCommit>,
+    branches: BTreeMap<String, String>,
+    head: Head,
+    workdir: Files,
+    restores: Vec<RestoreRecord>,
+    /// `cp-N` number to commit id
+    checkpoint_ids: BTreeMap<u64, String>,
+    next_id: u64,
+    usage_limits: UsageLimits,
+    usage_advised: bool,
+    active_task: Option<Task>,
+    /// Parked snapshots, oldest first, with the HEAD files they were taken on
+    snapshots: Vec<(Snapshot, Files, Files)>,
+    /// Notes by commit id
+    notes: HashMap<String, String>,
+    /// Skipped guard hooks, oldest first
+    bypasses: Vec<GuardBypass>,
+    message_max_bytes: usize,
+    permissions: Permissions,
+    team_user: String,
+    /// Patterns added by `apply_exclude_profile`, in order
+    excludes: Vec<String>,
+}
+
+/// An in-memory fake implementing [`GitBackend`]
+#[derive(Debug, Clone)]
+pub struct MemoryBackend {
+    state: Rc<RefCell<MemoryState>>,
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryBackend {
+    /// Create an empty repository with HEAD on an unborn `main` branch
+    pub fn new() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(MemoryState {
+                commits: HashMap::new(),
+                branches: BTreeMap::new(),
+                head: Head::Branch("main".to_string()),
+                workdir: Files::new(),
+                restores: Vec::new(),
+                checkpoint_ids: BTreeMap::new(),
+                next_id: 1,
+                usage_limits: UsageLimits::default(),
+                usage_advised: false,
+                active_task: None,
+                snapshots: Vec::new(),
+                notes: HashMap::new(),
+                bypasses: Vec::new(),
+                message_max_bytes: DEFAULT_MESSAGE_MAX_BYTES,
+                permissions: Permissions::Open,
+                team_user: "memory".to_string(),
+                excludes: Vec::new(),
+            })),
+        }
+    }
+
+    /// Set the thresholds returned by `usage_limits`
+    pub fn set_usage_limits(&self, limits: UsageLimits) {
+        self.state.borrow_mut().usage_limits = limits;
+    }
+
+    /// Set the cap returned by `message_max_bytes`
+    pub fn set_message_max_bytes(&self, bytes: usize) {
+        self.state.borrow_mut().message_max_bytes = bytes;
+    }
+
+    /// Set the mode returned by `team_permissions` and the user acting
+    pub fn set_team(&self, permissions: Permissions, user: &str) {
+        let mut state = self.state.borrow_mut();
+        state.permissions = permissions;
+        state.team_user = user.to_string();
+    }
+
+    /// Exclude patterns recorded by `apply_exclude_profile`
+    pub fn excludes(&self) -> Vec<String> {
+        self.state.borrow().excludes.clone()
+    }
+
+    /// Note attached to a commit with `add_note`
+    pub fn note(&self, hash: &str) -> Option<String> {
+        let state = self.state.borrow();
+        let id = state.resolve(hash).ok()?;
+        state.notes.get(&id).cloned()
+    }
+
+    /// Full message of a commit
+    pub fn full_message(&self, hash: &str) -> Option<String> {
+        let state = self.state.borrow();
+        let id = state.resolve(hash).ok()?;
+        Some(state.commits[&id].message.clone())
+    }
+
+    /// Write a file in the working directory
+    pub fn write_file(&self, path: &str, contents: &str) {
+        self.state
+            .borrow_mut()
+            .workdir
+            .insert(path.to_string(), contents.to_string());
+    }
+
+    /// Delete a file from the working directory
+    pub fn remove_file(&self, path: &str) {
+        self.state.borrow_mut().workdir.remove(path);
+    }
+
+    /// Contents of a working directory file
+    pub fn file(&self, path: &str) -> Option<String> {
+        self.state.borrow().workdir.get(path).cloned()
+    }
+
+    /// Commit the working directory onto the current branch, like `git commit -a`
+    ///
+    /// Unlike [`GitBackend::create_checkpoint`] this always creates a commit.
+    ///
+    /// # Returns
+    /// The full hash of the new commit
+    pub fn commit(&self, message: &str) -> String {
+        self.state.borrow_mut().commit(message)
+    }
+
+    /// Switch HEAD to an existing branch and check out its files
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if the branch does not exist
+    pub fn checkout(&self, branch: &str) -> CcResult<()> {
+        let mut state = self.state.borrow_mut();
+        let tip = state
+            .branches
+            .get(branch)
+            .cloned()
+            .ok_or_else(|| CheckpointError::BranchNotFound(branch.to_string()))?;
+        state.workdir = state.commits[&tip].files.clone();
+        state.head = Head::Branch(branch.to_string());
+        Ok(())
+    }
+
+    /// Detach HEAD at a commit and check out its files
+    pub fn detach(&self, hash: &str) -> CcResult<()> {
+        let mut state = self.state.borrow_mut();
+        let id = state.resolve(hash)?;
+        state.workdir = state.commits[&id].files.clone();
+        state.head = Head::Detached(id);
+        Ok(())
+    }
+
+    /// Tip of a local branch
+    pub fn branch_tip(&self, branch: &str) -> Option<String> {
+        self.state.borrow().branches.get(branch).cloned()
+    }
+
+    /// First line of a commit's message
+    pub fn message(&self, hash: &str) -> Option<String> {
+        let state = self.state.borrow();
+        let id = state.resolve(hash).ok()?;
+        state.commits[&id]
+            .message
+            .lines()
+            .next()
+            .map(str::to_string)
+    }
+}
+
+impl MemoryState {
+    /// Deterministic 40-character hex id with distinct short prefixes
+    fn next_hash(&mut self) -> String {
+        let n = self.next_id;
+        self.next_id += 1;
+        let mix = |mut z: u64| {
+            z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        format!("{:016x}{:016x}{:08x}", mix(n), mix(!n), n as u32)
+    }
+
+    fn head_commit(&self) -> Option<String> {
+        match &self.head {
+            Head::Branch(name) => self.branches.get(name).cloned(),
+            Head::Detached(id) => Some(id.clone()),
+        }
+    }
+
+    /// The tip of the ccg branch, or HEAD when there is none
+    fn checkpoint_tip(&self) -> Option<String> {
+        self.branches
+            .get(CCG_BRANCH_NAME)
+            .cloned()
+            .or_else(|| self.head_commit())
+    }
+
+    fn head_files(&self) -> Files {
+        self.head_commit()
+            .map(|id| self.commits[&id].files.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether files in HEAD were modified or deleted in the working directory
+    fn has_tracked_changes(&self) -> bool {
+        self.head_files()
+            .iter()
+            .any(|(path, content)| self.workdir.get(path) != Some(content))
+    }
+
+    /// Working directory files that are not in HEAD
+    fn untracked_files(&self) -> Vec<String> {
+        let head = self.head_files();
+        self.workdir
+            .keys()
+            .filter(|path| !head.contains_key(*path))
+            .cloned()
+            .collect()
+    }
+
+    /// Point HEAD (or the branch it is on) at `id`
+    fn move_head(&mut self, id: String) {
+        match &self.head {
+            Head::Branch(name) => {
+                self.branches.insert(name.clone(), id);
+            }
+            Head::Detached(_) => self.head = Head::Detached(id),
+        }
+    }
+
+    fn commit(&mut self, message: &str) -> String {
+        let files = self.workdir.clone();
+        self.commit_files(message, files)
+    }
+
+    /// Commit `files` as the new tree on top of HEAD
+    fn commit_files(&mut self, message: &str, files: Files) -> String {
+        let id = self.next_hash();
+        let commit = MemoryCommit {
+            message: message.to_string(),
+            parent: self.head_commit(),
+            files,
+        };
+        self.commits.insert(id.clone(), commit);
+        self.move_head(id.clone());
+        id
+    }
+
+    fn resolve(&self, hash: &str) -> CcResult<String> {
+        if let Some(number) = parse_checkpoint_id(hash) {
+            return self
+                .checkpoint_ids
+                .get(&number)
+                .cloned()
+                .ok_or_else(|| CheckpointError::CheckpointNotFound(hash.to_string()));
+        }
+        if let Some(offset) = parse_latest_ref(hash) {
+            return self
+                .checkpoint_tip()
+                .and_then(|tip| self.ancestors(&tip).into_iter().nth(offset))
+                .ok_or_else(|| CheckpointError::CheckpointNotFound(hash.to_string()));
+        }
+        if hash.len() < 2 {
+            return Err(CheckpointError::InvalidHash(format!(
+                "hash太短，至少需要2个字符: {hash}"
+            )));
+        }
+        let matches: Vec<&String> = self
+            .commits
+            .keys()
+            .filter(|id| id.starts_with(hash))
+            .collect();
+        match matches.as_slice() {
+            [] => Err(CheckpointError::CheckpointNotFound(hash.to_string())),
+            [id] => Ok((*id).clone()),
+            _ => Err(CheckpointError::InvalidHash(format!(
+                "短hash '{hash}' 匹配到多个提交"
+            ))),
+        }
+    }
+
+    /// Commits reachable from `id`, newest first
+    fn ancestors(&self, id: &str) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut current = Some(id.to_string());
+        while let Some(id) = current {
+            current = self.commits[&id].parent.clone();
+            result.push(id);
+        }
+        result
+    }
+
+    /// Copy the notes of rewritten commits to their replacements
+    fn copy_notes(&mut self, moved: &HashMap<String, String>) {
+        for (old, new) in moved {
+            if let Some(note) = self.notes.get(old).cloned() {
+                self.notes.insert(new.clone(), note);
+            }
+        }
+    }
+
+    /// Replace `target` on the CCG branch with a chain of commits and replay
+    /// its descendants onto the last one
+    ///
+    /// The first replacement keeps the target's parent; later ones are chained.
+    fn rewrite(
+        &mut self,
+        target: &str,
+        replacements: Vec<MemoryCommit>,
+    ) -> CcResult<RewrittenHistory> {
+        let tip = self
+            .branches
+            .get(CCG_BRANCH_NAME)
+            .cloned()
+            .ok_or_else(|| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?;
+
+        // ccg 分支上目标之后的提交，最旧的在前
+        let ancestors = self.ancestors(&tip);
+        let mut chain: Vec<String> = ancestors
+            .iter()
+            .take_while(|id| id.as_str() != target)
+            .cloned()
+            .collect();
+        if chain.len() == ancestors.len() {
+            return Err(CheckpointError::CheckpointNotFound(target.to_string()));
+        }
+        chain.reverse();
+
+        let mut parent = self.commits[target].parent.clone();
+        let mut new_hashes = Vec::new();
+        for mut commit in replacements {
+            commit.parent = parent;
+            let id = self.next_hash();
+            self.commits.insert(id.clone(), commit);
+            new_hashes.push(id.clone());
+            parent = Some(id);
+        }
+
+        let mut replayed = Vec::new();
+        for id in chain {
+            let mut commit = self.commits[&id].clone();
+            commit.parent = parent;
+            let new_id = self.next_hash();
+            self.commits.insert(new_id.clone(), commit);
+            replayed.push((id, new_id.clone()));
+            parent = Some(new_id);
+        }
+
+        let tip = parent
+            .ok_or_else(|| CheckpointError::InvalidArgument(t!("rewrite_drop_only").to_string()))?;
+        self.branches
+            .insert(CCG_BRANCH_NAME.to_string(), tip.clone());
+
+        // 第一个替代提交继承原检查点的 ID，其余替代提交分配新 ID
+        let mut moved: HashMap<String, String> = replayed.iter().cloned().collect();
+        if let Some(first) = new_hashes.first() {
+            moved.insert(target.to_string(), first.clone());
+        }
+        for id in self.checkpoint_ids.values_mut() {
+            if let Some(new_id) = moved.get(id) {
+                *id = new_id.clone();
+            }
+        }
+        self.copy_notes(&moved);
+        for extra in new_hashes.iter().skip(1) {
+            self.assign_checkpoint_id(extra);
+        }
+
+        Ok(RewrittenHistory {
+            replacements: new_hashes,
+            replayed,
+            tip,
+        })
+    }
+
+    /// Commits on the CCG branch that `onto` does not contain, oldest first,
+    /// with the resolved `onto`
+    fn rebase_candidates(&self, onto: &str) -> CcResult<(String, Vec<String>)> {
+        let onto = match self.branches.get(onto) {
+            Some(tip) => tip.clone(),
+            None => self
+                .resolve(onto)
+                .map_err(|_| CheckpointError::BranchNotFound(onto.to_string()))?,
+        };
+        let tip = self
+            .branches
+            .get(CCG_BRANCH_NAME)
+            .cloned()
+            .ok_or_else(|| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?;
+        let contained: HashSet<String> = self.ancestors(&onto).into_iter().collect();
+        let mut moved: Vec<String> = self
+            .ancestors(&tip)
+            .into_iter()
+            .take_while(|id| !contained.contains(id))
+            .collect();
+        // ccg 分支已经从 onto 开始
+        if moved
+            .last()
+            .is_some_and(|oldest| self.commits[oldest].parent.as_deref() == Some(onto.as_str()))
+        {
+            moved.clear();
+        }
+        moved.reverse();
+        Ok((onto, moved))
+    }
+
+    fn entry(&self, id: &str) -> CheckpointEntry {
+        CheckpointEntry {
+            message: self.commits[id].message.clone(),
+            author: "Memory Backend".to_string(),
+            time: 0,
+            id: self
+                .checkpoint_ids
+                .iter()
+                .find(|(_, hash)| *hash == id)
+                .map(|(number, _)| *number),
+            hash: id.to_string(),
+        }
+    }
+
+    /// Assign the next `cp-N` number to a commit
+    fn assign_checkpoint_id(&mut self, id: &str) {
+        let number = self
+            .checkpoint_ids
+            .keys()
+            .next_back()
+            .map_or(1, |last| last + 1);
+        self.checkpoint_ids.insert(number, id.to_string());
+    }
+
+    fn parent_files(&self, id: &str) -> Files {
+        self.commits[id]
+            .parent
+            .as_ref()
+            .map(|p| self.commits[p].files.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether the commit only modifies files and every change is whitespace
+    fn format_only(&self, id: &str) -> bool {
+        let files = &self.commits[id].files;
+        let parent = self.parent_files(id);
+        let changed = changes(&parent, files);
+        let normalized = |contents: &String| -> String {
+            contents.chars().filter(|c| !c.is_whitespace()).collect()
+        };
+        !changed.is_empty()
+            && changed.iter().all(|line| {
+                let path = &line[2..];
+                line.starts_with('M')
+                    && parent.get(path).map(normalized) == files.get(path).map(normalized)
+            })
+    }
+}
+
+/// Status lines (`A`/`M`/`D` + path) describing how `new` differs from `old`
+fn changes(old: &Files, new: &Files) -> Vec<String> {
+    let mut paths: Vec<&String> = old.keys().chain(new.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    paths
+        .into_iter()
+        .filter_map(|path| match (old.get(path), new.get(path)) {
+            (None, Some(_)) => Some(format!("A {path}")),
+            (Some(_), None) => Some(format!("D {path}")),
+            (Some(a), Some(b)) if a != b => Some(format!("M {path}")),
+            _ => None,
+        })
+        .collect()
+}
+
+impl GitBackend for MemoryBackend {
+    fn workdir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn init_checkpoints(&self) -> CcResult<()> {
+        let mut state = self.state.borrow_mut();
+        if state.branches.contains_key(CCG_BRANCH_NAME) {
+            return Ok(());
+        }
+        let tip = match state.head_commit() {
+            Some(tip) => tip,
+            None => state.commit(DEFAULT_COMMIT_MESSAGE),
+        };
+        state.branches.insert(CCG_BRANCH_NAME.to_string(), tip);
+        Ok(())
+    }
+
+    fn init_checkpoints_from(&self, from: &str) -> CcResult<()> {
+        let mut state = self.state.borrow_mut();
+        if state.branches.contains_key(CCG_BRANCH_NAME) {
+            return Err(CheckpointError::InvalidArgument(format!(
+                "'{CCG_BRANCH_NAME}' 分支已存在"
+            )));
+        }
+        let tip = match state.branches.get(from) {
+            Some(tip) => tip.clone(),
+            None => state
+                .resolve(from)
+                .map_err(|_| CheckpointError::BranchNotFound(from.to_string()))?,
+        };
+        state.branches.insert(CCG_BRANCH_NAME.to_string(), tip);
+        Ok(())
+    }
+
+    fn init_checkpoints_orphan(&self) -> CcResult<()> {
+        let mut state = self.state.borrow_mut();
+        if state.branches.contains_key(CCG_BRANCH_NAME) {
+            return Err(CheckpointError::InvalidArgument(format!(
+                "'{CCG_BRANCH_NAME}' 分支已存在"
+            )));
+        }
+        let id = state.next_hash();
+        let root = MemoryCommit {
+            message: "Orphan checkpoint root".to_string(),
+            parent: None,
+            files: state.head_files(),
+        };
+        state.commits.insert(id.clone(), root);
+        state.branches.insert(CCG_BRANCH_NAME.to_string(), id);
+        Ok(())
+    }
+
+    fn apply_exclude_profile(&self, profile: ExcludeProfile) -> CcResult<Vec<String>> {
+        let mut state = self.state.borrow_mut();
+        let mut added = Vec::new();
+        for pattern in profile.patterns() {
+            if !state.excludes.iter().any(|p| p == pattern) {
+                state.excludes.push(pattern.to_string());
+                added.push(pattern.to_string());
+            }
+        }
+        Ok(added)
+    }
+
+    fn rename_branch(&self, old: &str, new: &str) -> CcResult<()> {
+        let mut state = self.state.borrow_mut();
+        if state.branches.contains_key(new) {
+            return Err(CheckpointError::InvalidArgument(
+                t!("branch_already_exists", name = new).to_string(),
+            ));
+        }
+        let tip = state
+            .branches
+            .remove(old)
+            .ok_or_else(|| CheckpointError::BranchNotFound(old.to_string()))?;
+        state.branches.insert(new.to_string(), tip);
+        if state.head == Head::Branch(old.to_string()) {
+            state.head = Head::Branch(new.to_string());
+        }
+        Ok(())
+    }
+
+    fn has_ccg_branch(&self) -> CcResult<bool> {
+        Ok(self.state.borrow().branches.contains_key(CCG_BRANCH_NAME))
+    }
+
+    fn get_current_branch_name(&self) -> CcResult<String> {
+        Ok(match &self.state.borrow().head {
+            Head::Branch(name) => name.clone(),
+            Head::Detached(_) => "HEAD".to_string(),
+        })
+    }
+
+    fn ensure_ccg_branch(&self) -> CcResult<String> {
+        let mut state = self.state.borrow_mut();
+        let current = match &state.head {
+            Head::Branch(name) => name.clone(),
+            Head::Detached(id) => id.clone(),
+        };
+        if !state.branches.contains_key(CCG_BRANCH_NAME) {
+            return Err(CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()));
+        }
+        if current != CCG_BRANCH_NAME {
+            state.head = Head::Branch(CCG_BRANCH_NAME.to_string());
+        }
+        Ok(current)
+    }
+
+    fn restore_original_branch(&self, original_branch: &str) -> CcResult<()> {
+        if original_branch != CCG_BRANCH_NAME {
+            let mut state = self.state.borrow_mut();
+            state.head = if state.commits.contains_key(original_branch) {
+                Head::Detached(original_branch.to_string())
+            } else {
+                Head::Branch(original_branch.to_string())
+            };
+        }
+        Ok(())
+    }
+
+    fn message_locale(&self) -> String {
+        DEFAULT_MESSAGE_LOCALE.to_string()
+    }
+
+    fn usage_limits(&self) -> UsageLimits {
+        self.state.borrow().usage_limits
+    }
+
+    fn message_max_bytes(&self) -> usize {
+        self.state.borrow().message_max_bytes
+    }
+
+    /// The in-memory repository has no configuration
+    fn read_only(&self) -> bool {
+        false
+    }
+
+    /// Size counts each distinct file content once, like deduplicated blobs
+    fn check_usage(&self, limits: &UsageLimits) -> CcResult<Option<UsageAdvisory>> {
+        let mut state = self.state.borrow_mut();
+        let contents: HashSet<&String> = state
+            .checkpoint_ids
+            .values()
+            .filter_map(|id| state.commits.get(id))
+            .flat_map(|commit| commit.files.values())
+            .collect();
+        let advisory = UsageAdvisory {
+            checkpoints: state.checkpoint_ids.len() as u64,
+            bytes: contents.iter().map(|content| content.len() as u64).sum(),
+            limits: *limits,
+        };
+        if !advisory.exceeded() {
+            state.usage_advised = false;
+            return Ok(None);
+        }
+        if std::mem::replace(&mut state.usage_advised, true) {
+            return Ok(None);
+        }
+        Ok(Some(advisory))
+    }
+
+    /// Bytes are the distinct file contents of all checkpoints
+    fn storage_usage(&self) -> CcResult<StorageUsage> {
+        let state = self.state.borrow();
+        let contents: HashSet<&String> = state
+            .checkpoint_ids
+            .values()
+            .filter_map(|id| state.commits.get(id))
+            .flat_map(|commit| commit.files.values())
+            .collect();
+        Ok(StorageUsage {
+            checkpoints: state.checkpoint_ids.len() as u64,
+            bytes: contents.iter().map(|content| content.len() as u64).sum(),
+        })
+    }
+
+    fn repack_settings(&self) -> RepackSettings {
+        RepackSettings::default()
+    }
+
+    /// Nothing is stored as loose objects, so there is never anything to pack
+    fn repack_large_files(&self, _settings: &RepackSettings) -> CcResult<RepackReport> {
+        if !self.state.borrow().branches.contains_key(CCG_BRANCH_NAME) {
+            return Err(CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()));
+        }
+        Ok(RepackReport::default())
+    }
+
+    fn last_repack(&self) -> CcResult<Option<RepackReport>> {
+        Ok(None)
+    }
+
+    /// There are no object files to index
+    fn maintain_commit_graph(&self, _force: bool) -> CcResult<bool> {
+        Ok(false)
+    }
+
+    /// Each distinct file content counts as one object; there are no trees
+    fn dedupe_report(&self) -> CcResult<DedupeReport> {
+        let state = self.state.borrow();
+        let tip = state
+            .branches
+            .get(CCG_BRANCH_NAME)
+            .ok_or_else(|| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?;
+        let others: HashSet<String> = state
+            .branches
+            .iter()
+            .filter(|(name, _)| *name != CCG_BRANCH_NAME)
+            .flat_map(|(_, id)| state.ancestors(id))
+            .collect();
+        let shared: HashSet<&String> = others
+            .iter()
+            .flat_map(|id| state.commits[id].files.values())
+            .collect();
+
+        let mut report = DedupeReport::default();
+        let mut referenced = HashSet::new();
+        for id in state
+            .ancestors(tip)
+            .iter()
+            .filter(|id| !others.contains(*id))
+        {
+            let files = &state.commits[id].files;
+            report.checkpoints += 1;
+            report.logical_bytes += files.values().map(|c| c.len() as u64).sum::<u64>();
+            referenced.extend(files.values());
+        }
+        for content in referenced {
+            if shared.contains(content) {
+                report.shared_objects += 1;
+                report.shared_bytes += content.len() as u64;
+            } else {
+                report.unique_objects += 1;
+                report.unique_bytes += content.len() as u64;
+            }
+        }
+        Ok(report)
+    }
+
+    /// The in-memory store has no remotes to fetch from
+    fn fetch_checkpoints(&self, remote: &str, _depth: Option<u32>) -> CcResult<FetchReport> {
+        Err(CheckpointError::InvalidArgument(format!(
+            "unknown remote: {remote}"
+        )))
+    }
+
+    fn is_shallow(&self) -> bool {
+        false
+    }
+
+    fn team_permissions(&self) -> Permissions {
+        self.state.borrow().permissions
+    }
+
+    fn team_user(&self) -> String {
+        self.state.borrow().team_user.clone()
+    }
+
+    fn interleaved_files(&self, edits: &[ToolEdit]) -> CcResult<Vec<String>> {
+        let state = self.state.borrow();
+        let base = state
+            .branches
+            .get(CCG_BRANCH_NAME)
+            .map(|id| state.commits[id].files.clone())
+            .unwrap_or_default();
+        // 行级比较：只在一边出现的行即为变更的行
+        let paths: BTreeSet<&str> = edits.iter().map(|edit| edit.path.as_str()).collect();
+        Ok(paths
+            .into_iter()
+            .filter(|path| {
+                let edits: Vec<&ToolEdit> = edits.iter().filter(|e| e.path == *path).collect();
+                let old = base.get(*path).map(String::as_str).unwrap_or_default();
+                let new = state
+                    .workdir
+                    .get(*path)
+                    .map(String::as_str)
+                    .unwrap_or_default();
+                let added = new.lines().filter(|line| !old.lines().any(|o| o == *line));
+                let removed = old.lines().filter(|line| !new.lines().any(|n| n == *line));
+                let whole_file = edits.iter().any(|edit| edit.whole_file);
+                !(added
+                    .into_iter()
+                    .all(|line| edits.iter().any(|e| reported_line(&e.added, line)))
+                    && (whole_file
+                        || removed
+                            .into_iter()
+                            .all(|line| edits.iter().any(|e| reported_line(&e.removed, line)))))
+            })
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn publish_checkpoints(&self, remote: &str) -> CcResult<String> {
+        Err(CheckpointError::InvalidArgument(format!(
+            "unknown remote: {remote}"
+        )))
+    }
+
+    fn create_checkpoint(&self, message: &str) -> CcResult<String> {
+        let mut state = self.state.borrow_mut();
+        if state.head_commit().is_some() && state.workdir == state.head_files() {
+            return Err(CheckpointError::NoChangesToCommit);
+        }
+        let id = state.commit(message);
+        state.assign_checkpoint_id(&id);
+        Ok(id)
+    }
+
+    fn create_checkpoint_paths(&self, message: &str, paths: &[String]) -> CcResult<String> {
+        let mut state = self.state.borrow_mut();
+        let head_files = state.head_files();
+        let mut files = head_files.clone();
+        for path in paths {
+            match state.workdir.get(path) {
+                Some(content) => files.insert(path.clone(), content.clone()),
+                None => files.remove(path),
+            };
+        }
+        if state.head_commit().is_some() && files == head_files {
+            return Err(CheckpointError::NoChangesToCommit);
+        }
+        let id = state.commit_files(message, files);
+        state.assign_checkpoint_id(&id);
+        Ok(id)
+    }
+
+    fn add_note(&self, hash: &str, note: &str) -> CcResult<()> {
+        let mut state = self.state.borrow_mut();
+        let id = state.resolve(hash)?;
+        state.notes.insert(id, note.to_string());
+        Ok(())
+    }
+
+    fn checkpoint_note(&self, hash: &str) -> CcResult<Option<String>> {
+        let state = self.state.borrow();
+        let id = state.resolve(hash)?;
+        Ok(state.notes.get(&id).cloned())
+    }
+
+    /// The in-memory repository has no tags
+    fn checkpoint_tags(&self) -> CcResult<HashMap<String, Vec<String>>> {
+        Ok(HashMap::new())
+    }
+
+    fn create_empty_checkpoint(&self, message: &str) -> CcResult<String> {
+        let mut state = self.state.borrow_mut();
+        let parent = state.head_commit().ok_or_else(|| {
+            CheckpointError::GitOperationFailed(git2::Error::from_str("HEAD has no commits"))
+        })?;
+        let commit = MemoryCommit {
+            message: append_trailer(message, EMPTY_CHECKPOINT_TRAILER),
+            files: state.commits[&parent].files.clone(),
+            parent: Some(parent),
+        };
+        let id = state.next_hash();
+        state.commits.insert(id.clone(), commit);
+        state.move_head(id.clone());
+        state.assign_checkpoint_id(&id);
+        Ok(id)
+    }
+
+    fn list_checkpoints(
+        &self,
+        limit: usize,
+        _columns: &[ListColumn],
+        filter: &ListFilter,
+    ) -> CcResult<Vec<String>> {
+        // 内存实现没有远程，也就没有同步过来的用户命名空间
+        if let Some(user) = &filter.user {
+            return Err(CheckpointError::BranchNotFound(UserNamespaces::ref_name(
+                user,
+            )));
+        }
+        let entries = self.checkpoint_entries(usize::MAX)?;
+        let state = self.state.borrow();
+        Ok(entries
+            .into_iter()
+            .filter(|entry| filter.matches(entry))
+            .map(|entry| (state.format_only(&entry.hash), entry))
+            .filter(|(format_only, _)| !(filter.skip_format_only && *format_only))
+            .map(|(format_only, entry)| {
+                let mut badges: Vec<String> = entry
+                    .label()
+                    .map(|label| format!("[{label}]"))
+                    .into_iter()
+                    .collect();
+                if format_only {
+                    badges.push("[format-only]".to_string());
+                }
+                if !entry.interleaved().is_empty() {
+                    badges.push("[interleaved]".to_string());
+                }
+                if badges.is_empty() {
+                    format!("{} {}", entry.short_hash(), entry.summary())
+                } else {
+                    format!(
+                        "{} {} {}",
+                        entry.short_hash(),
+                        badges.join(" "),
+                        entry.summary()
+                    )
+                }
+            })
+            .take(limit)
+            .collect())
+    }
+
+    fn checkpoint_entries(&self, limit: usize) -> CcResult<Vec<CheckpointEntry>> {
+        let state = self.state.borrow();
+        let Some(tip) = state.checkpoint_tip() else {
+            return Ok(Vec::new());
+        };
+        Ok(state
+            .ancestors(&tip)
+            .into_iter()
+            .take(limit)
+            .map(|id| state.entry(&id))
+            .collect())
+    }
+
+    fn resolve_checkpoint(&self, hash: &str) -> CcResult<String> {
+        self.state.borrow().resolve(hash)
+    }
+
+    fn checkpoint_id(&self, hash: &str) -> CcResult<Option<u64>> {
+        let state = self.state.borrow();
+        let id = state.resolve(hash)?;
+        Ok(state
+            .checkpoint_ids
+            .iter()
+            .find(|(_, hash)| **hash == id)
+            .map(|(number, _)| *number))
+    }
+
+    fn record_restore(&self, target: &str, discarded_tip: &str, discarded: usize) -> CcResult<()> {
+        self.state.borrow_mut().restores.insert(
+            0,
+            RestoreRecord {
+                target: target.to_string(),
+                discarded_tip: discarded_tip.to_string(),
+                discarded,
+                time: 0,
+            },
+        );
+        Ok(())
+    }
+
+    fn restore_records(&self) -> CcResult<Vec<RestoreRecord>> {
+        Ok(self.state.borrow().restores.clone())
+    }
+
+    fn record_bypass(&self, hook: &str, target: &str, via: &str) -> CcResult<GuardBypass> {
+        let entry = GuardBypass {
+            time: 0,
+            user: "Memory Backend".to_string(),
+            hook: hook.to_string(),
+            target: target.to_string(),
+            via: via.to_string(),
+        };
+        self.state.borrow_mut().bypasses.push(entry.clone());
+        Ok(entry)
+    }
+
+    fn guard_bypasses(&self) -> CcResult<Vec<GuardBypass>> {
+        Ok(self.state.borrow().bypasses.clone())
+    }
+
+    /// Checkpoints are created without an index, so nothing can be left staged
+    fn repair_interrupted_create(&self) -> CcResult<Option<IndexRepair>> {
+        Ok(None)
+    }
+
+    fn head_checkpoint(&self) -> CcResult<String> {
+        self.state.borrow().checkpoint_tip().ok_or_else(|| {
+            CheckpointError::GitOperationFailed(git2::Error::from_str("HEAD has no commits"))
+        })
+    }
+
+    /// Files missing from HEAD count as untracked
+    fn has_uncommitted_changes(&self) -> CcResult<bool> {
+        Ok(self.state.borrow().has_tracked_changes())
+    }
+
+    fn untracked_files(&self) -> CcResult<Vec<String>> {
+        Ok(self.state.borrow().untracked_files())
+    }
+
+    fn count_commits_between(&self, from_hash: &str, to_hash: &str) -> CcResult<usize> {
+        Ok(self.ahead_behind(from_hash, to_hash)?.ahead)
+    }
+
+    fn ahead_behind(&self, from_hash: &str, to_hash: &str) -> CcResult<AheadBehind> {
+        let state = self.state.borrow();
+        let from: HashSet<String> = state
+            .ancestors(&state.resolve(from_hash)?)
+            .into_iter()
+            .collect();
+        let to: HashSet<String> = state
+            .ancestors(&state.resolve(to_hash)?)
+            .into_iter()
+            .collect();
+        Ok(AheadBehind {
+            ahead: to.difference(&from).count(),
+            behind: from.difference(&to).count(),
+        })
+    }
+
+    /// Memory repositories have no ignored files and every file is writable;
+    /// untracked files that the checkpoint also contains are overwritten and
+    /// reported as updated
+    fn reset_branch_to_checkpoint(
+        &self,
+        hash: &str,
+        clean: bool,
+        _continue_on_error: bool,
+    ) -> CcResult<CheckoutReport> {
+        let mut state = self.state.borrow_mut();
+        let id = state.resolve(hash)?;
+        if state.has_tracked_changes() {
+            return Err(CheckpointError::UncommittedChanges);
+        }
+        let mut workdir = state.commits[&id].files.clone();
+        let mut report = CheckoutReport {
+            updated: changes(&state.head_files(), &workdir)
+                .into_iter()
+                .map(|line| line[2..].to_string())
+                .collect(),
+            ..Default::default()
+        };
+        for path in state.untracked_files() {
+            if workdir.contains_key(&path) {
+                continue;
+            }
+            if clean {
+                report.removed.push(path);
+            } else {
+                workdir.insert(path.clone(), state.workdir[&path].clone());
+                report.skipped.push(path);
+            }
+        }
+        state.workdir = workdir;
+        state.move_head(id);
+        Ok(report)
+    }
+
+    /// Writes the hash, message and changed files; `files` entries match
+    /// exact paths only (no glob support)
+    fn show_checkpoint(
+        &self,
+        hash: &str,
+        show_diff: bool,
+        _full: bool,
+        files: &[String],
+        out: &mut dyn Write,
+    ) -> CcResult<()> {
+        let state = self.state.borrow();
+        let id = state.resolve(hash)?;
+        let commit = &state.commits[&id];
+
+        writeln!(out, "Commit: {id}")?;
+        writeln!(out, "Message: {}", commit.message)?;
+        for line in changes(&state.parent_files(&id), &commit.files) {
+            if files.is_empty() || files.iter().any(|f| line[2..] == *f) {
+                writeln!(out, "  {line}")?;
+                if show_diff {
+                    writeln!(out, "  --- a/{0}\n  +++ b/{0}", &line[2..])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn base_branch_tip(&self) -> CcResult<Option<(String, String)>> {
+        let state = self.state.borrow();
+        Ok(match &state.head {
+            Head::Branch(name) if name != CCG_BRANCH_NAME => state
+                .branches
+                .get(name)
+                .map(|tip| (name.clone(), tip.clone())),
+            _ => None,
+        })
+    }
+
+    fn base_drift(&self, hash: &str) -> CcResult<Option<BaseDrift>> {
+        let (branch, base, tip) = {
+            let state = self.state.borrow();
+            let trailers = Trailers::parse(&state.commits[&state.resolve(hash)?].message);
+            let (Some(branch), Some(base)) = (
+                trailers.get(BASE_BRANCH_TRAILER).map(str::to_string),
+                trailers.base_commit().map(str::to_string),
+            ) else {
+                return Ok(None);
+            };
+            let Some(tip) = state.branches.get(&branch).cloned() else {
+                return Ok(None);
+            };
+            (branch, base, tip)
+        };
+        let commits = self.count_commits_between(&base, &tip)?;
+        Ok((commits > 0).then_some(BaseDrift {
+            branch,
+            base,
+            commits,
+        }))
+    }
+
+    fn branch_drift(&self) -> CcResult<BranchDrift> {
+        let state = self.state.borrow();
+        let checkpoint = state
+            .branches
+            .get(CCG_BRANCH_NAME)
+            .cloned()
+            .ok_or_else(|| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?;
+        let branch = match &state.head {
+            Head::Branch(name) if name == CCG_BRANCH_NAME => {
+                return Err(CheckpointError::InvalidArgument(
+                    "HEAD is on the ccg branch".to_string(),
+                ));
+            }
+            Head::Branch(name) => name.clone(),
+            Head::Detached(hash) => hash.clone(),
+        };
+        let head = state
+            .head_commit()
+            .ok_or_else(|| CheckpointError::BranchNotFound(branch.clone()))?;
+        let checkpoint_ancestors = state.ancestors(&checkpoint);
+        let base = state
+            .ancestors(&head)
+            .into_iter()
+            .find(|id| checkpoint_ancestors.contains(id));
+        let base_files = base
+            .as_ref()
+            .map(|id| state.commits[id].files.clone())
+            .unwrap_or_default();
+        let changed = |id: &str| -> BTreeMap<String, Option<String>> {
+            let files = &state.commits[id].files;
+            files
+                .keys()
+                .chain(base_files.keys())
+                .filter(|path| files.get(*path) != base_files.get(*path))
+                .map(|path| (path.clone(), files.get(path).cloned()))
+                .collect()
+        };
+        let (ours, theirs) = (changed(&checkpoint), changed(&head));
+
+        let mut drift = BranchDrift {
+            branch,
+            head: head.clone(),
+            checkpoint: checkpoint.clone(),
+            base,
+            ..BranchDrift::default()
+        };
+        for path in ours.keys().chain(theirs.keys()).collect::<BTreeSet<_>>() {
+            match (ours.get(path), theirs.get(path)) {
+                (Some(_), None) => drift.only_checkpoint.push(path.clone()),
+                (None, Some(_)) => drift.only_branch.push(path.clone()),
+                (Some(a), Some(b)) if a != b => drift.diverged.push(path.clone()),
+                _ => {}
+            }
+        }
+        Ok(drift)
+    }
+
+    /// Files missing from the checkpoint are reported as untracked
+    fn changes_since(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>> {
+        let state = self.state.borrow();
+        let checkpoint = &state.commits[&state.resolve(hash)?].files;
+        Ok(changes(checkpoint, &state.workdir)
+            .into_iter()
+            .map(|line| {
+                let status = match &line[..1] {
+                    "A" => git2::Delta::Untracked,
+                    "D" => git2::Delta::Deleted,
+                    _ => git2::Delta::Modified,
+                };
+                FileChangeInfo::new(&line[2..], status)
+            })
+            .collect())
+    }
+
+    /// Line counts treat every line of a changed file as added or removed
+    fn restore_preview(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>> {
+        let state = self.state.borrow();
+        let target = &state.commits[&state.resolve(hash)?].files;
+        let lines = |files: &Files, path: &str| {
+            files
+                .get(path)
+                .map_or(0, |content| content.lines().count() as i32)
+        };
+        Ok(changes(&state.workdir, target)
+            .into_iter()
+            .map(|line| {
+                let path = &line[2..];
+                let status = match &line[..1] {
+                    "A" => git2::Delta::Added,
+                    "D" => git2::Delta::Deleted,
+                    _ => git2::Delta::Modified,
+                };
+                FileChangeInfo::with_stats(
+                    path,
+                    status,
+                    lines(target, path),
+                    lines(&state.workdir, path),
+                )
+            })
+            .collect())
+    }
+
+    /// Every changed file is a single hunk replacing the whole file
+    fn restore_hunks(&self, hash: &str) -> CcResult<Vec<RestoreHunk>> {
+        let state = self.state.borrow();
+        let target = &state.commits[&state.resolve(hash)?].files;
+        let lines = |files: &Files, path: &str, origin: char| -> Vec<String> {
+            files
+                .get(path)
+                .map(|content| content.lines().map(|l| format!("{origin}{l}")).collect())
+                .unwrap_or_default()
+        };
+        Ok(changes(&state.workdir, target)
+            .into_iter()
+            .map(|line| {
+                let path = &line[2..];
+                let mut hunk_lines = lines(&state.workdir, path, '-');
+                hunk_lines.extend(lines(target, path, '+'));
+                RestoreHunk {
+                    path: path.to_string(),
+                    header: "@@".to_string(),
+                    lines: hunk_lines,
+                }
+            })
+            .collect())
+    }
+
+    fn apply_restore_hunks(&self, hash: &str, selected: &[bool]) -> CcResult<usize> {
+        let hunks = self.restore_hunks(hash)?;
+        if hunks.len() != selected.len() {
+            return Err(CheckpointError::InvalidArgument(
+                "工作目录在选择变更块期间发生了变化，请重新运行".to_string(),
+            ));
+        }
+        let mut state = self.state.borrow_mut();
+        let target = state.commits[&state.resolve(hash)?].files.clone();
+        let mut applied = 0;
+        for (hunk, _) in hunks.iter().zip(selected).filter(|(_, s)| **s) {
+            match target.get(&hunk.path) {
+                Some(content) => state.workdir.insert(hunk.path.clone(), content.clone()),
+                None => state.workdir.remove(&hunk.path),
+            };
+            applied += 1;
+        }
+        Ok(applied)
+    }
+
+    fn checkpoint_file(&self, hash: &str, path: &str) -> CcResult<Vec<u8>> {
+        let state = self.state.borrow();
+        let id = state.resolve(hash)?;
+        state.commits[&id]
+            .files
+            .get(path)
+            .map(|content| content.clone().into_bytes())
+            .ok_or_else(|| {
+                CheckpointError::InvalidArgument(format!("文件 '{path}' 不存在于检查点 {id} 中"))
+            })
+    }
+
+    fn export_checkpoint(&self, hash: &str, dir: &Path) -> CcResult<usize> {
+        let state = self.state.borrow();
+        let id = state.resolve(hash)?;
+        let files = &state.commits[&id].files;
+        for (path, contents) in files {
+            let target = dir.join(path);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(target, contents)?;
+        }
+        Ok(files.len())
+    }
+
+    fn file_history(&self, path: &str) -> CcResult<Vec<FileVersion>> {
+        let state = self.state.borrow();
+        let Some(tip) = state.checkpoint_tip() else {
+            return Ok(Vec::new());
+        };
+        let mut versions = Vec::new();
+        let mut previous: Option<&String> = None;
+        for id in state.ancestors(&tip).into_iter().rev() {
+            let content = state.commits[&id].files.get(path);
+            if content == previous {
+                continue;
+            }
+            previous = content;
+            versions.push(FileVersion {
+                checkpoint: CheckpointEntry {
+                    message: state.commits[&id].message.clone(),
+                    author: "Memory Backend".to_string(),
+                    time: 0,
+                    id: state
+                        .checkpoint_ids
+                        .iter()
+                        .find(|(_, hash)| **hash == id)
+                        .map(|(number, _)| *number),
+                    hash: id,
+                },
+                content: content.map(|content| content.clone().into_bytes()),
+            });
+        }
+        Ok(versions)
+    }
+
+    /// Line counts treat every line of a changed file as added or removed,
+    /// and the patch is the one-line change summary of `diff_checkpoints`
+    fn file_patches(&self, from: Option<&str>, to: &str) -> CcResult<Vec<FilePatch>> {
+        let state = self.state.borrow();
+        let old = match from {
+            Some(from) => state.commits[&state.resolve(from)?].files.clone(),
+            None => Files::new(),
+        };
+        let new = &state.commits[&state.resolve(to)?].files;
+        let lines = |files: &Files, path: &str| {
+            files
+                .get(path)
+                .map_or(0, |content| content.lines().count() as i32)
+        };
+        Ok(changes(&old, new)
+            .into_iter()
+            .map(|line| {
+                let path = &line[2..];
+                let status = match &line[..1] {
+                    "A" => git2::Delta::Added,
+                    "D" => git2::Delta::Deleted,
+                    _ => git2::Delta::Modified,
+                };
+                FilePatch {
+                    change: FileChangeInfo::with_stats(
+                        path,
+                        status,
+                        lines(new, path),
+                        lines(&old, path),
+                    ),
+                    patch: line.clone(),
+                }
+            })
+            .collect())
+    }
+
+    fn diff_checkpoints(&self, hash_a: &str, hash_b: Option<&str>) -> CcResult<String> {
+        let state = self.state.borrow();
+        let a = state.resolve(hash_a)?;
+        let new = match hash_b {
+            Some(hash_b) => state.commits[&state.resolve(hash_b)?].files.clone(),
+            None => state.workdir.clone(),
+        };
+        Ok(changes(&state.commits[&a].files, &new).join("\n"))
+    }
+
+    /// Reads the directory from disk; ignore rules are not applied
+    fn diff_directory(&self, hash: &str, dir: &Path) -> CcResult<String> {
+        fn read_files(dir: &Path, prefix: &str, files: &mut Files) -> CcResult<()> {
+            for entry in std::fs::read_dir(dir)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name == ".git" {
+                    continue;
+                }
+                let path = format!("{prefix}{name}");
+                if entry.file_type()?.is_dir() {
+                    read_files(&entry.path(), &format!("{path}/"), files)?;
+                } else {
+                    let content = std::fs::read(entry.path())?;
+                    files.insert(path, String::from_utf8_lossy(&content).into_owned());
+                }
+            }
+            Ok(())
+        }
+
+        let state = self.state.borrow();
+        let a = state.resolve(hash)?;
+        let mut files = Files::new();
+        read_files(dir, "", &mut files)?;
+        Ok(changes(&state.commits[&a].files, &files).join("\n"))
+    }
+
+    /// Overlap is detected per file: any shared file counts as overlapping lines
+    fn checkpoint_impact(&self, hash: &str) -> CcResult<ImpactReport> {
+        let state = self.state.borrow();
+        let target = state.resolve(hash)?;
+        let tip = state
+            .branches
+            .get(CCG_BRANCH_NAME)
+            .cloned()
+            .ok_or_else(|| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?;
+        let ancestors = state.ancestors(&tip);
+        let later: Vec<&String> = ancestors.iter().take_while(|id| **id != target).collect();
+        if later.len() == ancestors.len() {
+            return Err(CheckpointError::CheckpointNotFound(hash.to_string()));
+        }
+
+        let paths = |id: &str| -> Vec<String> {
+            changes(&state.parent_files(id), &state.commits[id].files)
+                .into_iter()
+                .map(|line| line[2..].to_string())
+                .collect()
+        };
+        let target_paths = paths(&target);
+        let overlaps = later
+            .iter()
+            .rev()
+            .filter_map(|id| {
+                let files: Vec<String> = paths(id)
+                    .into_iter()
+                    .filter(|path| target_paths.contains(path))
+                    .collect();
+                (!files.is_empty()).then(|| CheckpointOverlap {
+                    hash: (*id).clone(),
+                    summary: state.commits[*id]
+                        .message
+                        .lines()
+                        .next()
+                        .unwrap_or("")
+                        .to_string(),
+                    overlapping_lines: files.clone(),
+                    files,
+                })
+            })
+            .collect();
+        let revert_conflicts = target_paths
+            .into_iter()
+            .filter(|path| {
+                state.commits[&tip].files.get(path) != state.commits[&target].files.get(path)
+            })
+            .collect();
+
+        Ok(ImpactReport {
+            hash: target,
+            later_checkpoints: later.len(),
+            overlaps,
+            revert_conflicts,
+        })
+    }
+
+    /// Conflicts are detected per file rather than per line
+    fn revert_checkpoint(&self, hash: &str) -> CcResult<String> {
+        let mut state = self.state.borrow_mut();
+        let target = state.resolve(hash)?;
+        let head_files = state.head_files();
+        let before = state.parent_files(&target);
+        let after = state.commits[&target].files.clone();
+
+        let mut files = head_files.clone();
+        let mut conflicts = Vec::new();
+        for line in changes(&before, &after) {
+            let path = &line[2..];
+            if head_files.get(path) != after.get(path)
+                || state.workdir.get(path) != head_files.get(path)
+            {
+                conflicts.push(path.to_string());
+                continue;
+            }
+            match before.get(path) {
+                Some(contents) => files.insert(path.to_string(), contents.clone()),
+                None => files.remove(path),
+            };
+        }
+        if !conflicts.is_empty() {
+            return Err(CheckpointError::Conflict(conflicts.join(", ")));
+        }
+        if files == head_files {
+            return Err(CheckpointError::NoChangesToCommit);
+        }
+
+        for line in changes(&head_files, &files) {
+            let path = &line[2..];
+            match files.get(path) {
+                Some(contents) => state.workdir.insert(path.to_string(), contents.clone()),
+                None => state.workdir.remove(path),
+            };
+        }
+        let summary = state.commits[&target].message.lines().next().unwrap_or("");
+        let commit = MemoryCommit {
+            message: format!("Revert \"{summary}\""),
+            parent: state.head_commit(),
+            files,
+        };
+        let id = state.next_hash();
+        state.commits.insert(id.clone(), commit);
+        state.move_head(id.clone());
+        state.assign_checkpoint_id(&id);
+        Ok(id)
+    }
+
+    fn active_task(&self) -> CcResult<Option<Task>> {
+        Ok(self.state.borrow().active_task.clone())
+    }
+
+    fn set_active_task(&self, task: Option<&Task>) -> CcResult<()> {
+        self.state.borrow_mut().active_task = task.cloned();
+        Ok(())
+    }
+
+    fn checkpoint_parent(&self, hash: &str) -> CcResult<Option<String>> {
+        let state = self.state.borrow();
+        let commit = state
+            .commits
+            .get(hash)
+            .ok_or_else(|| CheckpointError::CheckpointNotFound(hash.to_string()))?;
+        Ok(commit.parent.clone())
+    }
+
+    /// Conflicts are detected per file rather than per line
+    /// Line counts treat every line of a changed file as added or removed
+    fn checkpoint_stats(&self, hashes: &[String]) -> CcResult<Vec<DiffStats>> {
+        let state = self.state.borrow();
+        let lines = |files: &Files, path: &str| {
+            files
+                .get(path)
+                .map_or(0, |content| content.lines().count() as i32)
+        };
+        hashes
+            .iter()
+            .map(|hash| {
+                let id = state.resolve(hash)?;
+                let files = &state.commits[&id].files;
+                let parent = state.parent_files(&id);
+                let changed = changes(&parent, files);
+                Ok(DiffStats {
+                    total_files: changed.len(),
+                    additions: changed.iter().map(|line| lines(files, &line[2..])).sum(),
+                    deletions: changed.iter().map(|line| lines(&parent, &line[2..])).sum(),
+                    modifications: changed.iter().filter(|line| line.starts_with('M')).count()
+                        as i32,
+                    format_only: state.format_only(&id),
+                    ..DiffStats::new()
+                })
+            })
+            .collect()
+    }
+
+    fn is_format_only(&self, hash: &str) -> CcResult<bool> {
+        let state = self.state.borrow();
+        let id = state.resolve(hash)?;
+        Ok(state.format_only(&id))
+    }
+
+    fn squash_onto_head(
+        &self,
+        base: &str,
+        tip: &str,
+        skip: &[String],
+        message: &str,
+    ) -> CcResult<String> {
+        let mut state = self.state.borrow_mut();
+        let head_files = state.head_files();
+        let before = state.commits[base].files.clone();
+        let mut after = state.commits[tip].files.clone();
+        for hash in skip {
+            let id = state.resolve(hash)?;
+            let skipped = &state.commits[&id].files;
+            let parent = state.parent_files(&id);
+            let mut conflicts = Vec::new();
+            for line in changes(skipped, &parent) {
+                let path = &line[2..];
+                if after.get(path) != skipped.get(path) {
+                    conflicts.push(path.to_string());
+                    continue;
+                }
+                match parent.get(path) {
+                    Some(contents) => after.insert(path.to_string(), contents.clone()),
+                    None => after.remove(path),
+                };
+            }
+            if !conflicts.is_empty() {
+                return Err(CheckpointError::Conflict(conflicts.join(", ")));
+            }
+        }
+
+        let mut files = head_files.clone();
+        let mut conflicts = Vec::new();
+        for line in changes(&before, &after) {
+            let path = &line[2..];
+            if head_files.get(path) != before.get(path) && head_files.get(path) != after.get(path) {
+                conflicts.push(path.to_string());
+                continue;
+            }
+            match after.get(path) {
+                Some(contents) => files.insert(path.to_string(), contents.clone()),
+                None => files.remove(path),
+            };
+        }
+        if !conflicts.is_empty() {
+            return Err(CheckpointError::Conflict(conflicts.join(", ")));
+        }
+        if files == head_files {
+            return Err(CheckpointError::NoChangesToCommit);
+        }
+        // 仍保留跳过的变更的工作目录文件不修改
+        let tip_files = state.commits[tip].files.clone();
+        for line in changes(&head_files, &files) {
+            let path = &line[2..];
+            if !skip.is_empty() && state.workdir.get(path) == tip_files.get(path) {
+                continue;
+            }
+            match files.get(path) {
+                Some(contents) => state.workdir.insert(path.to_string(), contents.clone()),
+                None => state.workdir.remove(path),
+            };
+        }
+        Ok(state.commit_files(message, files))
+    }
+
+    fn reword_checkpoint(&self, hash: &str, message: &str) -> CcResult<RewrittenHistory> {
+        let mut state = self.state.borrow_mut();
+        let target = state.resolve(hash)?;
+        let mut replacement = state.commits[&target].clone();
+        replacement.message = message.to_string();
+        state.rewrite(&target, vec![replacement])
+    }
+
+    fn rebase_candidates(&self, onto: &str) -> CcResult<Vec<CheckpointEntry>> {
+        let state = self.state.borrow();
+        let (_, moved) = state.rebase_candidates(onto)?;
+        Ok(moved.iter().rev().map(|id| state.entry(id)).collect())
+    }
+
+    /// Replayed changes conflict per file rather than per line
+    fn rebase_checkpoints(
+        &self,
+        onto: &str,
+        replay: bool,
+        progress: &mut dyn FnMut(usize, usize, &str),
+    ) -> CcResult<RewrittenHistory> {
+        let mut state = self.state.borrow_mut();
+        let (onto, moved) = state.rebase_candidates(onto)?;
+        if moved.is_empty() {
+            let tip = state.branches[CCG_BRANCH_NAME].clone();
+            return Ok(RewrittenHistory {
+                replacements: Vec::new(),
+                replayed: Vec::new(),
+                tip,
+            });
+        }
+
+        let mut parent = onto;
+        let mut replayed = Vec::new();
+        for (position, id) in moved.iter().enumerate() {
+            progress(position + 1, moved.len(), id);
+            let mut commit = state.commits[id].clone();
+            if replay {
+                let original_parent = state.parent_files(id);
+                let mut files = state.commits[&parent].files.clone();
+                let mut conflicts = Vec::new();
+                for line in changes(&original_parent, &commit.files) {
+                    let path = &line[2..];
+                    let ours = files.get(path);
+                    if ours != original_parent.get(path) && ours != commit.files.get(path) {
+                        conflicts.push(path.to_string());
+                        continue;
+                    }
+                    match commit.files.get(path) {
+                        Some(contents) => files.insert(path.to_string(), contents.clone()),
+                        None => files.remove(path),
+                    };
+                }
+                if !conflicts.is_empty() {
+                    return Err(CheckpointError::Conflict(conflicts.join(", ")));
+                }
+                commit.files = files;
+            }
+            commit.parent = Some(parent);
+            let new_id = state.next_hash();
+            state.commits.insert(new_id.clone(), commit);
+            replayed.push((id.clone(), new_id.clone()));
+            parent = new_id;
+        }
+
+        state
+            .branches
+            .insert(CCG_BRANCH_NAME.to_string(), parent.clone());
+        let moved: HashMap<String, String> = replayed.iter().cloned().collect();
+        for id in state.checkpoint_ids.values_mut() {
+            if let Some(new_id) = moved.get(id) {
+                *id = new_id.clone();
+            }
+        }
+        state.copy_notes(&moved);
+        Ok(RewrittenHistory {
+            replacements: Vec::new(),
+            replayed,
+            tip: parent,
+        })
+    }
+
+    fn split_checkpoint(&self, hash: &str) -> CcResult<RewrittenHistory> {
+        let mut state = self.state.borrow_mut();
+        let target = state.resolve(hash)?;
+        let original = state.commits[&target].clone();
+        let mut files = state.parent_files(&target);
+        let changed = changes(&files, &original.files);
+        if changed.len() < 2 {
+            return Err(CheckpointError::InvalidArgument(
+                t!(
+                    "split_too_few_files",
+                    hash = short_hash(&target),
+                    count = changed.len()
+                )
+                .to_string(),
+            ));
+        }
+
+        let summary = original.message.lines().next().unwrap_or("");
+        let replacements = changed
+            .iter()
+            .map(|line| {
+                let path = &line[2..];
+                match original.files.get(path) {
+                    Some(contents) => files.insert(path.to_string(), contents.clone()),
+                    None => files.remove(path),
+                };
+                MemoryCommit {
+                    message: format!("{summary} ({path})"),
+                    parent: None,
+                    files: files.clone(),
+                }
+            })
+            .collect();
+        state.rewrite(&target, replacements)
+    }
+
+    fn drop_checkpoints(&self, hashes: &[String]) -> CcResult<RewrittenHistory> {
+        let mut state = self.state.borrow_mut();
+        let dropped = hashes
+            .iter()
+            .map(|hash| state.resolve(hash))
+            .collect::<CcResult<HashSet<String>>>()?;
+        let tip = state
+            .branches
+            .get(CCG_BRANCH_NAME)
+            .cloned()
+            .ok_or_else(|| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?;
+        let mut chain = state.ancestors(&tip);
+        chain.reverse();
+        if let Some(missing) = dropped.iter().find(|id| !chain.contains(id)) {
+            return Err(CheckpointError::CheckpointNotFound(missing.clone()));
+        }
+        let Some(start) = chain.iter().position(|id| dropped.contains(id)) else {
+            return Ok(RewrittenHistory {
+                replacements: Vec::new(),
+                replayed: Vec::new(),
+                tip,
+            });
+        };
+
+        let mut parent = state.commits[&chain[start]].parent.clone();
+        let mut replayed = Vec::new();
+        for id in chain[start..].iter().filter(|id| !dropped.contains(*id)) {
+            let mut commit = state.commits[id].clone();
+            commit.parent = parent;
+            let new_id = state.next_hash();
+            state.commits.insert(new_id.clone(), commit);
+            replayed.push((id.clone(), new_id.clone()));
+            parent = Some(new_id);
+        }
+        let tip = parent
+            .ok_or_else(|| CheckpointError::InvalidArgument(t!("prune_drop_all").to_string()))?;
+        state
+            .branches
+            .insert(CCG_BRANCH_NAME.to_string(), tip.clone());
+
+        let moved: HashMap<String, String> = replayed.iter().cloned().collect();
+        for id in state.checkpoint_ids.values_mut() {
+            if let Some(new_id) = moved.get(id) {
+                *id = new_id.clone();
+            }
+        }
+        state.copy_notes(&moved);
+        Ok(RewrittenHistory {
+            replacements: Vec::new(),
+            replayed,
+            tip,
+        })
+    }
+
+    fn list_snapshots(&self) -> CcResult<Vec<Snapshot>> {
+        let state = self.state.borrow();
+        Ok(state
+            .snapshots
+            .iter()
+            .rev()
+            .map(|(snapshot, _, _)| snapshot.clone())
+            .collect())
+    }
+
+    fn push_snapshot(&self, message: &str) -> CcResult<Snapshot> {
+        let mut state = self.state.borrow_mut();
+        let base = state.head_files();
+        if state.workdir == base {
+            return Err(CheckpointError::NoChangesToCommit);
+        }
+        let snapshot = Snapshot {
+            number: state.snapshots.last().map_or(0, |(s, _, _)| s.number + 1),
+            hash: state.next_hash(),
+            message: message.to_string(),
+            time: 0,
+        };
+        let files = std::mem::replace(&mut state.workdir, base.clone());
+        state.snapshots.push((snapshot.clone(), base, files));
+        Ok(snapshot)
+    }
+
+    /// Conflicts are detected per file rather than per line
+    fn pop_snapshot(&self, number: Option<usize>) -> CcResult<Snapshot> {
+        let mut state = self.state.borrow_mut();
+        let position = match number {
+            Some(number) => state
+                .snapshots
+                .iter()
+                .position(|(s, _, _)| s.number == number),
+            None => state.snapshots.len().checked_sub(1),
+        }
+        .ok_or_else(|| CheckpointError::InvalidArgument("no such snapshot".to_string()))?;
+        let (_, base, files) = &state.snapshots[position];
+        let mut workdir = state.workdir.clone();
+        let mut conflicts = Vec::new();
+        for line in changes(base, files) {
+            let path = &line[2..];
+            if workdir.get(path) != base.get(path) && workdir.get(path) != files.get(path) {
+                conflicts.push(path.to_string());
+                continue;
+            }
+            match files.get(path) {
+                Some(contents) => workdir.insert(path.to_string(), contents.clone()),
+                None => workdir.remove(path),
+            };
+        }
+        if !conflicts.is_empty() {
+            return Err(CheckpointError::Conflict(conflicts.join(", ")));
+        }
+        state.workdir = workdir;
+        Ok(state.snapshots.remove(position).0)
+    }
+}
@@ -0,0 +1,112 @@
+//! Object formats: SHA-1 and SHA-256 repositories
+//!
+//! Git can store objects under SHA-256 names (`git init
+//! --object-format=sha256`), which makes full hashes 64 hex digits instead
+//! of 40. Hash validation and the resolver ask the repository's
+//! [`ObjectFormat`] for the full length instead of assuming SHA-1.
+//!
+//! The libgit2 that ccg is built on reads SHA-1 repositories only. A
+//! SHA-256 repository is recognized from its configuration and reported as
+//! unsupported, rather than treated as "not a repository" and offered
+//! `ccg init`.
+
+use crate::error::{CheckpointError, Result as CcResult};
+use git2::Repository;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Configuration key naming the object format (absent for SHA-1)
+pub const OBJECT_FORMAT_KEY: &str = "extensions.objectformat";
+
+/// Hash function used to name a repository's objects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ObjectFormat {
+    /// The original format, 40 hex digit hashes
+    #[default]
+    Sha1,
+    /// 64 hex digit hashes
+    Sha256,
+}
+
+impl ObjectFormat {
+    /// All object formats
+    pub const ALL: [ObjectFormat; 2] = [ObjectFormat::Sha1, ObjectFormat::Sha256];
+
+    /// Name used by `extensions.objectformat`
+    pub fn name(self) -> &'static str {
+        match self {
+            ObjectFormat::Sha1 => "sha1",
+            ObjectFormat::Sha256 => "sha256",
+        }
+    }
+
+    /// Number of hex digits in a full hash
+    pub fn hex_len(self) -> usize {
+        match self {
+            ObjectFormat::Sha1 => 40,
+            ObjectFormat::Sha256 => 64,
+        }
+    }
+
+    /// Whether `text` is a complete hash in this format
+    pub fn is_full_hash(self, text: &str) -> bool {
+        text.len() == self.hex_len() && text.bytes().all(|b| b.is_ascii_hexdigit())
+    }
+
+    /// Whether this build of ccg can read repositories in this format
+    pub fn is_supported(self) -> bool {
+        self == ObjectFormat::Sha1
+    }
+
+    /// The object format of an open repository
+    pub fn of(repo: &Repository) -> Self {
+        repo.config()
+            .and_then(|config| config.get_string(OBJECT_FORMAT_KEY))
+            .ok()
+            .and_then(|name| name.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// The object format of the repository containing `path`, read straight
+    /// from its configuration file so it also works where libgit2 refuses
+    /// to open the repository
+    ///
+    /// # Returns
+    /// None if no repository contains `path`
+    pub fn at(path: &Path) -> Option<Self> {
+        let path = path.canonicalize().ok()?;
+        let git_dir = path.ancestors().find_map(|dir| {
+            [dir.join(".git"), dir.to_path_buf()]
+                .into_iter()
+                .find(|candidate| candidate.join("HEAD").is_file())
+        })?;
+        let config = git2::Config::open(&git_dir.join("config")).ok()?;
+        Some(match config.get_string(OBJECT_FORMAT_KEY) {
+            Ok(name) => name.parse().ok()?,
+            Err(_) => ObjectFormat::Sha1,
+        })
+    }
+
+    /// Fail with `CheckpointError::UnsupportedObjectFormat` when the
+    /// repository containing `path` uses a format this build cannot read
+    pub fn check_supported(path: &Path) -> CcResult<()> {
+        match Self::at(path) {
+            Some(format) if !format.is_supported() => Err(
+                CheckpointError::UnsupportedObjectFormat(format.name().to_string()),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for ObjectFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        ObjectFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| format!("unknown object format: {s}"))
+    }
+}
@@ -0,0 +1,75 @@
+//! File paths that are not valid UTF-8
+//!
+//! Git stores paths as raw bytes, and on Linux a file name can be any byte
+//! sequence (Latin-1 names copied from old archives are the usual case).
+//! Operations that touch the working directory, the index or a pathspec
+//! must use those original bytes; converting them with `to_string_lossy`
+//! turns the invalid bytes into U+FFFD and names a different file.
+//!
+//! Paths are therefore carried as [`PathBuf`] and only turned into text for
+//! display, where [`display_path`] escapes the invalid bytes so that two
+//! different names never print the same. Backslashes are doubled for the
+//! same reason: otherwise a file really named `a\x80` would print like `a`
+//! followed by the invalid byte 0x80.
+
+use std::path::{Path, PathBuf};
+
+/// A path for display: valid UTF-8 is shown as is except that `\` is
+/// written as `\\`, and every byte that is not part of a valid UTF-8
+/// sequence is written as `\xNN`
+pub fn display_path(path: &Path) -> String {
+    if let Some(text) = path.to_str()
+        && !text.contains('\\')
+    {
+        return text.to_string();
+    }
+    display_bytes(path.as_os_str().as_encoded_bytes())
+}
+
+/// [`display_path`] for a path given as git's raw bytes
+pub fn display_bytes(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        result.push_str(&chunk.valid().replace('\\', "\\\\"));
+        for byte in chunk.invalid() {
+            result.push_str(&format!("\\x{byte:02x}"));
+        }
+    }
+    result
+}
+
+/// The path named by git's raw path bytes
+///
+/// On Unix the bytes are used unchanged. Elsewhere paths in git are always
+/// UTF-8, and invalid bytes are replaced.
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// A pathspec that matches `path` and nothing else
+///
+/// git2's checkout options have no switch to turn pathspec matching off, so
+/// file names such as `[ab].txt` or `*.log` would select other files too.
+/// Wildcards, backslashes and spaces are escaped with a backslash, as is a
+/// leading `!` or `#`, which would otherwise negate or drop the pattern.
+pub fn literal_pathspec(path: &Path) -> PathBuf {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    let mut escaped = Vec::with_capacity(bytes.len());
+    for (index, byte) in bytes.iter().enumerate() {
+        let special = matches!(byte, b'\\' | b'*' | b'?' | b'[' | b']' | b' ')
+            || (index == 0 && matches!(byte, b'!' | b'#'));
+        if special {
+            escaped.push(b'\\');
+        }
+        escaped.push(*byte);
+    }
+    path_from_bytes(&escaped)
+}
@@ -0,0 +1,183 @@
+//! Queue of hook events waiting to be retried
+//!
+//! When a hook-triggered checkpoint fails for a transient reason (another
+//! process holding a lock, a short-lived I/O error), the raw hook payload is
+//! written to `.git/ccg/pending/`, one file per event, and replayed later.
+//! File names start with the enqueue time so events replay in order, and end
+//! with the payload's hash so the same event is never queued twice. An event
+//! is only removed once it has been replayed, so delivery is at-least-once.
+//!
+//! Each failed replay is counted in the file name, between the time and the
+//! hash. An event that still fails after [`MAX_ATTEMPTS`] replays is dropped
+//! so it cannot hold up the events queued after it forever.
+
+use super::ccg_dir;
+use crate::error::Result as CcResult;
+use git2::{ObjectType, Oid, Repository};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Replays after which an event that keeps failing is dropped
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// A queued hook event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEvent {
+    /// File holding the event
+    pub path: PathBuf,
+    /// The hook payload, exactly as it was received
+    pub payload: String,
+    /// Replays that have failed so far
+    pub attempts: u32,
+}
+
+/// The pending hook event queue
+pub struct PendingQueue<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> PendingQueue<'a> {
+    /// Create a new PendingQueue instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// Directory holding the queued events
+    pub fn dir(&self) -> PathBuf {
+        ccg_dir(self.repo).join("pending")
+    }
+
+    /// Queue a hook payload
+    ///
+    /// The file is written under a temporary name and renamed into place, so
+    /// a concurrent replay never reads a partial event.
+    ///
+    /// # Returns
+    /// false if the same payload is already queued
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the event cannot be written
+    pub fn push(&self, payload: &str) -> CcResult<bool> {
+        let id = Oid::hash_object(ObjectType::Blob, payload.as_bytes())?;
+        let suffix = format!("-{id}.json");
+        if self
+            .events()?
+            .iter()
+            .any(|path| path.to_string_lossy().ends_with(&suffix))
+        {
+            return Ok(false);
+        }
+
+        let dir = self.dir();
+        fs::create_dir_all(&dir)?;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+        let name = format!("{nanos:020}{suffix}");
+        let temp = dir.join(format!(".{name}.tmp"));
+        fs::write(&temp, payload)?;
+        fs::rename(&temp, dir.join(name))?;
+        Ok(true)
+    }
+
+    /// Queued events, oldest first
+    ///
+    /// Events whose file disappeared in the meantime (replayed by another
+    /// process) are skipped.
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the queue cannot be read
+    pub fn entries(&self) -> CcResult<Vec<PendingEvent>> {
+        let mut entries = Vec::new();
+        for path in self.events()? {
+            match fs::read_to_string(&path) {
+                Ok(payload) => {
+                    let attempts = Self::attempts(&path);
+                    entries.push(PendingEvent {
+                        path,
+                        payload,
+                        attempts,
+                    })
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Number of queued events
+    pub fn len(&self) -> CcResult<usize> {
+        Ok(self.events()?.len())
+    }
+
+    /// Whether no events are queued
+    pub fn is_empty(&self) -> CcResult<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Count a failed replay of an event that stays queued
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the event cannot be renamed
+    pub fn record_attempt(&self, event: &PendingEvent) -> CcResult<()> {
+        let attempts = event.attempts + 1;
+        let name = event
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // 文件名为 `<时间>-<哈希>.json` 或 `<时间>-<次数>-<哈希>.json`
+        let mut parts: Vec<&str> = name.split('-').collect();
+        if parts.len() == 3 {
+            parts.remove(1);
+        }
+        let renamed = format!("{}-{attempts}-{}", parts[0], parts[parts.len() - 1]);
+        fs::rename(&event.path, event.path.with_file_name(renamed))?;
+        Ok(())
+    }
+
+    /// Failed replays recorded in an event's file name
+    fn attempts(path: &Path) -> u32 {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let parts: Vec<&str> = name.split('-').collect();
+        match parts.as_slice() {
+            [_, attempts, _] => attempts.parse().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Remove a replayed event from the queue
+    ///
+    /// Removing an event that is already gone is not an error.
+    pub fn remove(&self, event: &PendingEvent) -> CcResult<()> {
+        match fs::remove_file(&event.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Paths of the queued event files, sorted by name
+    fn events(&self) -> CcResult<Vec<PathBuf>> {
+        let read = match fs::read_dir(self.dir()) {
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut paths = Vec::new();
+        for entry in read {
+            let path = entry?.path();
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned());
+            if name.is_some_and(|name| name.ends_with(".json") && !name.starts_with('.')) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+}
@@ -0,0 +1,212 @@
+//! Targeted repacking of large files that checkpoints store repeatedly
+//!
+//! Each checkpoint of a multi-megabyte file writes a new loose object holding
+//! the whole file, compressed on its own. A repack collects the loose versions
+//! of such files from the CCG branch and hands them to `git pack-objects`
+//! together with their paths, so versions of the same file are compared with
+//! each other and stored as deltas. The delta window and depth come from
+//! `ccg.repack.*`. Loose copies are removed afterwards with `git prune-packed`;
+//! objects that are already packed are left to `git gc`.
+//!
+//! `.git/ccg/repack` records the last report as
+//! `<time> <paths> <objects> <loose bytes> <packed bytes>`.
+
+use super::ccg_dir;
+use super::types::{CCG_BRANCH_NAME, RepackReport, RepackSettings};
+use crate::error::{CheckpointError, Result as CcResult};
+use git2::{Delta, Oid, Repository, Sort};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Repacks large files on the CCG branch
+pub struct Repacker<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> Repacker<'a> {
+    /// Create a new Repacker instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    fn report_path(&self) -> PathBuf {
+        ccg_dir(self.repo).join("repack")
+    }
+
+    /// Path of the loose object file for `id`
+    fn loose_path(&self, id: Oid) -> PathBuf {
+        let hex = id.to_string();
+        self.repo
+            .path()
+            .join("objects")
+            .join(&hex[..2])
+            .join(&hex[2..])
+    }
+
+    /// Loose blobs of at least `min_size` bytes that checkpoints introduced,
+    /// grouped by path
+    ///
+    /// # Returns
+    /// The object ids of each path, oldest first, for paths with at least two
+    /// such versions; a single version has nothing to delta against
+    pub fn candidates(&self, min_size: u64) -> CcResult<BTreeMap<String, Vec<Oid>>> {
+        let tip = self
+            .repo
+            .find_reference(&format!("refs/heads/{CCG_BRANCH_NAME}"))
+            .ok()
+            .and_then(|reference| reference.target())
+            .ok_or_else(|| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?;
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+        revwalk.push(tip)?;
+
+        let odb = self.repo.odb()?;
+        let mut seen = HashSet::new();
+        let mut versions: BTreeMap<String, Vec<Oid>> = BTreeMap::new();
+        for id in revwalk {
+            let commit = self.repo.find_commit(id?)?;
+            let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+            let diff =
+                self.repo
+                    .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+            for delta in diff.deltas() {
+                let file = delta.new_file();
+                if delta.status() == Delta::Deleted
+                    || !seen.insert(file.id())
+                    || !self.loose_path(file.id()).is_file()
+                    || odb.read_header(file.id())?.0 < min_size as usize
+                {
+                    continue;
+                }
+                let Some(path) = file.path() else {
+                    continue;
+                };
+                versions
+                    .entry(path.to_string_lossy().into_owned())
+                    .or_default()
+                    .push(file.id());
+            }
+        }
+        versions.retain(|_, ids| ids.len() >= 2);
+        Ok(versions)
+    }
+
+    /// Pack the loose versions of large files with delta compression
+    ///
+    /// # Returns
+    /// What was packed and how much space it saved; all counts are zero when
+    /// no file qualified. The report is also saved for `ccg stats`.
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if git cannot be started and
+    /// CheckpointError::GitOperationFailed with git's message if it fails
+    pub fn repack(&self, settings: &RepackSettings) -> CcResult<RepackReport> {
+        let candidates = self.candidates(settings.min_size)?;
+        let mut report = RepackReport {
+            time: chrono::Utc::now().timestamp(),
+            ..RepackReport::default()
+        };
+        if candidates.is_empty() {
+            return Ok(report);
+        }
+
+        // 每行 `<oid> <path>`：git 按路径为对象排序，同一文件的版本互相作为增量基准
+        let mut input = String::new();
+        for (path, ids) in &candidates {
+            for id in ids {
+                report.loose_bytes += fs::metadata(self.loose_path(*id))?.len();
+                input.push_str(&format!("{id} {path}\n"));
+            }
+            report.objects += ids.len() as u64;
+        }
+        report.paths = candidates.len() as u64;
+
+        let base = self.repo.path().join("objects").join("pack").join("pack");
+        let name = self.git(
+            &[
+                "pack-objects",
+                "-q",
+                "--delta-base-offset",
+                &format!("--window={}", settings.window),
+                &format!("--depth={}", settings.depth),
+                &base.to_string_lossy(),
+            ],
+            &input,
+        )?;
+        let pack = base.with_file_name(format!("pack-{}.pack", name.trim()));
+        report.packed_bytes = fs::metadata(pack)?.len();
+        self.git(&["prune-packed", "-q"], "")?;
+
+        let path = self.report_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(
+            path,
+            format!(
+                "{} {} {} {} {}\n",
+                report.time, report.paths, report.objects, report.loose_bytes, report.packed_bytes
+            ),
+        )?;
+        Ok(report)
+    }
+
+    /// The report of the last repack that packed anything, if any
+    pub fn last_report(&self) -> CcResult<Option<RepackReport>> {
+        let content = match fs::read_to_string(self.report_path()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        let [time, paths, objects, loose_bytes, packed_bytes] = fields[..] else {
+            return Ok(None);
+        };
+        let parse = |field: &str| field.parse::<u64>().ok();
+        let report = match (
+            time.parse().ok(),
+            parse(paths),
+            parse(objects),
+            parse(loose_bytes),
+            parse(packed_bytes),
+        ) {
+            (Some(time), Some(paths), Some(objects), Some(loose_bytes), Some(packed_bytes)) => {
+                Some(RepackReport {
+                    time,
+                    paths,
+                    objects,
+                    loose_bytes,
+                    packed_bytes,
+                })
+            }
+            _ => None,
+        };
+        Ok(report)
+    }
+
+    /// Run git on the repository with `input` on standard input
+    fn git(&self, args: &[&str], input: &str) -> CcResult<String> {
+        let mut child = Command::new("git")
+            .arg("--git-dir")
+            .arg(self.repo.path())
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CheckpointError::GitOperationFailed(git2::Error::from_str(
+                stderr.trim(),
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
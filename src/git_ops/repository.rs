@@ -3,9 +3,13 @@
 //! This module handles repository-level operations including initialization,
 //! opening existing repositories, and basic validation.
 
+use super::object_format::ObjectFormat;
 use super::types::CCG_BRANCH_NAME;
 use crate::error::{CheckpointError, Result as CcResult};
-use git2::Repository;
+use crate::ui::Icon;
+use git2::{Oid, Repository, RepositoryInitOptions};
+use rust_i18n::t;
+use std::fs;
 use std::path::Path;
 
 /// Operations related to repository initialization and management
@@ -27,7 +31,10 @@ impl<'a> RepositoryOperations<'a> {
 
     /// Initialize a new Git repository in the current directory
     pub fn init_repository(path: &str) -> CcResult<Repository> {
-        println!("📁 在 '{path}' 目录检测到不是Git仓库，正在初始化...");
+        println!(
+            "{}在 '{path}' 目录检测到不是Git仓库，正在初始化...",
+            Icon::Folder.prefix()
+        );
 
         // 初始化Git仓库
         let repo = Repository::init(path).map_err(CheckpointError::GitOperationFailed)?;
@@ -38,7 +45,7 @@ impl<'a> RepositoryOperations<'a> {
         repo.set_head(&ccg_ref)
             .map_err(CheckpointError::GitOperationFailed)?;
 
-        println!("✅ Git仓库初始化成功");
+        println!("{}Git仓库初始化成功", Icon::Success.prefix());
 
         Ok(repo)
     }
@@ -51,13 +58,94 @@ impl<'a> RepositoryOperations<'a> {
     /// # Returns
     /// * `Ok(Repository)` - Successfully opened repository
     /// * `Err(CheckpointError)` - Repository not found or other error
+    ///
+    /// # Errors
+    /// Returns CheckpointError::UnsupportedObjectFormat for a repository in
+    /// an object format this build cannot read, see [`ObjectFormat`]
     pub fn open_repository<P: AsRef<Path>>(path: P) -> CcResult<Repository> {
-        Repository::open(path).map_err(|e| match e.class() {
-            git2::ErrorClass::Repository => CheckpointError::RepositoryNotFound,
-            _ => CheckpointError::GitOperationFailed(e),
+        let path = path.as_ref();
+        Repository::open(path).map_err(|e| {
+            if let Err(unsupported) = ObjectFormat::check_supported(path) {
+                return unsupported;
+            }
+            match e.class() {
+                git2::ErrorClass::Repository => CheckpointError::RepositoryNotFound,
+                _ => CheckpointError::GitOperationFailed(e),
+            }
         })
     }
 
+    /// Open the separate checkpoint store configured in `ccg.store`,
+    /// creating it on first use
+    ///
+    /// The store is a git directory whose `core.worktree` is the project's
+    /// working directory: checkpoints, refs and ccg metadata live in the
+    /// store, while diffs and checkouts act on the project's files. HEAD
+    /// points at the CCG branch from the start, and the store's config
+    /// includes the project's so `ccg.*` and `user.*` settings still apply.
+    /// The store directory is excluded from both repositories' status.
+    ///
+    /// # Arguments
+    /// * `project` - The project repository
+    /// * `store` - Store path, relative to the project's working directory
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` if the project repository is bare
+    pub fn open_store(project: &Repository, store: &str) -> CcResult<Repository> {
+        let Some(workdir) = project.workdir() else {
+            return Err(CheckpointError::InvalidArgument(
+                t!("store_needs_workdir", path = project.path().display()).to_string(),
+            ));
+        };
+        let store_path = workdir.join(store);
+        if !store_path.join("HEAD").exists() {
+            let mut opts = RepositoryInitOptions::new();
+            opts.bare(true).initial_head(CCG_BRANCH_NAME);
+            let repo = Repository::init_opts(&store_path, &opts)?;
+            let mut config = repo.config()?.open_level(git2::ConfigLevel::Local)?;
+            config.set_bool("core.bare", false)?;
+            config.set_str("core.worktree", &workdir.canonicalize()?.to_string_lossy())?;
+            config.set_str(
+                "include.path",
+                &project
+                    .path()
+                    .canonicalize()?
+                    .join("config")
+                    .to_string_lossy(),
+            )?;
+
+            // 检查点仓库和项目仓库都不把仓库目录当作未跟踪文件
+            let pattern = format!("/{}/", store.trim_matches('/'));
+            for git_dir in [&store_path, project.path()] {
+                Self::add_exclude(&git_dir.join("info").join("exclude"), &pattern)?;
+            }
+            println!(
+                "{}{}",
+                Icon::Success.prefix(),
+                t!("store_created", path = store_path.display())
+            );
+        }
+        Self::open_repository(&store_path)
+    }
+
+    /// Append `pattern` to an exclude file unless it is already listed
+    fn add_exclude(path: &Path, pattern: &str) -> CcResult<()> {
+        let content = fs::read_to_string(path).unwrap_or_default();
+        if content.lines().any(|line| line.trim() == pattern) {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let separator = if content.is_empty() || content.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        fs::write(path, format!("{content}{separator}{pattern}\n"))?;
+        Ok(())
+    }
+
     /// Validate that the repository is in a good state
     pub fn validate_repository(&self) -> CcResult<()> {
         // Check if we can access the repository path
@@ -88,4 +176,51 @@ impl<'a> RepositoryOperations<'a> {
     pub fn get_workdir_path(&self) -> Option<&Path> {
         self.repo.workdir()
     }
+
+    /// Check whether the repository is a shallow clone
+    pub fn is_shallow(&self) -> bool {
+        self.repo.is_shallow()
+    }
+
+    /// Commits at the shallow boundary, whose parents were not fetched
+    ///
+    /// # Returns
+    /// The object ids listed in `.git/shallow`, or an empty vector for
+    /// complete clones
+    pub fn shallow_boundaries(&self) -> Vec<Oid> {
+        std::fs::read_to_string(self.repo.path().join("shallow"))
+            .map(|content| {
+                content
+                    .lines()
+                    .filter_map(|line| Oid::from_str(line.trim()).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Check whether the repository is a partial clone (e.g. `--filter=blob:none`)
+    ///
+    /// Partial clones mark their promisor remote with `remote.<name>.promisor`
+    /// or record the filter in `extensions.partialClone`.
+    pub fn is_partial_clone(&self) -> bool {
+        let Ok(config) = self.repo.config() else {
+            return false;
+        };
+        if config.get_string("extensions.partialClone").is_ok() {
+            return true;
+        }
+        let Ok(mut entries) = config.entries(Some(r"remote\..*\.promisor")) else {
+            return false;
+        };
+        let mut promisor = false;
+        while let Some(Ok(entry)) = entries.next() {
+            if entry
+                .value()
+                .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+            {
+                promisor = true;
+            }
+        }
+        promisor
+    }
 }
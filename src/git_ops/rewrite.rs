@@ -0,0 +1,538 @@
+//! History rewriting on the CCG branch
+//!
+//! Rewrites replace one checkpoint and replay every descendant on top of the
+//! replacement, keeping each descendant's tree, author and message. The CCG
+//! branch is then moved to the new tip; the working directory is not touched.
+//!
+//! Dropping several checkpoints at once replays the history after the oldest
+//! of them a single time, skipping every dropped one.
+//!
+//! A rebase moves the checkpoints that a branch does not contain onto that
+//! branch's head, either re-parenting them with their trees preserved or
+//! replaying each one's changes on top of the branch.
+
+use super::commit::CommitOperations;
+use super::paths::display_path;
+use super::types::{CCG_BRANCH_NAME, CHECKPOINT_NOTES_REF, RewrittenHistory};
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::ui::short_hash;
+use git2::build::TreeUpdateBuilder;
+use git2::{Commit, Delta, FileMode, Oid, Repository, Sort};
+use rust_i18n::t;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Operations that rewrite checkpoint history
+pub struct RewriteOperations<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> RewriteOperations<'a> {
+    /// Create a new RewriteOperations instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// Current tip of the CCG branch
+    fn ccg_tip(&self) -> CcResult<Oid> {
+        let reference = self
+            .repo
+            .find_reference(&format!("refs/heads/{CCG_BRANCH_NAME}"))
+            .map_err(|_| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?;
+        reference.target().ok_or_else(|| {
+            CheckpointError::GitOperationFailed(git2::Error::from_str(
+                "CCG branch is not a direct reference",
+            ))
+        })
+    }
+
+    /// Commits on the CCG branch from `base` (inclusive) to the tip, oldest first
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::CheckpointNotFound` if `base` is not on the CCG branch
+    pub fn branch_commits_from(&self, base: &Commit<'a>) -> CcResult<Vec<Commit<'a>>> {
+        let tip = self.ccg_tip()?;
+        if tip != base.id() && !self.repo.graph_descendant_of(tip, base.id())? {
+            return Err(CheckpointError::CheckpointNotFound(base.id().to_string()));
+        }
+
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+        revwalk.push(tip)?;
+        for parent in base.parent_ids() {
+            revwalk.hide(parent)?;
+        }
+
+        revwalk
+            .map(|oid| Ok(self.repo.find_commit(oid?)?))
+            .collect()
+    }
+
+    /// Replace a checkpoint and replay its descendants onto the replacement
+    ///
+    /// # Arguments
+    /// * `hash` - Full or abbreviated hash of a checkpoint on the CCG branch
+    /// * `reflog` - Reflog message for the branch update
+    /// * `replace` - Builds the replacement commits, oldest first, and returns
+    ///   their ids. The oldest should have the original's parents and each
+    ///   following one its predecessor; descendants are replayed onto the last.
+    ///   An empty list drops the checkpoint so its descendants are replayed
+    ///   onto its parent
+    ///
+    /// Tags and notes of a replayed commit are carried over to its
+    /// replacement, and those of the checkpoint itself to the first of its
+    /// replacements.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::CheckpointNotFound` if the checkpoint is not on
+    /// the CCG branch. The branch is left untouched on any error.
+    pub fn rewrite<F>(&self, hash: &str, reflog: &str, replace: F) -> CcResult<RewrittenHistory>
+    where
+        F: FnOnce(&Commit<'a>) -> CcResult<Vec<Oid>>,
+    {
+        let commits = CommitOperations::new(self.repo);
+        let target = commits.find_commit(hash)?;
+        let descendants = self.branch_commits_from(&target)?;
+        let replacement = replace(&target)?;
+
+        // 旧提交到新提交的映射，被丢弃的提交映射到它的父提交
+        let mut mapping: HashMap<Oid, Option<Oid>> = HashMap::new();
+        let replacement_tip = replacement
+            .last()
+            .copied()
+            .or_else(|| target.parent_id(0).ok());
+        mapping.insert(target.id(), replacement_tip);
+
+        let committer = commits.create_committer_signature()?;
+        let mut replayed = Vec::new();
+        for commit in descendants.iter().skip(1) {
+            let parents = commit
+                .parent_ids()
+                .filter_map(|id| mapping.get(&id).copied().unwrap_or(Some(id)))
+                .map(|id| self.repo.find_commit(id))
+                .collect::<Result<Vec<_>, _>>()?;
+            let parent_refs: Vec<&Commit> = parents.iter().collect();
+            let message = commit.message_raw().unwrap_or_default();
+            let new_id = self.repo.commit(
+                None,
+                &commit.author(),
+                &committer,
+                message,
+                &commit.tree()?,
+                &parent_refs,
+            )?;
+            mapping.insert(commit.id(), Some(new_id));
+            replayed.push((commit.id().to_string(), new_id.to_string()));
+        }
+
+        let old_tip = descendants.last().map(Commit::id).unwrap_or(target.id());
+        let new_tip =
+            mapping.get(&old_tip).copied().flatten().ok_or_else(|| {
+                CheckpointError::InvalidArgument(t!("rewrite_drop_only").to_string())
+            })?;
+        self.repo.reference(
+            &format!("refs/heads/{CCG_BRANCH_NAME}"),
+            new_tip,
+            true,
+            reflog,
+        )?;
+        // 原检查点对应它的第一个替换提交
+        let mut moved = replayed.clone();
+        if let Some(first) = replacement.first() {
+            moved.push((target.id().to_string(), first.to_string()));
+        }
+        self.carry_over(&moved, reflog)?;
+
+        Ok(RewrittenHistory {
+            replacements: replacement.iter().map(Oid::to_string).collect(),
+            replayed,
+            tip: new_tip.to_string(),
+        })
+    }
+
+    /// Checkpoints a rebase onto `onto` would move, oldest first
+    ///
+    /// These are the commits of the CCG branch that `onto` does not contain;
+    /// none when the CCG branch already starts from `onto`.
+    pub fn rebase_candidates(&self, onto: &Commit<'a>) -> CcResult<Vec<Commit<'a>>> {
+        let tip = self.ccg_tip()?;
+        if tip == onto.id() || self.repo.merge_base(tip, onto.id()).ok() == Some(onto.id()) {
+            return Ok(Vec::new());
+        }
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+        revwalk.push(tip)?;
+        revwalk.hide(onto.id())?;
+        revwalk
+            .map(|oid| Ok(self.repo.find_commit(oid?)?))
+            .collect()
+    }
+
+    /// Move the checkpoints `onto` does not contain onto its head
+    ///
+    /// # Arguments
+    /// * `onto` - Branch name or commit to rebase onto
+    /// * `replay` - Apply each checkpoint's changes on top of `onto` instead
+    ///   of keeping its tree
+    /// * `progress` - Called before each checkpoint is moved with its
+    ///   position (from 1), the total and the checkpoint
+    ///
+    /// # Returns
+    /// The moved checkpoints in `replayed`; empty when there was nothing to do.
+    /// Tags and notes of a moved checkpoint follow it.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if `onto` does not resolve
+    /// and `CheckpointError::Conflict` if a replayed checkpoint conflicts with
+    /// `onto`. The branch is left untouched on any error.
+    pub fn rebase_onto(
+        &self,
+        onto: &str,
+        replay: bool,
+        progress: &mut dyn FnMut(usize, usize, &Commit),
+    ) -> CcResult<RewrittenHistory> {
+        let onto_commit = self
+            .repo
+            .revparse_single(onto)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|_| CheckpointError::BranchNotFound(onto.to_string()))?;
+        let commits = self.rebase_candidates(&onto_commit)?;
+        let Some(old_tip) = commits.last().map(Commit::id) else {
+            return Ok(RewrittenHistory {
+                replacements: Vec::new(),
+                replayed: Vec::new(),
+                tip: self.ccg_tip()?.to_string(),
+            });
+        };
+
+        let committer = CommitOperations::new(self.repo).create_committer_signature()?;
+        let mut mapping: HashMap<Oid, Oid> = HashMap::new();
+        let mut replayed = Vec::new();
+        for (position, commit) in commits.iter().enumerate() {
+            progress(position + 1, commits.len(), commit);
+            // 不在移动范围内的父提交（原来的基准）换成 onto
+            let mut parent_ids: Vec<Oid> = commit
+                .parent_ids()
+                .map(|id| mapping.get(&id).copied().unwrap_or(onto_commit.id()))
+                .collect();
+            if parent_ids.is_empty() {
+                parent_ids.push(onto_commit.id());
+            }
+            parent_ids.dedup();
+            let parents = parent_ids
+                .iter()
+                .map(|id| self.repo.find_commit(*id))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let tree = if replay && commit.parent_count() == 1 {
+                let mut index = self.repo.cherrypick_commit(commit, &parents[0], 0, None)?;
+                let conflicts = CommitOperations::conflicted_paths(&index)?;
+                if !conflicts.is_empty() {
+                    return Err(CheckpointError::Conflict(format!(
+                        "{} ({})",
+                        conflicts.join(", "),
+                        short_hash(&commit.id().to_string())
+                    )));
+                }
+                self.repo.find_tree(index.write_tree_to(self.repo)?)?
+            } else {
+                commit.tree()?
+            };
+
+            let parent_refs: Vec<&Commit> = parents.iter().collect();
+            let new_id = self.repo.commit(
+                None,
+                &commit.author(),
+                &committer,
+                commit.message_raw().unwrap_or_default(),
+                &tree,
+                &parent_refs,
+            )?;
+            mapping.insert(commit.id(), new_id);
+            replayed.push((commit.id().to_string(), new_id.to_string()));
+        }
+
+        let new_tip = mapping[&old_tip];
+        let reflog = format!("ccg: rebase onto {onto}");
+        self.repo.reference(
+            &format!("refs/heads/{CCG_BRANCH_NAME}"),
+            new_tip,
+            true,
+            &reflog,
+        )?;
+        self.carry_over(&replayed, &reflog)?;
+        Ok(RewrittenHistory {
+            replacements: Vec::new(),
+            replayed,
+            tip: new_tip.to_string(),
+        })
+    }
+
+    /// Drop checkpoints from the CCG branch in one pass
+    ///
+    /// Every commit after the oldest dropped checkpoint is replayed once,
+    /// keeping its tree, author and message; dropped ones are skipped, so
+    /// their changes end up in the next kept checkpoint.
+    ///
+    /// # Arguments
+    /// * `hashes` - Full or abbreviated hashes of checkpoints on the CCG branch
+    ///
+    /// Tags pointing at a replayed commit are moved to its replacement, so
+    /// tagged checkpoints stay tagged; notes are copied along.
+    ///
+    /// # Returns
+    /// The rewritten history, with no replacements; the CCG branch tree is
+    /// unchanged unless the tip itself is dropped
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::CheckpointNotFound` if a checkpoint is not on
+    /// the CCG branch and `CheckpointError::InvalidArgument` if nothing would
+    /// be left on it. The branch is left untouched on any error.
+    pub fn drop(&self, hashes: &[String]) -> CcResult<RewrittenHistory> {
+        let commits = CommitOperations::new(self.repo);
+        let dropped = hashes
+            .iter()
+            .map(|hash| Ok(commits.find_commit(hash)?.id()))
+            .collect::<CcResult<HashSet<Oid>>>()?;
+
+        let tip = self.ccg_tip()?;
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+        revwalk.push(tip)?;
+        let branch = revwalk.collect::<Result<Vec<Oid>, _>>()?;
+        if let Some(missing) = dropped.iter().find(|id| !branch.contains(id)) {
+            return Err(CheckpointError::CheckpointNotFound(missing.to_string()));
+        }
+        let Some(start) = branch.iter().position(|id| dropped.contains(id)) else {
+            return Ok(RewrittenHistory {
+                replacements: Vec::new(),
+                replayed: Vec::new(),
+                tip: tip.to_string(),
+            });
+        };
+
+        // 旧提交到新提交的映射，被丢弃的提交映射到它（改写后）的父提交
+        let mut mapping: HashMap<Oid, Option<Oid>> = HashMap::new();
+        let committer = commits.create_committer_signature()?;
+        let mut replayed = Vec::new();
+        for id in &branch[start..] {
+            let commit = self.repo.find_commit(*id)?;
+            let parent_ids: Vec<Oid> = commit
+                .parent_ids()
+                .filter_map(|id| mapping.get(&id).copied().unwrap_or(Some(id)))
+                .collect();
+            if dropped.contains(id) {
+                mapping.insert(*id, parent_ids.first().copied());
+                continue;
+            }
+            let parents = parent_ids
+                .iter()
+                .map(|id| self.repo.find_commit(*id))
+                .collect::<Result<Vec<_>, _>>()?;
+            let parent_refs: Vec<&Commit> = parents.iter().collect();
+            let new_id = self.repo.commit(
+                None,
+                &commit.author(),
+                &committer,
+                commit.message_raw().unwrap_or_default(),
+                &commit.tree()?,
+                &parent_refs,
+            )?;
+            mapping.insert(*id, Some(new_id));
+            replayed.push((id.to_string(), new_id.to_string()));
+        }
+
+        let new_tip =
+            mapping.get(&tip).copied().flatten().ok_or_else(|| {
+                CheckpointError::InvalidArgument(t!("prune_drop_all").to_string())
+            })?;
+        let reflog = "ccg: prune checkpoints";
+        self.repo.reference(
+            &format!("refs/heads/{CCG_BRANCH_NAME}"),
+            new_tip,
+            true,
+            reflog,
+        )?;
+        self.carry_over(&replayed, reflog)?;
+
+        Ok(RewrittenHistory {
+            replacements: Vec::new(),
+            replayed,
+            tip: new_tip.to_string(),
+        })
+    }
+
+    /// Carry tags and notes of rewritten commits over to their replacements
+    fn carry_over(&self, rewritten: &[(String, String)], reflog: &str) -> CcResult<()> {
+        self.retag(rewritten, reflog)?;
+        self.copy_notes(rewritten)
+    }
+
+    /// Copy the notes under [`CHECKPOINT_NOTES_REF`] of rewritten commits to
+    /// their replacements, such as the part of a long message spilled there
+    fn copy_notes(&self, rewritten: &[(String, String)]) -> CcResult<()> {
+        for (old, new) in rewritten {
+            let note = match self
+                .repo
+                .find_note(Some(CHECKPOINT_NOTES_REF), Oid::from_str(old)?)
+            {
+                Ok(note) => note,
+                Err(e) if e.code() == git2::ErrorCode::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            self.repo.note(
+                &note.author(),
+                &note.committer(),
+                Some(CHECKPOINT_NOTES_REF),
+                Oid::from_str(new)?,
+                &String::from_utf8_lossy(note.message_bytes()),
+                true,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Move tags from rewritten commits to their replacements
+    ///
+    /// Lightweight tags are updated in place; annotated tags are recreated
+    /// with the same name, tagger and message. `reflog` is the reflog message
+    /// for the moved references.
+    fn retag(&self, rewritten: &[(String, String)], reflog: &str) -> CcResult<()> {
+        let moved: HashMap<Oid, Oid> = rewritten
+            .iter()
+            .map(|(old, new)| Ok((Oid::from_str(old)?, Oid::from_str(new)?)))
+            .collect::<Result<_, git2::Error>>()?;
+        let tags = self
+            .repo
+            .references_glob("refs/tags/*")?
+            .collect::<Result<Vec<_>, _>>()?;
+        for reference in tags {
+            let (Some(name), Some(target)) = (reference.name(), reference.target()) else {
+                continue;
+            };
+            if let Some(new_id) = moved.get(&target) {
+                self.repo.reference(name, *new_id, true, reflog)?;
+                continue;
+            }
+            // 附注标签指向标签对象，需要按原信息重建
+            let Ok(tag) = self.repo.find_tag(target) else {
+                continue;
+            };
+            let Some(new_id) = moved.get(&tag.target_id()) else {
+                continue;
+            };
+            let new_target = self.repo.find_object(*new_id, None)?;
+            let tagger = match tag.tagger() {
+                Some(tagger) => tagger.to_owned(),
+                None => CommitOperations::new(self.repo).create_committer_signature()?,
+            };
+            self.repo.tag(
+                tag.name().unwrap_or_default(),
+                &new_target,
+                &tagger,
+                tag.message().unwrap_or_default(),
+                true,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Change the message of a checkpoint, keeping its tree and author
+    ///
+    /// # Returns
+    /// The rewritten history; the CCG branch tree is unchanged
+    pub fn reword(&self, hash: &str, message: &str) -> CcResult<RewrittenHistory> {
+        let committer = CommitOperations::new(self.repo).create_committer_signature()?;
+        self.rewrite(hash, "ccg: reword checkpoint", |commit| {
+            let parents = commit.parents().collect::<Vec<_>>();
+            let parent_refs: Vec<&Commit> = parents.iter().collect();
+            let id = self.repo.commit(
+                None,
+                &commit.author(),
+                &committer,
+                message,
+                &commit.tree()?,
+                &parent_refs,
+            )?;
+            Ok(vec![id])
+        })
+    }
+
+    /// Split a checkpoint into one checkpoint per changed file
+    ///
+    /// Changes are applied in path order on top of the parent tree, so the
+    /// last new checkpoint has exactly the original tree. Each message is the
+    /// original summary followed by the file path.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::InvalidArgument` if the checkpoint changes
+    /// fewer than two files
+    pub fn split(&self, hash: &str) -> CcResult<RewrittenHistory> {
+        let committer = CommitOperations::new(self.repo).create_committer_signature()?;
+        self.rewrite(hash, "ccg: split checkpoint", |commit| {
+            let parent_tree = match commit.parents().next() {
+                Some(parent) => parent.tree()?,
+                None => {
+                    let empty = self.repo.treebuilder(None)?.write()?;
+                    self.repo.find_tree(empty)?
+                }
+            };
+            let diff =
+                self.repo
+                    .diff_tree_to_tree(Some(&parent_tree), Some(&commit.tree()?), None)?;
+
+            // 每个文件的变更：新内容和模式，删除时为 None
+            let changes: Vec<(PathBuf, Option<(Oid, FileMode)>)> = diff
+                .deltas()
+                .filter_map(|delta| {
+                    if delta.status() == Delta::Deleted {
+                        let path = delta.old_file().path()?;
+                        Some((path.to_path_buf(), None))
+                    } else {
+                        let file = delta.new_file();
+                        Some((file.path()?.to_path_buf(), Some((file.id(), file.mode()))))
+                    }
+                })
+                .collect();
+            if changes.len() < 2 {
+                return Err(CheckpointError::InvalidArgument(
+                    t!(
+                        "split_too_few_files",
+                        hash = short_hash(&commit.id().to_string()),
+                        count = changes.len()
+                    )
+                    .to_string(),
+                ));
+            }
+
+            let message = commit.message().unwrap_or_default();
+            let (summary, body) = message.split_once('\n').unwrap_or((message, ""));
+            let mut parents: Vec<Commit> = commit.parents().take(1).collect();
+            let mut tree = parent_tree;
+            let mut ids = Vec::with_capacity(changes.len());
+            for (path, change) in &changes {
+                let mut update = TreeUpdateBuilder::new();
+                match change {
+                    Some((id, mode)) => update.upsert(path, *id, *mode),
+                    None => update.remove(path),
+                };
+                tree = self
+                    .repo
+                    .find_tree(update.create_updated(self.repo, &tree)?)?;
+
+                let parent_refs: Vec<&Commit> = parents.iter().collect();
+                let id = self.repo.commit(
+                    None,
+                    &commit.author(),
+                    &committer,
+                    &format!("{summary} ({})\n{body}", display_path(path)),
+                    &tree,
+                    &parent_refs,
+                )?;
+                parents = vec![self.repo.find_commit(id)?];
+                ids.push(id);
+            }
+            Ok(ids)
+        })
+    }
+}
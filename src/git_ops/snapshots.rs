@@ -0,0 +1,240 @@
+//! Stash-like snapshots of the working directory
+//!
+//! `ccg snapshot push` parks the dirty working directory, untracked files
+//! included, as a commit on top of HEAD referenced by `refs/ccg/stash/<N>`,
+//! and resets the working directory to HEAD. Snapshots live outside the
+//! checkpoint timeline: they are not on the CCG branch, and restores or
+//! prunes never touch them. `pop` three-way merges a snapshot back onto the
+//! working directory as it is now and deletes its ref.
+
+use super::commit::{CommitOperations, write_entry};
+use super::diff::blob_mode;
+use super::paths::path_from_bytes;
+use super::types::{SNAPSHOT_REF_PREFIX, Snapshot};
+use crate::error::{CheckpointError, Result as CcResult};
+use git2::{Oid, Repository, Tree};
+use rust_i18n::t;
+use std::fs;
+
+/// Hash the working directory into a tree: `base` with every change git
+/// status reports applied, untracked (but not ignored) files included
+///
+/// File contents are written to the object database; nothing else changes.
+pub fn worktree_tree(repo: &Repository, base: &Tree) -> CcResult<Oid> {
+    let Some(workdir) = repo.workdir() else {
+        return Ok(base.id());
+    };
+    let mut opts = git2::StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false);
+    let statuses = repo.statuses(Some(&mut opts))?;
+    if statuses.is_empty() {
+        return Ok(base.id());
+    }
+
+    let mut index = git2::Index::new()?;
+    index.read_tree(base)?;
+    for entry in statuses.iter() {
+        // 按原始字节处理路径，不是合法 UTF-8 的文件名也要包含在快照中
+        let path = path_from_bytes(entry.path_bytes());
+        let full = workdir.join(&path);
+        let Ok(metadata) = fs::symlink_metadata(&full) else {
+            index.remove_path(&path)?;
+            continue;
+        };
+        let (id, mode) = if metadata.file_type().is_symlink() {
+            let target = fs::read_link(&full)?;
+            (
+                repo.blob(target.as_os_str().as_encoded_bytes())?,
+                git2::FileMode::Link,
+            )
+        } else {
+            (repo.blob_path(&full)?, blob_mode(&metadata))
+        };
+        let path = entry.path_bytes().to_vec();
+        index.add(&git2::IndexEntry {
+            ctime: git2::IndexTime::new(0, 0),
+            mtime: git2::IndexTime::new(0, 0),
+            dev: 0,
+            ino: 0,
+            mode: u32::from(mode),
+            uid: 0,
+            gid: 0,
+            file_size: 0,
+            id,
+            flags: path.len().min(0xfff) as u16,
+            flags_extended: 0,
+            path,
+        })?;
+    }
+    Ok(index.write_tree_to(repo)?)
+}
+
+/// The snapshots of a repository
+pub struct SnapshotStore<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> SnapshotStore<'a> {
+    /// Create a new SnapshotStore instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// Snapshots, newest first
+    pub fn list(&self) -> CcResult<Vec<Snapshot>> {
+        let mut snapshots = Vec::new();
+        for reference in self
+            .repo
+            .references_glob(&format!("{SNAPSHOT_REF_PREFIX}*"))?
+        {
+            let reference = reference?;
+            let Some(number) = reference
+                .name()
+                .and_then(|name| name.strip_prefix(SNAPSHOT_REF_PREFIX))
+                .and_then(|number| number.parse().ok())
+            else {
+                continue;
+            };
+            let commit = reference.peel_to_commit()?;
+            snapshots.push(Snapshot {
+                number,
+                hash: commit.id().to_string(),
+                message: commit.message().unwrap_or("").trim_end().to_string(),
+                time: commit.time().seconds(),
+            });
+        }
+        snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.number));
+        Ok(snapshots)
+    }
+
+    /// Park the working directory as a new snapshot and reset it to HEAD
+    ///
+    /// Untracked files are saved and removed too; ignored files stay.
+    ///
+    /// # Returns
+    /// The new snapshot, numbered one above the newest existing one
+    ///
+    /// # Errors
+    /// Returns CheckpointError::NoChangesToCommit if the working directory
+    /// matches HEAD
+    pub fn push(&self, message: &str) -> CcResult<Snapshot> {
+        let head = self.repo.head()?.peel_to_commit()?;
+        let tree_id = worktree_tree(self.repo, &head.tree()?)?;
+        if tree_id == head.tree_id() {
+            return Err(CheckpointError::NoChangesToCommit);
+        }
+
+        let commits = CommitOperations::new(self.repo);
+        let signature = commits.create_signature()?;
+        let commit_id = self.repo.commit(
+            None,
+            &signature,
+            &signature,
+            message,
+            &self.repo.find_tree(tree_id)?,
+            &[&head],
+        )?;
+        let number = self.list()?.first().map_or(0, |newest| newest.number + 1);
+        self.repo.reference(
+            &format!("{SNAPSHOT_REF_PREFIX}{number}"),
+            commit_id,
+            false,
+            "ccg snapshot push",
+        )?;
+
+        // 快照已保存，工作目录和索引恢复为 HEAD，未跟踪的文件一并移除
+        self.repo
+            .reset(head.as_object(), git2::ResetType::Hard, None)?;
+        let mut checkout = git2::build::CheckoutBuilder::new();
+        checkout.force().remove_untracked(true);
+        self.repo.checkout_head(Some(&mut checkout))?;
+
+        Ok(Snapshot {
+            number,
+            hash: commit_id.to_string(),
+            message: message.trim_end().to_string(),
+            time: self.repo.find_commit(commit_id)?.time().seconds(),
+        })
+    }
+
+    /// Apply a snapshot (the newest when `number` is None) onto the working
+    /// directory and delete it
+    ///
+    /// The snapshot's changes are three-way merged with the working directory
+    /// as it is now, so work done since the push is kept. Only the files the
+    /// merge changes are written; the index is not touched.
+    ///
+    /// # Errors
+    /// Returns CheckpointError::InvalidArgument if there is no such snapshot
+    /// and CheckpointError::Conflict, without changing anything, if the
+    /// working directory changed the same content
+    pub fn pop(&self, number: Option<usize>) -> CcResult<Snapshot> {
+        let snapshots = self.list()?;
+        let snapshot = match number {
+            Some(number) => snapshots.into_iter().find(|s| s.number == number),
+            None => snapshots.into_iter().next(),
+        }
+        .ok_or_else(|| {
+            CheckpointError::InvalidArgument(match number {
+                Some(number) => t!("snapshot_not_found", number = number).to_string(),
+                None => t!("snapshot_none").to_string(),
+            })
+        })?;
+
+        let commit = self.repo.find_commit(Oid::from_str(&snapshot.hash)?)?;
+        let ancestor = commit.parent(0)?.tree()?;
+        let head_tree = self.repo.head()?.peel_to_tree()?;
+        let ours = self.repo.find_tree(worktree_tree(self.repo, &head_tree)?)?;
+        let mut merged = self
+            .repo
+            .merge_trees(&ancestor, &ours, &commit.tree()?, None)?;
+        if merged.has_conflicts() {
+            let mut paths: Vec<String> = merged
+                .conflicts()?
+                .filter_map(|conflict| {
+                    let conflict = conflict.ok()?;
+                    let entry = conflict.our.or(conflict.their).or(conflict.ancestor)?;
+                    Some(String::from_utf8_lossy(&entry.path).to_string())
+                })
+                .collect();
+            paths.sort();
+            paths.dedup();
+            return Err(CheckpointError::Conflict(paths.join(", ")));
+        }
+        let merged = self.repo.find_tree(merged.write_tree_to(self.repo)?)?;
+
+        // 只写入合并结果与当前工作目录不同的文件
+        let workdir = self
+            .repo
+            .workdir()
+            .ok_or_else(|| CheckpointError::InvalidArgument(t!("snapshot_bare").to_string()))?;
+        let diff = self
+            .repo
+            .diff_tree_to_tree(Some(&ours), Some(&merged), None)?;
+        for delta in diff.deltas() {
+            let new = delta.new_file();
+            let Some(path) = new.path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let full = workdir.join(path);
+            match fs::remove_file(&full) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+            if new.exists() {
+                if let Some(parent) = full.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let blob = self.repo.find_blob(new.id())?;
+                write_entry(&full, blob.content(), i32::from(new.mode()))?;
+            }
+        }
+
+        self.repo
+            .find_reference(&format!("{SNAPSHOT_REF_PREFIX}{}", snapshot.number))?
+            .delete()?;
+        Ok(snapshot)
+    }
+}
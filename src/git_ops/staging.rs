@@ -0,0 +1,230 @@
+//! Recovery of the staging area after an interrupted create
+//!
+//! Creating a checkpoint stages the whole working directory and writes the
+//! index to disk before committing. If ccg is killed in between, the user is
+//! left with every change staged and no checkpoint to show for it. Around
+//! that window the original index is copied to `.git/ccg/index.backup` and a
+//! marker, `.git/ccg/create-in-progress`, records the CCG tip the commit was
+//! going to build on. The next ccg command that modifies checkpoints finds
+//! the marker and either restores the backup (the commit never happened) or
+//! just removes it (it did), and leaves a record for `ccg doctor`.
+//!
+//! The create holds an exclusive lock on `.git/ccg/create.lock` from `begin`
+//! until `finish`. The lock is released when the process exits, however it
+//! exits, so a marker whose lock is still held belongs to a create that is
+//! running right now and is left alone.
+
+use super::ccg_dir;
+use super::types::{CCG_BRANCH_NAME, IndexRepair};
+use crate::error::Result as CcResult;
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fs::{self, File, TryLockError};
+use std::path::PathBuf;
+
+/// What the marker records about the create in progress
+#[derive(Debug, Serialize, Deserialize)]
+struct Marker {
+    /// Start of the create, seconds since the Unix epoch
+    time: i64,
+    /// CCG tip before the commit, None on an unborn branch
+    tip: Option<String>,
+    /// Whether `.git/index` existed and was backed up
+    backed_up: bool,
+}
+
+/// Guards the index writes of checkpoint creation
+pub struct StagingGuard<'a> {
+    repo: &'a Repository,
+    /// The create lock, held from `begin` until `finish` or drop
+    lock: RefCell<Option<File>>,
+}
+
+impl<'a> StagingGuard<'a> {
+    /// Create a new StagingGuard instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self {
+            repo,
+            lock: RefCell::new(None),
+        }
+    }
+
+    fn dir(&self) -> PathBuf {
+        ccg_dir(self.repo)
+    }
+
+    fn marker_path(&self) -> PathBuf {
+        self.dir().join("create-in-progress")
+    }
+
+    fn backup_path(&self) -> PathBuf {
+        self.dir().join("index.backup")
+    }
+
+    fn record_path(&self) -> PathBuf {
+        self.dir().join("index-repair")
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.repo.path().join("index")
+    }
+
+    fn lock_path(&self) -> PathBuf {
+        self.dir().join("create.lock")
+    }
+
+    fn open_lock(&self) -> std::io::Result<File> {
+        File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(self.lock_path())
+    }
+
+    /// Whether another guard, in this or another process, holds the create
+    /// lock
+    fn is_locked(&self) -> bool {
+        self.open_lock()
+            .is_ok_and(|file| matches!(file.try_lock(), Err(TryLockError::WouldBlock)))
+    }
+
+    /// Save the on-disk index and mark a create as in progress
+    ///
+    /// Waits for a create running in another process to finish first; the
+    /// lock taken here is held until [`StagingGuard::finish`] or until the
+    /// guard is dropped.
+    ///
+    /// # Arguments
+    /// * `tip` - The commit the checkpoint will be built on
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the backup or marker cannot be written
+    pub fn begin(&self, tip: Option<Oid>) -> CcResult<()> {
+        fs::create_dir_all(self.dir())?;
+        let lock = self.open_lock()?;
+        lock.lock()?;
+        *self.lock.borrow_mut() = Some(lock);
+        let backed_up = match fs::copy(self.index_path(), self.backup_path()) {
+            Ok(_) => true,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => return Err(e.into()),
+        };
+        let marker = Marker {
+            time: chrono::Utc::now().timestamp(),
+            tip: tip.map(|tip| tip.to_string()),
+            backed_up,
+        };
+        let json = serde_json::to_string(&marker).map_err(std::io::Error::other)?;
+        fs::write(self.marker_path(), json)?;
+        Ok(())
+    }
+
+    /// The create finished: drop the marker and the backup
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the marker cannot be removed
+    pub fn finish(&self) -> CcResult<()> {
+        remove_if_exists(&self.backup_path())?;
+        remove_if_exists(&self.marker_path())?;
+        // 释放锁；文件本身保留，避免与等待中的进程竞争
+        self.lock.borrow_mut().take();
+        Ok(())
+    }
+
+    /// Whether a create was interrupted and has not been repaired yet
+    ///
+    /// A create that is still running is not interrupted.
+    pub fn is_interrupted(&self) -> bool {
+        self.marker_path().exists() && !self.is_locked()
+    }
+
+    /// Put the saved index back and drop the marker, for a create that failed
+    /// after staging
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the index cannot be restored
+    pub fn rollback(&self) -> CcResult<()> {
+        let backed_up = self.read_marker().is_none_or(|m| m.backed_up);
+        if backed_up && self.backup_path().exists() {
+            fs::copy(self.backup_path(), self.index_path())?;
+        } else if !backed_up {
+            // 中断前没有索引文件，删除写入的索引即回到原状
+            remove_if_exists(&self.index_path())?;
+        }
+        self.finish()
+    }
+
+    /// Repair the staging area left by an interrupted create
+    ///
+    /// When the CCG branch still points at the recorded tip the checkpoint
+    /// was never committed, and the saved index is put back. Otherwise the
+    /// checkpoint exists and the index already matches it.
+    ///
+    /// # Returns
+    /// The repair, or None if no create was interrupted or the create is
+    /// still running
+    ///
+    /// # Errors
+    /// Returns CheckpointError::IoError if the index cannot be restored; the
+    /// marker is kept so the repair is retried
+    pub fn repair(&self) -> CcResult<Option<IndexRepair>> {
+        if !self.marker_path().exists() {
+            return Ok(None);
+        }
+        // 持有锁直到修复完成，期间开始的 create 会等待
+        let lock = self.open_lock()?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        if !self.marker_path().exists() {
+            return Ok(None);
+        }
+        // 标记损坏时无法判断提交是否完成，保守地恢复备份
+        let marker = self.read_marker();
+        let tip = self
+            .repo
+            .find_branch(CCG_BRANCH_NAME, git2::BranchType::Local)
+            .ok()
+            .and_then(|branch| branch.get().target())
+            .map(|oid| oid.to_string());
+        let restored = marker.as_ref().is_none_or(|m| m.tip == tip);
+        if restored {
+            self.rollback()?;
+        } else {
+            self.finish()?;
+        }
+
+        let repair = IndexRepair {
+            interrupted_at: marker.map_or(0, |m| m.time),
+            repaired_at: chrono::Utc::now().timestamp(),
+            restored,
+        };
+        let json = serde_json::to_string(&repair).map_err(std::io::Error::other)?;
+        fs::write(self.record_path(), json)?;
+        Ok(Some(repair))
+    }
+
+    fn read_marker(&self) -> Option<Marker> {
+        let content = fs::read_to_string(self.marker_path()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// The last repair not yet reported by `ccg doctor`, removing its record
+    pub fn take_repair(&self) -> Option<IndexRepair> {
+        let content = fs::read_to_string(self.record_path()).ok()?;
+        let _ = fs::remove_file(self.record_path());
+        serde_json::from_str(&content).ok()
+    }
+}
+
+fn remove_if_exists(path: &std::path::Path) -> CcResult<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,262 @@
+//! Checkpoint statistics: parallel computation and the metadata index
+//!
+//! Computing per-checkpoint diff statistics one commit at a time is slow on
+//! long histories. This module fans the work out over a bounded rayon worker
+//! pool, where each worker thread opens its own repository handle (libgit2
+//! repositories are not `Sync`).
+//!
+//! Statistics never change for a given commit, so they are also recorded in
+//! `.git/ccg/stats` when a checkpoint is created, one line per checkpoint.
+//! Checkpoints missing from the index (created by older versions or by
+//! rewrites) are computed once and backfilled. The first line records the
+//! `ccg.diff.ignore` patterns the statistics were split by; the index is
+//! rebuilt when they change.
+
+use super::ccg_dir;
+use crate::config::CcgConfig;
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::git_ops::diff::DiffOperations;
+use crate::git_ops::types::DiffStats;
+use git2::{Oid, Repository};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Upper bound for the worker pool size
+///
+/// Tree diffs are mostly I/O and decompression bound, so more threads than
+/// this rarely help and only add memory pressure.
+pub const MAX_STATS_WORKERS: usize = 8;
+
+/// Computes diff statistics for many commits concurrently
+pub struct ParallelStats {
+    /// Path to the repository's .git directory
+    repo_path: PathBuf,
+    /// Number of worker threads
+    workers: usize,
+}
+
+impl ParallelStats {
+    /// Create a new ParallelStats for the repository at `repo_path`
+    ///
+    /// The worker count defaults to the available parallelism, capped at
+    /// `MAX_STATS_WORKERS`.
+    ///
+    /// # Arguments
+    /// * `repo_path` - Path to the repository (as returned by `Repository::path`)
+    pub fn new<P: AsRef<Path>>(repo_path: P) -> Self {
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(MAX_STATS_WORKERS);
+
+        Self {
+            repo_path: repo_path.as_ref().to_path_buf(),
+            workers,
+        }
+    }
+
+    /// Override the number of worker threads (at least one is always used)
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Compute statistics for each commit in `oids`
+    ///
+    /// # Arguments
+    /// * `oids` - Commits to compute statistics for
+    ///
+    /// # Returns
+    /// Statistics in the same order as `oids`
+    ///
+    /// # Errors
+    /// Returns the first error encountered while opening the repository or
+    /// computing a diff
+    pub fn compute(&self, oids: &[Oid]) -> CcResult<Vec<DiffStats>> {
+        if oids.len() <= 1 || self.workers == 1 {
+            let repo = Repository::open(&self.repo_path)?;
+            return oids.iter().map(|oid| stats_for(&repo, *oid)).collect();
+        }
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.workers)
+            .build()
+            .map_err(|e| {
+                CheckpointError::GitOperationFailed(git2::Error::from_str(&e.to_string()))
+            })?;
+
+        pool.install(|| {
+            oids.par_iter()
+                .map_init(
+                    || Repository::open(&self.repo_path),
+                    |repo, oid| match repo {
+                        Ok(repo) => stats_for(repo, *oid),
+                        Err(e) => Err(CheckpointError::GitOperationFailed(git2::Error::from_str(
+                            e.message(),
+                        ))),
+                    },
+                )
+                .collect()
+        })
+    }
+}
+
+/// Compute statistics for a single commit
+fn stats_for(repo: &Repository, oid: Oid) -> CcResult<DiffStats> {
+    let commit = repo.find_commit(oid)?;
+    DiffOperations::new(repo).commit_stats(&commit)
+}
+
+/// The per-checkpoint statistics index in `.git/ccg/stats`
+pub struct StatsIndex<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> StatsIndex<'a> {
+    /// Create a new StatsIndex instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    fn path(&self) -> PathBuf {
+        ccg_dir(self.repo).join("stats")
+    }
+
+    /// Header line naming the `ccg.diff.ignore` patterns in effect
+    fn header(&self) -> String {
+        format!(
+            "# ignore: {}",
+            CcgConfig::load(self.repo).diff_ignore.join(",")
+        )
+    }
+
+    /// Load the recorded statistics
+    ///
+    /// # Returns
+    /// An empty map when the index is missing or was recorded with other
+    /// `ccg.diff.ignore` patterns
+    pub fn load(&self) -> CcResult<HashMap<Oid, DiffStats>> {
+        let content = match fs::read_to_string(self.path()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut lines = content.lines();
+        if lines.next() != Some(self.header().as_str()) {
+            return Ok(HashMap::new());
+        }
+        Ok(lines.filter_map(parse_line).collect())
+    }
+
+    /// Append statistics to the index, starting a new index when the
+    /// recorded `ccg.diff.ignore` patterns are out of date
+    pub fn record(&self, entries: &[(Oid, DiffStats)]) -> CcResult<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let path = self.path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let header = self.header();
+        let current = fs::read_to_string(&path)
+            .ok()
+            .is_some_and(|content| content.lines().next() == Some(header.as_str()));
+        let mut file = if current {
+            OpenOptions::new().append(true).open(&path)?
+        } else {
+            let mut file = fs::File::create(&path)?;
+            writeln!(file, "{header}")?;
+            file
+        };
+        for (oid, stats) in entries {
+            writeln!(
+                file,
+                "{oid} {} {} {} {} {} {} {} {}",
+                stats.total_files,
+                stats.additions,
+                stats.deletions,
+                stats.modifications,
+                stats.generated_files,
+                stats.generated_additions,
+                stats.generated_deletions,
+                u8::from(stats.format_only)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Compute and record the statistics of a newly created checkpoint
+    ///
+    /// # Arguments
+    /// * `hash` - Full hash of the checkpoint
+    pub fn record_commit(&self, hash: &str) -> CcResult<()> {
+        let oid = Oid::from_str(hash)?;
+        let stats = stats_for(self.repo, oid)?;
+        self.record(&[(oid, stats)])
+    }
+
+    /// Statistics for each commit in `oids`, in the same order
+    ///
+    /// Commits missing from the index are computed in parallel and
+    /// backfilled; failing to write the index does not fail the lookup.
+    pub fn stats_for(&self, oids: &[Oid]) -> CcResult<Vec<DiffStats>> {
+        let mut known = self.load()?;
+        let missing: Vec<Oid> = oids
+            .iter()
+            .filter(|oid| !known.contains_key(oid))
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            let computed = ParallelStats::new(self.repo.path()).compute(&missing)?;
+            let backfill: Vec<(Oid, DiffStats)> = missing.into_iter().zip(computed).collect();
+            let _ = self.record(&backfill);
+            known.extend(backfill);
+        }
+        Ok(oids
+            .iter()
+            .map(|oid| known.get(oid).cloned().unwrap_or_default())
+            .collect())
+    }
+}
+
+/// Parse one `<hash> <files> <+> <-> <modified> <generated files> <+> <-> <format-only>` line
+///
+/// Lines written before the format-only flag existed are rejected, so those
+/// checkpoints are computed again and backfilled.
+fn parse_line(line: &str) -> Option<(Oid, DiffStats)> {
+    let mut fields = line.split_whitespace();
+    let oid = Oid::from_str(fields.next()?).ok()?;
+    let numbers: Vec<i64> = fields
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    let [
+        files,
+        additions,
+        deletions,
+        modifications,
+        gen_files,
+        gen_additions,
+        gen_deletions,
+        format_only,
+    ] = numbers[..]
+    else {
+        return None;
+    };
+    Some((
+        oid,
+        DiffStats {
+            total_files: files as usize,
+            additions: additions as i32,
+            deletions: deletions as i32,
+            modifications: modifications as i32,
+            generated_files: gen_files as usize,
+            generated_additions: gen_additions as i32,
+            generated_deletions: gen_deletions as i32,
+            format_only: format_only != 0,
+        },
+    ))
+}
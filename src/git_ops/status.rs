@@ -0,0 +1,158 @@
+//! Working directory status checks tuned for large repositories
+//!
+//! Status scans dominate the run time of most commands on big monorepos.
+//! The scans here never recurse into ignored or untracked directories and
+//! skip submodules, since checkpoints only need a yes/no answer. With
+//! `ccg.fsmonitor` enabled the scan is delegated to `git status`, which can
+//! use the fsmonitor hook or watchman configured in `core.fsmonitor`.
+//!
+//! [`StatusCache`] keeps each answer for the rest of a command invocation.
+//! Answers are keyed by HEAD and the index file, so checkouts and commits
+//! made by ccg itself invalidate them.
+
+use crate::config::CcgConfig;
+use crate::error::{CheckpointError, Result as CcResult};
+use git2::{Oid, Repository, StatusOptions};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+/// The question a status scan answers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusQuery {
+    /// Tracked files differ from HEAD (untracked files are not counted)
+    TrackedChanges,
+    /// Any non-ignored file differs from HEAD, untracked files included
+    NonIgnoredFiles,
+}
+
+/// Repository state a cached answer is valid for: HEAD and the index file
+type StatusKey = (Option<Oid>, Option<(SystemTime, u64)>);
+
+/// Status answers reused within one command invocation
+#[derive(Debug, Default)]
+pub struct StatusCache {
+    entries: RefCell<HashMap<StatusQuery, (StatusKey, bool)>>,
+}
+
+impl StatusCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget every cached answer
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+}
+
+/// Status checks on the working directory
+pub struct WorktreeStatus<'a> {
+    repo: &'a Repository,
+    cache: Option<&'a StatusCache>,
+}
+
+impl<'a> WorktreeStatus<'a> {
+    /// Create a new WorktreeStatus instance
+    ///
+    /// # Arguments
+    /// * `repo` - Reference to the git repository
+    /// * `cache` - Cache to reuse answers from; None scans every time
+    pub fn new(repo: &'a Repository, cache: Option<&'a StatusCache>) -> Self {
+        Self { repo, cache }
+    }
+
+    /// Status options for `query`
+    ///
+    /// Ignored directories are never descended into, untracked directories
+    /// are reported as a single entry and submodules are skipped.
+    pub fn options(query: StatusQuery) -> StatusOptions {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(query == StatusQuery::NonIgnoredFiles)
+            .recurse_untracked_dirs(false)
+            .include_ignored(false)
+            .recurse_ignored_dirs(false)
+            .exclude_submodules(true);
+        opts
+    }
+
+    /// Whether tracked files have changes that are not in HEAD
+    pub fn has_tracked_changes(&self) -> CcResult<bool> {
+        self.answer(StatusQuery::TrackedChanges)
+    }
+
+    /// Whether the working directory contains any non-ignored file
+    pub fn has_non_ignored_files(&self) -> CcResult<bool> {
+        self.answer(StatusQuery::NonIgnoredFiles)
+    }
+
+    /// Answer `query` from the cache, scanning only when HEAD or the index changed
+    fn answer(&self, query: StatusQuery) -> CcResult<bool> {
+        let Some(cache) = self.cache else {
+            return self.scan(query);
+        };
+        let key = self.key();
+        if let Some((cached_key, answer)) = cache.entries.borrow().get(&query)
+            && *cached_key == key
+        {
+            return Ok(*answer);
+        }
+        let answer = self.scan(query)?;
+        // 扫描可能刷新了索引，用扫描后的状态作为缓存键
+        cache
+            .entries
+            .borrow_mut()
+            .insert(query, (self.key(), answer));
+        Ok(answer)
+    }
+
+    /// Current HEAD and index file state
+    fn key(&self) -> StatusKey {
+        let head = self.repo.head().ok().and_then(|head| head.target());
+        let index = std::fs::metadata(self.repo.path().join("index"))
+            .ok()
+            .and_then(|meta| Some((meta.modified().ok()?, meta.len())));
+        (head, index)
+    }
+
+    /// Run the status scan for `query`
+    fn scan(&self, query: StatusQuery) -> CcResult<bool> {
+        if CcgConfig::load(self.repo).fsmonitor
+            && let Some(answer) = self.scan_with_git(query)
+        {
+            return Ok(answer);
+        }
+        let statuses = self
+            .repo
+            .statuses(Some(&mut Self::options(query)))
+            .map_err(CheckpointError::GitOperationFailed)?;
+        Ok(!statuses.is_empty())
+    }
+
+    /// Ask `git status`, which uses the configured fsmonitor or watchman
+    ///
+    /// # Returns
+    /// None when git is not available or fails, so the caller can fall back
+    /// to libgit2
+    fn scan_with_git(&self, query: StatusQuery) -> Option<bool> {
+        let workdir = self.repo.workdir()?;
+        let untracked = match query {
+            StatusQuery::TrackedChanges => "--untracked-files=no",
+            StatusQuery::NonIgnoredFiles => "--untracked-files=normal",
+        };
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(workdir)
+            .args(["status", "--porcelain", "-z", untracked])
+            .arg("--ignore-submodules=all")
+            // 只读查询，不让 git 写回索引，避免与 ccg 自身的索引操作冲突
+            .env("GIT_OPTIONAL_LOCKS", "0")
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        output.status.success().then_some(!output.stdout.is_empty())
+    }
+}
@@ -0,0 +1,255 @@
+//! Checkpoint descriptions generated from the pending changes
+//!
+//! When a checkpoint has no useful message, a short description is built
+//! from the diff between the CCG tip and the working directory using static
+//! heuristics: files are classified by path (tests, docs, config, code) and
+//! the functions touched in code files are read from the hunk headers, e.g.
+//! "Modified 3 functions in parser.rs; added tests in parser_tests.rs".
+
+use super::diff::DiffOperations;
+use super::paths::display_path;
+use super::types::CCG_BRANCH_NAME;
+use crate::error::Result as CcResult;
+use git2::{BranchType, Delta, Diff, DiffFormat, Patch, Repository};
+use rust_i18n::t;
+use std::path::Path;
+
+/// Most files named in a description; the rest are counted
+const MAX_FILES: usize = 3;
+
+/// What kind of file a path is, judged from its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// Test sources (`tests/`, `*_test.*`, `*.spec.*`, …)
+    Test,
+    /// Documentation (`docs/`, Markdown, plain text)
+    Docs,
+    /// Configuration and manifests (TOML, JSON, YAML, lock files)
+    Config,
+    /// Everything else
+    Code,
+}
+
+impl FileKind {
+    /// Classify `path`
+    pub fn of(path: &str) -> Self {
+        let lower = path.to_lowercase();
+        let name = lower.rsplit('/').next().unwrap_or(&lower);
+        let stem = name.split('.').next().unwrap_or(name);
+        let in_dir = |dir: &str| lower.split('/').rev().skip(1).any(|part| part == dir);
+        if in_dir("tests")
+            || in_dir("test")
+            || in_dir("__tests__")
+            || stem.starts_with("test_")
+            || stem.ends_with("_test")
+            || stem.ends_with("_tests")
+            || name.contains(".test.")
+            || name.contains(".spec.")
+        {
+            return FileKind::Test;
+        }
+        let extension = Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("");
+        match extension {
+            "md" | "markdown" | "rst" | "txt" | "adoc" => FileKind::Docs,
+            _ if in_dir("docs") || in_dir("doc") => FileKind::Docs,
+            "toml" | "json" | "yml" | "yaml" | "ini" | "cfg" | "lock" => FileKind::Config,
+            _ => FileKind::Code,
+        }
+    }
+}
+
+/// A changed file, as far as the description is concerned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedFile {
+    /// Path relative to the repository root
+    pub path: String,
+    /// Added, deleted or modified
+    pub status: Delta,
+    /// Functions named in the hunk headers, without duplicates
+    pub functions: Vec<String>,
+}
+
+impl ChangedFile {
+    /// File name without directories, as shown in descriptions
+    fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    /// One clause describing the change in `locale`
+    fn describe(&self, locale: &str) -> String {
+        let file = self.name();
+        let kind = FileKind::of(&self.path);
+        match (self.status, kind) {
+            (Delta::Added | Delta::Untracked, FileKind::Test) => {
+                t!("summary_added_tests", locale = locale, file = file).to_string()
+            }
+            (Delta::Added | Delta::Untracked, _) => {
+                t!("summary_added", locale = locale, file = file).to_string()
+            }
+            (Delta::Deleted, _) => t!("summary_removed", locale = locale, file = file).to_string(),
+            (_, FileKind::Test) => {
+                t!("summary_updated_tests", locale = locale, file = file).to_string()
+            }
+            (_, FileKind::Docs) => {
+                t!("summary_updated_docs", locale = locale, file = file).to_string()
+            }
+            (_, FileKind::Code) if self.functions.len() == 1 => t!(
+                "summary_modified_function",
+                locale = locale,
+                function = self.functions[0],
+                file = file
+            )
+            .to_string(),
+            (_, FileKind::Code) if self.functions.len() > 1 => t!(
+                "summary_modified_functions",
+                locale = locale,
+                count = self.functions.len(),
+                file = file
+            )
+            .to_string(),
+            _ => t!("summary_modified", locale = locale, file = file).to_string(),
+        }
+    }
+}
+
+/// Name of the function a hunk header points into, if it names one
+///
+/// The text after the second `@@` is the line git chose as the hunk's
+/// context, e.g. `pub fn parse(input: &str) -> Ast {`; the identifier just
+/// before the first `(` is taken as the function name.
+pub fn hunk_function(header: &str) -> Option<String> {
+    let context = header.splitn(3, "@@").nth(2)?.trim();
+    let before_paren = &context[..context.find('(')?];
+    let name = before_paren
+        .rsplit(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .next()?;
+    let keyword = matches!(name, "if" | "for" | "while" | "switch" | "match" | "return");
+    (!name.is_empty() && !keyword && !name.starts_with(|c: char| c.is_ascii_digit()))
+        .then(|| name.to_string())
+}
+
+/// Build a description of `files` in `locale`
+///
+/// # Returns
+/// None when nothing changed
+pub fn summarize(files: &[ChangedFile], locale: &str) -> Option<String> {
+    if files.is_empty() {
+        return None;
+    }
+    // 代码文件优先，其次是测试、文档和配置
+    let mut ordered: Vec<&ChangedFile> = files.iter().collect();
+    ordered.sort_by_key(|file| match FileKind::of(&file.path) {
+        FileKind::Code => (0, usize::MAX - file.functions.len()),
+        FileKind::Test => (1, 0),
+        FileKind::Docs => (2, 0),
+        FileKind::Config => (3, 0),
+    });
+    let mut clauses: Vec<String> = ordered
+        .iter()
+        .take(MAX_FILES)
+        .map(|file| file.describe(locale))
+        .collect();
+    if files.len() > MAX_FILES {
+        clauses.push(
+            t!(
+                "summary_more",
+                locale = locale,
+                count = files.len() - MAX_FILES
+            )
+            .to_string(),
+        );
+    }
+    let text = clauses.join("; ");
+    let mut chars = text.chars();
+    let first = chars.next()?;
+    Some(first.to_uppercase().chain(chars).collect())
+}
+
+/// Describes the changes a new checkpoint would record
+pub struct Summarizer<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> Summarizer<'a> {
+    /// Create a new Summarizer instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// Files changed in the working directory since the CCG tip
+    ///
+    /// Falls back to HEAD before the CCG branch exists, and to the empty
+    /// tree in a repository without commits.
+    ///
+    /// # Errors
+    /// Returns CheckpointError::GitOperationFailed if the diff cannot be generated
+    pub fn pending_changes(&self) -> CcResult<Vec<ChangedFile>> {
+        let diff = self.pending_diff()?;
+
+        let mut files = Vec::new();
+        for index in 0..diff.deltas().len() {
+            let delta = diff.get_delta(index).expect("delta index in range");
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let mut functions: Vec<String> = Vec::new();
+            if let Some(patch) = Patch::from_diff(&diff, index)? {
+                for hunk in 0..patch.num_hunks() {
+                    let (hunk, _) = patch.hunk(hunk)?;
+                    let header = String::from_utf8_lossy(hunk.header());
+                    if let Some(name) = hunk_function(&header)
+                        && !functions.contains(&name)
+                    {
+                        functions.push(name);
+                    }
+                }
+            }
+            files.push(ChangedFile {
+                path: display_path(path),
+                status: delta.status(),
+                functions,
+            });
+        }
+        Ok(files)
+    }
+
+    /// The pending changes as a unified patch, cut off after `max_bytes`
+    ///
+    /// Used as input for summaries that need the diff text itself.
+    pub fn pending_patch(&self, max_bytes: usize) -> CcResult<String> {
+        let diff = self.pending_diff()?;
+        let mut patch = Vec::new();
+        // 超过上限后停止输出，大型差异不会被完整渲染
+        let _ = diff.print(DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin() as u8);
+            }
+            patch.extend_from_slice(line.content());
+            patch.len() < max_bytes
+        });
+        patch.truncate(max_bytes);
+        Ok(String::from_utf8_lossy(&patch).into_owned())
+    }
+
+    /// Diff from the CCG tip to the working directory
+    fn pending_diff(&self) -> CcResult<Diff<'a>> {
+        let base = self
+            .repo
+            .find_branch(CCG_BRANCH_NAME, BranchType::Local)
+            .ok()
+            .and_then(|branch| branch.get().peel_to_tree().ok())
+            .or_else(|| self.repo.head().ok()?.peel_to_tree().ok());
+        DiffOperations::new(self.repo).workdir_diff(base.as_ref())
+    }
+
+    /// Description of the pending changes in `locale`
+    ///
+    /// # Returns
+    /// None when the working directory matches the CCG tip
+    pub fn describe(&self, locale: &str) -> CcResult<Option<String>> {
+        Ok(summarize(&self.pending_changes()?, locale))
+    }
+}
@@ -0,0 +1,80 @@
+//! Task brackets around checkpoints
+//!
+//! `ccg task start "implement auth"` records the active task in
+//! `.git/ccg/task` as a single `<id> <name>` line; every checkpoint created
+//! while it exists carries a `Ccg-Task: <id>` trailer, and `ccg task end`
+//! removes the file. The task's checkpoints are found again by that trailer.
+
+use super::ccg_dir;
+use super::types::Task;
+use crate::error::Result as CcResult;
+use git2::Repository;
+use std::fs;
+use std::path::PathBuf;
+
+/// Longest task ID derived from a name, before a uniqueness suffix
+const MAX_ID_LEN: usize = 40;
+
+/// Derive a task ID from its name: lowercase words joined by `-`
+///
+/// Names without letters or digits get the ID `task`.
+pub fn task_slug(name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug: String = slug.chars().take(MAX_ID_LEN).collect();
+    match slug.trim_end_matches('-') {
+        "" => "task".to_string(),
+        slug => slug.to_string(),
+    }
+}
+
+/// The active task marker
+pub struct ActiveTask<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> ActiveTask<'a> {
+    /// Create a new ActiveTask instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    fn path(&self) -> PathBuf {
+        ccg_dir(self.repo).join("task")
+    }
+
+    /// The active task, if one was started and not ended
+    pub fn load(&self) -> CcResult<Option<Task>> {
+        let content = match fs::read_to_string(self.path()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let line = content.lines().next().unwrap_or("").trim();
+        let (id, name) = line.split_once(' ').unwrap_or((line, line));
+        Ok((!id.is_empty()).then(|| Task {
+            id: id.to_string(),
+            name: name.to_string(),
+        }))
+    }
+
+    /// Make `task` the active task, or end the active task when None
+    pub fn save(&self, task: Option<&Task>) -> CcResult<()> {
+        let path = self.path();
+        let Some(task) = task else {
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => return Ok(()),
+            }
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, format!("{} {}\n", task.id, task.name))?;
+        Ok(())
+    }
+}
@@ -3,6 +3,9 @@
 //! This module contains common types, constants, and utilities used across
 //! all git operation modules.
 
+use super::paths::display_path;
+use std::path::{Path, PathBuf};
+
 /// The name of the CCG (Claude Code Checkpoint Guardian) branch
 ///
 /// This is the special branch where all checkpoints are stored.
@@ -13,6 +16,866 @@ pub const CCG_BRANCH_NAME: &str = "ccg";
 /// Used when creating the first commit in a new repository.
 pub const DEFAULT_COMMIT_MESSAGE: &str = "Initial commit - Claude Code Checkpoint Guardian init";
 
+/// Fallback author name when neither `ccg.authorName` nor `user.name` is set
+pub const DEFAULT_AUTHOR_NAME: &str = "Claude Code Checkpoint";
+
+/// Fallback author email when neither `ccg.authorEmail` nor `user.email` is set
+pub const DEFAULT_AUTHOR_EMAIL: &str = "claudecode@checkpoint.local";
+
+/// Committer name used when `ccg.machineCommitter` is enabled
+///
+/// Marks checkpoints as machine-generated in `git log --format=fuller`.
+pub const MACHINE_COMMITTER_NAME: &str = "ccg (machine-generated)";
+
+/// Committer email used when `ccg.machineCommitter` is enabled
+pub const MACHINE_COMMITTER_EMAIL: &str = "ccg@checkpoint.local";
+
+/// Trailer marking a checkpoint created with `--allow-empty`
+///
+/// Empty checkpoints share their parent's tree; the trailer tells them apart
+/// from checkpoints whose changes were later reverted.
+pub const EMPTY_CHECKPOINT_TRAILER: &str = "Ccg-Empty: true";
+
+/// Trailer naming the branch the user was on when a checkpoint was created
+pub const BASE_BRANCH_TRAILER: &str = "Ccg-Base-Branch";
+
+/// Trailer holding the tip of that branch when the checkpoint was created
+pub const BASE_COMMIT_TRAILER: &str = "Ccg-Base-Commit";
+
+/// Name of [`BASE_COMMIT_TRAILER`] written by older versions, still read
+pub const LEGACY_BASE_COMMIT_TRAILER: &str = "Ccg-Base";
+
+/// Trailer recording whether the verification command passed (`pass` or `fail`)
+pub const VERIFY_TRAILER: &str = "Ccg-Verify";
+
+/// Trailer holding the verification command that was run
+pub const VERIFY_COMMAND_TRAILER: &str = "Ccg-Verify-Command";
+
+/// Trailer naming the task a checkpoint was created in (`ccg task start`)
+pub const TASK_TRAILER: &str = "Ccg-Task";
+
+/// Trailer holding the task's name as given to `ccg task start`
+pub const TASK_NAME_TRAILER: &str = "Ccg-Task-Name";
+
+/// Trailer holding the Claude Code session a hook checkpoint was created in
+pub const SESSION_TRAILER: &str = "Ccg-Session";
+
+/// Trailer naming the Claude Code tool whose hook created a checkpoint
+pub const TOOL_TRAILER: &str = "Ccg-Tool";
+
+/// Trailer recording what created a checkpoint, see [`CheckpointSource`]
+pub const SOURCE_TRAILER: &str = "Ccg-Source";
+
+/// What created a checkpoint, recorded in the `Ccg-Source` trailer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointSource {
+    /// A Claude Code hook event (`ccg create` reading the hook payload)
+    Hook,
+    /// `ccg create` run by hand
+    Manual,
+    /// A file-saved event from an editor (`ccg listen`)
+    Editor,
+}
+
+impl CheckpointSource {
+    /// Value written to the trailer
+    pub fn name(self) -> &'static str {
+        match self {
+            CheckpointSource::Hook => "hook",
+            CheckpointSource::Manual => "manual",
+            CheckpointSource::Editor => "editor",
+        }
+    }
+}
+
+impl std::str::FromStr for CheckpointSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "hook" => Ok(CheckpointSource::Hook),
+            "manual" => Ok(CheckpointSource::Manual),
+            "editor" => Ok(CheckpointSource::Editor),
+            other => Err(format!("unknown checkpoint source: {other}")),
+        }
+    }
+}
+
+/// Trailer naming the team user who created a checkpoint, written when
+/// `ccg.team.permissions` is not `open`
+pub const USER_TRAILER: &str = "Ccg-User";
+
+/// Trailer naming a file whose changes in the checkpoint go beyond what the
+/// tool call reported, once per file
+pub const INTERLEAVED_TRAILER: &str = "Ccg-Interleaved";
+
+/// Who may discard or rewrite checkpoints, from `ccg.team.permissions`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Permissions {
+    /// Anyone may (the default)
+    #[default]
+    Open,
+    /// Only the user recorded in a checkpoint's `Ccg-User` trailer; older
+    /// checkpoints without the trailer stay open to everyone
+    Owner,
+    /// Nobody: checkpoints can only be added
+    AppendOnly,
+}
+
+impl Permissions {
+    /// Value used in the configuration
+    pub fn name(self) -> &'static str {
+        match self {
+            Permissions::Open => "open",
+            Permissions::Owner => "owner",
+            Permissions::AppendOnly => "append-only",
+        }
+    }
+}
+
+impl std::str::FromStr for Permissions {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "open" => Ok(Permissions::Open),
+            "owner" => Ok(Permissions::Owner),
+            "append-only" | "appendonly" => Ok(Permissions::AppendOnly),
+            other => Err(format!("unknown permissions mode: {other}")),
+        }
+    }
+}
+
+/// The trailer block of a commit message
+///
+/// Follows the rules of `git interpret-trailers`: the trailers are the last
+/// paragraph of the message, provided it is not the subject paragraph and
+/// every line in it is a `Key: value` line (keys are letters, digits and
+/// `-`) or a whitespace-indented continuation of the previous value. Keys
+/// are compared case-insensitively. All ccg metadata is stored this way, so
+/// external tools can read it with `git interpret-trailers --parse` or
+/// `git log --format=%(trailers)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trailers {
+    entries: Vec<(String, String)>,
+}
+
+impl Trailers {
+    /// An empty trailer block
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The trailers of `message`, empty if it has no trailer block
+    pub fn parse(message: &str) -> Self {
+        Self::split(message).1
+    }
+
+    /// Split `message` into the text before its trailer block (without
+    /// trailing whitespace) and the trailers
+    pub fn split(message: &str) -> (&str, Self) {
+        let message = message.trim_end();
+        let Some(start) = message.rfind("\n\n") else {
+            return (message, Self::new());
+        };
+        let mut entries: Vec<(String, String)> = Vec::new();
+        for line in message[start + 2..].lines() {
+            if line.starts_with([' ', '\t']) {
+                match entries.last_mut() {
+                    Some((_, value)) => {
+                        value.push(' ');
+                        value.push_str(line.trim());
+                        continue;
+                    }
+                    None => return (message, Self::new()),
+                }
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                return (message, Self::new());
+            };
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                return (message, Self::new());
+            }
+            entries.push((key.to_string(), value.trim().to_string()));
+        }
+        if entries.is_empty() {
+            return (message, Self::new());
+        }
+        (message[..start].trim_end(), Self { entries })
+    }
+
+    /// Value of the first trailer named `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Values of every trailer named `key`, in order
+    pub fn get_all<'s>(&'s self, key: &'s str) -> impl Iterator<Item = &'s str> + 's {
+        self.entries
+            .iter()
+            .filter(move |(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// All trailers as (key, value) pairs, in order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// The base commit recorded at creation, written as `Ccg-Base` by older versions
+    pub fn base_commit(&self) -> Option<&str> {
+        self.get(BASE_COMMIT_TRAILER)
+            .or_else(|| self.get(LEGACY_BASE_COMMIT_TRAILER))
+    }
+
+    /// Whether there are no trailers
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add a trailer; line breaks in the value are folded into spaces so the
+    /// value stays on one line
+    pub fn push(&mut self, key: &str, value: &str) -> &mut Self {
+        let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.entries.push((key.to_string(), value));
+        self
+    }
+
+    /// `message` with these trailers appended
+    ///
+    /// Joins the message's trailer block when it has one, otherwise starts a
+    /// new block after a blank line.
+    pub fn apply_to(&self, message: &str) -> String {
+        let (body, mut trailers) = Self::split(message);
+        if self.is_empty() {
+            return message.to_string();
+        }
+        trailers.entries.extend(self.entries.iter().cloned());
+        format!("{body}\n\n{trailers}")
+    }
+}
+
+impl std::fmt::Display for Trailers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (key, value) in &self.entries {
+            writeln!(f, "{key}: {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Marker ref holding the restore journal
+pub const RESTORE_JOURNAL_REF: &str = "refs/ccg/restores";
+
+/// Prefix of the refs holding snapshots (`refs/ccg/stash/<N>`)
+pub const SNAPSHOT_REF_PREFIX: &str = "refs/ccg/stash/";
+
+/// Prefix of the per-user checkpoint namespaces shared in team mode
+/// (`refs/ccg/users/<name>/ccg`)
+pub const USERS_REF_PREFIX: &str = "refs/ccg/users/";
+
+/// Notes ref holding the parts of checkpoint messages cut off at the size cap
+pub const CHECKPOINT_NOTES_REF: &str = "refs/notes/ccg";
+
+/// Size cap of a checkpoint message in bytes when `ccg.message.maxBytes` is unset
+pub const DEFAULT_MESSAGE_MAX_BYTES: usize = 16 * 1024;
+
+/// Trailer naming the checkpoint a restore reset the CCG branch to
+pub const RESTORE_TARGET_TRAILER: &str = "Ccg-Restore-Target";
+
+/// Trailer naming the CCG tip discarded by a restore
+pub const RESTORE_DISCARDED_TIP_TRAILER: &str = "Ccg-Discarded-Tip";
+
+/// Trailer holding the number of checkpoints discarded by a restore
+pub const RESTORE_DISCARDED_TRAILER: &str = "Ccg-Discarded";
+
+/// Prefix of human-friendly checkpoint IDs (`cp-1`, `cp-2`, …)
+pub const CHECKPOINT_ID_PREFIX: &str = "cp-";
+
+/// Name of the newest checkpoint; `latest~N` is the N-th before it in list order
+pub const LATEST_REF: &str = "latest";
+
+/// Legacy message prefix stripped from checkpoint summaries
+pub const RAW_INPUT_PREFIX: &str = "Checkpoint created with raw input: ";
+
+/// A checkpoint on the CCG branch
+///
+/// Structured counterpart of the formatted lines returned by
+/// `list_checkpoints`, intended for library users and tests.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CheckpointEntry {
+    /// Full commit hash
+    pub hash: String,
+    /// Full commit message
+    pub message: String,
+    /// Author name
+    pub author: String,
+    /// Commit time in seconds since the Unix epoch
+    pub time: i64,
+    /// Human-friendly ID number (`cp-N`), if one was assigned
+    pub id: Option<u64>,
+}
+
+impl CheckpointEntry {
+    /// The first seven characters of the hash
+    pub fn short_hash(&self) -> &str {
+        crate::ui::short_hash(&self.hash)
+    }
+
+    /// The `cp-N` ID, if one was assigned
+    pub fn display_id(&self) -> Option<String> {
+        self.id.map(super::ids::format_checkpoint_id)
+    }
+
+    /// First line of the message, without the legacy raw-input prefix
+    pub fn summary(&self) -> &str {
+        let line = self.message.lines().next().unwrap_or("");
+        line.strip_prefix(RAW_INPUT_PREFIX).unwrap_or(line)
+    }
+
+    /// The label derived from the message conventions, if any
+    pub fn label(&self) -> Option<super::labels::CheckpointLabel> {
+        super::labels::CheckpointLabel::classify(self.summary())
+    }
+
+    /// Name of the Claude Code tool whose hook created the checkpoint, if any
+    ///
+    /// Read from the `Ccg-Tool` trailer; for checkpoints created before it
+    /// was recorded, taken from hook-style messages such as `Edit on main.rs`.
+    pub fn tool(&self) -> Option<String> {
+        if let Some(tool) = self.trailers().get(TOOL_TRAILER) {
+            return Some(tool.to_string());
+        }
+        use super::labels::CheckpointLabel;
+        if !matches!(
+            self.label(),
+            Some(CheckpointLabel::Edit | CheckpointLabel::Write)
+        ) {
+            return None;
+        }
+        self.summary()
+            .split(|c: char| c.is_whitespace() || c == ':')
+            .next()
+            .map(str::to_string)
+    }
+
+    /// The verification result recorded when the checkpoint was created
+    pub fn verification(&self) -> VerifyStatus {
+        VerifyStatus::from_message(&self.message)
+    }
+
+    /// The trailers of the message
+    pub fn trailers(&self) -> Trailers {
+        Trailers::parse(&self.message)
+    }
+
+    /// ID of the task the checkpoint was created in, if any
+    pub fn task(&self) -> Option<String> {
+        self.trailers().get(TASK_TRAILER).map(str::to_string)
+    }
+
+    /// Name of the task the checkpoint was created in, if any
+    pub fn task_name(&self) -> Option<String> {
+        self.trailers().get(TASK_NAME_TRAILER).map(str::to_string)
+    }
+
+    /// ID of the Claude Code session the checkpoint was created in, if any
+    pub fn session(&self) -> Option<String> {
+        self.trailers().get(SESSION_TRAILER).map(str::to_string)
+    }
+
+    /// What created the checkpoint, if recorded
+    pub fn source(&self) -> Option<CheckpointSource> {
+        self.trailers().get(SOURCE_TRAILER)?.parse().ok()
+    }
+
+    /// Team user who created the checkpoint, if recorded
+    pub fn user(&self) -> Option<String> {
+        self.trailers().get(USER_TRAILER).map(str::to_string)
+    }
+
+    /// Files that were also edited by someone other than the tool while the
+    /// checkpoint was taken
+    pub fn interleaved(&self) -> Vec<String> {
+        self.trailers()
+            .get_all(INTERLEAVED_TRAILER)
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// A named chain of checkpoints, bracketed by `ccg task start` and `ccg task end`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    /// Identifier recorded in the `Ccg-Task` trailer, derived from the name
+    pub id: String,
+    /// Description given to `ccg task start`
+    pub name: String,
+}
+
+/// The checkpoints of one task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskRange {
+    /// Commit the first checkpoint of the range was created on
+    pub base: String,
+    /// Latest checkpoint of the range
+    pub tip: String,
+    /// The checkpoints of the range, newest first
+    pub checkpoints: Vec<CheckpointEntry>,
+}
+
+/// Verification result recorded in a checkpoint's `Ccg-Verify` trailer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyStatus {
+    /// The verification command passed
+    Passed,
+    /// The verification command failed
+    Failed,
+    /// No verification was recorded
+    Unknown,
+}
+
+impl VerifyStatus {
+    /// Read the status from a commit message
+    pub fn from_message(message: &str) -> Self {
+        match Trailers::parse(message).get(VERIFY_TRAILER) {
+            Some("pass") => VerifyStatus::Passed,
+            Some("fail") => VerifyStatus::Failed,
+            _ => VerifyStatus::Unknown,
+        }
+    }
+}
+
+/// Which checkpoints `ccg list` shows
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListFilter {
+    /// Keep only checkpoints carrying one of these labels (all when empty)
+    pub labels: Vec<super::labels::CheckpointLabel>,
+    /// Keep only checkpoints whose verification passed
+    pub only_passing: bool,
+    /// Keep only checkpoints created in this task
+    pub task: Option<String>,
+    /// Leave out checkpoints whose changes are whitespace-only; applied by
+    /// the backend, since it needs each checkpoint's statistics
+    pub skip_format_only: bool,
+    /// List this user's synced namespace instead of the CCG branch
+    pub user: Option<String>,
+}
+
+impl ListFilter {
+    /// Whether the filter keeps every checkpoint
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+            && !self.only_passing
+            && self.task.is_none()
+            && !self.skip_format_only
+    }
+
+    /// Whether `entry` is shown
+    pub fn matches(&self, entry: &CheckpointEntry) -> bool {
+        (self.labels.is_empty()
+            || entry
+                .label()
+                .is_some_and(|label| self.labels.contains(&label)))
+            && (!self.only_passing || entry.verification() == VerifyStatus::Passed)
+            && self
+                .task
+                .as_ref()
+                .is_none_or(|task| entry.task().as_ref() == Some(task))
+    }
+}
+
+/// A column of the checkpoint list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListColumn {
+    /// Short commit hash
+    Hash,
+    /// Checkpoint ID (`cp-N`)
+    Id,
+    /// Commit time
+    Time,
+    /// Label badge derived from the message (`tool` on the command line)
+    Label,
+    /// Verification badge recorded by `create --checkpoint-if-tests-pass`
+    Verify,
+    /// First line of the message, truncated to fit the terminal
+    Message,
+    /// Added/deleted lines and changed files
+    Stats,
+    /// Author name
+    Author,
+}
+
+impl ListColumn {
+    /// All columns, in the order they are documented
+    pub const ALL: [ListColumn; 8] = [
+        ListColumn::Hash,
+        ListColumn::Id,
+        ListColumn::Time,
+        ListColumn::Label,
+        ListColumn::Verify,
+        ListColumn::Message,
+        ListColumn::Stats,
+        ListColumn::Author,
+    ];
+
+    /// Columns shown when none are configured (`--stat` appends `Stats`)
+    pub const DEFAULT: [ListColumn; 6] = [
+        ListColumn::Hash,
+        ListColumn::Id,
+        ListColumn::Time,
+        ListColumn::Label,
+        ListColumn::Verify,
+        ListColumn::Message,
+    ];
+
+    /// Name used on the command line and in `ccg.list.columns`
+    pub fn name(self) -> &'static str {
+        match self {
+            ListColumn::Hash => "hash",
+            ListColumn::Id => "id",
+            ListColumn::Time => "time",
+            ListColumn::Label => "tool",
+            ListColumn::Verify => "verify",
+            ListColumn::Message => "message",
+            ListColumn::Stats => "stats",
+            ListColumn::Author => "author",
+        }
+    }
+}
+
+impl std::fmt::Display for ListColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for ListColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        match name.as_str() {
+            "label" => Ok(ListColumn::Label),
+            _ => ListColumn::ALL
+                .into_iter()
+                .find(|column| column.name() == name)
+                .ok_or_else(|| format!("unknown column '{s}'")),
+        }
+    }
+}
+
+/// Parse a comma-separated column list such as `hash,time,message`
+///
+/// # Errors
+/// Returns the message for the first unknown column
+pub fn parse_list_columns(text: &str) -> Result<Vec<ListColumn>, String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// A restore recorded in the restore journal
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct RestoreRecord {
+    /// Full hash of the checkpoint the CCG branch was reset to
+    pub target: String,
+    /// Full hash of the CCG tip before the reset
+    pub discarded_tip: String,
+    /// Number of checkpoints dropped by the reset
+    pub discarded: usize,
+    /// Time of the restore in seconds since the Unix epoch
+    pub time: i64,
+}
+
+/// The checkpoint history as a DAG, for front-ends that draw it
+///
+/// Holds the history of the CCG branch and the lines of checkpoints that
+/// restores discarded, which the restore journal keeps reachable, together
+/// with the restores themselves.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CheckpointGraph {
+    /// Full hash of the CCG branch tip
+    pub tip: String,
+    /// Every commit, children before parents and otherwise newest first; on
+    /// the branch alone this is the order of `list`
+    pub nodes: Vec<GraphNode>,
+    /// One edge per parent of every node
+    pub edges: Vec<GraphEdge>,
+    /// Restores from the restore journal, newest first; each connects the
+    /// tip it discarded to the checkpoint it restored
+    pub restores: Vec<RestoreRecord>,
+}
+
+impl CheckpointGraph {
+    /// The node of the commit `hash` (full hash), if it is in the graph
+    pub fn node(&self, hash: &str) -> Option<&GraphNode> {
+        self.nodes.iter().find(|node| node.entry.hash == hash)
+    }
+
+    /// Full hashes of the children of `hash`, in node order
+    pub fn children(&self, hash: &str) -> Vec<&str> {
+        self.edges
+            .iter()
+            .filter(|edge| edge.parent == hash)
+            .map(|edge| edge.child.as_str())
+            .collect()
+    }
+}
+
+/// A commit in a [`CheckpointGraph`]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct GraphNode {
+    /// Hash, message, author, time and `cp-N` number; trailers such as the
+    /// session or source are read from the message by its accessors
+    pub entry: CheckpointEntry,
+    /// Full hashes of the parents, first parent first
+    pub parents: Vec<String>,
+    /// Names of the tags pointing at the commit
+    pub tags: Vec<String>,
+    /// Whether the CCG branch still contains the commit; false for
+    /// checkpoints only a restore journal entry keeps
+    pub on_branch: bool,
+}
+
+/// A parent link in a [`CheckpointGraph`]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct GraphEdge {
+    /// Full hash of the child commit
+    pub child: String,
+    /// Full hash of the parent commit
+    pub parent: String,
+    /// Whether this is the child's first parent, the line `list` follows
+    pub first_parent: bool,
+}
+
+/// A guard hook skipped with `--bypass-guard` or `CCG_BYPASS_GUARD=1`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct GuardBypass {
+    /// Time of the bypass in seconds since the Unix epoch
+    pub time: i64,
+    /// Who bypassed the hook, as `Name <email>` from the git config
+    pub user: String,
+    /// Name of the skipped hook, e.g. `pre-create`
+    pub hook: String,
+    /// The checkpoint message or restore target the hook would have checked
+    pub target: String,
+    /// How the bypass was requested: `--bypass-guard` or `CCG_BYPASS_GUARD`
+    pub via: String,
+}
+
+/// Why a hook event was filtered out by `ccg.hooks.tools` or `ccg.hooks.paths`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum HookSkip {
+    /// The tool is not listed in `ccg.hooks.tools`
+    Tool { tool: String },
+    /// None of the files the event names match `ccg.hooks.paths`
+    Path { files: Vec<String> },
+}
+
+/// What a tool call reports it changed in one file
+///
+/// Compared with the real changes when the checkpoint is created, to notice
+/// edits someone else made to the same file in the meantime.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolEdit {
+    /// The file, absolute or relative to the repository root
+    pub path: String,
+    /// Text the tool replaced (`old_string` of an edit)
+    pub removed: Vec<String>,
+    /// Text the tool wrote: `new_string` of an edit, or a Write's `content`
+    pub added: Vec<String>,
+    /// The tool wrote the whole file, so every removed line is its doing
+    pub whole_file: bool,
+}
+
+/// A filtered hook event, as recorded in `.git/ccg/hook-filter.log`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FilteredEvent {
+    /// Time of the event in seconds since the Unix epoch
+    pub time: i64,
+    /// Tool named by the hook data
+    pub tool: String,
+    /// Why no checkpoint was created
+    pub skip: HookSkip,
+}
+
+/// Outcome of repairing the staging area after an interrupted create
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct IndexRepair {
+    /// When the interrupted create started, seconds since the Unix epoch
+    pub interrupted_at: i64,
+    /// When the repair ran, seconds since the Unix epoch
+    pub repaired_at: i64,
+    /// Whether the index was restored from its backup; false when the
+    /// checkpoint had already been committed and the index matched it
+    pub restored: bool,
+}
+
+/// How the latest checkpoint and the current branch have diverged
+///
+/// Both sides are compared with their common ancestor, so a file changed
+/// identically on both sides counts as in sync.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BranchDrift {
+    /// Branch HEAD is on, or the commit hash when HEAD is detached
+    pub branch: String,
+    /// Commit HEAD points to
+    pub head: String,
+    /// Latest checkpoint
+    pub checkpoint: String,
+    /// Common ancestor of the two, None when their histories are unrelated
+    pub base: Option<String>,
+    /// Files changed only by the checkpoints
+    pub only_checkpoint: Vec<String>,
+    /// Files changed only on the branch
+    pub only_branch: Vec<String>,
+    /// Files changed on both sides with different results
+    pub diverged: Vec<String>,
+}
+
+impl BranchDrift {
+    /// Whether the checkpoint and the branch have the same files
+    pub fn is_in_sync(&self) -> bool {
+        self.only_checkpoint.is_empty() && self.only_branch.is_empty() && self.diverged.is_empty()
+    }
+}
+
+/// Commits added to a checkpoint's base branch since it was created
+///
+/// Restoring such a checkpoint overwrites the working directory with files
+/// that predate those commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseDrift {
+    /// Branch the user was on when the checkpoint was created
+    pub branch: String,
+    /// Full hash of the branch tip recorded in the checkpoint
+    pub base: String,
+    /// Number of commits on the branch that the checkpoint predates
+    pub commits: usize,
+}
+
+/// Working directory changes parked with `ccg snapshot push`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Number in the snapshot's ref name
+    pub number: usize,
+    /// Full hash of the snapshot commit
+    pub hash: String,
+    /// Message given when the snapshot was pushed
+    pub message: String,
+    /// Creation time, seconds since the Unix epoch
+    pub time: i64,
+}
+
+/// How `ccg fetch` changed the local CCG branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchUpdate {
+    /// There was no local CCG branch; it now points at the fetched tip
+    Created,
+    /// The local branch was behind and now points at the fetched tip
+    FastForward,
+    /// The local branch already contains the fetched tip
+    UpToDate,
+    /// Both sides have checkpoints the other lacks; the local branch is kept
+    Diverged,
+}
+
+/// Outcome of `ccg fetch`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchReport {
+    pub remote: String,
+    /// Tip of the remote's CCG branch, kept in `refs/remotes/<remote>/ccg`;
+    /// None when the remote only has user namespaces
+    pub tip: Option<String>,
+    pub update: Option<FetchUpdate>,
+    /// Checkpoints reachable from the fetched tip that exist locally
+    pub available: usize,
+    /// History was cut at the requested depth
+    pub shallow: bool,
+    /// User namespaces fetched from the remote
+    pub users: Vec<String>,
+    /// User namespaces removed locally by their retention policy
+    pub expired: Vec<String>,
+}
+
+/// A user's synced checkpoints in team mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserNamespace {
+    pub name: String,
+    /// Newest checkpoint of the namespace
+    pub tip: String,
+    /// Commit time of the tip, seconds since the Unix epoch
+    pub time: i64,
+}
+
+/// Paths touched (or deliberately left alone) when a restore checks out a checkpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckoutReport {
+    /// Tracked files created, modified or deleted to match the checkpoint
+    pub updated: Vec<String>,
+    /// Untracked files removed because the restore was asked to clean
+    pub removed: Vec<String>,
+    /// Untracked and ignored files left in place
+    pub skipped: Vec<String>,
+    /// Files left as they were because they could not be written
+    pub unwritable: Vec<String>,
+}
+
+/// Outcome of rewriting checkpoint history on the CCG branch
+///
+/// Descendants of a rewritten checkpoint are replayed with their trees
+/// unchanged, so only their hashes (and parents) differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewrittenHistory {
+    /// Hashes of the checkpoints replacing the rewritten one, oldest first
+    /// (empty if it was dropped)
+    pub replacements: Vec<String>,
+    /// Old and new hashes of the replayed descendants, oldest first
+    pub replayed: Vec<(String, String)>,
+    /// New tip of the CCG branch
+    pub tip: String,
+}
+
+/// A later checkpoint touching files changed by an earlier one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckpointOverlap {
+    /// Full hash of the later checkpoint
+    pub hash: String,
+    /// First line of its message
+    pub summary: String,
+    /// Files changed by both checkpoints
+    pub files: Vec<String>,
+    /// Subset of `files` where the changed line ranges overlap
+    pub overlapping_lines: Vec<String>,
+}
+
+/// How a checkpoint relates to the checkpoints created after it
+///
+/// Line overlap is computed pairwise from hunk ranges: the earlier
+/// checkpoint's new-side ranges against each later checkpoint's old-side
+/// ranges. Line numbers are not adjusted for shifts introduced by checkpoints
+/// in between, so `revert_conflicts` is the authoritative answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpactReport {
+    /// Full hash of the analyzed checkpoint
+    pub hash: String,
+    /// Number of checkpoints after it on the CCG branch
+    pub later_checkpoints: usize,
+    /// Later checkpoints touching the same files, oldest first
+    pub overlaps: Vec<CheckpointOverlap>,
+    /// Files that would conflict if the checkpoint were reverted onto the tip
+    pub revert_conflicts: Vec<String>,
+}
+
 /// Statistics about file differences
 ///
 /// Contains aggregated information about changes in a diff, including
@@ -27,6 +890,14 @@ pub struct DiffStats {
     pub deletions: i32,
     /// Number of files modified (not including pure additions/deletions)
     pub modifications: i32,
+    /// Files matching `ccg.diff.ignore`, not included in the counts above
+    pub generated_files: usize,
+    /// Lines added in generated files
+    pub generated_additions: i32,
+    /// Lines deleted in generated files
+    pub generated_deletions: i32,
+    /// Every change only adds, removes or moves whitespace
+    pub format_only: bool,
 }
 
 impl DiffStats {
@@ -37,6 +908,10 @@ impl DiffStats {
             additions: 0,
             deletions: 0,
             modifications: 0,
+            generated_files: 0,
+            generated_additions: 0,
+            generated_deletions: 0,
+            format_only: false,
         }
     }
 }
@@ -53,8 +928,11 @@ impl Default for DiffStats {
 /// including the type of change and line statistics.
 #[derive(Debug, Clone)]
 pub struct FileChangeInfo {
-    /// Path to the changed file relative to repository root
+    /// Path to the changed file relative to repository root, for display;
+    /// bytes that are not valid UTF-8 are escaped (see [`display_path`])
     pub path: String,
+    /// The path as stored by git, for operations on the file
+    pub raw_path: PathBuf,
     /// Type of change (Added, Modified, Deleted, Renamed, etc.)
     pub status: git2::Delta,
     /// Number of lines added in this file
@@ -69,13 +947,8 @@ impl FileChangeInfo {
     /// # Arguments
     /// * `path` - The file path relative to repository root
     /// * `status` - The type of change made to the file
-    pub fn new(path: String, status: git2::Delta) -> Self {
-        Self {
-            path,
-            status,
-            additions: 0,
-            deletions: 0,
-        }
+    pub fn new(path: impl AsRef<Path>, status: git2::Delta) -> Self {
+        Self::with_stats(path, status, 0, 0)
     }
 
     /// Create a new FileChangeInfo with line change statistics
@@ -85,12 +958,224 @@ impl FileChangeInfo {
     /// * `status` - The type of change made to the file
     /// * `additions` - Number of lines added
     /// * `deletions` - Number of lines deleted
-    pub fn with_stats(path: String, status: git2::Delta, additions: i32, deletions: i32) -> Self {
+    pub fn with_stats(
+        path: impl AsRef<Path>,
+        status: git2::Delta,
+        additions: i32,
+        deletions: i32,
+    ) -> Self {
+        let path = path.as_ref();
         Self {
-            path,
+            path: display_path(path),
+            raw_path: path.to_path_buf(),
             status,
             additions,
             deletions,
         }
     }
 }
+
+/// One file's changes between two commits, with its unified diff
+#[derive(Debug, Clone)]
+pub struct FilePatch {
+    /// Path and line statistics of the change
+    pub change: FileChangeInfo,
+    /// Plain unified diff of the file, without color
+    pub patch: String,
+}
+
+/// Default `ccg.maxCheckpoints`
+pub const DEFAULT_MAX_CHECKPOINTS: u64 = 1000;
+
+/// Default `ccg.maxSize` (1 GiB)
+pub const DEFAULT_MAX_SIZE: u64 = 1 << 30;
+
+/// Default `ccg.cacheSize` for rendered diffs (64 MiB)
+pub const DEFAULT_CACHE_SIZE: u64 = 64 << 20;
+
+/// Default `ccg.lockTimeout`: how long to wait for another git process to
+/// release `.git/index.lock`, in milliseconds
+pub const DEFAULT_LOCK_TIMEOUT_MS: u64 = 2000;
+
+/// Checkpoint count and recorded size, shown by `ccg stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Number of checkpoints
+    pub checkpoints: u64,
+    /// Bytes of new blobs recorded when the checkpoints were created
+    pub bytes: u64,
+}
+
+/// How much storage checkpoints add beyond the rest of the history, shown by
+/// `ccg stats --dedupe`
+///
+/// Objects are the trees and blobs referenced by checkpoints; sizes are
+/// uncompressed object sizes, so packed storage is usually smaller still.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupeReport {
+    /// Checkpoints that are not part of any other branch, tag or remote
+    pub checkpoints: u64,
+    /// Object bytes of all those checkpoints added up, as if each were a full copy
+    pub logical_bytes: u64,
+    /// Objects also reachable from other branches, tags or remotes
+    pub shared_objects: u64,
+    pub shared_bytes: u64,
+    /// Objects only checkpoints reference, the storage they actually add
+    pub unique_objects: u64,
+    pub unique_bytes: u64,
+}
+
+/// Default `ccg.repack.window`: how many other objects each large file
+/// version is compared with when looking for a delta base
+pub const DEFAULT_REPACK_WINDOW: u64 = 250;
+
+/// Default `ccg.repack.depth`: the longest delta chain in the pack
+pub const DEFAULT_REPACK_DEPTH: u64 = 50;
+
+/// Default `ccg.repack.minSize`: files smaller than this are left to git gc (1 MiB)
+pub const DEFAULT_REPACK_MIN_SIZE: u64 = 1 << 20;
+
+/// Which objects a targeted repack collects and how hard it looks for deltas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepackSettings {
+    /// Delta window passed to `git pack-objects`
+    pub window: u64,
+    /// Maximum delta depth passed to `git pack-objects`
+    pub depth: u64,
+    /// Only file versions of at least this many bytes are repacked
+    pub min_size: u64,
+}
+
+impl Default for RepackSettings {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_REPACK_WINDOW,
+            depth: DEFAULT_REPACK_DEPTH,
+            min_size: DEFAULT_REPACK_MIN_SIZE,
+        }
+    }
+}
+
+/// Commits on each side of two possibly unrelated commits, like
+/// `git rev-list --left-right --count from...to`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AheadBehind {
+    /// Commits reachable from `to` but not from `from`
+    pub ahead: usize,
+    /// Commits reachable from `from` but not from `to`
+    pub behind: usize,
+}
+
+impl AheadBehind {
+    /// Whether `from` is not an ancestor of `to`, e.g. because `to` is on a
+    /// rewritten or unrelated history
+    pub fn diverged(&self) -> bool {
+        self.behind > 0
+    }
+}
+
+/// Outcome of a targeted repack of large checkpointed files, shown by
+/// `ccg repack` and, for the last one, by `ccg stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RepackReport {
+    /// When the repack ran, in seconds since the Unix epoch
+    pub time: i64,
+    /// Paths with at least two large loose versions
+    pub paths: u64,
+    /// Loose blobs moved into the new pack
+    pub objects: u64,
+    /// Bytes those blobs took as loose objects
+    pub loose_bytes: u64,
+    /// Bytes of the pack that replaced them
+    pub packed_bytes: u64,
+}
+
+impl RepackReport {
+    /// Share of the loose size the repack saved, from 0 to 100
+    pub fn saved_percent(&self) -> f64 {
+        if self.loose_bytes == 0 {
+            return 0.0;
+        }
+        self.loose_bytes.saturating_sub(self.packed_bytes) as f64 * 100.0 / self.loose_bytes as f64
+    }
+}
+
+/// Thresholds above which creating a checkpoint prints a cleanup advisory
+///
+/// A limit of 0 disables that check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageLimits {
+    /// Maximum number of checkpoints
+    pub max_checkpoints: u64,
+    /// Maximum bytes of objects attributable to checkpoints
+    pub max_bytes: u64,
+}
+
+impl Default for UsageLimits {
+    fn default() -> Self {
+        Self {
+            max_checkpoints: DEFAULT_MAX_CHECKPOINTS,
+            max_bytes: DEFAULT_MAX_SIZE,
+        }
+    }
+}
+
+/// Checkpoint usage that exceeded at least one of its limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageAdvisory {
+    /// Number of checkpoints
+    pub checkpoints: u64,
+    /// Bytes of objects attributable to checkpoints
+    pub bytes: u64,
+    /// The limits that were checked
+    pub limits: UsageLimits,
+}
+
+impl UsageAdvisory {
+    /// Whether the checkpoint count is over its limit
+    pub fn too_many_checkpoints(&self) -> bool {
+        self.limits.max_checkpoints > 0 && self.checkpoints > self.limits.max_checkpoints
+    }
+
+    /// Whether the attributable size is over its limit
+    pub fn too_large(&self) -> bool {
+        self.limits.max_bytes > 0 && self.bytes > self.limits.max_bytes
+    }
+
+    /// Whether any limit is exceeded
+    pub fn exceeded(&self) -> bool {
+        self.too_many_checkpoints() || self.too_large()
+    }
+}
+
+/// The latest checkpoint and what changed in the working directory since
+#[derive(Debug, Clone)]
+pub struct CheckpointStatus {
+    /// The latest checkpoint
+    pub latest: CheckpointEntry,
+    /// Files changed since it, untracked files included
+    pub changes: Vec<FileChangeInfo>,
+}
+
+/// A hunk a partial restore can bring back into the working directory
+///
+/// Oriented like a restore preview: `+` lines come back from the checkpoint
+/// and `-` lines are removed from the working directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreHunk {
+    /// Path to the file relative to repository root
+    pub path: String,
+    /// The `@@ -a,b +c,d @@` header line
+    pub header: String,
+    /// Hunk lines prefixed with their origin (`+`, `-` or a space)
+    pub lines: Vec<String>,
+}
+
+/// One version of a file in the checkpoint history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileVersion {
+    /// The checkpoint that introduced this version
+    pub checkpoint: CheckpointEntry,
+    /// File content, or None when the checkpoint deleted the file
+    pub content: Option<Vec<u8>>,
+}
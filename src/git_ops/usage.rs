@@ -0,0 +1,240 @@
+//! Checkpoint count and disk-usage tracking
+//!
+//! Next to the checkpoint ID index, `.git/ccg/sizes` records the bytes of new
+//! blobs each checkpoint introduced, one `<hash> <bytes>` pair per line, so
+//! the totals can be checked after every create without walking history.
+//! `.git/ccg/usage-advised` marks that the threshold advisory was already
+//! shown; it is cleared once usage drops back under the limits. The
+//! deduplication report walks the object graph instead, since it has to know
+//! which objects other branches share.
+
+use super::ccg_dir;
+use super::ids::CheckpointIds;
+use super::types::{CCG_BRANCH_NAME, DedupeReport, StorageUsage, UsageAdvisory, UsageLimits};
+use crate::error::{CheckpointError, Result as CcResult};
+use git2::{ObjectType, Oid, Repository};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// The checkpoint size index and advisory marker
+pub struct CheckpointUsage<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> CheckpointUsage<'a> {
+    /// Create a new CheckpointUsage instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    fn sizes_path(&self) -> PathBuf {
+        ccg_dir(self.repo).join("sizes")
+    }
+
+    fn marker_path(&self) -> PathBuf {
+        ccg_dir(self.repo).join("usage-advised")
+    }
+
+    /// Bytes of the blobs a commit added or changed relative to its parent
+    ///
+    /// # Arguments
+    /// * `hash` - Full hash of the commit
+    pub fn commit_bytes(&self, hash: &str) -> CcResult<u64> {
+        let commit = self.repo.find_commit(git2::Oid::from_str(hash)?)?;
+        let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+        let diff =
+            self.repo
+                .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        let mut bytes = 0;
+        for delta in diff.deltas() {
+            if delta.status() == git2::Delta::Deleted {
+                continue;
+            }
+            if let Ok(blob) = self.repo.find_blob(delta.new_file().id()) {
+                bytes += blob.size() as u64;
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Record the bytes a new checkpoint introduced
+    ///
+    /// # Arguments
+    /// * `hash` - Full hash of the checkpoint
+    pub fn record(&self, hash: &str) -> CcResult<()> {
+        let bytes = self.commit_bytes(hash)?;
+        let path = self.sizes_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{hash} {bytes}")?;
+        Ok(())
+    }
+
+    /// Total bytes recorded for all checkpoints
+    pub fn total_bytes(&self) -> CcResult<u64> {
+        let content = match fs::read_to_string(self.sizes_path()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| line.split_once(' ')?.1.trim().parse::<u64>().ok())
+            .sum())
+    }
+
+    /// Checkpoint count and total recorded bytes
+    pub fn totals(&self) -> CcResult<StorageUsage> {
+        Ok(StorageUsage {
+            checkpoints: CheckpointIds::new(self.repo).load()?.len() as u64,
+            bytes: self.total_bytes()?,
+        })
+    }
+
+    /// Compare the recorded totals with `limits`
+    ///
+    /// # Returns
+    /// An advisory the first time a limit is exceeded; None while under the
+    /// limits or when the advisory was already shown
+    pub fn check(&self, limits: &UsageLimits) -> CcResult<Option<UsageAdvisory>> {
+        let totals = self.totals()?;
+        let advisory = UsageAdvisory {
+            checkpoints: totals.checkpoints,
+            bytes: totals.bytes,
+            limits: *limits,
+        };
+        let marker = self.marker_path();
+        if !advisory.exceeded() {
+            if marker.exists() {
+                fs::remove_file(marker)?;
+            }
+            return Ok(None);
+        }
+        if marker.exists() {
+            return Ok(None);
+        }
+        fs::write(marker, "")?;
+        Ok(Some(advisory))
+    }
+
+    /// Partition the trees and blobs of checkpoints into those shared with
+    /// other branches, tags and remotes and those only checkpoints reference
+    ///
+    /// Walks every commit reachable from the other refs, then the checkpoints
+    /// that none of them contain; each tree is visited once.
+    ///
+    /// # Errors
+    /// Returns `CheckpointError::BranchNotFound` if the CCG branch does not exist
+    pub fn dedupe_report(&self) -> CcResult<DedupeReport> {
+        let ccg_ref = format!("refs/heads/{CCG_BRANCH_NAME}");
+        let tip = self
+            .repo
+            .find_reference(&ccg_ref)
+            .ok()
+            .and_then(|reference| reference.target())
+            .ok_or_else(|| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?;
+
+        let mut other_tips = Vec::new();
+        for reference in self.repo.references()? {
+            let reference = reference?;
+            let Some(name) = reference.name() else {
+                continue;
+            };
+            let tracked = ["refs/heads/", "refs/tags/", "refs/remotes/"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix));
+            if name != ccg_ref
+                && tracked
+                && let Ok(commit) = reference.peel_to_commit()
+            {
+                other_tips.push(commit.id());
+            }
+        }
+
+        // 其他分支可达的全部对象
+        let mut shared = HashSet::new();
+        if !other_tips.is_empty() {
+            let mut revwalk = self.repo.revwalk()?;
+            for oid in &other_tips {
+                revwalk.push(*oid)?;
+            }
+            for oid in revwalk {
+                let commit = self.repo.find_commit(oid?)?;
+                self.collect_objects(commit.tree_id(), &mut shared)?;
+            }
+        }
+
+        // 只属于 ccg 分支的检查点引用的对象
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(tip)?;
+        for oid in &other_tips {
+            revwalk.hide(*oid)?;
+        }
+        let odb = self.repo.odb()?;
+        let mut sizes = HashMap::new();
+        let mut referenced = HashSet::new();
+        let mut report = DedupeReport::default();
+        for oid in revwalk {
+            let commit = self.repo.find_commit(oid?)?;
+            report.checkpoints += 1;
+            report.logical_bytes += self.tree_bytes(commit.tree_id(), &odb, &mut sizes)?;
+            self.collect_objects(commit.tree_id(), &mut referenced)?;
+        }
+        for oid in referenced {
+            let (size, _) = odb.read_header(oid)?;
+            if shared.contains(&oid) {
+                report.shared_objects += 1;
+                report.shared_bytes += size as u64;
+            } else {
+                report.unique_objects += 1;
+                report.unique_bytes += size as u64;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Add a tree and everything below it to `objects`, skipping trees
+    /// already present
+    fn collect_objects(&self, tree: Oid, objects: &mut HashSet<Oid>) -> CcResult<()> {
+        if !objects.insert(tree) {
+            return Ok(());
+        }
+        for entry in self.repo.find_tree(tree)?.iter() {
+            match entry.kind() {
+                Some(ObjectType::Tree) => self.collect_objects(entry.id(), objects)?,
+                Some(ObjectType::Blob) => {
+                    objects.insert(entry.id());
+                }
+                // 子模块的提交不在本仓库中
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Bytes of a tree and everything below it, memoized per tree
+    fn tree_bytes(
+        &self,
+        tree: Oid,
+        odb: &git2::Odb,
+        sizes: &mut HashMap<Oid, u64>,
+    ) -> CcResult<u64> {
+        if let Some(bytes) = sizes.get(&tree) {
+            return Ok(*bytes);
+        }
+        let mut bytes = odb.read_header(tree)?.0 as u64;
+        for entry in self.repo.find_tree(tree)?.iter() {
+            match entry.kind() {
+                Some(ObjectType::Tree) => bytes += self.tree_bytes(entry.id(), odb, sizes)?,
+                Some(ObjectType::Blob) => bytes += odb.read_header(entry.id())?.0 as u64,
+                _ => {}
+            }
+        }
+        sizes.insert(tree, bytes);
+        Ok(bytes)
+    }
+}
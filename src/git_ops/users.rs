@@ -0,0 +1,176 @@
+//! Per-user checkpoint namespaces for shared repositories
+//!
+//! When several developers run agents against one repository, each keeps
+//! checkpointing on their own local CCG branch. `ccg push` publishes that
+//! branch as `refs/ccg/users/<name>/ccg` on a remote, and `ccg fetch` brings
+//! every published namespace back, so `ccg list --user <name>` can show a
+//! colleague's checkpoints without mixing them into your own timeline.
+//!
+//! Namespaces of colleagues that stop checkpointing are removed locally
+//! after `ccg.team.expireDays` days, or `ccg.user.<name>.expireDays` for one
+//! user; your own namespace is never expired.
+
+use super::fetch::run_git;
+use super::tasks::task_slug;
+use super::types::{CCG_BRANCH_NAME, USERS_REF_PREFIX, UserNamespace};
+use crate::config::CcgConfig;
+use crate::error::{CheckpointError, Result as CcResult};
+use git2::{Oid, Reference, Repository};
+
+/// Seconds in a day, for retention in days
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+/// The user namespaces of a repository
+pub struct UserNamespaces<'a> {
+    repo: &'a Repository,
+}
+
+impl<'a> UserNamespaces<'a> {
+    /// Create a new UserNamespaces instance
+    pub fn new(repo: &'a Repository) -> Self {
+        Self { repo }
+    }
+
+    /// Ref holding `user`'s checkpoints
+    pub fn ref_name(user: &str) -> String {
+        format!("{USERS_REF_PREFIX}{user}/{CCG_BRANCH_NAME}")
+    }
+
+    /// The namespace name of the current user: `ccg.team.user`, or the git
+    /// `user.name` in lowercase words joined by `-`
+    pub fn current(&self) -> String {
+        let config = CcgConfig::load(self.repo);
+        if let Some(user) = config.team_user {
+            return user;
+        }
+        let name = self
+            .repo
+            .config()
+            .ok()
+            .and_then(|c| c.get_string("user.name").ok())
+            .filter(|name| name.chars().any(char::is_alphanumeric));
+        name.map_or_else(|| "unknown".to_string(), |name| task_slug(&name))
+    }
+
+    /// All namespaces present locally, sorted by name
+    pub fn list(&self) -> CcResult<Vec<UserNamespace>> {
+        let mut users = Vec::new();
+        for reference in self.repo.references_glob(&format!("{USERS_REF_PREFIX}*"))? {
+            let reference = reference?;
+            let Some(name) = reference.name().and_then(user_of) else {
+                continue;
+            };
+            let commit = reference.peel_to_commit()?;
+            users.push(UserNamespace {
+                name: name.to_string(),
+                tip: commit.id().to_string(),
+                time: commit.time().seconds(),
+            });
+        }
+        users.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(users)
+    }
+
+    /// Newest checkpoint of `user`'s namespace
+    ///
+    /// # Errors
+    /// Returns CheckpointError::BranchNotFound, naming the known users, if
+    /// the namespace was never fetched
+    pub fn tip(&self, user: &str) -> CcResult<Oid> {
+        let name = Self::ref_name(user);
+        if let Ok(reference) = self.repo.find_reference(&name) {
+            return Ok(reference.peel_to_commit()?.id());
+        }
+        let known: Vec<String> = self.list()?.into_iter().map(|u| u.name).collect();
+        Err(CheckpointError::BranchNotFound(if known.is_empty() {
+            name
+        } else {
+            format!("{name} (known users: {})", known.join(", "))
+        }))
+    }
+
+    /// Publish the local CCG branch as the current user's namespace on `remote`
+    ///
+    /// The push is forced: restores rewind the CCG branch, and nobody else
+    /// writes to the namespace.
+    ///
+    /// # Returns
+    /// The namespace name
+    ///
+    /// # Errors
+    /// Returns CheckpointError::BranchNotFound if there is no CCG branch,
+    /// CheckpointError::InvalidArgument for an unknown remote or a user name
+    /// that is not a valid ref component, and
+    /// CheckpointError::GitOperationFailed when git fails
+    pub fn publish(&self, remote: &str) -> CcResult<String> {
+        if self.repo.find_remote(remote).is_err() {
+            return Err(CheckpointError::InvalidArgument(format!(
+                "unknown remote: {remote}"
+            )));
+        }
+        let user = self.current();
+        let name = Self::ref_name(&user);
+        if !Reference::is_valid_name(&name) {
+            return Err(CheckpointError::InvalidArgument(format!(
+                "invalid user name for a namespace: {user}"
+            )));
+        }
+        let tip = self
+            .repo
+            .find_branch(CCG_BRANCH_NAME, git2::BranchType::Local)
+            .map_err(|_| CheckpointError::BranchNotFound(CCG_BRANCH_NAME.to_string()))?
+            .get()
+            .peel_to_commit()?
+            .id();
+
+        run_git(
+            self.repo,
+            &[
+                "push",
+                "--force",
+                "--quiet",
+                remote,
+                &format!("refs/heads/{CCG_BRANCH_NAME}:{name}"),
+            ],
+        )?;
+        // 本地副本与远程保持一致，`list --user` 也能查看自己发布的检查点
+        self.repo
+            .reference(&name, tip, true, "ccg push: publish checkpoints")?;
+        Ok(user)
+    }
+
+    /// Delete the namespaces whose newest checkpoint is older than their
+    /// retention policy
+    ///
+    /// # Arguments
+    /// * `now` - Current time, seconds since the Unix epoch
+    ///
+    /// # Returns
+    /// Names of the removed namespaces
+    pub fn expire(&self, now: i64) -> CcResult<Vec<String>> {
+        let config = CcgConfig::load(self.repo);
+        let current = self.current();
+        let mut expired = Vec::new();
+        for user in self.list()? {
+            let Some(days) = config.expire_days_for(&user.name) else {
+                continue;
+            };
+            let days = i64::try_from(days).unwrap_or(i64::MAX);
+            if user.name == current || now - user.time <= days.saturating_mul(DAY_SECONDS) {
+                continue;
+            }
+            self.repo
+                .find_reference(&Self::ref_name(&user.name))?
+                .delete()?;
+            expired.push(user.name);
+        }
+        Ok(expired)
+    }
+}
+
+/// The user a namespace ref belongs to
+fn user_of(ref_name: &str) -> Option<&str> {
+    ref_name
+        .strip_prefix(USERS_REF_PREFIX)?
+        .strip_suffix(&format!("/{CCG_BRANCH_NAME}"))
+}
@@ -15,6 +15,20 @@ lazy_static! {
     };
 }
 
+/// 未配置 `ccg.messageLang` 时提交信息使用的语言
+pub const DEFAULT_MESSAGE_LOCALE: &str = "en";
+
+/// 将配置的语言（如 `zh`、`zh_CN.UTF-8`）规范化为可用的 locale
+///
+/// 提交信息会长期保存在历史中，因此不跟随终端的界面语言；
+/// 未配置或不支持的语言使用 `DEFAULT_MESSAGE_LOCALE`。
+pub fn message_locale(lang: Option<&str>) -> String {
+    lang.and_then(|l| l.split(['_', '-', '.']).next())
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| rust_i18n::available_locales!().contains(&l.as_str()))
+        .unwrap_or_else(|| DEFAULT_MESSAGE_LOCALE.to_string())
+}
+
 /// 初始化国际化设置
 ///
 /// 这个函数会根据检测到的操作系统语言来设置当前的 locale。
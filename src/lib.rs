@@ -1,8 +1,12 @@
 pub mod commands;
+pub mod config;
 pub mod error;
 pub mod git_ops;
 pub mod i18n;
 pub mod services;
+pub mod ui;
+
+rust_i18n::i18n!("locales");
 
 pub use commands::CommandContext;
 pub use error::{CheckpointError, Result};
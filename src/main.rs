@@ -1,16 +1,39 @@
-use anyhow::Result;
 use ccg::{
-    CommandContext,
+    CheckpointError, CommandContext,
     commands::{
-        Command as CommandTrait, CreateCommand, DiffCommand, InitCommand, ListCommand,
-        RestoreCommand, ShowCommand,
-        traits::{CreateArgs, DiffArgs, InitArgs, ListArgs, RestoreArgs, ShowArgs},
+        AliasCommand, Command as CommandTrait, CreateCommand, DiffCommand, DoctorCommand,
+        DriftCommand, EvolutionCommand, ExportMetadataCommand, FetchCommand, FlushCommand,
+        HookCommand, ImpactCommand, InitCommand, ListCommand, ListenCommand, MigrateCommand,
+        MultiCommand, PluginsCommand, PromoteCommand, PruneCommand, PushCommand, RebaseCommand,
+        RepackCommand, ReportCommand, RestoreCommand, RevertCommand, RewordCommand, RunCommand,
+        ScheduleCommand, SearchCommand, ShowCommand, SnapshotCommand, SplitCommand, StatsCommand,
+        StatusCommand, TaskCommand,
+        alias::expand_aliases,
+        init::confirm_auto_init,
+        traits::{
+            AliasAction, AliasArgs, CreateArgs, DiffArgs, DoctorArgs, DriftArgs, EvolutionArgs,
+            ExportMetadataArgs, FetchArgs, FlushArgs, HookAction, HookArgs, ImpactArgs, InitArgs,
+            ListArgs, ListenArgs, MigrateArgs, MultiArgs, MultiOperation, PluginsAction,
+            PluginsArgs, PromoteArgs, PruneArgs, PushArgs, RebaseArgs, RepackArgs, ReportArgs,
+            RestoreArgs, RevertArgs, RewordArgs, RunArgs, ScheduleAction, ScheduleArgs, SearchArgs,
+            ShowArgs, SnapshotAction, SnapshotArgs, SplitArgs, StatsArgs, StatusArgs, TaskAction,
+            TaskArgs,
+        },
     },
+    config::{self, CcgConfig, ExcludeProfile},
+    error::CliError,
+    git_ops::{CheckpointLabel, DiffSettings, GitOperations, ListColumn, ObjectFormat},
     i18n::setup_i18n,
+    services::{MetadataFormat, ReportFormat, integration::InstallTarget, schedule::Scheduler},
+    ui::{
+        Icon, IconMode, prompt::set_prompt_level, report, set_accessible, set_icon_mode, set_width,
+    },
 };
-use clap::{Arg, Command as ClapCommand};
+use clap::{Arg, ArgMatches, Command as ClapCommand};
 use git2::Repository;
 use rust_i18n::t;
+use std::ffi::OsString;
+use std::path::Path;
 use std::process;
 
 rust_i18n::i18n!("locales");
@@ -22,7 +45,79 @@ fn build_cli() -> ClapCommand {
         .long_about(t!("app_long_about"))
         .subcommand_required(true)
         .arg_required_else_help(true)
-        .subcommand(ClapCommand::new("init").about(t!("init_about")))
+        .allow_external_subcommands(true)
+        .arg(
+            Arg::new("ascii")
+                .long("ascii")
+                .global(true)
+                .action(clap::ArgAction::SetTrue)
+                .help(t!("ascii_help")),
+        )
+        .arg(
+            Arg::new("accessible")
+                .long("accessible")
+                .global(true)
+                .action(clap::ArgAction::SetTrue)
+                .help(t!("accessible_help")),
+        )
+        .arg(
+            Arg::new("repo")
+                .short('C')
+                .long("repo")
+                .global(true)
+                .value_name("PATH")
+                .help(t!("repo_help")),
+        )
+        .arg(
+            Arg::new("read_only")
+                .long("read-only")
+                .global(true)
+                .action(clap::ArgAction::SetTrue)
+                .help(t!("read_only_help")),
+        )
+        .arg(
+            Arg::new("width")
+                .long("width")
+                .global(true)
+                .value_parser(clap::value_parser!(usize))
+                .help(t!("width_help")),
+        )
+        .subcommand(
+            ClapCommand::new("init")
+                .about(t!("init_about"))
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .value_name("BRANCH")
+                        .help(t!("init_from_help")),
+                )
+                .arg(
+                    Arg::new("orphan")
+                        .long("orphan")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with("from")
+                        .help(t!("init_orphan_help")),
+                )
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .value_name("PROFILE")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append)
+                        .value_parser(ExcludeProfile::ALL.map(|profile| profile.name()))
+                        .help(t!("init_profile_help")),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("migrate").about(t!("migrate_about")).arg(
+                Arg::new("rename_branch")
+                    .long("rename-branch")
+                    .num_args(2)
+                    .value_names(["OLD", "NEW"])
+                    .required(true)
+                    .help(t!("migrate_rename_branch_help")),
+            ),
+        )
         .subcommand(
             ClapCommand::new("create")
                 .about(t!("create_about"))
@@ -32,23 +127,209 @@ fn build_cli() -> ClapCommand {
                         .long("tool-input-json")
                         .help(t!("create_tool_input_json_help"))
                         .long_help(t!("create_tool_input_json_long_help")),
+                )
+                .arg(
+                    Arg::new("allow_empty")
+                        .long("allow-empty")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("create_allow_empty_help")),
+                )
+                .arg(
+                    Arg::new("auto_init")
+                        .long("auto-init")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("create_auto_init_help")),
+                )
+                .arg(
+                    Arg::new("verify")
+                        .long("checkpoint-if-tests-pass")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("create_verify_help")),
+                )
+                .arg(
+                    Arg::new("require_pass")
+                        .long("require-pass")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("create_require_pass_help")),
+                )
+                .arg(
+                    Arg::new("bypass_guard")
+                        .long("bypass-guard")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("create_bypass_guard_help")),
+                )
+                .arg(
+                    Arg::new("porcelain")
+                        .long("porcelain")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("create_porcelain_help")),
                 ),
         )
         .subcommand(
-            ClapCommand::new("list").about(t!("list_about")).arg(
-                Arg::new("number")
-                    .short('n')
-                    .long("number")
-                    .help(t!("list_number_help"))
-                    .default_value("10"),
-            ),
+            ClapCommand::new("list")
+                .about(t!("list_about"))
+                .arg(
+                    Arg::new("number")
+                        .short('n')
+                        .long("number")
+                        .help(t!("list_number_help"))
+                        .default_value("10"),
+                )
+                .arg(
+                    Arg::new("stat")
+                        .long("stat")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("list_stat_help")),
+                )
+                .arg(
+                    Arg::new("with_restores")
+                        .long("with-restores")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("list_with_restores_help")),
+                )
+                .arg(
+                    Arg::new("columns")
+                        .long("columns")
+                        .value_name("COLUMNS")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append)
+                        .value_parser(ListColumn::ALL.map(|column| column.name()))
+                        .help(t!("list_columns_help")),
+                )
+                .arg(
+                    Arg::new("label")
+                        .long("label")
+                        .value_name("LABEL")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append)
+                        .value_parser(CheckpointLabel::ALL.map(|label| label.name()))
+                        .help(t!("list_label_help")),
+                )
+                .arg(
+                    Arg::new("only_passing")
+                        .long("only-passing")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("list_only_passing_help")),
+                )
+                .arg(
+                    Arg::new("skip_format_only")
+                        .long("skip-format-only")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("list_skip_format_only_help")),
+                )
+                .arg(
+                    Arg::new("task")
+                        .long("task")
+                        .value_name("ID")
+                        .help(t!("list_task_help")),
+                )
+                .arg(
+                    Arg::new("user")
+                        .long("user")
+                        .value_name("NAME")
+                        .conflicts_with("with_restores")
+                        .help(t!("list_user_help")),
+                ),
         )
         .subcommand(
-            ClapCommand::new("restore").about(t!("restore_about")).arg(
-                Arg::new("hash")
-                    .help(t!("restore_hash_help"))
-                    .required(true),
-            ),
+            ClapCommand::new("restore")
+                .about(t!("restore_about"))
+                .arg(
+                    Arg::new("hash")
+                        .help(t!("restore_hash_help"))
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("preview")
+                        .long("preview")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("restore_preview_help")),
+                )
+                .arg(
+                    Arg::new("force")
+                        .short('f')
+                        .long("force")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("restore_force_help")),
+                )
+                .arg(
+                    Arg::new("clean")
+                        .long("clean")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("restore_clean_help")),
+                )
+                .arg(
+                    Arg::new("continue-on-error")
+                        .long("continue-on-error")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("restore_continue_on_error_help")),
+                )
+                .arg(
+                    Arg::new("patch")
+                        .short('p')
+                        .long("patch")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with_all(["preview", "clean", "continue-on-error"])
+                        .help(t!("restore_patch_help")),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .value_name("DIR")
+                        .conflicts_with_all([
+                            "preview",
+                            "force",
+                            "clean",
+                            "continue-on-error",
+                            "patch",
+                        ])
+                        .help(t!("restore_to_help")),
+                )
+                .arg(
+                    Arg::new("bypass_guard")
+                        .long("bypass-guard")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("restore_bypass_guard_help")),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("run")
+                .about(t!("run_about"))
+                .arg(Arg::new("hash").help(t!("run_hash_help")).required(true))
+                .arg(
+                    Arg::new("keep")
+                        .long("keep")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("run_keep_help")),
+                )
+                .arg(
+                    Arg::new("command")
+                        .help(t!("run_command_help"))
+                        .required(true)
+                        .num_args(1..)
+                        .last(true),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("impact")
+                .about(t!("impact_about"))
+                .arg(Arg::new("hash").help(t!("impact_hash_help")).required(true)),
+        )
+        .subcommand(
+            ClapCommand::new("revert")
+                .about(t!("revert_about"))
+                .arg(Arg::new("hash").help(t!("revert_hash_help")).required(true)),
+        )
+        .subcommand(
+            ClapCommand::new("reword")
+                .about(t!("reword_about"))
+                .arg(Arg::new("hash").help(t!("reword_hash_help")).required(true))
+                .arg(
+                    Arg::new("message")
+                        .short('m')
+                        .long("message")
+                        .help(t!("reword_message_help")),
+                ),
         )
         .subcommand(
             ClapCommand::new("show")
@@ -60,83 +341,1092 @@ fn build_cli() -> ClapCommand {
                         .long("diff")
                         .action(clap::ArgAction::SetTrue)
                         .help(t!("show_diff_help")),
+                )
+                .arg(
+                    Arg::new("full")
+                        .long("full")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("show_full_help")),
+                )
+                .arg(
+                    Arg::new("files")
+                        .long("files")
+                        .value_name("GLOB")
+                        .action(clap::ArgAction::Append)
+                        .help(t!("show_files_help")),
+                )
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .value_name("PATH")
+                        .conflicts_with_all(["diff", "full", "files"])
+                        .help(t!("show_file_help")),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("DEST")
+                        .requires("file")
+                        .help(t!("show_output_help")),
+                )
+                .arg(
+                    Arg::new("highlight")
+                        .long("highlight")
+                        .action(clap::ArgAction::SetTrue)
+                        .requires("file")
+                        .conflicts_with("output")
+                        .help(t!("show_highlight_help")),
+                )
+                .args(diff_setting_args()),
+        )
+        .subcommand(
+            ClapCommand::new("split")
+                .about(t!("split_about"))
+                .arg(Arg::new("hash").help(t!("split_hash_help")).required(true)),
+        )
+        .subcommand(
+            ClapCommand::new("rebase")
+                .about(t!("rebase_about"))
+                .arg(
+                    Arg::new("onto")
+                        .long("onto")
+                        .value_name("BRANCH")
+                        .help(t!("rebase_onto_help")),
+                )
+                .arg(
+                    Arg::new("replay")
+                        .long("replay")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("rebase_replay_help")),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("repack")
+                .about(t!("repack_about"))
+                .arg(
+                    Arg::new("window")
+                        .long("window")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u64))
+                        .help(t!("repack_window_help")),
+                )
+                .arg(
+                    Arg::new("depth")
+                        .long("depth")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u64))
+                        .help(t!("repack_depth_help")),
+                )
+                .arg(
+                    Arg::new("min_size")
+                        .long("min-size")
+                        .value_name("BYTES")
+                        .value_parser(clap::value_parser!(u64))
+                        .help(t!("repack_min_size_help")),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("prune")
+                .about(t!("prune_about"))
+                .arg(
+                    Arg::new("keep")
+                        .long("keep")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .help(t!("prune_keep_help")),
+                )
+                .arg(
+                    Arg::new("before")
+                        .long("before")
+                        .value_name("TIME")
+                        .help(t!("prune_before_help")),
+                )
+                .arg(
+                    Arg::new("strategy")
+                        .long("strategy")
+                        .value_name("STRATEGY")
+                        .help(t!("prune_strategy_help")),
+                )
+                .arg(
+                    Arg::new("interactive")
+                        .short('i')
+                        .long("interactive")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("prune_interactive_help")),
                 ),
         )
         .subcommand(
             ClapCommand::new("diff")
                 .about(t!("diff_about"))
+                .arg(Arg::new("hash_a").help(t!("diff_hash_a_help")))
+                .arg(Arg::new("hash_b").help(t!("diff_hash_b_help")))
+                .arg(
+                    Arg::new("task")
+                        .long("task")
+                        .value_name("ID")
+                        .conflicts_with_all(["hash_a", "hash_b"])
+                        .help(t!("diff_task_help")),
+                )
+                .arg(
+                    Arg::new("dir")
+                        .long("dir")
+                        .value_name("PATH")
+                        .conflicts_with_all(["hash_b", "task"])
+                        .help(t!("diff_dir_help")),
+                )
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .value_name("TIME")
+                        .conflicts_with_all(["hash_a", "hash_b", "task", "dir"])
+                        .help(t!("diff_since_help")),
+                )
+                .args(diff_setting_args()),
+        )
+        .subcommand(
+            ClapCommand::new("task")
+                .about(t!("task_about"))
+                .subcommand_required(true)
+                .subcommand(
+                    ClapCommand::new("start")
+                        .about(t!("task_start_about"))
+                        .arg(Arg::new("name").help(t!("task_name_help")).required(true)),
+                )
+                .subcommand(ClapCommand::new("end").about(t!("task_end_about"))),
+        )
+        .subcommand(
+            ClapCommand::new("listen")
+                .about(t!("listen_about"))
+                .arg(
+                    Arg::new("socket")
+                        .long("socket")
+                        .value_name("PATH")
+                        .required(true)
+                        .help(t!("listen_socket_help")),
+                )
+                .arg(
+                    Arg::new("debounce")
+                        .long("debounce")
+                        .value_name("MS")
+                        .value_parser(clap::value_parser!(u64))
+                        .help(t!("listen_debounce_help")),
+                )
+                .arg(
+                    Arg::new("idle")
+                        .long("idle")
+                        .value_name("MINUTES")
+                        .value_parser(clap::value_parser!(u64))
+                        .help(t!("listen_idle_help")),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("export-metadata")
+                .about(t!("export_metadata_about"))
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["csv", "jsonl"])
+                        .default_value("csv")
+                        .help(t!("export_metadata_format_help")),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help(t!("export_metadata_output_help")),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("search").about(t!("search_about")).arg(
+                Arg::new("query")
+                    .value_name("QUERY")
+                    .num_args(1..)
+                    .required(true)
+                    .help(t!("search_query_help")),
+            ),
+        )
+        .subcommand(
+            ClapCommand::new("report")
+                .about(t!("report_about"))
+                .arg(
+                    Arg::new("session")
+                        .long("session")
+                        .value_name("ID")
+                        .help(t!("report_session_help")),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["md", "html"])
+                        .default_value("md")
+                        .help(t!("report_format_help")),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help(t!("report_output_help")),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("snapshot")
+                .about(t!("snapshot_about"))
+                .subcommand_required(true)
+                .subcommand(
+                    ClapCommand::new("push")
+                        .about(t!("snapshot_push_about"))
+                        .arg(
+                            Arg::new("message")
+                                .short('m')
+                                .long("message")
+                                .help(t!("snapshot_message_help")),
+                        ),
+                )
+                .subcommand(
+                    ClapCommand::new("pop").about(t!("snapshot_pop_about")).arg(
+                        Arg::new("number")
+                            .value_parser(clap::value_parser!(usize))
+                            .help(t!("snapshot_number_help")),
+                    ),
+                )
+                .subcommand(ClapCommand::new("list").about(t!("snapshot_list_about"))),
+        )
+        .subcommand(
+            ClapCommand::new("promote")
+                .about(t!("promote_about"))
                 .arg(
-                    Arg::new("hash_a")
-                        .help(t!("diff_hash_a_help"))
+                    Arg::new("task")
+                        .long("task")
+                        .value_name("ID")
+                        .required(true)
+                        .help(t!("promote_task_help")),
+                )
+                .arg(
+                    Arg::new("message")
+                        .short('m')
+                        .long("message")
+                        .help(t!("promote_message_help")),
+                )
+                .arg(
+                    Arg::new("skip_format_only")
+                        .long("skip-format-only")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("promote_skip_format_only_help")),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("evolution")
+                .about(t!("evolution_about"))
+                .arg(
+                    Arg::new("file")
+                        .help(t!("evolution_file_help"))
                         .required(true),
                 )
-                .arg(Arg::new("hash_b").help(t!("diff_hash_b_help"))),
+                .arg(
+                    Arg::new("full")
+                        .long("full")
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("evolution_full_help")),
+                )
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .value_name("TIME")
+                        .help(t!("evolution_since_help")),
+                )
+                .arg(
+                    Arg::new("until")
+                        .long("until")
+                        .value_name("TIME")
+                        .help(t!("evolution_until_help")),
+                ),
         )
+        .subcommand(ClapCommand::new("status").about(t!("status_about")))
+        .subcommand(ClapCommand::new("drift").about(t!("drift_about")))
+        .subcommand(
+            ClapCommand::new("stats").about(t!("stats_about")).arg(
+                Arg::new("dedupe")
+                    .long("dedupe")
+                    .action(clap::ArgAction::SetTrue)
+                    .help(t!("stats_dedupe_help")),
+            ),
+        )
+        .subcommand(
+            ClapCommand::new("fetch")
+                .about(t!("fetch_about"))
+                .arg(
+                    Arg::new("remote")
+                        .help(t!("fetch_remote_help"))
+                        .default_value("origin")
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("depth")
+                        .long("depth")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u32))
+                        .help(t!("fetch_depth_help")),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("push").about(t!("push_about")).arg(
+                Arg::new("remote")
+                    .help(t!("push_remote_help"))
+                    .default_value("origin")
+                    .index(1),
+            ),
+        )
+        .subcommand(ClapCommand::new("flush").about(t!("flush_about")))
+        .subcommand(ClapCommand::new("doctor").about(t!("doctor_about")))
+        .subcommand(
+            ClapCommand::new("hook")
+                .about(t!("hook_about"))
+                .subcommand_required(true)
+                .subcommand(
+                    ClapCommand::new("install")
+                        .about(t!("hook_install_about"))
+                        .arg(
+                            Arg::new("target")
+                                .long("target")
+                                .value_parser(["claude", "git", "all"])
+                                .default_value("claude")
+                                .help(t!("hook_install_target_help")),
+                        )
+                        .arg(
+                            Arg::new("print")
+                                .long("print")
+                                .action(clap::ArgAction::SetTrue)
+                                .help(t!("hook_install_print_help")),
+                        ),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("schedule")
+                .about(t!("schedule_about"))
+                .subcommand_required(true)
+                .subcommand(
+                    ClapCommand::new("install")
+                        .about(t!("schedule_install_about"))
+                        .arg(
+                            Arg::new("every")
+                                .long("every")
+                                .value_name("INTERVAL")
+                                .required(true)
+                                .help(t!("schedule_every_help")),
+                        )
+                        .arg(
+                            Arg::new("scheduler")
+                                .long("scheduler")
+                                .value_parser(Scheduler::ALL.map(Scheduler::name))
+                                .help(t!("schedule_scheduler_help")),
+                        )
+                        .arg(
+                            Arg::new("print")
+                                .long("print")
+                                .action(clap::ArgAction::SetTrue)
+                                .help(t!("schedule_print_help")),
+                        ),
+                )
+                .subcommand(ClapCommand::new("status").about(t!("schedule_status_about")))
+                .subcommand(ClapCommand::new("remove").about(t!("schedule_remove_about"))),
+        )
+        .subcommand(
+            ClapCommand::new("plugins")
+                .about(t!("plugins_about"))
+                .subcommand_required(true)
+                .subcommand(ClapCommand::new("list").about(t!("plugins_list_about"))),
+        )
+        .subcommand(
+            ClapCommand::new("multi")
+                .about(t!("multi_about"))
+                .subcommand_required(true)
+                .arg(
+                    Arg::new("roots")
+                        .long("roots")
+                        .value_name("PATHS")
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append)
+                        .global(true)
+                        .help(t!("multi_roots_help")),
+                )
+                .subcommand(
+                    ClapCommand::new("list").about(t!("list_about")).arg(
+                        Arg::new("number")
+                            .short('n')
+                            .long("number")
+                            .value_parser(clap::value_parser!(usize))
+                            .default_value("10")
+                            .help(t!("list_number_help")),
+                    ),
+                )
+                .subcommand(
+                    ClapCommand::new("create")
+                        .about(t!("create_about"))
+                        .arg(Arg::new("message").help(t!("create_message_help"))),
+                )
+                .subcommand(ClapCommand::new("status").about(t!("status_about"))),
+        )
+        .subcommand(
+            ClapCommand::new("alias")
+                .about(t!("alias_about"))
+                .subcommand_required(true)
+                .arg(
+                    Arg::new("global")
+                        .long("global")
+                        .global(true)
+                        .action(clap::ArgAction::SetTrue)
+                        .help(t!("alias_global_help")),
+                )
+                .subcommand(ClapCommand::new("list").about(t!("alias_list_about")))
+                .subcommand(
+                    ClapCommand::new("set")
+                        .about(t!("alias_set_about"))
+                        .arg(Arg::new("name").help(t!("alias_name_help")).required(true))
+                        .arg(
+                            Arg::new("expansion")
+                                .help(t!("alias_expansion_help"))
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    ClapCommand::new("unset")
+                        .about(t!("alias_unset_about"))
+                        .arg(Arg::new("name").help(t!("alias_name_help")).required(true)),
+                ),
+        )
+}
+
+/// `show` 和 `diff` 共用的差异选项
+fn diff_setting_args() -> [Arg; 5] {
+    [
+        Arg::new("diff_algorithm")
+            .long("diff-algorithm")
+            .value_name("ALGORITHM")
+            .value_parser(["myers", "default", "minimal", "patience", "histogram"])
+            .help(t!("diff_algorithm_help")),
+        Arg::new("inter_hunk_context")
+            .long("inter-hunk-context")
+            .value_name("LINES")
+            .value_parser(clap::value_parser!(u32))
+            .help(t!("diff_inter_hunk_context_help")),
+        Arg::new("find_renames")
+            .short('M')
+            .long("find-renames")
+            .value_name("PERCENT")
+            // 与 git 一样，不带值时相似度阈值为 50%
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_missing_value("50")
+            .value_parser(clap::value_parser!(u16).range(0..=100))
+            .help(t!("diff_find_renames_help")),
+        Arg::new("color_moved")
+            .long("color-moved")
+            .action(clap::ArgAction::SetTrue)
+            .overrides_with("no_color_moved")
+            .help(t!("diff_color_moved_help")),
+        Arg::new("no_color_moved")
+            .long("no-color-moved")
+            .action(clap::ArgAction::SetTrue)
+            .overrides_with("color_moved")
+            .help(t!("diff_no_color_moved_help")),
+    ]
+}
+
+/// 从命令行参数读取差异选项，未指定的选项使用配置
+fn diff_settings(matches: &ArgMatches) -> DiffSettings {
+    DiffSettings {
+        algorithm: matches
+            .get_one::<String>("diff_algorithm")
+            .and_then(|algorithm| algorithm.parse().ok()),
+        inter_hunk_context: matches.get_one::<u32>("inter_hunk_context").copied(),
+        rename_threshold: matches.get_one::<u16>("find_renames").copied(),
+        color_moved: if matches.get_flag("color_moved") {
+            Some(true)
+        } else if matches.get_flag("no_color_moved") {
+            Some(false)
+        } else {
+            None
+        },
+    }
+}
+
+/// 内置子命令，其余名称按插件处理
+fn is_builtin(name: &str) -> bool {
+    build_cli()
+        .get_subcommands()
+        .any(|sub| sub.get_name() == name)
 }
 
-fn run() -> Result<()> {
-    let matches = build_cli().get_matches();
-    let subcommand_name = matches.subcommand_name().unwrap_or("");
+/// 仓库的配置，不在仓库中时使用全局配置
+fn load_config(repo_path: &str) -> Option<git2::Config> {
+    Repository::open(repo_path)
+        .and_then(|repo| repo.config())
+        .or_else(|_| git2::Config::open_default())
+        .ok()
+}
+
+/// 根据 `--ascii`、`--accessible`、`--width` 参数和 `ccg.ui.icons`、`ccg.ui.accessible`、
+/// `ccg.prompt` 配置设置输出样式和确认级别
+fn setup_ui(matches: &ArgMatches, repo_path: &str) {
+    set_width(matches.get_one::<usize>("width").copied());
+    let config = load_config(repo_path)
+        .map(|config| CcgConfig::from_config(&config))
+        .unwrap_or_default();
+    let mode = if matches.get_flag("ascii") {
+        Some(IconMode::Ascii)
+    } else {
+        config.icons
+    };
+    if let Some(mode) = mode {
+        set_icon_mode(mode);
+    }
+    set_accessible(matches.get_flag("accessible") || config.accessible);
+    set_prompt_level(config.prompt.unwrap_or_default());
+}
+
+fn run() -> Result<(), CliError> {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let mut matches = build_cli().get_matches_from(&args);
+
+    // `-C/--repo` 指定要操作的仓库，与 `git -C` 一样默认为当前目录
+    let repo_arg = matches.get_one::<String>("repo").cloned();
+    if let Some(path) = &repo_arg
+        && !Path::new(path).is_dir()
+    {
+        return Err(
+            CheckpointError::InvalidArgument(t!("repo_not_a_directory", path = path)).into(),
+        );
+    }
+    let repo_path = repo_arg.as_deref().unwrap_or(".");
+
+    // 未知的子命令可能是 `ccg.alias.*` 中配置的别名，展开后重新解析
+    if let Some(name) = matches.subcommand_name()
+        && !is_builtin(name)
+    {
+        let aliases = load_config(repo_path)
+            .map(|config| config::aliases(&config))
+            .unwrap_or_default();
+        if aliases.contains_key(name) {
+            matches = build_cli().get_matches_from(expand_aliases(args, &aliases, is_builtin)?);
+        }
+    }
+    setup_ui(&matches, repo_path);
+
+    // Check if the target directory is a git repository
+    let is_repo = Repository::open(repo_path).is_ok();
 
-    // Check if the current directory is a git repository
-    let is_repo = Repository::open(".").is_ok();
+    // `--read-only` 只能开启只读模式，`ccg.readOnly` 由服务层自行读取
+    let read_only = matches.get_flag("read_only");
+
+    // 插件和插件列表不需要初始化仓库，在仓库检查之前处理
+    if let Some(("plugins", _)) = matches.subcommand() {
+        let cmd = PluginsCommand::new(None);
+        let args = PluginsArgs {
+            action: PluginsAction::List,
+        };
+        CommandTrait::execute(&cmd, args)?;
+        return Ok(());
+    }
+    if let Some(("multi", sub_matches)) = matches.subcommand() {
+        let cmd = MultiCommand::new(repo_arg.clone()).with_read_only(read_only);
+        let operation = match sub_matches.subcommand() {
+            Some(("list", list_matches)) => MultiOperation::List {
+                number: *list_matches.get_one::<usize>("number").unwrap(),
+            },
+            Some(("create", create_matches)) => MultiOperation::Create {
+                message: create_matches.get_one::<String>("message").cloned(),
+            },
+            _ => MultiOperation::Status,
+        };
+        let args = MultiArgs {
+            roots: sub_matches
+                .get_many::<String>("roots")
+                .map(|roots| roots.cloned().collect())
+                .unwrap_or_default(),
+            operation,
+        };
+        CommandTrait::validate_args(&cmd, &args)?;
+        let report = CommandTrait::execute(&cmd, args)?;
+        if !report.failed.is_empty() {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(("alias", sub_matches)) = matches.subcommand() {
+        let builtins = build_cli()
+            .get_subcommands()
+            .map(|sub| sub.get_name().to_string())
+            .collect();
+        let cmd = AliasCommand::new(builtins, repo_arg.clone());
+        let action = match sub_matches.subcommand() {
+            Some(("set", set_matches)) => AliasAction::Set {
+                name: set_matches.get_one::<String>("name").unwrap().clone(),
+                expansion: set_matches.get_one::<String>("expansion").unwrap().clone(),
+            },
+            Some(("unset", unset_matches)) => AliasAction::Unset {
+                name: unset_matches.get_one::<String>("name").unwrap().clone(),
+            },
+            _ => AliasAction::List,
+        };
+        let args = AliasArgs {
+            action,
+            global: sub_matches.get_flag("global"),
+        };
+        CommandTrait::execute(&cmd, args)?;
+        return Ok(());
+    }
+    if let Some((name, sub_matches)) = matches.subcommand()
+        && !is_builtin(name)
+    {
+        let context = if is_repo {
+            let mut context = CommandContext::new_with_path(Some(repo_path))?;
+            if read_only {
+                context.checkpoint_service.set_read_only(true);
+            }
+            Some(context)
+        } else {
+            None
+        };
+        let plugin_args: Vec<OsString> = sub_matches
+            .get_many::<OsString>("")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default();
+        let status = PluginsCommand::new(context).run_plugin(name, &plugin_args)?;
+        if !status.success() {
+            process::exit(status.code().unwrap_or(1));
+        }
+        return Ok(());
+    }
 
     if !is_repo {
-        match subcommand_name {
-            "init" | "create" => {
-                // These commands can proceed as they handle repository initialization
+        // libgit2 打不开 SHA-256 仓库，不能把它当作普通目录提示初始化
+        ObjectFormat::check_supported(Path::new(repo_path))?;
+        if read_only && let Some((name @ ("init" | "create"), _)) = matches.subcommand() {
+            return Err(CheckpointError::ReadOnly(name.to_string()).into());
+        }
+        match matches.subcommand() {
+            // 显式的 init 总是初始化仓库
+            Some(("init", _)) => {
+                GitOperations::init_at(repo_path)?;
+            }
+            // create 按 --auto-init 和 ccg.autoInit 配置决定，默认在终端中询问
+            Some(("create", sub_matches)) => {
+                let mode = load_config(repo_path)
+                    .and_then(|config| CcgConfig::from_config(&config).auto_init)
+                    .unwrap_or_default();
+                let forced = sub_matches.get_flag("auto_init");
+                if !confirm_auto_init(repo_path, forced, mode)? {
+                    println!("{}{}", Icon::Hint.prefix(), t!("auto_init_declined"));
+                    return Ok(());
+                }
+                GitOperations::init_at(repo_path)?;
             }
             _ => {
-                // For other commands, print a message and exit
-                println!("{}", t!("repo_not_initialized_tip"));
+                // 只读命令和其他命令从不初始化仓库
+                println!("{}{}", Icon::Hint.prefix(), t!("repo_not_initialized_tip"));
                 return Ok(());
             }
         }
     }
 
-    let context = CommandContext::new()?;
+    let mut context = CommandContext::new_with_path(Some(repo_path))?;
+    if read_only {
+        context.checkpoint_service.set_read_only(true);
+    }
 
     match matches.subcommand() {
-        Some(("init", _)) => {
+        Some(("init", sub_matches)) => {
             let cmd = InitCommand::new(context);
-            let args = InitArgs;
+            let args = InitArgs {
+                from: sub_matches.get_one::<String>("from").cloned(),
+                orphan: sub_matches.get_flag("orphan"),
+                profiles: sub_matches
+                    .get_many::<String>("profile")
+                    .map(|values| values.filter_map(|value| value.parse().ok()).collect())
+                    .unwrap_or_default(),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("migrate", sub_matches)) => {
+            let cmd = MigrateCommand::new(context);
+            let mut names = sub_matches
+                .get_many::<String>("rename_branch")
+                .unwrap()
+                .cloned();
+            let args = MigrateArgs {
+                old_branch: names.next().unwrap(),
+                new_branch: names.next().unwrap(),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
             CommandTrait::execute(&cmd, args)?;
         }
         Some(("create", sub_matches)) => {
             let cmd = CreateCommand::new(context);
             let args = CreateArgs {
                 message: sub_matches.get_one::<String>("message").cloned(),
+                allow_empty: sub_matches.get_flag("allow_empty"),
+                verify: sub_matches.get_flag("verify"),
+                require_pass: sub_matches.get_flag("require_pass"),
+                bypass_guard: sub_matches.get_flag("bypass_guard"),
+                porcelain: sub_matches.get_flag("porcelain"),
             };
             CommandTrait::execute(&cmd, args)?;
         }
         Some(("list", sub_matches)) => {
             let cmd = ListCommand::new(context);
             let number_str = sub_matches.get_one::<String>("number").unwrap();
-            let number = number_str.parse::<usize>()?;
-            let args = ListArgs { number };
+            let number = CliError::parse::<usize>("number", number_str)?;
+            let stat = sub_matches.get_flag("stat");
+            let with_restores = sub_matches.get_flag("with_restores");
+            let labels = sub_matches
+                .get_many::<String>("label")
+                .map(|values| values.filter_map(|value| value.parse().ok()).collect())
+                .unwrap_or_default();
+            // 命令行未指定列时使用 ccg.list.columns 配置
+            let columns = match sub_matches.get_many::<String>("columns") {
+                Some(values) => values.filter_map(|value| value.parse().ok()).collect(),
+                None => load_config(repo_path)
+                    .and_then(|config| CcgConfig::from_config(&config).list_columns)
+                    .unwrap_or_default(),
+            };
+            let args = ListArgs {
+                number,
+                stat,
+                columns,
+                with_restores,
+                labels,
+                only_passing: sub_matches.get_flag("only_passing"),
+                task: sub_matches.get_one::<String>("task").cloned(),
+                skip_format_only: sub_matches.get_flag("skip_format_only"),
+                user: sub_matches.get_one::<String>("user").cloned(),
+            };
             CommandTrait::validate_args(&cmd, &args)?;
             CommandTrait::execute(&cmd, args)?;
         }
         Some(("restore", sub_matches)) => {
             let cmd = RestoreCommand::new(context);
             let hash = sub_matches.get_one::<String>("hash").unwrap().clone();
-            let args = RestoreArgs { hash };
+            let preview = sub_matches.get_flag("preview");
+            let force = sub_matches.get_flag("force");
+            let clean = sub_matches.get_flag("clean");
+            let continue_on_error = sub_matches.get_flag("continue-on-error");
+            let patch = sub_matches.get_flag("patch");
+            let to = sub_matches.get_one::<String>("to").cloned();
+            let args = RestoreArgs {
+                hash,
+                preview,
+                force,
+                clean,
+                continue_on_error,
+                patch,
+                to,
+                bypass_guard: sub_matches.get_flag("bypass_guard"),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("run", sub_matches)) => {
+            let cmd = RunCommand::new(context);
+            let args = RunArgs {
+                hash: sub_matches.get_one::<String>("hash").unwrap().clone(),
+                command: sub_matches
+                    .get_many::<String>("command")
+                    .map(|values| values.cloned().collect())
+                    .unwrap_or_default(),
+                keep: sub_matches.get_flag("keep"),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            let status = CommandTrait::execute(&cmd, args)?;
+            if !status.success() {
+                process::exit(status.code().unwrap_or(1));
+            }
+        }
+        Some(("impact", sub_matches)) => {
+            let cmd = ImpactCommand::new(context);
+            let hash = sub_matches.get_one::<String>("hash").unwrap().clone();
+            let args = ImpactArgs { hash };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("revert", sub_matches)) => {
+            let cmd = RevertCommand::new(context);
+            let hash = sub_matches.get_one::<String>("hash").unwrap().clone();
+            let args = RevertArgs { hash };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("reword", sub_matches)) => {
+            let cmd = RewordCommand::new(context);
+            let hash = sub_matches.get_one::<String>("hash").unwrap().clone();
+            let message = sub_matches.get_one::<String>("message").cloned();
+            let args = RewordArgs { hash, message };
             CommandTrait::validate_args(&cmd, &args)?;
             CommandTrait::execute(&cmd, args)?;
         }
         Some(("show", sub_matches)) => {
             let cmd = ShowCommand::new(context);
             let hash = sub_matches.get_one::<String>("hash").unwrap().clone();
-            let diff = sub_matches.get_flag("diff");
-            let args = ShowArgs { hash, diff };
+            let full = sub_matches.get_flag("full");
+            let diff = sub_matches.get_flag("diff") || full;
+            let files = sub_matches
+                .get_many::<String>("files")
+                .map(|values| values.cloned().collect())
+                .unwrap_or_default();
+            let file = sub_matches.get_one::<String>("file").cloned();
+            let output = sub_matches.get_one::<String>("output").cloned();
+            let highlight = sub_matches.get_flag("highlight");
+            let args = ShowArgs {
+                hash,
+                diff,
+                full,
+                files,
+                file,
+                output,
+                highlight,
+                diff_settings: diff_settings(sub_matches),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("split", sub_matches)) => {
+            let cmd = SplitCommand::new(context);
+            let hash = sub_matches.get_one::<String>("hash").unwrap().clone();
+            let args = SplitArgs { hash };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("rebase", sub_matches)) => {
+            let cmd = RebaseCommand::new(context);
+            let args = RebaseArgs {
+                onto: sub_matches.get_one::<String>("onto").cloned(),
+                replay: sub_matches.get_flag("replay"),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("repack", sub_matches)) => {
+            let cmd = RepackCommand::new(context);
+            let args = RepackArgs {
+                window: sub_matches.get_one::<u64>("window").copied(),
+                depth: sub_matches.get_one::<u64>("depth").copied(),
+                min_size: sub_matches.get_one::<u64>("min_size").copied(),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("prune", sub_matches)) => {
+            let cmd = PruneCommand::new(context);
+            let args = PruneArgs {
+                keep: sub_matches.get_one::<usize>("keep").copied(),
+                before: sub_matches.get_one::<String>("before").cloned(),
+                strategy: sub_matches
+                    .get_one::<String>("strategy")
+                    .map(|value| CliError::parse("strategy", value))
+                    .transpose()?,
+                interactive: sub_matches.get_flag("interactive"),
+            };
             CommandTrait::validate_args(&cmd, &args)?;
             CommandTrait::execute(&cmd, args)?;
         }
         Some(("diff", sub_matches)) => {
             let cmd = DiffCommand::new(context);
-            let hash_a = sub_matches.get_one::<String>("hash_a").unwrap().clone();
+            let hash_a = sub_matches.get_one::<String>("hash_a").cloned();
             let hash_b = sub_matches.get_one::<String>("hash_b").cloned();
-            let args = DiffArgs { hash_a, hash_b };
+            let task = sub_matches.get_one::<String>("task").cloned();
+            let dir = sub_matches.get_one::<String>("dir").cloned();
+            let since = sub_matches.get_one::<String>("since").cloned();
+            let args = DiffArgs {
+                hash_a,
+                hash_b,
+                task,
+                dir,
+                since,
+                diff_settings: diff_settings(sub_matches),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("task", sub_matches)) => {
+            let cmd = TaskCommand::new(context);
+            let action = match sub_matches.subcommand() {
+                Some(("start", start_matches)) => TaskAction::Start {
+                    name: start_matches.get_one::<String>("name").unwrap().clone(),
+                },
+                _ => TaskAction::End,
+            };
+            let args = TaskArgs { action };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("listen", sub_matches)) => {
+            let cmd = ListenCommand::new(context);
+            let args = ListenArgs {
+                socket: sub_matches.get_one::<String>("socket").unwrap().clone(),
+                debounce: sub_matches.get_one::<u64>("debounce").copied(),
+                idle: sub_matches.get_one::<u64>("idle").copied(),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("export-metadata", sub_matches)) => {
+            let cmd = ExportMetadataCommand::new(context);
+            let args = ExportMetadataArgs {
+                format: match sub_matches.get_one::<String>("format").map(String::as_str) {
+                    Some("jsonl") => MetadataFormat::Jsonl,
+                    _ => MetadataFormat::Csv,
+                },
+                output: sub_matches.get_one::<String>("output").cloned(),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("search", sub_matches)) => {
+            let cmd = SearchCommand::new(context);
+            let text = sub_matches
+                .get_many::<String>("query")
+                .unwrap()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let args = SearchArgs {
+                query: CliError::parse("query", &text)?,
+                text,
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("report", sub_matches)) => {
+            let cmd = ReportCommand::new(context);
+            let args = ReportArgs {
+                session: sub_matches.get_one::<String>("session").cloned(),
+                format: match sub_matches.get_one::<String>("format").map(String::as_str) {
+                    Some("html") => ReportFormat::Html,
+                    _ => ReportFormat::Markdown,
+                },
+                output: sub_matches.get_one::<String>("output").cloned(),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("snapshot", sub_matches)) => {
+            let cmd = SnapshotCommand::new(context);
+            let action = match sub_matches.subcommand() {
+                Some(("push", push_matches)) => SnapshotAction::Push {
+                    message: push_matches.get_one::<String>("message").cloned(),
+                },
+                Some(("pop", pop_matches)) => SnapshotAction::Pop {
+                    number: pop_matches.get_one::<usize>("number").copied(),
+                },
+                _ => SnapshotAction::List,
+            };
+            let args = SnapshotArgs { action };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("promote", sub_matches)) => {
+            let cmd = PromoteCommand::new(context);
+            let args = PromoteArgs {
+                task: sub_matches.get_one::<String>("task").unwrap().clone(),
+                message: sub_matches.get_one::<String>("message").cloned(),
+                skip_format_only: sub_matches.get_flag("skip_format_only"),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("evolution", sub_matches)) => {
+            let cmd = EvolutionCommand::new(context);
+            let args = EvolutionArgs {
+                file: sub_matches.get_one::<String>("file").unwrap().clone(),
+                full: sub_matches.get_flag("full"),
+                since: sub_matches.get_one::<String>("since").cloned(),
+                until: sub_matches.get_one::<String>("until").cloned(),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("status", _)) => {
+            let cmd = StatusCommand::new(context);
+            CommandTrait::execute(&cmd, StatusArgs)?;
+        }
+        Some(("drift", _)) => {
+            let cmd = DriftCommand::new(context);
+            CommandTrait::execute(&cmd, DriftArgs)?;
+        }
+        Some(("stats", sub_matches)) => {
+            let cmd = StatsCommand::new(context);
+            let args = StatsArgs {
+                dedupe: sub_matches.get_flag("dedupe"),
+            };
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("fetch", sub_matches)) => {
+            let cmd = FetchCommand::new(context);
+            let args = FetchArgs {
+                remote: sub_matches.get_one::<String>("remote").unwrap().clone(),
+                depth: sub_matches.get_one::<u32>("depth").copied(),
+            };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("push", sub_matches)) => {
+            let cmd = PushCommand::new(context);
+            let args = PushArgs {
+                remote: sub_matches.get_one::<String>("remote").unwrap().clone(),
+            };
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("flush", _)) => {
+            let cmd = FlushCommand::new(context);
+            CommandTrait::execute(&cmd, FlushArgs)?;
+        }
+        Some(("doctor", _)) => {
+            let cmd = DoctorCommand::new(context);
+            CommandTrait::execute(&cmd, DoctorArgs)?;
+        }
+        Some(("schedule", sub_matches)) => {
+            let cmd = ScheduleCommand::new(context);
+            let action = match sub_matches.subcommand() {
+                Some(("install", install_matches)) => ScheduleAction::Install {
+                    every: CliError::parse(
+                        "--every",
+                        install_matches.get_one::<String>("every").unwrap(),
+                    )?,
+                    scheduler: install_matches
+                        .get_one::<String>("scheduler")
+                        .map(|name| CliError::parse("--scheduler", name))
+                        .transpose()?,
+                    print: install_matches.get_flag("print"),
+                },
+                Some(("remove", _)) => ScheduleAction::Remove,
+                _ => ScheduleAction::Status,
+            };
+            let args = ScheduleArgs { action };
+            CommandTrait::validate_args(&cmd, &args)?;
+            CommandTrait::execute(&cmd, args)?;
+        }
+        Some(("hook", sub_matches)) => {
+            let cmd = HookCommand::new(context);
+            let Some(("install", install_matches)) = sub_matches.subcommand() else {
+                unreachable!()
+            };
+            let targets = match install_matches
+                .get_one::<String>("target")
+                .unwrap()
+                .as_str()
+            {
+                "git" => vec![InstallTarget::GitHook],
+                "all" => vec![InstallTarget::ClaudeSettings, InstallTarget::GitHook],
+                _ => vec![InstallTarget::ClaudeSettings],
+            };
+            let args = HookArgs {
+                action: HookAction::Install,
+                targets,
+                print: install_matches.get_flag("print"),
+            };
             CommandTrait::validate_args(&cmd, &args)?;
             CommandTrait::execute(&cmd, args)?;
         }
@@ -150,23 +1440,7 @@ fn main() {
     setup_i18n(); // 初始化 i18n
 
     if let Err(error) = run() {
-        eprintln!("{}: {error}", t!("error_prefix"));
-
-        let mut source = error.source();
-        let mut level = 1;
-        while let Some(err) = source {
-            eprintln!(
-                "   {} {}: {}",
-                "  ".repeat(level),
-                t!("error_cause_prefix"),
-                err
-            );
-            source = err.source();
-            level += 1;
-        }
-
-        eprintln!();
-        eprintln!("{}", t!("error_tip"));
+        report::report(&error);
         process::exit(1);
     }
 }
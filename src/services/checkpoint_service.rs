@@ -1,51 +1,249 @@
+use crate::config::ExcludeProfile;
 use crate::error::{CheckpointError, Result as CcResult};
-use crate::git_ops::GitOperations;
+use crate::git_ops::commit::{cap_message, sanitize_message};
+use crate::git_ops::ids::format_checkpoint_id;
+use crate::git_ops::tasks::task_slug;
+use crate::git_ops::{
+    AheadBehind, BASE_BRANCH_TRAILER, BASE_COMMIT_TRAILER, BaseDrift, BranchDrift, CCG_BRANCH_NAME,
+    CHECKPOINT_NOTES_REF, CheckoutReport, CheckpointEntry, CheckpointLabel, CheckpointSource,
+    CheckpointStatus, FetchOperations, FetchReport, FetchUpdate, FileChangeInfo, FileVersion,
+    GitBackend, GitOperations, INTERLEAVED_TRAILER, ImpactReport, ListColumn, ListFilter,
+    Permissions, RepackReport, RepackSettings, RestoreHunk, RestoreRecord, RewrittenHistory,
+    SESSION_TRAILER, SOURCE_TRAILER, Snapshot, TASK_NAME_TRAILER, TASK_TRAILER, TOOL_TRAILER, Task,
+    TaskRange, ToolEdit, Trailers, USER_TRAILER, VERIFY_COMMAND_TRAILER, VERIFY_TRAILER,
+};
+use crate::services::hooks::{self, HookEvent, HookRunner};
+use crate::services::metadata::CheckpointMetadata;
+use crate::services::query::CheckpointQuery;
+use crate::services::report::{ReportCheckpoint, SessionReport};
+use crate::services::verify::Verification;
+use crate::ui::{self, Icon};
+use chrono::DateTime;
 use console::{Color, style};
+use rust_i18n::t;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+use std::process::ExitStatus;
+use std::str::FromStr;
+
+/// 创建检查点的选项
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptions {
+    /// 没有变更时也创建带标记的空检查点
+    pub allow_empty: bool,
+    /// 创建前运行的验证命令，结果记录在检查点的提交信息中
+    pub verify: Option<String>,
+    /// 验证未通过时不创建检查点
+    pub require_pass: bool,
+    /// 检查点所属的 Claude Code 会话（钩子数据中的 `session_id`），记录为 `Ccg-Session` 尾注
+    pub session: Option<String>,
+    /// 触发检查点的 Claude Code 工具（钩子数据中的 `tool_name`），记录为 `Ccg-Tool` 尾注
+    pub tool: Option<String>,
+    /// 检查点的来源，记录为 `Ccg-Source` 尾注
+    pub source: Option<CheckpointSource>,
+    /// 跳过阻塞型的 pre-create 钩子（`--bypass-guard`），跳过记录在审计日志中
+    pub bypass_guard: bool,
+    /// 钩子数据中工具报告的修改，与实际变更比较以发现同时进行的其他编辑
+    pub tool_edits: Vec<ToolEdit>,
+}
+
+/// 恢复检查点的选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RestoreOptions {
+    /// 检查点早于其所基于分支上的新提交时仍然恢复
+    pub force: bool,
+    /// 同时删除未跟踪的文件（默认保留）
+    pub clean: bool,
+    /// 跳过无法写入的文件并继续恢复其余文件（默认在修改前报错）
+    pub continue_on_error: bool,
+    /// 跳过阻塞型的 pre-restore 钩子（`--bypass-guard`），跳过记录在审计日志中
+    pub bypass_guard: bool,
+}
+
+/// 按规则选出要清理的检查点的策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneStrategy {
+    /// 每个会话只保留第一个和最后一个检查点，丢弃中间的检查点；没有会话的检查点不受影响
+    SessionEndpoints,
+}
+
+impl PruneStrategy {
+    /// 所有策略
+    pub const ALL: [PruneStrategy; 1] = [PruneStrategy::SessionEndpoints];
+
+    /// 命令行中使用的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            PruneStrategy::SessionEndpoints => "session-endpoints",
+        }
+    }
+}
+
+impl FromStr for PruneStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        PruneStrategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.name() == name)
+            .ok_or_else(|| format!("unknown prune strategy: {name}"))
+    }
+}
 
 /// 检查点服务，封装检查点相关的业务逻辑
+///
+/// 通过 `GitBackend` 访问存储，默认使用基于 libgit2 的 `GitOperations`，
+/// 测试中可以替换为内存实现 `MemoryBackend`。
 #[derive(Clone)]
-pub struct CheckpointService {
-    git_ops: GitOperations,
+pub struct CheckpointService<B: GitBackend = GitOperations> {
+    git_ops: B,
+    /// 只读模式下拒绝所有修改检查点的操作
+    read_only: bool,
 }
 
-impl CheckpointService {
-    pub fn new(git_ops: GitOperations) -> CcResult<Self> {
-        Ok(CheckpointService { git_ops })
+impl<B: GitBackend> CheckpointService<B> {
+    pub fn new(git_ops: B) -> CcResult<Self> {
+        let read_only = git_ops.read_only();
+        Ok(CheckpointService { git_ops, read_only })
+    }
+
+    /// 开启或关闭只读模式（`--read-only`），默认取自 `ccg.readOnly` 配置
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// 是否处于只读模式
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// 只读模式下拒绝 `operation`
+    ///
+    /// 所有修改检查点或仓库的服务方法都先经过这里，命令层无需各自检查。
+    fn ensure_writable(&self, operation: &str) -> CcResult<()> {
+        if self.read_only {
+            return Err(CheckpointError::ReadOnly(operation.to_string()));
+        }
+        self.repair_interrupted_create();
+        Ok(())
+    }
+
+    /// 上次创建检查点在写入索引后被中断时，先恢复用户的暂存区
+    ///
+    /// 只在修改检查点的操作开始时调用；仍在运行的 create 持有锁，不会被回滚。
+    fn repair_interrupted_create(&self) {
+        match self.git_ops.repair_interrupted_create() {
+            Ok(Some(repair)) if repair.restored => println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!("staging_restored")).fg(Color::Yellow)
+            ),
+            Ok(_) => {}
+            Err(e) => println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!("staging_repair_failed", error = e)).fg(Color::Yellow)
+            ),
+        }
+    }
+
+    /// 按 `ccg.team.permissions` 检查能否丢弃或改写 `affected` 中的检查点
+    ///
+    /// `append-only` 拒绝任何丢弃或改写；`owner` 只允许处理自己创建的检查点，
+    /// 没有 `Ccg-User` 记录的旧检查点不受限制。
+    fn ensure_permitted(&self, operation: &str, affected: &[CheckpointEntry]) -> CcResult<()> {
+        let user = self.git_ops.team_user();
+        match self.git_ops.team_permissions() {
+            Permissions::Open => Ok(()),
+            Permissions::AppendOnly if affected.is_empty() => Ok(()),
+            Permissions::AppendOnly => Err(CheckpointError::NotPermitted(t!(
+                "not_permitted_append_only",
+                operation = operation,
+                count = affected.len()
+            ))),
+            Permissions::Owner => {
+                let mut owners: Vec<String> = affected
+                    .iter()
+                    .filter_map(CheckpointEntry::user)
+                    .filter(|owner| *owner != user)
+                    .collect();
+                owners.sort();
+                owners.dedup();
+                if owners.is_empty() {
+                    return Ok(());
+                }
+                Err(CheckpointError::NotPermitted(t!(
+                    "not_permitted_owner",
+                    operation = operation,
+                    owners = owners.join(", "),
+                    user = user
+                )))
+            }
+        }
+    }
+
+    /// 底层存储
+    pub fn backend(&self) -> &B {
+        &self.git_ops
+    }
+
+    /// 用户扩展脚本的执行器（裸仓库没有工作目录时为 None）
+    fn hook_runner(&self) -> Option<HookRunner> {
+        self.git_ops.workdir().map(HookRunner::new)
+    }
+
+    /// 运行阻塞型钩子，除非通过 `--bypass-guard` 或 `CCG_BYPASS_GUARD` 要求跳过
+    ///
+    /// 跳过已存在的钩子时写入审计日志并打印警告，`target` 为钩子本应检查的内容。
+    fn run_guard(
+        &self,
+        hooks: &HookRunner,
+        event: HookEvent,
+        context: &serde_json::Value,
+        target: &str,
+        bypass: bool,
+    ) -> CcResult<()> {
+        let via = if bypass {
+            "--bypass-guard"
+        } else if hooks::bypass_requested() {
+            hooks::BYPASS_GUARD_ENV
+        } else {
+            return hooks.run(event, context);
+        };
+        if !hooks.exists(event) {
+            return Ok(());
+        }
+        let entry = self.git_ops.record_bypass(event.name(), target, via)?;
+        println!(
+            "{}{}",
+            style(Icon::Warning.prefix()).fg(Color::Yellow),
+            style(t!("guard_bypassed", hook = entry.hook, user = entry.user)).fg(Color::Yellow)
+        );
+        Ok(())
+    }
+
+    /// 读取 ccg 分支上的检查点，不切换 HEAD
+    ///
+    /// 只读的操作直接从 ccg 分支读取历史，不写 HEAD 和它的 reflog：只读挂载的
+    /// 仓库中也能使用，进程中途被终止时也不会停留在 ccg 分支上。
+    fn read_ccg_branch<F, R>(&self, operation: F) -> CcResult<R>
+    where
+        F: FnOnce(&B) -> CcResult<R>,
+    {
+        self.ensure_initialized()?;
+        operation(&self.git_ops)
     }
 
-    /// 在ccg分支上执行操作的通用包装器
+    /// 在ccg分支上执行修改操作的通用包装器，调用前须已通过 `ensure_writable`
     fn execute_on_ccg_branch<F, R>(&self, operation: F) -> CcResult<R>
     where
-        F: FnOnce(&GitOperations) -> CcResult<R>,
+        F: FnOnce(&B) -> CcResult<R>,
     {
-        // 确保在ccg分支上执行
-        let original_branch = match self.git_ops.ensure_ccg_branch() {
-            Ok(branch) => branch,
-            Err(CheckpointError::BranchNotFound(_)) => {
-                // 如果ccg分支不存在，则初始化它
-                println!(
-                    "{} {}",
-                    style("ℹ️").fg(Color::Blue),
-                    style("未找到 'ccg' 分支，将自动初始化...").fg(Color::White)
-                );
-                self.git_ops.init_checkpoints()?;
-                // 初始化后，再次确保切换到ccg分支
-                self.git_ops.ensure_ccg_branch()?
-            }
-            Err(CheckpointError::GitOperationFailed(e))
-                if e.code() == git2::ErrorCode::NotFound
-                    || e.code() == git2::ErrorCode::UnbornBranch =>
-            {
-                println!(
-                    "{} {}",
-                    style("ℹ️").fg(Color::Blue),
-                    style("未找到 'ccg' 分支或仓库未初始化，将自动初始化...").fg(Color::White)
-                );
-                self.git_ops.init_checkpoints()?;
-                self.git_ops.ensure_ccg_branch()?
-            }
-            Err(e) => return Err(e),
-        };
+        self.ensure_initialized()?;
+        let original_branch = self.git_ops.ensure_ccg_branch()?;
 
         // 执行操作
         let result = operation(&self.git_ops);
@@ -55,9 +253,9 @@ impl CheckpointService {
             // 如果恢复分支失败，但操作成功，我们仍然返回操作结果，但记录警告
             if result.is_ok() {
                 println!(
-                    "{} {}",
-                    style("⚠️").fg(Color::Yellow),
-                    style("操作成功完成，但分支恢复失败").fg(Color::Yellow)
+                    "{}{}",
+                    style(Icon::Warning.prefix()).fg(Color::Yellow),
+                    style(t!("restore_branch_switch_back_failed")).fg(Color::Yellow)
                 );
             }
         }
@@ -65,50 +263,125 @@ impl CheckpointService {
         result
     }
 
+    /// 重命名分支，用于迁移已有的检查点分支
+    ///
+    /// 只能迁移 ccg 分支和名为 `ccg-*` 的存档分支。只有分支名称改变：标签、
+    /// 注释、检查点 ID、大小索引和恢复日志都按提交记录，不依赖分支名称，
+    /// 重命名后保持有效。
+    pub fn migrate_branch(&self, old: &str, new: &str) -> CcResult<()> {
+        self.ensure_writable("migrate")?;
+        if old != CCG_BRANCH_NAME && !old.starts_with(&format!("{CCG_BRANCH_NAME}-")) {
+            return Err(CheckpointError::InvalidArgument(
+                t!(
+                    "migrate_not_checkpoint_branch",
+                    branch = old,
+                    ccg = CCG_BRANCH_NAME
+                )
+                .to_string(),
+            ));
+        }
+        self.git_ops.rename_branch(old, new)?;
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            style(t!(
+                "migrate_renamed",
+                old = style(old).fg(Color::Cyan).bold(),
+                new = style(new).fg(Color::Cyan).bold()
+            ))
+            .fg(Color::Green)
+        );
+        if old == CCG_BRANCH_NAME {
+            println!(
+                "{}{}",
+                style(Icon::Hint.prefix()).fg(Color::Blue),
+                style(t!("migrate_init_hint", branch = CCG_BRANCH_NAME)).fg(Color::White)
+            );
+        }
+        Ok(())
+    }
+
+    /// 确保 ccg 分支存在，不存在（包括 HEAD 指向尚无提交的 ccg 分支）时自动初始化
+    ///
+    /// 所有需要 ccg 分支的操作都先经过这里，分支缺失统一表现为
+    /// `CheckpointError::BranchNotFound`，不再依赖具体的 git 错误码。
+    pub fn ensure_initialized(&self) -> CcResult<()> {
+        if self.git_ops.has_ccg_branch()? {
+            return Ok(());
+        }
+        self.ensure_writable("init")?;
+        println!(
+            "{}{}",
+            style(Icon::Info.prefix()).fg(Color::Blue),
+            style("未找到 'ccg' 分支，将自动初始化...").fg(Color::White)
+        );
+        self.git_ops.init_checkpoints()
+    }
+
     /// 初始化检查点系统
     pub fn init(&self) -> CcResult<()> {
+        self.init_from(None)
+    }
+
+    /// 初始化检查点系统，指定 `from` 时 ccg 分支基于该分支而不是当前 HEAD 创建
+    pub fn init_from(&self, from: Option<&str>) -> CcResult<()> {
+        self.init_with(|git_ops| match from {
+            Some(branch) => git_ops.init_checkpoints_from(branch),
+            None => git_ops.init_checkpoints(),
+        })
+    }
+
+    /// 初始化检查点系统，ccg 分支创建为不与当前分支共享历史的孤立分支
+    ///
+    /// 同时记住此模式，ccg 分支被删除后重新创建时仍为孤立分支。
+    pub fn init_orphan(&self) -> CcResult<()> {
+        self.init_with(|git_ops| git_ops.init_checkpoints_orphan())
+    }
+
+    fn init_with(&self, create: impl FnOnce(&B) -> CcResult<()>) -> CcResult<()> {
+        self.ensure_writable("init")?;
         println!(
-            "{} {}",
-            style("🚀").fg(Color::Blue),
+            "{}{}",
+            style(Icon::Start.prefix()).fg(Color::Blue),
             style("初始化 Claude Code Checkpoint Guardian")
                 .fg(Color::Cyan)
                 .bold()
         );
 
         // 初始化检查点系统（会自动处理Git仓库和ccg分支）
-        self.git_ops.init_checkpoints()?;
+        create(&self.git_ops)?;
 
         // 检查是否是新初始化的Git仓库
         let current_branch = self.git_ops.get_current_branch_name()?;
-        if current_branch == "ccg" {
+        if current_branch == CCG_BRANCH_NAME {
             println!(
-                "{} {}",
-                style("✅").fg(Color::Green),
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
                 style("Claude Code Checkpoint Guardian 初始化完成！")
                     .fg(Color::Green)
                     .bold()
             );
             println!(
-                "{} {} {}",
-                style("📍").fg(Color::Blue),
+                "{}{} {}",
+                style(Icon::Location.prefix()).fg(Color::Blue),
                 style("当前分支:").fg(Color::White),
                 style(&current_branch).fg(Color::Yellow).bold()
             );
             println!(
-                "{} {}",
-                style("💡").fg(Color::Yellow),
+                "{}{}",
+                style(Icon::Hint.prefix()).fg(Color::Yellow),
                 style("提示: 现在可以使用 'ccg create' 创建检查点").fg(Color::White)
             );
         } else {
             println!(
-                "{} {} {}",
-                style("⚠️").fg(Color::Yellow),
+                "{}{} {}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
                 style("当前分支:").fg(Color::White),
                 style(&current_branch).fg(Color::Yellow).bold()
             );
             println!(
-                "{} {}",
-                style("💡").fg(Color::Yellow),
+                "{}{}",
+                style(Icon::Hint.prefix()).fg(Color::Yellow),
                 style("提示: ccg 分支已准备就绪，使用 'git checkout ccg' 切换").fg(Color::White)
             );
         }
@@ -116,190 +389,1070 @@ impl CheckpointService {
         Ok(())
     }
 
+    /// 把内置排除规则写入仓库配置，列出新增的规则
+    pub fn apply_exclude_profiles(&self, profiles: &[ExcludeProfile]) -> CcResult<()> {
+        for profile in profiles {
+            let added = self.git_ops.apply_exclude_profile(*profile)?;
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                t!(
+                    "init_profile_applied",
+                    profile = style(profile.name()).fg(Color::Cyan).bold(),
+                    count = added.len()
+                )
+            );
+            if !added.is_empty() {
+                println!("   {}", style(added.join(", ")).dim());
+            }
+        }
+        if !profiles.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Hint.prefix()).fg(Color::Blue),
+                t!("init_profile_hint")
+            );
+        }
+        Ok(())
+    }
+
     /// 创建检查点
     pub fn create_checkpoint(&self, tool_input: Option<&str>) -> CcResult<String> {
+        self.create_checkpoint_with(tool_input, false)
+    }
+
+    /// 创建检查点，`allow_empty` 为 true 时在没有变更时也创建带标记的空检查点
+    ///
+    /// 没有变更且不允许空检查点时返回空字符串。
+    pub fn create_checkpoint_with(
+        &self,
+        tool_input: Option<&str>,
+        allow_empty: bool,
+    ) -> CcResult<String> {
+        self.create_checkpoint_with_options(
+            tool_input,
+            &CreateOptions {
+                allow_empty,
+                ..CreateOptions::default()
+            },
+        )
+    }
+
+    /// 按指定选项创建检查点
+    ///
+    /// 指定验证命令时先在工作目录中运行它，并把结果记录为 `Ccg-Verify`
+    /// 提交信息尾注；`require_pass` 为 true 且验证未通过时返回
+    /// `VerificationFailed` 错误，不创建检查点。
+    pub fn create_checkpoint_with_options(
+        &self,
+        tool_input: Option<&str>,
+        options: &CreateOptions,
+    ) -> CcResult<String> {
+        let message = match tool_input {
+            Some(message) => message.to_string(),
+            None => t!("message_default", locale = &self.git_ops.message_locale()).to_string(),
+        };
+        let hashes = self.create_checkpoints(&[(None, message)], options)?;
+        Ok(hashes.into_iter().next().unwrap_or_default())
+    }
+
+    /// 为每个文件分别创建检查点，便于之后按文件恢复或挑选
+    ///
+    /// `files` 为（文件路径，提交信息），路径可以是绝对路径或相对仓库根目录的路径。
+    /// 所有检查点在同一次切换到 ccg 分支期间依次提交，每个只包含对应文件的变更；
+    /// 没有变更的文件被跳过。验证命令只运行一次，结果记录在每个检查点中。
+    pub fn create_checkpoint_per_file(
+        &self,
+        files: &[(String, String)],
+        options: &CreateOptions,
+    ) -> CcResult<Vec<String>> {
+        let workdir = self.git_ops.workdir();
+        let parts: Vec<(Option<String>, String)> = files
+            .iter()
+            .map(|(path, message)| {
+                let relative = workdir
+                    .as_deref()
+                    .and_then(|root| Path::new(path).strip_prefix(root).ok())
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.clone());
+                (Some(relative), message.clone())
+            })
+            .collect();
+        self.create_checkpoints(&parts, options)
+    }
+
+    /// 依次创建检查点，每项为（只提交的路径，提交信息），路径为 None 时提交所有变更
+    fn create_checkpoints(
+        &self,
+        parts: &[(Option<String>, String)],
+        options: &CreateOptions,
+    ) -> CcResult<Vec<String>> {
+        let allow_empty = options.allow_empty;
+        self.ensure_writable("create")?;
         println!(
-            "{} {}",
-            style("🔄").fg(Color::Blue),
+            "{}{}",
+            style(Icon::Switch.prefix()).fg(Color::Blue),
             style("开始创建检查点...").fg(Color::White)
         );
 
-        self.execute_on_ccg_branch(|git_ops| {
-            let message = tool_input.unwrap_or("Checkpoint created without a specific message.");
+        let hooks = self.hook_runner();
+        if let Some(hooks) = &hooks {
+            for (_, message) in parts {
+                let subject = message.lines().next().unwrap_or_default();
+                self.run_guard(
+                    hooks,
+                    HookEvent::PreCreate,
+                    &json!({ "message": message }),
+                    subject,
+                    options.bypass_guard,
+                )?;
+            }
+        }
 
-            match git_ops.create_checkpoint(message) {
-                Ok(hash) => {
-                    let short_hash = &hash[..7];
-                    println!(
-                        "{} {}",
-                        style("✅ Created checkpoint:").fg(Color::Green).bold(),
-                        style(short_hash).fg(Color::Yellow).bold(),
-                    );
-                    Ok(hash)
+        // 记录用户当前所在分支及其提交，恢复时据此判断检查点是否早于分支上的新提交
+        let mut trailers = Trailers::new();
+        if let Some((branch, tip)) = self.git_ops.base_branch_tip()? {
+            trailers
+                .push(BASE_BRANCH_TRAILER, &branch)
+                .push(BASE_COMMIT_TRAILER, &tip);
+        }
+
+        // 进行中的任务记录在每个检查点中
+        if let Some(task) = self.git_ops.active_task()? {
+            trailers
+                .push(TASK_TRAILER, &task.id)
+                .push(TASK_NAME_TRAILER, &task.name);
+        }
+
+        if let Some(session) = &options.session {
+            trailers.push(SESSION_TRAILER, session);
+        }
+        if let Some(tool) = &options.tool {
+            trailers.push(TOOL_TRAILER, tool);
+        }
+        if let Some(source) = options.source {
+            trailers.push(SOURCE_TRAILER, source.name());
+        }
+        // 有权限限制时记录创建者，供 owner 模式判断谁能丢弃或改写
+        if self.git_ops.team_permissions() != Permissions::Open {
+            trailers.push(USER_TRAILER, &self.git_ops.team_user());
+        }
+
+        // 在切换到 ccg 分支之前验证，命令看到的是用户当前的工作目录
+        if let Some(command) = &options.verify {
+            let verification = self.verify(command)?;
+            if !verification.passed && options.require_pass {
+                return Err(CheckpointError::VerificationFailed(verification.command));
+            }
+            trailers
+                .push(VERIFY_TRAILER, verification.status())
+                .push(VERIFY_COMMAND_TRAILER, &verification.command);
+        }
+
+        // 工具报告之外的变更说明有人同时编辑了这些文件，检查点不是纯粹的工具快照
+        let interleaved = self.git_ops.interleaved_files(&options.tool_edits)?;
+        if !interleaved.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!("create_interleaved", files = interleaved.join(", "))).fg(Color::Yellow)
+            );
+        }
+
+        let max_bytes = self.git_ops.message_max_bytes();
+        let locale = self.git_ops.message_locale();
+        self.execute_on_ccg_branch(|git_ops| {
+            let mut hashes = Vec::new();
+            for (path, message) in parts {
+                let mut trailers = trailers.clone();
+                for file in &interleaved {
+                    if path.as_ref().is_none_or(|path| path == file) {
+                        trailers.push(INTERLEAVED_TRAILER, file);
+                    }
                 }
-                Err(CheckpointError::NoChangesToCommit) => {
-                    println!(
-                        "{} {}",
-                        style("ℹ️").fg(Color::Blue),
-                        style("没有检测到文件变更，跳过创建检查点").fg(Color::Yellow)
-                    );
-                    Ok(String::new())
+                // 工具输出可能带有终端控制字符；超过上限的部分保存为 git note
+                let message = sanitize_message(message);
+                let (kept, overflow) = cap_message(&message, max_bytes);
+                let commit_message = match overflow {
+                    Some(rest) => trailers.apply_to(&format!(
+                        "{}\n\n{}",
+                        kept.trim_end(),
+                        t!(
+                            "message_truncated",
+                            locale = &locale,
+                            bytes = rest.len(),
+                            notes_ref = CHECKPOINT_NOTES_REF
+                        )
+                    )),
+                    None => trailers.apply_to(kept),
+                };
+                let result = match path {
+                    Some(path) => {
+                        git_ops.create_checkpoint_paths(&commit_message, std::slice::from_ref(path))
+                    }
+                    None => git_ops.create_checkpoint(&commit_message),
+                };
+                let (hash, empty) = match result {
+                    Ok(hash) => (hash, false),
+                    Err(CheckpointError::NoChangesToCommit) if allow_empty && path.is_none() => {
+                        (git_ops.create_empty_checkpoint(&commit_message)?, true)
+                    }
+                    Err(CheckpointError::NoChangesToCommit) => continue,
+                    Err(e) => return Err(e),
+                };
+                if let Some(rest) = overflow {
+                    git_ops.add_note(&hash, rest)?;
                 }
-                Err(e) => Err(e),
+
+                let short_hash = ui::short_hash(&hash);
+                let label = if empty {
+                    "Created empty checkpoint:"
+                } else {
+                    "Created checkpoint:"
+                };
+                let id = git_ops
+                    .checkpoint_id(&hash)?
+                    .map(|number| format!(" ({})", format_checkpoint_id(number)))
+                    .unwrap_or_default();
+                let file = path
+                    .as_deref()
+                    .map(|path| format!(" {path}"))
+                    .unwrap_or_default();
+                println!(
+                    "{}{} {}{}{}",
+                    style(Icon::Success.prefix()).fg(Color::Green).bold(),
+                    style(label).fg(Color::Green).bold(),
+                    style(short_hash).fg(Color::Yellow).bold(),
+                    style(id).fg(Color::Magenta),
+                    style(file).fg(Color::Cyan),
+                );
+                if let Some(hooks) = &hooks {
+                    hooks.run(
+                        HookEvent::PostCreate,
+                        &json!({ "message": message, "hash": hash, "empty": empty }),
+                    )?;
+                }
+                hashes.push(hash);
+            }
+
+            if hashes.is_empty() {
+                println!(
+                    "{}{}",
+                    style(Icon::Info.prefix()).fg(Color::Blue),
+                    style("没有检测到文件变更，跳过创建检查点").fg(Color::Yellow)
+                );
+            } else {
+                Self::advise_usage(git_ops);
+                // commit-graph 只用于加速，写入失败不影响检查点的创建
+                let _ = git_ops.maintain_commit_graph(false);
             }
+            Ok(hashes)
         })
     }
 
-    /// 列出检查点
-    pub fn list_checkpoints(&self, number: usize) -> CcResult<()> {
-        self.execute_on_ccg_branch(|git_ops| {
-            let checkpoints = git_ops.list_checkpoints(number)?;
+    /// 运行验证命令并显示结果
+    fn verify(&self, command: &str) -> CcResult<Verification> {
+        println!(
+            "{}{}",
+            style(Icon::Search.prefix()).fg(Color::Blue),
+            t!("verify_running", command = style(command).fg(Color::Cyan))
+        );
+        let workdir = self.git_ops.workdir().unwrap_or_else(|| ".".into());
+        let verification = Verification::run(&workdir, command)?;
+        if verification.passed {
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!("verify_passed")).fg(Color::Green)
+            );
+        } else {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!("verify_failed")).fg(Color::Yellow)
+            );
+        }
+        Ok(verification)
+    }
+
+    /// 检查点数量或占用空间首次超过阈值时提示清理
+    ///
+    /// 检查失败不影响检查点的创建。
+    fn advise_usage(git_ops: &B) {
+        let Ok(Some(advisory)) = git_ops.check_usage(&git_ops.usage_limits()) else {
+            return;
+        };
+        if advisory.too_many_checkpoints() {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!(
+                    "usage_too_many_checkpoints",
+                    count = advisory.checkpoints,
+                    limit = advisory.limits.max_checkpoints
+                ))
+                .fg(Color::Yellow)
+            );
+        }
+        if advisory.too_large() {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!(
+                    "usage_too_large",
+                    size = ui::format_size(advisory.bytes),
+                    limit = ui::format_size(advisory.limits.max_bytes)
+                ))
+                .fg(Color::Yellow)
+            );
+        }
+        println!(
+            "{}{}",
+            style(Icon::Hint.prefix()).fg(Color::Blue),
+            style(t!("usage_cleanup_hint")).fg(Color::White)
+        );
+    }
+
+    /// 以结构化形式返回最近的检查点（最新的在前）
+    pub fn checkpoints(&self, number: usize) -> CcResult<Vec<CheckpointEntry>> {
+        self.read_ccg_branch(|git_ops| git_ops.checkpoint_entries(number))
+    }
+
+    /// 按 `columns` 的顺序列出 `filter` 保留的检查点，`with_restores` 为 true 时在时间线中显示恢复记录
+    pub fn list_checkpoints(
+        &self,
+        number: usize,
+        columns: &[ListColumn],
+        with_restores: bool,
+        filter: &ListFilter,
+    ) -> CcResult<()> {
+        // 同事的命名空间不在 ccg 分支上，无需切换分支
+        if let Some(user) = &filter.user {
+            let checkpoints = self.git_ops.list_checkpoints(number, columns, filter)?;
+            if checkpoints.is_empty() {
+                println!(
+                    "{}",
+                    style(format!("{}No checkpoints found.", Icon::Empty.prefix()))
+                        .fg(Color::Yellow)
+                );
+                return Ok(());
+            }
+            println!(
+                "{}",
+                style(format!(
+                    "{}{}",
+                    Icon::List.prefix(),
+                    t!("list_user_header", user = user)
+                ))
+                .fg(Color::Green)
+                .bold()
+            );
+            println!();
+            for checkpoint in &checkpoints {
+                println!(
+                    "  {} {checkpoint}",
+                    style(Icon::Bullet.glyph()).fg(Color::Blue)
+                );
+            }
+            return Ok(());
+        }
+        self.read_ccg_branch(|git_ops| {
+            let checkpoints = git_ops.list_checkpoints(number, columns, filter)?;
+            // 恢复记录显示在其目标检查点之上，即恢复后新检查点开始的位置
+            let (hashes, restores) = if with_restores {
+                let limit = if filter.is_empty() {
+                    number
+                } else {
+                    usize::MAX
+                };
+                let hashes = git_ops
+                    .checkpoint_entries(limit)?
+                    .into_iter()
+                    .filter(|entry| filter.matches(entry))
+                    .take(number)
+                    .map(|entry| entry.hash)
+                    .collect();
+                (hashes, git_ops.restore_records()?)
+            } else {
+                (Vec::new(), Vec::new())
+            };
             if checkpoints.is_empty() {
-                println!("{}", style("📭 No checkpoints found.").fg(Color::Yellow));
+                println!(
+                    "{}",
+                    style(format!("{}No checkpoints found.", Icon::Empty.prefix()))
+                        .fg(Color::Yellow)
+                );
             } else {
                 println!(
                     "{}",
-                    style("📋 Recent checkpoints:").fg(Color::Green).bold()
+                    style(format!("{}Recent checkpoints:", Icon::List.prefix()))
+                        .fg(Color::Green)
+                        .bold()
                 );
                 println!();
                 for (i, checkpoint) in checkpoints.iter().enumerate() {
+                    let hash = hashes.get(i);
+                    for restore in restores.iter().filter(|r| Some(&r.target) == hash) {
+                        Self::print_restore_line(restore);
+                    }
                     let prefix = if i == 0 {
-                        style("  ●").fg(Color::Green).bold()
+                        style(Icon::Current.glyph()).fg(Color::Green).bold()
                     } else {
-                        style("  ○").fg(Color::Blue)
+                        style(Icon::Bullet.glyph()).fg(Color::Blue)
                     };
-                    println!("{prefix} {checkpoint}");
+                    println!("  {prefix} {checkpoint}");
+                }
+                if checkpoints.len() < number && git_ops.is_shallow() {
+                    println!();
+                    println!(
+                        "{}{}",
+                        style(Icon::Hint.prefix()).fg(Color::Blue),
+                        style(t!("list_shallow_note")).fg(Color::White)
+                    );
                 }
             }
             Ok(())
         })
     }
 
-    /// 恢复检查点 - 真正的时光机效果，丢弃后续提交
-    pub fn restore_checkpoint(&self, hash: &str) -> CcResult<()> {
-        let short_hash = if hash.len() >= 7 { &hash[..7] } else { hash };
-
-        // 记录当前分支
-        let original_branch = self.git_ops.get_current_branch_name()?;
+    fn print_restore_line(restore: &RestoreRecord) {
+        let datetime = DateTime::from_timestamp(restore.time, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "Unknown time".to_string());
+        println!(
+            "  {} {} {}",
+            style(Icon::Restored.glyph()).fg(Color::Magenta).bold(),
+            style(datetime).fg(Color::Cyan),
+            style(format!(
+                "restored to {} (discarded {} checkpoints)",
+                ui::short_hash(&restore.target),
+                restore.discarded
+            ))
+            .fg(Color::Magenta)
+        );
+    }
 
-        // 确保在 ccg 分支上执行
-        self.git_ops.ensure_ccg_branch()?;
+    /// 恢复到检查点将修改的文件（相对于当前工作目录）
+    pub fn restore_preview(&self, hash: &str) -> CcResult<Vec<FileChangeInfo>> {
+        self.read_ccg_branch(|git_ops| git_ops.restore_preview(hash))
+    }
 
-        // 安全检查：检查是否有未提交的更改
-        if self.git_ops.has_uncommitted_changes()? {
-            // 如果有未提交更改，恢复到原始分支
-            if original_branch != "ccg" {
-                let _ = self.git_ops.restore_original_branch(&original_branch);
+    /// 恢复到 `hash` 时将被丢弃的后续检查点（最新的在前）
+    pub fn discarded_checkpoints(&self, hash: &str) -> CcResult<Vec<CheckpointEntry>> {
+        self.read_ccg_branch(|git_ops| {
+            let target = git_ops.resolve_checkpoint(hash)?;
+            let head = git_ops.head_checkpoint()?;
+            let ahead = git_ops.count_commits_between(&target, &head)?;
+            if ahead == 0 {
+                return Ok(Vec::new());
             }
+            git_ops.checkpoint_entries(ahead)
+        })
+    }
+
+    /// 当前检查点与 `hash` 各自独有的提交数量
+    ///
+    /// `ahead` 是恢复时丢弃的检查点数量；`behind` 不为 0 时 `hash` 不在当前历史中
+    /// （例如在硬恢复改写分支之前创建），恢复会带回这些提交。
+    pub fn restore_divergence(&self, hash: &str) -> CcResult<AheadBehind> {
+        self.read_ccg_branch(|git_ops| {
+            let target = git_ops.resolve_checkpoint(hash)?;
+            let head = git_ops.head_checkpoint()?;
+            git_ops.ahead_behind(&target, &head)
+        })
+    }
 
+    /// 列出将被丢弃的检查点，最多显示 `limit` 个，其余只显示数量
+    ///
+    /// 目标不在当前历史中时，警告同时说明丢弃和带回的检查点数量。
+    pub fn print_discarded_checkpoints(
+        &self,
+        entries: &[CheckpointEntry],
+        divergence: AheadBehind,
+        limit: usize,
+    ) {
+        if entries.is_empty() && !divergence.diverged() {
+            return;
+        }
+        let warning = if divergence.diverged() {
+            t!(
+                "restore_diverged_warning",
+                ahead = divergence.ahead,
+                behind = divergence.behind
+            )
+        } else {
+            t!("restore_discard_warning", count = entries.len())
+        };
+        println!(
+            "{}{}",
+            style(Icon::Warning.prefix()).fg(Color::Yellow),
+            style(warning).fg(Color::Yellow)
+        );
+        for entry in entries.iter().take(limit) {
+            let datetime = DateTime::from_timestamp(entry.time, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "Unknown time".to_string());
             println!(
-                "{} {}",
-                style("⚠️").fg(Color::Yellow),
-                style("检测到未提交的更改。恢复检查点将会丢失这些更改。").fg(Color::Yellow)
+                "  {} {} {} {}",
+                style(Icon::Drop.glyph()).fg(Color::Red),
+                style(entry.short_hash()).fg(Color::Yellow).bold(),
+                style(datetime).fg(Color::Cyan),
+                style(ui::truncate_end(
+                    entry.summary(),
+                    ui::width().saturating_sub(33)
+                ))
+                .fg(Color::White)
             );
+        }
+        if entries.len() > limit {
             println!(
-                "{} {}",
-                style("💡").fg(Color::Blue),
-                style("建议先提交或暂存您的更改，然后再恢复检查点。").fg(Color::White)
+                "  {} {}",
+                style(Icon::Ellipsis.glyph()).dim(),
+                style(t!("restore_discard_more", count = entries.len() - limit)).dim()
             );
-            return Err(CheckpointError::UncommittedChanges);
         }
+        if !entries.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Hint.prefix()).fg(Color::Blue),
+                style(t!("restore_discard_hint")).fg(Color::White)
+            );
+        }
+    }
 
-        // 获取目标检查点信息，用于确认操作
-        let target_commit = self.git_ops.find_commit(hash)?;
-        let current_head = self.git_ops.get_head_commit()?;
-
-        // 检查是否会丢失后续提交
-        let commits_ahead = self.git_ops.count_commits_between(
-            &target_commit.id().to_string(),
-            &current_head.id().to_string(),
-        )?;
+    /// 显示恢复预览：将被修改的文件及增删行数，以及未跟踪文件的去留
+    ///
+    /// `clean` 为 true 时列出将被删除的未跟踪文件，否则提示它们会被保留。
+    pub fn show_restore_preview(&self, hash: &str, clean: bool) -> CcResult<()> {
+        let (full_hash, files, untracked) = self.read_ccg_branch(|git_ops| {
+            Ok((
+                git_ops.resolve_checkpoint(hash)?,
+                git_ops.restore_preview(hash)?,
+                git_ops.untracked_files()?,
+            ))
+        })?;
+        let short_hash = ui::short_hash(&full_hash);
 
-        if commits_ahead > 0 {
+        if files.is_empty() {
             println!(
-                "{} {} {} {}",
-                style("⚠️").fg(Color::Yellow),
-                style("警告: 此操作将丢失").fg(Color::Yellow),
-                style(commits_ahead.to_string()).fg(Color::Red).bold(),
-                style("个后续检查点").fg(Color::Yellow)
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                t!(
+                    "restore_preview_unchanged",
+                    hash = style(short_hash).fg(Color::Yellow).bold()
+                )
             );
+        } else {
+            Self::print_preview_files(short_hash, &files);
+        }
 
-            // 可以在这里添加确认提示，但现在直接执行
+        if untracked.is_empty() {
+            return Ok(());
+        }
+        if clean {
+            println!(
+                "{}{}",
+                style(Icon::Drop.prefix()).fg(Color::Red),
+                style(t!("restore_clean_untracked", count = untracked.len())).fg(Color::Red)
+            );
+            for path in &untracked {
+                println!("  {} {}", style("D").fg(Color::Red).bold(), path);
+            }
+            println!();
+        } else {
             println!(
-                "{} {}",
-                style("�").fg(Color::Red),
-                style("继续执行将永久丢失这些检查点!").fg(Color::Red).bold()
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!("restore_keep_untracked", count = untracked.len())).fg(Color::White)
             );
+            println!();
         }
+        Ok(())
+    }
 
-        println!(
-            "{} {} {}",
-            style("�").fg(Color::Blue),
-            style("恢复到检查点并重置分支:").fg(Color::White),
-            style(short_hash).fg(Color::Yellow).bold()
-        );
+    /// 逐块恢复时可选择的变更块
+    pub fn restore_hunks(&self, hash: &str) -> CcResult<Vec<RestoreHunk>> {
+        self.read_ccg_branch(|git_ops| git_ops.restore_hunks(hash))
+    }
 
-        // 执行硬重置操作 - 这是关键变化
-        self.git_ops.reset_branch_to_checkpoint(hash)?;
+    /// 将选中的变更块应用到工作目录，不移动分支、不修改索引
+    ///
+    /// `selected` 与 [`Self::restore_hunks`] 返回的变更块一一对应。
+    pub fn apply_restore_hunks(&self, hash: &str, selected: &[bool]) -> CcResult<usize> {
+        self.ensure_writable("restore")?;
+        self.execute_on_ccg_branch(|git_ops| git_ops.apply_restore_hunks(hash, selected))
+    }
 
+    /// 显示一个变更块，`+` 行来自检查点，`-` 行将从工作目录中移除
+    pub fn print_restore_hunk(&self, hunk: &RestoreHunk, index: usize, total: usize) {
         println!(
-            "{} {} {}",
-            style("✅").fg(Color::Green),
-            style("成功恢复到检查点:").fg(Color::Green).bold(),
-            style(short_hash).fg(Color::Yellow).bold()
+            "{}{} {}",
+            style(Icon::File.prefix()).fg(Color::Blue),
+            style(&hunk.path).fg(Color::Cyan).bold(),
+            style(format!("({}/{total})", index + 1)).dim()
         );
+        println!("{}", style(&hunk.header).fg(Color::Cyan));
+        for line in &hunk.lines {
+            match line.chars().next() {
+                Some('+') => println!("{}", style(line).fg(Color::Green)),
+                Some('-') => println!("{}", style(line).fg(Color::Red)),
+                _ => println!("{}", style(line).dim()),
+            }
+        }
+    }
 
-        // 显示当前状态信息
+    fn print_preview_files(short_hash: &str, files: &[FileChangeInfo]) {
+        let additions: i32 = files.iter().map(|f| f.additions).sum();
+        let deletions: i32 = files.iter().map(|f| f.deletions).sum();
         println!(
-            "{} {}",
-            style("📍").fg(Color::Blue),
-            style("ccg 分支已重置到指定检查点，后续提交已被丢弃").fg(Color::White)
+            "{}{} ({}, {}):",
+            style(Icon::Search.prefix()).fg(Color::Blue),
+            t!(
+                "restore_preview_header",
+                hash = style(short_hash).fg(Color::Yellow).bold(),
+                count = style(files.len()).fg(Color::Cyan).bold()
+            ),
+            style(format!("+{additions}")).fg(Color::Green),
+            style(format!("{}{deletions}", Icon::Minus.glyph())).fg(Color::Red)
         );
 
-        // 如果原始分支不是 ccg，提供切换提示
-        if original_branch != "ccg" {
+        // 路径列按最长路径对齐，但不超过终端宽度的一半
+        let path_width = files
+            .iter()
+            .map(|f| console::measure_text_width(&f.path))
+            .max()
+            .unwrap_or(0)
+            .min(ui::width() / 2);
+        for file in files {
+            let (status, color) = match file.status {
+                git2::Delta::Added => ("A", Color::Green),
+                git2::Delta::Deleted => ("D", Color::Red),
+                _ => ("M", Color::Yellow),
+            };
+            let mut counts = Vec::new();
+            if file.additions > 0 {
+                counts.push(
+                    style(format!("+{}", file.additions))
+                        .fg(Color::Green)
+                        .to_string(),
+                );
+            }
+            if file.deletions > 0 {
+                counts.push(
+                    style(format!("{}{}", Icon::Minus.glyph(), file.deletions))
+                        .fg(Color::Red)
+                        .to_string(),
+                );
+            }
             println!(
-                "{} {}",
-                style("💡").fg(Color::Yellow),
-                style("提示: 你现在在 ccg 分支上").fg(Color::White)
+                "  {} {}  {}",
+                style(status).fg(color).bold(),
+                ui::pad_end(&ui::truncate_start(&file.path, path_width), path_width),
+                counts.join(" ")
             );
+        }
+        println!();
+    }
+
+    /// 恢复摘要：列出被更新、删除和保留的路径
+    fn print_checkout_report(report: &CheckoutReport) {
+        let sections = [
+            (
+                &report.updated,
+                Icon::FileModified,
+                "restore_report_updated",
+                Color::Yellow,
+            ),
+            (
+                &report.removed,
+                Icon::Drop,
+                "restore_report_removed",
+                Color::Red,
+            ),
+            (
+                &report.skipped,
+                Icon::Info,
+                "restore_report_skipped",
+                Color::Blue,
+            ),
+            (
+                &report.unwritable,
+                Icon::Warning,
+                "restore_report_unwritable",
+                Color::Red,
+            ),
+        ];
+        for (paths, icon, key, color) in sections {
+            if paths.is_empty() {
+                continue;
+            }
             println!(
-                "  {} {} {}",
-                style("•").fg(Color::Blue),
-                style("使用 'git switch").fg(Color::White),
-                style(&format!("{original_branch}' 返回原始分支")).fg(Color::Cyan)
+                "{}{}",
+                style(icon.prefix()).fg(color),
+                t!(key, count = style(paths.len()).fg(color).bold())
             );
+            for path in paths {
+                println!("  {}", style(path).fg(color));
+            }
         }
-
-        Ok(())
     }
 
-    /// 显示检查点详情
-    pub fn show_checkpoint(&self, hash: &str, show_diff: bool) -> CcResult<()> {
-        self.execute_on_ccg_branch(|git_ops| {
-            // 先查找提交以获取完整hash和短hash显示
-            match git_ops.find_commit(hash) {
-                Ok(commit) => {
-                    let full_hash = commit.id().to_string();
-                    let short_hash = &full_hash[..7];
+    /// 检查恢复目标是否早于其所基于分支上的新提交
+    ///
+    /// 是则给出警告并返回 `StaleBase` 错误。
+    pub fn check_restore_base(&self, hash: &str) -> CcResult<()> {
+        self.read_ccg_branch(|git_ops| Self::guard_base(git_ops, hash, false))
+    }
 
-                    println!(
-                        "{} {} {}",
-                        style("📋").fg(Color::Blue),
-                        style("Checkpoint details for").fg(Color::White),
-                        style(short_hash).fg(Color::Yellow).bold()
-                    );
-                    println!();
+    fn guard_base(git_ops: &B, hash: &str, force: bool) -> CcResult<()> {
+        if force {
+            return Ok(());
+        }
+        let Some(drift) = git_ops.base_drift(hash)? else {
+            return Ok(());
+        };
+        Self::warn_base_drift(&drift);
+        Err(CheckpointError::StaleBase(format!(
+            "{} commits on {}",
+            drift.commits, drift.branch
+        )))
+    }
 
-                    let details = git_ops.show_checkpoint(hash, show_diff)?;
-                    println!("{details}");
-                    Ok(())
-                }
-                Err(CheckpointError::InvalidHash(msg)) => {
+    fn warn_base_drift(drift: &BaseDrift) {
+        println!(
+            "{}{}",
+            style(Icon::Warning.prefix()).fg(Color::Yellow),
+            t!(
+                "restore_base_drift",
+                count = style(drift.commits).fg(Color::Red).bold(),
+                branch = style(&drift.branch).fg(Color::Cyan).bold()
+            )
+        );
+        println!(
+            "{}{}",
+            style(Icon::Danger.prefix()).fg(Color::Red),
+            t!(
+                "restore_base_drift_detail",
+                base = style(ui::short_hash(&drift.base)).fg(Color::Yellow)
+            )
+        );
+        println!(
+            "{}{}",
+            style(Icon::Hint.prefix()).fg(Color::Blue),
+            style(t!("restore_base_drift_hint")).fg(Color::White)
+        );
+    }
+
+    /// 把检查点的文件写入 `dir`，不修改工作目录和 ccg 分支
+    ///
+    /// `dir` 不存在时创建；已存在且不为空时拒绝，避免覆盖其中的文件。
+    pub fn restore_to_directory(&self, hash: &str, dir: &Path) -> CcResult<usize> {
+        let occupied = dir
+            .read_dir()
+            .map(|mut entries| entries.next().is_some())
+            .unwrap_or(false);
+        if occupied || dir.is_file() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("restore_to_not_empty", dir = dir.display()).to_string(),
+            ));
+        }
+        let (target, count) = self.read_ccg_branch(|git_ops| {
+            let target = git_ops.resolve_checkpoint(hash)?;
+            let count = git_ops.export_checkpoint(&target, dir)?;
+            Ok((target, count))
+        })?;
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green).bold(),
+            t!(
+                "restore_to_done",
+                count = count,
+                hash = style(ui::short_hash(&target)).fg(Color::Yellow).bold(),
+                dir = style(dir.display()).fg(Color::Cyan)
+            )
+        );
+        Ok(count)
+    }
+
+    /// 在临时目录中写出检查点并在其中运行 `command`，不修改工作目录
+    ///
+    /// 命令直接继承终端的输入输出，环境变量 `CCG_CHECKPOINT` 为检查点的完整哈希。
+    /// 运行结束后删除临时目录，`keep` 为 true 时保留以便查看。
+    pub fn run_in_checkpoint(
+        &self,
+        hash: &str,
+        command: &[String],
+        keep: bool,
+    ) -> CcResult<ExitStatus> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| CheckpointError::InvalidArgument(t!("run_no_command").to_string()))?;
+        let target = self.resolve_checkpoint(hash)?;
+        let dir = std::env::temp_dir().join(format!(
+            "ccg-run-{}-{}",
+            ui::short_hash(&target),
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        self.restore_to_directory(&target, &dir)?;
+
+        println!(
+            "{}{}",
+            style(Icon::Switch.prefix()).fg(Color::Blue),
+            t!(
+                "run_starting",
+                command = style(command.join(" ")).fg(Color::Cyan),
+                hash = style(ui::short_hash(&target)).fg(Color::Yellow)
+            )
+        );
+        let status = std::process::Command::new(program)
+            .args(args)
+            .current_dir(&dir)
+            .env("CCG_CHECKPOINT", &target)
+            .status();
+        if keep {
+            println!(
+                "{}{}",
+                style(Icon::Hint.prefix()).fg(Color::Yellow),
+                t!("run_kept", dir = style(dir.display()).fg(Color::Cyan))
+            );
+        } else {
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+        let status = status?;
+
+        let code = status
+            .code()
+            .map(|code| code.to_string())
+            .unwrap_or_else(|| "signal".to_string());
+        if status.success() {
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!("run_exit", code = code)).fg(Color::Green)
+            );
+        } else {
+            println!(
+                "{}{}",
+                style(Icon::Error.prefix()).fg(Color::Red),
+                style(t!("run_exit", code = code)).fg(Color::Red)
+            );
+        }
+        Ok(status)
+    }
+
+    /// 恢复检查点 - 真正的时光机效果，丢弃后续提交
+    pub fn restore_checkpoint(&self, hash: &str) -> CcResult<()> {
+        self.restore_checkpoint_with(hash, RestoreOptions::default())
+            .map(|_| ())
+    }
+
+    /// 按指定选项恢复检查点，返回被更新、删除和保留的路径
+    pub fn restore_checkpoint_with(
+        &self,
+        hash: &str,
+        options: RestoreOptions,
+    ) -> CcResult<CheckoutReport> {
+        self.ensure_writable("restore")?;
+        let short_hash = ui::short_hash(hash);
+
+        // 确保在 ccg 分支上执行，并记录原始分支（分离 HEAD 时为提交 hash）
+        self.ensure_initialized()?;
+        let original_branch = self.git_ops.ensure_ccg_branch()?;
+
+        // 重置前失败时切回原始分支再返回错误
+        let abort = |e: CheckpointError| -> CcResult<CheckoutReport> {
+            if original_branch != CCG_BRANCH_NAME {
+                let _ = self.git_ops.restore_original_branch(&original_branch);
+            }
+            Err(e)
+        };
+
+        // 安全检查：检查是否有未提交的更改
+        let has_changes = match self.git_ops.has_uncommitted_changes() {
+            Ok(has_changes) => has_changes,
+            Err(e) => return abort(e),
+        };
+        if has_changes {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!("restore_uncommitted")).fg(Color::Yellow)
+            );
+            println!(
+                "{}{}",
+                style(Icon::Hint.prefix()).fg(Color::Blue),
+                style(t!("restore_uncommitted_hint")).fg(Color::White)
+            );
+            return abort(CheckpointError::UncommittedChanges);
+        }
+
+        // 获取目标检查点信息，用于确认操作
+        let lookup = self.git_ops.resolve_checkpoint(hash).and_then(|target| {
+            let head = self.git_ops.head_checkpoint()?;
+            // 检查是否会丢失后续提交
+            let ahead = self.git_ops.count_commits_between(&target, &head)?;
+            Ok((target, head, ahead))
+        });
+        let (target_commit, current_head, commits_ahead) = match lookup {
+            Ok(lookup) => lookup,
+            Err(e) => return abort(e),
+        };
+
+        // 团队权限：恢复会丢弃目标之后的检查点
+        let permitted = self
+            .git_ops
+            .checkpoint_entries(commits_ahead)
+            .and_then(|discarded| self.ensure_permitted("restore", &discarded));
+        if let Err(e) = permitted {
+            return abort(e);
+        }
+
+        // 检查点早于所基于分支上的新提交时，恢复会悄悄撤销这些提交的修改
+        if let Err(e) = Self::guard_base(&self.git_ops, &target_commit, options.force) {
+            return abort(e);
+        }
+
+        println!(
+            "{}{}",
+            style(Icon::Switch.prefix()).fg(Color::Blue),
+            t!(
+                "restore_resetting",
+                hash = style(short_hash).fg(Color::Yellow).bold()
+            )
+        );
+
+        let hook_context = json!({
+            "hash": target_commit,
+            "previous_head": current_head,
+            "original_branch": original_branch,
+            "discarded": commits_ahead,
+        });
+        let hooks = self.hook_runner();
+        if let Some(hooks) = &hooks
+            && let Err(e) = self.run_guard(
+                hooks,
+                HookEvent::PreRestore,
+                &hook_context,
+                &target_commit,
+                options.bypass_guard,
+            )
+        {
+            return abort(e);
+        }
+
+        // 执行硬重置操作 - 这是关键变化；未跟踪的文件默认保留，被忽略的文件始终保留。
+        // 有文件无法写入时在修改任何文件之前失败，工作目录保持原样
+        let report = match self.git_ops.reset_branch_to_checkpoint(
+            hash,
+            options.clean,
+            options.continue_on_error,
+        ) {
+            Ok(report) => report,
+            Err(e @ CheckpointError::PermissionDenied(_)) => {
+                println!(
+                    "{}{}",
+                    style(Icon::Hint.prefix()).fg(Color::Blue),
+                    style(t!("restore_continue_on_error_hint")).fg(Color::White)
+                );
+                return abort(e);
+            }
+            Err(e) => return abort(e),
+        };
+
+        // 记录恢复操作，被丢弃的检查点仍可从日志中找回；分支已重置，记录失败只警告
+        if let Err(e) = self
+            .git_ops
+            .record_restore(&target_commit, &current_head, commits_ahead)
+        {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!("restore_journal_failed", error = e)).fg(Color::Yellow)
+            );
+        }
+
+        if let Some(hooks) = &hooks {
+            hooks.run(HookEvent::PostRestore, &hook_context)?;
+        }
+
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            t!(
+                "restore_success",
+                hash = style(short_hash).fg(Color::Yellow).bold()
+            )
+        );
+        Self::print_checkout_report(&report);
+
+        // 显示当前状态信息
+        println!(
+            "{}{}",
+            style(Icon::Location.prefix()).fg(Color::Blue),
+            style(t!("restore_branch_reset")).fg(Color::White)
+        );
+
+        // 如果原始分支不是 ccg，提供切换提示
+        if original_branch != CCG_BRANCH_NAME {
+            println!(
+                "{}{}",
+                style(Icon::Hint.prefix()).fg(Color::Yellow),
+                style(t!("restore_on_ccg_branch")).fg(Color::White)
+            );
+            println!(
+                "  {} {}",
+                style(Icon::Dot.glyph()).fg(Color::Blue),
+                t!(
+                    "restore_switch_back",
+                    branch = style(&original_branch).fg(Color::Cyan)
+                )
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// 显示检查点详情
+    ///
+    /// 根检查点的差异默认只显示每个文件的统计，`full` 为 true 时显示完整差异。
+    pub fn show_checkpoint(
+        &self,
+        hash: &str,
+        show_diff: bool,
+        full: bool,
+        files: &[String],
+    ) -> CcResult<()> {
+        self.read_ccg_branch(|git_ops| {
+            // 先查找提交以获取完整hash和短hash显示
+            match git_ops.resolve_checkpoint(hash) {
+                Ok(full_hash) => {
+                    let short_hash = ui::short_hash(&full_hash);
+
+                    println!(
+                        "{}{} {}",
+                        style(Icon::List.prefix()).fg(Color::Blue),
+                        style("Checkpoint details for").fg(Color::White),
+                        style(short_hash).fg(Color::Yellow).bold()
+                    );
+                    println!();
+
+                    let mut out = std::io::stdout().lock();
+                    match git_ops.show_checkpoint(hash, show_diff, full, files, &mut out) {
+                        // 输出被管道提前关闭（如 `| head`）时视为正常结束
+                        Err(CheckpointError::IoError(e))
+                            if e.kind() == std::io::ErrorKind::BrokenPipe =>
+                        {
+                            Ok(())
+                        }
+                        other => other,
+                    }
+                }
+                Err(CheckpointError::InvalidHash(msg)) => {
                     // 如果是多个匹配的错误，直接显示错误信息
                     println!(
-                        "{} {}",
-                        style("❌").fg(Color::Red),
+                        "{}{}",
+                        style(Icon::Error.prefix()).fg(Color::Red),
                         style(&msg).fg(Color::Yellow)
                     );
                     Ok(())
@@ -309,11 +1462,471 @@ impl CheckpointService {
         })
     }
 
+    /// 输出文件在检查点中的完整内容
+    ///
+    /// 指定 `output` 时写入该文件而不修改工作目录；否则输出到标准输出，
+    /// `highlight` 为 true 时按扩展名进行语法高亮。二进制文件只能写入文件。
+    pub fn show_checkpoint_file(
+        &self,
+        hash: &str,
+        path: &str,
+        highlight: bool,
+        output: Option<&Path>,
+    ) -> CcResult<()> {
+        let content = self.read_ccg_branch(|git_ops| git_ops.checkpoint_file(hash, path))?;
+
+        if let Some(dest) = output {
+            if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(dest, &content)?;
+            println!(
+                "{}{} {} {} {}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style("Wrote").fg(Color::Green),
+                style(path).fg(Color::Cyan),
+                style("to").fg(Color::Green),
+                style(dest.display()).fg(Color::Cyan)
+            );
+            return Ok(());
+        }
+
+        // 二进制内容直接输出会破坏终端
+        if content.contains(&0) {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(format!("{path} is a binary file; use --output to save it"))
+                    .fg(Color::Yellow)
+            );
+            return Ok(());
+        }
+
+        let text = String::from_utf8_lossy(&content);
+        let rendered = if highlight {
+            ui::highlight::highlight(&text, path)
+        } else {
+            text.into_owned()
+        };
+        let mut out = std::io::stdout().lock();
+        match out.write_all(rendered.as_bytes()).and_then(|_| out.flush()) {
+            // 输出被管道提前关闭（如 `| head`）时视为正常结束
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+            other => Ok(other?),
+        }
+    }
+
+    /// 文件在各检查点中的版本（从旧到新），只保留内容有变化的检查点
+    ///
+    /// `since`/`until` 为 Unix 时间戳，用于限定检查点的创建时间范围。
+    /// 返回值第一项是范围之前的最后一个版本，用作第一个差异的比较基准。
+    pub fn file_evolution(
+        &self,
+        path: &str,
+        since: Option<i64>,
+        until: Option<i64>,
+    ) -> CcResult<(Option<FileVersion>, Vec<FileVersion>)> {
+        let history = self.read_ccg_branch(|git_ops| git_ops.file_history(path))?;
+        if history.is_empty() {
+            return Err(CheckpointError::InvalidArgument(format!(
+                "文件 '{path}' 不存在于任何检查点中"
+            )));
+        }
+        let mut before = None;
+        let mut versions = Vec::new();
+        for version in history {
+            let time = version.checkpoint.time;
+            if since.is_some_and(|since| time < since) {
+                before = Some(version);
+            } else if until.is_none_or(|until| time <= until) {
+                versions.push(version);
+            }
+        }
+        Ok((before, versions))
+    }
+
+    /// 逐个显示文件在各检查点中的版本
+    ///
+    /// 默认显示相对上一个版本的精简差异，`full` 为 true 时输出每个版本的完整内容。
+    pub fn show_file_evolution(
+        &self,
+        path: &str,
+        full: bool,
+        since: Option<i64>,
+        until: Option<i64>,
+    ) -> CcResult<()> {
+        let (mut previous, versions) = self.file_evolution(path, since, until)?;
+        if versions.is_empty() {
+            println!(
+                "{}No versions of {path} in the given time range",
+                Icon::Empty.prefix()
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{}{} {} ({} versions)",
+            style(Icon::List.prefix()).fg(Color::Blue),
+            style("Evolution of").fg(Color::Blue).bold(),
+            style(path).fg(Color::Cyan).bold(),
+            versions.len()
+        );
+        for version in &versions {
+            Self::print_version_header(version);
+            let old = previous.as_ref().and_then(|v| v.content.as_deref());
+            match &version.content {
+                None => println!("  {}", style("deleted").fg(Color::Red)),
+                Some(content) if content.contains(&0) => {
+                    println!("  {}", style("binary file").fg(Color::Yellow))
+                }
+                Some(content) if full => {
+                    print!("{}", String::from_utf8_lossy(content));
+                    if !content.ends_with(b"\n") {
+                        println!();
+                    }
+                }
+                Some(content) => Self::print_compact_diff(path, old, content)?,
+            }
+            previous = Some(version.clone());
+        }
+        Ok(())
+    }
+
+    fn print_version_header(version: &FileVersion) {
+        let entry = &version.checkpoint;
+        let datetime = DateTime::from_timestamp(entry.time, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "Unknown time".to_string());
+        println!(
+            "{}",
+            style(Icon::Separator.repeat(ui::width()))
+                .fg(Color::Blue)
+                .dim()
+        );
+        let id = entry
+            .display_id()
+            .map(|id| format!("{} ", style(id).fg(Color::Magenta)))
+            .unwrap_or_default();
+        println!(
+            "{} {id}{} {} {}",
+            style(Icon::Bullet.glyph()).fg(Color::Yellow).bold(),
+            style(entry.short_hash()).fg(Color::Yellow).bold(),
+            style(datetime).fg(Color::Cyan),
+            entry.summary()
+        );
+    }
+
+    /// 以一行上下文输出两个版本之间的差异，省略文件头
+    fn print_compact_diff(path: &str, old: Option<&[u8]>, new: &[u8]) -> CcResult<()> {
+        let mut opts = git2::DiffOptions::new();
+        opts.context_lines(1);
+        let patch = git2::Patch::from_buffers(
+            old.unwrap_or_default(),
+            Some(Path::new(path)),
+            new,
+            Some(Path::new(path)),
+            Some(&mut opts),
+        )?;
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, lines) = patch.hunk(hunk_idx)?;
+            let header = String::from_utf8_lossy(hunk.header());
+            println!("{}", style(header.trim_end()).fg(Color::Cyan));
+            for line_idx in 0..lines {
+                let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                let text = String::from_utf8_lossy(line.content());
+                let text = format!("{}{}", line.origin(), text.trim_end_matches('\n'));
+                match line.origin() {
+                    '+' => println!("{}", style(text).fg(Color::Green)),
+                    '-' => println!("{}", style(text).fg(Color::Red)),
+                    _ => println!("{}", style(text).dim()),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 最新检查点（ccg 分支顶端）的完整 hash
+    pub fn latest_checkpoint(&self) -> CcResult<String> {
+        self.read_ccg_branch(|git_ops| git_ops.head_checkpoint())
+    }
+
+    /// 最新检查点及其之后工作目录中的变更（包含未跟踪的文件）
+    pub fn checkpoint_status(&self) -> CcResult<CheckpointStatus> {
+        self.read_ccg_branch(|git_ops| {
+            let latest = git_ops
+                .checkpoint_entries(1)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| CheckpointError::CheckpointNotFound(CCG_BRANCH_NAME.to_string()))?;
+            let changes = git_ops.changes_since(&latest.hash)?;
+            Ok(CheckpointStatus { latest, changes })
+        })
+    }
+
+    /// 显示最新检查点以及之后工作目录中的变更，未跟踪的文件单独列出
+    pub fn show_status(&self) -> CcResult<()> {
+        let status = self.checkpoint_status()?;
+        let summary = status.latest.message.lines().next().unwrap_or_default();
+        println!(
+            "{}{} {} {}",
+            style(Icon::Location.prefix()).fg(Color::Blue),
+            style("Latest checkpoint:").fg(Color::White),
+            style(status.latest.short_hash()).fg(Color::Yellow).bold(),
+            summary
+        );
+
+        let (untracked, tracked): (Vec<_>, Vec<_>) = status
+            .changes
+            .iter()
+            .partition(|file| file.status == git2::Delta::Untracked);
+        if tracked.is_empty() && untracked.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style("Working directory matches the latest checkpoint").fg(Color::Green)
+            );
+            return Ok(());
+        }
+
+        if !tracked.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::List.prefix()).fg(Color::Blue),
+                style(format!("Changed since the checkpoint ({}):", tracked.len()))
+                    .fg(Color::White)
+                    .bold()
+            );
+            for file in tracked {
+                let (code, color) = match file.status {
+                    git2::Delta::Added => ("A", Color::Green),
+                    git2::Delta::Deleted => ("D", Color::Red),
+                    _ => ("M", Color::Yellow),
+                };
+                println!("  {} {}", style(code).fg(color).bold(), file.path);
+            }
+        }
+        if !untracked.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Search.prefix()).fg(Color::Green),
+                style(format!("Untracked files ({}):", untracked.len()))
+                    .fg(Color::White)
+                    .bold()
+            );
+            for file in untracked {
+                println!("  {} {}", style("?").fg(Color::Green).bold(), file.path);
+            }
+        }
+        Ok(())
+    }
+
+    /// 从远程获取检查点（`ccg fetch`），`depth` 只获取最近的若干个
+    ///
+    /// 远程分支保存在 `refs/remotes/<remote>/ccg`；本地 ccg 分支只做快进，
+    /// 双方都有对方没有的检查点时保持不变。
+    pub fn fetch_checkpoints(&self, remote: &str, depth: Option<u32>) -> CcResult<FetchReport> {
+        self.ensure_writable("fetch")?;
+        let report = self.git_ops.fetch_checkpoints(remote, depth)?;
+        if let (Some(tip), Some(update)) = (&report.tip, report.update) {
+            let short_tip = ui::short_hash(tip);
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!(
+                    "fetch_done",
+                    count = report.available,
+                    remote = report.remote
+                ))
+                .fg(Color::Green)
+            );
+            let (icon, color, line) = match update {
+                FetchUpdate::Created => (
+                    Icon::Info,
+                    Color::White,
+                    t!("fetch_created", tip = short_tip),
+                ),
+                FetchUpdate::FastForward => (
+                    Icon::Info,
+                    Color::White,
+                    t!("fetch_fast_forward", tip = short_tip),
+                ),
+                FetchUpdate::UpToDate => (Icon::Info, Color::White, t!("fetch_up_to_date")),
+                FetchUpdate::Diverged => (
+                    Icon::Warning,
+                    Color::Yellow,
+                    t!(
+                        "fetch_diverged",
+                        tracking = FetchOperations::tracking_ref(&report.remote)
+                    ),
+                ),
+            };
+            println!(
+                "{}{}",
+                style(icon.prefix()).fg(color),
+                style(line).fg(color)
+            );
+        }
+        if !report.users.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!(
+                    "fetch_users",
+                    users = report.users.join(", "),
+                    remote = report.remote
+                ))
+                .fg(Color::Green)
+            );
+        }
+        if !report.expired.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Drop.prefix()).fg(Color::Red),
+                style(t!("fetch_users_expired", users = report.expired.join(", ")))
+                    .fg(Color::White)
+            );
+        }
+        if report.shallow {
+            println!(
+                "{}{}",
+                style(Icon::Hint.prefix()).fg(Color::Blue),
+                style(t!("fetch_shallow_note")).fg(Color::White)
+            );
+        }
+        Ok(report)
+    }
+
+    /// 把 ccg 分支发布为自己在远程上的命名空间（`ccg push`），返回命名空间名称
+    pub fn publish_checkpoints(&self, remote: &str) -> CcResult<String> {
+        let user = self.git_ops.publish_checkpoints(remote)?;
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            style(t!("push_done", user = user, remote = remote)).fg(Color::Green)
+        );
+        Ok(user)
+    }
+
+    /// 显示检查点占用的存储，`dedupe` 为 true 时还遍历对象图，区分与其他分支共享的内容
+    pub fn show_storage_stats(&self, dedupe: bool) -> CcResult<()> {
+        let usage = self.git_ops.storage_usage()?;
+        println!(
+            "{}{}",
+            style(Icon::Stats.prefix()).fg(Color::Blue),
+            style(t!("stats_header")).fg(Color::White).bold()
+        );
+        println!("  {}", t!("stats_checkpoints", count = usage.checkpoints));
+        println!(
+            "  {}",
+            t!("stats_recorded", size = ui::format_size(usage.bytes))
+        );
+        if let Some(repack) = self.git_ops.last_repack()? {
+            let time = DateTime::from_timestamp(repack.time, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            println!(
+                "  {}",
+                t!(
+                    "stats_repack",
+                    time = time,
+                    count = repack.objects,
+                    loose = ui::format_size(repack.loose_bytes),
+                    packed = ui::format_size(repack.packed_bytes),
+                    percent = format!("{:.1}", repack.saved_percent())
+                )
+            );
+        }
+        if !dedupe {
+            return Ok(());
+        }
+
+        let report = self.git_ops.dedupe_report()?;
+        println!(
+            "  {}",
+            t!(
+                "stats_logical",
+                size = ui::format_size(report.logical_bytes)
+            )
+        );
+        println!(
+            "  {}",
+            t!(
+                "stats_shared",
+                count = report.shared_objects,
+                size = ui::format_size(report.shared_bytes)
+            )
+        );
+        println!(
+            "  {}",
+            t!(
+                "stats_unique",
+                count = report.unique_objects,
+                size = ui::format_size(report.unique_bytes)
+            )
+        );
+        if report.logical_bytes > 0 {
+            let percent = report.unique_bytes as f64 * 100.0 / report.logical_bytes as f64;
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!(
+                    "stats_saving",
+                    size = ui::format_size(report.unique_bytes),
+                    percent = format!("{percent:.1}")
+                ))
+                .fg(Color::Green)
+            );
+        }
+        Ok(())
+    }
+
+    /// 把多次变更的大文件的松散对象打包为增量，返回打包结果
+    ///
+    /// 只处理 ccg 分支上不小于 `settings.min_size` 且至少有两个松散版本的文件，
+    /// 已打包的对象留给 `git gc`。
+    pub fn repack_large_files(&self, settings: &RepackSettings) -> CcResult<RepackReport> {
+        self.ensure_writable("repack")?;
+        let report = self.git_ops.repack_large_files(settings)?;
+        if report.objects == 0 {
+            println!(
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!(
+                    "repack_nothing",
+                    size = ui::format_size(settings.min_size)
+                ))
+                .fg(Color::White)
+            );
+            return Ok(report);
+        }
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            style(t!(
+                "repack_done",
+                count = report.objects,
+                paths = report.paths,
+                loose = ui::format_size(report.loose_bytes),
+                packed = ui::format_size(report.packed_bytes),
+                percent = format!("{:.1}", report.saved_percent())
+            ))
+            .fg(Color::Green)
+        );
+        Ok(report)
+    }
+
     /// 比较检查点差异
-    pub fn diff_checkpoints(&self, hash_a: &str, hash_b: Option<&str>) -> CcResult<()> {
-        self.execute_on_ccg_branch(|git_ops| {
+    ///
+    /// 未指定 `hash_a` 时比较最新检查点与当前工作目录，即“上个检查点之后改了什么”。
+    pub fn diff_checkpoints(&self, hash_a: Option<&str>, hash_b: Option<&str>) -> CcResult<()> {
+        self.read_ccg_branch(|git_ops| {
+            let latest = match hash_a {
+                Some(_) => None,
+                None => Some(git_ops.head_checkpoint()?),
+            };
+            let hash_a = hash_a.or(latest.as_deref()).unwrap_or_default();
             let short_hash_a = if hash_a.len() >= 7 {
-                &hash_a[..7]
+                ui::short_hash(hash_a)
             } else {
                 hash_a
             };
@@ -321,22 +1934,31 @@ impl CheckpointService {
 
             if let Some(hash_b) = hash_b {
                 let short_hash_b = if hash_b.len() >= 7 {
-                    &hash_b[..7]
+                    ui::short_hash(hash_b)
                 } else {
                     hash_b
                 };
                 println!(
-                    "{} {} {} {} {}",
-                    style("🔍").fg(Color::Blue),
+                    "{}{} {} {} {}",
+                    style(Icon::Search.prefix()).fg(Color::Blue),
                     style("Differences between").fg(Color::White),
                     style(short_hash_a).fg(Color::Yellow).bold(),
                     style("and").fg(Color::White),
                     style(short_hash_b).fg(Color::Yellow).bold()
                 );
+            } else if latest.is_some() {
+                println!(
+                    "{}{} {} {} {}",
+                    style(Icon::Search.prefix()).fg(Color::Blue),
+                    style("Changes since the latest checkpoint").fg(Color::White),
+                    style(short_hash_a).fg(Color::Yellow).bold(),
+                    style("in").fg(Color::White),
+                    style("working directory").fg(Color::Cyan)
+                );
             } else {
                 println!(
-                    "{} {} {} {} {}",
-                    style("🔍").fg(Color::Blue),
+                    "{}{} {} {} {}",
+                    style(Icon::Search.prefix()).fg(Color::Blue),
                     style("Differences between").fg(Color::White),
                     style(short_hash_a).fg(Color::Yellow).bold(),
                     style("and").fg(Color::White),
@@ -349,16 +1971,1169 @@ impl CheckpointService {
         })
     }
 
-    /// 清理旧检查点
-    pub fn prune_checkpoints(&self, keep: Option<usize>, before: Option<&str>) -> CcResult<()> {
+    /// 比较检查点与磁盘上的任意目录（如部署目录或另一个克隆）
+    ///
+    /// 未指定 `hash` 时使用最新检查点。
+    pub fn diff_directory(&self, hash: Option<&str>, dir: &Path) -> CcResult<()> {
+        let (target, diff) = self.read_ccg_branch(|git_ops| {
+            let target = match hash {
+                Some(hash) => git_ops.resolve_checkpoint(hash)?,
+                None => git_ops.head_checkpoint()?,
+            };
+            let diff = git_ops.diff_directory(&target, dir)?;
+            Ok((target, diff))
+        })?;
+        println!(
+            "{}{} {} {} {}",
+            style(Icon::Search.prefix()).fg(Color::Blue),
+            style("Differences between").fg(Color::White),
+            style(ui::short_hash(&target)).fg(Color::Yellow).bold(),
+            style("and").fg(Color::White),
+            style(dir.display()).fg(Color::Cyan)
+        );
+        println!();
+        println!("{diff}");
+        Ok(())
+    }
+
+    /// 分析检查点对后续检查点的影响，帮助判断 revert 还是 restore 更安全
+    pub fn checkpoint_impact(&self, hash: &str) -> CcResult<ImpactReport> {
+        self.read_ccg_branch(|git_ops| {
+            let target = git_ops.resolve_checkpoint(hash)?;
+            git_ops.checkpoint_impact(&target)
+        })
+    }
+
+    /// 撤销单个检查点引入的变更，并创建新的检查点
+    ///
+    /// 后续检查点保持不变；与后续变更或未提交的修改冲突时不做任何改动。
+    pub fn revert_checkpoint(&self, hash: &str) -> CcResult<String> {
+        self.ensure_writable("revert")?;
         self.execute_on_ccg_branch(|git_ops| {
-            git_ops.prune_checkpoints(keep, before)?;
+            let target = git_ops.resolve_checkpoint(hash)?;
+            match git_ops.revert_checkpoint(&target) {
+                Ok(new_hash) => {
+                    println!(
+                        "{}{}",
+                        style(Icon::Revert.prefix()).fg(Color::Blue),
+                        t!(
+                            "revert_done",
+                            target = style(ui::short_hash(&target)).fg(Color::Yellow),
+                            hash = style(ui::short_hash(&new_hash)).fg(Color::Yellow).bold()
+                        )
+                    );
+                    Ok(new_hash)
+                }
+                Err(CheckpointError::Conflict(paths)) => {
+                    println!(
+                        "{}{}",
+                        style(Icon::Error.prefix()).fg(Color::Red),
+                        style(t!("revert_conflict")).fg(Color::Red)
+                    );
+                    println!("  {}", style(&paths).fg(Color::Yellow));
+                    println!(
+                        "{}{}",
+                        style(Icon::Hint.prefix()).fg(Color::Yellow),
+                        style(t!("revert_conflict_hint")).fg(Color::White)
+                    );
+                    Err(CheckpointError::Conflict(paths))
+                }
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    /// 开始任务，之后创建的检查点都记录任务 ID，直到 `end_task`
+    ///
+    /// 任务 ID 由名称生成，与已有检查点中的任务重复时追加序号。
+    pub fn start_task(&self, name: &str) -> CcResult<Task> {
+        self.ensure_writable("task")?;
+        let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        if name.is_empty() {
+            return Err(CheckpointError::InvalidArgument(
+                t!("task_empty_name").to_string(),
+            ));
+        }
+        if let Some(active) = self.git_ops.active_task()? {
+            return Err(CheckpointError::InvalidArgument(
+                t!("task_already_active", id = active.id).to_string(),
+            ));
+        }
+
+        let used: HashSet<String> = self
+            .checkpoints(usize::MAX)?
+            .iter()
+            .filter_map(CheckpointEntry::task)
+            .collect();
+        let slug = task_slug(&name);
+        let id = (1..)
+            .map(|n| match n {
+                1 => slug.clone(),
+                n => format!("{slug}-{n}"),
+            })
+            .find(|id| !used.contains(id))
+            .unwrap_or(slug);
+        let task = Task { id, name };
+        self.git_ops.set_active_task(Some(&task))?;
+        println!(
+            "{}{} {}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            style(t!("task_started", name = &task.name)).fg(Color::Green),
+            style(&task.id).fg(Color::Cyan).bold()
+        );
+        Ok(task)
+    }
+
+    /// 结束进行中的任务
+    pub fn end_task(&self) -> CcResult<Task> {
+        let task = self
+            .git_ops
+            .active_task()?
+            .ok_or_else(|| CheckpointError::InvalidArgument(t!("task_none_active").to_string()))?;
+        self.git_ops.set_active_task(None)?;
+        let count = self
+            .checkpoints(usize::MAX)?
+            .iter()
+            .filter(|entry| entry.task().as_deref() == Some(task.id.as_str()))
+            .count();
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            style(t!("task_ended", id = &task.id, count = count)).fg(Color::Green)
+        );
+        Ok(task)
+    }
+
+    /// 把工作目录的修改（包括未跟踪的文件）暂存为快照，并把工作目录恢复为 HEAD
+    ///
+    /// 快照不在检查点时间线上，未提供说明时使用 `WIP on <分支>`。
+    pub fn push_snapshot(&self, message: Option<&str>) -> CcResult<Snapshot> {
+        self.ensure_writable("snapshot push")?;
+        let message = match message {
+            Some(message) => message.to_string(),
+            None => format!("WIP on {}", self.git_ops.get_current_branch_name()?),
+        };
+        let snapshot = match self.git_ops.push_snapshot(&message) {
+            Err(CheckpointError::NoChangesToCommit) => {
+                return Err(CheckpointError::InvalidArgument(
+                    t!("snapshot_nothing_to_save").to_string(),
+                ));
+            }
+            result => result?,
+        };
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            style(t!(
+                "snapshot_pushed",
+                number = snapshot.number,
+                message = &snapshot.message
+            ))
+            .fg(Color::Green)
+        );
+        Ok(snapshot)
+    }
+
+    /// 把快照（默认最新的）合并回工作目录并删除它
+    pub fn pop_snapshot(&self, number: Option<usize>) -> CcResult<Snapshot> {
+        self.ensure_writable("snapshot pop")?;
+        let snapshot = self.git_ops.pop_snapshot(number)?;
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            style(t!(
+                "snapshot_popped",
+                number = snapshot.number,
+                message = &snapshot.message
+            ))
+            .fg(Color::Green)
+        );
+        Ok(snapshot)
+    }
+
+    /// 列出快照，最新的在前
+    pub fn list_snapshots(&self) -> CcResult<Vec<Snapshot>> {
+        let snapshots = self.git_ops.list_snapshots()?;
+        if snapshots.is_empty() {
             println!(
-                "{} {}",
-                style("🗑️").fg(Color::Red),
-                style("Pruned old checkpoints.").fg(Color::Green).bold()
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!("snapshot_list_empty")).fg(Color::White)
             );
-            Ok(())
+            return Ok(snapshots);
+        }
+        for snapshot in &snapshots {
+            let datetime = DateTime::from_timestamp(snapshot.time, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "Unknown time".to_string());
+            println!(
+                "  {} {} {} {} {}",
+                style(Icon::Dot.glyph()).fg(Color::Blue),
+                style(format!("#{}", snapshot.number))
+                    .fg(Color::Magenta)
+                    .bold(),
+                style(ui::short_hash(&snapshot.hash)).fg(Color::Yellow),
+                style(datetime).fg(Color::Cyan),
+                style(&snapshot.message).fg(Color::White)
+            );
+        }
+        Ok(snapshots)
+    }
+
+    /// 任务的检查点及其起点
+    ///
+    /// 没有检查点记录该任务时返回 `CheckpointNotFound`。
+    pub fn task_range(&self, id: &str) -> CcResult<TaskRange> {
+        self.read_ccg_branch(|git_ops| {
+            let checkpoints: Vec<CheckpointEntry> = git_ops
+                .checkpoint_entries(usize::MAX)?
+                .into_iter()
+                .filter(|entry| entry.task().as_deref() == Some(id))
+                .collect();
+            let (Some(tip), Some(first)) = (checkpoints.first(), checkpoints.last()) else {
+                return Err(CheckpointError::CheckpointNotFound(
+                    t!("task_not_found", id = id).to_string(),
+                ));
+            };
+            let base = git_ops.checkpoint_parent(&first.hash)?.ok_or_else(|| {
+                CheckpointError::InvalidArgument(t!("task_no_base", id = id).to_string())
+            })?;
+            let tip = tip.hash.clone();
+            Ok(TaskRange {
+                base,
+                tip,
+                checkpoints,
+            })
         })
     }
+
+    /// 所有检查点的元数据，从旧到新
+    ///
+    /// 行数统计取自元数据索引，缺失的检查点先计算并补记到索引中。
+    pub fn checkpoint_metadata(&self) -> CcResult<Vec<CheckpointMetadata>> {
+        self.read_ccg_branch(|git_ops| {
+            let mut entries = git_ops.checkpoint_entries(usize::MAX)?;
+            entries.reverse();
+            let hashes: Vec<String> = entries.iter().map(|entry| entry.hash.clone()).collect();
+            let stats = git_ops.checkpoint_stats(&hashes)?;
+            Ok(entries
+                .into_iter()
+                .zip(stats)
+                .map(|(entry, stats)| CheckpointMetadata { entry, stats })
+                .collect())
+        })
+    }
+
+    /// 满足查询的检查点，从新到旧
+    ///
+    /// 先按元数据索引中的信息筛选，剩下的检查点才读取改动的文件和备注。
+    pub fn search_checkpoints(&self, query: &CheckpointQuery) -> CcResult<Vec<CheckpointMetadata>> {
+        self.read_ccg_branch(|git_ops| {
+            let entries = git_ops.checkpoint_entries(usize::MAX)?;
+            let hashes: Vec<String> = entries.iter().map(|entry| entry.hash.clone()).collect();
+            let stats = git_ops.checkpoint_stats(&hashes)?;
+            let mut matches = Vec::new();
+            for (entry, stats) in entries.into_iter().zip(stats) {
+                let metadata = CheckpointMetadata { entry, stats };
+                if !query.matches_metadata(&metadata) {
+                    continue;
+                }
+                let hash = &metadata.entry.hash;
+                let files: Vec<String> = if query.needs_files() {
+                    let parent = git_ops.checkpoint_parent(hash)?;
+                    git_ops
+                        .file_patches(parent.as_deref(), hash)?
+                        .into_iter()
+                        .map(|file| file.change.path)
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                let note = if query.needs_note() {
+                    git_ops.checkpoint_note(hash)?
+                } else {
+                    None
+                };
+                if query.matches(&metadata, &files, note.as_deref()) {
+                    matches.push(metadata);
+                }
+            }
+            Ok(matches)
+        })
+    }
+
+    /// 会话的报告：检查点时间线、每个检查点改动的文件和会话的整体变更
+    ///
+    /// 未指定会话时使用最新的记录了会话的检查点所属的会话。整体变更从会话的
+    /// 第一个检查点之前算到最后一个检查点，期间其他检查点的变更也包含在内。
+    pub fn session_report(&self, session: Option<&str>) -> CcResult<SessionReport> {
+        self.read_ccg_branch(|git_ops| {
+            let entries = git_ops.checkpoint_entries(usize::MAX)?;
+            let session = match session {
+                Some(session) => session.to_string(),
+                None => entries
+                    .iter()
+                    .find_map(CheckpointEntry::session)
+                    .ok_or_else(|| {
+                        CheckpointError::CheckpointNotFound(t!("report_no_sessions").to_string())
+                    })?,
+            };
+            let mut entries: Vec<CheckpointEntry> = entries
+                .into_iter()
+                .filter(|entry| entry.session().as_deref() == Some(session.as_str()))
+                .collect();
+            entries.reverse();
+            let (Some(first), Some(last)) = (entries.first(), entries.last()) else {
+                return Err(CheckpointError::CheckpointNotFound(
+                    t!("report_session_not_found", session = &session).to_string(),
+                ));
+            };
+            let base = git_ops.checkpoint_parent(&first.hash)?;
+            let files = git_ops.file_patches(base.as_deref(), &last.hash)?;
+
+            let mut checkpoints = Vec::new();
+            for entry in entries {
+                let parent = git_ops.checkpoint_parent(&entry.hash)?;
+                let files = git_ops
+                    .file_patches(parent.as_deref(), &entry.hash)?
+                    .into_iter()
+                    .map(|file| file.change)
+                    .collect();
+                checkpoints.push(ReportCheckpoint { entry, files });
+            }
+            Ok(SessionReport {
+                session,
+                checkpoints,
+                files,
+            })
+        })
+    }
+
+    /// `since` 之后创建的检查点，以及时间窗口开始时的状态
+    ///
+    /// 窗口是时间线上最新的一段连续检查点；基准是其中最早一个的父提交，
+    /// 即窗口之前最后的检查点。窗口内没有检查点时返回 None。
+    pub fn since_range(&self, since: i64) -> CcResult<Option<TaskRange>> {
+        self.read_ccg_branch(|git_ops| {
+            let checkpoints: Vec<CheckpointEntry> = git_ops
+                .checkpoint_entries(usize::MAX)?
+                .into_iter()
+                .take_while(|entry| entry.time >= since)
+                .collect();
+            let (Some(tip), Some(first)) = (checkpoints.first(), checkpoints.last()) else {
+                return Ok(None);
+            };
+            let base = git_ops.checkpoint_parent(&first.hash)?.ok_or_else(|| {
+                CheckpointError::InvalidArgument(t!("diff_since_no_base").to_string())
+            })?;
+            let tip = tip.hash.clone();
+            Ok(Some(TaskRange {
+                base,
+                tip,
+                checkpoints,
+            }))
+        })
+    }
+
+    /// 显示 `since` 之后所有检查点合并后的差异
+    pub fn diff_since(&self, since: i64) -> CcResult<()> {
+        let start = DateTime::from_timestamp(since, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let Some(range) = self.since_range(since)? else {
+            println!(
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!("diff_since_empty", since = start)).fg(Color::White)
+            );
+            return Ok(());
+        };
+        let diff = self
+            .read_ccg_branch(|git_ops| git_ops.diff_checkpoints(&range.base, Some(&range.tip)))?;
+        println!(
+            "{}{} {}",
+            style(Icon::Search.prefix()).fg(Color::Blue),
+            style(t!("diff_since_header", count = range.checkpoints.len())).fg(Color::White),
+            style(start).fg(Color::Cyan).bold()
+        );
+        println!();
+        println!("{diff}");
+        Ok(())
+    }
+
+    /// 比较最新检查点与当前分支 HEAD 的文件，并显示偏离报告
+    pub fn show_drift(&self) -> CcResult<BranchDrift> {
+        let drift = self.git_ops.branch_drift()?;
+        print!("{}", crate::services::drift::render(&drift));
+        Ok(drift)
+    }
+
+    /// 显示任务所有检查点合并后的差异
+    pub fn diff_task(&self, id: &str) -> CcResult<()> {
+        let range = self.task_range(id)?;
+        let diff = self
+            .read_ccg_branch(|git_ops| git_ops.diff_checkpoints(&range.base, Some(&range.tip)))?;
+        println!(
+            "{}{} {}",
+            style(Icon::Search.prefix()).fg(Color::Blue),
+            style(t!("task_diff_header", count = range.checkpoints.len())).fg(Color::White),
+            style(id).fg(Color::Cyan).bold()
+        );
+        println!();
+        println!("{diff}");
+        Ok(())
+    }
+
+    /// 把任务的所有检查点压缩为当前分支上的一个提交
+    ///
+    /// 未指定 `message` 时使用任务名称，正文列出被压缩的检查点。
+    /// `skip_format_only` 时只修改空白的检查点不包含在提交中。
+    pub fn promote_task(
+        &self,
+        id: &str,
+        message: Option<&str>,
+        skip_format_only: bool,
+    ) -> CcResult<String> {
+        self.ensure_writable("promote")?;
+        if self.git_ops.get_current_branch_name()? == CCG_BRANCH_NAME {
+            return Err(CheckpointError::InvalidArgument(
+                t!("promote_on_ccg").to_string(),
+            ));
+        }
+        let range = self.task_range(id)?;
+        // 与 range.checkpoints 一样从新到旧排列
+        let mut skipped = Vec::new();
+        if skip_format_only {
+            for entry in &range.checkpoints {
+                if self.git_ops.is_format_only(&entry.hash)? {
+                    skipped.push(entry.hash.clone());
+                }
+            }
+        }
+        let kept: Vec<&CheckpointEntry> = range
+            .checkpoints
+            .iter()
+            .filter(|entry| !skipped.contains(&entry.hash))
+            .collect();
+        let message = match message {
+            Some(message) => message.to_string(),
+            None => {
+                let locale = self.git_ops.message_locale();
+                let title = range.checkpoints[0]
+                    .task_name()
+                    .unwrap_or_else(|| id.to_string());
+                let mut message = format!(
+                    "{title}\n\n{}\n",
+                    t!(
+                        "message_task_squash",
+                        locale = &locale,
+                        count = kept.len(),
+                        id = id
+                    )
+                );
+                for entry in kept.iter().rev() {
+                    message.push_str(&format!("- {}\n", entry.summary()));
+                }
+                message
+            }
+        };
+
+        match self
+            .git_ops
+            .squash_onto_head(&range.base, &range.tip, &skipped, &message)
+        {
+            Ok(hash) => {
+                if !skipped.is_empty() {
+                    println!(
+                        "{}{}",
+                        style(Icon::Info.prefix()).fg(Color::Blue),
+                        style(t!("promote_skipped_format_only", count = skipped.len()))
+                            .fg(Color::White)
+                    );
+                }
+                println!(
+                    "{}{} {}",
+                    style(Icon::Success.prefix()).fg(Color::Green).bold(),
+                    style(t!("promote_done", count = kept.len(), id = id))
+                        .fg(Color::Green)
+                        .bold(),
+                    style(ui::short_hash(&hash)).fg(Color::Yellow).bold()
+                );
+                Ok(hash)
+            }
+            Err(CheckpointError::Conflict(paths)) => {
+                println!(
+                    "{}{}",
+                    style(Icon::Error.prefix()).fg(Color::Red),
+                    style(t!("promote_conflict")).fg(Color::Red)
+                );
+                println!("  {}", style(&paths).fg(Color::Yellow));
+                Err(CheckpointError::Conflict(paths))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 在 ccg 分支上将短 hash 解析为完整 hash
+    pub fn resolve_checkpoint(&self, hash: &str) -> CcResult<String> {
+        self.read_ccg_branch(|git_ops| git_ops.resolve_checkpoint(hash))
+    }
+
+    /// 修改检查点的提交信息
+    ///
+    /// 不是最新检查点时，后续检查点会以相同的文件树重放到新提交之上。
+    pub fn reword_checkpoint(&self, hash: &str, message: &str) -> CcResult<RewrittenHistory> {
+        self.ensure_writable("reword")?;
+        let message = message.trim();
+        if message.is_empty() {
+            return Err(CheckpointError::InvalidArgument(t!("reword_empty_message")));
+        }
+
+        // 在 ccg 分支上解析短 hash
+        let (old_hash, history) = self.execute_on_ccg_branch(|git_ops| {
+            let old_hash = git_ops.resolve_checkpoint(hash)?;
+            self.ensure_permitted("reword", &self.rewritten_entries(git_ops, &old_hash)?)?;
+            let history = git_ops.reword_checkpoint(&old_hash, message)?;
+            Ok((old_hash, history))
+        })?;
+        let new_hash = history
+            .replacements
+            .last()
+            .map(String::as_str)
+            .unwrap_or_default();
+
+        println!(
+            "{}{}",
+            style(Icon::Reword.prefix()).fg(Color::Blue),
+            t!(
+                "reword_done",
+                old = style(ui::short_hash(&old_hash)).fg(Color::Yellow),
+                arrow = style(Icon::Arrow.glyph()).fg(Color::White),
+                new = style(ui::short_hash(new_hash)).fg(Color::Yellow).bold()
+            )
+        );
+        if !history.replayed.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Replay.prefix()).fg(Color::Blue),
+                t!(
+                    "rewrite_replayed",
+                    count = style(history.replayed.len()).fg(Color::Cyan).bold()
+                )
+            );
+        }
+
+        Ok(history)
+    }
+
+    /// 改写 `hash` 时会被替换的检查点：它本身和之后重放的检查点
+    fn rewritten_entries(&self, git_ops: &B, hash: &str) -> CcResult<Vec<CheckpointEntry>> {
+        let head = git_ops.head_checkpoint()?;
+        let count = git_ops.count_commits_between(hash, &head)?;
+        git_ops.checkpoint_entries(count + 1)
+    }
+
+    /// 将检查点按文件拆分为多个连续的检查点
+    ///
+    /// 后续检查点会以相同的文件树重放到拆分结果之上。
+    pub fn split_checkpoint(&self, hash: &str) -> CcResult<RewrittenHistory> {
+        self.ensure_writable("split")?;
+        let (old_hash, history) = self.execute_on_ccg_branch(|git_ops| {
+            let old_hash = git_ops.resolve_checkpoint(hash)?;
+            self.ensure_permitted("split", &self.rewritten_entries(git_ops, &old_hash)?)?;
+            let history = git_ops.split_checkpoint(&old_hash)?;
+            Ok((old_hash, history))
+        })?;
+
+        println!(
+            "{}{}",
+            style(Icon::Split.prefix()).fg(Color::Blue),
+            t!(
+                "split_done",
+                hash = style(ui::short_hash(&old_hash)).fg(Color::Yellow),
+                count = style(history.replacements.len()).fg(Color::Cyan).bold()
+            )
+        );
+        for hash in &history.replacements {
+            println!(
+                "  {} {}",
+                style(Icon::Dot.glyph()).fg(Color::Blue),
+                style(ui::short_hash(hash)).fg(Color::Yellow)
+            );
+        }
+        if !history.replayed.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Replay.prefix()).fg(Color::Blue),
+                t!(
+                    "rewrite_replayed",
+                    count = style(history.replayed.len()).fg(Color::Cyan).bold()
+                )
+            );
+        }
+
+        Ok(history)
+    }
+
+    /// 把当前分支（或 `onto`）不包含的检查点移到它的最新提交之上
+    ///
+    /// 默认保留每个检查点的文件树，只更换父提交；`replay` 时把每个检查点的
+    /// 变更应用到新基准上，冲突时 ccg 分支保持不变。
+    pub fn rebase_checkpoints(
+        &self,
+        onto: Option<&str>,
+        replay: bool,
+    ) -> CcResult<RewrittenHistory> {
+        self.ensure_writable("rebase")?;
+        let current = self.git_ops.get_current_branch_name()?;
+        // 在 ccg 分支上重放会改变已检出的文件树
+        let onto = match onto {
+            _ if replay && current == CCG_BRANCH_NAME => None,
+            Some(onto) => Some(onto.to_string()),
+            None if current != CCG_BRANCH_NAME => Some(current),
+            None => None,
+        }
+        .ok_or_else(|| CheckpointError::InvalidArgument(t!("rebase_on_ccg").to_string()))?;
+
+        let moved = self.git_ops.rebase_candidates(&onto)?;
+        self.ensure_permitted("rebase", &moved)?;
+        if moved.is_empty() {
+            println!(
+                "{}{}",
+                style(Icon::Success.prefix()).fg(Color::Green),
+                style(t!("rebase_up_to_date", onto = &onto)).fg(Color::Green)
+            );
+            return self
+                .git_ops
+                .rebase_checkpoints(&onto, replay, &mut |_, _, _| {});
+        }
+
+        println!(
+            "{}{}",
+            style(Icon::Replay.prefix()).fg(Color::Blue),
+            t!(
+                "rebase_start",
+                count = style(moved.len()).fg(Color::Cyan).bold(),
+                onto = style(&onto).fg(Color::Cyan).bold()
+            )
+        );
+        let history =
+            self.git_ops
+                .rebase_checkpoints(&onto, replay, &mut |position, total, hash| {
+                    let summary = moved
+                        .iter()
+                        .find(|entry| entry.hash == hash)
+                        .map(CheckpointEntry::summary)
+                        .unwrap_or_default();
+                    println!(
+                        "  {} {} {}",
+                        style(format!("[{position}/{total}]")).dim(),
+                        style(ui::short_hash(hash)).fg(Color::Yellow),
+                        summary
+                    );
+                })?;
+        println!(
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            t!(
+                "rebase_done",
+                count = history.replayed.len(),
+                tip = style(ui::short_hash(&history.tip)).fg(Color::Yellow).bold()
+            )
+        );
+        Ok(history)
+    }
+
+    /// 按 `keep`（保留最新的几个）、`before`（早于此时间）和 `strategy` 选出要清理的
+    /// 检查点，从新到旧
+    ///
+    /// 给出多个条件时满足其一即被选中，受保护的检查点（如里程碑）也包括在内，由
+    /// [`prune_checkpoints`](Self::prune_checkpoints) 跳过。
+    pub fn prune_candidates(
+        &self,
+        keep: Option<usize>,
+        before: Option<&str>,
+        strategy: Option<PruneStrategy>,
+    ) -> CcResult<Vec<CheckpointEntry>> {
+        let cutoff = before
+            .map(crate::commands::evolution::parse_time)
+            .transpose()?;
+        self.read_ccg_branch(|git_ops| {
+            let entries = git_ops.checkpoint_entries(usize::MAX)?;
+            let intermediate = match strategy {
+                Some(PruneStrategy::SessionEndpoints) => session_intermediates(&entries),
+                None => HashSet::new(),
+            };
+            Ok(entries
+                .into_iter()
+                .enumerate()
+                .filter(|(i, entry)| {
+                    keep.is_some_and(|keep| *i >= keep)
+                        || cutoff.is_some_and(|cutoff| entry.time < cutoff)
+                        || intermediate.contains(&entry.hash)
+                })
+                .map(|(_, entry)| entry)
+                .collect())
+        })
+    }
+
+    /// 清理时受保护的检查点及原因
+    ///
+    /// 有 git 标签指向的检查点以标签名为原因，里程碑检查点以 `milestone` 为原因，
+    /// ccg 分支的根提交也受保护。
+    pub fn protected_checkpoints(&self) -> CcResult<HashMap<String, String>> {
+        self.read_ccg_branch(|git_ops| self.protection(git_ops))
+    }
+
+    fn protection(&self, git_ops: &B) -> CcResult<HashMap<String, String>> {
+        let tags = git_ops.checkpoint_tags()?;
+        let entries = git_ops.checkpoint_entries(usize::MAX)?;
+        // 没有父提交的根提交是分支的起点，不能丢弃
+        let root = match entries.last() {
+            Some(oldest) if git_ops.checkpoint_parent(&oldest.hash)?.is_none() => {
+                Some(oldest.hash.clone())
+            }
+            _ => None,
+        };
+        Ok(entries
+            .into_iter()
+            .filter_map(|entry| {
+                let reason = match tags.get(&entry.hash) {
+                    Some(names) => names.join(", "),
+                    None if root.as_ref() == Some(&entry.hash) => t!("prune_root").to_string(),
+                    None if entry.label() == Some(CheckpointLabel::Milestone) => {
+                        CheckpointLabel::Milestone.name().to_string()
+                    }
+                    None => return None,
+                };
+                Some((entry.hash, reason))
+            })
+            .collect())
+    }
+
+    /// 在一次改写中丢弃检查点，返回被丢弃的检查点，从新到旧
+    ///
+    /// 受保护的检查点（见 [`protected_checkpoints`](Self::protected_checkpoints)）
+    /// 被跳过；最旧的被丢弃检查点之后的检查点以相同的文件树重放一次，被丢弃的
+    /// 变更并入之后保留的检查点。
+    pub fn prune_checkpoints(&self, hashes: &[String]) -> CcResult<Vec<CheckpointEntry>> {
+        self.ensure_writable("prune")?;
+        let (dropped, history) = self.execute_on_ccg_branch(|git_ops| {
+            let selected = hashes
+                .iter()
+                .map(|hash| git_ops.resolve_checkpoint(hash))
+                .collect::<CcResult<HashSet<String>>>()?;
+            let protection = self.protection(git_ops)?;
+            let entries = git_ops.checkpoint_entries(usize::MAX)?;
+            for entry in entries
+                .iter()
+                .filter(|entry| selected.contains(&entry.hash))
+            {
+                if let Some(reason) = protection.get(&entry.hash) {
+                    println!(
+                        "{}{}",
+                        style(Icon::Info.prefix()).fg(Color::Blue),
+                        style(t!(
+                            "prune_protected",
+                            hash = ui::short_hash(&entry.hash),
+                            reason = reason
+                        ))
+                        .fg(Color::White)
+                    );
+                }
+            }
+
+            let dropped: Vec<CheckpointEntry> = entries
+                .iter()
+                .filter(|entry| {
+                    selected.contains(&entry.hash) && !protection.contains_key(&entry.hash)
+                })
+                .cloned()
+                .collect();
+            let Some(oldest) = dropped.last() else {
+                return Ok((dropped, None));
+            };
+            // 最旧的被丢弃检查点及之后的检查点都会被改写
+            let rewritten = entries
+                .iter()
+                .position(|entry| entry.hash == oldest.hash)
+                .map_or(0, |i| i + 1);
+            self.ensure_permitted("prune", &entries[..rewritten])?;
+            let hashes: Vec<String> = dropped.iter().map(|entry| entry.hash.clone()).collect();
+            let history = git_ops.drop_checkpoints(&hashes)?;
+            Ok((dropped, Some(history)))
+        })?;
+
+        match history {
+            None => println!(
+                "{}{}",
+                style(Icon::Info.prefix()).fg(Color::Blue),
+                style(t!("prune_nothing")).fg(Color::White)
+            ),
+            Some(history) => {
+                println!(
+                    "{}{}",
+                    style(Icon::Drop.prefix()).fg(Color::Red),
+                    style(t!(
+                        "prune_done",
+                        count = dropped.len(),
+                        replayed = history.replayed.len(),
+                        tip = ui::short_hash(&history.tip)
+                    ))
+                    .fg(Color::Green)
+                    .bold()
+                );
+            }
+        }
+        Ok(dropped)
+    }
+}
+
+/// 每个会话中除第一个和最后一个以外的检查点
+fn session_intermediates(entries: &[CheckpointEntry]) -> HashSet<String> {
+    let mut sessions: HashMap<String, Vec<&CheckpointEntry>> = HashMap::new();
+    for entry in entries {
+        if let Some(session) = entry.session() {
+            sessions.entry(session).or_default().push(entry);
+        }
+    }
+    sessions
+        .into_values()
+        .filter(|members| members.len() > 2)
+        .flat_map(|members| {
+            let last = members.len() - 1;
+            members[1..last]
+                .iter()
+                .map(|entry| entry.hash.clone())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_ops::{CCG_BRANCH_NAME, MemoryBackend};
+
+    /// 在 main 分支上有一个提交的内存仓库
+    fn seeded() -> (MemoryBackend, CheckpointService<MemoryBackend>) {
+        let backend = MemoryBackend::new();
+        backend.write_file("a.txt", "one");
+        backend.commit("initial");
+        let service = CheckpointService::new(backend.clone()).unwrap();
+        (backend, service)
+    }
+
+    #[test]
+    fn create_initializes_ccg_and_returns_to_original_branch() {
+        let (backend, service) = seeded();
+        let main_tip = backend.branch_tip("main");
+
+        backend.write_file("a.txt", "two");
+        let hash = service.create_checkpoint(Some("edit a")).unwrap();
+
+        assert_eq!(backend.branch_tip(CCG_BRANCH_NAME), Some(hash.clone()));
+        assert_eq!(backend.branch_tip("main"), main_tip);
+        assert_eq!(backend.get_current_branch_name().unwrap(), "main");
+        assert_eq!(backend.message(&hash).as_deref(), Some("edit a"));
+    }
+
+    #[test]
+    fn create_without_changes_is_skipped() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        service.create_checkpoint(Some("first")).unwrap();
+
+        let hash = service.create_checkpoint(Some("second")).unwrap();
+
+        assert!(hash.is_empty());
+        assert_eq!(backend.get_current_branch_name().unwrap(), "main");
+    }
+
+    #[test]
+    fn create_with_allow_empty_marks_checkpoint() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        let first = service.create_checkpoint(Some("first")).unwrap();
+
+        let empty = service
+            .create_checkpoint_with(Some("nothing"), true)
+            .unwrap();
+
+        assert!(!empty.is_empty());
+        assert_eq!(backend.diff_checkpoints(&first, Some(&empty)).unwrap(), "");
+        let entries = service.checkpoints(1).unwrap();
+        assert!(entries[0].message.ends_with("Ccg-Empty: true\n"));
+    }
+
+    #[test]
+    fn per_file_create_commits_each_file_separately_and_skips_unchanged() {
+        let (backend, service) = seeded();
+        backend.write_file("b.txt", "new");
+        backend.write_file("c.txt", "pending");
+        let files = [
+            ("a.txt".to_string(), "edit a".to_string()),
+            ("b.txt".to_string(), "edit b".to_string()),
+        ];
+
+        let hashes = service
+            .create_checkpoint_per_file(&files, &CreateOptions::default())
+            .unwrap();
+
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(backend.message(&hashes[0]).as_deref(), Some("edit b"));
+        // 没有指定的文件仍然待提交
+        assert!(!service.create_checkpoint(Some("rest")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn create_strips_escapes_and_spills_long_messages_to_a_note() {
+        let (backend, service) = seeded();
+        backend.set_message_max_bytes(30);
+        backend.write_file("a.txt", "two");
+        let message =
+            "Bash\r\n\n\u{1b}[31mline one\u{1b}[0m\nline two\n\u{1b}]0;title\u{7}line three\n";
+
+        let hash = service.create_checkpoint(Some(message)).unwrap();
+
+        let full = backend.full_message(&hash).unwrap();
+        assert!(full.starts_with(
+            "Bash\n\nline one\nline two\n\n[11 more bytes in git note refs/notes/ccg]\n\nCcg-Base-Branch: main\n"
+        ));
+        assert_eq!(backend.note(&hash).as_deref(), Some("line three\n"));
+    }
+
+    #[test]
+    fn restore_is_recorded_for_list_timeline() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        let first = service.create_checkpoint(Some("first")).unwrap();
+        backend.write_file("a.txt", "three");
+        let second = service.create_checkpoint(Some("second")).unwrap();
+
+        service.restore_checkpoint(&first).unwrap();
+
+        let records = backend.restore_records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].target, first);
+        assert_eq!(records[0].discarded_tip, second);
+        assert_eq!(records[0].discarded, 1);
+        service
+            .list_checkpoints(10, &ListColumn::DEFAULT, true, &ListFilter::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn checkpoint_ids_resolve_like_hashes() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        let first = service.create_checkpoint(Some("first")).unwrap();
+        backend.write_file("a.txt", "three");
+        let second = service.create_checkpoint(Some("second")).unwrap();
+
+        assert_eq!(service.resolve_checkpoint("cp-1").unwrap(), first);
+        assert_eq!(service.resolve_checkpoint("cp-2").unwrap(), second);
+        assert_eq!(backend.checkpoint_id(&second).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn create_in_empty_repository_creates_initial_commit() {
+        let backend = MemoryBackend::new();
+        backend.write_file("a.txt", "one");
+        let service = CheckpointService::new(backend.clone()).unwrap();
+
+        service.create_checkpoint(Some("first")).unwrap();
+
+        let tip = backend.branch_tip(CCG_BRANCH_NAME).unwrap();
+        assert!(backend.message(&tip).is_some());
+    }
+
+    #[test]
+    fn restore_refuses_dirty_workdir_and_switches_back() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        let first = service.create_checkpoint(Some("first")).unwrap();
+        backend.write_file("a.txt", "three");
+        service.create_checkpoint(Some("second")).unwrap();
+        let ccg_tip = backend.branch_tip(CCG_BRANCH_NAME);
+
+        backend.write_file("a.txt", "unsaved");
+        let result = service.restore_checkpoint(&first);
+
+        assert!(matches!(result, Err(CheckpointError::UncommittedChanges)));
+        assert_eq!(backend.get_current_branch_name().unwrap(), "main");
+        assert_eq!(backend.branch_tip(CCG_BRANCH_NAME), ccg_tip);
+        assert_eq!(backend.file("a.txt").as_deref(), Some("unsaved"));
+    }
+
+    #[test]
+    fn restore_resets_ccg_branch_and_discards_later_checkpoints() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        let first = service.create_checkpoint(Some("first")).unwrap();
+        backend.write_file("a.txt", "three");
+        service.create_checkpoint(Some("second")).unwrap();
+
+        service.restore_checkpoint(&first[..7]).unwrap();
+
+        assert_eq!(backend.branch_tip(CCG_BRANCH_NAME), Some(first));
+        assert_eq!(backend.get_current_branch_name().unwrap(), CCG_BRANCH_NAME);
+        assert_eq!(backend.file("a.txt").as_deref(), Some("two"));
+    }
+
+    #[test]
+    fn reword_replays_later_checkpoints_with_same_files() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        let first = service.create_checkpoint(Some("first")).unwrap();
+        backend.write_file("a.txt", "three");
+        let second = service.create_checkpoint(Some("second")).unwrap();
+
+        let history = service.reword_checkpoint(&first, "renamed").unwrap();
+
+        let new_first = &history.replacements[0];
+        assert_eq!(backend.message(new_first).as_deref(), Some("renamed"));
+        assert_eq!(history.replayed.len(), 1);
+        assert_eq!(history.replayed[0].0, second);
+        assert_eq!(
+            backend.branch_tip(CCG_BRANCH_NAME),
+            Some(history.tip.clone())
+        );
+        assert_eq!(backend.message(&history.tip).as_deref(), Some("second"));
+        assert_eq!(
+            backend
+                .diff_checkpoints(&second, Some(&history.tip))
+                .unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn reword_rejects_empty_message() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        let first = service.create_checkpoint(Some("first")).unwrap();
+
+        let result = service.reword_checkpoint(&first, "  \n");
+
+        assert!(matches!(result, Err(CheckpointError::InvalidArgument(_))));
+        assert_eq!(backend.branch_tip(CCG_BRANCH_NAME), Some(first));
+    }
+
+    #[test]
+    fn split_creates_one_checkpoint_per_file() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        backend.write_file("b.txt", "new");
+        let both = service.create_checkpoint(Some("both")).unwrap();
+
+        let history = service.split_checkpoint(&both).unwrap();
+
+        assert_eq!(history.replacements.len(), 2);
+        assert_eq!(
+            backend.message(&history.replacements[0]).as_deref(),
+            Some("both (a.txt)")
+        );
+        assert_eq!(
+            backend.message(&history.replacements[1]).as_deref(),
+            Some("both (b.txt)")
+        );
+        assert_eq!(
+            backend.diff_checkpoints(&both, Some(&history.tip)).unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn revert_undoes_one_checkpoint_and_keeps_later_ones() {
+        let (backend, service) = seeded();
+        backend.write_file("b.txt", "bad");
+        let bad = service.create_checkpoint(Some("bad")).unwrap();
+        backend.write_file("a.txt", "two");
+        let good = service.create_checkpoint(Some("good")).unwrap();
+
+        let reverted = service.revert_checkpoint(&bad).unwrap();
+
+        assert_eq!(backend.branch_tip(CCG_BRANCH_NAME), Some(reverted.clone()));
+        assert_eq!(backend.file("b.txt"), None);
+        assert_eq!(backend.file("a.txt").as_deref(), Some("two"));
+        assert_eq!(
+            backend.diff_checkpoints(&good, Some(&reverted)).unwrap(),
+            "D b.txt"
+        );
+    }
+
+    #[test]
+    fn impact_lists_later_checkpoints_touching_the_same_files() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        let first = service.create_checkpoint(Some("first")).unwrap();
+        backend.write_file("b.txt", "other");
+        service.create_checkpoint(Some("unrelated")).unwrap();
+        backend.write_file("a.txt", "three");
+        let later = service.create_checkpoint(Some("later")).unwrap();
+
+        let report = service.checkpoint_impact(&first).unwrap();
+
+        assert_eq!(report.later_checkpoints, 2);
+        assert_eq!(report.overlaps.len(), 1);
+        assert_eq!(report.overlaps[0].hash, later);
+        assert_eq!(report.revert_conflicts, ["a.txt"]);
+    }
+
+    #[test]
+    fn restore_unknown_hash_fails() {
+        let (backend, service) = seeded();
+        backend.write_file("a.txt", "two");
+        service.create_checkpoint(Some("first")).unwrap();
+
+        let result = service.restore_checkpoint("ffffffff");
+
+        assert!(matches!(
+            result,
+            Err(CheckpointError::CheckpointNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn owner_permissions_protect_other_users_checkpoints() {
+        let (backend, service) = seeded();
+        backend.set_team(Permissions::Owner, "alice");
+        backend.write_file("a.txt", "two");
+        let first = service.create_checkpoint(Some("first")).unwrap();
+        backend.write_file("a.txt", "three");
+        service.create_checkpoint(Some("alice's")).unwrap();
+
+        backend.set_team(Permissions::Owner, "bob");
+        let restore = service.restore_checkpoint(&first);
+        let reword = service.reword_checkpoint(&first, "renamed");
+
+        assert!(matches!(restore, Err(CheckpointError::NotPermitted(m)) if m.contains("alice")));
+        assert!(matches!(reword, Err(CheckpointError::NotPermitted(_))));
+        assert_eq!(backend.get_current_branch_name().unwrap(), "main");
+
+        backend.set_team(Permissions::Owner, "alice");
+        service.restore_checkpoint(&first).unwrap();
+    }
+
+    #[test]
+    fn append_only_permissions_allow_creating_but_not_discarding() {
+        let (backend, service) = seeded();
+        backend.set_team(Permissions::AppendOnly, "alice");
+        backend.write_file("a.txt", "two");
+        let first = service.create_checkpoint(Some("first")).unwrap();
+        backend.write_file("a.txt", "three");
+        let second = service.create_checkpoint(Some("second")).unwrap();
+
+        assert!(matches!(
+            service.restore_checkpoint(&first),
+            Err(CheckpointError::NotPermitted(_))
+        ));
+        // 恢复到最新检查点不丢弃任何内容
+        service.restore_checkpoint(&second).unwrap();
+        assert!(matches!(
+            service.prune_checkpoints(std::slice::from_ref(&first)),
+            Err(CheckpointError::NotPermitted(_))
+        ));
+    }
 }
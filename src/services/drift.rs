@@ -0,0 +1,98 @@
+//! 检查点与用户分支的偏离报告
+//!
+//! `ccg drift` 比较最新检查点和当前分支 HEAD 的文件，两边都与共同祖先比较，
+//! 列出只在检查点中修改、只在分支上修改以及两边修改结果不同的文件，
+//! 帮助判断现在提升检查点是否干净，还是应该先以分支为新基准。
+
+use crate::git_ops::BranchDrift;
+use crate::ui::{Icon, short_hash};
+use console::{Color, style};
+use rust_i18n::t;
+use std::fmt::Write;
+
+/// 每组最多列出的文件数，其余的只计数
+pub const DRIFT_LIST_LIMIT: usize = 20;
+
+/// 把偏离报告渲染为终端输出
+pub fn render(drift: &BranchDrift) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}{}",
+        style(Icon::Search.prefix()).fg(Color::Blue),
+        t!(
+            "drift_header",
+            checkpoint = style(short_hash(&drift.checkpoint))
+                .fg(Color::Yellow)
+                .bold(),
+            branch = style(&drift.branch).fg(Color::Cyan).bold(),
+            head = style(short_hash(&drift.head)).fg(Color::Yellow)
+        )
+    );
+    let base = match &drift.base {
+        Some(base) => t!("drift_base", hash = short_hash(base)),
+        None => t!("drift_no_base"),
+    };
+    let _ = writeln!(out, "   {}", style(base).dim());
+    let _ = writeln!(out);
+
+    if drift.is_in_sync() {
+        let _ = writeln!(
+            out,
+            "{}{}",
+            style(Icon::Success.prefix()).fg(Color::Green),
+            style(t!("drift_in_sync", branch = &drift.branch)).fg(Color::Green)
+        );
+        return out;
+    }
+
+    let groups = [
+        (
+            t!("drift_only_checkpoint"),
+            &drift.only_checkpoint,
+            "+",
+            Color::Green,
+        ),
+        (
+            t!("drift_only_branch", branch = &drift.branch),
+            &drift.only_branch,
+            "-",
+            Color::Cyan,
+        ),
+        (t!("drift_diverged"), &drift.diverged, "!", Color::Red),
+    ];
+    for (title, files, marker, color) in groups {
+        if files.is_empty() {
+            continue;
+        }
+        let _ = writeln!(
+            out,
+            "{} ({})",
+            style(title).fg(color).bold(),
+            style(files.len()).fg(color)
+        );
+        for file in files.iter().take(DRIFT_LIST_LIMIT) {
+            let _ = writeln!(out, "  {} {file}", style(marker).fg(color));
+        }
+        if files.len() > DRIFT_LIST_LIMIT {
+            let more = files.len() - DRIFT_LIST_LIMIT;
+            let _ = writeln!(out, "  {}", style(t!("drift_more", count = more)).dim());
+        }
+        let _ = writeln!(out);
+    }
+
+    let hint = if !drift.diverged.is_empty() {
+        t!("drift_hint_diverged")
+    } else if !drift.only_branch.is_empty() {
+        t!("drift_hint_rebaseline", branch = &drift.branch)
+    } else {
+        t!("drift_hint_promote")
+    };
+    let _ = writeln!(
+        out,
+        "{}{}",
+        style(Icon::Hint.prefix()).fg(Color::Blue),
+        style(hint).fg(Color::White)
+    );
+    out
+}
@@ -0,0 +1,150 @@
+use crate::error::{CheckpointError, Result as CcResult};
+use crate::ui::Icon;
+use console::{Color, style};
+use rust_i18n::t;
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// 用户扩展脚本所在目录（相对于工作目录）
+pub const HOOKS_DIR: &str = ".ccg/hooks";
+
+/// 设为 `1` 或 `true` 时跳过阻塞型钩子，效果同 `--bypass-guard`
+pub const BYPASS_GUARD_ENV: &str = "CCG_BYPASS_GUARD";
+
+/// 环境变量是否要求跳过阻塞型钩子
+pub fn bypass_requested() -> bool {
+    std::env::var(BYPASS_GUARD_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+/// 支持的钩子事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreCreate,
+    PostCreate,
+    PreRestore,
+    PostRestore,
+}
+
+impl HookEvent {
+    /// 所有钩子事件
+    pub const ALL: [HookEvent; 4] = [
+        HookEvent::PreCreate,
+        HookEvent::PostCreate,
+        HookEvent::PreRestore,
+        HookEvent::PostRestore,
+    ];
+
+    /// 钩子脚本的文件名
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookEvent::PreCreate => "pre-create",
+            HookEvent::PostCreate => "post-create",
+            HookEvent::PreRestore => "pre-restore",
+            HookEvent::PostRestore => "post-restore",
+        }
+    }
+
+    /// pre-* 钩子返回非零时会中止操作
+    pub fn is_blocking(&self) -> bool {
+        matches!(self, HookEvent::PreCreate | HookEvent::PreRestore)
+    }
+}
+
+/// 运行 `.ccg/hooks` 目录下的用户脚本
+///
+/// 脚本通过 stdin 接收 JSON 格式的上下文，工作目录为仓库根目录。
+#[derive(Debug, Clone)]
+pub struct HookRunner {
+    workdir: PathBuf,
+}
+
+impl HookRunner {
+    pub fn new<P: AsRef<Path>>(workdir: P) -> Self {
+        HookRunner {
+            workdir: workdir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// 钩子脚本的完整路径
+    pub fn hook_path(&self, event: HookEvent) -> PathBuf {
+        self.workdir.join(HOOKS_DIR).join(event.name())
+    }
+
+    /// 指定事件的钩子是否存在且可执行
+    pub fn exists(&self, event: HookEvent) -> bool {
+        is_executable(&self.hook_path(event))
+    }
+
+    /// 运行指定事件的钩子
+    ///
+    /// 钩子不存在或不可执行时直接返回成功。pre-* 钩子以非零状态退出时返回
+    /// `CheckpointError::HookRejected`；post-* 钩子失败只打印警告。
+    pub fn run(&self, event: HookEvent, context: &Value) -> CcResult<()> {
+        let path = self.hook_path(event);
+        if !is_executable(&path) {
+            return Ok(());
+        }
+
+        let mut payload = context.clone();
+        if let Value::Object(map) = &mut payload {
+            map.insert("event".to_string(), Value::from(event.name()));
+            map.insert(
+                "repo".to_string(),
+                Value::from(self.workdir.to_string_lossy().to_string()),
+            );
+        }
+
+        let mut child = Command::new(&path)
+            .current_dir(&self.workdir)
+            .env("CCG_HOOK", event.name())
+            .stdin(Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // 脚本可能不读取 stdin，忽略 BrokenPipe
+            let _ = stdin.write_all(payload.to_string().as_bytes());
+        }
+
+        let status = child.wait()?;
+        if status.success() {
+            return Ok(());
+        }
+
+        let code = status
+            .code()
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "signal".to_string());
+
+        if event.is_blocking() {
+            Err(CheckpointError::HookRejected(format!(
+                "{} (exit {code})",
+                event.name()
+            )))
+        } else {
+            println!(
+                "{}{}",
+                style(Icon::Warning.prefix()).fg(Color::Yellow),
+                style(t!("hook_post_failed", hook = event.name(), code = code)).fg(Color::Yellow)
+            );
+            Ok(())
+        }
+    }
+}
+
+/// 判断路径是否为可执行文件
+#[cfg(unix)]
+pub(crate) fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
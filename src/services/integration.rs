@@ -0,0 +1,238 @@
+use crate::error::{CheckpointError, Result as CcResult};
+use git2::Repository;
+use rust_i18n::t;
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Claude Code 项目级配置文件（相对于工作目录）
+pub const CLAUDE_SETTINGS_PATH: &str = ".claude/settings.json";
+
+/// 触发检查点的 Claude Code 工具
+pub const CLAUDE_TOOL_MATCHER: &str = "Edit|MultiEdit|Write";
+
+/// Claude Code 钩子执行的命令
+pub const CLAUDE_HOOK_COMMAND: &str = "ccg create";
+
+/// 安装的 git 钩子名称
+pub const GIT_HOOK_NAME: &str = "post-commit";
+
+/// git 钩子中 ccg 片段的起止标记，用于识别已安装的片段
+pub const GIT_HOOK_BEGIN: &str = "# >>> ccg >>>";
+pub const GIT_HOOK_END: &str = "# <<< ccg <<<";
+
+/// 修改已有文件前备份的后缀
+pub const BACKUP_SUFFIX: &str = ".ccg-backup";
+
+/// 安装目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallTarget {
+    ClaudeSettings,
+    GitHook,
+}
+
+/// 待写入的文件变更
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedChange {
+    pub target: InstallTarget,
+    pub path: PathBuf,
+    /// 文件原有内容（不存在时为 None）
+    pub original: Option<String>,
+    /// 合并后的内容
+    pub content: String,
+}
+
+impl PlannedChange {
+    /// 合并后内容与原有内容一致，无需写入
+    pub fn is_unchanged(&self) -> bool {
+        self.original.as_deref() == Some(self.content.as_str())
+    }
+
+    /// 写入合并后的内容，修改已有文件前先备份
+    ///
+    /// 返回备份文件路径（新建文件或内容未变化时为 None）
+    pub fn apply(&self) -> CcResult<Option<PathBuf>> {
+        if self.is_unchanged() {
+            return Ok(None);
+        }
+
+        let backup = match &self.original {
+            Some(_) => {
+                let mut name = self.path.as_os_str().to_os_string();
+                name.push(BACKUP_SUFFIX);
+                let backup = PathBuf::from(name);
+                fs::copy(&self.path, &backup)?;
+                Some(backup)
+            }
+            None => None,
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, &self.content)?;
+        if self.target == InstallTarget::GitHook {
+            make_executable(&self.path)?;
+        }
+
+        Ok(backup)
+    }
+}
+
+/// 计算 Claude Code 配置的变更
+pub fn plan_claude_settings(workdir: &Path) -> CcResult<PlannedChange> {
+    let path = workdir.join(CLAUDE_SETTINGS_PATH);
+    let original = read_optional(&path)?;
+    let content = merge_claude_settings(original.as_deref())?;
+    Ok(PlannedChange {
+        target: InstallTarget::ClaudeSettings,
+        path,
+        original,
+        content,
+    })
+}
+
+/// 将 ccg 的 PostToolUse 钩子合并进已有的 Claude Code 配置
+///
+/// 保留所有已有配置和钩子条目，只在尚未配置 `ccg create` 时追加新条目。
+/// 配置结构不符合预期时返回错误，而不是覆盖用户配置。
+pub fn merge_claude_settings(existing: Option<&str>) -> CcResult<String> {
+    let mut settings = match existing {
+        Some(text) if !text.trim().is_empty() => serde_json::from_str(text).map_err(|e| {
+            CheckpointError::InvalidArgument(t!("install_settings_invalid", error = e))
+        })?,
+        _ => Value::Object(Map::new()),
+    };
+
+    let post_tool_use = settings
+        .as_object_mut()
+        .ok_or_else(|| invalid_settings("settings"))?
+        .entry("hooks")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| invalid_settings("hooks"))?
+        .entry("PostToolUse")
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array_mut()
+        .ok_or_else(|| invalid_settings("hooks.PostToolUse"))?;
+
+    // 已安装时保留原文件不变
+    if post_tool_use.iter().any(runs_ccg) {
+        return Ok(existing.unwrap_or_default().to_string());
+    }
+    post_tool_use.push(json!({
+        "matcher": CLAUDE_TOOL_MATCHER,
+        "hooks": [{ "type": "command", "command": CLAUDE_HOOK_COMMAND }]
+    }));
+
+    let mut content = serde_json::to_string_pretty(&settings)
+        .map_err(|e| CheckpointError::InvalidArgument(e.to_string()))?;
+    content.push('\n');
+    Ok(content)
+}
+
+/// PostToolUse 条目中是否已有运行 `ccg create` 的命令
+fn runs_ccg(entry: &Value) -> bool {
+    entry
+        .get("hooks")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|hook| hook.get("command").and_then(Value::as_str))
+        .any(|command| command.trim_start().starts_with(CLAUDE_HOOK_COMMAND))
+}
+
+fn invalid_settings(field: &str) -> CheckpointError {
+    CheckpointError::InvalidArgument(t!("install_settings_unexpected", field = field))
+}
+
+/// git 钩子目录，遵循 `core.hooksPath` 配置
+///
+/// 相对路径按 git 的规则相对于工作目录解析。
+pub fn git_hooks_dir(repo: &Repository) -> PathBuf {
+    let configured = repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_path("core.hooksPath").ok());
+
+    match configured {
+        Some(path) if path.is_absolute() => path,
+        Some(path) => repo.workdir().unwrap_or_else(|| repo.path()).join(path),
+        None => repo.path().join("hooks"),
+    }
+}
+
+/// 计算 git 钩子的变更
+pub fn plan_git_hook(repo: &Repository) -> CcResult<PlannedChange> {
+    let path = git_hooks_dir(repo).join(GIT_HOOK_NAME);
+    let original = read_optional(&path)?;
+    let content = merge_git_hook(original.as_deref())?;
+    Ok(PlannedChange {
+        target: InstallTarget::GitHook,
+        path,
+        original,
+        content,
+    })
+}
+
+/// 将 ccg 片段追加到已有的 git 钩子脚本
+///
+/// 已有脚本保持原样并在其后执行 ccg；非 shell 脚本无法安全追加，返回错误。
+pub fn merge_git_hook(existing: Option<&str>) -> CcResult<String> {
+    let block = format!(
+        "{GIT_HOOK_BEGIN}\n{CLAUDE_HOOK_COMMAND} \"git commit\" >/dev/null 2>&1 || true\n{GIT_HOOK_END}\n"
+    );
+
+    let Some(script) = existing.filter(|s| !s.trim().is_empty()) else {
+        return Ok(format!("#!/bin/sh\n{block}"));
+    };
+
+    if script.contains(GIT_HOOK_BEGIN) {
+        return Ok(script.to_string());
+    }
+
+    let shebang = script.lines().next().unwrap_or_default();
+    let is_shell = match shebang.strip_prefix("#!") {
+        Some(interpreter) => interpreter
+            .split_whitespace()
+            .filter_map(|part| part.rsplit('/').next())
+            .any(|name| matches!(name, "sh" | "bash" | "zsh" | "dash")),
+        None => true,
+    };
+    if !is_shell {
+        return Err(CheckpointError::InvalidArgument(t!(
+            "install_hook_not_shell",
+            shebang = shebang
+        )));
+    }
+
+    let mut content = script.to_string();
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push('\n');
+    content.push_str(&block);
+    Ok(content)
+}
+
+fn read_optional(path: &Path) -> CcResult<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> CcResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> CcResult<()> {
+    Ok(())
+}
@@ -0,0 +1,153 @@
+//! 检查点元数据导出
+//!
+//! `ccg export-metadata` 每个检查点输出一行（哈希、时间、工具、文件数、增删行数、
+//! 会话、标签），供电子表格或 notebook 统计 AI 产生的变更量。行数统计取自
+//! `.git/ccg/stats` 元数据索引，缺失的检查点会先计算并补记。
+
+use crate::git_ops::{CheckpointEntry, DiffStats, VerifyStatus};
+use chrono::{DateTime, SecondsFormat};
+use serde_json::json;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetadataFormat {
+    #[default]
+    Csv,
+    /// 每行一个 JSON 对象
+    Jsonl,
+}
+
+impl FromStr for MetadataFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(MetadataFormat::Csv),
+            "jsonl" | "ndjson" => Ok(MetadataFormat::Jsonl),
+            other => Err(format!("unknown metadata format: {other}")),
+        }
+    }
+}
+
+/// CSV 的列，也是 JSONL 对象的字段
+pub const METADATA_COLUMNS: [&str; 13] = [
+    "hash",
+    "id",
+    "time",
+    "author",
+    "tool",
+    "files",
+    "additions",
+    "deletions",
+    "session",
+    "task",
+    "labels",
+    "verify",
+    "summary",
+];
+
+/// 一个检查点的元数据
+#[derive(Debug, Clone)]
+pub struct CheckpointMetadata {
+    pub entry: CheckpointEntry,
+    pub stats: DiffStats,
+}
+
+impl CheckpointMetadata {
+    /// 检查点的标签：按提交信息约定分类的标签，以及 `format-only` 和 `interleaved`
+    pub fn labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = self
+            .entry
+            .label()
+            .map(|label| label.name().to_string())
+            .into_iter()
+            .collect();
+        if self.stats.format_only {
+            labels.push("format-only".to_string());
+        }
+        if !self.entry.interleaved().is_empty() {
+            labels.push("interleaved".to_string());
+        }
+        labels
+    }
+
+    /// 与 `METADATA_COLUMNS` 对应的值
+    fn values(&self) -> [String; 13] {
+        let entry = &self.entry;
+        [
+            entry.hash.clone(),
+            entry.display_id().unwrap_or_default(),
+            DateTime::from_timestamp(entry.time, 0)
+                .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .unwrap_or_default(),
+            entry.author.clone(),
+            entry.tool().unwrap_or_default().to_string(),
+            self.stats.total_files.to_string(),
+            self.stats.additions.to_string(),
+            self.stats.deletions.to_string(),
+            entry.session().unwrap_or_default(),
+            entry.task().unwrap_or_default(),
+            self.labels().join(";"),
+            verify_value(entry).to_string(),
+            entry.summary().to_string(),
+        ]
+    }
+}
+
+/// 按指定格式输出所有行，CSV 带表头
+pub fn render(rows: &[CheckpointMetadata], format: MetadataFormat) -> String {
+    let mut out = String::new();
+    match format {
+        MetadataFormat::Csv => {
+            let _ = writeln!(out, "{}", METADATA_COLUMNS.join(","));
+            for row in rows {
+                let values = row.values();
+                let fields: Vec<String> = values.iter().map(|value| csv_field(value)).collect();
+                let _ = writeln!(out, "{}", fields.join(","));
+            }
+        }
+        MetadataFormat::Jsonl => {
+            for row in rows {
+                let entry = &row.entry;
+                let value = json!({
+                    "hash": entry.hash,
+                    "id": entry.display_id(),
+                    "time": DateTime::from_timestamp(entry.time, 0)
+                        .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                    "author": entry.author,
+                    "tool": entry.tool(),
+                    "files": row.stats.total_files,
+                    "additions": row.stats.additions,
+                    "deletions": row.stats.deletions,
+                    "session": entry.session(),
+                    "task": entry.task(),
+                    "labels": row.labels(),
+                    "verify": verify_value(entry),
+                    "summary": entry.summary(),
+                });
+                let _ = writeln!(out, "{value}");
+            }
+        }
+    }
+    out
+}
+
+/// 验证结果，与 `Ccg-Verify` 尾注相同（pass 或 fail），未验证时为空
+fn verify_value(entry: &CheckpointEntry) -> &'static str {
+    match entry.verification() {
+        VerifyStatus::Passed => "pass",
+        VerifyStatus::Failed => "fail",
+        VerifyStatus::Unknown => "",
+    }
+}
+
+/// CSV 字段：包含逗号、引号或换行时加引号，引号写两次
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
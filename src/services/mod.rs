@@ -1,4 +1,19 @@
 pub mod checkpoint_service;
+pub mod drift;
+pub mod hooks;
+pub mod integration;
+pub mod metadata;
+pub mod query;
+pub mod report;
+pub mod schedule;
+pub mod summary;
+pub mod verify;
 
 // 重新导出主要类型
-pub use checkpoint_service::CheckpointService;
+pub use checkpoint_service::{CheckpointService, CreateOptions, PruneStrategy, RestoreOptions};
+pub use hooks::{HookEvent, HookRunner};
+pub use metadata::{CheckpointMetadata, MetadataFormat};
+pub use query::CheckpointQuery;
+pub use report::{ReportFormat, SessionReport};
+pub use summary::{SummaryProvider, SummarySettings};
+pub use verify::Verification;
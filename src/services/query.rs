@@ -0,0 +1,305 @@
+//! 检查点搜索的查询语言
+//!
+//! `ccg search` 把 `tool:Edit file:src/**.rs after:2024-06-01 text:"refactor"` 这样的
+//! 查询解析为一组条件，在元数据索引上筛选检查点。不同字段的条件都要满足，同一字段的
+//! 多个条件满足其一即可，如 `tool:Edit tool:Write`。值含空格时用双引号括起，不带字段名
+//! 的词等同于 `text:`。
+//!
+//! 只有 `file:` 需要检查点改动的文件，只有 `text:` 需要备注，其余条件只看提交信息、
+//! trailer 和统计，先被它们筛掉的检查点不会计算差异。
+
+use crate::commands::evolution::parse_time;
+use crate::git_ops::CheckpointLabel;
+use crate::services::CheckpointMetadata;
+use crate::ui::{Icon, short_hash};
+use chrono::DateTime;
+use console::{Color, style};
+use git2::{Pathspec, PathspecFlags};
+use rust_i18n::t;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// 查询支持的字段
+pub const QUERY_FIELDS: [&str; 9] = [
+    "tool", "file", "after", "before", "text", "author", "task", "session", "label",
+];
+
+/// 查询中的一个条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryTerm {
+    /// 创建检查点的工具，不区分大小写
+    Tool(String),
+    /// 改动了匹配此 pathspec 的文件
+    File(String),
+    /// 在此时间（Unix 时间戳）或之后创建
+    After(i64),
+    /// 在此时间之前创建
+    Before(i64),
+    /// 提交信息或备注包含此文本，不区分大小写
+    Text(String),
+    /// 作者名包含此文本，不区分大小写
+    Author(String),
+    /// 在此任务中创建，ID 或名称均可
+    Task(String),
+    /// 会话 ID 以此开头
+    Session(String),
+    /// 带有此标签，包括 `format-only` 和 `interleaved`
+    Label(String),
+}
+
+impl QueryTerm {
+    fn new(field: &str, value: String) -> Result<Self, String> {
+        let term = match field.to_ascii_lowercase().as_str() {
+            "tool" => QueryTerm::Tool(value),
+            "file" => QueryTerm::File(value),
+            "after" => QueryTerm::After(parse_time(&value).map_err(|e| e.to_string())?),
+            "before" => QueryTerm::Before(parse_time(&value).map_err(|e| e.to_string())?),
+            "text" => QueryTerm::Text(value.to_lowercase()),
+            "author" => QueryTerm::Author(value.to_lowercase()),
+            "task" => QueryTerm::Task(value),
+            "session" => QueryTerm::Session(value),
+            "label" => {
+                let label = value.to_ascii_lowercase();
+                let known = CheckpointLabel::ALL
+                    .map(CheckpointLabel::name)
+                    .into_iter()
+                    .chain(["format-only", "interleaved"]);
+                if !known.clone().any(|name| name == label) {
+                    return Err(format!(
+                        "unknown label `{value}`, expected one of: {}",
+                        known.collect::<Vec<_>>().join(", ")
+                    ));
+                }
+                QueryTerm::Label(label)
+            }
+            other => {
+                return Err(format!(
+                    "unknown field `{other}`, expected one of: {}",
+                    QUERY_FIELDS.join(", ")
+                ));
+            }
+        };
+        Ok(term)
+    }
+
+    /// 条件的字段名
+    pub fn field(&self) -> &'static str {
+        match self {
+            QueryTerm::Tool(_) => "tool",
+            QueryTerm::File(_) => "file",
+            QueryTerm::After(_) => "after",
+            QueryTerm::Before(_) => "before",
+            QueryTerm::Text(_) => "text",
+            QueryTerm::Author(_) => "author",
+            QueryTerm::Task(_) => "task",
+            QueryTerm::Session(_) => "session",
+            QueryTerm::Label(_) => "label",
+        }
+    }
+
+    /// `contents` 为 None 时，需要文件或备注的条件视为满足，留待之后判断
+    fn matches(&self, metadata: &CheckpointMetadata, contents: Option<&Contents>) -> bool {
+        let entry = &metadata.entry;
+        match self {
+            QueryTerm::Tool(tool) => entry
+                .tool()
+                .is_some_and(|name| name.eq_ignore_ascii_case(tool)),
+            QueryTerm::File(pattern) => contents.is_none_or(|contents| {
+                Pathspec::new([pattern.as_str()]).is_ok_and(|spec| {
+                    contents
+                        .files
+                        .iter()
+                        .any(|file| spec.matches_path(file.as_ref(), PathspecFlags::DEFAULT))
+                })
+            }),
+            QueryTerm::After(time) => entry.time >= *time,
+            QueryTerm::Before(time) => entry.time < *time,
+            QueryTerm::Text(text) => {
+                entry.message.to_lowercase().contains(text)
+                    || contents.is_none_or(|contents| {
+                        contents
+                            .note
+                            .is_some_and(|note| note.to_lowercase().contains(text))
+                    })
+            }
+            QueryTerm::Author(author) => entry.author.to_lowercase().contains(author),
+            QueryTerm::Task(task) => {
+                entry.task().as_ref() == Some(task) || entry.task_name().as_ref() == Some(task)
+            }
+            QueryTerm::Session(session) => entry
+                .session()
+                .is_some_and(|id| id.starts_with(session.as_str())),
+            QueryTerm::Label(label) => metadata.labels().contains(label),
+        }
+    }
+}
+
+/// 检查点改动的文件和备注
+struct Contents<'a> {
+    files: &'a [String],
+    note: Option<&'a str>,
+}
+
+/// 解析后的查询
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckpointQuery {
+    pub terms: Vec<QueryTerm>,
+}
+
+impl CheckpointQuery {
+    /// 是否有条件需要检查点改动的文件
+    pub fn needs_files(&self) -> bool {
+        self.terms
+            .iter()
+            .any(|term| matches!(term, QueryTerm::File(_)))
+    }
+
+    /// 是否有条件需要检查点的备注
+    pub fn needs_note(&self) -> bool {
+        self.terms
+            .iter()
+            .any(|term| matches!(term, QueryTerm::Text(_)))
+    }
+
+    /// 只按元数据判断，需要文件或备注的条件暂时视为满足
+    pub fn matches_metadata(&self, metadata: &CheckpointMetadata) -> bool {
+        self.evaluate(metadata, None)
+    }
+
+    /// 检查点是否满足查询
+    ///
+    /// `files` 和 `note` 只在 [`needs_files`](Self::needs_files) 和
+    /// [`needs_note`](Self::needs_note) 时才会用到。
+    pub fn matches(
+        &self,
+        metadata: &CheckpointMetadata,
+        files: &[String],
+        note: Option<&str>,
+    ) -> bool {
+        self.evaluate(metadata, Some(&Contents { files, note }))
+    }
+
+    fn evaluate(&self, metadata: &CheckpointMetadata, contents: Option<&Contents>) -> bool {
+        QUERY_FIELDS.iter().all(|field| {
+            let mut terms = self
+                .terms
+                .iter()
+                .filter(|term| term.field() == *field)
+                .peekable();
+            terms.peek().is_none() || terms.any(|term| term.matches(metadata, contents))
+        })
+    }
+}
+
+impl FromStr for CheckpointQuery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let terms = tokenize(s)?
+            .into_iter()
+            .map(|(field, value)| match field {
+                Some(field) if value.is_empty() => Err(format!("`{field}:` needs a value")),
+                Some(field) => QueryTerm::new(&field, value),
+                None => Ok(QueryTerm::Text(value.to_lowercase())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if terms.is_empty() {
+            return Err("the query has no terms".to_string());
+        }
+        Ok(CheckpointQuery { terms })
+    }
+}
+
+/// 按空白拆分查询，双引号内的空白和冒号不拆分
+///
+/// 每一项是 `(字段, 值)`，第一个不在引号内的冒号分隔字段和值。
+fn tokenize(query: &str) -> Result<Vec<(Option<String>, String)>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(tokens);
+        }
+        let mut field = None;
+        let mut value = String::new();
+        let mut quoted = false;
+        for c in chars.by_ref() {
+            match c {
+                '"' => quoted = !quoted,
+                ':' if !quoted && field.is_none() && !value.is_empty() => {
+                    field = Some(std::mem::take(&mut value));
+                }
+                c if c.is_whitespace() && !quoted => break,
+                c => value.push(c),
+            }
+        }
+        if quoted {
+            return Err("unterminated quote".to_string());
+        }
+        // 只有引号的词（`""`）没有内容，忽略
+        if field.is_some() || !value.is_empty() {
+            tokens.push((field, value));
+        }
+    }
+}
+
+/// 把搜索结果渲染为终端输出，从新到旧每个检查点一行
+pub fn render(query: &str, matches: &[CheckpointMetadata]) -> String {
+    let mut out = String::new();
+    if matches.is_empty() {
+        let _ = writeln!(
+            out,
+            "{}",
+            style(format!(
+                "{}{}",
+                Icon::Empty.prefix(),
+                t!("search_no_matches", query = query)
+            ))
+            .fg(Color::Yellow)
+        );
+        return out;
+    }
+    let _ = writeln!(
+        out,
+        "{}",
+        style(format!(
+            "{}{}",
+            Icon::Search.prefix(),
+            t!("search_header", count = matches.len(), query = query)
+        ))
+        .fg(Color::Green)
+        .bold()
+    );
+    let _ = writeln!(out);
+    for metadata in matches {
+        let entry = &metadata.entry;
+        let time = DateTime::from_timestamp(entry.time, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let mut line = format!(
+            "  {} {}",
+            style(short_hash(&entry.hash)).fg(Color::Yellow).bold(),
+            style(time).fg(Color::Cyan)
+        );
+        if let Some(id) = entry.display_id() {
+            let _ = write!(line, " {}", style(id).fg(Color::Magenta));
+        }
+        if let Some(tool) = entry.tool() {
+            let _ = write!(line, " {}", style(format!("[{tool}]")).fg(Color::Blue));
+        }
+        let _ = writeln!(
+            out,
+            "{line} {} {}",
+            entry.summary(),
+            style(format!(
+                "(+{} {}{})",
+                metadata.stats.additions,
+                Icon::Minus.glyph(),
+                metadata.stats.deletions
+            ))
+            .dim()
+        );
+    }
+    out
+}
@@ -0,0 +1,352 @@
+//! 会话报告
+//!
+//! `ccg report` 把一个 Claude Code 会话的检查点整理成可读的 Markdown 或 HTML：
+//! 检查点时间线、每个文件的变更统计、总计，以及改动最大的几个文件的差异，
+//! 便于附在 PR 描述中或发给审阅者。
+
+use crate::git_ops::{CheckpointEntry, FileChangeInfo, FilePatch};
+use chrono::DateTime;
+use rust_i18n::t;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// 报告中嵌入差异的文件数上限，按改动行数从多到少选取
+pub const REPORT_DIFF_FILES: usize = 5;
+
+/// 每个嵌入的差异最多保留的行数
+pub const REPORT_DIFF_LINES: usize = 60;
+
+/// 报告格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "md" | "markdown" => Ok(ReportFormat::Markdown),
+            "html" => Ok(ReportFormat::Html),
+            other => Err(format!("unknown report format: {other}")),
+        }
+    }
+}
+
+/// 会话中的一个检查点及其改动的文件
+#[derive(Debug, Clone)]
+pub struct ReportCheckpoint {
+    pub entry: CheckpointEntry,
+    pub files: Vec<FileChangeInfo>,
+}
+
+impl ReportCheckpoint {
+    fn additions(&self) -> i32 {
+        self.files.iter().map(|file| file.additions).sum()
+    }
+
+    fn deletions(&self) -> i32 {
+        self.files.iter().map(|file| file.deletions).sum()
+    }
+}
+
+/// 一个会话的检查点和整体变更
+#[derive(Debug, Clone)]
+pub struct SessionReport {
+    /// 会话 ID
+    pub session: String,
+    /// 会话的检查点，从旧到新
+    pub checkpoints: Vec<ReportCheckpoint>,
+    /// 第一个检查点之前到最后一个检查点的变更，按路径排列
+    pub files: Vec<FilePatch>,
+}
+
+impl SessionReport {
+    /// 整体新增的行数
+    pub fn additions(&self) -> i32 {
+        self.files.iter().map(|file| file.change.additions).sum()
+    }
+
+    /// 整体删除的行数
+    pub fn deletions(&self) -> i32 {
+        self.files.iter().map(|file| file.change.deletions).sum()
+    }
+
+    /// 按指定格式生成报告
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.markdown(),
+            ReportFormat::Html => self.html(),
+        }
+    }
+
+    /// 修改过 `path` 的检查点数
+    fn touched_by(&self, path: &str) -> usize {
+        self.checkpoints
+            .iter()
+            .filter(|checkpoint| checkpoint.files.iter().any(|file| file.path == path))
+            .count()
+    }
+
+    /// 嵌入差异的文件：改动行数最多的几个有文本差异的文件
+    fn key_diffs(&self) -> Vec<&FilePatch> {
+        let mut files: Vec<&FilePatch> = self
+            .files
+            .iter()
+            .filter(|file| file.change.additions + file.change.deletions > 0)
+            .collect();
+        files.sort_by_key(|file| std::cmp::Reverse(file.change.additions + file.change.deletions));
+        files.truncate(REPORT_DIFF_FILES);
+        files
+    }
+
+    /// 报告开头的概要：检查点数、时间范围和总计
+    fn overview(&self) -> Vec<String> {
+        let first = self.checkpoints.first().map(|c| c.entry.time).unwrap_or(0);
+        let last = self.checkpoints.last().map(|c| c.entry.time).unwrap_or(0);
+        vec![
+            t!("report_checkpoints", count = self.checkpoints.len()).to_string(),
+            t!(
+                "report_period",
+                start = format_time(first),
+                end = format_time(last),
+                minutes = (last - first) / 60
+            )
+            .to_string(),
+            t!(
+                "report_totals",
+                files = self.files.len(),
+                additions = self.additions(),
+                deletions = self.deletions()
+            )
+            .to_string(),
+        ]
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}\n", t!("report_title", session = &self.session));
+        for line in self.overview() {
+            let _ = writeln!(out, "- {line}");
+        }
+
+        let _ = writeln!(out, "\n## {}\n", t!("report_timeline"));
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            t!("report_col_time"),
+            t!("report_col_checkpoint"),
+            t!("report_col_message"),
+            t!("report_col_changes")
+        );
+        let _ = writeln!(out, "|---|---|---|---|");
+        for checkpoint in &self.checkpoints {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                format_time(checkpoint.entry.time),
+                checkpoint_label(&checkpoint.entry, "`"),
+                markdown_cell(checkpoint.entry.summary()),
+                change_summary(checkpoint)
+            );
+        }
+
+        let _ = writeln!(out, "\n## {}\n", t!("report_files"));
+        let _ = writeln!(
+            out,
+            "| {} | {} | + | - | {} |",
+            t!("report_col_file"),
+            t!("report_col_status"),
+            t!("report_col_checkpoints")
+        );
+        let _ = writeln!(out, "|---|---|---:|---:|---:|");
+        for file in &self.files {
+            let change = &file.change;
+            let _ = writeln!(
+                out,
+                "| `{}` | {} | {} | {} | {} |",
+                change.path,
+                status_name(change.status),
+                change.additions,
+                change.deletions,
+                self.touched_by(&change.path)
+            );
+        }
+
+        let key_diffs = self.key_diffs();
+        if !key_diffs.is_empty() {
+            let _ = writeln!(out, "\n## {}", t!("report_key_diffs"));
+            for file in key_diffs {
+                let change = &file.change;
+                let _ = writeln!(
+                    out,
+                    "\n### `{}` (+{} -{})\n\n```diff\n{}```",
+                    change.path,
+                    change.additions,
+                    change.deletions,
+                    truncate_patch(&file.patch)
+                );
+            }
+        }
+        out
+    }
+
+    fn html(&self) -> String {
+        let mut out = String::new();
+        let title = escape_html(&t!("report_title", session = &self.session));
+        let _ = writeln!(
+            out,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>body{{font-family:sans-serif;max-width:60em;margin:auto}}\
+             table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:2px 6px}}\
+             pre{{background:#f6f8fa;padding:8px;overflow-x:auto}}\
+             .add{{color:#1a7f37}}.del{{color:#cf222e}}</style>\n</head>\n<body>\n<h1>{title}</h1>"
+        );
+        let _ = writeln!(out, "<ul>");
+        for line in self.overview() {
+            let _ = writeln!(out, "<li>{}</li>", escape_html(&line));
+        }
+        let _ = writeln!(out, "</ul>");
+
+        let _ = writeln!(
+            out,
+            "<h2>{}</h2>\n<table>\n<tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>",
+            escape_html(&t!("report_timeline")),
+            escape_html(&t!("report_col_time")),
+            escape_html(&t!("report_col_checkpoint")),
+            escape_html(&t!("report_col_message")),
+            escape_html(&t!("report_col_changes"))
+        );
+        for checkpoint in &self.checkpoints {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                format_time(checkpoint.entry.time),
+                escape_html(&checkpoint_label(&checkpoint.entry, "")),
+                escape_html(checkpoint.entry.summary()),
+                escape_html(&change_summary(checkpoint))
+            );
+        }
+        let _ = writeln!(out, "</table>");
+
+        let _ = writeln!(
+            out,
+            "<h2>{}</h2>\n<table>\n<tr><th>{}</th><th>{}</th><th>+</th><th>-</th><th>{}</th></tr>",
+            escape_html(&t!("report_files")),
+            escape_html(&t!("report_col_file")),
+            escape_html(&t!("report_col_status")),
+            escape_html(&t!("report_col_checkpoints"))
+        );
+        for file in &self.files {
+            let change = &file.change;
+            let _ = writeln!(
+                out,
+                "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&change.path),
+                escape_html(&status_name(change.status)),
+                change.additions,
+                change.deletions,
+                self.touched_by(&change.path)
+            );
+        }
+        let _ = writeln!(out, "</table>");
+
+        let key_diffs = self.key_diffs();
+        if !key_diffs.is_empty() {
+            let _ = writeln!(out, "<h2>{}</h2>", escape_html(&t!("report_key_diffs")));
+            for file in key_diffs {
+                let change = &file.change;
+                let _ = writeln!(
+                    out,
+                    "<h3><code>{}</code> (+{} -{})</h3>\n<pre>",
+                    escape_html(&change.path),
+                    change.additions,
+                    change.deletions
+                );
+                for line in truncate_patch(&file.patch).lines() {
+                    let class = match line.chars().next() {
+                        Some('+') if !line.starts_with("+++") => " class=\"add\"",
+                        Some('-') if !line.starts_with("---") => " class=\"del\"",
+                        _ => "",
+                    };
+                    let _ = writeln!(out, "<span{class}>{}</span>", escape_html(line));
+                }
+                let _ = writeln!(out, "</pre>");
+            }
+        }
+        let _ = writeln!(out, "</body>\n</html>");
+        out
+    }
+}
+
+/// 检查点的 `cp-N` 编号和短哈希，哈希用 `quote` 包围
+fn checkpoint_label(entry: &CheckpointEntry, quote: &str) -> String {
+    let hash = format!("{quote}{}{quote}", entry.short_hash());
+    match entry.display_id() {
+        Some(id) => format!("{id} {hash}"),
+        None => hash,
+    }
+}
+
+/// 检查点的变更统计，如 `+3 -1 (2 files)`
+fn change_summary(checkpoint: &ReportCheckpoint) -> String {
+    t!(
+        "report_change_summary",
+        additions = checkpoint.additions(),
+        deletions = checkpoint.deletions(),
+        files = checkpoint.files.len()
+    )
+    .to_string()
+}
+
+fn format_time(time: i64) -> String {
+    DateTime::from_timestamp(time, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+fn status_name(status: git2::Delta) -> String {
+    match status {
+        git2::Delta::Added => t!("report_status_added"),
+        git2::Delta::Deleted => t!("report_status_deleted"),
+        git2::Delta::Renamed => t!("report_status_renamed"),
+        _ => t!("report_status_modified"),
+    }
+    .to_string()
+}
+
+/// 差异最多保留 `REPORT_DIFF_LINES` 行，超出部分注明省略的行数，结尾总有换行
+fn truncate_patch(patch: &str) -> String {
+    let lines: Vec<&str> = patch.lines().collect();
+    let mut out: String = lines
+        .iter()
+        .take(REPORT_DIFF_LINES)
+        .map(|line| format!("{line}\n"))
+        .collect();
+    if lines.len() > REPORT_DIFF_LINES {
+        let _ = writeln!(
+            out,
+            "{}",
+            t!(
+                "report_diff_truncated",
+                count = lines.len() - REPORT_DIFF_LINES
+            )
+        );
+    }
+    out
+}
+
+/// Markdown 表格单元格中的竖线需要转义
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,522 @@
+//! 定时快照
+//!
+//! `ccg schedule install --every 30m` 在用户级调度器中注册一个定时任务，在仓库中运行
+//! `ccg create "scheduled snapshot"`。它不依赖 Claude Code 钩子，长时间手动编辑时
+//! 也能定期留下检查点。调度器按平台选择：macOS 用 launchd，Windows 用任务计划程序，
+//! Linux 上有 systemd 用户实例时用 systemd 定时器，否则用 cron。
+//!
+//! 每个仓库一个条目，以工作目录路径的哈希命名，重复安装会替换原条目。安装时使用的
+//! 调度器和间隔记录在 `ccg.schedule.scheduler` 和 `ccg.schedule.every` 中，`status`
+//! 和 `remove` 据此找到条目。
+
+use crate::error::{CheckpointError, Result as CcResult};
+use git2::{ObjectType, Oid};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+/// 定时快照的提交信息
+pub const SCHEDULED_MESSAGE: &str = "scheduled snapshot";
+
+/// 条目名称的前缀，后接工作目录路径的哈希
+pub const ENTRY_PREFIX: &str = "ccg-snapshot-";
+
+/// 一天的分钟数
+const MINUTES_PER_DAY: u64 = 24 * 60;
+
+/// 注册定时任务的调度器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheduler {
+    Cron,
+    Launchd,
+    /// systemd 用户实例的定时器
+    Systemd,
+    /// Windows 任务计划程序
+    TaskScheduler,
+}
+
+impl Scheduler {
+    /// 所有调度器
+    pub const ALL: [Scheduler; 4] = [
+        Scheduler::Cron,
+        Scheduler::Launchd,
+        Scheduler::Systemd,
+        Scheduler::TaskScheduler,
+    ];
+
+    /// 配置和命令行中使用的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Scheduler::Cron => "cron",
+            Scheduler::Launchd => "launchd",
+            Scheduler::Systemd => "systemd",
+            Scheduler::TaskScheduler => "schtasks",
+        }
+    }
+
+    /// 当前平台默认使用的调度器
+    pub fn detect() -> Self {
+        if cfg!(target_os = "macos") {
+            Scheduler::Launchd
+        } else if cfg!(windows) {
+            Scheduler::TaskScheduler
+        } else if Path::new("/run/systemd/system").exists() {
+            Scheduler::Systemd
+        } else {
+            Scheduler::Cron
+        }
+    }
+}
+
+impl fmt::Display for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Scheduler {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        Scheduler::ALL
+            .into_iter()
+            .find(|scheduler| scheduler.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Scheduler::ALL.map(Scheduler::name).to_vec();
+                format!("unknown scheduler: {s} (expected {})", names.join(", "))
+            })
+    }
+}
+
+/// 定时快照的间隔，以分钟为单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleInterval {
+    minutes: u64,
+}
+
+impl ScheduleInterval {
+    /// 间隔的分钟数
+    pub fn minutes(self) -> u64 {
+        self.minutes
+    }
+}
+
+impl FromStr for ScheduleInterval {
+    type Err = String;
+
+    /// 解析 `30m`、`2h`、`1d` 这样的间隔，不带单位时为分钟
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        let digits = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        let amount: u64 = text[..digits]
+            .parse()
+            .map_err(|_| format!("invalid interval: {s} (e.g. 30m, 2h or 1d)"))?;
+        let unit = match text[digits..].trim().to_ascii_lowercase().as_str() {
+            "" | "m" | "min" | "mins" | "minute" | "minutes" => 1,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 60,
+            "d" | "day" | "days" => MINUTES_PER_DAY,
+            _ => return Err(format!("invalid interval: {s} (e.g. 30m, 2h or 1d)")),
+        };
+        match amount.checked_mul(unit) {
+            Some(minutes) if minutes > 0 => Ok(ScheduleInterval { minutes }),
+            _ => Err(format!("invalid interval: {s} (must be at least 1 minute)")),
+        }
+    }
+}
+
+impl fmt::Display for ScheduleInterval {
+    /// 以能整除的最大单位显示，如 `90m`、`2h`、`1d`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.minutes {
+            m if m.is_multiple_of(MINUTES_PER_DAY) => write!(f, "{}d", m / MINUTES_PER_DAY),
+            m if m.is_multiple_of(60) => write!(f, "{}h", m / 60),
+            m => write!(f, "{m}m"),
+        }
+    }
+}
+
+/// 一个仓库的定时快照条目
+#[derive(Debug, Clone)]
+pub struct ScheduleEntry {
+    pub scheduler: Scheduler,
+    /// 仓库的工作目录
+    pub repo: PathBuf,
+    /// 定时运行的 ccg 可执行文件
+    pub exe: PathBuf,
+    pub interval: ScheduleInterval,
+    /// 用户主目录，launchd 和 systemd 的配置文件在其下
+    pub home: PathBuf,
+}
+
+impl ScheduleEntry {
+    /// 条目名称，同一仓库总是相同
+    pub fn name(&self) -> String {
+        let path = self.repo.to_string_lossy();
+        let hash = Oid::hash_object(ObjectType::Blob, path.as_bytes())
+            .map(|oid| oid.to_string())
+            .unwrap_or_default();
+        format!("{ENTRY_PREFIX}{}", &hash[..hash.len().min(12)])
+    }
+
+    /// 调度器运行的命令
+    pub fn command(&self) -> Vec<String> {
+        vec![
+            self.exe.to_string_lossy().into_owned(),
+            "-C".to_string(),
+            self.repo.to_string_lossy().into_owned(),
+            "create".to_string(),
+            SCHEDULED_MESSAGE.to_string(),
+        ]
+    }
+
+    /// launchd 的任务标签
+    fn launchd_label(&self) -> String {
+        format!("com.ccg.{}", self.name().trim_start_matches("ccg-"))
+    }
+
+    /// 条目写入的配置文件；cron 和任务计划程序由各自的命令保存，没有文件
+    pub fn files(&self) -> Vec<(PathBuf, String)> {
+        match self.scheduler {
+            Scheduler::Cron | Scheduler::TaskScheduler => Vec::new(),
+            Scheduler::Launchd => vec![(
+                self.home
+                    .join("Library/LaunchAgents")
+                    .join(format!("{}.plist", self.launchd_label())),
+                self.launchd_plist(),
+            )],
+            Scheduler::Systemd => {
+                let dir = self.home.join(".config/systemd/user");
+                let (service, timer) = self.systemd_units();
+                vec![
+                    (dir.join(format!("{}.service", self.name())), service),
+                    (dir.join(format!("{}.timer", self.name())), timer),
+                ]
+            }
+        }
+    }
+
+    /// crontab 中的一行，行尾注释标记所属的仓库
+    ///
+    /// cron 只能按整除一小时的分钟数、整除一天的小时数或整天重复。
+    pub fn cron_line(&self) -> CcResult<String> {
+        let minutes = self.interval.minutes;
+        let timing = if minutes < 60 && 60u64.is_multiple_of(minutes) {
+            format!("*/{minutes} * * * *")
+        } else if minutes.is_multiple_of(60)
+            && minutes < MINUTES_PER_DAY
+            && MINUTES_PER_DAY.is_multiple_of(minutes)
+        {
+            format!("0 */{} * * *", minutes / 60)
+        } else if minutes.is_multiple_of(MINUTES_PER_DAY) {
+            format!("0 0 */{} * *", minutes / MINUTES_PER_DAY)
+        } else {
+            return Err(CheckpointError::InvalidArgument(format!(
+                "cron cannot run every {}; choose minutes that divide an hour, hours that divide a day, or whole days",
+                self.interval
+            )));
+        };
+        let command = self
+            .command()
+            .iter()
+            // cron 把命令中的 `%` 当作换行
+            .map(|arg| shell_quote(arg).replace('%', "\\%"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Ok(format!(
+            "{timing} {command} >/dev/null 2>&1 # {}",
+            self.name()
+        ))
+    }
+
+    /// launchd 的任务定义
+    pub fn launchd_plist(&self) -> String {
+        let arguments: String = self
+            .command()
+            .iter()
+            .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+            .collect();
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>StartInterval</key>
+    <integer>{seconds}</integer>
+    <key>StandardOutPath</key>
+    <string>/dev/null</string>
+    <key>StandardErrorPath</key>
+    <string>/dev/null</string>
+</dict>
+</plist>
+"#,
+            label = self.launchd_label(),
+            seconds = self.interval.minutes * 60,
+        )
+    }
+
+    /// systemd 的服务和定时器单元
+    pub fn systemd_units(&self) -> (String, String) {
+        let exec = self
+            .command()
+            .iter()
+            .map(|arg| format!("\"{}\"", systemd_escape(arg)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let repo = systemd_escape(&self.repo.to_string_lossy());
+        let service = format!(
+            "[Unit]\nDescription=ccg scheduled snapshot of {repo}\n\n[Service]\nType=oneshot\nExecStart={exec}\n"
+        );
+        let timer = format!(
+            "[Unit]\nDescription=ccg scheduled snapshot of {repo} every {interval}\n\n[Timer]\nOnActiveSec={minutes}min\nOnUnitActiveSec={minutes}min\n\n[Install]\nWantedBy=timers.target\n",
+            interval = self.interval,
+            minutes = self.interval.minutes,
+        );
+        (service, timer)
+    }
+
+    /// 创建任务计划程序任务的 `schtasks` 参数
+    pub fn schtasks_args(&self) -> Vec<String> {
+        let minutes = self.interval.minutes;
+        let (schedule, modifier) = if minutes.is_multiple_of(MINUTES_PER_DAY) {
+            ("DAILY", minutes / MINUTES_PER_DAY)
+        } else if minutes.is_multiple_of(60) {
+            ("HOURLY", minutes / 60)
+        } else {
+            ("MINUTE", minutes)
+        };
+        let command = self
+            .command()
+            .iter()
+            .map(|arg| format!("\"{arg}\""))
+            .collect::<Vec<_>>()
+            .join(" ");
+        [
+            "/Create",
+            "/F",
+            "/TN",
+            &self.name(),
+            "/SC",
+            schedule,
+            "/MO",
+            &modifier.to_string(),
+            "/TR",
+            &command,
+        ]
+        .map(str::to_string)
+        .to_vec()
+    }
+
+    /// 安装时写入或执行的内容，供 `--print` 显示
+    pub fn describe(&self) -> CcResult<String> {
+        Ok(match self.scheduler {
+            Scheduler::Cron => format!("crontab:\n{}\n", self.cron_line()?),
+            Scheduler::TaskScheduler => {
+                let args: Vec<String> = self
+                    .schtasks_args()
+                    .iter()
+                    .map(|arg| {
+                        if arg.contains([' ', '"']) {
+                            format!("\"{}\"", arg.replace('"', "\\\""))
+                        } else {
+                            arg.clone()
+                        }
+                    })
+                    .collect();
+                format!("schtasks {}\n", args.join(" "))
+            }
+            Scheduler::Launchd | Scheduler::Systemd => self
+                .files()
+                .into_iter()
+                .map(|(path, content)| format!("{}:\n{content}\n", path.display()))
+                .collect(),
+        })
+    }
+
+    /// 注册条目，替换同一仓库已有的条目
+    ///
+    /// # Errors
+    /// 配置文件无法写入或调度器命令失败时返回错误
+    pub fn install(&self) -> CcResult<()> {
+        match self.scheduler {
+            Scheduler::Cron => {
+                let line = self.cron_line()?;
+                let mut crontab = self.crontab_without_entry()?;
+                crontab.push_str(&line);
+                crontab.push('\n');
+                run("crontab", &["-"], Some(&crontab)).map(|_| ())
+            }
+            Scheduler::Launchd => {
+                let path = write_files(&self.files())?;
+                let path = path.to_string_lossy();
+                // 已加载的旧任务先卸载，否则新的间隔不生效
+                let _ = run("launchctl", &["unload", &path], None);
+                run("launchctl", &["load", "-w", &path], None).map(|_| ())
+            }
+            Scheduler::Systemd => {
+                write_files(&self.files())?;
+                run("systemctl", &["--user", "daemon-reload"], None)?;
+                let timer = format!("{}.timer", self.name());
+                run("systemctl", &["--user", "enable", "--now", &timer], None)?;
+                // 已在运行的定时器按新的间隔重新开始
+                run("systemctl", &["--user", "restart", &timer], None).map(|_| ())
+            }
+            Scheduler::TaskScheduler => {
+                let args = self.schtasks_args();
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                run("schtasks", &args, None).map(|_| ())
+            }
+        }
+    }
+
+    /// 删除条目
+    ///
+    /// # Returns
+    /// 是否找到并删除了条目
+    pub fn remove(&self) -> CcResult<bool> {
+        if !self.is_installed() {
+            return Ok(false);
+        }
+        match self.scheduler {
+            Scheduler::Cron => {
+                let crontab = self.crontab_without_entry()?;
+                run("crontab", &["-"], Some(&crontab))?;
+            }
+            Scheduler::Launchd => {
+                for (path, _) in self.files() {
+                    let _ = run(
+                        "launchctl",
+                        &["unload", "-w", &path.to_string_lossy()],
+                        None,
+                    );
+                    fs::remove_file(path)?;
+                }
+            }
+            Scheduler::Systemd => {
+                let timer = format!("{}.timer", self.name());
+                let _ = run("systemctl", &["--user", "disable", "--now", &timer], None);
+                for (path, _) in self.files() {
+                    fs::remove_file(path)?;
+                }
+                run("systemctl", &["--user", "daemon-reload"], None)?;
+            }
+            Scheduler::TaskScheduler => {
+                run("schtasks", &["/Delete", "/F", "/TN", &self.name()], None)?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// 条目是否已注册
+    pub fn is_installed(&self) -> bool {
+        match self.scheduler {
+            Scheduler::Cron => run("crontab", &["-l"], None)
+                .is_ok_and(|crontab| crontab.lines().any(|line| self.is_own_cron_line(line))),
+            Scheduler::Launchd | Scheduler::Systemd => {
+                self.files().iter().any(|(path, _)| path.exists())
+            }
+            Scheduler::TaskScheduler => {
+                run("schtasks", &["/Query", "/TN", &self.name()], None).is_ok()
+            }
+        }
+    }
+
+    fn is_own_cron_line(&self, line: &str) -> bool {
+        line.trim_end().ends_with(&format!("# {}", self.name()))
+    }
+
+    /// 当前 crontab 去掉本仓库的条目，没有 crontab 时为空
+    fn crontab_without_entry(&self) -> CcResult<String> {
+        // 没有 crontab 时 `crontab -l` 以非零状态退出
+        let current = run("crontab", &["-l"], None).unwrap_or_default();
+        Ok(current
+            .lines()
+            .filter(|line| !self.is_own_cron_line(line))
+            .map(|line| format!("{line}\n"))
+            .collect())
+    }
+}
+
+/// 用户主目录
+pub fn home_dir() -> CcResult<PathBuf> {
+    ["HOME", "USERPROFILE"]
+        .iter()
+        .find_map(|key| std::env::var_os(key).filter(|value| !value.is_empty()))
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            CheckpointError::InvalidArgument("cannot find the home directory".to_string())
+        })
+}
+
+/// 写入配置文件，返回第一个文件的路径
+fn write_files(files: &[(PathBuf, String)]) -> CcResult<PathBuf> {
+    for (path, content) in files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+    }
+    files.first().map(|(path, _)| path.clone()).ok_or_else(|| {
+        CheckpointError::InvalidArgument("the scheduler writes no files".to_string())
+    })
+}
+
+/// 运行调度器命令并返回标准输出
+///
+/// # Errors
+/// 命令无法启动时返回 CheckpointError::IoError，失败时错误中带有其标准错误输出
+fn run(program: &str, args: &[&str], input: Option<&str>) -> CcResult<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{program}: {e}")))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CheckpointError::IoError(std::io::Error::other(format!(
+            "{program} {}: {}",
+            args.join(" "),
+            stderr.trim()
+        ))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 用单引号括起 shell 参数
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 转义 systemd 单元文件中带引号的值，`%` 是说明符的前缀
+fn systemd_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+}
@@ -0,0 +1,49 @@
+use crate::error::Result as CcResult;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// 创建检查点前运行的验证命令的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    /// 运行的命令，来自 `ccg.verifyCommand`
+    pub command: String,
+    /// 命令是否以零状态退出
+    pub passed: bool,
+}
+
+impl Verification {
+    /// 在 `workdir` 中通过 shell 运行 `command`
+    ///
+    /// 命令的输出直接显示给用户；命令无法启动时返回错误，
+    /// 以非零状态退出只记为未通过。
+    pub fn run(workdir: &Path, command: &str) -> CcResult<Self> {
+        let status = shell(command)
+            .current_dir(workdir)
+            .env("CCG_VERIFY", "1")
+            .stdin(Stdio::null())
+            .status()?;
+        Ok(Verification {
+            command: command.to_string(),
+            passed: status.success(),
+        })
+    }
+
+    /// 记录在检查点提交信息中的状态值
+    pub fn status(&self) -> &'static str {
+        if self.passed { "pass" } else { "fail" }
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
@@ -0,0 +1,456 @@
+//! 终端输出的图标表和布局宽度
+//!
+//! 所有命令和差异格式化输出的图标都通过 [`Icon`] 获取，由全局的
+//! [`IconMode`] 决定输出 emoji、ASCII 还是不输出图标。模式由 `--ascii`
+//! 参数或 `ccg.ui.icons` 配置设置。
+//!
+//! 分隔线和列表列宽按 [`width`] 适配终端宽度，可用 `--width` 覆盖。
+//!
+//! 无障碍模式（`--accessible` 或 `ccg.ui.accessible`）面向屏幕阅读器：图标
+//! 换成 `added:`、`Warning:` 之类的文字或省略，不输出分隔线，差异行用文字
+//! 而不是颜色标明新增、删除和移动。
+
+pub mod columns;
+pub mod highlight;
+pub mod prompt;
+pub mod report;
+
+use console::{Term, measure_text_width};
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// 图标显示模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconMode {
+    /// emoji 和 Unicode 符号（默认）
+    Unicode,
+    /// 纯 ASCII 标记，适用于无法正确显示 emoji 的终端和日志收集器
+    Ascii,
+    /// 不显示图标；项目符号、箭头和分隔线使用 ASCII
+    None,
+}
+
+impl FromStr for IconMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "unicode" => Ok(IconMode::Unicode),
+            "ascii" => Ok(IconMode::Ascii),
+            "none" => Ok(IconMode::None),
+            other => Err(format!("unknown icon mode: {other}")),
+        }
+    }
+}
+
+static ICON_MODE: AtomicU8 = AtomicU8::new(0);
+
+/// 设置全局图标模式
+pub fn set_icon_mode(mode: IconMode) {
+    let value = match mode {
+        IconMode::Unicode => 0,
+        IconMode::Ascii => 1,
+        IconMode::None => 2,
+    };
+    ICON_MODE.store(value, Ordering::Relaxed);
+}
+
+/// 当前的全局图标模式
+pub fn icon_mode() -> IconMode {
+    match ICON_MODE.load(Ordering::Relaxed) {
+        1 => IconMode::Ascii,
+        2 => IconMode::None,
+        _ => IconMode::Unicode,
+    }
+}
+
+static ACCESSIBLE: AtomicBool = AtomicBool::new(false);
+
+/// 开启或关闭无障碍输出模式
+pub fn set_accessible(accessible: bool) {
+    ACCESSIBLE.store(accessible, Ordering::Relaxed);
+}
+
+/// 是否为无障碍输出模式
+pub fn accessible() -> bool {
+    ACCESSIBLE.load(Ordering::Relaxed)
+}
+
+/// 差异行前的标记：`+`、`-` 或空格，无障碍模式下为说明该行的文字
+///
+/// `moved` 表示该行属于被移动的代码块（默认只用颜色区分）。
+pub fn diff_marker(origin: char, moved: bool) -> &'static str {
+    match (accessible(), origin, moved) {
+        (false, '+', _) => "+",
+        (false, '-', _) => "-",
+        (false, _, _) => " ",
+        (true, '+', false) => "added:",
+        (true, '+', true) => "moved here:",
+        (true, '-', false) => "removed:",
+        (true, '-', true) => "moved away:",
+        (true, _, _) => "unchanged:",
+    }
+}
+
+/// 输出中使用的图标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    Success,
+    Warning,
+    Danger,
+    Error,
+    Hint,
+    Info,
+    Location,
+    Start,
+    Switch,
+    Empty,
+    List,
+    Search,
+    Revert,
+    Reword,
+    Replay,
+    Split,
+    Drop,
+    Note,
+    Folder,
+    Branch,
+    Stats,
+    Plugin,
+    Doctor,
+    FileAdded,
+    FileDeleted,
+    FileModified,
+    FileRenamed,
+    FileCopied,
+    File,
+    /// 列表中的当前项
+    Current,
+    /// 列表中的其他项
+    Bullet,
+    /// 列表中的恢复记录
+    Restored,
+    /// 提示信息中的列表项
+    Dot,
+    Arrow,
+    Minus,
+    Separator,
+    /// 截断文本的省略号
+    Ellipsis,
+}
+
+impl Icon {
+    /// emoji / Unicode 形式
+    pub fn unicode(self) -> &'static str {
+        match self {
+            Icon::Success => "✅",
+            Icon::Warning => "⚠️",
+            Icon::Danger => "🚨",
+            Icon::Error => "❌",
+            Icon::Hint => "💡",
+            Icon::Info => "ℹ️",
+            Icon::Location => "📍",
+            Icon::Start => "🚀",
+            Icon::Switch => "🔄",
+            Icon::Empty => "📭",
+            Icon::List => "📋",
+            Icon::Search => "🔍",
+            Icon::Revert => "↩️",
+            Icon::Reword => "✏️",
+            Icon::Replay => "🔁",
+            Icon::Split => "✂️",
+            Icon::Drop => "🗑️",
+            Icon::Note => "📝",
+            Icon::Folder => "📁",
+            Icon::Branch => "🌿",
+            Icon::Stats => "📊",
+            Icon::Plugin => "🧩",
+            Icon::Doctor => "🩺",
+            Icon::FileAdded => "📄",
+            Icon::FileDeleted => "🗑️",
+            Icon::FileModified => "📝",
+            Icon::FileRenamed => "📋",
+            Icon::FileCopied => "📑",
+            Icon::File => "📄",
+            Icon::Current => "●",
+            Icon::Bullet => "○",
+            Icon::Restored => "⟲",
+            Icon::Dot => "•",
+            Icon::Arrow => "→",
+            Icon::Minus => "−",
+            Icon::Separator => "─",
+            Icon::Ellipsis => "…",
+        }
+    }
+
+    /// ASCII 形式
+    pub fn ascii(self) -> &'static str {
+        match self {
+            Icon::Success => "[ok]",
+            Icon::Warning => "[!]",
+            Icon::Danger => "[!!]",
+            Icon::Error => "[x]",
+            Icon::Hint => "[tip]",
+            Icon::Info => "[i]",
+            Icon::Location => "[@]",
+            Icon::Start => "[>]",
+            Icon::Switch => "[~]",
+            Icon::Empty => "[-]",
+            Icon::List => "[#]",
+            Icon::Search => "[?]",
+            Icon::Revert => "[<]",
+            Icon::Reword => "[e]",
+            Icon::Replay => "[r]",
+            Icon::Split => "[/]",
+            Icon::Drop => "[-]",
+            Icon::Note => "[*]",
+            Icon::Folder => "[d]",
+            Icon::Branch => "[b]",
+            Icon::Stats => "[=]",
+            Icon::Plugin => "[p]",
+            Icon::Doctor => "[+]",
+            Icon::FileAdded => "[A]",
+            Icon::FileDeleted => "[D]",
+            Icon::FileModified => "[M]",
+            Icon::FileRenamed => "[R]",
+            Icon::FileCopied => "[C]",
+            Icon::File => "[F]",
+            Icon::Current => "*",
+            Icon::Bullet => "o",
+            Icon::Restored => "<",
+            Icon::Dot => "-",
+            Icon::Arrow => "->",
+            Icon::Minus => "-",
+            Icon::Separator => "-",
+            Icon::Ellipsis => "...",
+        }
+    }
+
+    /// 无障碍模式下的形式：表达含义的图标换成文字，装饰性的图标和分隔线省略
+    pub fn label(self) -> &'static str {
+        match self {
+            Icon::Success => "OK:",
+            Icon::Warning => "Warning:",
+            Icon::Danger => "Danger:",
+            Icon::Error => "Error:",
+            Icon::Hint => "Tip:",
+            Icon::Info => "Info:",
+            Icon::FileAdded => "added:",
+            Icon::FileDeleted => "deleted:",
+            Icon::FileModified => "modified:",
+            Icon::FileRenamed => "renamed:",
+            Icon::FileCopied => "copied:",
+            Icon::Current => "current:",
+            Icon::Restored => "restored:",
+            Icon::Arrow => "to",
+            Icon::Minus => "-",
+            Icon::Ellipsis => "...",
+            _ => "",
+        }
+    }
+
+    /// 项目符号、箭头等结构性符号，不显示图标时仍需保留
+    fn is_structural(self) -> bool {
+        matches!(
+            self,
+            Icon::Current
+                | Icon::Bullet
+                | Icon::Restored
+                | Icon::Dot
+                | Icon::Arrow
+                | Icon::Minus
+                | Icon::Separator
+                | Icon::Ellipsis
+        )
+    }
+
+    /// 当前模式下的图标（不显示图标时为空字符串）
+    pub fn glyph(self) -> &'static str {
+        if accessible() {
+            return self.label();
+        }
+        match icon_mode() {
+            IconMode::Unicode => self.unicode(),
+            IconMode::Ascii => self.ascii(),
+            IconMode::None if self.is_structural() => self.ascii(),
+            IconMode::None => "",
+        }
+    }
+
+    /// 放在文字前的图标，带一个空格分隔（不显示图标时为空字符串）
+    pub fn prefix(self) -> String {
+        match self.glyph() {
+            "" => String::new(),
+            glyph => format!("{glyph} "),
+        }
+    }
+
+    /// 由图标重复组成的分隔线
+    pub fn repeat(self, width: usize) -> String {
+        self.glyph().repeat(width)
+    }
+}
+
+/// 无法检测终端宽度（如输出被重定向）时使用的宽度
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// 布局宽度的下限，避免极窄终端下分隔线和列宽失去意义
+pub const MIN_WIDTH: usize = 40;
+
+static WIDTH_OVERRIDE: AtomicUsize = AtomicUsize::new(0);
+
+/// 覆盖检测到的终端宽度（None 恢复自动检测）
+pub fn set_width(width: Option<usize>) {
+    WIDTH_OVERRIDE.store(width.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// 输出布局使用的宽度
+///
+/// 优先使用 `--width`，其次是标准输出所在终端的宽度，否则为 `DEFAULT_WIDTH`。
+pub fn width() -> usize {
+    let width = match WIDTH_OVERRIDE.load(Ordering::Relaxed) {
+        0 => Term::stdout()
+            .size_checked()
+            .map(|(_, columns)| columns as usize)
+            .unwrap_or(DEFAULT_WIDTH),
+        width => width,
+    };
+    width.max(MIN_WIDTH)
+}
+
+/// 未按仓库计算时显示的短哈希长度
+pub const SHORT_HASH_LEN: usize = 7;
+
+/// 短哈希长度，0 表示尚未由 [`ABBREV_RESOLVER`] 计算
+static ABBREV: AtomicUsize = AtomicUsize::new(SHORT_HASH_LEN);
+
+/// 计算短哈希长度的函数
+type AbbrevResolver = Box<dyn FnOnce() -> usize + Send>;
+
+static ABBREV_RESOLVER: Mutex<Option<AbbrevResolver>> = Mutex::new(None);
+
+/// 设置显示的短哈希长度
+pub fn set_abbrev(len: usize) {
+    let mut resolver = ABBREV_RESOLVER.lock().unwrap_or_else(|e| e.into_inner());
+    *resolver = None;
+    ABBREV.store(len.max(1), Ordering::Relaxed);
+}
+
+/// 设置计算短哈希长度的函数，打开仓库时按 `ccg.abbrev` 和检查点历史计算
+///
+/// 计算要遍历历史，所以推迟到第一次显示短哈希时进行；不显示哈希的命令
+/// （如钩子事件）不必付出这个开销。
+pub fn set_abbrev_with(resolve: impl FnOnce() -> usize + Send + 'static) {
+    let mut resolver = ABBREV_RESOLVER.lock().unwrap_or_else(|e| e.into_inner());
+    *resolver = Some(Box::new(resolve));
+    ABBREV.store(0, Ordering::Relaxed);
+}
+
+/// 显示的短哈希长度
+pub fn abbrev() -> usize {
+    match ABBREV.load(Ordering::Relaxed) {
+        0 => {
+            let mut resolver = ABBREV_RESOLVER.lock().unwrap_or_else(|e| e.into_inner());
+            // 等待锁期间其他线程可能已经算好
+            if let Some(len) = NonZeroUsize::new(ABBREV.load(Ordering::Relaxed)) {
+                return len.get();
+            }
+            let len = resolver
+                .take()
+                .map_or(SHORT_HASH_LEN, |resolve| resolve())
+                .max(1);
+            ABBREV.store(len, Ordering::Relaxed);
+            len
+        }
+        len => len,
+    }
+}
+
+/// 哈希的前 [`abbrev`] 个字符，较短的输入原样返回
+///
+/// 用户输入的前缀可能比短哈希还短，也可能含有多字节字符，直接切片会 panic。
+pub fn short_hash(hash: &str) -> &str {
+    match hash.char_indices().nth(abbrev()) {
+        Some((end, _)) => &hash[..end],
+        None => hash,
+    }
+}
+
+/// 截断文本使其显示宽度不超过 `max`，保留开头并以省略号结尾
+///
+/// 按字素簇截断，组合字符和 emoji 序列不会被拆开。
+pub fn truncate_end(text: &str, max: usize) -> String {
+    if measure_text_width(text) <= max {
+        return text.to_string();
+    }
+    let ellipsis = Icon::Ellipsis.glyph();
+    let mut result = take_width(
+        text.graphemes(true),
+        max.saturating_sub(measure_text_width(ellipsis)),
+    )
+    .concat();
+    result.push_str(ellipsis);
+    result
+}
+
+/// 截断文本使其显示宽度不超过 `max`，保留结尾并以省略号开头
+///
+/// 用于路径，保证文件名始终可见。
+pub fn truncate_start(text: &str, max: usize) -> String {
+    if measure_text_width(text) <= max {
+        return text.to_string();
+    }
+    let ellipsis = Icon::Ellipsis.glyph();
+    let kept = take_width(
+        text.graphemes(true).rev(),
+        max.saturating_sub(measure_text_width(ellipsis)),
+    );
+    format!("{ellipsis}{}", kept.into_iter().rev().collect::<String>())
+}
+
+/// 截断文本使其最多有 `max` 个字素簇，不加省略号
+pub fn truncate_graphemes(text: &str, max: usize) -> &str {
+    match text.grapheme_indices(true).nth(max) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// 依次取字素簇，直到显示宽度将超过 `max`
+fn take_width<'a>(graphemes: impl Iterator<Item = &'a str>, max: usize) -> Vec<&'a str> {
+    let mut result = Vec::new();
+    let mut used = 0;
+    for grapheme in graphemes {
+        let grapheme_width = measure_text_width(grapheme);
+        if used + grapheme_width > max {
+            break;
+        }
+        used += grapheme_width;
+        result.push(grapheme);
+    }
+    result
+}
+
+/// 用空格将文本补齐到显示宽度 `width`
+pub fn pad_end(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(measure_text_width(text));
+    format!("{text}{}", " ".repeat(padding))
+}
+
+/// 以二进制单位（KB、MB、GB，按 1024 进位）格式化字节数
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
@@ -0,0 +1,120 @@
+//! 按列对齐的表格布局
+//!
+//! 列表类输出（如 `ccg list`）由若干列组成：固定列按最宽的单元格对齐，
+//! 唯一的弹性列（通常是提交信息）在布局宽度内截断，保证每行不超出终端。
+//! 全为空的列不占位置，行尾不留多余空格。
+
+use super::{pad_end, truncate_end, width};
+use console::{Style, measure_text_width};
+
+/// 弹性列不会被截断到这个宽度以下，即使终端很窄
+pub const MIN_FLEXIBLE_WIDTH: usize = 20;
+
+/// 表格中的一列
+pub struct Column {
+    /// 每行一个单元格；固定列的单元格可以已带样式
+    cells: Vec<String>,
+    /// 弹性列的样式，在截断后应用
+    style: Style,
+    /// 是否为弹性列
+    flexible: bool,
+}
+
+impl Column {
+    /// 固定宽度的列，列宽为最宽的单元格
+    pub fn fixed(cells: Vec<String>) -> Self {
+        Column {
+            cells,
+            style: Style::new(),
+            flexible: false,
+        }
+    }
+
+    /// 弹性列：单元格为纯文本，超出剩余宽度时截断后再应用 `style`
+    pub fn flexible(cells: Vec<String>, style: Style) -> Self {
+        Column {
+            cells,
+            style,
+            flexible: true,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.cells
+            .iter()
+            .map(|cell| measure_text_width(cell))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// 把各列排成对齐的行
+///
+/// `indent` 是调用方在每行前输出的前缀宽度，计入布局宽度。
+pub fn layout(columns: Vec<Column>, indent: usize) -> Vec<String> {
+    let rows = columns
+        .iter()
+        .map(|column| column.cells.len())
+        .max()
+        .unwrap_or(0);
+    let columns: Vec<Column> = columns
+        .into_iter()
+        .filter(|column| column.width() > 0)
+        .collect();
+
+    // 固定列和列间空格之外的宽度留给弹性列
+    let fixed: usize = columns
+        .iter()
+        .filter(|column| !column.flexible)
+        .map(Column::width)
+        .sum::<usize>()
+        + columns.len().saturating_sub(1);
+    let budget = width()
+        .saturating_sub(indent + fixed)
+        .max(MIN_FLEXIBLE_WIDTH);
+    let cells: Vec<Vec<String>> = columns
+        .iter()
+        .map(|column| {
+            if column.flexible {
+                column
+                    .cells
+                    .iter()
+                    .map(|cell| truncate_end(cell, budget))
+                    .collect()
+            } else {
+                column.cells.clone()
+            }
+        })
+        .collect();
+    let widths: Vec<usize> = cells
+        .iter()
+        .map(|column| {
+            column
+                .iter()
+                .map(|cell| measure_text_width(cell))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    (0..rows)
+        .map(|row| {
+            let mut line = String::new();
+            for (i, column) in columns.iter().enumerate() {
+                let cell = cells[i].get(row).map(String::as_str).unwrap_or("");
+                let cell = if column.flexible {
+                    // 先按纯文本补齐再上色，避免样式影响宽度计算
+                    let padding = widths[i].saturating_sub(measure_text_width(cell));
+                    format!("{}{}", column.style.apply_to(cell), " ".repeat(padding))
+                } else {
+                    pad_end(cell, widths[i])
+                };
+                if i > 0 {
+                    line.push(' ');
+                }
+                line.push_str(&cell);
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
@@ -0,0 +1,253 @@
+//! 轻量的语法高亮
+//!
+//! 按文件扩展名识别语言，逐行着色注释、字符串、数字和关键字。
+//! 不做完整的词法分析：跨行的字符串和块注释只按行处理。
+
+use console::{Color, style};
+
+/// 语言的高亮规则
+struct Language {
+    line_comment: &'static [&'static str],
+    quotes: &'static [char],
+    keywords: &'static [&'static str],
+}
+
+const RUST: Language = Language {
+    line_comment: &["//"],
+    quotes: &['"'],
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+};
+
+const PYTHON: Language = Language {
+    line_comment: &["#"],
+    quotes: &['"', '\''],
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "False", "finally", "for", "from", "global", "if", "import",
+        "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return", "True",
+        "try", "while", "with", "yield",
+    ],
+};
+
+const JAVASCRIPT: Language = Language {
+    line_comment: &["//"],
+    quotes: &['"', '\'', '`'],
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "return",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "type",
+        "typeof",
+        "undefined",
+        "var",
+        "while",
+        "yield",
+    ],
+};
+
+const GO: Language = Language {
+    line_comment: &["//"],
+    quotes: &['"', '`'],
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "false",
+        "for",
+        "func",
+        "go",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "nil",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "true",
+        "type",
+        "var",
+    ],
+};
+
+const C_LIKE: Language = Language {
+    line_comment: &["//"],
+    quotes: &['"', '\''],
+    keywords: &[
+        "break",
+        "case",
+        "catch",
+        "char",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "extends",
+        "false",
+        "final",
+        "float",
+        "for",
+        "if",
+        "import",
+        "int",
+        "long",
+        "new",
+        "null",
+        "nullptr",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "short",
+        "static",
+        "struct",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typedef",
+        "void",
+        "while",
+    ],
+};
+
+const SHELL: Language = Language {
+    line_comment: &["#"],
+    quotes: &['"', '\''],
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "while",
+    ],
+};
+
+const CONFIG: Language = Language {
+    line_comment: &["#"],
+    quotes: &['"', '\''],
+    keywords: &["false", "true", "null"],
+};
+
+fn language_for(path: &str) -> Option<&'static Language> {
+    let extension = path.rsplit_once('.')?.1.to_lowercase();
+    match extension.as_str() {
+        "rs" => Some(&RUST),
+        "py" => Some(&PYTHON),
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => Some(&JAVASCRIPT),
+        "go" => Some(&GO),
+        "c" | "h" | "cc" | "cpp" | "hpp" | "java" | "kt" | "cs" | "swift" => Some(&C_LIKE),
+        "sh" | "bash" | "zsh" => Some(&SHELL),
+        "toml" | "yaml" | "yml" | "ini" | "conf" => Some(&CONFIG),
+        _ => None,
+    }
+}
+
+/// 为 `path` 的内容着色；无法识别语言时原样返回
+pub fn highlight(source: &str, path: &str) -> String {
+    let Some(language) = language_for(path) else {
+        return source.to_string();
+    };
+    source
+        .split_inclusive('\n')
+        .map(|line| highlight_line(line, language))
+        .collect()
+}
+
+fn highlight_line(line: &str, language: &Language) -> String {
+    let mut result = String::new();
+    let mut rest = line;
+    while let Some(ch) = rest.chars().next() {
+        if language.line_comment.iter().any(|c| rest.starts_with(c)) {
+            let comment = rest.trim_end_matches('\n');
+            result.push_str(&style(comment).fg(Color::Green).dim().to_string());
+            result.push_str(&rest[comment.len()..]);
+            break;
+        }
+
+        let token_len = if language.quotes.contains(&ch) {
+            let len = string_len(rest, ch);
+            result.push_str(&style(&rest[..len]).fg(Color::Yellow).to_string());
+            len
+        } else if ch.is_alphanumeric() || ch == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+            if ch.is_ascii_digit() {
+                result.push_str(&style(word).fg(Color::Magenta).to_string());
+            } else if language.keywords.contains(&word) {
+                result.push_str(&style(word).fg(Color::Blue).bold().to_string());
+            } else {
+                result.push_str(word);
+            }
+            len
+        } else {
+            result.push(ch);
+            ch.len_utf8()
+        };
+        rest = &rest[token_len..];
+    }
+    result
+}
+
+/// 以 `quote` 开头的字符串字面量的字节长度（未闭合时到行尾）
+fn string_len(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, ch) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == quote {
+            return i + ch.len_utf8();
+        } else if ch == '\n' {
+            return i;
+        }
+    }
+    text.len()
+}
@@ -0,0 +1,166 @@
+//! 本地化的确认提示
+//!
+//! 所有是/否提示都通过这里发出：提示文字来自 i18n 词条，选项和回显的
+//! 答案由 [`LocalizedTheme`] 按当前语言显示。`ccg.prompt` 配置的
+//! [`PromptLevel`] 决定哪些确认可以跳过，跳过的确认视为同意并输出一行说明。
+
+use crate::error::Result as CcResult;
+use console::style;
+use dialoguer::theme::Theme;
+use dialoguer::{Confirm, MultiSelect};
+use rust_i18n::t;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 哪些确认需要询问用户
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PromptLevel {
+    /// 所有确认都询问（默认）
+    #[default]
+    Always,
+    /// 只询问会丢弃数据的确认，如恢复检查点
+    DestructiveOnly,
+    /// 从不询问，所有确认视为同意
+    Never,
+}
+
+impl FromStr for PromptLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "always" => Ok(PromptLevel::Always),
+            "destructive-only" | "destructive" => Ok(PromptLevel::DestructiveOnly),
+            "never" => Ok(PromptLevel::Never),
+            other => Err(format!("unknown prompt level: {other}")),
+        }
+    }
+}
+
+/// 确认的类别，决定 [`PromptLevel`] 是否跳过它
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    /// 不会丢失数据的确认，如在当前目录初始化仓库
+    Routine,
+    /// 会覆盖工作目录或丢弃检查点的确认
+    Destructive,
+}
+
+static PROMPT_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+/// 设置全局确认级别
+pub fn set_prompt_level(level: PromptLevel) {
+    let value = match level {
+        PromptLevel::Always => 0,
+        PromptLevel::DestructiveOnly => 1,
+        PromptLevel::Never => 2,
+    };
+    PROMPT_LEVEL.store(value, Ordering::Relaxed);
+}
+
+/// 当前的全局确认级别
+pub fn prompt_level() -> PromptLevel {
+    match PROMPT_LEVEL.load(Ordering::Relaxed) {
+        1 => PromptLevel::DestructiveOnly,
+        2 => PromptLevel::Never,
+        _ => PromptLevel::Always,
+    }
+}
+
+impl PromptLevel {
+    /// 此级别下 `kind` 类的确认是否需要询问
+    pub fn asks(self, kind: PromptKind) -> bool {
+        match self {
+            PromptLevel::Always => true,
+            PromptLevel::DestructiveOnly => kind == PromptKind::Destructive,
+            PromptLevel::Never => false,
+        }
+    }
+}
+
+/// 按当前语言显示选项和答案的 dialoguer 主题
+///
+/// 按键仍然是 y/n，只有显示的文字随语言变化。
+pub struct LocalizedTheme;
+
+impl Theme for LocalizedTheme {
+    fn format_confirm_prompt(
+        &self,
+        f: &mut dyn fmt::Write,
+        prompt: &str,
+        default: Option<bool>,
+    ) -> fmt::Result {
+        if !prompt.is_empty() {
+            write!(f, "{prompt} ")?;
+        }
+        let choices = match default {
+            None => t!("prompt_choices"),
+            Some(true) => t!("prompt_choices_default_yes"),
+            Some(false) => t!("prompt_choices_default_no"),
+        };
+        write!(f, "{choices} ")
+    }
+
+    fn format_confirm_prompt_selection(
+        &self,
+        f: &mut dyn fmt::Write,
+        prompt: &str,
+        selection: Option<bool>,
+    ) -> fmt::Result {
+        let selection = selection.map(answer);
+        match selection {
+            Some(selection) if prompt.is_empty() => write!(f, "{selection}"),
+            Some(selection) => write!(f, "{prompt} {selection}"),
+            None => write!(f, "{prompt}"),
+        }
+    }
+}
+
+/// 答案在当前语言中的文字
+fn answer(yes: bool) -> String {
+    if yes {
+        t!("prompt_yes").to_string()
+    } else {
+        t!("prompt_no").to_string()
+    }
+}
+
+/// 请求确认；当前 [`PromptLevel`] 不询问此类确认时直接视为同意
+pub fn confirm(kind: PromptKind, prompt: &str, default: Option<bool>) -> CcResult<bool> {
+    if !prompt_level().asks(kind) {
+        println!(
+            "{} {} {}",
+            prompt,
+            answer(true),
+            style(t!("prompt_skipped")).dim()
+        );
+        return Ok(true);
+    }
+    ask(prompt, default)
+}
+
+/// 询问是/否问题，不受 [`PromptLevel`] 影响
+///
+/// 用于选择而非确认的场景，如逐块恢复时选择变更块。
+pub fn ask(prompt: &str, default: Option<bool>) -> CcResult<bool> {
+    let theme = LocalizedTheme;
+    let mut confirm = Confirm::with_theme(&theme).with_prompt(prompt);
+    if let Some(default) = default {
+        confirm = confirm.default(default);
+    }
+    Ok(confirm.interact()?)
+}
+
+/// 多选列表，返回选中项的下标；`checked` 为初始选中的项
+///
+/// 空格切换选中，回车确认，不受 [`PromptLevel`] 影响。
+pub fn select(prompt: &str, items: &[String], checked: &[bool]) -> CcResult<Vec<usize>> {
+    let theme = LocalizedTheme;
+    Ok(MultiSelect::with_theme(&theme)
+        .with_prompt(prompt)
+        .items(items)
+        .defaults(checked)
+        .max_length(20)
+        .interact()?)
+}
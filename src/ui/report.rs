@@ -0,0 +1,52 @@
+//! 命令失败时的错误输出
+//!
+//! `main` 和逐个仓库执行的 `ccg multi` 都通过这里输出错误：错误信息、
+//! 逐级缩进的原因链，以及有合适建议时的提示。
+
+use super::Icon;
+use crate::error::CliError;
+use console::{Color, style};
+use rust_i18n::t;
+use std::fmt::Write;
+
+/// 把错误渲染为终端输出：错误信息、原因链和建议
+pub fn render(error: &CliError) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}{}: {error}",
+        style(Icon::Error.prefix()).fg(Color::Red),
+        t!("error_prefix")
+    );
+    let mut source = std::error::Error::source(error);
+    let mut level = 1;
+    while let Some(err) = source {
+        // 原因已包含在上一级的信息中时不重复输出
+        if !out.contains(&err.to_string()) {
+            let _ = writeln!(
+                out,
+                "   {} {}: {err}",
+                "  ".repeat(level),
+                t!("error_cause_prefix")
+            );
+        }
+        source = err.source();
+        level += 1;
+    }
+    if let Some(suggestion) = error.suggestion() {
+        let _ = writeln!(
+            out,
+            "{}{}",
+            style(Icon::Hint.prefix()).fg(Color::Blue),
+            suggestion
+        );
+    }
+    out
+}
+
+/// 把命令的错误输出到 stderr，最后提示查看帮助
+pub fn report(error: &CliError) {
+    eprint!("{}", render(error));
+    eprintln!();
+    eprintln!("{}{}", Icon::Hint.prefix(), t!("error_tip"));
+}
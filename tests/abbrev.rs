@@ -0,0 +1,67 @@
+mod common;
+
+use ccg::git_ops::unique_abbrev_len;
+use common::Fixture;
+use std::collections::HashSet;
+
+#[test]
+fn short_hashes_are_long_enough_to_be_unique() {
+    let fixture = Fixture::new();
+    for i in 0..40 {
+        fixture.write("a.txt", &format!("{i}\n"));
+        fixture.commit(&format!("commit {i}"));
+    }
+    let repo = fixture.repo();
+    let mut revwalk = repo.revwalk().unwrap();
+    revwalk.push_head().unwrap();
+    let hashes: Vec<String> = revwalk.map(|oid| oid.unwrap().to_string()).collect();
+
+    let len = unique_abbrev_len(&repo, 1).unwrap();
+    assert!(len >= 4, "never shorter than git's minimum");
+    let prefixes: HashSet<&str> = hashes.iter().map(|h| &h[..len]).collect();
+    assert_eq!(prefixes.len(), hashes.len());
+    if len > 4 {
+        // 再短一位就会有重复
+        let shorter: HashSet<&str> = hashes.iter().map(|h| &h[..len - 1]).collect();
+        assert!(shorter.len() < hashes.len());
+    }
+    assert_eq!(unique_abbrev_len(&repo, 100).unwrap(), 40);
+}
+
+#[test]
+fn ccg_abbrev_sets_the_minimum_length() {
+    let fixture = Fixture::new();
+    let git_ops = fixture.git_ops();
+    assert_eq!(git_ops.abbrev_len(), 7);
+
+    let mut config = fixture.repo().config().unwrap();
+    config.set_str("ccg.abbrev", "12").unwrap();
+    assert_eq!(git_ops.abbrev_len(), 12);
+    config.set_str("ccg.abbrev", "auto").unwrap();
+    assert_eq!(git_ops.abbrev_len(), 7);
+    config.set_str("ccg.abbrev", "2").unwrap();
+    assert_eq!(git_ops.abbrev_len(), 4);
+}
+
+#[test]
+fn abbrev_len_is_cached_with_the_ref_tips() {
+    let fixture = Fixture::new();
+    let git_ops = fixture.git_ops();
+    let cache = fixture.path().join(".git/ccg/abbrev");
+    assert_eq!(git_ops.abbrev_len(), 7);
+    let content = std::fs::read_to_string(&cache).unwrap();
+    let head = fixture.branch_tip("main").unwrap().to_string();
+    assert!(content.lines().any(|line| line == head), "{content}");
+
+    // 提示没有移动时直接使用缓存的长度
+    let tips: Vec<&str> = content.lines().skip(1).collect();
+    std::fs::write(&cache, format!("20\n{}\n", tips.join("\n"))).unwrap();
+    assert_eq!(git_ops.abbrev_len(), 20);
+
+    // 新提交只与缓存的长度比较，不必重新遍历历史
+    fixture.write("a.txt", "new\n");
+    let new_head = fixture.commit("new").to_string();
+    assert_eq!(git_ops.abbrev_len(), 20);
+    let content = std::fs::read_to_string(&cache).unwrap();
+    assert!(content.lines().any(|line| line == new_head), "{content}");
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use ccg::ui::set_accessible;
+use common::{Fixture, show};
+
+// 无障碍模式是进程级全局状态，单独放在一个测试二进制中
+
+#[test]
+fn accessible_mode_uses_words_instead_of_symbols_and_color() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture.write("a.txt", "one\nkeep\n");
+    service.create_checkpoint(Some("first")).unwrap();
+    fixture.write("a.txt", "two\nkeep\n");
+    let second = service.create_checkpoint(Some("second")).unwrap();
+
+    set_accessible(true);
+    let output = show(&fixture, &second, true, &[]);
+    set_accessible(false);
+
+    assert!(output.contains("modified: "), "{output}");
+    assert!(output.contains("removed: one"), "{output}");
+    assert!(output.contains("added: two"), "{output}");
+    assert!(output.contains("unchanged: keep"), "{output}");
+    assert!(
+        !output.contains('─') && !output.contains("---") && !output.contains('📝'),
+        "{output}"
+    );
+}
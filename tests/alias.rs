@@ -0,0 +1,123 @@
+mod common;
+
+use ccg::CheckpointError;
+use ccg::commands::AliasCommand;
+use ccg::commands::alias::{expand_aliases, split_words};
+use ccg::config;
+use common::Fixture;
+use git2::ConfigLevel;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+
+fn args(words: &[&str]) -> Vec<OsString> {
+    words.iter().map(OsString::from).collect()
+}
+
+fn aliases(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(name, expansion)| (name.to_string(), expansion.to_string()))
+        .collect()
+}
+
+fn is_builtin(name: &str) -> bool {
+    matches!(name, "list" | "create" | "restore")
+}
+
+#[test]
+fn split_words_honours_quotes() {
+    assert_eq!(
+        split_words(r#"create "fix: a b" --allow-empty"#).unwrap(),
+        ["create", "fix: a b", "--allow-empty"]
+    );
+    assert_eq!(split_words("  list   -n 5 ").unwrap(), ["list", "-n", "5"]);
+    assert!(split_words("create 'open").is_err());
+}
+
+#[test]
+fn expand_aliases_replaces_the_subcommand_after_global_flags() {
+    let aliases = aliases(&[("l", "list -n 5"), ("ll", "l --stat")]);
+
+    let expanded = expand_aliases(
+        args(&[
+            "ccg",
+            "-C",
+            "repo",
+            "--width",
+            "100",
+            "--ascii",
+            "ll",
+            "--with-restores",
+        ]),
+        &aliases,
+        is_builtin,
+    )
+    .unwrap();
+
+    assert_eq!(
+        expanded,
+        args(&[
+            "ccg",
+            "-C",
+            "repo",
+            "--width",
+            "100",
+            "--ascii",
+            "list",
+            "-n",
+            "5",
+            "--stat",
+            "--with-restores"
+        ])
+    );
+}
+
+#[test]
+fn expand_aliases_leaves_builtins_and_options_alone() {
+    let aliases = aliases(&[("list", "restore"), ("x", "create")]);
+
+    let unchanged = args(&["ccg", "list", "x"]);
+    assert_eq!(
+        expand_aliases(unchanged.clone(), &aliases, is_builtin).unwrap(),
+        unchanged
+    );
+    let help = args(&["ccg", "--help", "x"]);
+    assert_eq!(
+        expand_aliases(help.clone(), &aliases, is_builtin).unwrap(),
+        help
+    );
+}
+
+#[test]
+fn expand_aliases_detects_loops() {
+    let aliases = aliases(&[("a", "b"), ("b", "a -v")]);
+
+    let result = expand_aliases(args(&["ccg", "a"]), &aliases, is_builtin);
+
+    assert!(matches!(result, Err(CheckpointError::InvalidArgument(_))));
+}
+
+#[test]
+fn set_and_unset_aliases_in_repository_config() {
+    let fixture = Fixture::new();
+    let mut local = fixture
+        .repo()
+        .config()
+        .unwrap()
+        .open_level(ConfigLevel::Local)
+        .unwrap();
+    let command = AliasCommand::new(vec!["list".to_string()], None);
+
+    command
+        .set_alias(&mut local, "rb", "restore --force")
+        .unwrap();
+    assert!(command.set_alias(&mut local, "list", "create").is_err());
+    assert!(command.set_alias(&mut local, "9x", "list").is_err());
+
+    let found = config::aliases(&fixture.repo().config().unwrap());
+    assert_eq!(found.get("rb").map(String::as_str), Some("restore --force"));
+
+    command.unset_alias(&mut local, "rb").unwrap();
+    assert!(!config::aliases(&fixture.repo().config().unwrap()).contains_key("rb"));
+    assert!(command.unset_alias(&mut local, "rb").is_err());
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{Fixture, ccg};
+
+#[test]
+fn repo_flag_runs_commands_against_another_directory() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    fixture
+        .service()
+        .create_checkpoint(Some("from library"))
+        .unwrap();
+    let outside = tempfile::tempdir().unwrap();
+
+    fixture.write("a.txt", "two\n");
+    let output = ccg(outside.path())
+        .args(["-C", fixture.path_str(), "create", "from -C"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let entries = fixture.service().checkpoints(10).unwrap();
+    assert_eq!(entries[0].summary(), "from -C");
+
+    // 全局参数也可以写在子命令之后
+    let output = ccg(outside.path())
+        .args(["list", "--repo", fixture.path_str()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("from -C"), "{stdout}");
+    assert!(stdout.contains("from library"), "{stdout}");
+    assert!(!outside.path().join(".git").exists());
+}
+
+#[test]
+fn repo_flag_rejects_a_missing_directory() {
+    let outside = tempfile::tempdir().unwrap();
+    let missing = outside.path().join("missing");
+
+    let output = ccg(outside.path())
+        .args(["-C", missing.to_str().unwrap(), "list"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!(
+            "Repository path is not a directory: {}",
+            missing.display()
+        )),
+        "{stderr}"
+    );
+    assert!(!missing.exists());
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use ccg::git_ops::{AheadBehind, CommitGraph};
+use common::Fixture;
+use git2::{Oid, Repository, Signature};
+
+fn revwalk_count(repo: &Repository, from: Oid, to: Oid) -> usize {
+    let mut revwalk = repo.revwalk().unwrap();
+    revwalk.push(to).unwrap();
+    revwalk.hide(from).unwrap();
+    revwalk.count()
+}
+
+fn left_right(repo: &Repository, from: Oid, to: Oid) -> AheadBehind {
+    AheadBehind {
+        ahead: revwalk_count(repo, from, to),
+        behind: revwalk_count(repo, to, from),
+    }
+}
+
+#[test]
+fn commit_graph_is_written_when_enabled_and_follows_prune() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let graph_path = fixture.repo().path().join("objects/info/commit-graph");
+    fixture.write("a.txt", "0\n");
+    service.create_checkpoint(Some("off")).unwrap();
+    assert!(!graph_path.exists(), "ccg.commitGraph is off by default");
+
+    let mut config = fixture.repo().config().unwrap();
+    config.set_bool("ccg.commitGraph", true).unwrap();
+    let mut hashes = Vec::new();
+    for i in 1..5 {
+        fixture.write("a.txt", &format!("{i}\n"));
+        hashes.push(service.create_checkpoint(Some(&format!("v{i}"))).unwrap());
+    }
+    let repo = fixture.repo();
+    let graph = fixture.git_ops().commit_graph().unwrap().unwrap();
+    let first: Oid = hashes[0].parse().unwrap();
+    assert!(graph.contains(first), "written by the first checkpoint");
+    // 之后的检查点还没有达到刷新阈值
+    let tip = fixture.branch_tip("ccg").unwrap();
+    assert!(!graph.contains(tip));
+    let git_ops = fixture.git_ops();
+    assert_eq!(
+        git_ops
+            .count_commits_between(&hashes[0], &hashes[3])
+            .unwrap(),
+        3
+    );
+
+    let dropped = service.prune_checkpoints(&[hashes[1].clone()]).unwrap();
+    assert_eq!(dropped.len(), 1);
+    let graph = git_ops.commit_graph().unwrap().unwrap();
+    let tip = fixture.branch_tip("ccg").unwrap();
+    let parent = repo.find_commit(tip).unwrap().parent_id(0).unwrap();
+    assert_eq!(
+        graph.generation(tip),
+        graph.generation(parent).map(|g| g + 1)
+    );
+    assert_eq!(
+        git_ops.commits().find_commit("latest~2").unwrap().id(),
+        first
+    );
+    assert_eq!(
+        git_ops
+            .count_commits_between(&hashes[0], &tip.to_string())
+            .unwrap(),
+        2
+    );
+}
+
+#[test]
+fn generation_walk_matches_rev_list_left_right_across_merges() {
+    let fixture = Fixture::new();
+    let base = fixture.commit("base");
+    let mut side = Vec::new();
+    for i in 0..3 {
+        fixture.write("main.txt", &format!("{i}\n"));
+        side.push(fixture.commit(&format!("main {i}")));
+    }
+    let repo = fixture.repo();
+    let signature = Signature::now("Fixture", "fixture@example.com").unwrap();
+    let tree = repo.find_commit(base).unwrap().tree().unwrap();
+    let mut parent = repo.find_commit(base).unwrap();
+    let mut branch = Vec::new();
+    for i in 0..4 {
+        let id = repo
+            .commit(
+                None,
+                &signature,
+                &signature,
+                &format!("side {i}"),
+                &tree,
+                &[&parent],
+            )
+            .unwrap();
+        parent = repo.find_commit(id).unwrap();
+        branch.push(id);
+    }
+    let main = repo.find_commit(side[2]).unwrap();
+    let merge = repo
+        .commit(
+            None,
+            &signature,
+            &signature,
+            "merge",
+            &tree,
+            &[&main, &parent],
+        )
+        .unwrap();
+    repo.reference("refs/heads/merged", merge, true, "test")
+        .unwrap();
+
+    CommitGraph::write(&repo).unwrap();
+    let graph = CommitGraph::load(&repo).unwrap().unwrap();
+    assert!(graph.len() >= 9);
+    assert_eq!(
+        graph.generation(merge),
+        graph.generation(base).map(|g| g + 5)
+    );
+
+    let mut all = vec![base, merge];
+    all.extend(&side);
+    all.extend(&branch);
+    for &from in &all {
+        for &to in &all {
+            assert_eq!(
+                graph.ahead_behind(from, to),
+                Some(left_right(&repo, from, to)),
+                "{from}..{to}"
+            );
+        }
+    }
+    assert_eq!(graph.linear_ancestor(branch[3], 3), Some(branch[0]));
+    assert_eq!(graph.linear_ancestor(merge, 1), None);
+}
@@ -0,0 +1,178 @@
+//! Shared fixtures for the integration tests
+//!
+//! A [`Fixture`] owns a throwaway git repository in a temp directory. Tests
+//! seed it with files and commits, then drive ccg through the library API, or
+//! through the binary with [`ccg`] where the command-line parsing matters.
+
+#![allow(dead_code)]
+
+use ccg::commands::CommandContext;
+use ccg::{CheckpointService, GitOperations};
+use git2::{Oid, Repository, Signature};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// A checkpoint for [`Fixture::with_checkpoints`]: its message and the
+/// `(path, contents)` files written before it
+pub type Step = (&'static str, &'static [(&'static str, &'static str)]);
+
+/// A temporary git repository
+pub struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    /// A repository without any commits, with HEAD on an unborn `main`
+    pub fn empty() -> Self {
+        let dir = TempDir::new().expect("create temp dir");
+        let repo = Repository::init(dir.path()).expect("git init");
+        repo.set_head("refs/heads/main")
+            .expect("point HEAD at main");
+        let mut config = repo.config().expect("open config");
+        config.set_str("user.name", "Fixture").unwrap();
+        config.set_str("user.email", "fixture@example.com").unwrap();
+        Fixture { dir }
+    }
+
+    /// A repository with a single commit on `main` containing `README.md`
+    pub fn new() -> Self {
+        let fixture = Self::empty();
+        fixture.write("README.md", "# fixture\n");
+        fixture.commit("initial");
+        fixture
+    }
+
+    /// A repository like [`Fixture::new`] with one checkpoint per step on
+    /// top of the initial commit
+    ///
+    /// Each step writes its files, then creates a checkpoint with its message.
+    /// Returns the checkpoint hashes in creation order.
+    pub fn with_checkpoints<const N: usize>(steps: [Step; N]) -> (Self, [String; N]) {
+        let fixture = Self::new();
+        let service = fixture.service();
+        let hashes = steps.map(|(message, files)| {
+            for (path, contents) in files {
+                fixture.write(path, contents);
+            }
+            service.create_checkpoint(Some(message)).unwrap()
+        });
+        (fixture, hashes)
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn path_str(&self) -> &str {
+        self.dir.path().to_str().expect("utf-8 temp path")
+    }
+
+    pub fn repo(&self) -> Repository {
+        Repository::open(self.path()).expect("open fixture repo")
+    }
+
+    /// Write a file relative to the repository root, creating parent dirs
+    pub fn write(&self, path: &str, contents: &str) -> &Self {
+        let full = self.file_path(path);
+        if let Some(parent) = full.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(full, contents).unwrap();
+        self
+    }
+
+    pub fn read(&self, path: &str) -> Option<String> {
+        fs::read_to_string(self.file_path(path)).ok()
+    }
+
+    pub fn remove(&self, path: &str) {
+        fs::remove_file(self.file_path(path)).unwrap();
+    }
+
+    pub fn file_path(&self, path: &str) -> PathBuf {
+        self.path().join(path)
+    }
+
+    /// Stage everything and commit on the current HEAD, like `git commit -am`
+    pub fn commit(&self, message: &str) -> Oid {
+        let repo = self.repo();
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Fixture", "fixture@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap()
+    }
+
+    /// Detach HEAD at the current commit
+    pub fn detach(&self) -> Oid {
+        let repo = self.repo();
+        let oid = repo.head().unwrap().target().unwrap();
+        repo.set_head_detached(oid).unwrap();
+        oid
+    }
+
+    /// Name of the branch HEAD points to, or `None` when detached
+    pub fn head_branch(&self) -> Option<String> {
+        let repo = self.repo();
+        let head = repo.find_reference("HEAD").unwrap();
+        head.symbolic_target()
+            .and_then(|t| t.strip_prefix("refs/heads/"))
+            .map(str::to_string)
+    }
+
+    /// Commit hash a local branch points to
+    pub fn branch_tip(&self, branch: &str) -> Option<Oid> {
+        self.repo()
+            .find_branch(branch, git2::BranchType::Local)
+            .ok()
+            .and_then(|b| b.get().target())
+    }
+
+    /// Install an executable `.ccg/hooks/<event>` script
+    #[cfg(unix)]
+    pub fn hook(&self, event: &str, script: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        let path = self.file_path(&format!(".ccg/hooks/{event}"));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    pub fn git_ops(&self) -> GitOperations {
+        GitOperations::new_from_path(self.path()).expect("open GitOperations")
+    }
+
+    pub fn service(&self) -> CheckpointService {
+        CheckpointService::new(self.git_ops()).unwrap()
+    }
+
+    pub fn context(&self) -> CommandContext {
+        CommandContext::new_with_path(Some(self.path_str())).expect("create context")
+    }
+}
+
+/// The ccg binary run from `cwd` with English messages
+pub fn ccg(cwd: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ccg"));
+    command.current_dir(cwd).env("LANG", "en_US.UTF-8");
+    command
+}
+
+/// Show output with ANSI styling removed
+pub fn show(fixture: &Fixture, hash: &str, diff: bool, files: &[&str]) -> String {
+    let files: Vec<String> = files.iter().map(|f| f.to_string()).collect();
+    let mut out = Vec::new();
+    fixture
+        .git_ops()
+        .show_checkpoint(hash, diff, false, &files, &mut out)
+        .expect("show checkpoint");
+    console::strip_ansi_codes(&String::from_utf8(out).unwrap()).to_string()
+}
@@ -0,0 +1,1265 @@
+mod common;
+
+use ccg::CheckpointError;
+use ccg::commands::create::CreateOutcome;
+use ccg::commands::traits::FlushArgs;
+use ccg::commands::{Command, CreateCommand, FlushCommand};
+use ccg::config::ExcludeProfile;
+use ccg::git_ops::pending::MAX_ATTEMPTS;
+use ccg::git_ops::{EMPTY_CHECKPOINT_TRAILER, MACHINE_COMMITTER_EMAIL, MACHINE_COMMITTER_NAME};
+use ccg::git_ops::{ListColumn, ListFilter};
+use ccg::services::CreateOptions;
+use ccg::services::summary;
+use common::Fixture;
+
+#[test]
+fn create_commits_on_ccg_and_keeps_original_branch() {
+    let fixture = Fixture::new();
+    let main_tip = fixture.branch_tip("main");
+
+    fixture.write("src/lib.rs", "pub fn answer() -> u32 { 42 }\n");
+    let hash = fixture
+        .service()
+        .create_checkpoint(Some("add lib"))
+        .unwrap();
+
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), hash);
+    assert_eq!(fixture.branch_tip("main"), main_tip);
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+    let entries = fixture.service().checkpoints(1).unwrap();
+    assert_eq!(entries[0].summary(), "add lib");
+}
+
+#[test]
+fn create_without_changes_is_skipped() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    let service = fixture.service();
+    service.create_checkpoint(Some("first")).unwrap();
+    let tip = fixture.branch_tip("ccg");
+
+    let hash = service.create_checkpoint(Some("second")).unwrap();
+
+    assert!(hash.is_empty());
+    assert_eq!(fixture.branch_tip("ccg"), tip);
+}
+
+#[test]
+fn rewriting_identical_content_is_skipped() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    let service = fixture.service();
+    service.create_checkpoint(Some("first")).unwrap();
+    let tip = fixture.branch_tip("ccg");
+
+    fixture.remove("a.txt");
+    fixture.write("a.txt", "one\n");
+    let hash = service.create_checkpoint(Some("rewrite")).unwrap();
+
+    assert!(hash.is_empty());
+    assert_eq!(fixture.branch_tip("ccg"), tip);
+}
+
+#[test]
+fn allow_empty_creates_marked_checkpoint_with_same_tree() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    let service = fixture.service();
+    let first = service.create_checkpoint(Some("first")).unwrap();
+
+    let hash = service
+        .create_checkpoint_with(Some("nothing"), true)
+        .unwrap();
+
+    let repo = fixture.repo();
+    let commit = repo.find_commit(hash.parse().unwrap()).unwrap();
+    let parent = repo.find_commit(first.parse().unwrap()).unwrap();
+    assert_eq!(commit.parent_id(0).unwrap(), parent.id());
+    assert_eq!(commit.tree_id(), parent.tree_id());
+    assert!(commit.message().unwrap().contains(EMPTY_CHECKPOINT_TRAILER));
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+
+    // 空检查点和其他检查点一样记入占用空间和统计索引
+    let sizes = std::fs::read_to_string(repo.path().join("ccg").join("sizes")).unwrap();
+    assert!(sizes.contains(&format!("{hash} 0")));
+    let stats = ccg::git_ops::StatsIndex::new(&repo).load().unwrap();
+    assert_eq!(stats[&commit.id()].total_files, 0);
+    assert_eq!(fixture.git_ops().storage_usage().unwrap().checkpoints, 2);
+}
+
+#[test]
+fn create_in_empty_repository_creates_initial_commit() {
+    let fixture = Fixture::empty();
+    fixture.write("a.txt", "one\n");
+
+    fixture.service().create_checkpoint(Some("first")).unwrap();
+
+    let ccg_tip = fixture.branch_tip("ccg").expect("ccg branch created");
+    let repo = fixture.repo();
+    let tree = repo.find_commit(ccg_tip).unwrap().tree().unwrap();
+    assert!(tree.get_name("a.txt").is_some());
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn create_from_detached_head_returns_to_detached_commit() {
+    let fixture = Fixture::new();
+    let detached = fixture.detach();
+
+    fixture.write("a.txt", "one\n");
+    fixture.service().create_checkpoint(Some("first")).unwrap();
+
+    assert_eq!(fixture.head_branch(), None);
+    assert_eq!(fixture.repo().head().unwrap().target(), Some(detached));
+    assert!(fixture.branch_tip("ccg").is_some());
+}
+
+#[test]
+fn create_from_hook_payload_summarizes_tool_call() {
+    let fixture = Fixture::new();
+    fixture.write("src/main.rs", "fn main() {}\n");
+    let payload = serde_json::json!({
+        "tool_name": "Edit",
+        "tool_input": { "file_path": fixture.file_path("src/main.rs") },
+        "tool_response": { "structuredPatch": [{ "lines": ["-old", "+fn main() {}"] }] },
+        "cwd": fixture.path_str(),
+    });
+
+    CreateCommand::new(fixture.context())
+        .create_from_payload(&payload.to_string())
+        .unwrap();
+
+    let entries = fixture.service().checkpoints(1).unwrap();
+    let entry = &entries[0];
+    assert_eq!(entry.summary(), "Edit on main.rs");
+    assert!(entry.message.contains("+fn main() {}"));
+}
+
+#[test]
+fn huge_hook_payloads_are_capped_with_the_rest_in_a_note() {
+    let fixture = Fixture::new();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_str("ccg.message.maxBytes", "1k")
+        .unwrap();
+    fixture.write("big.txt", "big\n");
+    let lines: Vec<String> = (0..500).map(|i| format!("+\u{1b}[32mline {i}")).collect();
+    let payload = serde_json::json!({
+        "tool_name": "Write",
+        "tool_input": { "file_path": fixture.file_path("big.txt") },
+        "tool_response": { "structuredPatch": [{ "lines": lines }] },
+        "cwd": fixture.path_str(),
+    });
+
+    let hash = CreateCommand::new(fixture.context())
+        .create_from_payload(&payload.to_string())
+        .unwrap();
+
+    let repo = fixture.repo();
+    let oid = hash.parse().unwrap();
+    let message = repo
+        .find_commit(oid)
+        .unwrap()
+        .message()
+        .unwrap()
+        .to_string();
+    assert!(message.len() < 1200);
+    assert!(message.starts_with("Write on big.txt\n"));
+    assert!(!message.contains('\u{1b}'));
+    assert!(message.contains("more bytes in git note refs/notes/ccg]"));
+    assert!(message.contains("Ccg-Tool: Write\n"));
+    let note = repo.find_note(Some("refs/notes/ccg"), oid).unwrap();
+    assert!(note.message().unwrap().contains("+line 499\n"));
+}
+
+#[test]
+fn hook_events_failing_on_a_lock_are_queued_and_replayed() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    fixture.service().create_checkpoint(Some("first")).unwrap();
+    fixture.write("src/main.rs", "fn main() {}\n");
+    let payload = serde_json::json!({
+        "tool_name": "Write",
+        "tool_input": { "file_path": fixture.file_path("src/main.rs") },
+        "tool_response": {},
+        "cwd": fixture.path_str(),
+    })
+    .to_string();
+
+    // 另一个进程持有 ccg 分支的锁
+    let lock = fixture.repo().path().join("refs/heads/ccg.lock");
+    std::fs::write(&lock, "").unwrap();
+    let command = CreateCommand::new(fixture.context());
+    let err = command.create_from_payload(&payload).unwrap_err();
+    assert!(err.is_transient(), "{err}");
+    // 同一事件只排队一次
+    assert!(command.create_from_payload(&payload).is_err());
+    let git_ops = fixture.git_ops();
+    assert_eq!(git_ops.pending().len().unwrap(), 1);
+
+    std::fs::remove_file(&lock).unwrap();
+    let replayed = FlushCommand::new(fixture.context())
+        .execute(FlushArgs)
+        .unwrap();
+    assert_eq!(replayed, 1);
+    assert!(git_ops.pending().is_empty().unwrap());
+    let entries = fixture.service().checkpoints(1).unwrap();
+    assert_eq!(entries[0].summary(), "Write on main.rs");
+}
+
+#[test]
+fn a_queued_event_that_keeps_failing_is_dropped_after_max_attempts() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    fixture.service().create_checkpoint(Some("first")).unwrap();
+    let payload = |file: &str| {
+        fixture.write(file, "content\n");
+        serde_json::json!({
+            "tool_name": "Write",
+            "tool_input": { "file_path": fixture.file_path(file) },
+            "tool_response": {},
+            "cwd": fixture.path_str(),
+        })
+        .to_string()
+    };
+    let git_ops = fixture.git_ops();
+    let queue = git_ops.pending();
+    queue.push(&payload("poison.txt")).unwrap();
+    queue.push(&payload("b.txt")).unwrap();
+
+    // 队首的事件每次重放都因锁失败
+    let lock = fixture.repo().path().join("refs/heads/ccg.lock");
+    std::fs::write(&lock, "").unwrap();
+    let flush = || FlushCommand::new(fixture.context()).execute(FlushArgs);
+    for attempt in 1..MAX_ATTEMPTS {
+        assert!(flush().is_err());
+        let entries = queue.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].attempts, attempt);
+        assert_eq!(entries[1].attempts, 0, "later events are not attempted");
+    }
+    assert!(flush().is_err());
+    let entries = queue.entries().unwrap();
+    assert_eq!(entries.len(), 1, "the poison event is dropped");
+    assert!(entries[0].payload.contains("b.txt"));
+
+    std::fs::remove_file(&lock).unwrap();
+    assert_eq!(flush().unwrap(), 1);
+    assert!(queue.is_empty().unwrap());
+    // 权限等其他 I/O 错误重试也不会成功
+    let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+    assert!(!CheckpointError::IoError(denied).is_transient());
+}
+
+#[test]
+fn create_works_while_another_git_process_holds_the_index_lock() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    fixture.service().create_checkpoint(Some("first")).unwrap();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_i64("ccg.lockTimeout", 50)
+        .unwrap();
+    let index = fixture.repo().path().join("index");
+    let lock = fixture.repo().path().join("index.lock");
+    std::fs::write(&lock, "").unwrap();
+    let before = std::fs::read(&index).unwrap();
+
+    fixture.write("a.txt", "two\n");
+    let started = std::time::Instant::now();
+    let hash = fixture.service().create_checkpoint(Some("second")).unwrap();
+
+    // 等待后改用内存中的索引，磁盘上的索引和锁都保持原样
+    assert!(started.elapsed() >= std::time::Duration::from_millis(50));
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), hash);
+    assert_eq!(std::fs::read(&index).unwrap(), before);
+    assert!(lock.exists());
+    let repo = fixture.repo();
+    let tree = repo
+        .find_commit(hash.parse().unwrap())
+        .unwrap()
+        .tree()
+        .unwrap();
+    let blob = repo
+        .find_blob(tree.get_path("a.txt".as_ref()).unwrap().id())
+        .unwrap();
+    assert_eq!(blob.content(), b"two\n");
+}
+
+#[test]
+fn per_file_granularity_creates_one_checkpoint_per_edited_file() {
+    let fixture = Fixture::new();
+    fixture
+        .write("src/a.rs", "fn a() {}\n")
+        .write("src/b.rs", "fn b() {}\n")
+        .write("notes.txt", "later\n");
+    let mut config = fixture.repo().config().unwrap();
+    config
+        .set_str("ccg.checkpoint.granularity", "per-file")
+        .unwrap();
+    let payload = serde_json::json!({
+        "tool_name": "MultiEdit",
+        "tool_input": {
+            "file_path": fixture.file_path("src/a.rs"),
+            "edits": [
+                { "file_path": fixture.file_path("src/b.rs") },
+                { "file_path": fixture.file_path("src/a.rs") },
+            ],
+        },
+        "tool_response": {},
+        "cwd": fixture.path_str(),
+    });
+
+    let tip = CreateCommand::new(fixture.context())
+        .create_from_payload(&payload.to_string())
+        .unwrap();
+
+    let entries = fixture.service().checkpoints(10).unwrap();
+    assert_eq!(entries[0].hash, tip);
+    let summaries: Vec<&str> = entries.iter().take(2).map(|e| e.summary()).collect();
+    assert_eq!(summaries, ["MultiEdit on b.rs", "MultiEdit on a.rs"]);
+
+    // 每个检查点只包含自己的文件，事件没有提到的文件留给之后的检查点
+    let repo = fixture.repo();
+    let tree = |hash: &str| {
+        repo.find_commit(hash.parse().unwrap())
+            .unwrap()
+            .tree()
+            .unwrap()
+    };
+    let first = tree(&entries[1].hash);
+    assert!(first.get_path("src/a.rs".as_ref()).is_ok());
+    assert!(first.get_path("src/b.rs".as_ref()).is_err());
+    let second = tree(&entries[0].hash);
+    assert!(second.get_path("src/b.rs".as_ref()).is_ok());
+    assert!(second.get_path("notes.txt".as_ref()).is_err());
+
+    // 默认每个事件一个检查点
+    fixture
+        .write("src/a.rs", "fn a2() {}\n")
+        .write("src/b.rs", "fn b2() {}\n");
+    config.remove("ccg.checkpoint.granularity").unwrap();
+    CreateCommand::new(fixture.context())
+        .create_from_payload(&payload.to_string())
+        .unwrap();
+    let entries = fixture.service().checkpoints(10).unwrap();
+    assert_eq!(entries[0].summary(), "MultiEdit on a.rs");
+    assert_eq!(entries[1].hash, tip);
+}
+
+#[test]
+fn create_from_plain_payload_uses_raw_text() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+
+    CreateCommand::new(fixture.context())
+        .create_from_payload("not json")
+        .unwrap();
+
+    let entries = fixture.service().checkpoints(1).unwrap();
+    let entry = &entries[0];
+    assert_eq!(entry.summary(), "not json");
+}
+
+#[cfg(unix)]
+#[test]
+fn message_lang_controls_generated_messages() {
+    let fixture = Fixture::new();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_str("ccg.messageLang", "zh_CN.UTF-8")
+        .unwrap();
+    fixture.write("src/main.rs", "fn main() {}\n");
+    let payload = serde_json::json!({
+        "tool_name": "Write",
+        "tool_input": { "file_path": fixture.file_path("src/main.rs") },
+        "tool_response": {},
+        "cwd": fixture.path_str(),
+    });
+
+    CreateCommand::new(fixture.context())
+        .create_from_payload(&payload.to_string())
+        .unwrap();
+
+    let entries = fixture.service().checkpoints(1).unwrap();
+    assert_eq!(entries[0].summary(), "Write 修改 main.rs");
+    assert!(entries[0].message.contains("工具输入:"));
+}
+
+#[test]
+fn unsupported_message_lang_falls_back_to_english() {
+    let fixture = Fixture::new();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_str("ccg.messageLang", "tlh")
+        .unwrap();
+    fixture.write("a.txt", "one\n");
+
+    fixture.service().create_checkpoint(None).unwrap();
+
+    let entries = fixture.service().checkpoints(1).unwrap();
+    assert_eq!(
+        entries[0].summary(),
+        "Checkpoint created without a specific message."
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn pre_create_hook_can_reject_checkpoint() {
+    let fixture = Fixture::new();
+    fixture.hook("pre-create", "exit 3");
+    fixture.write("a.txt", "one\n");
+
+    let result = fixture.service().create_checkpoint(Some("blocked"));
+
+    assert!(matches!(result, Err(CheckpointError::HookRejected(_))));
+    assert!(fixture.branch_tip("ccg").is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn bypassing_a_guard_hook_is_audited() {
+    let fixture = Fixture::new();
+    fixture.hook("pre-create", "exit 3");
+    fixture.write("a.txt", "one\n");
+
+    let options = CreateOptions {
+        bypass_guard: true,
+        ..Default::default()
+    };
+    fixture
+        .service()
+        .create_checkpoint_with_options(Some("urgent fix\n\ndetails"), &options)
+        .unwrap();
+
+    assert!(fixture.branch_tip("ccg").is_some());
+    let bypasses = fixture.git_ops().guard_bypasses().unwrap();
+    assert_eq!(bypasses.len(), 1);
+    assert_eq!(bypasses[0].hook, "pre-create");
+    assert_eq!(bypasses[0].target, "urgent fix");
+    assert_eq!(bypasses[0].via, "--bypass-guard");
+    assert_eq!(bypasses[0].user, "Fixture <fixture@example.com>");
+}
+
+#[cfg(unix)]
+#[test]
+fn post_create_hook_receives_json_payload() {
+    let fixture = Fixture::new();
+    let out = tempfile::NamedTempFile::new().unwrap();
+    fixture.hook("post-create", &format!("cat > '{}'", out.path().display()));
+    fixture.write("a.txt", "one\n");
+
+    let hash = fixture.service().create_checkpoint(Some("hooked")).unwrap();
+
+    let payload: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(out.path()).unwrap()).unwrap();
+    assert_eq!(payload["event"], "post-create");
+    assert_eq!(payload["hash"], hash);
+    assert_eq!(payload["message"], "hooked");
+    assert_eq!(payload["empty"], false);
+}
+
+#[test]
+fn usage_advisory_is_shown_once_per_threshold_crossing() {
+    let fixture = Fixture::new();
+    let mut config = fixture.repo().config().unwrap();
+    config.set_str("ccg.maxCheckpoints", "1").unwrap();
+    config.set_str("ccg.maxSize", "1k").unwrap();
+    let service = fixture.service();
+    fixture.write("a.txt", &"x".repeat(600));
+    service.create_checkpoint(Some("first")).unwrap();
+    fixture.write("b.txt", &"y".repeat(600));
+    service.create_checkpoint(Some("second")).unwrap();
+
+    let git_ops = fixture.git_ops();
+    let limits = git_ops.usage_limits();
+    assert_eq!(limits.max_checkpoints, 1);
+    assert_eq!(limits.max_bytes, 1024);
+    assert_eq!(git_ops.usage().total_bytes().unwrap(), 1200);
+    // 第二次创建时已提示过，不再重复
+    assert_eq!(git_ops.check_usage(&limits).unwrap(), None);
+
+    let relaxed = ccg::git_ops::UsageLimits::default();
+    assert_eq!(git_ops.check_usage(&relaxed).unwrap(), None);
+    let advisory = git_ops.check_usage(&limits).unwrap().unwrap();
+    assert!(advisory.too_many_checkpoints() && advisory.too_large());
+    assert_eq!(ccg::ui::format_size(advisory.bytes), "1.2 KB");
+}
+
+#[test]
+fn dedupe_report_separates_content_shared_with_main() {
+    let fixture = Fixture::new();
+    fixture.write("big.txt", &"x".repeat(1000));
+    fixture.commit("add big file");
+    let service = fixture.service();
+    fixture.write("small.txt", "new\n");
+    service.create_checkpoint(Some("add small")).unwrap();
+
+    let report = fixture.git_ops().dedupe_report().unwrap();
+
+    // main 上的提交不算检查点；大文件与 main 共享，检查点只增加新文件和新的树
+    assert_eq!(report.checkpoints, 1);
+    assert!(report.shared_bytes >= 1000);
+    assert!(report.unique_bytes >= 4 && report.unique_bytes < 1000);
+    assert_eq!(
+        report.logical_bytes,
+        report.shared_bytes + report.unique_bytes
+    );
+    assert_eq!(report.unique_objects, 2);
+    service.show_storage_stats(true).unwrap();
+
+    // 再次创建相同内容的检查点不增加新的对象
+    fixture.write("small.txt", "changed\n");
+    service.create_checkpoint(Some("change small")).unwrap();
+    fixture.write("small.txt", "new\n");
+    service.create_checkpoint(Some("change back")).unwrap();
+    let again = fixture.git_ops().dedupe_report().unwrap();
+    assert_eq!(again.checkpoints, 3);
+    assert_eq!(again.unique_objects, 4);
+    assert!(again.logical_bytes > 3 * 1000);
+}
+
+#[test]
+fn opening_a_plain_directory_does_not_initialize_a_repository() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    assert!(matches!(
+        ccg::GitOperations::new(Some(path)),
+        Err(CheckpointError::RepositoryNotFound)
+    ));
+    assert!(!dir.path().join(".git").exists());
+
+    ccg::GitOperations::init_at(path).unwrap();
+    assert!(dir.path().join(".git").exists());
+}
+
+#[test]
+fn auto_init_follows_flag_and_config() {
+    use ccg::commands::init::confirm_auto_init;
+    use ccg::config::AutoInit;
+
+    assert_eq!("always".parse(), Ok(AutoInit::Always));
+    assert_eq!("false".parse(), Ok(AutoInit::Never));
+    assert!("sometimes".parse::<AutoInit>().is_err());
+
+    assert!(confirm_auto_init(".", true, AutoInit::Never).unwrap());
+    assert!(confirm_auto_init(".", false, AutoInit::Always).unwrap());
+    assert!(!confirm_auto_init(".", false, AutoInit::Never).unwrap());
+}
+
+#[test]
+fn missing_ccg_branch_is_reported_uniformly_and_recovered() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().to_str().unwrap();
+    // 新初始化的仓库中 HEAD 指向尚无提交的 ccg 分支
+    let git_ops = ccg::GitOperations::init_at(path).unwrap();
+    assert!(!git_ops.has_ccg_branch().unwrap());
+    assert!(matches!(
+        git_ops.ensure_ccg_branch(),
+        Err(CheckpointError::BranchNotFound(_))
+    ));
+
+    std::fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+    let service = ccg::CheckpointService::new(git_ops).unwrap();
+    service.ensure_initialized().unwrap();
+
+    assert!(service.backend().has_ccg_branch().unwrap());
+    assert_eq!(service.checkpoints(10).unwrap().len(), 1);
+}
+
+#[test]
+fn init_from_branch_starts_ccg_at_that_branch() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("feature", &head, false).unwrap();
+    repo.set_head("refs/heads/feature").unwrap();
+    fixture.write("feature.txt", "wip\n");
+    let feature_tip = fixture.commit("feature work");
+    repo.set_head("refs/heads/main").unwrap();
+    let service = fixture.service();
+
+    service.init_from(Some("feature")).unwrap();
+
+    assert_eq!(fixture.branch_tip("ccg"), Some(feature_tip));
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+    assert!(matches!(
+        service.init_from(Some("feature")),
+        Err(CheckpointError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn init_orphan_keeps_checkpoints_out_of_main_history() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo();
+    let main_tip = fixture.branch_tip("main").unwrap();
+    let service = fixture.service();
+
+    service.init_orphan().unwrap();
+
+    // 起点提交没有父提交，但包含 main 的文件并记录基准分支
+    let root = repo
+        .find_commit(fixture.branch_tip("ccg").unwrap())
+        .unwrap();
+    assert_eq!(root.parent_count(), 0);
+    assert_eq!(
+        root.tree_id(),
+        repo.find_commit(main_tip).unwrap().tree_id()
+    );
+    assert!(root.message().unwrap().contains(&main_tip.to_string()));
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+
+    fixture.write("a.txt", "one\n");
+    let hash = service.create_checkpoint(Some("add a")).unwrap();
+    let entries = service.checkpoints(10).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].hash, hash);
+    assert!(repo.merge_base(main_tip, root.id()).is_err());
+
+    // 删除 ccg 分支后重新创建时仍为孤立分支
+    repo.find_branch("ccg", git2::BranchType::Local)
+        .unwrap()
+        .delete()
+        .unwrap();
+    fixture.write("a.txt", "two\n");
+    service.create_checkpoint(Some("change a")).unwrap();
+    let tip = repo
+        .find_commit(fixture.branch_tip("ccg").unwrap())
+        .unwrap();
+    assert_eq!(tip.parent(0).unwrap().parent_count(), 0);
+    assert!(matches!(
+        service.init_orphan(),
+        Err(CheckpointError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn separate_store_keeps_checkpoints_out_of_project_repository() {
+    let fixture = Fixture::new();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_str("ccg.store", ".ccg-store")
+        .unwrap();
+    fixture.write("a.txt", "one\n");
+    let service = fixture.service();
+    service.ensure_initialized().unwrap();
+    fixture.write("a.txt", "two\n");
+    let hash = service.create_checkpoint(Some("change a")).unwrap();
+
+    // 项目仓库中没有 ccg 分支，检查点都在检查点仓库中
+    assert_eq!(fixture.branch_tip("ccg"), None);
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+    let store = git2::Repository::open(fixture.path().join(".ccg-store")).unwrap();
+    let tip = store
+        .find_reference("refs/heads/ccg")
+        .unwrap()
+        .target()
+        .unwrap();
+    assert_eq!(tip.to_string(), hash);
+    assert_eq!(
+        store.workdir().unwrap().canonicalize().unwrap(),
+        fixture.path().canonicalize().unwrap()
+    );
+
+    // 检查点仓库目录不会出现在任何一方的状态中，也不会进入检查点
+    let project = fixture.repo();
+    let statuses = project.statuses(None).unwrap();
+    assert!(statuses.iter().all(
+        |entry| !entry.path().unwrap().starts_with(".ccg-store") || entry.status().is_ignored()
+    ));
+    let tree = store.find_commit(tip).unwrap().tree().unwrap();
+    assert!(tree.get_name(".ccg-store").is_none());
+    assert!(tree.get_name("a.txt").is_some());
+
+    // 恢复作用于项目的工作目录
+    let first = service.checkpoints(10).unwrap()[1].hash.clone();
+    service.restore_checkpoint(&first).unwrap();
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("one\n"));
+}
+
+#[test]
+fn init_from_unknown_branch_is_branch_not_found() {
+    let fixture = Fixture::new();
+
+    assert!(matches!(
+        fixture.service().init_from(Some("nope")),
+        Err(CheckpointError::BranchNotFound(name)) if name == "nope"
+    ));
+    assert_eq!(fixture.branch_tip("ccg"), None);
+}
+
+#[test]
+fn migrate_renames_checkpoint_branch_and_keeps_ids() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture.write("a.txt", "one\n");
+    let hash = service.create_checkpoint(Some("add a")).unwrap();
+
+    service.migrate_branch("ccg", "ccg-archive").unwrap();
+
+    assert_eq!(fixture.branch_tip("ccg"), None);
+    assert_eq!(fixture.branch_tip("ccg-archive").unwrap().to_string(), hash);
+    assert_eq!(fixture.git_ops().checkpoint_id(&hash).unwrap(), Some(1));
+    assert!(matches!(
+        service.migrate_branch("ccg", "other"),
+        Err(CheckpointError::BranchNotFound(_))
+    ));
+    assert!(matches!(
+        service.migrate_branch("ccg-archive", "main"),
+        Err(CheckpointError::InvalidArgument(_))
+    ));
+    // 只能迁移检查点分支，其他分支保持原样
+    let main = fixture.branch_tip("main");
+    assert!(matches!(
+        service.migrate_branch("main", "ccg-main"),
+        Err(CheckpointError::InvalidArgument(_))
+    ));
+    assert_eq!(fixture.branch_tip("main"), main);
+    assert_eq!(fixture.branch_tip("ccg-main"), None);
+
+    service.migrate_branch("ccg-archive", "ccg").unwrap();
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), hash);
+}
+
+#[test]
+fn read_only_mode_refuses_mutations_but_allows_reads() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    let hash = fixture.service().create_checkpoint(Some("add a")).unwrap();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_bool("ccg.readOnly", true)
+        .unwrap();
+    let service = fixture.service();
+    fixture.write("a.txt", "two\n");
+
+    assert!(service.is_read_only());
+    assert!(matches!(
+        service.create_checkpoint(Some("edit a")),
+        Err(CheckpointError::ReadOnly(op)) if op == "create"
+    ));
+    assert!(matches!(
+        service.restore_checkpoint(&hash),
+        Err(CheckpointError::ReadOnly(_))
+    ));
+    assert_eq!(service.checkpoints(10).unwrap().len(), 2);
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("two\n"));
+
+    // 命令行的 --read-only 同样可以在未配置时开启只读模式
+    let fresh = Fixture::new();
+    let mut service = fresh.service();
+    service.set_read_only(true);
+    assert!(matches!(service.init(), Err(CheckpointError::ReadOnly(_))));
+}
+
+/// Make every file and directory under `path` read-only, or writable again
+#[cfg(unix)]
+fn set_read_only(path: &std::path::Path, read_only: bool) {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = path.is_dir();
+    if dir && !read_only {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    if dir {
+        for entry in std::fs::read_dir(path).unwrap() {
+            set_read_only(&entry.unwrap().path(), read_only);
+        }
+    }
+    let mode = match (dir, read_only) {
+        (true, true) => 0o555,
+        (true, false) => 0o755,
+        (false, true) => 0o444,
+        (false, false) => 0o644,
+    };
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn reads_do_not_switch_head_on_a_read_only_git_dir() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    fixture.service().create_checkpoint(Some("add a")).unwrap();
+    fixture.write("a.txt", "two\n");
+    let latest = fixture.service().create_checkpoint(Some("edit a")).unwrap();
+    let reflog = fixture.repo().reflog("HEAD").unwrap().len();
+    let git_dir = fixture.path().join(".git");
+    set_read_only(&git_dir, true);
+
+    let list = common::ccg(fixture.path())
+        .args(["--read-only", "list"])
+        .output()
+        .unwrap();
+    let show = common::ccg(fixture.path())
+        .args(["--read-only", "show", "latest"])
+        .output()
+        .unwrap();
+    let service = fixture.service();
+    let resolved = service.resolve_checkpoint("latest~1");
+    set_read_only(&git_dir, false);
+
+    assert!(
+        list.status.success(),
+        "{}",
+        String::from_utf8_lossy(&list.stderr)
+    );
+    let stdout = String::from_utf8(list.stdout).unwrap();
+    assert!(
+        stdout.contains("edit a") && stdout.contains("add a"),
+        "{stdout}"
+    );
+    assert!(
+        show.status.success(),
+        "{}",
+        String::from_utf8_lossy(&show.stderr)
+    );
+    assert!(
+        String::from_utf8(show.stdout)
+            .unwrap()
+            .contains(&latest[..7])
+    );
+    assert!(resolved.is_ok());
+    // 读取不切换 HEAD，也不写 HEAD 的 reflog
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+    assert_eq!(fixture.repo().reflog("HEAD").unwrap().len(), reflog);
+}
+
+#[test]
+fn verification_result_is_recorded_and_can_block_the_checkpoint() {
+    use ccg::git_ops::commit::trailer_value;
+    use ccg::git_ops::{VERIFY_COMMAND_TRAILER, VERIFY_TRAILER};
+
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let verify = |command: &str, require_pass| CreateOptions {
+        verify: Some(command.to_string()),
+        require_pass,
+        ..CreateOptions::default()
+    };
+
+    // 验证命令在工作目录中运行
+    fixture.write("a.txt", "one\n");
+    let passed = service
+        .create_checkpoint_with_options(Some("passing"), &verify("test -f a.txt", false))
+        .unwrap();
+    let message = fixture
+        .repo()
+        .find_commit(passed.parse().unwrap())
+        .unwrap()
+        .message()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        trailer_value(&message, VERIFY_TRAILER).as_deref(),
+        Some("pass")
+    );
+    assert_eq!(
+        trailer_value(&message, VERIFY_COMMAND_TRAILER).as_deref(),
+        Some("test -f a.txt")
+    );
+
+    // 未通过时默认仍然创建，但标记为失败
+    fixture.write("a.txt", "two\n");
+    let failed = service
+        .create_checkpoint_with_options(Some("failing"), &verify("exit 3", false))
+        .unwrap();
+    let repo = fixture.repo();
+    let commit = repo.find_commit(failed.parse().unwrap()).unwrap();
+    assert_eq!(
+        trailer_value(commit.message().unwrap(), VERIFY_TRAILER).as_deref(),
+        Some("fail")
+    );
+
+    // --require-pass 时不创建检查点
+    fixture.write("a.txt", "three\n");
+    let err = service
+        .create_checkpoint_with_options(Some("blocked"), &verify("exit 1", true))
+        .unwrap_err();
+    assert!(matches!(err, CheckpointError::VerificationFailed(_)));
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), failed);
+}
+
+#[test]
+fn manual_checkpoints_are_described_from_the_diff() {
+    use ccg::git_ops::CheckpointLabel;
+    use ccg::git_ops::summarize::{FileKind, hunk_function};
+
+    assert_eq!(
+        hunk_function("@@ -10,4 +10,5 @@ pub fn parse_expr(input: &str) -> Expr {").as_deref(),
+        Some("parse_expr")
+    );
+    assert_eq!(hunk_function("@@ -1,3 +1,4 @@"), None);
+    assert_eq!(hunk_function("@@ -5 +5 @@ if (ready) {"), None);
+    assert_eq!(FileKind::of("src/parser_tests.rs"), FileKind::Test);
+    assert_eq!(FileKind::of("tests/cli.rs"), FileKind::Test);
+    assert_eq!(FileKind::of("README.md"), FileKind::Docs);
+    assert_eq!(FileKind::of("Cargo.toml"), FileKind::Config);
+
+    let fixture = Fixture::new();
+    let functions = |body: &str| {
+        ["fn a() {", "fn b() {", "fn c() {"]
+            .iter()
+            .map(|head| format!("{head}\n    1;\n    2;\n    3;\n    {body};\n}}\n\n\n\n"))
+            .collect::<String>()
+    };
+    fixture.write("src/parser.rs", &functions("old"));
+    fixture.service().create_checkpoint(Some("parser")).unwrap();
+    fixture
+        .write("src/parser.rs", &functions("new"))
+        .write("src/parser_tests.rs", "#[test]\nfn parses() {}\n");
+
+    let description = fixture.git_ops().describe_changes("en").unwrap().unwrap();
+    assert_eq!(
+        description,
+        "Modified 3 functions in parser.rs; added tests in parser_tests.rs"
+    );
+    let summary = format!("Manual checkpoint: {description}");
+    assert_eq!(
+        CheckpointLabel::classify(&summary),
+        Some(CheckpointLabel::Manual)
+    );
+    assert_eq!(
+        CheckpointLabel::classify("Edit: Modified parser.rs"),
+        Some(CheckpointLabel::Edit)
+    );
+
+    // 没有变更时不生成描述
+    fixture.service().create_checkpoint(Some("all")).unwrap();
+    assert_eq!(fixture.git_ops().describe_changes("en").unwrap(), None);
+}
+
+/// A fixture with a pending new file and the Ollama summary provider configured
+fn with_summary_provider() -> (Fixture, git2::Config) {
+    let fixture = Fixture::new();
+    fixture.write("src/parser.rs", "fn parse() {}\n");
+    let mut config = fixture.repo().config().unwrap();
+    config.set_str("ccg.summary.provider", "ollama").unwrap();
+    config.set_i64("ccg.summary.timeout", 2).unwrap();
+    (fixture, config)
+}
+
+#[test]
+fn llm_summary_falls_back_to_the_heuristic_when_the_service_fails() {
+    let (fixture, mut config) = with_summary_provider();
+
+    // 服务不可用（或没有 curl）时使用本地描述
+    config
+        .set_str("ccg.summary.url", "http://127.0.0.1:9")
+        .unwrap();
+    assert_eq!(
+        summary::describe_changes(&fixture.git_ops(), "en").as_deref(),
+        Some("Added parser.rs")
+    );
+}
+
+#[test]
+fn llm_summary_uses_the_model_reply() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    // 请求经由 curl 发送，没有 curl 的环境只能覆盖回退路径
+    if std::process::Command::new("curl")
+        .arg("--version")
+        .output()
+        .is_err()
+    {
+        eprintln!("curl not found, skipping");
+        return;
+    }
+    let (fixture, mut config) = with_summary_provider();
+
+    // 模型的回复作为摘要，发送的差异已去掉密钥
+    fixture.write(".env", "TOKEN=abc123\n");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/api/generate", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            if line.trim().is_empty() {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let reply = r#"{"response":"Add an empty parser"}"#;
+        write!(
+            reader.get_mut(),
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{reply}",
+            reply.len()
+        )
+        .unwrap();
+        String::from_utf8(body).unwrap()
+    });
+    config.set_str("ccg.summary.url", &url).unwrap();
+    assert_eq!(
+        summary::describe_changes(&fixture.git_ops(), "en").as_deref(),
+        Some("Add an empty parser")
+    );
+    let request: serde_json::Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+    let prompt = request["prompt"].as_str().unwrap();
+    assert!(prompt.contains("[content omitted]"));
+    assert!(!prompt.contains("abc123"));
+    assert_eq!(request["model"], "llama3.2");
+    assert!(prompt.contains("+fn parse() {}"));
+}
+
+/// Paths in the tree of a checkpoint, recursively
+fn tree_paths(fixture: &Fixture, hash: &str) -> Vec<String> {
+    let repo = fixture.repo();
+    let tree = repo
+        .find_commit(git2::Oid::from_str(hash).unwrap())
+        .unwrap()
+        .tree()
+        .unwrap();
+    let mut paths = Vec::new();
+    tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() == Some(git2::ObjectType::Blob) {
+            paths.push(format!("{dir}{}", entry.name().unwrap()));
+        }
+        git2::TreeWalkResult::Ok
+    })
+    .unwrap();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn create_skips_files_excluded_by_a_profile() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    service
+        .apply_exclude_profiles(&[ExcludeProfile::Node, ExcludeProfile::Ml])
+        .unwrap();
+    fixture
+        .write("src/app.js", "app\n")
+        .write("node_modules/left-pad/index.js", "pad\n")
+        .write("packages/web/node_modules/react/index.js", "react\n")
+        .write("models/weights.ckpt", "weights\n");
+
+    let hash = service.create_checkpoint(Some("app")).unwrap();
+
+    assert_eq!(tree_paths(&fixture, &hash), ["README.md", "src/app.js"]);
+}
+
+#[test]
+fn create_skips_files_over_the_size_limit() {
+    let fixture = Fixture::new();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_str("ccg.checkpoint.maxFileSize", "1k")
+        .unwrap();
+    fixture
+        .write("small.txt", "small\n")
+        .write("package-lock.json", &"x".repeat(4096));
+
+    let hash = fixture.service().create_checkpoint(Some("lock")).unwrap();
+
+    assert_eq!(tree_paths(&fixture, &hash), ["README.md", "small.txt"]);
+}
+
+#[test]
+fn applying_a_profile_twice_adds_nothing() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    service
+        .apply_exclude_profiles(&[ExcludeProfile::Rust])
+        .unwrap();
+    service
+        .apply_exclude_profiles(&[ExcludeProfile::Rust])
+        .unwrap();
+
+    let config = ccg::config::CcgConfig::load(&fixture.repo());
+    assert_eq!(config.checkpoint_exclude, ["target", "*/target/*"]);
+    assert_eq!(config.checkpoint_max_file_size, Some(10 * 1024 * 1024));
+}
+
+fn hook_payload(fixture: &Fixture, tool: &str, file: &str) -> String {
+    serde_json::json!({
+        "tool_name": tool,
+        "tool_input": { "file_path": fixture.file_path(file) },
+        "tool_response": {},
+        "cwd": fixture.path_str(),
+    })
+    .to_string()
+}
+
+#[test]
+fn hook_events_from_unlisted_tools_are_recorded_and_skipped() {
+    let fixture = Fixture::new();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_str("ccg.hooks.tools", "Edit,Write,MultiEdit")
+        .unwrap();
+    fixture.write("a.txt", "a\n");
+    let command = CreateCommand::new(fixture.context());
+
+    let outcome = command
+        .create_from_payload_outcome(
+            &hook_payload(&fixture, "Read", "a.txt"),
+            &CreateOptions::default(),
+        )
+        .unwrap();
+
+    assert_eq!(outcome.porcelain(), "skipped\ttool\tRead");
+    assert!(fixture.branch_tip("ccg").is_none());
+    let logged = fixture.git_ops().hook_filter().entries().unwrap();
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0].tool, "Read");
+
+    let outcome = command
+        .create_from_payload_outcome(
+            &hook_payload(&fixture, "edit", "a.txt"),
+            &CreateOptions::default(),
+        )
+        .unwrap();
+    assert!(matches!(outcome, CreateOutcome::Created(_)));
+}
+
+#[test]
+fn hook_events_outside_the_configured_paths_are_skipped() {
+    let fixture = Fixture::new();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_str("ccg.hooks.paths", "src")
+        .unwrap();
+    fixture.write("docs/notes.md", "notes\n");
+    let command = CreateCommand::new(fixture.context());
+
+    let outcome = command
+        .create_from_payload_outcome(
+            &hook_payload(&fixture, "Write", "docs/notes.md"),
+            &CreateOptions::default(),
+        )
+        .unwrap();
+
+    assert_eq!(outcome.porcelain(), "skipped\tpath\tdocs/notes.md");
+    fixture.write("src/lib.rs", "lib\n");
+    let outcome = command
+        .create_from_payload_outcome(
+            &hook_payload(&fixture, "Write", "src/lib.rs"),
+            &CreateOptions::default(),
+        )
+        .unwrap();
+    assert!(outcome.porcelain().starts_with("created\t"));
+}
+
+fn edit_payload(fixture: &Fixture, file: &str, old: &str, new: &str) -> String {
+    serde_json::json!({
+        "tool_name": "Edit",
+        "tool_input": {
+            "file_path": fixture.file_path(file),
+            "old_string": old,
+            "new_string": new,
+        },
+        "tool_response": {},
+        "cwd": fixture.path_str(),
+    })
+    .to_string()
+}
+
+#[test]
+fn edits_made_alongside_the_tool_mark_the_checkpoint_interleaved() {
+    let fixture = Fixture::new();
+    fixture.write("src/lib.rs", "let foo = 1;\nlet other = 2;\n");
+    fixture.service().create_checkpoint(Some("base")).unwrap();
+    // 工具把 foo 改为 bar，同时有人在文件末尾加了一行
+    fixture.write(
+        "src/lib.rs",
+        "let bar = 1;\nlet other = 2;\nlet human = 3;\n",
+    );
+
+    CreateCommand::new(fixture.context())
+        .create_from_payload(&edit_payload(&fixture, "src/lib.rs", "foo", "bar"))
+        .unwrap();
+
+    let entry = &fixture.service().checkpoints(1).unwrap()[0];
+    assert_eq!(entry.interleaved(), ["src/lib.rs"]);
+    let git_ops = fixture.git_ops();
+    git_ops.ensure_ccg_branch().unwrap();
+    let list = git_ops
+        .list_checkpoints(1, &ListColumn::DEFAULT, &ListFilter::default())
+        .unwrap();
+    assert!(console::strip_ansi_codes(&list[0]).contains("[interleaved]"));
+}
+
+#[test]
+fn edits_matching_the_tool_call_are_not_interleaved() {
+    let fixture = Fixture::new();
+    fixture.write("src/lib.rs", "let foo = 1;\nlet other = 2;\n");
+    fixture.service().create_checkpoint(Some("base")).unwrap();
+    fixture.write("src/lib.rs", "let bar = 1;\nlet other = 2;\n");
+
+    CreateCommand::new(fixture.context())
+        .create_from_payload(&edit_payload(&fixture, "src/lib.rs", "foo", "bar"))
+        .unwrap();
+
+    let entry = &fixture.service().checkpoints(1).unwrap()[0];
+    assert!(entry.interleaved().is_empty());
+}
+
+#[test]
+fn configured_identity_is_used_for_author_and_committer() {
+    let fixture = Fixture::new();
+    let repo = fixture.repo();
+    let mut config = repo.config().unwrap();
+    config.set_str("ccg.authorName", "Claude").unwrap();
+    config
+        .set_str("ccg.authorEmail", "claude@example.com")
+        .unwrap();
+
+    // 未配置提交者时沿用作者身份
+    fixture.write("a.txt", "one\n");
+    let first = fixture.service().create_checkpoint(Some("one")).unwrap();
+    let commit = repo.find_commit(first.parse().unwrap()).unwrap();
+    assert_eq!(commit.author().name(), Some("Claude"));
+    assert_eq!(commit.author().email(), Some("claude@example.com"));
+    assert_eq!(commit.committer().name(), Some("Claude"));
+
+    config.set_bool("ccg.machineCommitter", true).unwrap();
+    fixture.write("a.txt", "two\n");
+    let second = fixture.service().create_checkpoint(Some("two")).unwrap();
+    let commit = repo.find_commit(second.parse().unwrap()).unwrap();
+    assert_eq!(commit.author().name(), Some("Claude"));
+    assert_eq!(commit.committer().name(), Some(MACHINE_COMMITTER_NAME));
+    assert_eq!(commit.committer().email(), Some(MACHINE_COMMITTER_EMAIL));
+
+    // 显式配置的提交者优先于机器身份
+    config.set_str("ccg.committerName", "CI").unwrap();
+    config
+        .set_str("ccg.committerEmail", "ci@example.com")
+        .unwrap();
+    fixture.write("a.txt", "three\n");
+    let third = fixture.service().create_checkpoint(Some("three")).unwrap();
+    let commit = repo.find_commit(third.parse().unwrap()).unwrap();
+    assert_eq!(commit.author().email(), Some("claude@example.com"));
+    assert_eq!(commit.committer().name(), Some("CI"));
+    assert_eq!(commit.committer().email(), Some("ci@example.com"));
+}
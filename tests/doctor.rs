@@ -0,0 +1,146 @@
+mod common;
+
+use ccg::commands::DoctorCommand;
+use ccg::commands::doctor::{CheckLevel, DoctorCheck};
+use common::{Fixture, show};
+
+fn checks(fixture: &Fixture) -> Vec<DoctorCheck> {
+    DoctorCommand::new(fixture.context()).checks()
+}
+
+#[test]
+fn doctor_reports_no_problems_for_regular_repository() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    fixture.service().create_checkpoint(Some("first")).unwrap();
+
+    let checks = checks(&fixture);
+
+    assert!(
+        checks.iter().all(|c| c.level == CheckLevel::Ok),
+        "{checks:?}"
+    );
+}
+
+#[test]
+fn doctor_warns_about_missing_ccg_branch() {
+    let fixture = Fixture::new();
+
+    assert!(
+        checks(&fixture)
+            .iter()
+            .any(|c| c.level == CheckLevel::Warn && c.detail.contains("ccg init"))
+    );
+}
+
+#[test]
+fn shallow_boundary_is_reported_and_diffed_against_empty_tree() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    let boundary = fixture.commit("second");
+    // 在 .git/shallow 中登记边界提交，模拟 `git clone --depth 1`
+    std::fs::write(fixture.path().join(".git/shallow"), format!("{boundary}\n")).unwrap();
+
+    let checks = checks(&fixture);
+    let output = show(&fixture, &boundary.to_string(), false, &[]);
+
+    assert!(
+        checks
+            .iter()
+            .any(|c| c.level == CheckLevel::Warn && c.detail.contains("--unshallow"))
+    );
+    assert!(output.contains("Shallow clone boundary"));
+    assert!(output.contains("A README.md"));
+}
+
+#[test]
+fn partial_clone_is_reported() {
+    let fixture = Fixture::new();
+    let mut config = fixture.repo().config().unwrap();
+    config.set_bool("remote.origin.promisor", true).unwrap();
+
+    assert!(
+        checks(&fixture)
+            .iter()
+            .any(|c| c.level == CheckLevel::Warn && c.detail.contains("partial clone"))
+    );
+}
+
+#[test]
+fn interrupted_create_restores_staging_area_and_is_reported() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    fixture.service().create_checkpoint(Some("first")).unwrap();
+    assert!(!fixture.git_ops().staging().is_interrupted());
+    fixture.write("a.txt", "two\n");
+    fixture.write("b.txt", "new\n");
+    let index = fixture.path().join(".git/index");
+    let original = std::fs::read(&index).unwrap();
+
+    // 模拟 create 在写入索引之后、提交之前被终止
+    let tip = fixture.branch_tip("ccg");
+    fixture.git_ops().staging().begin(tip).unwrap();
+    let repo = fixture.repo();
+    let mut staged = repo.index().unwrap();
+    staged
+        .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    staged.write().unwrap();
+    assert_ne!(std::fs::read(&index).unwrap(), original);
+
+    // 只读的命令不修复，下一个修改检查点的命令才修复
+    assert!(
+        checks(&fixture)
+            .iter()
+            .any(|c| c.level == CheckLevel::Error)
+    );
+    assert_ne!(std::fs::read(&index).unwrap(), original);
+    fixture.service().prune_checkpoints(&[]).unwrap();
+    let first = checks(&fixture);
+    let again = checks(&fixture);
+
+    assert_eq!(std::fs::read(&index).unwrap(), original);
+    assert!(
+        first
+            .iter()
+            .any(|c| c.level == CheckLevel::Warn && c.detail.contains("restored"))
+    );
+    assert!(again.iter().all(|c| c.level == CheckLevel::Ok), "{again:?}");
+}
+
+#[test]
+fn running_create_is_not_rolled_back_and_read_only_does_not_repair() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    fixture.service().create_checkpoint(Some("first")).unwrap();
+    fixture.write("a.txt", "two\n");
+    let index = fixture.path().join(".git/index");
+
+    // 另一个 create 正处于写入索引和提交之间，持有锁
+    let git_ops = fixture.git_ops();
+    let running = git_ops.staging();
+    running.begin(fixture.branch_tip("ccg")).unwrap();
+    let repo = fixture.repo();
+    let mut staged = repo.index().unwrap();
+    staged
+        .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    staged.write().unwrap();
+    let staged = std::fs::read(&index).unwrap();
+
+    fixture.service().prune_checkpoints(&[]).unwrap();
+    assert_eq!(std::fs::read(&index).unwrap(), staged);
+    assert!(checks(&fixture).iter().all(|c| c.level == CheckLevel::Ok));
+
+    // 进程退出（守卫被丢弃）后锁被释放，只读模式仍不修复
+    drop(running);
+    let mut service = fixture.service();
+    service.set_read_only(true);
+    assert!(service.prune_checkpoints(&[]).is_err());
+    assert_eq!(std::fs::read(&index).unwrap(), staged);
+    assert!(git_ops.staging().is_interrupted());
+    service.set_read_only(false);
+    service.prune_checkpoints(&[]).unwrap();
+    assert!(!git_ops.staging().is_interrupted());
+    assert_ne!(std::fs::read(&index).unwrap(), staged);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use ccg::error::CheckpointError;
+use common::Fixture;
+
+#[test]
+fn drift_sorts_files_by_the_side_that_changed_them() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture.write("shared.txt", "base\n");
+    fixture.write("same.txt", "base\n");
+    fixture.commit("base");
+    let base = fixture.branch_tip("main").unwrap();
+
+    fixture.write("agent.txt", "agent\n");
+    fixture.write("shared.txt", "agent\n");
+    fixture.write("same.txt", "both\n");
+    service.create_checkpoint(Some("agent work")).unwrap();
+
+    // 用户在 main 上提交了自己的修改
+    std::fs::remove_file(fixture.file_path("agent.txt")).unwrap();
+    fixture.write("user.txt", "user\n");
+    fixture.write("shared.txt", "user\n");
+    fixture.write("same.txt", "both\n");
+    fixture.commit("user work");
+
+    let drift = service.show_drift().unwrap();
+
+    assert_eq!(drift.branch, "main");
+    assert_eq!(drift.base, Some(base.to_string()));
+    assert_eq!(drift.only_checkpoint, ["agent.txt"]);
+    assert_eq!(drift.only_branch, ["user.txt"]);
+    assert_eq!(drift.diverged, ["shared.txt"]);
+    assert!(!drift.is_in_sync());
+}
+
+#[test]
+fn drift_is_in_sync_right_after_a_checkpoint_of_the_branch() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    fixture.commit("second");
+    fixture.write("a.txt", "two\n");
+    fixture.service().create_checkpoint(Some("edit")).unwrap();
+    fixture.commit("commit the same edit");
+
+    let drift = fixture.service().show_drift().unwrap();
+
+    assert!(drift.is_in_sync(), "{drift:?}");
+    let missing = Fixture::new().service().show_drift();
+    assert!(matches!(missing, Err(CheckpointError::BranchNotFound(_))));
+}
@@ -0,0 +1,36 @@
+use ccg::CheckpointError;
+use ccg::error::CliError;
+use ccg::ui::report::render;
+
+#[test]
+fn invalid_arguments_become_invalid_value_errors() {
+    let error = CliError::parse::<usize>("number", "ten").unwrap_err();
+    let CliError::InvalidValue { arg, value, .. } = &error else {
+        panic!("unexpected error: {error:?}");
+    };
+    assert_eq!((arg.as_str(), value.as_str()), ("number", "ten"));
+    assert!(error.to_string().contains("'ten'"), "{error}");
+    assert!(error.suggestion().is_some());
+
+    assert_eq!(CliError::parse::<usize>("number", "10").unwrap(), 10);
+}
+
+#[test]
+fn render_shows_the_message_causes_and_suggestion() {
+    let error = CliError::from(CheckpointError::CheckpointNotFound("abc1234".to_string()));
+    let output = console::strip_ansi_codes(&render(&error)).into_owned();
+    assert!(output.contains("Checkpoint not found: abc1234"), "{output}");
+    assert!(output.contains(&error.suggestion().unwrap()), "{output}");
+
+    // 原因已包含在错误信息中时不再重复
+    let io = std::io::Error::other("disk on fire");
+    let output = render(&CliError::from(io));
+    assert_eq!(output.matches("disk on fire").count(), 1, "{output}");
+
+    let error = CliError::from(CheckpointError::NoChangesToCommit);
+    assert!(error.suggestion().is_none());
+    assert_eq!(
+        console::strip_ansi_codes(&render(&error)).lines().count(),
+        1
+    );
+}
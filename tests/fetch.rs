@@ -0,0 +1,83 @@
+mod common;
+
+use ccg::CheckpointError;
+use ccg::git_ops::FetchUpdate;
+use common::Fixture;
+
+/// A repository with `count` checkpoints, and a clone-like repository that
+/// has it as `origin`
+fn with_remote(count: usize) -> (Fixture, Fixture, Vec<String>) {
+    let remote = Fixture::new();
+    let hashes = (0..count)
+        .map(|i| {
+            remote.write("a.txt", &format!("{i}\n"));
+            remote
+                .service()
+                .create_checkpoint(Some(&format!("checkpoint {i}")))
+                .unwrap()
+        })
+        .collect();
+    let local = Fixture::new();
+    local
+        .repo()
+        .remote("origin", &format!("file://{}", remote.path_str()))
+        .unwrap();
+    (remote, local, hashes)
+}
+
+#[test]
+fn shallow_fetch_only_brings_recent_checkpoints() {
+    let (_remote, local, hashes) = with_remote(4);
+
+    let report = local
+        .service()
+        .fetch_checkpoints("origin", Some(2))
+        .unwrap();
+
+    assert_eq!(report.update, Some(FetchUpdate::Created));
+    assert_eq!(report.tip.as_deref(), Some(hashes[3].as_str()));
+    assert_eq!(report.available, 2);
+    assert!(report.shallow);
+    let listed: Vec<String> = local
+        .service()
+        .checkpoints(10)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.hash)
+        .collect();
+    assert_eq!(listed, [hashes[3].clone(), hashes[2].clone()]);
+
+    // 没有获取的旧检查点给出提示，而不是普通的“找不到”
+    let result = local.git_ops().find_commit(&hashes[0]).map(|c| c.id());
+    assert!(matches!(result, Err(CheckpointError::NotFetched(_))));
+}
+
+#[test]
+fn fetch_fast_forwards_and_keeps_diverged_branches() {
+    let (remote, local, hashes) = with_remote(2);
+    local.service().fetch_checkpoints("origin", None).unwrap();
+
+    remote.write("a.txt", "newer\n");
+    let newer = remote.service().create_checkpoint(Some("newer")).unwrap();
+    let report = local.service().fetch_checkpoints("origin", None).unwrap();
+    assert_eq!(report.update, Some(FetchUpdate::FastForward));
+    assert!(!report.shallow);
+    assert_eq!(local.branch_tip("ccg").unwrap().to_string(), newer);
+
+    let report = local.service().fetch_checkpoints("origin", None).unwrap();
+    assert_eq!(report.update, Some(FetchUpdate::UpToDate));
+
+    // 两边各自创建了新检查点：本地分支保持不变，远程的在跟踪引用中
+    local.write("b.txt", "local\n");
+    let mine = local.service().create_checkpoint(Some("mine")).unwrap();
+    remote.write("a.txt", "theirs\n");
+    let theirs = remote.service().create_checkpoint(Some("theirs")).unwrap();
+    let report = local.service().fetch_checkpoints("origin", None).unwrap();
+    assert_eq!(report.update, Some(FetchUpdate::Diverged));
+    assert_eq!(report.tip, Some(theirs));
+    assert_eq!(local.branch_tip("ccg").unwrap().to_string(), mine);
+    assert_ne!(hashes[0], mine);
+
+    let unknown = local.service().fetch_checkpoints("upstream", None);
+    assert!(matches!(unknown, Err(CheckpointError::InvalidArgument(_))));
+}
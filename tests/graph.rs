@@ -0,0 +1,65 @@
+mod common;
+
+use common::Fixture;
+use std::collections::HashMap;
+
+#[test]
+fn checkpoint_graph_includes_discarded_lines_and_restores() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let mut hashes = Vec::new();
+    for message in ["one", "two", "three"] {
+        fixture.write("a.txt", &format!("{message}\n"));
+        hashes.push(service.create_checkpoint(Some(message)).unwrap());
+    }
+    let repo = fixture.repo();
+    let two = repo.find_commit(hashes[1].parse().unwrap()).unwrap();
+    repo.tag_lightweight("v1", two.as_object(), false).unwrap();
+    service.restore_checkpoint(&hashes[0]).unwrap();
+    fixture.write("a.txt", "four\n");
+    let four = service.create_checkpoint(Some("four")).unwrap();
+
+    let graph = fixture.git_ops().checkpoint_graph().unwrap();
+    assert_eq!(graph.tip, four);
+    let branch: Vec<&str> = graph
+        .nodes
+        .iter()
+        .filter(|node| node.on_branch)
+        .map(|node| node.entry.hash.as_str())
+        .collect();
+    assert_eq!(branch[..2], [four.as_str(), hashes[0].as_str()]);
+    assert_eq!(graph.restores.len(), 1);
+    assert_eq!(graph.restores[0].target, hashes[0]);
+    assert_eq!(graph.restores[0].discarded_tip, hashes[2]);
+
+    let two = graph.node(&hashes[1]).unwrap();
+    assert!(!two.on_branch);
+    assert_eq!(two.tags, ["v1"]);
+    assert_eq!(two.entry.display_id().as_deref(), Some("cp-2"));
+    assert!(graph.node(&hashes[0]).unwrap().on_branch);
+    // 恢复目标是分叉点：新检查点和被丢弃的那一行都从它开始
+    let mut children = graph.children(&hashes[0]);
+    children.sort_unstable();
+    let mut expected = [four.as_str(), hashes[1].as_str()];
+    expected.sort_unstable();
+    assert_eq!(children, expected);
+
+    // 每条边的两端都是节点，子节点排在父节点之前
+    let position: HashMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.entry.hash.as_str(), i))
+        .collect();
+    assert_eq!(position.len(), graph.nodes.len());
+    for edge in &graph.edges {
+        assert!(position[edge.child.as_str()] < position[edge.parent.as_str()]);
+    }
+    let parents: usize = graph.nodes.iter().map(|node| node.parents.len()).sum();
+    assert_eq!(graph.edges.len(), parents);
+    assert!(
+        serde_json::to_string(&graph)
+            .unwrap()
+            .contains("\"on_branch\":false")
+    );
+}
@@ -0,0 +1,948 @@
+mod common;
+
+use ccg::CheckpointError;
+use ccg::git_ops::{AheadBehind, ListColumn, ListFilter, RESTORE_JOURNAL_REF};
+use ccg::services::RestoreOptions;
+use common::{Fixture, Step};
+
+/// Two checkpoints editing `a.txt`
+const EDITS: [Step; 2] = [
+    ("first", &[("a.txt", "one\n")]),
+    ("second", &[("a.txt", "two\n")]),
+];
+
+#[test]
+fn restore_resets_ccg_branch_and_working_directory() {
+    let (fixture, [first, _]) = Fixture::with_checkpoints(EDITS);
+
+    fixture.service().restore_checkpoint(&first[..7]).unwrap();
+
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), first);
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("one\n"));
+    assert_eq!(fixture.head_branch().as_deref(), Some("ccg"));
+}
+
+#[test]
+fn restore_preview_lists_changes_without_restoring() {
+    let (fixture, [first, _]) = Fixture::with_checkpoints(EDITS);
+    fixture.write("b.txt", "bee\nbee\n");
+    let third = fixture.service().create_checkpoint(Some("third")).unwrap();
+
+    let preview = fixture.service().restore_preview(&first).unwrap();
+
+    let summary: Vec<(&str, git2::Delta, i32, i32)> = preview
+        .iter()
+        .map(|f| (f.path.as_str(), f.status, f.additions, f.deletions))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("a.txt", git2::Delta::Modified, 1, 1),
+            ("b.txt", git2::Delta::Deleted, 0, 2),
+        ]
+    );
+    assert!(
+        fixture
+            .service()
+            .restore_preview(&third)
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), third);
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("two\n"));
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn restore_is_recorded_in_journal_and_keeps_discarded_reachable() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+
+    fixture.service().restore_checkpoint(&first).unwrap();
+
+    let records = fixture.git_ops().restore_records().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].target, first);
+    assert_eq!(records[0].discarded_tip, second);
+    assert_eq!(records[0].discarded, 1);
+
+    let repo = fixture.repo();
+    let journal = repo
+        .find_reference(RESTORE_JOURNAL_REF)
+        .unwrap()
+        .target()
+        .unwrap();
+    assert!(
+        repo.graph_descendant_of(journal, second.parse().unwrap())
+            .unwrap()
+    );
+}
+
+#[test]
+fn restore_journal_lists_newest_first() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    let service = fixture.service();
+    service.restore_checkpoint(&first).unwrap();
+    fixture.write("a.txt", "three\n");
+    let third = service.create_checkpoint(Some("third")).unwrap();
+
+    service.restore_checkpoint(&first).unwrap();
+
+    let records = fixture.git_ops().restore_records().unwrap();
+    let tips: Vec<&str> = records.iter().map(|r| r.discarded_tip.as_str()).collect();
+    assert_eq!(tips, [third.as_str(), second.as_str()]);
+    service
+        .list_checkpoints(
+            10,
+            &ccg::git_ops::ListColumn::DEFAULT,
+            true,
+            &ccg::git_ops::ListFilter::default(),
+        )
+        .unwrap();
+}
+
+#[test]
+fn restore_accepts_checkpoint_ids_and_never_reuses_them() {
+    let (fixture, [first, _]) = Fixture::with_checkpoints(EDITS);
+    let service = fixture.service();
+
+    service.restore_checkpoint("cp-1").unwrap();
+    fixture.write("a.txt", "three\n");
+    let third = service.create_checkpoint(Some("third")).unwrap();
+
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), third);
+    let entries = service.checkpoints(2).unwrap();
+    assert_eq!(entries[0].display_id().as_deref(), Some("cp-3"));
+    assert_eq!(entries[1].hash, first);
+    assert_eq!(entries[1].display_id().as_deref(), Some("cp-1"));
+    assert!(matches!(
+        service.resolve_checkpoint("cp-9"),
+        Err(CheckpointError::CheckpointNotFound(_))
+    ));
+}
+
+#[test]
+fn restore_refuses_checkpoint_predating_commits_on_its_base_branch() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    fixture.write("c.txt", "human\n");
+    fixture.commit("human commit");
+    let third = fixture.service().create_checkpoint(Some("third")).unwrap();
+
+    let result = fixture.service().restore_checkpoint(&first);
+
+    assert!(matches!(result, Err(CheckpointError::StaleBase(_))));
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), third);
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+    // 基于同一分支提交的检查点不受影响
+    assert!(fixture.service().check_restore_base(&third).is_ok());
+    assert!(fixture.service().check_restore_base(&second).is_err());
+
+    fixture
+        .service()
+        .restore_checkpoint_with(
+            &first,
+            RestoreOptions {
+                force: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), first);
+    assert_eq!(fixture.read("c.txt"), None);
+}
+
+#[test]
+fn restore_keeps_untracked_files_by_default() {
+    let (fixture, [first, _]) = Fixture::with_checkpoints(EDITS);
+    fixture.write("notes.txt", "mine\n");
+
+    assert_eq!(
+        fixture.git_ops().untracked_files().unwrap(),
+        vec!["notes.txt".to_string()]
+    );
+    let report = fixture
+        .service()
+        .restore_checkpoint_with(&first, RestoreOptions::default())
+        .unwrap();
+
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("one\n"));
+    assert_eq!(fixture.read("notes.txt").as_deref(), Some("mine\n"));
+    assert_eq!(report.updated, vec!["a.txt".to_string()]);
+    assert!(report.removed.is_empty());
+    assert_eq!(report.skipped, vec!["notes.txt".to_string()]);
+}
+
+/// A fixture whose checkpoints ignore `target/` and `.env`
+fn with_ignored_files() -> (Fixture, String) {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture
+        .write(".gitignore", "target/\n.env\n")
+        .write("a.txt", "one\n");
+    let first = service.create_checkpoint(Some("first")).unwrap();
+    fixture.write("a.txt", "two\n");
+    service.create_checkpoint(Some("second")).unwrap();
+    fixture
+        .write("target/debug/app", "binary\n")
+        .write(".env", "SECRET=1\n")
+        .write("notes.txt", "mine\n");
+    (fixture, first)
+}
+
+#[test]
+fn restore_clean_removes_untracked_but_never_ignored_files() {
+    let (fixture, first) = with_ignored_files();
+
+    let report = fixture
+        .service()
+        .restore_checkpoint_with(
+            &first,
+            RestoreOptions {
+                clean: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("one\n"));
+    assert_eq!(fixture.read("notes.txt"), None);
+    assert_eq!(
+        fixture.read("target/debug/app").as_deref(),
+        Some("binary\n")
+    );
+    assert_eq!(fixture.read(".env").as_deref(), Some("SECRET=1\n"));
+    assert_eq!(report.updated, vec!["a.txt".to_string()]);
+    assert_eq!(report.removed, vec!["notes.txt".to_string()]);
+    assert!(report.skipped.contains(&".env".to_string()));
+    assert!(report.skipped.iter().any(|p| p.starts_with("target")));
+}
+
+#[test]
+fn restore_refuses_dirty_working_directory() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    fixture.write("a.txt", "unsaved\n");
+
+    let result = fixture.service().restore_checkpoint(&first);
+
+    assert!(matches!(result, Err(CheckpointError::UncommittedChanges)));
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("unsaved\n"));
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), second);
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn restore_unknown_checkpoint_switches_back() {
+    let (fixture, [_, _]) = Fixture::with_checkpoints(EDITS);
+
+    let result = fixture.service().restore_checkpoint("0000000");
+
+    assert!(matches!(
+        result,
+        Err(CheckpointError::CheckpointNotFound(_))
+    ));
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn restore_reports_short_and_multibyte_hashes_without_panicking() {
+    let (fixture, [_, _]) = Fixture::with_checkpoints(EDITS);
+    let service = fixture.service();
+
+    for input in ["ab", "ééééé", "提交哈希"] {
+        assert!(service.restore_checkpoint(input).is_err(), "{input}");
+        assert!(
+            service.show_checkpoint(input, false, false, &[]).is_err(),
+            "{input}"
+        );
+    }
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn failed_restore_from_detached_head_keeps_head_detached() {
+    let fixture = Fixture::new();
+    let detached = fixture.detach();
+    let service = fixture.service();
+    fixture.write("a.txt", "one\n");
+    let first = service.create_checkpoint(Some("first")).unwrap();
+    fixture.write("a.txt", "dirty\n");
+
+    let result = service.restore_checkpoint(&first);
+
+    assert!(matches!(result, Err(CheckpointError::UncommittedChanges)));
+    assert_eq!(fixture.head_branch(), None);
+    assert_eq!(fixture.repo().head().unwrap().target(), Some(detached));
+}
+
+#[cfg(unix)]
+#[test]
+fn pre_restore_hook_can_reject_restore() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    fixture.hook("pre-restore", "exit 1");
+    // 钩子脚本本身是未跟踪文件，先纳入检查点
+    fixture
+        .service()
+        .create_checkpoint(Some("add hook"))
+        .unwrap();
+    let tip = fixture.branch_tip("ccg");
+
+    let result = fixture.service().restore_checkpoint(&first);
+
+    assert!(matches!(result, Err(CheckpointError::HookRejected(_))));
+    assert_eq!(fixture.branch_tip("ccg"), tip);
+    assert_ne!(tip.unwrap().to_string(), second);
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn prune_runs_on_ccg_branch_and_switches_back() {
+    let (fixture, [first, _]) = Fixture::with_checkpoints(EDITS);
+
+    let dropped = fixture.service().prune_checkpoints(&[first]).unwrap();
+
+    assert_eq!(dropped.len(), 1);
+
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn revert_undoes_one_checkpoint_and_keeps_later_edits() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture.write("a.txt", "1\n2\n3\n4\n5\n6\n7\n8\n");
+    service.create_checkpoint(Some("base")).unwrap();
+    fixture
+        .write("a.txt", "1\nbad\n3\n4\n5\n6\n7\n8\n")
+        .write("b.txt", "bad\n");
+    let bad = service.create_checkpoint(Some("bad")).unwrap();
+    fixture.write("a.txt", "1\nbad\n3\n4\n5\n6\n7\ngood\n");
+    service.create_checkpoint(Some("good")).unwrap();
+
+    let reverted = service.revert_checkpoint(&bad[..7]).unwrap();
+
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), reverted);
+    assert_eq!(
+        fixture.read("a.txt").as_deref(),
+        Some("1\n2\n3\n4\n5\n6\n7\ngood\n")
+    );
+    assert!(fixture.read("b.txt").is_none());
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+    let entries = service.checkpoints(1).unwrap();
+    assert_eq!(entries[0].summary(), "Revert \"bad\"");
+
+    // 撤销提交与其他检查点一样记入占用空间和统计索引，stats 与 list 的数量一致
+    let repo = fixture.repo();
+    let sizes = std::fs::read_to_string(repo.path().join("ccg").join("sizes")).unwrap();
+    assert_eq!(sizes.lines().count(), 4);
+    assert!(sizes.contains(&reverted));
+    assert_eq!(fixture.git_ops().storage_usage().unwrap().checkpoints, 4);
+    let stats = ccg::git_ops::StatsIndex::new(&repo).load().unwrap();
+    let recorded = &stats[&reverted.parse().unwrap()];
+    assert_eq!(
+        (recorded.additions, recorded.deletions, recorded.total_files),
+        (1, 2, 2)
+    );
+}
+
+#[test]
+fn revert_refuses_when_later_checkpoints_change_the_same_lines() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+
+    let result = fixture.service().revert_checkpoint(&first);
+
+    assert!(matches!(result, Err(CheckpointError::Conflict(_))));
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), second);
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("two\n"));
+}
+
+#[test]
+fn revert_refuses_to_overwrite_uncommitted_changes() {
+    let (fixture, [_, second]) = Fixture::with_checkpoints(EDITS);
+    fixture.write("a.txt", "unsaved\n");
+
+    let result = fixture.service().revert_checkpoint(&second);
+
+    assert!(matches!(result, Err(CheckpointError::Conflict(_))));
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), second);
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("unsaved\n"));
+}
+
+#[test]
+fn impact_separates_same_file_from_same_line_overlaps() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture.write("a.txt", "1\n2\n3\n4\n5\n6\n7\n8\n");
+    service.create_checkpoint(Some("base")).unwrap();
+    fixture.write("a.txt", "1\ntarget\n3\n4\n5\n6\n7\n8\n");
+    let target = service.create_checkpoint(Some("target")).unwrap();
+    fixture.write("b.txt", "unrelated\n");
+    service.create_checkpoint(Some("unrelated")).unwrap();
+    fixture.write("a.txt", "1\ntarget\n3\n4\n5\n6\n7\nend\n");
+    let same_file = service.create_checkpoint(Some("same file")).unwrap();
+
+    let report = service.checkpoint_impact(&target[..7]).unwrap();
+    assert_eq!(report.later_checkpoints, 2);
+    assert_eq!(report.overlaps.len(), 1);
+    assert_eq!(report.overlaps[0].hash, same_file);
+    assert_eq!(report.overlaps[0].files, ["a.txt"]);
+    assert!(report.overlaps[0].overlapping_lines.is_empty());
+    assert!(report.revert_conflicts.is_empty());
+
+    fixture.write("a.txt", "1\nrewritten\n3\n4\n5\n6\n7\nend\n");
+    let same_lines = service.create_checkpoint(Some("same lines")).unwrap();
+
+    let report = service.checkpoint_impact(&target).unwrap();
+    assert_eq!(report.overlaps[1].hash, same_lines);
+    assert_eq!(report.overlaps[1].overlapping_lines, ["a.txt"]);
+    assert_eq!(report.revert_conflicts, ["a.txt"]);
+}
+
+#[test]
+fn restore_patch_applies_only_selected_hunks() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let original: String = (1..=20).map(|n| format!("line {n}\n")).collect();
+    fixture.write("a.txt", &original);
+    let first = service.create_checkpoint(Some("first")).unwrap();
+    let edited = original
+        .replace("line 2\n", "changed 2\n")
+        .replace("line 19\n", "changed 19\n");
+    fixture.write("a.txt", &edited).write("b.txt", "bee\n");
+    service.create_checkpoint(Some("second")).unwrap();
+
+    let hunks = service.restore_hunks(&first).unwrap();
+    assert_eq!(hunks.len(), 3);
+    assert!(hunks[0].lines.contains(&"+line 2".to_string()));
+    assert!(hunks[0].lines.contains(&"-changed 2".to_string()));
+    assert_eq!(hunks[2].path, "b.txt");
+
+    let applied = service
+        .apply_restore_hunks(&first, &[false, true, false])
+        .unwrap();
+
+    assert_eq!(applied, 1);
+    let restored = fixture.read("a.txt").unwrap();
+    assert!(restored.contains("changed 2\n"));
+    assert!(restored.contains("line 19\n"));
+    assert_eq!(fixture.read("b.txt").as_deref(), Some("bee\n"));
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn restore_patch_rejects_stale_selection() {
+    let (fixture, [first, _]) = Fixture::with_checkpoints(EDITS);
+
+    let result = fixture.service().apply_restore_hunks(&first, &[true, true]);
+
+    assert!(matches!(result, Err(CheckpointError::InvalidArgument(_))));
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("two\n"));
+}
+
+#[test]
+fn discarded_checkpoints_lists_checkpoints_after_the_target() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    let service = fixture.service();
+    fixture.write("a.txt", "three\n");
+    let third = service.create_checkpoint(Some("third")).unwrap();
+
+    let discarded: Vec<String> = service
+        .discarded_checkpoints(&first)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.hash)
+        .collect();
+
+    assert_eq!(discarded, [third.clone(), second]);
+    assert!(service.discarded_checkpoints(&third).unwrap().is_empty());
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn divergence_counts_both_sides_after_a_restore_rewrote_the_branch() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    let service = fixture.service();
+    fixture.write("a.txt", "three\n");
+    let third = service.create_checkpoint(Some("third")).unwrap();
+    service.restore_checkpoint(&first).unwrap();
+    fixture.write("a.txt", "four\n");
+    let fourth = service.create_checkpoint(Some("fourth")).unwrap();
+
+    // 旧分支末端不是新末端的祖先
+    let git_ops = fixture.git_ops();
+    let divergence = service.restore_divergence(&third).unwrap();
+    assert_eq!(
+        divergence,
+        AheadBehind {
+            ahead: 1,
+            behind: 2
+        }
+    );
+    assert!(divergence.diverged());
+    assert_eq!(git_ops.count_commits_between(&third, &fourth).unwrap(), 1);
+    let discarded = service.discarded_checkpoints(&third).unwrap();
+    assert_eq!(discarded.len(), 1);
+    assert_eq!(discarded[0].hash, fourth);
+    assert!(!service.restore_divergence(&first).unwrap().diverged());
+    assert_eq!(
+        git_ops.ahead_behind(&fourth, &second).unwrap(),
+        AheadBehind {
+            ahead: 1,
+            behind: 1
+        }
+    );
+
+    // 没有共同历史的提交
+    let repo = fixture.repo();
+    let signature = repo.signature().unwrap();
+    let tree = repo
+        .find_commit(fourth.parse().unwrap())
+        .unwrap()
+        .tree()
+        .unwrap();
+    let orphan = repo
+        .commit(None, &signature, &signature, "orphan", &tree, &[])
+        .unwrap()
+        .to_string();
+    let counts = git_ops.ahead_behind(&orphan, &fourth).unwrap();
+    assert_eq!(counts.behind, 1);
+    assert_eq!(
+        counts.ahead,
+        git_ops.ahead_behind(&fourth, &orphan).unwrap().behind
+    );
+    assert!(counts.ahead >= 3);
+}
+
+#[test]
+fn task_checkpoints_can_be_listed_diffed_and_promoted() {
+    use ccg::git_ops::tasks::task_slug;
+
+    assert_eq!(task_slug("Implement auth!"), "implement-auth");
+    assert_eq!(task_slug("   "), "task");
+
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let main_tip = fixture.branch_tip("main").unwrap();
+    fixture.write("notes.txt", "before\n");
+    service.create_checkpoint(Some("before")).unwrap();
+
+    let task = service.start_task("implement  auth").unwrap();
+    assert_eq!(task.id, "implement-auth");
+    assert!(matches!(
+        service.start_task("other"),
+        Err(CheckpointError::InvalidArgument(_))
+    ));
+    fixture.write("auth.rs", "fn login() {}\n");
+    service.create_checkpoint(Some("add login")).unwrap();
+    fixture.write("auth.rs", "fn login() {}\nfn logout() {}\n");
+    service.create_checkpoint(Some("add logout")).unwrap();
+    service.end_task().unwrap();
+    fixture.write("notes.txt", "after\n");
+    service.create_checkpoint(Some("after")).unwrap();
+
+    let filter = ListFilter {
+        task: Some(task.id.clone()),
+        ..ListFilter::default()
+    };
+    let in_task: Vec<String> = service
+        .checkpoints(10)
+        .unwrap()
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .map(|entry| entry.summary().to_string())
+        .collect();
+    assert_eq!(in_task, ["add logout", "add login"]);
+
+    // 合并差异只包含任务中的变更
+    let range = service.task_range(&task.id).unwrap();
+    let diff = fixture
+        .git_ops()
+        .diff_checkpoints(&range.base, Some(&range.tip))
+        .unwrap();
+    assert!(diff.contains("auth.rs"));
+    assert!(diff.contains("fn logout() {}"));
+    assert!(!diff.contains("notes.txt"));
+
+    // 同名任务得到新的 ID
+    assert_eq!(
+        service.start_task("Implement auth").unwrap().id,
+        "implement-auth-2"
+    );
+    service.end_task().unwrap();
+
+    // 压缩到 main 上的一个提交，不带任务之外的变更
+    let hash = service.promote_task(&task.id, None, false).unwrap();
+    let repo = fixture.repo();
+    let commit = repo.find_commit(hash.parse().unwrap()).unwrap();
+    assert_eq!(commit.parent_id(0).unwrap(), main_tip);
+    assert_eq!(fixture.branch_tip("main").unwrap(), commit.id());
+    let message = commit.message().unwrap();
+    assert!(message.starts_with("implement auth\n\n"));
+    assert!(message.contains("- add login\n- add logout\n"));
+    let tree = commit.tree().unwrap();
+    assert!(tree.get_path("auth.rs".as_ref()).is_ok());
+    assert!(tree.get_path("notes.txt".as_ref()).is_err());
+    assert_eq!(fixture.read("notes.txt").as_deref(), Some("after\n"));
+
+    assert!(matches!(
+        service.task_range("missing"),
+        Err(CheckpointError::CheckpointNotFound(_))
+    ));
+}
+
+#[test]
+fn format_only_checkpoints_are_marked_and_can_be_left_out() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let main_tip = fixture.branch_tip("main").unwrap();
+    let task = service.start_task("parser").unwrap();
+    fixture.write("parse.rs", "fn parse(a: u8) -> u8 { a }\n");
+    service.create_checkpoint(Some("add parser")).unwrap();
+    let formatted = "fn parse(a: u8) -> u8 {\n    a\n}\n";
+    fixture.write("parse.rs", formatted);
+    let reformat = service.create_checkpoint(Some("rustfmt")).unwrap();
+    fixture.write("lex.rs", "fn lex() {}\n");
+    service.create_checkpoint(Some("add lexer")).unwrap();
+    service.end_task().unwrap();
+
+    let git_ops = fixture.git_ops();
+    assert!(git_ops.is_format_only(&reformat).unwrap());
+    git_ops.ensure_ccg_branch().unwrap();
+    let list = |skip_format_only| -> Vec<String> {
+        git_ops
+            .list_checkpoints(
+                10,
+                &ListColumn::DEFAULT,
+                &ListFilter {
+                    skip_format_only,
+                    ..ListFilter::default()
+                },
+            )
+            .unwrap()
+            .iter()
+            .map(|line| console::strip_ansi_codes(line).to_string())
+            .collect()
+    };
+    // 最后一行是 main 上的初始提交
+    let all = list(false);
+    assert_eq!(all.len(), 4);
+    assert!(all[1].contains("[format-only]") && all[1].contains("rustfmt"));
+    assert!(!all[0].contains("[format-only]") && !all[2].contains("[format-only]"));
+    let kept = list(true);
+    assert_eq!(kept.len(), 3);
+    assert!(kept.iter().all(|line| !line.contains("rustfmt")));
+    git_ops.restore_original_branch("main").unwrap();
+
+    // 提交中不包含格式化，格式化后的内容留在工作目录中
+    let hash = service.promote_task(&task.id, None, true).unwrap();
+    let repo = fixture.repo();
+    let commit = repo.find_commit(hash.parse().unwrap()).unwrap();
+    assert_eq!(commit.parent_id(0).unwrap(), main_tip);
+    let message = commit.message().unwrap();
+    assert!(message.contains("- add parser\n- add lexer\n"), "{message}");
+    let tree = commit.tree().unwrap();
+    let blob = repo
+        .find_blob(tree.get_path("parse.rs".as_ref()).unwrap().id())
+        .unwrap();
+    assert_eq!(blob.content(), b"fn parse(a: u8) -> u8 { a }\n");
+    assert!(tree.get_path("lex.rs".as_ref()).is_ok());
+    assert_eq!(fixture.read("parse.rs").as_deref(), Some(formatted));
+    let statuses = repo.statuses(None).unwrap();
+    let status = statuses
+        .iter()
+        .find(|entry| entry.path() == Some("parse.rs"))
+        .unwrap()
+        .status();
+    assert_eq!(status, git2::Status::WT_MODIFIED);
+}
+
+#[test]
+fn restore_to_directory_leaves_working_tree_alone() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    fixture.write("src/main.rs", "fn main() {}\n");
+    let third = fixture.service().create_checkpoint(Some("third")).unwrap();
+    let target = tempfile::tempdir().unwrap();
+    let dir = target.path().join("old");
+
+    let count = fixture
+        .service()
+        .restore_to_directory(&first[..7], &dir)
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "one\n");
+    assert!(!dir.join("src/main.rs").exists());
+    assert_eq!(count, std::fs::read_dir(&dir).unwrap().count());
+    // 工作目录和 ccg 分支都不变
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("two\n"));
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), third);
+    assert_ne!(second, third);
+
+    // 新检查点的子目录也会写出；已有内容的目录被拒绝
+    let newer = target.path().join("new");
+    fixture
+        .service()
+        .restore_to_directory(&third, &newer)
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(newer.join("src/main.rs")).unwrap(),
+        "fn main() {}\n"
+    );
+    assert!(matches!(
+        fixture.service().restore_to_directory(&third, &newer),
+        Err(CheckpointError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn run_in_checkpoint_uses_a_temporary_copy() {
+    let (fixture, [first, _]) = Fixture::with_checkpoints(EDITS);
+    let command = |script: &str| vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+
+    let status = fixture
+        .service()
+        .run_in_checkpoint(
+            &first[..7],
+            &command(&format!(
+                r#"test "$(cat a.txt)" = one && test "$CCG_CHECKPOINT" = {first}"#
+            )),
+            false,
+        )
+        .unwrap();
+    assert!(status.success());
+
+    // 命令的退出状态原样返回，对临时目录的修改不影响工作目录
+    let status = fixture
+        .service()
+        .run_in_checkpoint(&first, &command("echo changed > a.txt; exit 3"), false)
+        .unwrap();
+    assert_eq!(status.code(), Some(3));
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("two\n"));
+
+    assert!(matches!(
+        fixture.service().run_in_checkpoint(&first, &[], false),
+        Err(CheckpointError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn diff_directory_compares_checkpoint_with_any_folder() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    let other = tempfile::tempdir().unwrap();
+    std::fs::write(other.path().join("a.txt"), "deployed\n").unwrap();
+    std::fs::create_dir_all(other.path().join("conf/.git")).unwrap();
+    std::fs::write(other.path().join("conf/.git/HEAD"), "ref\n").unwrap();
+    std::fs::write(other.path().join("conf/app.toml"), "port = 80\n").unwrap();
+
+    fixture
+        .service()
+        .diff_directory(Some(&first[..7]), other.path())
+        .unwrap();
+    fixture
+        .service()
+        .diff_directory(None, other.path())
+        .unwrap();
+
+    let diff = fixture
+        .git_ops()
+        .diff_directory(&second, other.path())
+        .unwrap();
+    assert!(diff.contains("deployed"));
+    assert!(diff.contains("conf/app.toml"));
+    assert!(!diff.contains(".git"));
+    // 目录内容只在内存中哈希，不写入仓库
+    let blob = git2::Oid::hash_object(git2::ObjectType::Blob, b"deployed\n").unwrap();
+    assert!(!fixture.repo().odb().unwrap().exists(blob));
+
+    assert!(matches!(
+        fixture
+            .service()
+            .diff_directory(None, &other.path().join("missing")),
+        Err(CheckpointError::InvalidArgument(_))
+    ));
+}
+
+/// 让文件无法写入：普通用户用只读权限，root 用 `chattr +i`；都不可行时返回 false
+fn make_unwritable(fixture: &Fixture, path: &str, unwritable: bool) -> bool {
+    let full = fixture.file_path(path);
+    let chattr = |flag: &str| {
+        let _ = std::process::Command::new("chattr")
+            .arg(flag)
+            .arg(&full)
+            .output();
+    };
+    if !unwritable {
+        chattr("-i");
+    }
+    let mut permissions = std::fs::metadata(&full).unwrap().permissions();
+    permissions.set_readonly(unwritable);
+    std::fs::set_permissions(&full, permissions).unwrap();
+    if unwritable {
+        chattr("+i");
+    }
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&full)
+        .is_err()
+        == unwritable
+}
+
+#[test]
+fn restore_stops_before_touching_files_it_cannot_write() {
+    let (fixture, [first, _]) = Fixture::with_checkpoints(EDITS);
+    fixture.write("b.txt", "bee\n");
+    let third = fixture.service().create_checkpoint(Some("third")).unwrap();
+    if !make_unwritable(&fixture, "a.txt", true) {
+        return;
+    }
+
+    let result = fixture.service().restore_checkpoint(&first);
+    let untouched = fixture.read("b.txt");
+    let tip = fixture.branch_tip("ccg").unwrap().to_string();
+
+    // 跳过无法写入的文件，其余文件照常恢复
+    let report = fixture.service().restore_checkpoint_with(
+        &first,
+        RestoreOptions {
+            continue_on_error: true,
+            ..Default::default()
+        },
+    );
+    make_unwritable(&fixture, "a.txt", false);
+
+    assert!(matches!(
+        result,
+        Err(CheckpointError::PermissionDenied(ref path)) if path == "a.txt"
+    ));
+    assert_eq!(untouched.as_deref(), Some("bee\n"));
+    assert_eq!(tip, third);
+    let report = report.unwrap();
+    assert_eq!(report.unwritable, vec!["a.txt".to_string()]);
+    assert_eq!(fixture.read("b.txt"), None);
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("two\n"));
+}
+
+#[test]
+fn continue_on_error_matches_file_names_literally() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture.write("a.txt", "one\n").write("[ab].txt", "one\n");
+    let first = service.create_checkpoint(Some("first")).unwrap();
+    fixture.write("a.txt", "two\n").write("[ab].txt", "two\n");
+    service.create_checkpoint(Some("second")).unwrap();
+    if !make_unwritable(&fixture, "a.txt", true) {
+        return;
+    }
+
+    // `[ab].txt` 作为通配符会匹配到无法写入的 a.txt
+    let report = service.restore_checkpoint_with(
+        &first,
+        RestoreOptions {
+            continue_on_error: true,
+            ..Default::default()
+        },
+    );
+    make_unwritable(&fixture, "a.txt", false);
+
+    assert_eq!(report.unwrap().unwritable, vec!["a.txt".to_string()]);
+    assert_eq!(fixture.read("[ab].txt").as_deref(), Some("one\n"));
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("two\n"));
+}
+
+#[test]
+fn committing_selected_paths_matches_file_names_literally() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n").write("[ab].txt", "one\n");
+    fixture.commit("base");
+    fixture.write("a.txt", "two\n").remove("[ab].txt");
+
+    let hash = fixture
+        .git_ops()
+        .commits()
+        .create_commit_paths("remove [ab].txt", &["[ab].txt".to_string()])
+        .unwrap();
+
+    let repo = fixture.repo();
+    let tree = repo
+        .find_commit(hash.parse().unwrap())
+        .unwrap()
+        .tree()
+        .unwrap();
+    assert!(tree.get_name("[ab].txt").is_none());
+    let a = tree.get_name("a.txt").unwrap().to_object(&repo).unwrap();
+    assert_eq!(a.as_blob().unwrap().content(), b"one\n");
+}
+
+#[test]
+fn restore_failing_partway_rolls_the_working_directory_back() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture
+        .write("a.txt", "one\n")
+        .write("d/x.txt", "x\n")
+        .write("d/y.txt", "y\n");
+    let first = service.create_checkpoint(Some("first")).unwrap();
+    fixture.write("a.txt", "two\n").remove("d/y.txt");
+    let second = service.create_checkpoint(Some("second")).unwrap();
+    fixture.write("notes.txt", "untracked\n");
+
+    // 目录不可修改时无法预先发现，检出会在写入 a.txt 之后、创建 d/y.txt 时失败
+    let dir = fixture.file_path("d");
+    let locked = std::process::Command::new("chattr")
+        .arg("+i")
+        .arg(&dir)
+        .status()
+        .is_ok_and(|status| status.success());
+    if !locked {
+        return;
+    }
+    let result = fixture.service().restore_checkpoint_with(
+        &first,
+        RestoreOptions {
+            clean: true,
+            ..Default::default()
+        },
+    );
+    let _ = std::process::Command::new("chattr")
+        .arg("-i")
+        .arg(&dir)
+        .status();
+
+    assert!(matches!(
+        result,
+        Err(CheckpointError::RestoreRolledBack(ref path)) if path.contains("y.txt")
+    ));
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("two\n"));
+    assert_eq!(fixture.read("notes.txt").as_deref(), Some("untracked\n"));
+    assert_eq!(fixture.read("d/y.txt"), None);
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), second);
+}
+
+#[test]
+fn since_range_combines_checkpoints_in_time_window() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture.write("old.txt", "old\n");
+    let old = service.create_checkpoint(Some("old")).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    fixture.write("a.txt", "one\n");
+    service.create_checkpoint(Some("one")).unwrap();
+    fixture.write("a.txt", "two\n");
+    let tip = service.create_checkpoint(Some("two")).unwrap();
+    let since = service.checkpoints(2).unwrap()[1].time;
+
+    let range = service.since_range(since).unwrap().unwrap();
+    let diff = fixture
+        .git_ops()
+        .diff_checkpoints(&range.base, Some(&range.tip))
+        .unwrap();
+
+    assert_eq!(range.base, old);
+    assert_eq!(range.tip, tip);
+    assert_eq!(range.checkpoints.len(), 2);
+    assert!(diff.contains("a.txt") && diff.contains("two"));
+    assert!(!diff.contains("old.txt"));
+    assert!(service.since_range(since + 3600).unwrap().is_none());
+    let hour_ago = ccg::commands::evolution::parse_time("1 hour ago").unwrap();
+    assert_eq!(
+        ccg::commands::evolution::parse_time("1h").unwrap(),
+        hour_ago
+    );
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use ccg::commands::traits::{HookAction, HookArgs};
+use ccg::commands::{Command, HookCommand};
+use ccg::services::integration::{BACKUP_SUFFIX, InstallTarget};
+use common::Fixture;
+use serde_json::Value;
+
+fn install(fixture: &Fixture, targets: &[InstallTarget], print: bool) {
+    let args = HookArgs {
+        action: HookAction::Install,
+        targets: targets.to_vec(),
+        print,
+    };
+    HookCommand::new(fixture.context()).execute(args).unwrap();
+}
+
+#[test]
+fn claude_settings_keep_existing_entries_and_are_backed_up() {
+    let fixture = Fixture::new();
+    let original = r#"{"model":"opus","hooks":{"PostToolUse":[{"matcher":"Bash","hooks":[{"type":"command","command":"echo hi"}]}]}}"#;
+    fixture.write(".claude/settings.json", original);
+
+    install(&fixture, &[InstallTarget::ClaudeSettings], false);
+
+    let settings: Value =
+        serde_json::from_str(&fixture.read(".claude/settings.json").unwrap()).unwrap();
+    let entries = settings["hooks"]["PostToolUse"].as_array().unwrap();
+    assert_eq!(settings["model"], "opus");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["hooks"][0]["command"], "echo hi");
+    assert_eq!(entries[1]["hooks"][0]["command"], "ccg create");
+    assert_eq!(
+        fixture
+            .read(&format!(".claude/settings.json{BACKUP_SUFFIX}"))
+            .unwrap(),
+        original
+    );
+}
+
+#[test]
+fn install_is_idempotent() {
+    let fixture = Fixture::new();
+
+    install(&fixture, &[InstallTarget::ClaudeSettings], false);
+    let first = fixture.read(".claude/settings.json").unwrap();
+    install(&fixture, &[InstallTarget::ClaudeSettings], false);
+
+    assert_eq!(fixture.read(".claude/settings.json").unwrap(), first);
+    assert!(
+        !fixture
+            .file_path(&format!(".claude/settings.json{BACKUP_SUFFIX}"))
+            .exists()
+    );
+}
+
+#[test]
+fn git_hook_respects_core_hooks_path_and_chains_existing_script() {
+    let fixture = Fixture::new();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_str("core.hooksPath", "githooks")
+        .unwrap();
+    fixture.write("githooks/post-commit", "#!/bin/sh\necho user hook\n");
+
+    install(&fixture, &[InstallTarget::GitHook], false);
+
+    let script = fixture.read("githooks/post-commit").unwrap();
+    assert!(script.starts_with("#!/bin/sh\necho user hook\n"));
+    assert!(script.contains("ccg create"));
+    assert!(!fixture.path().join(".git/hooks/post-commit").exists());
+    assert_eq!(
+        fixture
+            .read(&format!("githooks/post-commit{BACKUP_SUFFIX}"))
+            .unwrap(),
+        "#!/bin/sh\necho user hook\n"
+    );
+}
+
+#[test]
+fn print_does_not_write_files() {
+    let fixture = Fixture::new();
+
+    install(
+        &fixture,
+        &[InstallTarget::ClaudeSettings, InstallTarget::GitHook],
+        true,
+    );
+
+    assert!(!fixture.file_path(".claude/settings.json").exists());
+    assert!(!fixture.path().join(".git/hooks/post-commit").exists());
+}
+
+#[test]
+fn unmergeable_configuration_is_left_untouched() {
+    let fixture = Fixture::new();
+    fixture.write(".claude/settings.json", r#"{"hooks":{"PostToolUse":{}}}"#);
+    fixture.write(
+        ".git/hooks/post-commit",
+        "#!/usr/bin/env python3\nprint()\n",
+    );
+
+    let args = HookArgs {
+        action: HookAction::Install,
+        targets: vec![InstallTarget::GitHook, InstallTarget::ClaudeSettings],
+        print: false,
+    };
+    let result = HookCommand::new(fixture.context()).execute(args);
+
+    assert!(result.is_err());
+    assert_eq!(
+        fixture.read(".claude/settings.json").unwrap(),
+        r#"{"hooks":{"PostToolUse":{}}}"#
+    );
+    assert_eq!(
+        fixture.read(".git/hooks/post-commit").unwrap(),
+        "#!/usr/bin/env python3\nprint()\n"
+    );
+}
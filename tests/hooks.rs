@@ -0,0 +1,73 @@
+#![cfg(unix)]
+
+mod common;
+
+use ccg::error::CheckpointError;
+use ccg::services::{HookEvent, HookRunner};
+use common::Fixture;
+use serde_json::{Value, json};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+#[test]
+fn missing_or_non_executable_hooks_are_skipped() {
+    let fixture = Fixture::new();
+    let runner = HookRunner::new(fixture.path());
+    for event in HookEvent::ALL {
+        assert!(!runner.exists(event));
+        runner.run(event, &json!({})).unwrap();
+    }
+
+    fixture.hook("pre-create", "exit 1");
+    let path = runner.hook_path(HookEvent::PreCreate);
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+    assert!(!runner.exists(HookEvent::PreCreate));
+    runner.run(HookEvent::PreCreate, &json!({})).unwrap();
+}
+
+#[test]
+fn hook_receives_context_on_stdin_in_repo_root() {
+    let fixture = Fixture::new();
+    fixture.hook(
+        "post-restore",
+        "cat > hook-input.json\necho \"$CCG_HOOK\" > hook-env.txt\npwd > hook-pwd.txt",
+    );
+    let runner = HookRunner::new(fixture.path());
+    assert!(runner.exists(HookEvent::PostRestore));
+
+    runner
+        .run(HookEvent::PostRestore, &json!({ "hash": "abc123" }))
+        .unwrap();
+
+    let payload: Value = serde_json::from_str(&fixture.read("hook-input.json").unwrap()).unwrap();
+    assert_eq!(payload["hash"], "abc123");
+    assert_eq!(payload["event"], "post-restore");
+    assert_eq!(payload["repo"], fixture.path_str());
+    assert_eq!(fixture.read("hook-env.txt").unwrap().trim(), "post-restore");
+    assert_eq!(
+        fs::canonicalize(fixture.read("hook-pwd.txt").unwrap().trim()).unwrap(),
+        fs::canonicalize(fixture.path()).unwrap()
+    );
+}
+
+#[test]
+fn only_pre_hooks_abort_on_nonzero_exit() {
+    let fixture = Fixture::new();
+    for event in HookEvent::ALL {
+        fixture.hook(event.name(), "exit 7");
+    }
+    let runner = HookRunner::new(fixture.path());
+
+    for event in HookEvent::ALL {
+        let result = runner.run(event, &json!({}));
+        if event.is_blocking() {
+            let Err(CheckpointError::HookRejected(message)) = result else {
+                panic!("{} should reject, got {result:?}", event.name());
+            };
+            assert_eq!(message, format!("{} (exit 7)", event.name()));
+        } else {
+            // post-* 钩子失败只警告
+            result.unwrap();
+        }
+    }
+}
@@ -0,0 +1,735 @@
+mod common;
+
+use ccg::CheckpointError;
+use ccg::git_ops::{
+    BranchOperations, CheckpointEntry, CommitOperations, DiffOperations, ParallelStats,
+};
+use common::{Fixture, Step, show};
+use std::io::{self, Write};
+
+/// Two checkpoints: one adding `a.txt`, one editing it and adding `b.txt`
+const EDITS: [Step; 2] = [
+    ("add a", &[("a.txt", "one\n")]),
+    ("edit a, add b", &[("a.txt", "two\n"), ("b.txt", "bee\n")]),
+];
+
+#[test]
+fn list_returns_structured_entries() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+
+    let entries = fixture.service().checkpoints(10).unwrap();
+
+    let hashes: Vec<&str> = entries.iter().map(|e| e.hash.as_str()).collect();
+    assert_eq!(entries.len(), 3);
+    assert!(hashes.contains(&first.as_str()));
+    assert!(hashes.contains(&second.as_str()));
+    assert!(entries.iter().all(|e| e.author == "Fixture"));
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn same_second_checkpoints_have_one_stable_order() {
+    let (fixture, [_, second]) = Fixture::with_checkpoints(EDITS);
+    // 同一秒内从同一个检查点分出的两个提交，再合并
+    let repo = fixture.repo();
+    let sig = git2::Signature::new(
+        "Agent",
+        "agent@example.com",
+        &git2::Time::new(2_000_000_000, 0),
+    )
+    .unwrap();
+    let base = repo.find_commit(second.parse().unwrap()).unwrap();
+    let tree = base.tree().unwrap();
+    let mut sides: Vec<git2::Commit> = ["left", "right"]
+        .iter()
+        .map(|message| {
+            let oid = repo
+                .commit(None, &sig, &sig, message, &tree, &[&base])
+                .unwrap();
+            repo.find_commit(oid).unwrap()
+        })
+        .collect();
+    let merge = repo
+        .commit(None, &sig, &sig, "merge", &tree, &[&sides[0], &sides[1]])
+        .unwrap();
+    repo.reference("refs/heads/ccg", merge, true, "test")
+        .unwrap();
+    sides.sort_by_key(|commit| commit.id());
+
+    let service = fixture.service();
+    let hashes: Vec<String> = service
+        .checkpoints(4)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.hash)
+        .collect();
+    let expected = [
+        merge.to_string(),
+        sides[0].id().to_string(),
+        sides[1].id().to_string(),
+        second,
+    ];
+    assert_eq!(hashes, expected);
+    for (offset, hash) in expected.iter().enumerate() {
+        let reference = format!("latest~{offset}");
+        assert_eq!(&service.resolve_checkpoint(&reference).unwrap(), hash);
+    }
+    assert_eq!(service.resolve_checkpoint("latest").unwrap(), expected[0]);
+}
+
+#[test]
+fn list_respects_limit() {
+    let (fixture, [_, _]) = Fixture::with_checkpoints(EDITS);
+
+    assert_eq!(fixture.service().checkpoints(2).unwrap().len(), 2);
+}
+
+#[test]
+fn show_lists_changed_files() {
+    let (fixture, [_, second]) = Fixture::with_checkpoints(EDITS);
+
+    let output = show(&fixture, &second, false, &[]);
+
+    assert!(output.contains(&second));
+    assert!(output.contains("edit a, add b"));
+    assert!(output.contains("M a.txt"));
+    assert!(output.contains("A b.txt"));
+    assert!(!output.contains("Detailed Diff"));
+}
+
+#[test]
+fn show_files_filter_limits_output() {
+    let (fixture, [_, second]) = Fixture::with_checkpoints(EDITS);
+
+    let output = show(&fixture, &second, true, &["b.*"]);
+
+    assert!(output.contains("A b.txt"));
+    assert!(!output.contains("a.txt"));
+    assert!(output.contains("Detailed Diff"));
+    assert!(output.contains("bee"));
+}
+
+#[test]
+fn focused_operations_agree_with_the_facade() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    let git_ops = fixture.git_ops();
+    let repo = fixture.repo();
+    let commits = CommitOperations::new(&repo);
+
+    let hashes = |entries: Vec<CheckpointEntry>| -> Vec<(String, Option<u64>)> {
+        entries.into_iter().map(|e| (e.hash, e.id)).collect()
+    };
+    assert_eq!(
+        hashes(commits.list_entries(10).unwrap()),
+        hashes(git_ops.checkpoint_entries(10).unwrap())
+    );
+    assert_eq!(
+        commits.find_commit("latest").unwrap().id().to_string(),
+        second
+    );
+    assert_eq!(
+        commits.get_commit_details(&first).unwrap(),
+        git_ops.get_commit_details(&first).unwrap()
+    );
+    assert_eq!(
+        BranchOperations::new(&repo)
+            .get_current_branch_name()
+            .unwrap(),
+        git_ops.get_current_branch_name().unwrap()
+    );
+
+    // 直接使用子结构恢复与经由门面恢复的行为一致
+    repo.set_head("refs/heads/ccg").unwrap();
+    fixture.write("a.txt", "dirty\n");
+    let result = commits.restore_commit(&first, false);
+    assert!(matches!(result, Err(CheckpointError::UncommittedChanges)));
+    assert!(matches!(
+        git_ops.restore_checkpoint(&first, false),
+        Err(CheckpointError::UncommittedChanges)
+    ));
+    fixture.write("a.txt", "two\n");
+    commits.restore_commit(&first, false).unwrap();
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("one\n"));
+}
+
+#[test]
+fn parallel_stats_match_serial_stats_in_order() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let mut hashes = Vec::new();
+    for i in 1..=6 {
+        let lines: String = (0..i).map(|n| format!("{n}\n")).collect();
+        fixture.write(&format!("f{}.txt", i % 3), &lines);
+        hashes.push(service.create_checkpoint(Some(&format!("v{i}"))).unwrap());
+    }
+    let repo = fixture.repo();
+    let oids: Vec<_> = hashes.iter().map(|hash| hash.parse().unwrap()).collect();
+
+    let serial = ParallelStats::new(repo.path())
+        .with_workers(1)
+        .compute(&oids)
+        .unwrap();
+    let parallel = ParallelStats::new(repo.path())
+        .with_workers(4)
+        .compute(&oids)
+        .unwrap();
+    assert_eq!(parallel, serial);
+    let diffs = DiffOperations::new(&repo);
+    for (oid, stats) in oids.iter().zip(&serial) {
+        let commit = repo.find_commit(*oid).unwrap();
+        assert_eq!(&diffs.commit_stats(&commit).unwrap(), stats);
+    }
+
+    // 统计索引缺失时重新计算，结果不变
+    std::fs::remove_file(repo.path().join("ccg").join("stats")).unwrap();
+    let reversed: Vec<String> = hashes.iter().rev().cloned().collect();
+    let mut listed = fixture.git_ops().checkpoint_stats(&reversed).unwrap();
+    listed.reverse();
+    assert_eq!(listed, serial);
+}
+
+/// Records each write separately, to see what has been streamed when
+#[derive(Default)]
+struct Chunks(Vec<Vec<u8>>);
+
+impl Write for Chunks {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.push(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn streamed_diff_matches_buffered_output() {
+    let (fixture, [_, _]) = Fixture::with_checkpoints(EDITS);
+    fixture
+        .write("a.txt", "three\n")
+        .write("b.txt", "bee\nbuzz\n")
+        .write("c.txt", "sea\n");
+    let hash = fixture.service().create_checkpoint(Some("three")).unwrap();
+    let git_ops = fixture.git_ops();
+    let diffs = git_ops.diffs();
+    let diff = diffs
+        .get_commit_diff(&git_ops.find_commit(&hash).unwrap())
+        .unwrap();
+
+    let buffered = diffs.format_diff_output(&diff).unwrap();
+    let mut chunks = Chunks::default();
+    diffs.write_diff_output(&diff, &mut chunks).unwrap();
+
+    assert_eq!(String::from_utf8(chunks.0.concat()).unwrap(), buffered);
+    // 每个文件单独写出，前面的文件不等后面的差异生成
+    let written_at = |needle: &str| {
+        chunks
+            .0
+            .iter()
+            .position(|chunk| String::from_utf8_lossy(chunk).contains(needle))
+            .unwrap()
+    };
+    assert!(written_at("three") < written_at("buzz"));
+    assert!(written_at("buzz") < written_at("sea"));
+
+    let mut out = Vec::new();
+    git_ops
+        .show_checkpoint(&hash, true, false, &[], &mut out)
+        .unwrap();
+    assert!(String::from_utf8(out).unwrap().ends_with(&buffered));
+}
+
+#[test]
+fn root_checkpoint_diff_defaults_to_stats() {
+    let fixture = Fixture::empty();
+    fixture
+        .write("src/lib.rs", "pub fn a() {}\npub fn b() {}\n")
+        .write("logo.png", "\u{0}\u{1}png");
+    let root = fixture.commit("initial").to_string();
+
+    let output = show(&fixture, &root, true, &[]);
+    assert!(output.contains("Diff Stat:"));
+    assert!(output.contains(" logo.png   | Bin\n"));
+    assert!(output.contains(" src/lib.rs |   2 ++\n"));
+    assert!(!output.contains("Detailed Diff"));
+    assert!(!output.contains("pub fn a()"));
+
+    let mut out = Vec::new();
+    fixture
+        .git_ops()
+        .show_checkpoint(&root, true, true, &[], &mut out)
+        .unwrap();
+    let output = String::from_utf8(out).unwrap();
+    assert!(output.contains("Detailed Diff"));
+    assert!(output.contains("pub fn a()"));
+}
+
+#[test]
+fn diff_between_checkpoints_includes_changes() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+
+    let diff = fixture
+        .git_ops()
+        .diff_checkpoints(&first, Some(&second))
+        .unwrap();
+    let diff = console::strip_ansi_codes(&diff);
+
+    assert!(diff.contains("a.txt"));
+    assert!(diff.contains("b.txt"));
+}
+
+#[test]
+fn checkpoint_file_returns_content_at_checkpoint() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    let git_ops = fixture.git_ops();
+
+    assert_eq!(git_ops.checkpoint_file(&first, "a.txt").unwrap(), b"one\n");
+    assert_eq!(git_ops.checkpoint_file(&second, "a.txt").unwrap(), b"two\n");
+    assert!(git_ops.checkpoint_file(&first, "b.txt").is_err());
+}
+
+#[test]
+fn show_file_output_writes_without_touching_workdir() {
+    let (fixture, [first, _]) = Fixture::with_checkpoints(EDITS);
+    let dest = fixture.file_path("out/a.txt");
+
+    fixture
+        .service()
+        .show_checkpoint_file(&first, "a.txt", false, Some(&dest))
+        .unwrap();
+
+    assert_eq!(fixture.read("out/a.txt").as_deref(), Some("one\n"));
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("two\n"));
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn highlight_preserves_text_and_leaves_unknown_files_alone() {
+    let source = "fn main() { // entry \"quoted\" 42\n}\n";
+
+    let highlighted = ccg::ui::highlight::highlight(source, "main.rs");
+
+    assert_eq!(console::strip_ansi_codes(&highlighted), source);
+    assert_eq!(ccg::ui::highlight::highlight(source, "notes.txt"), source);
+}
+
+#[test]
+fn diff_without_arguments_uses_latest_checkpoint() {
+    let (fixture, [_, second]) = Fixture::with_checkpoints(EDITS);
+    fixture.write("a.txt", "three\n");
+    let service = fixture.service();
+
+    assert_eq!(service.latest_checkpoint().unwrap(), second);
+    service.diff_checkpoints(None, None).unwrap();
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn workdir_diff_includes_unstaged_and_untracked_files() {
+    let (fixture, [_, second]) = Fixture::with_checkpoints(EDITS);
+    fixture
+        .write("a.txt", "three\n")
+        .write("notes/new.txt", "fresh\n");
+    let git_ops = fixture.git_ops();
+
+    let diff = git_ops.diff_checkpoints(&second, None).unwrap();
+    let diff = console::strip_ansi_codes(&diff);
+
+    assert!(diff.contains("three"));
+    assert!(diff.contains("未跟踪文件 (1)"));
+    assert!(diff.contains("notes/new.txt"));
+    assert!(diff.contains("fresh"));
+    assert!(diff.find("a.txt") < diff.find("notes/new.txt"));
+}
+
+#[test]
+fn has_changes_to_commit_counts_untracked_files() {
+    let fixture = Fixture::new();
+    let git_ops = fixture.git_ops();
+    assert!(!git_ops.commits().has_changes_to_commit().unwrap());
+
+    fixture.write("new.txt", "fresh\n");
+
+    assert!(git_ops.commits().has_changes_to_commit().unwrap());
+}
+
+#[test]
+fn status_checks_skip_ignored_dirs_and_refresh_after_commits() {
+    let fixture = Fixture::new();
+    fixture
+        .write(".gitignore", "target/\n")
+        .commit("ignore target");
+    fixture.write("target/debug/out.bin", "build\n");
+    assert!(!fixture.git_ops().has_uncommitted_changes().unwrap());
+
+    fixture.write("README.md", "changed\n");
+    let git_ops = fixture.git_ops();
+    assert!(git_ops.has_uncommitted_changes().unwrap());
+    // 缓存的结果在 HEAD 或索引变化后失效
+    fixture.commit("edit readme");
+    assert!(!git_ops.has_uncommitted_changes().unwrap());
+    fixture.write("notes.txt", "new\n");
+    assert!(git_ops.commits().has_non_ignored_files().unwrap());
+}
+
+#[test]
+fn fsmonitor_status_goes_through_git_status() {
+    let fixture = Fixture::new();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_bool("ccg.fsmonitor", true)
+        .unwrap();
+    let status = |repo: &git2::Repository| {
+        ccg::git_ops::WorktreeStatus::new(repo, None)
+            .has_tracked_changes()
+            .unwrap()
+    };
+    let repo = fixture.repo();
+    assert!(!status(&repo));
+
+    fixture.write("README.md", "changed\n");
+
+    assert!(status(&repo));
+}
+
+#[test]
+fn status_reports_changes_since_latest_checkpoint() {
+    let (fixture, [_, second]) = Fixture::with_checkpoints(EDITS);
+    fixture
+        .write("a.txt", "three\n")
+        .write("new.txt", "fresh\n");
+    fixture.remove("b.txt");
+
+    let status = fixture.service().checkpoint_status().unwrap();
+
+    assert_eq!(status.latest.hash, second);
+    let changes: Vec<(&str, git2::Delta)> = status
+        .changes
+        .iter()
+        .map(|file| (file.path.as_str(), file.status))
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            ("a.txt", git2::Delta::Modified),
+            ("b.txt", git2::Delta::Deleted),
+            ("new.txt", git2::Delta::Untracked),
+        ]
+    );
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn file_history_keeps_only_checkpoints_that_changed_the_file() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    let service = fixture.service();
+    fixture.write("b.txt", "buzz\n");
+    service.create_checkpoint(Some("edit b")).unwrap();
+    fixture.remove("a.txt");
+    let deleted = service.create_checkpoint(Some("drop a")).unwrap();
+
+    let (before, versions) = service.file_evolution("a.txt", None, None).unwrap();
+
+    assert!(before.is_none());
+    let hashes: Vec<&str> = versions
+        .iter()
+        .map(|v| v.checkpoint.hash.as_str())
+        .collect();
+    assert_eq!(hashes, [first.as_str(), second.as_str(), deleted.as_str()]);
+    assert_eq!(versions[0].content.as_deref(), Some(&b"one\n"[..]));
+    assert_eq!(versions[1].content.as_deref(), Some(&b"two\n"[..]));
+    assert_eq!(versions[2].content, None);
+    service
+        .show_file_evolution("a.txt", false, None, None)
+        .unwrap();
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+}
+
+#[test]
+fn file_evolution_time_range_keeps_previous_version_as_base() {
+    let (fixture, [_, second]) = Fixture::with_checkpoints(EDITS);
+    let time = fixture.service().checkpoints(1).unwrap()[0].time;
+
+    let (before, versions) = fixture
+        .service()
+        .file_evolution("b.txt", Some(time + 1), None)
+        .unwrap();
+    assert_eq!(before.map(|v| v.checkpoint.hash), Some(second));
+    assert!(versions.is_empty());
+    assert!(
+        fixture
+            .service()
+            .file_evolution("c.txt", None, None)
+            .is_err()
+    );
+    assert!(ccg::commands::evolution::parse_time("yesterday").is_err());
+    assert!(ccg::commands::evolution::parse_time("2026-10-16 12:00").is_ok());
+}
+
+#[test]
+fn labels_are_parsed_from_message_conventions() {
+    use ccg::git_ops::CheckpointLabel::{self, *};
+
+    let cases = [
+        ("Edit on main.rs", Some(Edit)),
+        ("MultiEdit 修改 lib.rs", Some(Edit)),
+        ("Write", Some(Write)),
+        ("Manual checkpoint", Some(Manual)),
+        ("手动检查点", Some(Manual)),
+        ("Milestone: parser done", Some(Milestone)),
+        ("Revert \"Edit on main.rs\"", Some(Restore)),
+        ("恢复到 abc1234", Some(Restore)),
+        ("Write docs for the parser", None),
+        ("refactor", None),
+    ];
+    for (summary, label) in cases {
+        assert_eq!(CheckpointLabel::classify(summary), label, "{summary}");
+    }
+    assert_eq!("MILESTONE".parse::<CheckpointLabel>(), Ok(Milestone));
+}
+
+#[test]
+fn list_label_filter_keeps_matching_checkpoints() {
+    let (fixture, [_, _]) = Fixture::with_checkpoints(EDITS);
+    let service = fixture.service();
+    fixture.write("c.txt", "sea\n");
+    service.create_checkpoint(Some("Write on c.txt")).unwrap();
+    fixture.write("c.txt", "see\n");
+    service
+        .create_checkpoint(Some("Manual checkpoint"))
+        .unwrap();
+    let git_ops = fixture.git_ops();
+    git_ops.ensure_ccg_branch().unwrap();
+
+    let lines: Vec<String> = git_ops
+        .list_checkpoints(
+            10,
+            &ccg::git_ops::ListColumn::DEFAULT,
+            &ccg::git_ops::ListFilter {
+                labels: vec![ccg::git_ops::CheckpointLabel::Write],
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .iter()
+        .map(|line| console::strip_ansi_codes(line).to_string())
+        .collect();
+
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains("[write] Write on c.txt"));
+}
+
+#[test]
+fn diff_ignore_collapses_generated_files_and_counts_them_separately() {
+    let fixture = Fixture::new();
+    let mut config = fixture.repo().config().unwrap();
+    config
+        .set_multivar("ccg.diff.ignore", "^$", "*.lock")
+        .unwrap();
+    config
+        .set_multivar("ccg.diff.ignore", "^$", "dist/**")
+        .unwrap();
+    let lock: String = (0..50).map(|i| format!("dep-{i}\n")).collect();
+    fixture
+        .write("a.txt", "one\n")
+        .write("Cargo.lock", &lock)
+        .write("dist/app.js", "bundle\n");
+    let hash = fixture.service().create_checkpoint(Some("deps")).unwrap();
+
+    let output = show(&fixture, &hash, true, &[]);
+
+    assert!(output.contains("Cargo.lock (+50 -0)"));
+    assert!(!output.contains("dep-7"));
+    assert!(!output.contains("bundle"));
+    assert!(output.contains("one"));
+    let repo = fixture.repo();
+    let commit = repo
+        .find_commit(git2::Oid::from_str(&hash).unwrap())
+        .unwrap();
+    let stats = ccg::git_ops::diff::DiffOperations::new(&repo)
+        .commit_stats(&commit)
+        .unwrap();
+    assert_eq!((stats.total_files, stats.additions), (1, 1));
+    assert_eq!((stats.generated_files, stats.generated_additions), (2, 51));
+}
+
+#[test]
+fn show_diff_output_is_cached_per_checkpoint_and_options() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    let cache_dir = fixture.repo().path().join("ccg").join("cache");
+
+    let rendered = show(&fixture, &second, true, &[]);
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 1);
+    assert_eq!(show(&fixture, &second, true, &[]), rendered);
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 1);
+
+    // 不同的文件过滤条件使用不同的缓存项
+    show(&fixture, &second, true, &["b.*"]);
+    let git_ops = fixture.git_ops();
+    let diff = git_ops.diff_checkpoints(&first, Some(&second)).unwrap();
+    assert_eq!(
+        git_ops.diff_checkpoints(&first, Some(&second)).unwrap(),
+        diff
+    );
+    assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 3);
+}
+
+#[test]
+fn diff_cache_evicts_least_recently_used_entries() {
+    let fixture = Fixture::new();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_i64("ccg.cacheSize", 10)
+        .unwrap();
+    let repo = fixture.repo();
+    let cache = ccg::git_ops::DiffCache::new(&repo);
+
+    cache.put("a", b"aaaa").unwrap();
+    cache.put("b", b"bbbb").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    assert!(cache.get("a").is_some());
+    cache.put("c", b"cccc").unwrap();
+
+    assert_eq!(cache.get("a").as_deref(), Some(&b"aaaa"[..]));
+    assert!(cache.get("b").is_none());
+    assert!(cache.get("c").is_some());
+    cache.put("huge", &[0; 11]).unwrap();
+    assert!(cache.get("huge").is_none());
+}
+
+#[test]
+fn list_stats_come_from_the_index_and_are_backfilled() {
+    let (fixture, [first, second]) = Fixture::with_checkpoints(EDITS);
+    let repo = fixture.repo();
+    let index = ccg::git_ops::StatsIndex::new(&repo);
+    let oid = |hash: &str| git2::Oid::from_str(hash).unwrap();
+
+    // 创建检查点时即写入索引
+    let recorded = index.load().unwrap();
+    assert_eq!(recorded[&oid(&second)].additions, 2);
+    assert_eq!(recorded[&oid(&second)].total_files, 2);
+
+    // 缺失的条目在查询时补算并写回
+    std::fs::remove_file(repo.path().join("ccg").join("stats")).unwrap();
+    let stats = index.stats_for(&[oid(&first), oid(&second)]).unwrap();
+    assert_eq!((stats[0].additions, stats[1].deletions), (1, 1));
+    assert_eq!(index.load().unwrap().len(), 2);
+
+    // 修改 ccg.diff.ignore 后旧索引失效
+    repo.config()
+        .unwrap()
+        .set_str("ccg.diff.ignore", "b.txt")
+        .unwrap();
+    assert!(index.load().unwrap().is_empty());
+    let stats = index.stats_for(&[oid(&second)]).unwrap();
+    assert_eq!((stats[0].total_files, stats[0].generated_files), (1, 1));
+}
+
+#[test]
+fn verification_badges_and_only_passing_filter() {
+    use ccg::git_ops::{ListColumn, ListFilter};
+    use ccg::services::CreateOptions;
+
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let verify = |command: &str| CreateOptions {
+        verify: Some(command.to_string()),
+        ..CreateOptions::default()
+    };
+    fixture.write("a.txt", "one\n");
+    let passed = service
+        .create_checkpoint_with_options(Some("good"), &verify("true"))
+        .unwrap();
+    fixture.write("a.txt", "two\n");
+    let failed = service
+        .create_checkpoint_with_options(Some("broken"), &verify("false"))
+        .unwrap();
+    fixture.write("a.txt", "three\n");
+    let unverified = service.create_checkpoint(Some("plain")).unwrap();
+
+    let git_ops = fixture.git_ops();
+    git_ops.ensure_ccg_branch().unwrap();
+    let list = |filter: &ListFilter| -> Vec<String> {
+        git_ops
+            .list_checkpoints(10, &[ListColumn::Verify, ListColumn::Message], filter)
+            .unwrap()
+            .iter()
+            .map(|line| console::strip_ansi_codes(line).to_string())
+            .collect()
+    };
+    let lines = list(&ListFilter::default());
+    assert_eq!(lines[0].trim(), "plain");
+    assert!(lines[1].starts_with('❌') && lines[1].ends_with("broken"));
+    assert!(lines[2].starts_with('✅') && lines[2].ends_with("good"));
+
+    let passing = list(&ListFilter {
+        only_passing: true,
+        ..ListFilter::default()
+    });
+    assert_eq!(passing.len(), 1);
+    assert!(passing[0].ends_with("good"));
+
+    // show 中的 Verification 段落
+    let output = show(&fixture, &passed, false, &[]);
+    assert!(
+        output.contains("Verification:\n  ✅ passed (true)"),
+        "{output}"
+    );
+    let output = show(&fixture, &failed, false, &[]);
+    assert!(output.contains("❌ failed (false)"), "{output}");
+    let output = show(&fixture, &unverified, false, &[]);
+    assert!(output.contains("unknown (not verified)"), "{output}");
+}
+
+#[test]
+fn diff_settings_detect_renames_and_change_the_cache_key() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let body: String = (0..20).map(|i| format!("line {i}\n")).collect();
+    fixture.write("old.txt", &body);
+    let first = service.create_checkpoint(Some("add old")).unwrap();
+    fixture.remove("old.txt");
+    fixture.write("new.txt", &format!("{body}line 20\n"));
+    let second = service.create_checkpoint(Some("rename")).unwrap();
+
+    // 默认不检测重命名
+    let plain = show(&fixture, &second, true, &[]);
+    assert!(plain.contains("D old.txt") && plain.contains("A new.txt"));
+
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_i64("ccg.diff.renames", 50)
+        .unwrap();
+    let renamed = show(&fixture, &second, true, &[]);
+    assert!(renamed.contains("R new.txt"));
+    assert!(!renamed.contains("D old.txt"));
+    let diff = fixture
+        .git_ops()
+        .diff_checkpoints(&first, Some(&second))
+        .unwrap();
+    assert!(!diff.contains("line 5"));
+
+    // 配置中无法识别的算法被忽略，命令行选项优先于配置
+    let mut config = fixture.repo().config().unwrap();
+    config.set_str("ccg.diff.algorithm", "fancy").unwrap();
+    config.set_i64("ccg.diff.interHunkContext", 3).unwrap();
+    let configured = ccg::git_ops::DiffOperations::new(&fixture.repo()).display_settings();
+    assert_eq!(configured.algorithm, None);
+    assert_eq!(configured.inter_hunk_context, Some(3));
+    let cli = ccg::git_ops::DiffSettings {
+        algorithm: Some(ccg::config::DiffAlgorithm::Patience),
+        rename_threshold: Some(0),
+        ..Default::default()
+    };
+    let merged = cli.or(configured);
+    assert_eq!(merged.algorithm, Some(ccg::config::DiffAlgorithm::Patience));
+    assert_eq!(merged.inter_hunk_context, Some(3));
+    assert_eq!(merged.rename_threshold, Some(0));
+}
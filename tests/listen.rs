@@ -0,0 +1,118 @@
+#![cfg(unix)]
+
+mod common;
+
+use ccg::commands::ListenCommand;
+use ccg::commands::listen::{parse_event, serve_connection};
+use common::Fixture;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc;
+
+#[test]
+fn parse_event_accepts_paths_and_lsp_did_save() {
+    assert_eq!(parse_event(r#"{"path": "/w/a.rs"}"#), vec!["/w/a.rs"]);
+    assert_eq!(
+        parse_event(r#"{"paths": ["a.rs", "b.rs"]}"#),
+        vec!["a.rs", "b.rs"]
+    );
+    assert_eq!(
+        parse_event(
+            r#"{"jsonrpc": "2.0", "method": "textDocument/didSave",
+                "params": {"textDocument": {"uri": "file:///w/my%20file.rs"}}}"#
+        ),
+        vec!["/w/my file.rs"]
+    );
+    assert!(parse_event("not json").is_empty());
+    assert!(parse_event(r#"{"uri": "https://example.com/a.rs"}"#).is_empty());
+}
+
+#[test]
+fn serve_connection_answers_http_posts_and_reads_json_lines() {
+    let (sender, receiver) = mpsc::channel();
+
+    let (mut client, server) = UnixStream::pair().unwrap();
+    let body = r#"{"path": "src/a.rs"}"#;
+    write!(
+        client,
+        "POST /saved HTTP/1.1\r\nHost: ccg\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    serve_connection(server, &sender).unwrap();
+    let mut reply = String::new();
+    client.read_to_string(&mut reply).unwrap();
+    assert!(reply.starts_with("HTTP/1.1 202 Accepted"));
+    assert_eq!(receiver.recv().unwrap(), vec!["src/a.rs"]);
+
+    let (mut client, server) = UnixStream::pair().unwrap();
+    client
+        .write_all(b"{\"path\": \"a.rs\"}\ngarbage\n{\"paths\": [\"b.rs\"]}\n")
+        .unwrap();
+    drop(client);
+    serve_connection(server, &sender).unwrap();
+    let events: Vec<Vec<String>> = receiver.try_iter().collect();
+    assert_eq!(events, vec![vec!["a.rs"], vec!["b.rs"]]);
+}
+
+#[test]
+fn saves_outside_the_scope_do_not_create_checkpoints() {
+    let fixture = Fixture::new();
+    fixture
+        .write(".gitignore", "*.log\n")
+        .write("docs/notes.md", "notes\n")
+        .commit("ignore logs");
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_str("ccg.listen.include", "src/**")
+        .unwrap();
+    fixture
+        .write("src/a.rs", "fn a() {}\n")
+        .write("build.log", "log\n")
+        .write("docs/notes.md", "more notes\n");
+    let command = ListenCommand::new(fixture.context());
+
+    let outside = std::env::temp_dir().join("elsewhere.rs");
+    let skipped = command
+        .checkpoint_saved(&[
+            fixture.file_path("build.log").to_string_lossy().to_string(),
+            "docs/notes.md".to_string(),
+            outside.to_string_lossy().to_string(),
+        ])
+        .unwrap();
+    assert_eq!(skipped, None);
+    assert!(fixture.branch_tip("ccg").is_none());
+
+    let hash = command
+        .checkpoint_saved(&[
+            fixture.file_path("src/a.rs").to_string_lossy().to_string(),
+            "build.log".to_string(),
+        ])
+        .unwrap()
+        .unwrap();
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), hash);
+    let entries = fixture.service().checkpoints(1).unwrap();
+    assert_eq!(entries[0].summary(), "Saved a.rs");
+}
+
+#[test]
+fn pending_changes_are_checkpointed_without_a_save_event() {
+    let fixture = Fixture::new();
+    let command = ListenCommand::new(fixture.context());
+    assert_eq!(command.checkpoint_pending("Idle for 5 min").unwrap(), None);
+
+    // 没有保存事件报告的变更也会被记录
+    fixture.write("generated.rs", "// formatter output\n");
+    let hash = command
+        .checkpoint_pending("Idle for 5 min")
+        .unwrap()
+        .unwrap();
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), hash);
+    assert_eq!(
+        fixture.service().checkpoints(1).unwrap()[0].summary(),
+        "Idle for 5 min"
+    );
+    assert_eq!(command.checkpoint_pending("Idle for 5 min").unwrap(), None);
+}
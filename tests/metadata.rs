@@ -0,0 +1,48 @@
+mod common;
+
+use ccg::commands::CreateCommand;
+use ccg::services::MetadataFormat;
+use ccg::services::metadata::render;
+use common::Fixture;
+use serde_json::json;
+
+#[test]
+fn export_metadata_has_one_row_per_checkpoint() {
+    let fixture = Fixture::new();
+    fixture.write("src/main.rs", "fn main() {}\n");
+    let payload = json!({
+        "session_id": "s1",
+        "tool_name": "Edit",
+        "tool_input": { "file_path": fixture.file_path("src/main.rs") },
+        "tool_response": {},
+        "cwd": fixture.path_str(),
+    });
+    CreateCommand::new(fixture.context())
+        .create_from_payload(&payload.to_string())
+        .unwrap();
+    fixture.write("a.txt", "one\ntwo\n").write("b.txt", "x\n");
+    let service = fixture.service();
+    service
+        .create_checkpoint(Some("milestone: \"v1\", done"))
+        .unwrap();
+
+    // 第一行是 ccg 分支所基于的初始提交
+    let rows = service.checkpoint_metadata().unwrap();
+    assert_eq!(rows.len(), 3);
+
+    let csv = render(&rows, MetadataFormat::Csv);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "hash,id,time,author,tool,files,additions,deletions,session,task,labels,verify,summary"
+    );
+    assert!(lines[2].contains(",Edit,1,1,0,s1,,edit,,Edit on main.rs"));
+    assert!(lines[3].ends_with(",,2,3,0,,,milestone,,\"milestone: \"\"v1\"\", done\""));
+
+    let jsonl = render(&rows, MetadataFormat::Jsonl);
+    let first: serde_json::Value = serde_json::from_str(jsonl.lines().nth(1).unwrap()).unwrap();
+    assert_eq!(first["tool"], "Edit");
+    assert_eq!(first["session"], "s1");
+    assert_eq!(first["labels"], json!(["edit"]));
+    assert!(first["time"].as_str().unwrap().ends_with('Z'));
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use ccg::commands::Command;
+use ccg::commands::multi::{MultiCommand, WORKSPACE_FILE};
+use ccg::commands::traits::{MultiArgs, MultiOperation};
+use common::Fixture;
+use std::fs;
+
+#[test]
+fn multi_create_isolates_failing_roots() {
+    let first = Fixture::new();
+    let second = Fixture::new();
+    first.write("a.txt", "one\n");
+    second.write("b.txt", "two\n");
+    let not_repo = tempfile::TempDir::new().unwrap();
+    let missing = not_repo.path().to_str().unwrap().to_string();
+
+    let report = MultiCommand::new(None)
+        .execute(MultiArgs {
+            roots: vec![
+                first.path_str().to_string(),
+                missing.clone(),
+                second.path_str().to_string(),
+            ],
+            operation: MultiOperation::Create {
+                message: Some("batch".to_string()),
+            },
+        })
+        .unwrap();
+
+    assert_eq!(
+        report.succeeded,
+        [first.path_str().to_string(), second.path_str().to_string()]
+    );
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, missing);
+    assert!(!not_repo.path().join(".git").exists());
+    for fixture in [&first, &second] {
+        let entries = fixture.service().checkpoints(1).unwrap();
+        assert_eq!(entries[0].message.lines().next(), Some("batch"));
+    }
+}
+
+#[test]
+fn workspace_file_lists_roots_relative_to_itself() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let workspace = dir.path().join(WORKSPACE_FILE);
+    fs::write(&workspace, "# services\napi\n\n  /srv/web  \n").unwrap();
+
+    let roots = MultiCommand::read_workspace(&workspace).unwrap();
+
+    assert_eq!(
+        roots,
+        [
+            dir.path().join("api").to_string_lossy().to_string(),
+            "/srv/web".to_string()
+        ]
+    );
+    assert!(MultiCommand::read_workspace(&dir.path().join("missing")).is_err());
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use ccg::git_ops::{GitBackend, ObjectFormat, RepositoryOperations};
+use ccg::{CheckpointError, GitOperations};
+use common::Fixture;
+
+#[test]
+fn hash_validation_follows_the_object_format() {
+    assert_eq!(ObjectFormat::Sha1.hex_len(), 40);
+    assert_eq!(ObjectFormat::Sha256.hex_len(), 64);
+    assert_eq!("SHA256".parse(), Ok(ObjectFormat::Sha256));
+    assert!(ObjectFormat::Sha256.is_full_hash(&"ab".repeat(32)));
+    assert!(!ObjectFormat::Sha1.is_full_hash(&"ab".repeat(32)));
+    assert!(!ObjectFormat::Sha1.is_full_hash(&"zz".repeat(20)));
+
+    let fixture = Fixture::new();
+    assert_eq!(ObjectFormat::of(&fixture.repo()), ObjectFormat::Sha1);
+    assert_eq!(
+        ObjectFormat::at(&fixture.path().join("missing/dir")),
+        None,
+        "only existing paths are looked up"
+    );
+    assert_eq!(ObjectFormat::at(fixture.path()), Some(ObjectFormat::Sha1));
+
+    let git_ops = fixture.git_ops();
+    let head = fixture.repo().head().unwrap().target().unwrap().to_string();
+    assert_eq!(git_ops.resolve_checkpoint(&head).unwrap(), head);
+    // SHA-256 长度的哈希在 SHA-1 仓库中是无效的格式
+    assert!(matches!(
+        git_ops.resolve_checkpoint(&"ab".repeat(32)),
+        Err(CheckpointError::InvalidHash(_))
+    ));
+}
+
+#[test]
+fn sha256_repositories_are_reported_as_unsupported() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let init = std::process::Command::new("git")
+        .args(["init", "--quiet", "--object-format=sha256"])
+        .arg(dir.path())
+        .status();
+    // 较旧的 git 不支持 SHA-256，无法构造这样的仓库
+    if !init.is_ok_and(|status| status.success()) {
+        return;
+    }
+    let nested = dir.path().join("src");
+    std::fs::create_dir(&nested).unwrap();
+
+    assert_eq!(ObjectFormat::at(&nested), Some(ObjectFormat::Sha256));
+    for path in [dir.path(), nested.as_path()] {
+        assert!(matches!(
+            RepositoryOperations::open_repository(path),
+            Err(CheckpointError::UnsupportedObjectFormat(ref name)) if name == "sha256"
+        ));
+        assert!(GitOperations::new_from_path(path).is_err());
+    }
+    assert!(!dir.path().join(".git/refs/heads/ccg").exists());
+}
@@ -0,0 +1,89 @@
+//! File names that are not valid UTF-8 (Unix only: other platforms cannot
+//! create them)
+#![cfg(unix)]
+
+mod common;
+
+use ccg::git_ops::{display_bytes, display_path};
+use ccg::services::RestoreOptions;
+use common::Fixture;
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+const LATIN1: &[u8] = b"caf\xe9.txt";
+
+fn write_raw(fixture: &Fixture, name: &[u8], contents: &str) {
+    std::fs::write(fixture.path().join(OsStr::from_bytes(name)), contents).unwrap();
+}
+
+fn read_raw(fixture: &Fixture, name: &[u8]) -> Option<String> {
+    std::fs::read_to_string(fixture.path().join(OsStr::from_bytes(name))).ok()
+}
+
+#[test]
+fn invalid_bytes_are_escaped_for_display() {
+    assert_eq!(display_bytes(LATIN1), "caf\\xe9.txt");
+    assert_eq!(display_bytes("café.txt".as_bytes()), "café.txt");
+    assert_eq!(
+        display_path(Path::new(OsStr::from_bytes(b"d\xff/\xc3"))),
+        "d\\xff/\\xc3"
+    );
+}
+
+#[test]
+fn backslashes_are_escaped_so_names_never_collide() {
+    // 合法 UTF-8 的 `a\x80` 和含无效字节 0x80 的 `a<0x80>` 是两个不同的文件
+    let literal = display_path(Path::new("a\\x80"));
+    let invalid = display_path(Path::new(OsStr::from_bytes(b"a\x80")));
+    assert_ne!(literal, invalid);
+    assert_eq!(literal, "a\\\\x80");
+    assert_eq!(invalid, "a\\x80");
+    assert_eq!(display_bytes(b"a\\\x80"), "a\\\\\\x80");
+}
+
+#[test]
+fn restore_round_trips_non_utf8_file_names() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    write_raw(&fixture, LATIN1, "one\n");
+    let first = service.create_checkpoint(Some("first")).unwrap();
+    write_raw(&fixture, LATIN1, "two\n");
+    service.create_checkpoint(Some("second")).unwrap();
+
+    let preview = service.restore_preview(&first).unwrap();
+    assert_eq!(preview.len(), 1);
+    assert_eq!(preview[0].path, "caf\\xe9.txt");
+    assert_eq!(preview[0].raw_path.as_os_str().as_bytes(), LATIN1);
+
+    // 未跟踪的同类文件不再被静默忽略
+    write_raw(&fixture, b"n\xf6tes.txt", "untracked\n");
+    assert_eq!(
+        fixture.git_ops().untracked_files().unwrap(),
+        ["n\\xf6tes.txt"]
+    );
+
+    let report = service
+        .restore_checkpoint_with(
+            &first,
+            RestoreOptions {
+                clean: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(read_raw(&fixture, LATIN1).as_deref(), Some("one\n"));
+    assert_eq!(read_raw(&fixture, b"n\xf6tes.txt"), None);
+    assert!(report.updated.contains(&"caf\\xe9.txt".to_string()));
+    assert_eq!(report.removed, ["n\\xf6tes.txt"]);
+    // 没有按替换字符另建一个文件
+    let names: Vec<_> = std::fs::read_dir(fixture.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert!(
+        !names
+            .iter()
+            .any(|name| name.to_str().is_some_and(|n| n.contains('\u{fffd}')))
+    );
+}
@@ -0,0 +1,107 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{Fixture, ccg};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Output;
+
+/// 在 `dir` 中放一个打印参数和导出环境变量的 `ccg-foo` 插件
+fn stub_plugin(dir: &Path) {
+    let path = dir.join("ccg-foo");
+    fs::write(
+        &path,
+        "#!/bin/sh\nprintf 'arg=%s\\n' \"$@\"\nenv | grep '^CCG_'\nexit 3\n",
+    )
+    .unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// 以插件目录优先的 PATH 运行 ccg
+fn run(plugins: &Path, cwd: &Path, args: &[&str]) -> Output {
+    let path = std::env::join_paths(
+        std::iter::once(plugins.to_path_buf())
+            .chain(std::env::split_paths(&std::env::var_os("PATH").unwrap())),
+    )
+    .unwrap();
+    ccg(cwd).args(args).env("PATH", path).output().unwrap()
+}
+
+#[test]
+fn unknown_subcommand_runs_plugin_with_args_and_repo_env() {
+    let fixture = Fixture::new();
+    let plugins = tempfile::tempdir().unwrap();
+    stub_plugin(plugins.path());
+    let outside = tempfile::tempdir().unwrap();
+
+    let output = run(
+        plugins.path(),
+        outside.path(),
+        &["-C", fixture.path_str(), "foo", "--bar", "baz qux"],
+    );
+
+    // 插件的退出码原样传给调用者
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[..2], ["arg=--bar", "arg=baz qux"]);
+    let repo = fixture.repo();
+    let git_dir = format!("CCG_GIT_DIR={}", repo.path().display());
+    let root = format!("CCG_REPO_ROOT={}", repo.workdir().unwrap().display());
+    let version = format!("CCG_VERSION={}", env!("CARGO_PKG_VERSION"));
+    assert!(lines.contains(&git_dir.as_str()), "{stdout}");
+    assert!(lines.contains(&"CCG_BRANCH=ccg"), "{stdout}");
+    assert!(lines.contains(&root.as_str()), "{stdout}");
+    assert!(lines.contains(&"CCG_CURRENT_BRANCH=main"), "{stdout}");
+    assert!(lines.contains(&version.as_str()), "{stdout}");
+}
+
+#[test]
+fn plugin_outside_a_repository_gets_no_repo_env() {
+    let plugins = tempfile::tempdir().unwrap();
+    stub_plugin(plugins.path());
+    let outside = tempfile::tempdir().unwrap();
+
+    let output = run(plugins.path(), outside.path(), &["foo"]);
+
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("CCG_GIT_DIR="), "{stdout}");
+    assert!(!stdout.contains("CCG_BRANCH="), "{stdout}");
+    assert!(stdout.contains("CCG_VERSION="), "{stdout}");
+}
+
+#[test]
+fn plugins_list_shows_plugins_on_path() {
+    let plugins = tempfile::tempdir().unwrap();
+    stub_plugin(plugins.path());
+    // 不可执行的文件不算插件
+    fs::write(plugins.path().join("ccg-bar"), "").unwrap();
+
+    let output = run(plugins.path(), plugins.path(), &["plugins", "list"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains(&plugins.path().join("ccg-foo").display().to_string()),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("ccg-bar"), "{stdout}");
+}
+
+#[test]
+fn unknown_subcommand_without_plugin_fails() {
+    let fixture = Fixture::new();
+    let plugins = tempfile::tempdir().unwrap();
+
+    let output = run(plugins.path(), fixture.path(), &["no-such-plugin", "x"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Unknown command or plugin: no-such-plugin"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,108 @@
+//! Property tests: random input must never make the hash resolver or the
+//! diff formatter panic, and what they return must stay consistent
+
+mod common;
+
+use ccg::git_ops::{DiffFormatter, GitBackend};
+use common::Fixture;
+use git2::{Diff, Patch};
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestRunner};
+
+/// Strings shaped like the references users type: hash prefixes of any
+/// length, `cp-N`, `latest~N`, and arbitrary text
+fn hash_input() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[0-9a-f]{0,40}",
+        "[0-9a-fA-F]{1,45}",
+        any::<u64>().prop_map(|n| format!("cp-{n}")),
+        any::<usize>().prop_map(|n| format!("latest~{n}")),
+        "(latest|cp-)[~0-9-]{0,25}",
+        any::<String>(),
+    ]
+}
+
+/// File names mixing ASCII, unicode, spaces and nested directories
+fn file_name() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9_.éü中文🦀-]{1,6}( [a-zA-Z0-9_.éü中文🦀-]{1,6})?(/[a-zA-Z0-9_.中🦀]{1,8}){0,2}"
+}
+
+/// File contents: text lines, arbitrary unicode, or binary bytes
+fn contents() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        prop::collection::vec("[ -~\t]{0,30}", 0..30).prop_map(|lines| lines.join("\n").into()),
+        any::<String>().prop_map(String::into_bytes),
+        prop::collection::vec(any::<u8>(), 0..200),
+    ]
+}
+
+#[test]
+fn resolving_any_input_never_panics() {
+    let fixture = Fixture::new();
+    let mut hashes = vec![fixture.repo().head().unwrap().target().unwrap()];
+    for i in 0..3 {
+        fixture.write("a.txt", &format!("{i}\n"));
+        hashes.push(fixture.commit(&format!("commit {i}")));
+    }
+    let git_ops = fixture.git_ops();
+
+    let mut runner = TestRunner::new(Config::with_cases(256));
+    runner
+        .run(&hash_input(), |input| {
+            if let Ok(full) = git_ops.resolve_checkpoint(&input) {
+                prop_assert!(hashes.iter().any(|oid| oid.to_string() == full));
+                if input.chars().all(|c| c.is_ascii_hexdigit()) {
+                    prop_assert!(full.starts_with(&input.to_ascii_lowercase()));
+                }
+            }
+            Ok(())
+        })
+        .unwrap();
+
+    // 每个提交都能通过任意不少于 7 位的前缀找到
+    for oid in &hashes {
+        let full = oid.to_string();
+        for len in 7..=40 {
+            assert_eq!(git_ops.resolve_checkpoint(&full[..len]).unwrap(), full);
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn formatting_generated_patches_never_panics(
+        path in file_name(),
+        old in contents(),
+        new in contents(),
+    ) {
+        let mut patch = Patch::from_buffers(&old, Some(path.as_ref()), &new, Some(path.as_ref()), None)
+            .unwrap();
+        let text = patch.to_buf().unwrap();
+        let Ok(diff) = Diff::from_buffer(&text) else {
+            return Ok(());
+        };
+        let output = DiffFormatter::new(&[], true).format(&diff).unwrap();
+        let plain = console::strip_ansi_codes(&output);
+        if old != new {
+            prop_assert!(plain.contains(&path), "{}", plain);
+        }
+    }
+
+    #[test]
+    fn formatting_parsed_garbage_never_panics(
+        text in prop::collection::vec(any::<u8>(), 0..400),
+        header in file_name(),
+    ) {
+        // 随机内容前加上合法的文件头，让解析器走得更远
+        let mut patch = format!(
+            "diff --git a/{header} b/{header}\n--- a/{header}\n+++ b/{header}\n@@ -1,3 +1,3 @@\n"
+        )
+        .into_bytes();
+        patch.extend_from_slice(&text);
+        for buffer in [&patch[..], &text[..]] {
+            if let Ok(diff) = Diff::from_buffer(buffer) {
+                let _ = DiffFormatter::new(&["*.lock".to_string()], true).format(&diff);
+            }
+        }
+    }
+}
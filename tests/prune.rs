@@ -0,0 +1,154 @@
+mod common;
+
+use ccg::commands::prune::{PruneItem, prune_items, selected_checkpoints};
+use ccg::git_ops::{CheckpointEntry, DiffStats};
+use ccg::services::{CheckpointMetadata, CreateOptions, PruneStrategy};
+use common::Fixture;
+
+#[test]
+fn prune_drops_in_one_pass_and_keeps_protected_checkpoints() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let mut hashes = Vec::new();
+    for (i, message) in ["one", "Milestone: parser works", "three", "four", "five"]
+        .iter()
+        .enumerate()
+    {
+        fixture.write("a.txt", &format!("{i}\n"));
+        hashes.push(service.create_checkpoint(Some(message)).unwrap());
+    }
+    let repo = fixture.repo();
+    let tagged = repo.find_commit(hashes[3].parse().unwrap()).unwrap();
+    repo.tag_lightweight("v1", tagged.as_object(), false)
+        .unwrap();
+    let protected = service.protected_checkpoints().unwrap();
+    assert_eq!(protected[&hashes[1]], "milestone");
+    assert_eq!(protected[&hashes[3]], "v1");
+    assert_eq!(protected.len(), 3, "and the root commit");
+
+    let candidates = service.prune_candidates(Some(1), None, None).unwrap();
+    assert_eq!(candidates.len(), 5, "the base commit is selected too");
+    let selected: Vec<String> = candidates.iter().map(|e| e.hash.clone()).collect();
+    let tip_tree = repo
+        .find_commit(fixture.branch_tip("ccg").unwrap())
+        .unwrap()
+        .tree_id();
+    let dropped = service.prune_checkpoints(&selected).unwrap();
+    let dropped: Vec<&str> = dropped.iter().map(|e| e.summary()).collect();
+    assert_eq!(dropped, ["three", "one"]);
+
+    let remaining: Vec<String> = service
+        .checkpoints(10)
+        .unwrap()
+        .iter()
+        .map(|e| e.summary().to_string())
+        .collect();
+    assert_eq!(remaining[..3], ["five", "four", "Milestone: parser works"]);
+    let tip = repo
+        .find_commit(fixture.branch_tip("ccg").unwrap())
+        .unwrap();
+    assert_eq!(tip.tree_id(), tip_tree, "snapshots are kept");
+    assert_ne!(tip.id().to_string(), hashes[4]);
+    // 被重放的检查点保留 ID，标签随之移动
+    let four = service.checkpoints(2).unwrap()[1].clone();
+    assert_ne!(four.hash, hashes[3]);
+    assert_eq!(four.display_id().as_deref(), Some("cp-4"));
+    let tag = repo.revparse_single("v1^{commit}").unwrap();
+    assert_eq!(tag.id().to_string(), four.hash);
+
+    assert!(service.prune_checkpoints(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn session_endpoints_keep_the_first_and_last_checkpoint_of_each_session() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let steps = [
+        ("s1", "s1 start"),
+        ("s1", "s1 tweak"),
+        ("s2", "s2 only"),
+        ("s1", "Milestone: s1 halfway"),
+        ("s1", "s1 tweak again"),
+        ("s1", "s1 end"),
+    ];
+    for (i, (session, message)) in steps.iter().enumerate() {
+        fixture.write("a.txt", &format!("{i}\n"));
+        let options = CreateOptions {
+            session: Some(session.to_string()),
+            ..CreateOptions::default()
+        };
+        service
+            .create_checkpoint_with_options(Some(message), &options)
+            .unwrap();
+    }
+    fixture.write("a.txt", "manual\n");
+    service.create_checkpoint(Some("no session")).unwrap();
+
+    let candidates = service
+        .prune_candidates(None, None, Some(PruneStrategy::SessionEndpoints))
+        .unwrap();
+    let hashes: Vec<String> = candidates.iter().map(|e| e.hash.clone()).collect();
+    let dropped = service.prune_checkpoints(&hashes).unwrap();
+    let dropped: Vec<&str> = dropped.iter().map(|e| e.summary()).collect();
+    assert_eq!(dropped, ["s1 tweak again", "s1 tweak"]);
+
+    let remaining: Vec<String> = service
+        .checkpoints(10)
+        .unwrap()
+        .iter()
+        .map(|e| e.summary().to_string())
+        .collect();
+    assert_eq!(
+        remaining[..5],
+        [
+            "no session",
+            "s1 end",
+            "Milestone: s1 halfway",
+            "s2 only",
+            "s1 start"
+        ]
+    );
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("manual\n"));
+    assert!("endpoints".parse::<PruneStrategy>().is_err());
+}
+
+fn metadata(hash: &str, time: i64, session: Option<&str>) -> CheckpointMetadata {
+    let mut message = "work".to_string();
+    if let Some(session) = session {
+        message.push_str(&format!("\n\nCcg-Session: {session}"));
+    }
+    CheckpointMetadata {
+        entry: CheckpointEntry {
+            hash: hash.to_string(),
+            message,
+            author: "dev".to_string(),
+            time,
+            id: None,
+        },
+        stats: DiffStats::new(),
+    }
+}
+
+#[test]
+fn interactive_items_group_by_day_and_session() {
+    let day = 86_400;
+    let list = [
+        metadata("a1", 2 * day + 30, Some("s1")),
+        metadata("a2", 2 * day + 20, Some("s1")),
+        metadata("a3", 2 * day + 10, None),
+        metadata("a4", day + 10, None),
+    ];
+    let items = prune_items(&list);
+    assert_eq!(items.len(), 7);
+    assert!(matches!(&items[0], PruneItem::Group { members, .. } if members == &[0, 1]));
+    assert_eq!(items[1], PruneItem::Checkpoint(0));
+    assert!(matches!(&items[3], PruneItem::Group { members, .. } if members == &[2]));
+    assert!(matches!(&items[5], PruneItem::Group { members, .. } if members == &[3]));
+    let PruneItem::Group { title, .. } = &items[5] else {
+        unreachable!()
+    };
+    assert!(title.starts_with("1970-01-02"));
+
+    // 选中分组标题等于选中组内全部检查点
+    assert_eq!(selected_checkpoints(&items, &[0, 2, 6]), [0, 1, 3]);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use ccg::git_ops::RepackSettings;
+use common::Fixture;
+
+/// 不易压缩的伪随机文本，使打包前后的差异来自增量而不是 zlib
+fn noise(len: usize, seed: u64) -> String {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            char::from(b'a' + (state >> 59) as u8)
+        })
+        .collect()
+}
+
+#[test]
+fn repack_stores_versions_of_large_files_as_deltas() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let base = noise(200_000, 1);
+    for i in 0..3 {
+        fixture
+            .write("data/model.bin", &format!("{base}\nversion {i}\n"))
+            .write("small.txt", &format!("{i}\n"));
+        service.create_checkpoint(Some(&format!("v{i}"))).unwrap();
+    }
+    let git_ops = fixture.git_ops();
+    let settings = RepackSettings {
+        min_size: 100_000,
+        ..RepackSettings::default()
+    };
+    let candidates = git_ops.repacker().candidates(settings.min_size).unwrap();
+    assert_eq!(candidates.keys().collect::<Vec<_>>(), ["data/model.bin"]);
+    let ids = candidates["data/model.bin"].clone();
+    assert_eq!(ids.len(), 3);
+
+    let report = service.repack_large_files(&settings).unwrap();
+    assert_eq!((report.paths, report.objects), (1, 3));
+    assert!(
+        report.packed_bytes * 2 < report.loose_bytes,
+        "{} packed vs {} loose",
+        report.packed_bytes,
+        report.loose_bytes
+    );
+    assert!(report.saved_percent() > 50.0);
+    assert_eq!(git_ops.last_repack().unwrap(), Some(report));
+
+    // 松散副本已删除，内容仍可从包中读取
+    let repo = fixture.repo();
+    for id in &ids {
+        let hex = id.to_string();
+        assert!(
+            !repo
+                .path()
+                .join("objects")
+                .join(&hex[..2])
+                .join(&hex[2..])
+                .exists()
+        );
+        assert!(repo.find_blob(*id).unwrap().size() > 200_000);
+    }
+    let again = service.repack_large_files(&settings).unwrap();
+    assert_eq!(again.objects, 0);
+    assert_eq!(git_ops.last_repack().unwrap(), Some(report));
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use ccg::CheckpointError;
+use ccg::commands::CreateCommand;
+use ccg::services::ReportFormat;
+use common::Fixture;
+use serde_json::json;
+
+fn hook_save(fixture: &Fixture, session: &str, path: &str, contents: &str) {
+    fixture.write(path, contents);
+    let payload = json!({
+        "session_id": session,
+        "tool_name": "Write",
+        "tool_input": { "file_path": fixture.file_path(path) },
+        "tool_response": {},
+        "cwd": fixture.path_str(),
+    });
+    CreateCommand::new(fixture.context())
+        .create_from_payload(&payload.to_string())
+        .unwrap();
+}
+
+#[test]
+fn report_covers_the_checkpoints_of_one_session() {
+    let fixture = Fixture::new();
+    hook_save(&fixture, "old", "old.txt", "before\n");
+    hook_save(&fixture, "abc", "src/main.rs", "fn main() {}\n");
+    hook_save(
+        &fixture,
+        "abc",
+        "src/main.rs",
+        "fn main() {\n    run();\n}\n",
+    );
+    hook_save(&fixture, "abc", "notes.md", "a <b> & c\n");
+    let service = fixture.service();
+
+    let report = service.session_report(None).unwrap();
+    assert_eq!(report.session, "abc");
+    assert_eq!(report.checkpoints.len(), 3);
+    let paths: Vec<&str> = report
+        .files
+        .iter()
+        .map(|file| file.change.path.as_str())
+        .collect();
+    assert_eq!(paths, ["notes.md", "src/main.rs"]);
+    assert_eq!((report.additions(), report.deletions()), (4, 0));
+
+    let markdown = report.render(ReportFormat::Markdown);
+    assert!(markdown.starts_with("# Session report: abc\n"));
+    assert!(markdown.contains("| Write on main.rs | +3 -1 in 1 files |"));
+    assert!(markdown.contains("| `src/main.rs` | added | 3 | 0 | 2 |"));
+    assert!(markdown.contains("```diff\ndiff --git a/src/main.rs b/src/main.rs\n"));
+    assert!(!markdown.contains("old.txt"));
+
+    let html = report.render(ReportFormat::Html);
+    assert!(html.contains("<span class=\"add\">+a &lt;b&gt; &amp; c</span>"));
+
+    assert!(matches!(
+        service.session_report(Some("missing")),
+        Err(CheckpointError::CheckpointNotFound(_))
+    ));
+}
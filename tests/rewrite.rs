@@ -0,0 +1,322 @@
+mod common;
+
+use ccg::CheckpointError;
+use common::{Fixture, Step};
+
+/// Three checkpoints editing `a.txt`
+const EDITS: [Step; 3] = [
+    ("one", &[("a.txt", "one\n")]),
+    ("two", &[("a.txt", "two\n")]),
+    ("three", &[("a.txt", "three\n")]),
+];
+
+fn tree_of(fixture: &Fixture, hash: &str) -> git2::Oid {
+    let repo = fixture.repo();
+    let commit = repo
+        .find_commit(git2::Oid::from_str(hash).unwrap())
+        .unwrap();
+    commit.tree_id()
+}
+
+#[test]
+fn reword_tip_changes_only_the_message() {
+    let (fixture, hashes) = Fixture::with_checkpoints(EDITS);
+
+    let history = fixture
+        .service()
+        .reword_checkpoint(&hashes[2][..7], "renamed")
+        .unwrap();
+
+    let tip = fixture.branch_tip("ccg").unwrap().to_string();
+    let repo = fixture.repo();
+    let commit = repo
+        .find_commit(git2::Oid::from_str(&tip).unwrap())
+        .unwrap();
+    assert_eq!(history.replacements, [tip.as_str()]);
+    assert!(history.replayed.is_empty());
+    assert_eq!(commit.message(), Some("renamed"));
+    assert_eq!(commit.parent_id(0).unwrap().to_string(), hashes[1]);
+    assert_eq!(tree_of(&fixture, &tip), tree_of(&fixture, &hashes[2]));
+}
+
+#[test]
+fn reword_older_checkpoint_replays_descendants_with_same_trees() {
+    let (fixture, hashes) = Fixture::with_checkpoints(EDITS);
+    let main_tip = fixture.branch_tip("main");
+
+    let history = fixture
+        .service()
+        .reword_checkpoint(&hashes[0], "first, renamed")
+        .unwrap();
+
+    let entries = fixture.service().checkpoints(3).unwrap();
+    let summaries: Vec<&str> = entries.iter().map(|e| e.summary()).collect();
+    assert_eq!(summaries, ["three", "two", "first, renamed"]);
+    assert_eq!(history.replayed.len(), 2);
+    for (old, new) in &history.replayed {
+        assert_eq!(tree_of(&fixture, old), tree_of(&fixture, new));
+    }
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), history.tip);
+    assert_eq!(fixture.branch_tip("main"), main_tip);
+    assert_eq!(fixture.head_branch().as_deref(), Some("main"));
+    assert_eq!(fixture.read("a.txt").as_deref(), Some("three\n"));
+}
+
+#[test]
+fn reword_and_split_move_tags_to_the_rewritten_checkpoints() {
+    let (fixture, hashes) = Fixture::with_checkpoints([
+        ("one", &[("a.txt", "one\n")]),
+        ("both", &[("a.txt", "two\n"), ("b.txt", "two\n")]),
+        ("three", &[("a.txt", "three\n")]),
+    ]);
+    let repo = fixture.repo();
+    for (name, hash) in [("descendant", &hashes[2]), ("target", &hashes[1])] {
+        let commit = repo.find_commit(hash.parse().unwrap()).unwrap();
+        repo.tag_lightweight(name, commit.as_object(), false)
+            .unwrap();
+    }
+    let tagged = |name: &str| {
+        repo.revparse_single(&format!("{name}^{{commit}}"))
+            .unwrap()
+            .id()
+            .to_string()
+    };
+    let service = fixture.service();
+
+    let reworded = service.reword_checkpoint(&hashes[0], "first").unwrap();
+    assert_eq!(tagged("descendant"), reworded.tip);
+
+    let target = tagged("target");
+    let split = service.split_checkpoint(&target).unwrap();
+    assert_eq!(tagged("target"), split.replacements[0]);
+    assert_eq!(tagged("descendant"), split.tip);
+}
+
+#[test]
+fn notes_follow_reworded_rebased_and_pruned_checkpoints() {
+    let (fixture, hashes, _) = with_main_ahead();
+    let git_ops = fixture.git_ops();
+    for (hash, note) in hashes.iter().zip(["note one", "note two", "note three"]) {
+        git_ops.add_note(hash, note).unwrap();
+    }
+    let notes = |history: &[String]| -> Vec<Option<String>> {
+        history
+            .iter()
+            .map(|hash| git_ops.checkpoint_note(hash).unwrap())
+            .collect()
+    };
+    let service = fixture.service();
+
+    let reworded = service.reword_checkpoint("cp-1", "first").unwrap();
+    let rebased = service.rebase_checkpoints(None, false).unwrap();
+    service.prune_checkpoints(&["cp-2".to_string()]).unwrap();
+
+    assert_eq!(
+        notes(&reworded.replacements),
+        [Some("note one".to_string())]
+    );
+    assert_eq!(notes(&[rebased.tip]), [Some("note three".to_string())]);
+    let remaining: Vec<String> = service
+        .checkpoints(2)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.hash)
+        .collect();
+    assert_eq!(
+        notes(&remaining),
+        [Some("note three".to_string()), Some("note one".to_string())]
+    );
+}
+
+#[test]
+fn rebase_moves_tags_with_the_checkpoints() {
+    let (fixture, hashes, _) = with_main_ahead();
+    let repo = fixture.repo();
+    let commit = repo.find_commit(hashes[1].parse().unwrap()).unwrap();
+    repo.tag(
+        "v1",
+        commit.as_object(),
+        &repo.signature().unwrap(),
+        "v1",
+        false,
+    )
+    .unwrap();
+
+    let history = fixture.service().rebase_checkpoints(None, false).unwrap();
+
+    let tag = repo.revparse_single("v1^{commit}").unwrap();
+    assert_eq!(tag.id().to_string(), history.replayed[1].1);
+}
+
+#[test]
+fn reword_rejects_commits_outside_the_ccg_branch() {
+    let (fixture, hashes) = Fixture::with_checkpoints(EDITS);
+    fixture.write("b.txt", "main only\n");
+    let main_only = fixture.commit("main only").to_string();
+
+    let result = fixture.service().reword_checkpoint(&main_only, "nope");
+
+    assert!(matches!(
+        result,
+        Err(CheckpointError::CheckpointNotFound(_))
+    ));
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), hashes[2]);
+}
+
+#[test]
+fn split_applies_one_file_per_checkpoint_and_keeps_final_tree() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture
+        .write("a.txt", "a\n")
+        .write("b.txt", "b\n")
+        .write("c.txt", "c\n");
+    let setup = service.create_checkpoint(Some("setup")).unwrap();
+    fixture
+        .write("a.txt", "a2\n")
+        .write("d/e.txt", "e\n")
+        .remove("c.txt");
+    let mixed = service.create_checkpoint(Some("mixed")).unwrap();
+    fixture.write("b.txt", "b2\n");
+    let later = service.create_checkpoint(Some("later")).unwrap();
+
+    let history = service.split_checkpoint(&mixed[..7]).unwrap();
+
+    let entries = service.checkpoints(5).unwrap();
+    let summaries: Vec<&str> = entries.iter().map(|e| e.summary()).collect();
+    assert_eq!(
+        summaries,
+        [
+            "later",
+            "mixed (d/e.txt)",
+            "mixed (c.txt)",
+            "mixed (a.txt)",
+            "setup"
+        ]
+    );
+    assert_eq!(entries[4].hash, setup);
+    assert_eq!(
+        tree_of(&fixture, &history.replacements[2]),
+        tree_of(&fixture, &mixed)
+    );
+    assert_eq!(tree_of(&fixture, &history.tip), tree_of(&fixture, &later));
+    assert_eq!(history.replayed, [(later, history.tip.clone())]);
+}
+
+#[test]
+fn split_refuses_single_file_checkpoints() {
+    let (fixture, hashes) = Fixture::with_checkpoints(EDITS);
+
+    let result = fixture.service().split_checkpoint(&hashes[1]);
+
+    assert!(matches!(result, Err(CheckpointError::InvalidArgument(_))));
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), hashes[2]);
+}
+
+#[test]
+fn reword_carries_checkpoint_ids_over_to_new_hashes() {
+    let (fixture, _) = Fixture::with_checkpoints(EDITS);
+    let service = fixture.service();
+
+    service.reword_checkpoint("cp-2", "second").unwrap();
+
+    let ids: Vec<_> = service
+        .checkpoints(3)
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.display_id().unwrap(), entry.summary().to_string()))
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            ("cp-3".to_string(), "three".to_string()),
+            ("cp-2".to_string(), "second".to_string()),
+            ("cp-1".to_string(), "one".to_string()),
+        ]
+    );
+}
+
+/// The `EDITS` checkpoints, then a commit on `main` adding `b.txt`
+fn with_main_ahead() -> (Fixture, [String; 3], git2::Oid) {
+    let (fixture, hashes) = Fixture::with_checkpoints(EDITS);
+    fixture.remove("a.txt");
+    fixture.write("b.txt", "main\n");
+    let main_tip = fixture.commit("main moves on");
+    (fixture, hashes, main_tip)
+}
+
+#[test]
+fn rebase_reparents_checkpoints_keeping_their_trees() {
+    let (fixture, hashes, main_tip) = with_main_ahead();
+
+    let history = fixture.service().rebase_checkpoints(None, false).unwrap();
+
+    let olds: Vec<&str> = history
+        .replayed
+        .iter()
+        .map(|(old, _)| old.as_str())
+        .collect();
+    assert_eq!(olds, hashes.iter().map(String::as_str).collect::<Vec<_>>());
+    for (old, new) in &history.replayed {
+        assert_eq!(tree_of(&fixture, old), tree_of(&fixture, new));
+    }
+    let repo = fixture.repo();
+    let first = repo
+        .find_commit(git2::Oid::from_str(&history.replayed[0].1).unwrap())
+        .unwrap();
+    assert_eq!(first.parent_id(0).unwrap(), main_tip);
+    assert_eq!(fixture.branch_tip("ccg").unwrap().to_string(), history.tip);
+    assert_eq!(fixture.branch_tip("main"), Some(main_tip));
+}
+
+#[test]
+fn rebase_replay_applies_changes_onto_the_branch() {
+    let (fixture, _, _) = with_main_ahead();
+
+    let history = fixture
+        .service()
+        .rebase_checkpoints(Some("main"), true)
+        .unwrap();
+
+    assert_eq!(history.replayed.len(), 3);
+    let repo = fixture.repo();
+    let tip = repo
+        .find_commit(git2::Oid::from_str(&history.tip).unwrap())
+        .unwrap();
+    let tree = tip.tree().unwrap();
+    assert!(tree.get_name("a.txt").is_some());
+    assert!(tree.get_name("b.txt").is_some());
+    let summaries: Vec<String> = fixture
+        .service()
+        .checkpoints(3)
+        .unwrap()
+        .iter()
+        .map(|e| e.summary().to_string())
+        .collect();
+    assert_eq!(summaries, ["three", "two", "one"]);
+}
+
+#[test]
+fn rebase_replay_conflict_leaves_the_branch_alone() {
+    let (fixture, _) = Fixture::with_checkpoints(EDITS);
+    fixture.write("a.txt", "main's own\n");
+    fixture.commit("conflicting");
+    let ccg_tip = fixture.branch_tip("ccg");
+
+    let result = fixture.service().rebase_checkpoints(None, true);
+
+    assert!(matches!(result, Err(CheckpointError::Conflict(_))));
+    assert_eq!(fixture.branch_tip("ccg"), ccg_tip);
+}
+
+#[test]
+fn rebase_twice_is_a_no_op() {
+    let (fixture, _, _) = with_main_ahead();
+    let service = fixture.service();
+    let first = service.rebase_checkpoints(None, false).unwrap();
+
+    let second = service.rebase_checkpoints(None, false).unwrap();
+
+    assert!(second.replayed.is_empty());
+    assert_eq!(second.tip, first.tip);
+}
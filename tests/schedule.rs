@@ -0,0 +1,84 @@
+use ccg::services::schedule::{ScheduleEntry, ScheduleInterval, Scheduler};
+use std::path::PathBuf;
+
+fn entry(scheduler: Scheduler, every: &str) -> ScheduleEntry {
+    ScheduleEntry {
+        scheduler,
+        repo: PathBuf::from("/work/my repo"),
+        exe: PathBuf::from("/usr/local/bin/ccg"),
+        interval: every.parse().unwrap(),
+        home: PathBuf::from("/home/dev"),
+    }
+}
+
+#[test]
+fn intervals_parse_and_display_in_the_largest_unit() {
+    let parse = |text: &str| text.parse::<ScheduleInterval>();
+    assert_eq!(parse("30m").unwrap().minutes(), 30);
+    assert_eq!(parse("45").unwrap().minutes(), 45);
+    assert_eq!(parse("2h").unwrap().to_string(), "2h");
+    assert_eq!(parse("90 minutes").unwrap().to_string(), "90m");
+    assert_eq!(parse("1d").unwrap().minutes(), 24 * 60);
+    for bad in ["", "0m", "m", "5s", "-1h", "99999999999999999999d"] {
+        assert!(parse(bad).is_err(), "{bad}");
+    }
+    assert_eq!("SYSTEMD".parse(), Ok(Scheduler::Systemd));
+    assert!("at".parse::<Scheduler>().is_err());
+}
+
+#[test]
+fn entries_render_for_every_scheduler() {
+    let cron = entry(Scheduler::Cron, "30m");
+    let name = cron.name();
+    assert!(name.starts_with("ccg-snapshot-"));
+    assert_eq!(
+        entry(Scheduler::Systemd, "1d").name(),
+        name,
+        "one entry per repository"
+    );
+    assert_eq!(
+        cron.cron_line().unwrap(),
+        format!(
+            "*/30 * * * * '/usr/local/bin/ccg' '-C' '/work/my repo' 'create' 'scheduled snapshot' >/dev/null 2>&1 # {name}"
+        )
+    );
+    assert!(
+        entry(Scheduler::Cron, "2h")
+            .cron_line()
+            .unwrap()
+            .starts_with("0 */2 * * * ")
+    );
+    assert!(
+        entry(Scheduler::Cron, "3d")
+            .cron_line()
+            .unwrap()
+            .starts_with("0 0 */3 * * ")
+    );
+    // cron 无法表示不能整除一小时或一天的间隔
+    assert!(entry(Scheduler::Cron, "45m").cron_line().is_err());
+    assert!(entry(Scheduler::Cron, "5h").cron_line().is_err());
+
+    let systemd = entry(Scheduler::Systemd, "90m");
+    let files = systemd.files();
+    assert_eq!(
+        files[1].0,
+        PathBuf::from(format!("/home/dev/.config/systemd/user/{name}.timer"))
+    );
+    assert!(files[0].1.contains(
+        "ExecStart=\"/usr/local/bin/ccg\" \"-C\" \"/work/my repo\" \"create\" \"scheduled snapshot\""
+    ));
+    assert!(files[1].1.contains("OnUnitActiveSec=90min"));
+
+    let launchd = entry(Scheduler::Launchd, "1h");
+    let (path, plist) = &launchd.files()[0];
+    assert!(path.starts_with("/home/dev/Library/LaunchAgents"));
+    assert!(plist.contains("<integer>3600</integer>"));
+    assert!(plist.contains("<string>/work/my repo</string>"));
+
+    let args = entry(Scheduler::TaskScheduler, "2h").schtasks_args();
+    assert_eq!(
+        args[..8],
+        ["/Create", "/F", "/TN", &name, "/SC", "HOURLY", "/MO", "2"]
+    );
+    assert!(entry(Scheduler::Cron, "30m").files().is_empty());
+}
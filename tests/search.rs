@@ -0,0 +1,88 @@
+mod common;
+
+use ccg::commands::CreateCommand;
+use ccg::services::CheckpointQuery;
+use ccg::services::query::QueryTerm;
+use common::Fixture;
+use serde_json::json;
+
+#[test]
+fn queries_parse_into_terms() {
+    let query: CheckpointQuery =
+        r#"tool:Edit file:src/**.rs after:2024-06-01 text:"Big refactor" loose"#
+            .parse()
+            .unwrap();
+    assert_eq!(query.terms.len(), 5);
+    assert_eq!(query.terms[0], QueryTerm::Tool("Edit".to_string()));
+    assert_eq!(query.terms[1], QueryTerm::File("src/**.rs".to_string()));
+    assert!(matches!(query.terms[2], QueryTerm::After(_)));
+    assert_eq!(query.terms[3], QueryTerm::Text("big refactor".to_string()));
+    assert_eq!(query.terms[4], QueryTerm::Text("loose".to_string()));
+
+    // 引号内的冒号不分隔字段
+    let query: CheckpointQuery = r#"text:"a:b" before:"2024-06-01 12:00""#.parse().unwrap();
+    assert_eq!(query.terms[0], QueryTerm::Text("a:b".to_string()));
+    assert!(matches!(query.terms[1], QueryTerm::Before(_)));
+
+    for bad in [
+        "",
+        "colour:red",
+        "tool:",
+        "text:\"open",
+        "after:someday",
+        "label:nope",
+    ] {
+        assert!(bad.parse::<CheckpointQuery>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn search_filters_checkpoints_by_metadata_files_and_notes() {
+    let fixture = Fixture::new();
+    fixture.write("src/main.rs", "fn main() {}\n");
+    let payload = json!({
+        "session_id": "s1",
+        "tool_name": "Edit",
+        "tool_input": { "file_path": fixture.file_path("src/main.rs") },
+        "tool_response": {},
+        "cwd": fixture.path_str(),
+    });
+    CreateCommand::new(fixture.context())
+        .create_from_payload(&payload.to_string())
+        .unwrap();
+    let service = fixture.service();
+    fixture.write("docs/guide.md", "# Guide\n");
+    service.create_checkpoint(Some("Refactor docs")).unwrap();
+    fixture.write("src/lib.rs", "pub fn lib() {}\n");
+    let noted = service.create_checkpoint(Some("misc")).unwrap();
+    fixture
+        .git_ops()
+        .add_note(&noted, "investigated a flaky timeout")
+        .unwrap();
+
+    let summaries = |query: &str| -> Vec<String> {
+        service
+            .search_checkpoints(&query.parse().unwrap())
+            .unwrap()
+            .iter()
+            .map(|m| m.entry.summary().to_string())
+            .collect()
+    };
+    assert_eq!(summaries("tool:edit"), ["Edit on main.rs"]);
+    assert_eq!(summaries("file:src/**.rs"), ["misc", "Edit on main.rs"]);
+    assert_eq!(summaries("file:docs"), ["Refactor docs"]);
+    assert_eq!(summaries("text:refactor"), ["Refactor docs"]);
+    assert_eq!(summaries("timeout"), ["misc"]);
+    assert_eq!(
+        summaries("session:s tool:Edit file:src"),
+        ["Edit on main.rs"]
+    );
+    // 同一字段的条件满足其一，不同字段都要满足
+    assert_eq!(
+        summaries("text:misc text:refactor file:src"),
+        ["misc"],
+        "file: narrows the text: alternatives"
+    );
+    assert!(summaries("tool:Write").is_empty());
+    assert!(summaries("after:2000-01-01 before:2000-01-02").is_empty());
+}
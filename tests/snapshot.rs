@@ -0,0 +1,50 @@
+mod common;
+
+use ccg::CheckpointError;
+use common::Fixture;
+
+#[test]
+fn snapshot_push_parks_changes_and_pop_merges_them_back() {
+    let fixture = Fixture::new();
+    fixture
+        .write("README.md", "# fixture\nhuman edit\n")
+        .write("notes.txt", "draft\n");
+    let service = fixture.service();
+
+    let snapshot = service.push_snapshot(Some("my work")).unwrap();
+    assert_eq!(snapshot.number, 0);
+    assert_eq!(fixture.read("README.md").as_deref(), Some("# fixture\n"));
+    assert_eq!(fixture.read("notes.txt"), None);
+    assert!(fixture.repo().find_reference("refs/ccg/stash/0").is_ok());
+    assert!(fixture.branch_tip("ccg").is_none());
+
+    // 快照之后的修改（例如代理的工作）在弹出时保留
+    fixture.write("agent.txt", "generated\n");
+    let popped = service.pop_snapshot(None).unwrap();
+
+    assert_eq!(popped.message, "my work");
+    assert_eq!(
+        fixture.read("README.md").as_deref(),
+        Some("# fixture\nhuman edit\n")
+    );
+    assert_eq!(fixture.read("notes.txt").as_deref(), Some("draft\n"));
+    assert_eq!(fixture.read("agent.txt").as_deref(), Some("generated\n"));
+    assert!(service.list_snapshots().unwrap().is_empty());
+}
+
+#[test]
+fn snapshot_pop_refuses_conflicting_changes() {
+    let fixture = Fixture::new();
+    fixture.write("README.md", "# human\n");
+    let service = fixture.service();
+    service.push_snapshot(None).unwrap();
+    fixture.write("README.md", "# agent\n");
+
+    let result = service.pop_snapshot(None);
+
+    assert!(matches!(result, Err(CheckpointError::Conflict(ref paths)) if paths == "README.md"));
+    assert_eq!(fixture.read("README.md").as_deref(), Some("# agent\n"));
+    let snapshots = service.list_snapshots().unwrap();
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].message, "WIP on main");
+}
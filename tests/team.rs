@@ -0,0 +1,143 @@
+mod common;
+
+use ccg::git_ops::{ListColumn, ListFilter};
+use common::Fixture;
+
+/// A developer's clone with `server` as `origin` and `name` as team user
+fn member(server: &Fixture, name: &str) -> Fixture {
+    let fixture = Fixture::new();
+    let repo = fixture.repo();
+    repo.remote("origin", &format!("file://{}", server.path_str()))
+        .unwrap();
+    repo.config()
+        .unwrap()
+        .set_str("ccg.team.user", name)
+        .unwrap();
+    fixture
+}
+
+#[test]
+fn colleagues_checkpoints_are_listed_from_their_namespace() {
+    let server = Fixture::new();
+    let alice = member(&server, "alice");
+    alice.write("a.txt", "alice\n");
+    let first = alice
+        .service()
+        .create_checkpoint(Some("alice one"))
+        .unwrap();
+    alice.write("a.txt", "alice two\n");
+    let second = alice
+        .service()
+        .create_checkpoint(Some("alice two"))
+        .unwrap();
+    assert_eq!(
+        alice.service().publish_checkpoints("origin").unwrap(),
+        "alice"
+    );
+
+    let bob = member(&server, "bob");
+    bob.write("b.txt", "bob\n");
+    bob.service().create_checkpoint(Some("bob one")).unwrap();
+    let report = bob.service().fetch_checkpoints("origin", None).unwrap();
+    assert_eq!(report.tip, None);
+    assert_eq!(report.users, ["alice"]);
+
+    let listed = bob
+        .git_ops()
+        .list_checkpoints(
+            10,
+            &[ListColumn::Message],
+            &ListFilter {
+                user: Some("alice".to_string()),
+                ..ListFilter::default()
+            },
+        )
+        .unwrap();
+    assert!(listed[0].contains("alice two"));
+    assert!(listed[1].contains("alice one"));
+    // 自己的时间线不受影响
+    let own = bob.service().checkpoints(10).unwrap();
+    assert!(
+        own.iter()
+            .all(|entry| entry.hash != first && entry.hash != second)
+    );
+    // 短hash也能找到同事的检查点
+    let found = bob.git_ops().find_commit(&second[..10]).unwrap().id();
+    assert_eq!(found.to_string(), second);
+
+    let unknown = bob.git_ops().list_checkpoints(
+        10,
+        &[ListColumn::Message],
+        &ListFilter {
+            user: Some("carol".to_string()),
+            ..ListFilter::default()
+        },
+    );
+    assert!(
+        unknown
+            .unwrap_err()
+            .to_string()
+            .contains("known users: alice")
+    );
+}
+
+#[test]
+fn inactive_namespaces_expire_by_user_policy() {
+    let server = Fixture::new();
+    for name in ["alice", "carol"] {
+        let colleague = member(&server, name);
+        colleague.write("a.txt", &format!("{name}\n"));
+        colleague.service().create_checkpoint(Some(name)).unwrap();
+        colleague.service().publish_checkpoints("origin").unwrap();
+    }
+    let bob = member(&server, "bob");
+    bob.write("b.txt", "bob\n");
+    bob.service().create_checkpoint(Some("bob")).unwrap();
+    bob.service().publish_checkpoints("origin").unwrap();
+    bob.service().fetch_checkpoints("origin", None).unwrap();
+
+    let mut config = bob.repo().config().unwrap();
+    config.set_i64("ccg.team.expireDays", 30).unwrap();
+    config.set_i64("ccg.user.alice.expireDays", 1).unwrap();
+    let in_two_days = chrono::Utc::now().timestamp() + 2 * 24 * 60 * 60;
+
+    // alice 的保留期是 1 天；carol 使用团队默认的 30 天；自己的命名空间从不删除
+    let expired = bob.git_ops().users().expire(in_two_days).unwrap();
+    assert_eq!(expired, ["alice"]);
+    let left: Vec<String> = bob
+        .git_ops()
+        .users()
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|user| user.name)
+        .collect();
+    assert_eq!(left, ["bob", "carol"]);
+}
+
+#[test]
+fn owner_permissions_are_read_from_config() {
+    let fixture = Fixture::new();
+    let config = |key: &str, value: &str| {
+        fixture
+            .repo()
+            .config()
+            .unwrap()
+            .set_str(key, value)
+            .unwrap();
+    };
+    config("ccg.team.permissions", "owner");
+    config("ccg.team.user", "alice");
+    fixture.write("a.txt", "one\n");
+    let first = fixture.service().create_checkpoint(Some("one")).unwrap();
+    fixture.write("a.txt", "two\n");
+    fixture.service().create_checkpoint(Some("two")).unwrap();
+
+    config("ccg.team.user", "bob");
+    let err = fixture.service().restore_checkpoint(&first).unwrap_err();
+    assert!(err.to_string().contains("ccg.team.permissions"));
+    assert!(err.to_string().contains("alice"));
+
+    config("ccg.team.user", "alice");
+    fixture.service().restore_checkpoint(&first).unwrap();
+}
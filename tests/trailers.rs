@@ -0,0 +1,96 @@
+mod common;
+
+use ccg::commands::CreateCommand;
+use ccg::git_ops::{CheckpointSource, Trailers};
+use common::Fixture;
+use serde_json::json;
+
+#[test]
+fn trailers_follow_git_interpret_trailers_rules() {
+    let message = "Edit on main.rs\n\nNote: this paragraph is the body\n\nCcg-Tool: Edit\nCcg-Session: abc\n  continued\n";
+    let (body, trailers) = Trailers::split(message);
+    assert_eq!(body, "Edit on main.rs\n\nNote: this paragraph is the body");
+    assert_eq!(trailers.get("ccg-tool"), Some("Edit"));
+    assert_eq!(trailers.get("Ccg-Session"), Some("abc continued"));
+    assert_eq!(trailers.get("Note"), None);
+
+    // 主题段落和含有非尾注行的段落都不是尾注
+    assert!(Trailers::parse("Key: value").is_empty());
+    assert!(Trailers::parse("Subject\n\nnot a trailer\nKey: value").is_empty());
+
+    let written = Trailers::new()
+        .push("Ccg-Tool", "Write")
+        .push("Ccg-Source", "hook\nwith newline")
+        .apply_to(message);
+    assert_eq!(
+        Trailers::parse(&written).iter().collect::<Vec<_>>(),
+        [
+            ("Ccg-Tool", "Edit"),
+            ("Ccg-Session", "abc continued"),
+            ("Ccg-Tool", "Write"),
+            ("Ccg-Source", "hook with newline"),
+        ]
+    );
+    assert_eq!(
+        Trailers::new().push("A", "1").apply_to("Subject"),
+        "Subject\n\nA: 1\n"
+    );
+}
+
+#[test]
+fn hook_checkpoints_record_their_metadata_as_trailers() {
+    let fixture = Fixture::new();
+    fixture.write("src/main.rs", "fn main() {}\n");
+    let payload = json!({
+        "session_id": "abc",
+        "tool_name": "MultiEdit",
+        "tool_input": { "file_path": fixture.file_path("src/main.rs") },
+        "tool_response": {},
+        "cwd": fixture.path_str(),
+    });
+    CreateCommand::new(fixture.context())
+        .create_from_payload(&payload.to_string())
+        .unwrap();
+
+    let entry = fixture.service().checkpoints(1).unwrap().remove(0);
+    let trailers = entry.trailers();
+    assert_eq!(trailers.get("Ccg-Base-Branch"), Some("main"));
+    assert!(trailers.get("Ccg-Base-Commit").is_some());
+    assert_eq!(entry.tool().as_deref(), Some("MultiEdit"));
+    assert_eq!(entry.session().as_deref(), Some("abc"));
+    assert_eq!(entry.source(), Some(CheckpointSource::Hook));
+}
+
+#[test]
+fn legacy_base_trailer_is_still_read() {
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    let hash = fixture
+        .service()
+        .create_checkpoint_with_options(Some("old checkpoint"), &Default::default())
+        .unwrap();
+
+    // 旧版本把基准提交写为 `Ccg-Base`
+    let repo = fixture.repo();
+    let commit = repo.find_commit(hash.parse().unwrap()).unwrap();
+    let legacy = commit
+        .message()
+        .unwrap()
+        .replace("Ccg-Base-Commit:", "Ccg-Base:");
+    let legacy = commit
+        .amend(
+            Some("refs/heads/ccg"),
+            None,
+            None,
+            None,
+            Some(&legacy),
+            None,
+        )
+        .unwrap()
+        .to_string();
+    fixture.write("b.txt", "two\n").commit("later work");
+
+    let drift = fixture.git_ops().base_drift(&legacy).unwrap().unwrap();
+    assert_eq!(drift.branch, "main");
+    assert_eq!(drift.commits, 1);
+}
@@ -0,0 +1,231 @@
+mod common;
+
+use ccg::git_ops::ListFilter;
+use ccg::ui::{
+    Icon, IconMode, set_icon_mode, set_width, short_hash, truncate_end, truncate_graphemes,
+    truncate_start,
+};
+use common::{Fixture, show};
+
+// 图标模式是进程级全局状态，单独放在一个测试二进制中，并在同一个测试里切换
+
+#[test]
+fn icon_modes_control_markers_in_output() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture.write("a.txt", "one\n");
+    service.create_checkpoint(Some("first")).unwrap();
+    fixture.write("a.txt", "two\n");
+    let second = service.create_checkpoint(Some("second")).unwrap();
+
+    set_icon_mode(IconMode::Ascii);
+    let output = show(&fixture, &second, true, &[]);
+    assert!(output.contains("[M] "), "{output}");
+    assert!(output.contains(&"-".repeat(80)));
+    assert!(!output.contains('─') && !output.contains('📝'), "{output}");
+
+    set_icon_mode(IconMode::None);
+    assert_eq!(Icon::Success.prefix(), "");
+    assert_eq!(Icon::Arrow.glyph(), "->");
+    let output = show(&fixture, &second, true, &[]);
+    assert!(
+        !output.contains("[M]") && !output.contains('📝'),
+        "{output}"
+    );
+
+    set_icon_mode(IconMode::Unicode);
+    let output = show(&fixture, &second, true, &[]);
+    assert!(output.contains("📝 "), "{output}");
+}
+
+#[test]
+fn list_lines_fit_the_layout_width() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture.write("a.txt", "one\n");
+    service
+        .create_checkpoint(Some(&"a very long checkpoint summary ".repeat(5)))
+        .unwrap();
+    fixture.write("a.txt", "two\n");
+    service.create_checkpoint(Some("short")).unwrap();
+
+    set_width(Some(90));
+    let mut columns = ccg::git_ops::ListColumn::DEFAULT.to_vec();
+    columns.push(ccg::git_ops::ListColumn::Stats);
+    let git_ops = fixture.git_ops();
+    git_ops.ensure_ccg_branch().unwrap();
+    let lines: Vec<String> = git_ops
+        .list_checkpoints(10, &columns, &ListFilter::default())
+        .unwrap()
+        .iter()
+        .map(|line| console::strip_ansi_codes(line).to_string())
+        .collect();
+    set_width(None);
+
+    for line in &lines {
+        assert!(console::measure_text_width(line) <= 90 - 4, "{line}");
+    }
+    assert!(
+        lines[1].contains('…') || lines[1].contains("..."),
+        "{}",
+        lines[1]
+    );
+    // 统计列对齐
+    let column = |line: &String| console::measure_text_width(&line[..line.find('+').unwrap()]);
+    assert_eq!(column(&lines[0]), column(&lines[1]));
+}
+
+#[test]
+fn list_columns_follow_the_requested_order() {
+    use ccg::git_ops::{ListColumn, parse_list_columns};
+
+    let fixture = Fixture::new();
+    fixture.write("a.txt", "one\n");
+    let hash = fixture
+        .service()
+        .create_checkpoint(Some("Write on a.txt"))
+        .unwrap();
+    let git_ops = fixture.git_ops();
+    git_ops.ensure_ccg_branch().unwrap();
+
+    let columns = parse_list_columns("message, hash,tool").unwrap();
+    assert_eq!(
+        columns,
+        [ListColumn::Message, ListColumn::Hash, ListColumn::Label]
+    );
+    let lines: Vec<String> = git_ops
+        .list_checkpoints(1, &columns, &ListFilter::default())
+        .unwrap()
+        .iter()
+        .map(|line| console::strip_ansi_codes(line).to_string())
+        .collect();
+
+    assert_eq!(lines, [format!("Write on a.txt {} [write]", &hash[..7])]);
+    assert!(parse_list_columns("hash,colour").is_err());
+}
+
+#[test]
+fn prompt_level_decides_which_confirmations_are_asked() {
+    use ccg::config::CcgConfig;
+    use ccg::ui::prompt::{self, LocalizedTheme, PromptKind, PromptLevel};
+    use dialoguer::theme::Theme;
+
+    let fixture = Fixture::new();
+    let mut config = fixture.repo().config().unwrap();
+    config.set_str("ccg.prompt", "destructive-only").unwrap();
+    let level = CcgConfig::load(&fixture.repo()).prompt.unwrap();
+    assert_eq!(level, PromptLevel::DestructiveOnly);
+    assert!(level.asks(PromptKind::Destructive));
+    assert!(!level.asks(PromptKind::Routine));
+    assert!(!PromptLevel::Never.asks(PromptKind::Destructive));
+    assert!("sometimes".parse::<PromptLevel>().is_err());
+
+    // 跳过的确认视为同意，不会读取终端
+    prompt::set_prompt_level(PromptLevel::Never);
+    assert!(prompt::confirm(PromptKind::Destructive, "Restore?", None).unwrap());
+    prompt::set_prompt_level(PromptLevel::Always);
+
+    // 选项和答案来自 i18n 词条
+    let mut text = String::new();
+    LocalizedTheme
+        .format_confirm_prompt(&mut text, "Restore?", Some(false))
+        .unwrap();
+    assert_eq!(text, "Restore? [y/N] ");
+    text.clear();
+    LocalizedTheme
+        .format_confirm_prompt_selection(&mut text, "Restore?", Some(true))
+        .unwrap();
+    assert_eq!(text, "Restore? yes");
+}
+
+#[test]
+fn color_moved_marks_relocated_blocks() {
+    use console::{Color, style};
+
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    let block = "fn alpha() {}\nfn beta() {}\nfn gamma() {}\n";
+    fixture.write("a.txt", &format!("{block}one\ntwo\nthree\nfour\n"));
+    service.create_checkpoint(Some("first")).unwrap();
+    fixture.write("a.txt", &format!("one\ntwo\nthree\nFOUR\n{block}"));
+    let second = service.create_checkpoint(Some("move block")).unwrap();
+
+    // 颜色是进程级全局状态，只在这个测试中打开
+    console::set_colors_enabled(true);
+    let moved_away = style("- fn beta() {}\n").fg(Color::Magenta).to_string();
+    let moved_in = style("+ fn beta() {}\n").fg(Color::Cyan).to_string();
+    let changed = style("- four\n").fg(Color::Red).to_string();
+
+    let render = || {
+        let mut out = Vec::new();
+        fixture
+            .git_ops()
+            .show_checkpoint(&second, true, false, &[], &mut out)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let plain = render();
+    fixture
+        .repo()
+        .config()
+        .unwrap()
+        .set_bool("ccg.diff.colorMoved", true)
+        .unwrap();
+    let colored = render();
+    console::set_colors_enabled(false);
+
+    assert!(!plain.contains(&moved_away) && !plain.contains(&moved_in));
+    assert!(colored.contains(&moved_away), "{colored}");
+    assert!(colored.contains(&moved_in), "{colored}");
+    // 单独修改的行仍然显示为删除
+    assert!(colored.contains(&changed), "{colored}");
+}
+
+#[test]
+fn list_stat_bar_grows_with_the_size_of_the_change() {
+    let fixture = Fixture::new();
+    let service = fixture.service();
+    fixture.write("a.txt", "one\n");
+    service.create_checkpoint(Some("tweak")).unwrap();
+    fixture.write("b.txt", &"line\n".repeat(1000));
+    service.create_checkpoint(Some("huge")).unwrap();
+
+    let git_ops = fixture.git_ops();
+    git_ops.ensure_ccg_branch().unwrap();
+    let lines: Vec<String> = git_ops
+        .list_checkpoints(
+            2,
+            &[ccg::git_ops::ListColumn::Stats],
+            &ListFilter::default(),
+        )
+        .unwrap()
+        .iter()
+        .map(|line| console::strip_ansi_codes(line).to_string())
+        .collect();
+    git_ops.restore_original_branch("main").unwrap();
+
+    // 最新的在前：1000 行填满变更条，一行的修改只有一个标记
+    assert!(lines[0].contains(" ++++++++++ (1 files)"), "{}", lines[0]);
+    assert!(lines[1].contains(" +          (1 files)"), "{}", lines[1]);
+    assert_eq!(lines[0].find('('), lines[1].find('('));
+}
+
+#[test]
+fn truncation_never_splits_characters_or_graphemes() {
+    let ellipsis = Icon::Ellipsis.glyph();
+    assert_eq!(short_hash("abc"), "abc");
+    assert_eq!(short_hash("0123456789abcdef"), "0123456");
+    assert_eq!(short_hash("提交哈希提交哈希"), "提交哈希提交哈");
+
+    // 组合重音符和 ZWJ 连接的 emoji 序列作为一个整体保留或丢弃
+    let message = "cafe\u{301} 👩‍👩‍👧 family trip";
+    let truncated = truncate_end(message, 6 + console::measure_text_width(ellipsis));
+    assert!(truncated.starts_with("cafe\u{301} "), "{truncated}");
+    assert!(!truncated.contains('\u{200d}') || truncated.contains("👩‍👩‍👧"));
+    assert_eq!(truncate_graphemes(message, 4), "cafe\u{301}");
+    assert_eq!(truncate_graphemes("短", 4), "短");
+
+    let path = truncate_start("目录/子目录/文件.rs", 10);
+    assert!(path.ends_with("文件.rs"), "{path}");
+    assert!(console::measure_text_width(&path) <= 10, "{path}");
+}